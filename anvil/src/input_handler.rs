@@ -7,12 +7,12 @@ use crate::udev::UdevData;
 
 use smithay::{
     backend::input::{
-        self, Axis, AxisSource, Event, InputBackend, InputEvent, KeyState, KeyboardKeyEvent,
-        PointerAxisEvent, PointerButtonEvent,
+        self, Axis, AxisSource, Event, InputBackend, InputEvent, KeyboardKeyEvent, PointerAxisEvent,
+        PointerButtonEvent,
     },
     desktop::{layer_map_for_output, WindowSurfaceType},
     input::{
-        keyboard::{keysyms as xkb, FilterResult, Keysym, ModifiersState},
+        keyboard::{Binding, FilterResult, KeyBindings, DEFAULT_MODE},
         pointer::{AxisFrame, ButtonEvent, MotionEvent},
     },
     output::Scale,
@@ -38,14 +38,16 @@ use crate::state::Backend;
 use smithay::{
     backend::{
         input::{
-            Device, DeviceCapability, PointerMotionEvent, ProximityState, TabletToolButtonEvent,
-            TabletToolEvent, TabletToolProximityEvent, TabletToolTipEvent, TabletToolTipState,
+            Device, DeviceCapability, DeviceConfig, PointerMotionEvent, ProximityState, SwitchToggleEvent,
+            TabletPadButtonEvent, TabletPadEvent, TabletPadRingEvent, TabletPadStripEvent,
+            TabletToolButtonEvent, TabletToolEvent, TabletToolProximityEvent, TabletToolTipEvent,
+            TabletToolTipState,
         },
         session::Session,
     },
     wayland::{
         seat::WaylandFocus,
-        tablet_manager::{TabletDescriptor, TabletSeatTrait},
+        tablet_manager::{TabletDescriptor, TabletPadHandle, TabletSeatTrait},
     },
 };
 
@@ -71,6 +73,12 @@ impl<Backend> AnvilState<Backend> {
                 }
             }
 
+            KeyAction::NextLayout => {
+                let keyboard = self.seat.get_keyboard().unwrap();
+                keyboard.cycle_layout_next(self, SCOUNTER.next_serial());
+                info!(self.log, "Switched keyboard layout"; "layout" => keyboard.active_layout());
+            }
+
             _ => unreachable!(
                 "Common key action handler encountered backend specific action {:?}",
                 action
@@ -85,7 +93,6 @@ impl<Backend> AnvilState<Backend> {
         let serial = SCOUNTER.next_serial();
         let log = self.log.clone();
         let time = Event::time(&evt);
-        let mut suppressed_keys = self.suppressed_keys.clone();
         let keyboard = self.seat.get_keyboard().unwrap();
 
         for layer in self.layer_shell_state.layer_surfaces().rev() {
@@ -120,49 +127,20 @@ impl<Backend> AnvilState<Backend> {
             .map(|inhibitor| inhibitor.is_active())
             .unwrap_or(false);
 
-        let action = keyboard
-            .input(self, keycode, state, serial, time, |_, modifiers, handle| {
-                let keysym = handle.modified_sym();
-
+        keyboard
+            .input(self, keycode, state, serial, time, |data, modifiers, handle| {
                 debug!(log, "keysym";
                     "state" => format!("{:?}", state),
                     "mods" => format!("{:?}", modifiers),
-                    "keysym" => ::xkbcommon::xkb::keysym_get_name(keysym)
+                    "keysym" => ::xkbcommon::xkb::keysym_get_name(handle.modified_sym())
                 );
 
-                // If the key is pressed and triggered a action
-                // we will not forward the key to the client.
-                // Additionally add the key to the suppressed keys
-                // so that we can decide on a release if the key
-                // should be forwarded to the client or not.
-                if let KeyState::Pressed = state {
-                    if !inhibited {
-                        let action = process_keyboard_shortcut(*modifiers, keysym);
-
-                        if action.is_some() {
-                            suppressed_keys.push(keysym);
-                        }
-
-                        action
-                            .map(FilterResult::Intercept)
-                            .unwrap_or(FilterResult::Forward)
-                    } else {
-                        FilterResult::Forward
-                    }
-                } else {
-                    let suppressed = suppressed_keys.contains(&keysym);
-                    if suppressed {
-                        suppressed_keys.retain(|k| *k != keysym);
-                        FilterResult::Intercept(KeyAction::None)
-                    } else {
-                        FilterResult::Forward
-                    }
-                }
+                // Keys triggering a binding are not forwarded to the client,
+                // the bindings also take care of suppressing their release.
+                data.key_bindings.filter(modifiers, handle, state, inhibited)
             })
-            .unwrap_or(KeyAction::None);
-
-        self.suppressed_keys = suppressed_keys;
-        action
+            .flatten()
+            .unwrap_or(KeyAction::None)
     }
 
     fn on_pointer_button<B: InputBackend>(&mut self, evt: B::PointerButtonEvent) {
@@ -361,6 +339,8 @@ impl<Backend: crate::state::Backend> AnvilState<Backend> {
                     output.change_current_state(None, None, Some(Scale::Fractional(new_scale)), None);
 
                     crate::shell::fixup_positions(&mut self.space);
+                    #[cfg(feature = "xwayland")]
+                    self.update_xwayland_scale();
                     self.backend_data.reset_buffers(&output);
                 }
 
@@ -377,11 +357,13 @@ impl<Backend: crate::state::Backend> AnvilState<Backend> {
                     output.change_current_state(None, None, Some(Scale::Fractional(new_scale)), None);
 
                     crate::shell::fixup_positions(&mut self.space);
+                    #[cfg(feature = "xwayland")]
+                    self.update_xwayland_scale();
                     self.backend_data.reset_buffers(&output);
                 }
 
                 action => match action {
-                    KeyAction::None | KeyAction::Quit | KeyAction::Run(_) => {
+                    KeyAction::None | KeyAction::Quit | KeyAction::Run(_) | KeyAction::NextLayout => {
                         self.process_common_key_action(action)
                     }
 
@@ -429,6 +411,8 @@ impl<Backend: crate::state::Backend> AnvilState<Backend> {
                 time: evt.time(),
             },
         );
+        #[cfg(feature = "xwayland")]
+        self.xwayland_pointer_motion(evt.time());
     }
 }
 
@@ -481,6 +465,8 @@ impl AnvilState<UdevData> {
                         self.pointer_location = output_location + pointer_output_location;
 
                         crate::shell::fixup_positions(&mut self.space);
+                        #[cfg(feature = "xwayland")]
+                        self.update_xwayland_scale();
                         let under = self.surface_under();
                         if let Some(ptr) = self.seat.get_pointer() {
                             ptr.motion(
@@ -520,6 +506,8 @@ impl AnvilState<UdevData> {
                         self.pointer_location = output_location + pointer_output_location;
 
                         crate::shell::fixup_positions(&mut self.space);
+                        #[cfg(feature = "xwayland")]
+                        self.update_xwayland_scale();
                         let under = self.surface_under();
                         if let Some(ptr) = self.seat.get_pointer() {
                             ptr.motion(
@@ -537,7 +525,7 @@ impl AnvilState<UdevData> {
                 }
 
                 action => match action {
-                    KeyAction::None | KeyAction::Quit | KeyAction::Run(_) => {
+                    KeyAction::None | KeyAction::Quit | KeyAction::Run(_) | KeyAction::NextLayout => {
                         self.process_common_key_action(action)
                     }
 
@@ -552,12 +540,33 @@ impl AnvilState<UdevData> {
             InputEvent::TabletToolProximity { event, .. } => self.on_tablet_tool_proximity::<B>(dh, event),
             InputEvent::TabletToolTip { event, .. } => self.on_tablet_tool_tip::<B>(event),
             InputEvent::TabletToolButton { event, .. } => self.on_tablet_button::<B>(event),
-            InputEvent::DeviceAdded { device } => {
+            InputEvent::TabletPadButton { event, .. } => self.on_tablet_pad_button::<B>(event),
+            InputEvent::TabletPadRing { event, .. } => self.on_tablet_pad_ring::<B>(event),
+            InputEvent::TabletPadStrip { event, .. } => self.on_tablet_pad_strip::<B>(event),
+            InputEvent::SwitchToggle { event, .. } => {
+                info!(self.log, "Switch toggled"; "switch" => ?event.switch(), "state" => ?event.state());
+            }
+            InputEvent::DeviceAdded { mut device } => {
+                if device.tap_finger_count() > 0 {
+                    if let Err(err) = device.set_tap_enabled(true) {
+                        warn!(
+                            self.log,
+                            "Failed to enable tap-to-click on {}: {}",
+                            device.name(),
+                            err
+                        );
+                    }
+                }
                 if device.has_capability(DeviceCapability::TabletTool) {
                     self.seat
                         .tablet_seat()
                         .add_tablet::<Self>(dh, &TabletDescriptor::from(&device));
                 }
+                if let Some(pad) = device.tablet_pad() {
+                    self.seat
+                        .tablet_seat()
+                        .add_pad::<Self>(dh, &TabletDescriptor::from(&device), &pad);
+                }
             }
             InputEvent::DeviceRemoved { device } => {
                 if device.has_capability(DeviceCapability::TabletTool) {
@@ -570,6 +579,11 @@ impl AnvilState<UdevData> {
                         tablet_seat.clear_tools();
                     }
                 }
+                if device.has_capability(DeviceCapability::TabletPad) {
+                    self.seat
+                        .tablet_seat()
+                        .remove_pad(&TabletDescriptor::from(&device));
+                }
             }
             _ => {
                 // other events are not handled in anvil (yet)
@@ -597,6 +611,8 @@ impl AnvilState<UdevData> {
                 },
             );
         }
+        #[cfg(feature = "xwayland")]
+        self.xwayland_pointer_motion(evt.time());
     }
 
     fn on_pointer_move_absolute<B: InputBackend>(
@@ -637,6 +653,8 @@ impl AnvilState<UdevData> {
                 },
             );
         }
+        #[cfg(feature = "xwayland")]
+        self.xwayland_pointer_motion(evt.time());
     }
 
     fn on_tablet_tool_axis<B: InputBackend>(&mut self, evt: B::TabletToolAxisEvent) {
@@ -760,6 +778,33 @@ impl AnvilState<UdevData> {
         }
     }
 
+    fn tablet_pad<B: InputBackend, E: TabletPadEvent<B>>(&self, evt: &E) -> Option<TabletPadHandle> {
+        let pad = self
+            .seat
+            .tablet_seat()
+            .get_pad(&TabletDescriptor::from(&evt.device()))?;
+        pad.mode(evt.mode_group(), evt.mode(), SCOUNTER.next_serial(), evt.time());
+        Some(pad)
+    }
+
+    fn on_tablet_pad_button<B: InputBackend>(&mut self, evt: B::TabletPadButtonEvent) {
+        if let Some(pad) = self.tablet_pad::<B, _>(&evt) {
+            pad.button(evt.button(), evt.button_state(), evt.time());
+        }
+    }
+
+    fn on_tablet_pad_ring<B: InputBackend>(&mut self, evt: B::TabletPadRingEvent) {
+        if let Some(pad) = self.tablet_pad::<B, _>(&evt) {
+            pad.ring(evt.number(), evt.position(), evt.source(), evt.time());
+        }
+    }
+
+    fn on_tablet_pad_strip<B: InputBackend>(&mut self, evt: B::TabletPadStripEvent) {
+        if let Some(pad) = self.tablet_pad::<B, _>(&evt) {
+            pad.strip(evt.number(), evt.position(), evt.source(), evt.time());
+        }
+    }
+
     fn clamp_coords(&self, pos: Point<f64, Logical>) -> Point<f64, Logical> {
         if self.space.outputs().next().is_none() {
             return pos;
@@ -790,8 +835,8 @@ impl AnvilState<UdevData> {
 }
 
 /// Possible results of a keyboard action
-#[derive(Debug)]
-enum KeyAction {
+#[derive(Debug, Clone)]
+pub enum KeyAction {
     /// Quit the compositor
     Quit,
     /// Trigger a vt-switch
@@ -802,32 +847,38 @@ enum KeyAction {
     Screen(usize),
     ScaleUp,
     ScaleDown,
+    /// Switch to the next keyboard layout
+    NextLayout,
     /// Do nothing more
     None,
 }
 
-fn process_keyboard_shortcut(modifiers: ModifiersState, keysym: Keysym) -> Option<KeyAction> {
-    if modifiers.ctrl && modifiers.alt && keysym == xkb::KEY_BackSpace
-        || modifiers.logo && keysym == xkb::KEY_q
-    {
-        // ctrl+alt+backspace = quit
-        // logo + q = quit
-        Some(KeyAction::Quit)
-    } else if (xkb::KEY_XF86Switch_VT_1..=xkb::KEY_XF86Switch_VT_12).contains(&keysym) {
-        // VTSwitch
-        Some(KeyAction::VtSwitch(
-            (keysym - xkb::KEY_XF86Switch_VT_1 + 1) as i32,
-        ))
-    } else if modifiers.logo && keysym == xkb::KEY_Return {
-        // run terminal
-        Some(KeyAction::Run("weston-terminal".into()))
-    } else if modifiers.logo && (xkb::KEY_1..=xkb::KEY_9).contains(&keysym) {
-        Some(KeyAction::Screen((keysym - xkb::KEY_1) as usize))
-    } else if modifiers.logo && modifiers.shift && keysym == xkb::KEY_M {
-        Some(KeyAction::ScaleDown)
-    } else if modifiers.logo && modifiers.shift && keysym == xkb::KEY_P {
-        Some(KeyAction::ScaleUp)
-    } else {
-        None
+/// The keybindings of anvil
+pub fn key_bindings() -> KeyBindings<KeyAction> {
+    let mut bindings = KeyBindings::new();
+    let mut bind = |sequence: &str, action: KeyAction| {
+        bindings
+            .bind(DEFAULT_MODE, sequence, action)
+            .expect("Invalid keybinding");
+    };
+
+    bind("Ctrl+Alt+BackSpace", KeyAction::Quit);
+    bind("Super+q", KeyAction::Quit);
+    bind("Super+Return", KeyAction::Run("weston-terminal".into()));
+    for screen in 1..=9 {
+        bind(&format!("Super+{}", screen), KeyAction::Screen(screen - 1));
     }
+    bind("Super+Shift+M", KeyAction::ScaleDown);
+    bind("Super+Shift+P", KeyAction::ScaleUp);
+    bind("Super+space", KeyAction::NextLayout);
+
+    // switching the vt has to work even if a client inhibits shortcuts
+    for vt in 1..=12 {
+        let binding = Binding::parse(&format!("Ctrl+Alt+XF86Switch_VT_{}", vt), KeyAction::VtSwitch(vt))
+            .expect("Invalid keybinding")
+            .ignore_inhibitor();
+        bindings.add(DEFAULT_MODE, binding);
+    }
+
+    bindings
 }
@@ -3,16 +3,12 @@ use std::cell::RefCell;
 use smithay::{
     backend::renderer::utils::on_commit_buffer_handler,
     desktop::{
-        find_popup_root_surface, layer_map_for_output, Kind as SurfaceKind, LayerSurface, PopupKeyboardGrab,
-        PopupKind, PopupManager, PopupPointerGrab, PopupUngrabStrategy, Space, Window, WindowSurfaceType,
-    },
-    input::{
-        pointer::{
-            AxisFrame, ButtonEvent, Focus, GrabStartData as PointerGrabStartData, MotionEvent, PointerGrab,
-            PointerInnerHandle,
-        },
-        Seat,
+        find_popup_root_surface,
+        grabs::{ack_resize, commit_resize, MoveSurfaceGrab, ResizeSurfaceGrab, SpaceGrabHandler},
+        layer_map_for_output, Kind as SurfaceKind, LayerSurface, PopupKeyboardGrab, PopupKind, PopupManager,
+        PopupPointerGrab, PopupUngrabStrategy, Space, Window, WindowSurfaceType,
     },
+    input::{pointer::Focus, Seat},
     output::Output,
     reexports::{
         wayland_protocols::xdg::shell::server::xdg_toplevel,
@@ -21,7 +17,7 @@ use smithay::{
             Resource,
         },
     },
-    utils::{IsAlive, Logical, Point, Rectangle, Serial, Size},
+    utils::{Logical, Point, Rectangle, Serial, Size},
     wayland::{
         buffer::BufferHandler,
         compositor::{
@@ -35,8 +31,8 @@ use smithay::{
                 WlrLayerShellState,
             },
             xdg::{
-                Configure, PopupSurface, PositionerState, SurfaceCachedState, ToplevelSurface,
-                XdgPopupSurfaceData, XdgShellHandler, XdgShellState, XdgToplevelSurfaceData,
+                Configure, PopupSurface, PositionerState, ToplevelSurface, XdgPopupSurfaceData,
+                XdgShellHandler, XdgShellState, XdgToplevelSurfaceData,
             },
         },
     },
@@ -47,257 +43,6 @@ use crate::{
     state::{AnvilState, Backend},
 };
 
-struct MoveSurfaceGrab<B: 'static> {
-    start_data: PointerGrabStartData<AnvilState<B>>,
-    window: Window,
-    initial_window_location: Point<i32, Logical>,
-}
-
-impl<BackendData> PointerGrab<AnvilState<BackendData>> for MoveSurfaceGrab<BackendData> {
-    fn motion(
-        &mut self,
-        data: &mut AnvilState<BackendData>,
-        handle: &mut PointerInnerHandle<'_, AnvilState<BackendData>>,
-        _focus: Option<(FocusTarget, Point<i32, Logical>)>,
-        event: &MotionEvent,
-    ) {
-        // While the grab is active, no client has pointer focus
-        handle.motion(data, None, event);
-
-        let delta = event.location - self.start_data.location;
-        let new_location = self.initial_window_location.to_f64() + delta;
-
-        data.space
-            .map_element(self.window.clone(), new_location.to_i32_round(), true);
-    }
-
-    fn button(
-        &mut self,
-        data: &mut AnvilState<BackendData>,
-        handle: &mut PointerInnerHandle<'_, AnvilState<BackendData>>,
-        event: &ButtonEvent,
-    ) {
-        handle.button(data, event);
-        if handle.current_pressed().is_empty() {
-            // No more buttons are pressed, release the grab.
-            handle.unset_grab(data, event.serial, event.time);
-        }
-    }
-
-    fn axis(
-        &mut self,
-        data: &mut AnvilState<BackendData>,
-        handle: &mut PointerInnerHandle<'_, AnvilState<BackendData>>,
-        details: AxisFrame,
-    ) {
-        handle.axis(data, details)
-    }
-
-    fn start_data(&self) -> &PointerGrabStartData<AnvilState<BackendData>> {
-        &self.start_data
-    }
-}
-
-bitflags::bitflags! {
-    struct ResizeEdge: u32 {
-        const NONE = 0;
-        const TOP = 1;
-        const BOTTOM = 2;
-        const LEFT = 4;
-        const TOP_LEFT = 5;
-        const BOTTOM_LEFT = 6;
-        const RIGHT = 8;
-        const TOP_RIGHT = 9;
-        const BOTTOM_RIGHT = 10;
-    }
-}
-
-impl From<xdg_toplevel::ResizeEdge> for ResizeEdge {
-    #[inline]
-    fn from(x: xdg_toplevel::ResizeEdge) -> Self {
-        Self::from_bits(x as u32).unwrap()
-    }
-}
-
-impl From<ResizeEdge> for xdg_toplevel::ResizeEdge {
-    #[inline]
-    fn from(x: ResizeEdge) -> Self {
-        use std::convert::TryFrom;
-
-        Self::try_from(x.bits()).unwrap()
-    }
-}
-
-struct ResizeSurfaceGrab<B: 'static> {
-    start_data: PointerGrabStartData<AnvilState<B>>,
-    window: Window,
-    edges: ResizeEdge,
-    initial_window_location: Point<i32, Logical>,
-    initial_window_size: Size<i32, Logical>,
-    last_window_size: Size<i32, Logical>,
-}
-
-impl<BackendData> PointerGrab<AnvilState<BackendData>> for ResizeSurfaceGrab<BackendData> {
-    fn motion(
-        &mut self,
-        data: &mut AnvilState<BackendData>,
-        handle: &mut PointerInnerHandle<'_, AnvilState<BackendData>>,
-        _focus: Option<(FocusTarget, Point<i32, Logical>)>,
-        event: &MotionEvent,
-    ) {
-        // While the grab is active, no client has pointer focus
-        handle.motion(data, None, event);
-
-        // It is impossible to get `min_size` and `max_size` of dead toplevel, so we return early.
-        if !self.window.toplevel().alive() {
-            handle.unset_grab(data, event.serial, event.time);
-            return;
-        }
-
-        let (mut dx, mut dy) = (event.location - self.start_data.location).into();
-
-        let mut new_window_width = self.initial_window_size.w;
-        let mut new_window_height = self.initial_window_size.h;
-
-        let left_right = ResizeEdge::LEFT | ResizeEdge::RIGHT;
-        let top_bottom = ResizeEdge::TOP | ResizeEdge::BOTTOM;
-
-        if self.edges.intersects(left_right) {
-            if self.edges.intersects(ResizeEdge::LEFT) {
-                dx = -dx;
-            }
-
-            new_window_width = (self.initial_window_size.w as f64 + dx) as i32;
-        }
-
-        if self.edges.intersects(top_bottom) {
-            if self.edges.intersects(ResizeEdge::TOP) {
-                dy = -dy;
-            }
-
-            new_window_height = (self.initial_window_size.h as f64 + dy) as i32;
-        }
-
-        let (min_size, max_size) = with_states(self.window.toplevel().wl_surface(), |states| {
-            let data = states.cached_state.current::<SurfaceCachedState>();
-            (data.min_size, data.max_size)
-        });
-
-        let min_width = min_size.w.max(1);
-        let min_height = min_size.h.max(1);
-        let max_width = if max_size.w == 0 {
-            i32::max_value()
-        } else {
-            max_size.w
-        };
-        let max_height = if max_size.h == 0 {
-            i32::max_value()
-        } else {
-            max_size.h
-        };
-
-        new_window_width = new_window_width.max(min_width).min(max_width);
-        new_window_height = new_window_height.max(min_height).min(max_height);
-
-        self.last_window_size = (new_window_width, new_window_height).into();
-
-        match &self.window.toplevel() {
-            SurfaceKind::Xdg(xdg) => {
-                xdg.with_pending_state(|state| {
-                    state.states.set(xdg_toplevel::State::Resizing);
-                    state.size = Some(self.last_window_size);
-                });
-                xdg.send_configure();
-            }
-            #[cfg(feature = "xwayland")]
-            SurfaceKind::X11(_) => {
-                // TODO: What to do here? Send the update via X11?
-            }
-        }
-    }
-
-    fn button(
-        &mut self,
-        data: &mut AnvilState<BackendData>,
-        handle: &mut PointerInnerHandle<'_, AnvilState<BackendData>>,
-        event: &ButtonEvent,
-    ) {
-        handle.button(data, event);
-        if handle.current_pressed().is_empty() {
-            // No more buttons are pressed, release the grab.
-            handle.unset_grab(data, event.serial, event.time);
-
-            // If toplevel is dead, we can't resize it, so we return early.
-            if !self.window.toplevel().alive() {
-                return;
-            }
-
-            #[cfg_attr(not(feature = "xwayland"), allow(irrefutable_let_patterns))]
-            if let SurfaceKind::Xdg(xdg) = &self.window.toplevel() {
-                xdg.with_pending_state(|state| {
-                    state.states.unset(xdg_toplevel::State::Resizing);
-                    state.size = Some(self.last_window_size);
-                });
-                xdg.send_configure();
-                if self.edges.intersects(ResizeEdge::TOP_LEFT) {
-                    let geometry = self.window.geometry();
-                    let mut location = data.space.element_location(&self.window).unwrap();
-
-                    if self.edges.intersects(ResizeEdge::LEFT) {
-                        location.x =
-                            self.initial_window_location.x + (self.initial_window_size.w - geometry.size.w);
-                    }
-                    if self.edges.intersects(ResizeEdge::TOP) {
-                        location.y =
-                            self.initial_window_location.y + (self.initial_window_size.h - geometry.size.h);
-                    }
-
-                    data.space.map_element(self.window.clone(), location, true);
-                }
-
-                with_states(self.window.toplevel().wl_surface(), |states| {
-                    let mut data = states
-                        .data_map
-                        .get::<RefCell<SurfaceData>>()
-                        .unwrap()
-                        .borrow_mut();
-                    if let ResizeState::Resizing(resize_data) = data.resize_state {
-                        data.resize_state = ResizeState::WaitingForFinalAck(resize_data, event.serial);
-                    } else {
-                        panic!("invalid resize state: {:?}", data.resize_state);
-                    }
-                });
-            } else {
-                with_states(self.window.toplevel().wl_surface(), |states| {
-                    let mut data = states
-                        .data_map
-                        .get::<RefCell<SurfaceData>>()
-                        .unwrap()
-                        .borrow_mut();
-                    if let ResizeState::Resizing(resize_data) = data.resize_state {
-                        data.resize_state = ResizeState::WaitingForCommit(resize_data);
-                    } else {
-                        panic!("invalid resize state: {:?}", data.resize_state);
-                    }
-                });
-            }
-        }
-    }
-
-    fn axis(
-        &mut self,
-        data: &mut AnvilState<BackendData>,
-        handle: &mut PointerInnerHandle<'_, AnvilState<BackendData>>,
-        details: AxisFrame,
-    ) {
-        handle.axis(data, details)
-    }
-
-    fn start_data(&self) -> &PointerGrabStartData<AnvilState<BackendData>> {
-        &self.start_data
-    }
-}
-
 fn fullscreen_output_geometry(
     wl_surface: &WlSurface,
     wl_output: Option<&wl_output::WlOutput>,
@@ -358,6 +103,7 @@ impl<BackendData: Backend> CompositorHandler for AnvilState<BackendData> {
             }
             if let Some(window) = self.window_for_surface(&root) {
                 window.on_commit();
+                commit_resize(&mut self.space, &window);
             }
         }
         self.popups.commit(surface);
@@ -384,13 +130,9 @@ impl<BackendData: Backend> XdgShellHandler for AnvilState<BackendData> {
         // of a xdg_surface has to be sent during the commit if
         // the surface is not already configured
 
-        // TODO: properly recompute the geometry with the whole of positioner state
+        let geometry = self.unconstrain_popup(&surface, &positioner);
         surface.with_pending_state(|state| {
-            // NOTE: This is not really necessary as the default geometry
-            // is already set the same way, but for demonstrating how
-            // to set the initial popup geometry this code is left as
-            // an example
-            state.geometry = positioner.get_geometry();
+            state.geometry = geometry;
         });
         if let Err(err) = self.popups.track_popup(PopupKind::from(surface)) {
             slog::warn!(self.log, "Failed to track popup: {}", err);
@@ -398,12 +140,8 @@ impl<BackendData: Backend> XdgShellHandler for AnvilState<BackendData> {
     }
 
     fn reposition_request(&mut self, surface: PopupSurface, positioner: PositionerState, token: u32) {
+        let geometry = self.unconstrain_popup(&surface, &positioner);
         surface.with_pending_state(|state| {
-            // NOTE: This is again a simplification, a proper compositor would
-            // calculate the geometry of the popup here. For simplicity we just
-            // use the default implementation here that does not take the
-            // window position and output constraints into account.
-            let geometry = positioner.get_geometry();
             state.geometry = geometry;
             state.positioner = positioner;
         });
@@ -463,11 +201,7 @@ impl<BackendData: Backend> XdgShellHandler for AnvilState<BackendData> {
             initial_window_location = (pos.x as i32, pos.y as i32).into();
         }
 
-        let grab = MoveSurfaceGrab {
-            start_data,
-            window,
-            initial_window_location,
-        };
+        let grab = MoveSurfaceGrab::new(start_data, window, initial_window_location);
 
         pointer.set_grab(self, grab, serial, Focus::Clear);
     }
@@ -504,83 +238,15 @@ impl<BackendData: Backend> XdgShellHandler for AnvilState<BackendData> {
             return;
         }
 
-        let geometry = window.geometry();
-        let loc = self.space.element_location(&window).unwrap();
-        let (initial_window_location, initial_window_size) = (loc, geometry.size);
-
-        with_states(surface.wl_surface(), move |states| {
-            states
-                .data_map
-                .get::<RefCell<SurfaceData>>()
-                .unwrap()
-                .borrow_mut()
-                .resize_state = ResizeState::Resizing(ResizeData {
-                edges: edges.into(),
-                initial_window_location,
-                initial_window_size,
-            });
-        });
-
-        let grab = ResizeSurfaceGrab {
-            start_data,
-            window,
-            edges: edges.into(),
-            initial_window_location,
-            initial_window_size,
-            last_window_size: initial_window_size,
-        };
+        let geometry = self.space.element_geometry(&window).unwrap();
+        let grab = ResizeSurfaceGrab::new(start_data, window, edges.into(), geometry);
 
         pointer.set_grab(self, grab, serial, Focus::Clear);
     }
 
     fn ack_configure(&mut self, surface: WlSurface, configure: Configure) {
-        if let Configure::Toplevel(configure) = configure {
-            if let Some(serial) = with_states(&surface, |states| {
-                if let Some(data) = states.data_map.get::<RefCell<SurfaceData>>() {
-                    if let ResizeState::WaitingForFinalAck(_, serial) = data.borrow().resize_state {
-                        return Some(serial);
-                    }
-                }
-
-                None
-            }) {
-                // When the resize grab is released the surface
-                // resize state will be set to WaitingForFinalAck
-                // and the client will receive a configure request
-                // without the resize state to inform the client
-                // resizing has finished. Here we will wait for
-                // the client to acknowledge the end of the
-                // resizing. To check if the surface was resizing
-                // before sending the configure we need to use
-                // the current state as the received acknowledge
-                // will no longer have the resize state set
-                let is_resizing = with_states(&surface, |states| {
-                    states
-                        .data_map
-                        .get::<XdgToplevelSurfaceData>()
-                        .unwrap()
-                        .lock()
-                        .unwrap()
-                        .current
-                        .states
-                        .contains(xdg_toplevel::State::Resizing)
-                });
-
-                if configure.serial >= serial && is_resizing {
-                    with_states(&surface, |states| {
-                        let mut data = states
-                            .data_map
-                            .get::<RefCell<SurfaceData>>()
-                            .unwrap()
-                            .borrow_mut();
-                        if let ResizeState::WaitingForFinalAck(resize_data, _) = data.resize_state {
-                            data.resize_state = ResizeState::WaitingForCommit(resize_data);
-                        } else {
-                            unreachable!()
-                        }
-                    });
-                }
-            }
+        if let Some(window) = self.window_for_surface(&surface) {
+            ack_resize(&window, &configure);
         }
     }
 
@@ -719,6 +385,12 @@ impl<BackendData: Backend> XdgShellHandler for AnvilState<BackendData> {
     }
 }
 
+impl<BackendData> SpaceGrabHandler<Window> for AnvilState<BackendData> {
+    fn space_for_element(&mut self, _window: &Window) -> Option<&mut Space<Window>> {
+        Some(&mut self.space)
+    }
+}
+
 impl<BackendData> WlrLayerShellHandler for AnvilState<BackendData> {
     fn shell_state(&mut self) -> &mut WlrLayerShellState {
         &mut self.layer_shell_state
@@ -747,42 +419,39 @@ impl<BackendData> AnvilState<BackendData> {
             .find(|window| window.toplevel().wl_surface() == surface)
             .cloned()
     }
-}
-
-/// Information about the resize operation.
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
-pub struct ResizeData {
-    /// The edges the surface is being resized with.
-    edges: ResizeEdge,
-    /// The initial window location.
-    initial_window_location: Point<i32, Logical>,
-    /// The initial window size (geometry width and height).
-    initial_window_size: Size<i32, Logical>,
-}
 
-/// State of the resize operation.
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
-pub enum ResizeState {
-    /// The surface is not being resized.
-    NotResizing,
-    /// The surface is currently being resized.
-    Resizing(ResizeData),
-    /// The resize has finished, and the surface needs to ack the final configure.
-    WaitingForFinalAck(ResizeData, Serial),
-    /// The resize has finished, and the surface needs to commit its final state.
-    WaitingForCommit(ResizeData),
-}
+    fn unconstrain_popup(
+        &self,
+        popup: &PopupSurface,
+        positioner: &PositionerState,
+    ) -> Rectangle<i32, Logical> {
+        let kind = PopupKind::Xdg(popup.clone());
+        let target = find_popup_root_surface(&kind).ok().and_then(|root| {
+            let (root_location, output) = if let Some(window) = self.window_for_surface(&root) {
+                let output = self.space.outputs_for_element(&window).into_iter().next()?;
+                (self.space.element_geometry(&window)?.loc, output)
+            } else {
+                self.space.outputs().find_map(|o| {
+                    let map = layer_map_for_output(o);
+                    let layer = map.layer_for_surface(&root, WindowSurfaceType::TOPLEVEL)?;
+                    let location = map.layer_geometry(layer)?.loc + self.space.output_geometry(o)?.loc;
+                    Some((location, o.clone()))
+                })?
+            };
+            let output_geometry = self.space.output_geometry(&output)?;
+            PopupManager::popup_target_area(&kind, root_location, &output, output_geometry).ok()
+        });
 
-impl Default for ResizeState {
-    fn default() -> Self {
-        ResizeState::NotResizing
+        match target {
+            Some(target) => positioner.get_unconstrained_geometry(target),
+            None => positioner.get_geometry(),
+        }
     }
 }
 
 #[derive(Default)]
 pub struct SurfaceData {
     pub geometry: Option<Rectangle<i32, Logical>>,
-    pub resize_state: ResizeState,
 }
 
 fn ensure_initial_configure(surface: &WlSurface, space: &Space<Window>, popups: &mut PopupManager) {
@@ -820,19 +489,6 @@ fn ensure_initial_configure(surface: &WlSurface, space: &Space<Window>, popups:
             }
         }
 
-        with_states(surface, |states| {
-            let mut data = states
-                .data_map
-                .get::<RefCell<SurfaceData>>()
-                .unwrap()
-                .borrow_mut();
-
-            // Finish resizing.
-            if let ResizeState::WaitingForCommit(_) = data.resize_state {
-                data.resize_state = ResizeState::NotResizing;
-            }
-        });
-
         return;
     }
 
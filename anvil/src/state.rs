@@ -1,6 +1,7 @@
 use std::{
     os::unix::prelude::AsRawFd,
     sync::{atomic::AtomicBool, Arc, Mutex},
+    time::Duration,
 };
 
 use smithay::{
@@ -9,13 +10,20 @@ use smithay::{
     delegate_seat, delegate_shm, delegate_tablet_manager, delegate_text_input_manager, delegate_viewporter,
     delegate_xdg_activation, delegate_xdg_decoration, delegate_xdg_shell,
     desktop::{PopupManager, Space, Window},
-    input::{keyboard::XkbConfig, pointer::CursorImageStatus, Seat, SeatHandler, SeatState},
+    input::{
+        keyboard::{KeyBindings, XkbConfig},
+        pointer::CursorImageStatus,
+        Seat, SeatHandler, SeatState,
+    },
     output::Output,
     reexports::{
         calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction},
         io_lifetimes::OwnedFd,
-        wayland_protocols::xdg::decoration::{
-            self as xdg_decoration, zv1::server::zxdg_toplevel_decoration_v1::Mode as DecorationMode,
+        wayland_protocols::{
+            wp::primary_selection::zv1::server::zwp_primary_selection_source_v1::ZwpPrimarySelectionSourceV1 as PrimarySource,
+            xdg::decoration::{
+                self as xdg_decoration, zv1::server::zxdg_toplevel_decoration_v1::Mode as DecorationMode,
+            },
         },
         wayland_server::{
             backend::{ClientData, ClientId, DisconnectReason},
@@ -55,11 +63,14 @@ use smithay::{
     },
 };
 
-use crate::focus::FocusTarget;
 #[cfg(feature = "xwayland")]
 use crate::xwayland::X11State;
+use crate::{focus::FocusTarget, input_handler::KeyAction};
 #[cfg(feature = "xwayland")]
-use smithay::xwayland::{XWayland, XWaylandEvent};
+use smithay::{
+    wayland::{data_device::with_source_metadata, primary_selection},
+    xwayland::{selection::SelectionType, XWayland, XWaylandConfig, XWaylandEvent},
+};
 
 pub struct CalloopData<BackendData: 'static> {
     pub state: AnvilState<BackendData>,
@@ -105,7 +116,7 @@ pub struct AnvilState<BackendData: 'static> {
     pub log: slog::Logger,
 
     // input-related fields
-    pub suppressed_keys: Vec<u32>,
+    pub key_bindings: KeyBindings<KeyAction>,
     pub pointer_location: Point<f64, Logical>,
     pub cursor_status: Arc<Mutex<CursorImageStatus>>,
     pub seat_name: String,
@@ -124,21 +135,48 @@ impl<BackendData> DataDeviceHandler for AnvilState<BackendData> {
     fn data_device_state(&self) -> &DataDeviceState {
         &self.data_device_state
     }
-    fn send_selection(&mut self, _mime_type: String, _fd: OwnedFd) {
-        unreachable!("Anvil doesn't do server-side selections");
+    #[cfg_attr(not(feature = "xwayland"), allow(unused_variables))]
+    fn new_selection(&mut self, source: Option<WlDataSource>) {
+        #[cfg(feature = "xwayland")]
+        self.xwayland_new_selection(
+            SelectionType::Clipboard,
+            source.and_then(|source| with_source_metadata(&source, |meta| meta.mime_types.clone()).ok()),
+        );
+    }
+    #[cfg_attr(not(feature = "xwayland"), allow(unused_variables))]
+    fn send_selection(&mut self, mime_type: String, fd: OwnedFd) {
+        // the only server-side selections are the ones of X11 clients
+        #[cfg(feature = "xwayland")]
+        self.xwayland_send_selection(SelectionType::Clipboard, mime_type, fd);
     }
 }
 impl<BackendData> ClientDndGrabHandler for AnvilState<BackendData> {
-    fn started(&mut self, _source: Option<WlDataSource>, icon: Option<WlSurface>, _seat: Seat<Self>) {
+    #[cfg_attr(not(feature = "xwayland"), allow(unused_variables))]
+    fn started(&mut self, source: Option<WlDataSource>, icon: Option<WlSurface>, _seat: Seat<Self>) {
         self.dnd_icon = icon;
+        #[cfg(feature = "xwayland")]
+        self.xwayland_dnd_started(source);
     }
     fn dropped(&mut self, _seat: Seat<Self>) {
         self.dnd_icon = None;
+        #[cfg(feature = "xwayland")]
+        self.xwayland_dnd_dropped();
+    }
+    #[cfg(feature = "xwayland")]
+    fn external_drop(&mut self, _seat: Seat<Self>) -> bool {
+        self.xwayland_dnd_drop()
     }
 }
 impl<BackendData> ServerDndGrabHandler for AnvilState<BackendData> {
-    fn send(&mut self, _mime_type: String, _fd: OwnedFd) {
-        unreachable!("Anvil doesn't do server-side grabs");
+    #[cfg_attr(not(feature = "xwayland"), allow(unused_variables))]
+    fn send(&mut self, mime_type: String, fd: OwnedFd) {
+        // the only server-side grabs are drag'n'drop operations of X11 clients
+        #[cfg(feature = "xwayland")]
+        self.xwayland_send_selection(SelectionType::Dnd, mime_type, fd);
+    }
+    fn finished(&mut self) {
+        #[cfg(feature = "xwayland")]
+        self.xwayland_dnd_finished();
     }
 }
 delegate_data_device!(@<BackendData: 'static> AnvilState<BackendData>);
@@ -149,6 +187,21 @@ impl<BackendData> PrimarySelectionHandler for AnvilState<BackendData> {
     fn primary_selection_state(&self) -> &PrimarySelectionState {
         &self.primary_selection_state
     }
+    #[cfg_attr(not(feature = "xwayland"), allow(unused_variables))]
+    fn new_selection(&mut self, source: Option<PrimarySource>) {
+        #[cfg(feature = "xwayland")]
+        self.xwayland_new_selection(
+            SelectionType::Primary,
+            source.and_then(|source| {
+                primary_selection::with_source_metadata(&source, |meta| meta.mime_types.clone()).ok()
+            }),
+        );
+    }
+    #[cfg_attr(not(feature = "xwayland"), allow(unused_variables))]
+    fn send_selection(&mut self, mime_type: String, fd: OwnedFd) {
+        #[cfg(feature = "xwayland")]
+        self.xwayland_send_selection(SelectionType::Primary, mime_type, fd);
+    }
 }
 delegate_primary_selection!(@<BackendData: 'static> AnvilState<BackendData>);
 
@@ -322,8 +375,10 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
 
         let cursor_status = Arc::new(Mutex::new(CursorImageStatus::Default));
         seat.add_pointer();
-        seat.add_keyboard(XkbConfig::default(), 200, 25)
+        let keyboard = seat
+            .add_keyboard(XkbConfig::default(), 200, 25)
             .expect("Failed to initialize the keyboard");
+        keyboard.set_layout_per_focus(true);
 
         let cursor_status2 = cursor_status.clone();
         seat.tablet_seat().on_cursor_surface(move |_tool, new_status| {
@@ -337,7 +392,12 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
 
         #[cfg(feature = "xwayland")]
         let xwayland = {
-            let (xwayland, channel) = XWayland::new(log.clone(), &display.handle());
+            // only spawn Xwayland once an X11 client shows up, and again whenever it went away
+            let config = XWaylandConfig {
+                lazy: true,
+                ..Default::default()
+            };
+            let (xwayland, channel) = XWayland::with_config(log.clone(), &display.handle(), config);
             let ret = handle.insert_source(channel, |event, _, data| match event {
                 XWaylandEvent::Ready {
                     connection, client, ..
@@ -375,7 +435,7 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
             xdg_shell_state,
             dnd_icon: None,
             log,
-            suppressed_keys: Vec::new(),
+            key_bindings: crate::input_handler::key_bindings(),
             pointer_location: (0.0, 0.0).into(),
             cursor_status,
             seat_name,
@@ -389,14 +449,19 @@ impl<BackendData: Backend + 'static> AnvilState<BackendData> {
     }
 
     pub fn send_frames(&self, output: &Output) {
+        let time = self.start_time.elapsed().as_millis() as u32;
         self.space.elements().for_each(|window| {
-            if self.space.outputs_for_element(window).contains(output) {
-                window.send_frame(self.start_time.elapsed().as_millis() as u32)
+            let outputs = self.space.outputs_for_element(window);
+            if outputs.contains(output) {
+                window.send_frame(time)
+            } else if outputs.is_empty() {
+                // keep hidden windows going, but don't let them render at full speed
+                window.send_frame_throttled(time, Duration::from_secs(1))
             }
         });
         let map = smithay::desktop::layer_map_for_output(output);
         for layer_surface in map.layers() {
-            layer_surface.send_frame(self.start_time.elapsed().as_millis() as u32)
+            layer_surface.send_frame(time)
         }
     }
 }
@@ -6,7 +6,7 @@ use std::{
     path::PathBuf,
     rc::Rc,
     sync::{atomic::Ordering, Mutex},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use slog::Logger;
@@ -29,7 +29,7 @@ use smithay::{
 };
 use smithay::{
     backend::{
-        drm::{DrmDevice, DrmError, DrmEvent, DrmNode, GbmBufferedSurface, NodeType},
+        drm::{edid::EdidInfo, DrmDevice, DrmError, DrmEvent, DrmNode, GbmBufferedSurface, NodeType},
         egl::{EGLContext, EGLDevice, EGLDisplay},
         libinput::{LibinputInputBackend, LibinputSessionInterface},
         renderer::{
@@ -44,16 +44,14 @@ use smithay::{
         SwapBuffersError,
     },
     desktop::{
+        frame_scheduler_for_output,
         space::{Space, SurfaceTree},
         Window,
     },
     input::pointer::{CursorImageAttributes, CursorImageStatus},
     output::{Mode, Output, PhysicalProperties, Subpixel},
     reexports::{
-        calloop::{
-            timer::{TimeoutAction, Timer},
-            Dispatcher, EventLoop, LoopHandle, RegistrationToken,
-        },
+        calloop::{Dispatcher, EventLoop, LoopHandle, RegistrationToken},
         drm::{
             self,
             control::{
@@ -430,17 +428,34 @@ fn scan_connectors(
                 crtc,
             );
 
-            let mode = connector_info.modes()[0];
-            let mut surface = match device.create_surface(crtc, mode, &[connector_info.handle()]) {
+            let edid = EdidInfo::for_connector(device, connector_info.handle())
+                .ok()
+                .flatten();
+            // use the native mode of the monitor, if the driver offers it
+            let mode = edid
+                .as_ref()
+                .and_then(|info| info.preferred_mode)
+                .and_then(|preferred| {
+                    connector_info
+                        .modes()
+                        .iter()
+                        .filter(|mode| {
+                            let (w, h) = mode.size();
+                            (w as i32, h as i32) == (preferred.size.w, preferred.size.h)
+                        })
+                        .min_by_key(|mode| (mode.vrefresh() as i32 * 1000 - preferred.refresh).abs())
+                })
+                .copied()
+                .unwrap_or(connector_info.modes()[0]);
+            let surface = match device.create_surface(crtc, mode, &[connector_info.handle()]) {
                 Ok(surface) => surface,
                 Err(err) => {
                     warn!(logger, "Failed to create drm surface: {}", err);
                     continue;
                 }
             };
-            surface.link(signaler.clone());
 
-            let gbm_surface =
+            let mut gbm_surface =
                 match GbmBufferedSurface::new(surface, gbm.clone(), formats.clone(), logger.clone()) {
                     Ok(renderer) => renderer,
                     Err(err) => {
@@ -448,6 +463,8 @@ fn scan_connectors(
                         continue;
                     }
                 };
+            // resets the drm surface and its buffers once the session is active again
+            gbm_surface.link(signaler.clone());
 
             let size = mode.size();
             let mode = Mode {
@@ -470,16 +487,20 @@ fn scan_connectors(
             let output_name = format!("{}-{}", interface_short_name, connector_info.interface_id());
 
             let (phys_w, phys_h) = connector_info.size().unwrap_or((0, 0));
-            let output = Output::new(
-                output_name,
-                PhysicalProperties {
+            let physical_properties = match edid {
+                Some(info) => {
+                    info!(logger, "Found monitor {:?}", info.identity());
+                    info.physical_properties()
+                }
+                None => PhysicalProperties {
                     size: (phys_w as i32, phys_h as i32).into(),
                     subpixel: Subpixel::Unknown,
                     make: "Smithay".into(),
                     model: "Generic DRM".into(),
+                    hdr: None,
                 },
-                None,
-            );
+            };
+            let output = Output::new(output_name, physical_properties, None);
             let global = output.create_global::<AnvilState<UdevData>>(&display.handle());
             let position = (
                 space
@@ -569,19 +590,20 @@ impl AnvilState<UdevData> {
             &self.backend_data.signaler,
             &self.log,
         )));
+        #[cfg(feature = "xwayland")]
+        self.update_xwayland_scale();
 
         let handle = self.handle.clone();
-        let restart_token = self.backend_data.signaler.register(move |signal| match signal {
-            SessionSignal::ActivateSession | SessionSignal::ActivateDevice { .. } => {
+        let restart_token = self.backend_data.signaler.register(move |signal| {
+            if let SessionSignal::ActivateSession = signal {
                 handle.insert_idle(move |data| data.state.render(node, None));
             }
-            _ => {}
         });
 
         device.link(self.backend_data.signaler.clone());
         let event_dispatcher =
             Dispatcher::new(device, move |event, _, data: &mut CalloopData<_>| match event {
-                DrmEvent::VBlank(crtc) => data.state.render(node, Some(crtc)),
+                DrmEvent::VBlank(crtc) => data.state.frame_finish(node, crtc),
                 DrmEvent::Error(error) => {
                     error!(data.state.log, "{:?}", error);
                 }
@@ -663,6 +685,8 @@ impl AnvilState<UdevData> {
                 schedule_initial_render(&mut self.backend_data.gpus, surface.clone(), &loop_handle, logger);
             }
         }
+        #[cfg(feature = "xwayland")]
+        self.update_xwayland_scale();
     }
 
     fn device_removed(&mut self, device: dev_t) {
@@ -692,6 +716,8 @@ impl AnvilState<UdevData> {
                 self.space.unmap_output(&output);
             }
             crate::shell::fixup_positions(&mut self.space);
+            #[cfg(feature = "xwayland")]
+            self.update_xwayland_scale();
 
             self.handle.remove(backend_data.registration_token);
             let _device = backend_data.event_dispatcher.into_source_inner();
@@ -700,6 +726,30 @@ impl AnvilState<UdevData> {
         }
     }
 
+    fn frame_finish(&mut self, dev_id: DrmNode, crtc: crtc::Handle) {
+        let output = if let Some(output) = self.space.outputs().find(|o| {
+            o.user_data().get::<UdevOutputId>()
+                == Some(&UdevOutputId {
+                    device_id: dev_id,
+                    crtc,
+                })
+        }) {
+            output.clone()
+        } else {
+            // somehow we got called with an invalid output
+            return;
+        };
+
+        // delay the next frame to just before the next vblank
+        let mut scheduler = frame_scheduler_for_output(&output);
+        scheduler.presented(Instant::now());
+        scheduler
+            .schedule_render(&self.handle, Instant::now(), move |data| {
+                data.state.render(dev_id, Some(crtc))
+            })
+            .expect("failed to schedule frame timer");
+    }
+
     // If crtc is `Some()`, render it, else render all crtcs
     fn render(&mut self, dev_id: DrmNode, crtc: Option<crtc::Handle>) {
         let device_backend = match self.backend_data.backends.get_mut(&dev_id) {
@@ -776,6 +826,7 @@ impl AnvilState<UdevData> {
                 continue;
             };
 
+            frame_scheduler_for_output(&output).render_started(Instant::now());
             let result = render_surface(
                 &mut *surface.borrow_mut(),
                 &mut renderer,
@@ -789,6 +840,9 @@ impl AnvilState<UdevData> {
                 &mut *self.cursor_status.lock().unwrap(),
                 &self.log,
             );
+            if let Ok(true) = result {
+                frame_scheduler_for_output(&output).render_finished(Instant::now());
+            }
             let reschedule = match result {
                 Ok(has_rendered) => !has_rendered,
                 Err(err) => {
@@ -809,13 +863,9 @@ impl AnvilState<UdevData> {
             };
 
             if reschedule {
-                let timer = Timer::from_duration(Duration::from_millis(
-                    1000 /*a seconds*/ / 60, /*refresh rate*/
-                ));
-                self.handle
-                    .insert_source(timer, move |_, _, data| {
-                        data.state.render(dev_id, Some(crtc));
-                        TimeoutAction::Drop
+                frame_scheduler_for_output(&output)
+                    .schedule_render(&self.handle, Instant::now(), move |data| {
+                        data.state.render(dev_id, Some(crtc))
                     })
                     .expect("failed to schedule frame timer");
             }
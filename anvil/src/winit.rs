@@ -103,6 +103,7 @@ pub fn run_winit(log: Logger) {
             subpixel: Subpixel::Unknown,
             make: "Smithay".into(),
             model: "Winit".into(),
+            hdr: None,
         },
         log.clone(),
     );
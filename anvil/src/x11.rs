@@ -167,6 +167,7 @@ pub fn run_x11(log: Logger) {
             subpixel: Subpixel::Unknown,
             make: "Smithay".into(),
             model: "X11".into(),
+            hdr: None,
         },
         log.clone(),
     );
@@ -3,9 +3,19 @@ use std::{collections::HashMap, convert::TryFrom, os::unix::net::UnixStream, syn
 use crate::AnvilState;
 use smithay::{
     desktop::{Kind, Space, Window, X11Surface},
-    reexports::wayland_server::{protocol::wl_surface::WlSurface, Client, DisplayHandle, Resource},
-    utils::{x11rb::X11Source, Logical, Point},
-    wayland::compositor::give_role,
+    reexports::{
+        io_lifetimes::OwnedFd,
+        wayland_server::{
+            protocol::{wl_data_source::WlDataSource, wl_surface::WlSurface},
+            Client, DisplayHandle, Resource,
+        },
+    },
+    utils::{x11rb::X11Source, Logical, Physical, Point, Size},
+    wayland::{
+        compositor::{give_role, with_states},
+        seat::WaylandFocus,
+    },
+    xwayland::selection::{SelectionType, XWaylandSelection},
 };
 use x11rb::{
     connection::Connection as _,
@@ -31,13 +41,27 @@ impl<BackendData: 'static> AnvilState<BackendData> {
     pub fn xwayland_ready(&mut self, connection: UnixStream, client: Client) {
         let (wm, source) = X11State::start_wm(connection, client, self.log.clone()).unwrap();
         self.x11_state = Some(wm);
+        self.update_xwayland_scale();
         let log = self.log.clone();
+        let handle = self.handle.clone();
         self.handle
             .insert_source(source, move |event, _, data| {
+                let dh = data.display.handle();
                 if let Some(x11) = data.state.x11_state.as_mut() {
-                    match x11.handle_event(event, &data.display.handle(), &mut data.state.space) {
-                        Ok(()) => {}
-                        Err(err) => error!(log, "Error while handling X11 event: {}", err),
+                    match x11.selection.handle_event(&event, &handle) {
+                        Ok(Some(selection_event)) => {
+                            if let Err(err) =
+                                x11.selection
+                                    .forward_event(selection_event, &dh, &data.state.seat)
+                            {
+                                error!(log, "Error while forwarding X11 selection event: {}", err);
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(err) => error!(log, "Error while handling X11 selection event: {}", err),
+                    }
+                    if let Err(err) = x11.handle_event(event, &dh, &mut data.state.space) {
+                        error!(log, "Error while handling X11 event: {}", err);
                     }
                 }
             })
@@ -46,10 +70,150 @@ impl<BackendData: 'static> AnvilState<BackendData> {
 
     pub fn xwayland_exited(&mut self) {
         let _ = self.x11_state.take();
-        error!(self.log, "Xwayland crashed");
+        info!(self.log, "Xwayland exited");
+    }
+
+    /// Let XWayland render at the highest output scale, if enabled through `ANVIL_XWAYLAND_HIDPI`
+    ///
+    /// The X11 coordinate space is then `scale` times larger than the logical one.
+    /// Call this whenever outputs are added, removed or change their scale.
+    pub fn update_xwayland_scale(&mut self) {
+        let x11 = match self.x11_state.as_mut() {
+            Some(x11) => x11,
+            None => return,
+        };
+        if !x11.hidpi {
+            return;
+        }
+
+        let scale = self
+            .space
+            .outputs()
+            .map(|output| output.current_scale().integer_scale())
+            .max()
+            .unwrap_or(1)
+            .max(1) as u32;
+        // new outputs need to be advertised scaled as well
+        for output in self.space.outputs() {
+            output.set_xdg_output_client_scale(&x11.client, scale);
+        }
+        if scale == x11.scale {
+            return;
+        }
+
+        info!(self.log, "Running Xwayland at scale {}", scale);
+        x11.scale = scale;
+        for window in self.space.elements() {
+            if let Kind::X11(surface) = window.toplevel() {
+                surface.set_scale(scale);
+            }
+        }
+    }
+
+    /// Offer a new selection of a wayland client to X11 clients
+    pub fn xwayland_new_selection(&mut self, selection: SelectionType, mime_types: Option<Vec<String>>) {
+        if let Some(x11) = self.x11_state.as_mut() {
+            if let Err(err) = x11.selection.set_wayland_selection(selection, mime_types) {
+                warn!(self.log, "Failed to offer selection to X11"; "err" => %err);
+            }
+        }
+    }
+
+    /// Read a selection owned by an X11 client
+    pub fn xwayland_send_selection(&mut self, selection: SelectionType, mime_type: String, fd: OwnedFd) {
+        if let Some(x11) = self.x11_state.as_mut() {
+            if let Err(err) = x11
+                .selection
+                .request_x11_selection(selection, mime_type, fd, &self.handle)
+            {
+                warn!(self.log, "Failed to request selection from X11"; "err" => %err);
+            }
+        }
+    }
+
+    /// A wayland client started a drag'n'drop operation, which may be dropped onto X11 windows
+    pub fn xwayland_dnd_started(&mut self, source: Option<WlDataSource>) {
+        // drags without a source stay within their client
+        let (x11, source) = match (self.x11_state.as_mut(), source) {
+            (Some(x11), Some(source)) => (x11, source),
+            _ => return,
+        };
+        if let Err(err) = x11.selection.start_wayland_dnd(source) {
+            warn!(self.log, "Failed to forward drag'n'drop to X11"; "err" => %err);
+        }
+    }
+
+    /// The wayland drag'n'drop operation was not accepted by a wayland client, try the X11 window below
+    pub fn xwayland_dnd_drop(&mut self) -> bool {
+        let time = self.start_time.elapsed().as_millis() as u32;
+        let x11 = match self.x11_state.as_mut() {
+            Some(x11) => x11,
+            None => return false,
+        };
+
+        x11.selection.wayland_dnd_drop(time).unwrap_or_else(|err| {
+            warn!(self.log, "Failed to drop onto X11 window"; "err" => %err);
+            false
+        })
+    }
+
+    /// The wayland drag'n'drop operation ended
+    pub fn xwayland_dnd_dropped(&mut self) {
+        if let Some(x11) = self.x11_state.as_mut() {
+            // a successful drop onto an X11 window finishes through `SelectionEvent::DndFinished`
+            if let Err(err) = x11.selection.cancel_wayland_dnd() {
+                warn!(self.log, "Failed to cancel drag'n'drop"; "err" => %err);
+            }
+        }
+    }
+
+    /// A wayland client finished the drop of an X11 drag'n'drop operation
+    pub fn xwayland_dnd_finished(&mut self) {
+        if let Some(x11) = self.x11_state.as_mut() {
+            if let Err(err) = x11.selection.x11_dnd_finished() {
+                warn!(self.log, "Failed to finish drag'n'drop of X11 client"; "err" => %err);
+            }
+        }
+    }
+
+    /// Forward drag'n'drop operations across the pointer moving between X11 and wayland windows
+    pub fn xwayland_pointer_motion(&mut self, time: u32) {
+        let location = self.pointer_location;
+        let x11_window = self
+            .space
+            .element_under(location)
+            .and_then(|(window, _)| match window.toplevel() {
+                Kind::X11(surface) => with_states(&surface.surface, |states| {
+                    states.data_map.get::<X11WindowId>().map(|id| id.0)
+                }),
+                _ => None,
+            });
+        let focus = self
+            .surface_under()
+            .filter(|_| x11_window.is_none())
+            .and_then(|(target, loc)| target.wl_surface().map(|surface| (surface.clone(), loc)));
+
+        let x11 = match self.x11_state.as_mut() {
+            Some(x11) => x11,
+            None => return,
+        };
+        // X11 root coordinates are scaled along with XWayland
+        let x11_location = location.upscale(x11.scale as f64).to_i32_round();
+        if let Err(err) = x11.selection.wayland_dnd_motion(x11_window, x11_location, time) {
+            warn!(self.log, "Failed to forward drag'n'drop motion to X11"; "err" => %err);
+        }
+        if let Err(err) =
+            x11.selection
+                .x11_dnd_motion(&self.display_handle, &self.seat, focus, location, time)
+        {
+            warn!(self.log, "Failed to forward drag'n'drop of X11 client"; "err" => %err);
+        }
     }
 }
 
+/// The X11 window of an XWayland surface
+struct X11WindowId(X11Window);
+
 x11rb::atom_manager! {
     Atoms: AtomsCookie {
         WM_S0,
@@ -66,6 +230,11 @@ pub struct X11State {
     client: Client,
     log: slog::Logger,
     unpaired_surfaces: HashMap<u32, (X11Window, Point<i32, Logical>)>,
+    /// Whether XWayland should run at the highest output scale
+    hidpi: bool,
+    /// Scale of the X11 coordinate space relative to logical coordinates
+    scale: u32,
+    selection: XWaylandSelection,
 }
 
 impl X11State {
@@ -112,11 +281,15 @@ impl X11State {
         conn.flush()?;
 
         let conn = Arc::new(conn);
+        let selection = XWaylandSelection::new(Arc::clone(&conn), 0, log.clone())?;
         let wm = Self {
             conn: Arc::clone(&conn),
             atoms,
             client,
             unpaired_surfaces: Default::default(),
+            hidpi: std::env::var("ANVIL_XWAYLAND_HIDPI").is_ok(),
+            scale: 1,
+            selection,
             log: log.clone(),
         };
 
@@ -171,7 +344,8 @@ impl X11State {
 
                     let location = {
                         match self.conn.get_geometry(msg.window)?.reply() {
-                            Ok(geo) => (geo.x as i32, geo.y as i32).into(),
+                            Ok(geo) => Point::<i32, Physical>::from((geo.x as i32, geo.y as i32))
+                                .to_logical(self.scale as i32),
                             Err(err) => {
                                 error!(
                                     self.log,
@@ -225,7 +399,20 @@ impl X11State {
             return;
         }
 
-        let x11surface = X11Surface { surface };
+        with_states(&surface, |states| {
+            states.data_map.insert_if_missing(|| X11WindowId(window))
+        });
+        let conn = Arc::clone(&self.conn);
+        let configure = move |size: Size<i32, Physical>| {
+            let aux = ConfigureWindowAux::default()
+                .width(size.w.max(1) as u32)
+                .height(size.h.max(1) as u32);
+            if conn.configure_window(window, &aux).is_ok() {
+                let _ = conn.flush();
+            }
+        };
+        let x11surface = X11Surface::new(surface, Some(Arc::new(configure)));
+        x11surface.set_scale(self.scale);
         space.map_element(Window::new(Kind::X11(x11surface)), location, true);
     }
 }
@@ -1,5 +0,0 @@
-pub mod move_grab;
-pub use move_grab::MoveSurfaceGrab;
-
-pub mod resize_grab;
-pub use resize_grab::ResizeSurfaceGrab;
@@ -1,7 +1,8 @@
-use crate::{grabs::resize_grab, Smallvil};
+use crate::Smallvil;
 use smithay::{
     backend::renderer::utils::on_commit_buffer_handler,
     delegate_compositor, delegate_shm,
+    desktop::grabs::commit_resize,
     reexports::wayland_server::protocol::{wl_buffer, wl_surface::WlSurface},
     wayland::{
         buffer::BufferHandler,
@@ -24,13 +25,18 @@ impl CompositorHandler for Smallvil {
             while let Some(parent) = get_parent(&root) {
                 root = parent;
             }
-            if let Some(window) = self.space.elements().find(|w| w.toplevel().wl_surface() == &root) {
+            if let Some(window) = self
+                .space
+                .elements()
+                .find(|w| w.toplevel().wl_surface() == &root)
+                .cloned()
+            {
                 window.on_commit();
+                commit_resize(&mut self.space, &window);
             }
         };
 
         xdg_shell::handle_commit(&self.space, surface);
-        resize_grab::handle_commit(&mut self.space, surface);
     }
 }
 
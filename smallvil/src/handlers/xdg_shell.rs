@@ -1,6 +1,9 @@
 use smithay::{
     delegate_xdg_shell,
-    desktop::{Kind, Space, Window},
+    desktop::{
+        grabs::{ack_resize, MoveSurfaceGrab, ResizeSurfaceGrab, SpaceGrabHandler},
+        Kind, Space, Window,
+    },
     input::{
         pointer::{Focus, GrabStartData as PointerGrabStartData},
         Seat,
@@ -12,20 +15,17 @@ use smithay::{
             Resource,
         },
     },
-    utils::{Rectangle, Serial},
+    utils::Serial,
     wayland::{
         compositor::with_states,
         shell::xdg::{
-            PopupSurface, PositionerState, ToplevelSurface, XdgShellHandler, XdgShellState,
+            Configure, PopupSurface, PositionerState, ToplevelSurface, XdgShellHandler, XdgShellState,
             XdgToplevelSurfaceData,
         },
     },
 };
 
-use crate::{
-    grabs::{MoveSurfaceGrab, ResizeSurfaceGrab},
-    Smallvil,
-};
+use crate::Smallvil;
 
 impl XdgShellHandler for Smallvil {
     fn xdg_shell_state(&mut self) -> &mut XdgShellState {
@@ -57,11 +57,7 @@ impl XdgShellHandler for Smallvil {
                 .clone();
             let initial_window_location = self.space.element_location(&window).unwrap();
 
-            let grab = MoveSurfaceGrab {
-                start_data,
-                window,
-                initial_window_location,
-            };
+            let grab = MoveSurfaceGrab::new(start_data, window, initial_window_location);
 
             pointer.set_grab(self, grab, serial, Focus::Clear);
         }
@@ -87,31 +83,35 @@ impl XdgShellHandler for Smallvil {
                 .find(|w| w.toplevel().wl_surface() == wl_surface)
                 .unwrap()
                 .clone();
-            let initial_window_location = self.space.element_location(&window).unwrap();
-            let initial_window_size = window.geometry().size;
+            let initial_window_geometry = self.space.element_geometry(&window).unwrap();
 
-            surface.with_pending_state(|state| {
-                state.states.set(xdg_toplevel::State::Resizing);
-            });
-
-            surface.send_configure();
-
-            let grab = ResizeSurfaceGrab::start(
-                start_data,
-                window,
-                edges.into(),
-                Rectangle::from_loc_and_size(initial_window_location, initial_window_size),
-            );
+            let grab = ResizeSurfaceGrab::new(start_data, window, edges.into(), initial_window_geometry);
 
             pointer.set_grab(self, grab, serial, Focus::Clear);
         }
     }
 
+    fn ack_configure(&mut self, surface: WlSurface, configure: Configure) {
+        if let Some(window) = self
+            .space
+            .elements()
+            .find(|w| w.toplevel().wl_surface() == &surface)
+        {
+            ack_resize(window, &configure);
+        }
+    }
+
     fn grab(&mut self, _surface: PopupSurface, _seat: wl_seat::WlSeat, _serial: Serial) {
         // TODO popup grabs
     }
 }
 
+impl SpaceGrabHandler<Window> for Smallvil {
+    fn space_for_element(&mut self, _window: &Window) -> Option<&mut Space<Window>> {
+        Some(&mut self.space)
+    }
+}
+
 // Xdg Shell
 delegate_xdg_shell!(Smallvil);
 
@@ -4,7 +4,6 @@ use slog::Drain;
 
 mod handlers;
 
-mod grabs;
 mod input;
 mod state;
 mod winit;
@@ -39,6 +39,7 @@ pub fn init_winit(
             subpixel: Subpixel::Unknown,
             make: "Smithay".into(),
             model: "Winit".into(),
+            hdr: None,
         },
         log.clone(),
     );
@@ -212,6 +212,11 @@ where
         self.slots = Default::default();
     }
 
+    /// Returns the format of the buffers of this swapchain
+    pub fn format(&self) -> Fourcc {
+        self.fourcc
+    }
+
     /// Remove all internally cached buffers to e.g. reset age values
     pub fn reset_buffers(&mut self) {
         for slot in &mut self.slots {
@@ -185,6 +185,37 @@ impl<A: AsRawFd + 'static> AtomicDrmDevice<A> {
 
         Ok(())
     }
+
+    pub(in crate::backend::drm) fn restore_gamma(&self) -> Result<(), Error> {
+        // we just need to set the color management blobs captured on creation again
+        let mut req = AtomicModeReq::new();
+        for (crtc, set) in &self.old_state.1 {
+            let (prop_handles, values) = set.as_props_and_values();
+            for name in ["DEGAMMA_LUT", "CTM", "GAMMA_LUT"] {
+                let color_prop = match self.prop_mapping.1.get(crtc).and_then(|props| props.get(name)) {
+                    Some(prop) => *prop,
+                    None => continue,
+                };
+                if let Some(val) = prop_handles.iter().zip(values.iter()).find_map(|(handle, val)| {
+                    if *handle == color_prop {
+                        Some(*val)
+                    } else {
+                        None
+                    }
+                }) {
+                    req.add_raw_property((*crtc).into(), color_prop, val);
+                }
+            }
+        }
+
+        self.fd
+            .atomic_commit(AtomicCommitFlags::empty(), req)
+            .map_err(|source| Error::Access {
+                errmsg: "Failed to restore color management properties",
+                dev: self.fd.dev_path(),
+                source,
+            })
+    }
 }
 
 impl<A: AsRawFd + 'static> Drop for AtomicDrmDevice<A> {
@@ -10,13 +10,14 @@ use drm::control::{connector, crtc, Device as ControlDevice};
 use super::{DevPath, FdWrapper};
 use crate::backend::drm::error::Error;
 
-use slog::{error, info, o};
+use slog::{error, info, o, warn};
 
 #[derive(Debug)]
 pub struct LegacyDrmDevice<A: AsRawFd + 'static> {
     pub(crate) fd: Arc<FdWrapper<A>>,
     pub(crate) active: Arc<AtomicBool>,
     old_state: HashMap<crtc::Handle, (crtc::Info, Vec<connector::Handle>)>,
+    old_gamma: HashMap<crtc::Handle, [Vec<u16>; 3]>,
     logger: ::slog::Logger,
 }
 
@@ -31,6 +32,7 @@ impl<A: AsRawFd + 'static> LegacyDrmDevice<A> {
             fd,
             active,
             old_state: HashMap::new(),
+            old_gamma: HashMap::new(),
             logger: logger.new(o!("smithay_module" => "backend_drm_legacy", "drm_module" => "device")),
         };
 
@@ -69,6 +71,21 @@ impl<A: AsRawFd + 'static> LegacyDrmDevice<A> {
             }
         }
 
+        // Also save the gamma ramps of every crtc, those are not restored
+        // by a modeset and we want to leave them as we found them.
+        for &crtc in res_handles.crtcs() {
+            match get_gamma(&*dev.fd, crtc) {
+                Ok(Some(gamma)) => {
+                    dev.old_gamma.insert(crtc, gamma);
+                }
+                Ok(None) => {}
+                Err(err) => warn!(
+                    dev.logger,
+                    "Failed to read gamma ramps of crtc {:?}: {}", crtc, err
+                ),
+            }
+        }
+
         // If the user does not explicitly requests us to skip this,
         // we clear out the complete connector<->crtc mapping on device creation.
         //
@@ -108,6 +125,19 @@ impl<A: AsRawFd + 'static> LegacyDrmDevice<A> {
 
         Ok(())
     }
+
+    pub(in crate::backend::drm) fn restore_gamma(&self) -> Result<(), Error> {
+        for (crtc, [red, green, blue]) in self.old_gamma.iter() {
+            self.fd
+                .set_gamma(*crtc, red, green, blue)
+                .map_err(|source| Error::Access {
+                    errmsg: "Failed to restore gamma ramps",
+                    dev: self.fd.dev_path(),
+                    source,
+                })?;
+        }
+        Ok(())
+    }
 }
 
 impl<A: AsRawFd + 'static> Drop for LegacyDrmDevice<A> {
@@ -130,6 +160,9 @@ impl<A: AsRawFd + 'static> Drop for LegacyDrmDevice<A> {
                     error!(self.logger, "Failed to reset crtc ({:?}). Error: {}", handle, err);
                 }
             }
+            if let Err(err) = self.restore_gamma() {
+                error!(self.logger, "Failed to restore gamma ramps. Error: {}", err);
+            }
         }
     }
 }
@@ -189,3 +222,30 @@ where
     }
     Ok(())
 }
+
+// Reads the current gamma ramps of a crtc, returns `None` if the crtc does not support gamma ramps.
+pub fn get_gamma<D: ControlDevice>(fd: &D, crtc: crtc::Handle) -> Result<Option<[Vec<u16>; 3]>, Error> {
+    let size = fd
+        .get_crtc(crtc)
+        .map_err(|source| Error::Access {
+            errmsg: "Error loading crtc info",
+            dev: fd.dev_path(),
+            source,
+        })?
+        .gamma_length() as usize;
+    if size == 0 {
+        return Ok(None);
+    }
+
+    let mut red = vec![0; size];
+    let mut green = vec![0; size];
+    let mut blue = vec![0; size];
+    fd.get_gamma(crtc, &mut red, &mut green, &mut blue)
+        .map_err(|source| Error::Access {
+            errmsg: "Failed to get gamma ramps",
+            dev: fd.dev_path(),
+            source,
+        })?;
+
+    Ok(Some([red, green, blue]))
+}
@@ -92,6 +92,18 @@ impl<A: AsRawFd + 'static> AsRawFd for DrmDeviceInternal<A> {
 impl<A: AsRawFd + 'static> BasicDevice for DrmDeviceInternal<A> {}
 impl<A: AsRawFd + 'static> ControlDevice for DrmDeviceInternal<A> {}
 
+impl<A: AsRawFd + 'static> DrmDeviceInternal<A> {
+    // Restores the gamma ramps (and degamma luts and color matrices on atomic devices)
+    // found on device creation for all crtcs
+    #[cfg(feature = "backend_session")]
+    pub(super) fn restore_gamma(&self) -> Result<(), Error> {
+        match self {
+            DrmDeviceInternal::Atomic(dev) => dev.restore_gamma(),
+            DrmDeviceInternal::Legacy(dev) => dev.restore_gamma(),
+        }
+    }
+}
+
 impl<A: AsRawFd + 'static> DrmDevice<A> {
     /// Create a new [`DrmDevice`] from an open drm node
     ///
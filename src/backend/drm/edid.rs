@@ -0,0 +1,516 @@
+//! Helpers to read and parse the EDID of connected monitors
+//!
+//! The EDID (Extended Display Identification Data) of a monitor is exposed by the drm subsystem
+//! via the `EDID` property of a [`connector`](drm::control::connector).
+//! Use [`read_edid`] to retrieve it and the parsing functions of this module
+//! to fill out the [`PhysicalProperties`](crate::output::PhysicalProperties) of an [`Output`](crate::output::Output).
+//!
+//! ```no_run
+//! # use smithay::backend::drm::{edid::EdidInfo, DrmDevice};
+//! # use smithay::output::{Output, PhysicalProperties, Subpixel};
+//! # use smithay::reexports::drm::control::connector;
+//! # fn scan(device: &DrmDevice<std::fs::File>, connector: connector::Handle) {
+//! let info = EdidInfo::for_connector(device, connector).ok().flatten();
+//! let properties = match info.as_ref() {
+//!     Some(info) => info.physical_properties(),
+//!     None => PhysicalProperties {
+//!         size: (0, 0).into(),
+//!         subpixel: Subpixel::Unknown,
+//!         make: "Unknown".into(),
+//!         model: "Unknown".into(),
+//!         hdr: None,
+//!     },
+//! };
+//! let output = Output::new("DP-1".into(), properties, None);
+//! if let Some(mode) = info.and_then(|info| info.preferred_mode) {
+//!     output.set_preferred(mode);
+//! }
+//! # }
+//! ```
+
+use drm::control::{connector, Device as ControlDevice};
+
+use super::{DevPath, DrmError};
+use crate::output::{HdrCapabilities, Mode, PhysicalProperties, Subpixel};
+
+const EDID_BLOCK_SIZE: usize = 128;
+const EDID_HEADER: [u8; 8] = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+
+const EDID_DESCRIPTOR_SERIAL: u8 = 0xFF;
+const EDID_DESCRIPTOR_RANGE_LIMITS: u8 = 0xFD;
+const EDID_DESCRIPTOR_PRODUCT_NAME: u8 = 0xFC;
+
+const CTA_EXTENSION_TAG: u8 = 0x02;
+const CTA_EXTENDED_TAG: u8 = 0x07;
+const CTA_COLORIMETRY_BLOCK: u8 = 0x05;
+const CTA_HDR_STATIC_METADATA_BLOCK: u8 = 0x06;
+
+const DISPLAYID_EXTENSION_TAG: u8 = 0x70;
+const DISPLAYID_TYPE_I_TIMING_BLOCK: u8 = 0x03;
+const DISPLAYID_TYPE_VII_TIMING_BLOCK: u8 = 0x22;
+const DISPLAYID_ADAPTIVE_SYNC_BLOCK: u8 = 0x2B;
+
+/// Information about a monitor parsed from its EDID
+///
+/// Besides the base EDID block, the CTA-861 and DisplayID extension blocks
+/// are taken into account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdidInfo {
+    /// Three letter PNP id of the manufacturer (e.g. `"DEL"`)
+    pub manufacturer: String,
+    /// Manufacturer specific product code
+    pub product_code: u16,
+    /// Model name as advertised by the monitor, if any
+    pub model: Option<String>,
+    /// Serial number of the monitor, if any
+    pub serial: Option<String>,
+    /// Physical size of the monitor in millimeters, if known
+    pub size: Option<(u32, u32)>,
+    /// Detailed timings advertised by the monitor
+    pub modes: Vec<Mode>,
+    /// The preferred (usually native) mode of the monitor
+    pub preferred_mode: Option<Mode>,
+    /// HDR capabilities, see [`hdr_capabilities`]
+    pub hdr: Option<HdrCapabilities>,
+    /// Refresh rate range in Hz supported with variable refresh rate, if any
+    pub vrr_range: Option<(u32, u32)>,
+}
+
+/// Stable identity of a monitor
+///
+/// Unlike the connector a monitor is plugged into, this stays the same across
+/// reboots and re-plugs, so it can be used to key saved output configurations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MonitorIdentity {
+    /// Three letter PNP id of the manufacturer
+    pub manufacturer: String,
+    /// Manufacturer specific product code
+    pub product_code: u16,
+    /// Serial number of the monitor, if any
+    pub serial: Option<String>,
+}
+
+impl EdidInfo {
+    /// Parses a raw EDID blob.
+    ///
+    /// Returns `None`, if the blob is not a valid EDID.
+    pub fn parse(edid: &[u8]) -> Option<EdidInfo> {
+        if edid.len() < EDID_BLOCK_SIZE || edid[0..8] != EDID_HEADER {
+            return None;
+        }
+        let base = &edid[0..EDID_BLOCK_SIZE];
+
+        let manufacturer = u16::from_be_bytes([base[8], base[9]]);
+        let manufacturer = [10, 5, 0]
+            .iter()
+            .map(|shift| (((manufacturer >> shift) & 0x1F) as u8 + b'A' - 1) as char)
+            .collect::<String>();
+        let product_code = u16::from_le_bytes([base[10], base[11]]);
+        let serial_number = u32::from_le_bytes([base[12], base[13], base[14], base[15]]);
+
+        let mut info = EdidInfo {
+            manufacturer,
+            product_code,
+            model: None,
+            serial: None,
+            size: None,
+            modes: Vec::new(),
+            preferred_mode: None,
+            hdr: hdr_capabilities(edid),
+            vrr_range: None,
+        };
+
+        let mut range_limits = None;
+        for descriptor in base[54..126].chunks_exact(18) {
+            if descriptor[0] != 0 || descriptor[1] != 0 {
+                if let Some((mode, size)) = detailed_timing(descriptor) {
+                    // the first detailed timing is the preferred one
+                    if info.preferred_mode.is_none() {
+                        info.preferred_mode = Some(mode);
+                        info.size = size;
+                    }
+                    info.add_mode(mode);
+                }
+                continue;
+            }
+            match descriptor[3] {
+                EDID_DESCRIPTOR_SERIAL => info.serial = descriptor_text(descriptor),
+                EDID_DESCRIPTOR_PRODUCT_NAME => info.model = descriptor_text(descriptor),
+                EDID_DESCRIPTOR_RANGE_LIMITS => {
+                    let min = descriptor[5] as u32 + if descriptor[4] & 0x01 != 0 { 255 } else { 0 };
+                    let max = descriptor[6] as u32 + if descriptor[4] & 0x02 != 0 { 255 } else { 0 };
+                    range_limits = Some((min, max));
+                }
+                _ => {}
+            }
+        }
+        if info.serial.is_none() && serial_number != 0 {
+            info.serial = Some(serial_number.to_string());
+        }
+        // monitors supporting continuous frequencies can be driven at any refresh rate
+        // inside their range limits
+        if base[24] & 0x01 != 0 {
+            info.vrr_range = range_limits.filter(|(min, max)| min < max);
+        }
+        // fall back to the less precise size in centimeters
+        if info.size.is_none() && base[21] != 0 && base[22] != 0 {
+            info.size = Some((base[21] as u32 * 10, base[22] as u32 * 10));
+        }
+
+        for block in edid.chunks_exact(EDID_BLOCK_SIZE).skip(1) {
+            match block[0] {
+                CTA_EXTENSION_TAG => {
+                    let start = (block[2] as usize).clamp(4, 127);
+                    for descriptor in block[start..127].chunks_exact(18) {
+                        if let Some((mode, _)) = detailed_timing(descriptor) {
+                            info.add_mode(mode);
+                        }
+                    }
+                }
+                DISPLAYID_EXTENSION_TAG => info.parse_displayid(&block[1..]),
+                _ => {}
+            }
+        }
+
+        Some(info)
+    }
+
+    /// Reads and parses the EDID of a connector.
+    ///
+    /// Returns `Ok(None)`, if the connector does not provide a valid EDID.
+    pub fn for_connector<D: ControlDevice>(
+        dev: &D,
+        connector: connector::Handle,
+    ) -> Result<Option<EdidInfo>, DrmError> {
+        Ok(read_edid(dev, connector)?.and_then(|edid| EdidInfo::parse(&edid)))
+    }
+
+    /// Returns the stable identity of this monitor
+    pub fn identity(&self) -> MonitorIdentity {
+        MonitorIdentity {
+            manufacturer: self.manufacturer.clone(),
+            product_code: self.product_code,
+            serial: self.serial.clone(),
+        }
+    }
+
+    /// Creates [`PhysicalProperties`] for an [`Output`](crate::output::Output) representing this monitor.
+    ///
+    /// The subpixel layout is not part of the EDID and thus always [`Subpixel::Unknown`].
+    pub fn physical_properties(&self) -> PhysicalProperties {
+        let (w, h) = self.size.unwrap_or((0, 0));
+        PhysicalProperties {
+            size: (w as i32, h as i32).into(),
+            subpixel: Subpixel::Unknown,
+            make: self.manufacturer.clone(),
+            model: self
+                .model
+                .clone()
+                .unwrap_or_else(|| format!("0x{:04X}", self.product_code)),
+            hdr: self.hdr,
+        }
+    }
+
+    fn add_mode(&mut self, mode: Mode) {
+        if !self.modes.contains(&mode) {
+            self.modes.push(mode);
+        }
+    }
+
+    // `section` starts with the DisplayID section header
+    fn parse_displayid(&mut self, section: &[u8]) {
+        let end = (section[1] as usize + 4).min(section.len());
+        let mut data = &section[4..end];
+        while data.len() >= 3 {
+            let (tag, revision, len) = (data[0], data[1], data[2] as usize);
+            if len + 3 > data.len() {
+                break;
+            }
+            let payload = &data[3..3 + len];
+            match tag {
+                DISPLAYID_TYPE_I_TIMING_BLOCK | DISPLAYID_TYPE_VII_TIMING_BLOCK => {
+                    // type I timings are in units of 10 kHz, type VII timings in units of 1 kHz
+                    let clock_unit = if tag == DISPLAYID_TYPE_I_TIMING_BLOCK {
+                        10_000
+                    } else {
+                        1_000
+                    };
+                    for descriptor in payload.chunks_exact(20) {
+                        let clock = (u32::from_le_bytes([descriptor[0], descriptor[1], descriptor[2], 0]) + 1)
+                            as u64
+                            * clock_unit;
+                        let h_active = u16::from_le_bytes([descriptor[4], descriptor[5]]) as u64 + 1;
+                        let h_blank = u16::from_le_bytes([descriptor[6], descriptor[7]]) as u64 + 1;
+                        let v_active = u16::from_le_bytes([descriptor[12], descriptor[13]]) as u64 + 1;
+                        let v_blank = u16::from_le_bytes([descriptor[14], descriptor[15]]) as u64 + 1;
+                        let mode = Mode {
+                            size: (h_active as i32, v_active as i32).into(),
+                            refresh: (clock * 1000 / ((h_active + h_blank) * (v_active + v_blank))) as i32,
+                        };
+                        if descriptor[3] & 0x80 != 0 && self.preferred_mode.is_none() {
+                            self.preferred_mode = Some(mode);
+                        }
+                        self.add_mode(mode);
+                    }
+                }
+                DISPLAYID_ADAPTIVE_SYNC_BLOCK => {
+                    // bits 4-6 of the revision contain the size of each descriptor
+                    let descriptor_len = (((revision >> 4) & 0x07) as usize).max(6);
+                    if let Some(descriptor) = payload.chunks_exact(descriptor_len).next() {
+                        let min = descriptor[2] as u32;
+                        let max = (u16::from_le_bytes([descriptor[3], descriptor[4]]) & 0x3FF) as u32 + 1;
+                        if min < max {
+                            self.vrr_range = Some((min, max));
+                        }
+                    }
+                }
+                _ => {}
+            }
+            data = &data[3 + len..];
+        }
+    }
+}
+
+// Parses an 18 byte detailed timing descriptor into a mode and the physical size in mm
+fn detailed_timing(descriptor: &[u8]) -> Option<(Mode, Option<(u32, u32)>)> {
+    let clock = u16::from_le_bytes([descriptor[0], descriptor[1]]) as u64 * 10_000;
+    if clock == 0 {
+        return None;
+    }
+    let h_active = descriptor[2] as u64 | ((descriptor[4] as u64 & 0xF0) << 4);
+    let h_blank = descriptor[3] as u64 | ((descriptor[4] as u64 & 0x0F) << 8);
+    let v_active = descriptor[5] as u64 | ((descriptor[7] as u64 & 0xF0) << 4);
+    let v_blank = descriptor[6] as u64 | ((descriptor[7] as u64 & 0x0F) << 8);
+    let total = (h_active + h_blank) * (v_active + v_blank);
+    if total == 0 {
+        return None;
+    }
+
+    let mode = Mode {
+        size: (h_active as i32, v_active as i32).into(),
+        refresh: (clock * 1000 / total) as i32,
+    };
+    let width = descriptor[12] as u32 | ((descriptor[14] as u32 & 0xF0) << 4);
+    let height = descriptor[13] as u32 | ((descriptor[14] as u32 & 0x0F) << 8);
+    let size = if width != 0 && height != 0 {
+        Some((width, height))
+    } else {
+        None
+    };
+    Some((mode, size))
+}
+
+// Text of a display descriptor, terminated by a line feed and padded with spaces
+fn descriptor_text(descriptor: &[u8]) -> Option<String> {
+    let text = &descriptor[5..18];
+    let text = text.split(|c| *c == b'\n').next().unwrap_or(text);
+    let text = String::from_utf8_lossy(text).trim().to_string();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+/// Reads the raw EDID blob of a connector.
+///
+/// Returns `Ok(None)`, if the connector does not provide an EDID (e.g. because no monitor is connected).
+pub fn read_edid<D: ControlDevice>(
+    dev: &D,
+    connector: connector::Handle,
+) -> Result<Option<Vec<u8>>, DrmError> {
+    let props = dev.get_properties(connector).map_err(|source| DrmError::Access {
+        errmsg: "Failed to get properties for connector",
+        dev: dev.dev_path(),
+        source,
+    })?;
+    let (handles, values) = props.as_props_and_values();
+    for (handle, value) in handles.iter().zip(values.iter()) {
+        let info = dev.get_property(*handle).map_err(|source| DrmError::Access {
+            errmsg: "Failed to get property of connector",
+            dev: dev.dev_path(),
+            source,
+        })?;
+        if info.name().to_str().map(|x| x == "EDID").unwrap_or(false) {
+            if *value == 0 {
+                return Ok(None);
+            }
+            let blob = dev.get_property_blob(*value).map_err(|source| DrmError::Access {
+                errmsg: "Failed to get EDID blob",
+                dev: dev.dev_path(),
+                source,
+            })?;
+            return Ok(Some(blob));
+        }
+    }
+    Ok(None)
+}
+
+/// Parses the HDR capabilities of a monitor from its EDID.
+///
+/// The capabilities are advertised in the HDR static metadata and colorimetry data blocks
+/// of the CTA-861 extension. Returns `None`, if the EDID is invalid or no HDR static metadata
+/// block is present.
+pub fn hdr_capabilities(edid: &[u8]) -> Option<HdrCapabilities> {
+    if edid.len() < EDID_BLOCK_SIZE || edid[0..8] != EDID_HEADER {
+        return None;
+    }
+
+    let mut capabilities = None;
+    let mut bt2020_rgb = false;
+    for block in edid.chunks_exact(EDID_BLOCK_SIZE).skip(1) {
+        if block[0] != CTA_EXTENSION_TAG {
+            continue;
+        }
+
+        for data_block in cta_data_blocks(block) {
+            if data_block.len() < 2 || data_block[0] >> 5 != CTA_EXTENDED_TAG {
+                continue;
+            }
+            let payload = &data_block[2..];
+            match data_block[1] {
+                CTA_COLORIMETRY_BLOCK if !payload.is_empty() => {
+                    bt2020_rgb = payload[0] & 0x80 != 0;
+                }
+                CTA_HDR_STATIC_METADATA_BLOCK if !payload.is_empty() => {
+                    let eotfs = payload[0];
+                    let max_luminance = payload.get(2).filter(|cv| **cv != 0).map(|cv| luminance(*cv));
+                    capabilities = Some(HdrCapabilities {
+                        traditional_sdr: eotfs & 0x01 != 0,
+                        traditional_hdr: eotfs & 0x02 != 0,
+                        pq: eotfs & 0x04 != 0,
+                        hlg: eotfs & 0x08 != 0,
+                        bt2020_rgb: false,
+                        max_luminance,
+                        max_frame_average_luminance: payload
+                            .get(3)
+                            .filter(|cv| **cv != 0)
+                            .map(|cv| luminance(*cv)),
+                        // the minimum luminance is encoded relative to the maximum luminance
+                        min_luminance: max_luminance
+                            .zip(payload.get(4))
+                            .map(|(max, cv)| max * (*cv as f32 / 255.0).powi(2) / 100.0),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    capabilities.map(|capabilities| HdrCapabilities {
+        bt2020_rgb,
+        ..capabilities
+    })
+}
+
+// CTA-861 luminance code values are encoded as 50 * 2^(cv / 32) cd/m²
+fn luminance(cv: u8) -> f32 {
+    50.0 * 2f32.powf(cv as f32 / 32.0)
+}
+
+// Iterates over the data block collection of a CTA-861 extension block,
+// each returned slice includes the header byte of the data block.
+fn cta_data_blocks(block: &[u8]) -> impl Iterator<Item = &[u8]> {
+    // byte 2 contains the offset of the detailed timing descriptors,
+    // the data blocks are located in between.
+    let end = (block[2] as usize).clamp(4, block.len());
+    let mut data = &block[4..end];
+    std::iter::from_fn(move || {
+        let header = *data.first()?;
+        let len = (header & 0x1F) as usize + 1;
+        if len > data.len() {
+            return None;
+        }
+        let (data_block, rest) = data.split_at(len);
+        data = rest;
+        Some(data_block)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edid_with_cta_blocks(data_blocks: &[&[u8]]) -> Vec<u8> {
+        let mut edid = vec![0; EDID_BLOCK_SIZE * 2];
+        edid[0..8].copy_from_slice(&EDID_HEADER);
+        edid[126] = 1;
+
+        let cta = &mut edid[EDID_BLOCK_SIZE..];
+        cta[0] = CTA_EXTENSION_TAG;
+        cta[1] = 3;
+        let mut offset = 4;
+        for data_block in data_blocks {
+            cta[offset..offset + data_block.len()].copy_from_slice(data_block);
+            offset += data_block.len();
+        }
+        cta[2] = offset as u8;
+        edid
+    }
+
+    #[test]
+    fn parse_base_block() {
+        let mut edid = vec![0; EDID_BLOCK_SIZE];
+        edid[0..8].copy_from_slice(&EDID_HEADER);
+        // "DEL", product 0xA0C1, serial 12345
+        edid[8..10].copy_from_slice(&[0x10, 0xAC]);
+        edid[10..12].copy_from_slice(&0xA0C1u16.to_le_bytes());
+        edid[12..16].copy_from_slice(&12345u32.to_le_bytes());
+        edid[21] = 60;
+        edid[22] = 34;
+        // 1920x1080@60 with 597x336 mm
+        edid[54..72].copy_from_slice(&[
+            0x02, 0x3A, 0x80, 0x18, 0x71, 0x38, 0x2D, 0x40, 0x58, 0x2C, 0x45, 0x00, 0x55, 0x50, 0x21, 0x00,
+            0x00, 0x1E,
+        ]);
+        edid[72..90].copy_from_slice(b"\0\0\0\xFC\0Monitor 27\n  ");
+
+        let info = EdidInfo::parse(&edid).expect("Failed to parse edid");
+        assert_eq!(info.manufacturer, "DEL");
+        assert_eq!(info.product_code, 0xA0C1);
+        assert_eq!(info.model.as_deref(), Some("Monitor 27"));
+        assert_eq!(info.serial.as_deref(), Some("12345"));
+        assert_eq!(info.size, Some((597, 336)));
+        assert_eq!(info.vrr_range, None);
+
+        let mode = info.preferred_mode.expect("No preferred mode");
+        assert_eq!(mode.size, (1920, 1080).into());
+        assert_eq!(mode.refresh, 60000);
+        assert_eq!(info.modes, vec![mode]);
+
+        let properties = info.physical_properties();
+        assert_eq!(properties.make, "DEL");
+        assert_eq!(properties.model, "Monitor 27");
+    }
+
+    #[test]
+    fn no_hdr_without_extension() {
+        let mut edid = vec![0; EDID_BLOCK_SIZE];
+        edid[0..8].copy_from_slice(&EDID_HEADER);
+        assert_eq!(hdr_capabilities(&edid), None);
+        assert_eq!(hdr_capabilities(&[0; 16]), None);
+    }
+
+    #[test]
+    fn parse_hdr_static_metadata() {
+        let edid = edid_with_cta_blocks(&[
+            // colorimetry: BT2020_RGB
+            &[0xE3, CTA_COLORIMETRY_BLOCK, 0x80, 0x00],
+            // hdr static metadata: SDR + PQ, max 115 (~604 cd/m²), avg 90, min 10
+            &[0xE6, CTA_HDR_STATIC_METADATA_BLOCK, 0x05, 0x01, 115, 90, 10],
+        ]);
+
+        let capabilities = hdr_capabilities(&edid).expect("Failed to parse hdr capabilities");
+        assert!(capabilities.traditional_sdr);
+        assert!(!capabilities.traditional_hdr);
+        assert!(capabilities.pq);
+        assert!(!capabilities.hlg);
+        assert!(capabilities.bt2020_rgb);
+
+        let max = capabilities.max_luminance.unwrap();
+        assert!((600.0..610.0).contains(&max));
+        assert!(capabilities.max_frame_average_luminance.is_some());
+        let min = capabilities.min_luminance.unwrap();
+        assert!((min - max * (10.0f32 / 255.0).powi(2) / 100.0).abs() < f32::EPSILON);
+    }
+}
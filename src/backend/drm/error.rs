@@ -56,9 +56,29 @@ pub enum Error {
         /// Property name
         name: &'static str,
     },
+    /// The DrmDevice does not support the requested value of a property
+    #[error(
+        "The DrmDevice does not support the value '{value}' of property '{name}' for handle ({handle:?})"
+    )]
+    UnsupportedPropertyValue {
+        /// Property handle
+        handle: RawResourceHandle,
+        /// Property name
+        name: &'static str,
+        /// Requested value
+        value: &'static str,
+    },
     /// Atomic Test failed for new properties
     #[error("Atomic Test failed for new properties on crtc ({0:?})")]
     TestFailed(crtc::Handle),
+    /// The provided gamma ramps do not match the gamma size of the crtc
+    #[error("Gamma ramps of size {provided} do not match the gamma size ({expected}) of the crtc")]
+    GammaSizeMismatch {
+        /// Gamma size of the crtc
+        expected: usize,
+        /// Size of the provided ramp
+        provided: usize,
+    },
 }
 
 impl From<Error> for SwapBuffersError {
@@ -0,0 +1,190 @@
+use std::mem;
+
+/// Electro-optical transfer function signaled to a monitor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HdrEotf {
+    /// Traditional gamma, SDR luminance range
+    TraditionalSdr = 0,
+    /// Traditional gamma, HDR luminance range
+    TraditionalHdr = 1,
+    /// SMPTE ST 2084 (PQ), as used by HDR10
+    Pq = 2,
+    /// Hybrid log-gamma (HLG)
+    Hlg = 3,
+}
+
+/// Static HDR metadata (CTA-861-G static metadata type 1) send to a monitor
+/// via the `HDR_OUTPUT_METADATA` connector property.
+///
+/// Luminance values of `0` indicate, that the value is unknown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HdrOutputMetadata {
+    /// Transfer function of the content
+    pub eotf: HdrEotf,
+    /// Chromaticity coordinates (x, y) of the red, green and blue primaries
+    /// of the mastering display in units of 0.00002
+    pub display_primaries: [(u16, u16); 3],
+    /// Chromaticity coordinates (x, y) of the white point of the mastering display in units of 0.00002
+    pub white_point: (u16, u16),
+    /// Maximum luminance of the mastering display in cd/m²
+    pub max_display_mastering_luminance: u16,
+    /// Minimum luminance of the mastering display in units of 0.0001 cd/m²
+    pub min_display_mastering_luminance: u16,
+    /// Maximum content light level in cd/m²
+    pub max_content_light_level: u16,
+    /// Maximum frame-average light level in cd/m²
+    pub max_frame_average_light_level: u16,
+}
+
+impl HdrOutputMetadata {
+    /// Metadata for content using the given transfer function, BT.2020 primaries
+    /// and the D65 white point with unknown luminance levels.
+    pub fn bt2020(eotf: HdrEotf) -> HdrOutputMetadata {
+        HdrOutputMetadata {
+            eotf,
+            display_primaries: [(35400, 14600), (8500, 39850), (6550, 2300)],
+            white_point: (15635, 16450),
+            max_display_mastering_luminance: 0,
+            min_display_mastering_luminance: 0,
+            max_content_light_level: 0,
+            max_frame_average_light_level: 0,
+        }
+    }
+
+    pub(super) fn to_raw(self) -> RawHdrOutputMetadata {
+        let mut display_primaries = [RawChromaticity::default(); 3];
+        for (raw, (x, y)) in display_primaries.iter_mut().zip(self.display_primaries) {
+            *raw = RawChromaticity { x, y };
+        }
+
+        RawHdrOutputMetadata {
+            metadata_type: HDMI_STATIC_METADATA_TYPE1,
+            hdmi_metadata_type1: RawHdrMetadataInfoframe {
+                eotf: self.eotf as u8,
+                metadata_type: HDMI_STATIC_METADATA_TYPE1 as u8,
+                display_primaries,
+                white_point: RawChromaticity {
+                    x: self.white_point.0,
+                    y: self.white_point.1,
+                },
+                max_display_mastering_luminance: self.max_display_mastering_luminance,
+                min_display_mastering_luminance: self.min_display_mastering_luminance,
+                max_cll: self.max_content_light_level,
+                max_fall: self.max_frame_average_light_level,
+            },
+        }
+    }
+
+    pub(super) fn from_blob(data: &[u8]) -> Option<HdrOutputMetadata> {
+        if data.len() < mem::size_of::<RawHdrOutputMetadata>() {
+            return None;
+        }
+        // `RawHdrOutputMetadata` only consists of integers, so every bit pattern is valid.
+        let raw = unsafe { std::ptr::read_unaligned(data.as_ptr() as *const RawHdrOutputMetadata) };
+        if raw.metadata_type != HDMI_STATIC_METADATA_TYPE1 {
+            return None;
+        }
+
+        let info = raw.hdmi_metadata_type1;
+        let eotf = match info.eotf {
+            0 => HdrEotf::TraditionalSdr,
+            1 => HdrEotf::TraditionalHdr,
+            2 => HdrEotf::Pq,
+            3 => HdrEotf::Hlg,
+            _ => return None,
+        };
+        let primaries = info.display_primaries;
+        Some(HdrOutputMetadata {
+            eotf,
+            display_primaries: [
+                (primaries[0].x, primaries[0].y),
+                (primaries[1].x, primaries[1].y),
+                (primaries[2].x, primaries[2].y),
+            ],
+            white_point: (info.white_point.x, info.white_point.y),
+            max_display_mastering_luminance: info.max_display_mastering_luminance,
+            min_display_mastering_luminance: info.min_display_mastering_luminance,
+            max_content_light_level: info.max_cll,
+            max_frame_average_light_level: info.max_fall,
+        })
+    }
+}
+
+const HDMI_STATIC_METADATA_TYPE1: u32 = 0;
+
+// These mirror `struct hdr_output_metadata` of the kernel uapi (drm_mode.h)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct RawChromaticity {
+    x: u16,
+    y: u16,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct RawHdrMetadataInfoframe {
+    eotf: u8,
+    metadata_type: u8,
+    display_primaries: [RawChromaticity; 3],
+    white_point: RawChromaticity,
+    max_display_mastering_luminance: u16,
+    min_display_mastering_luminance: u16,
+    max_cll: u16,
+    max_fall: u16,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub(super) struct RawHdrOutputMetadata {
+    metadata_type: u32,
+    hdmi_metadata_type1: RawHdrMetadataInfoframe,
+}
+
+/// Colorimetry signaled to a monitor via the `Colorspace` connector property
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Colorspace {
+    /// Driver default, usually sRGB (BT.709) for RGB output
+    Default,
+    /// BT.709 YCbCr
+    Bt709Ycc,
+    /// opRGB (formerly AdobeRGB)
+    OpRgb,
+    /// BT.2020 RGB, as used by HDR10
+    Bt2020Rgb,
+    /// BT.2020 YCbCr
+    Bt2020Ycc,
+    /// DCI-P3 RGB with D65 white point
+    DciP3RgbD65,
+}
+
+impl Colorspace {
+    // name of the enum value as exposed by the kernel
+    pub(super) fn name(&self) -> &'static str {
+        match self {
+            Colorspace::Default => "Default",
+            Colorspace::Bt709Ycc => "BT709_YCC",
+            Colorspace::OpRgb => "opRGB",
+            Colorspace::Bt2020Rgb => "BT2020_RGB",
+            Colorspace::Bt2020Ycc => "BT2020_YCC",
+            Colorspace::DciP3RgbD65 => "DCI-P3_RGB_D65",
+        }
+    }
+
+    pub(super) fn from_name(name: &str) -> Option<Colorspace> {
+        Some(match name {
+            "Default" => Colorspace::Default,
+            "BT709_YCC" => Colorspace::Bt709Ycc,
+            "opRGB" => Colorspace::OpRgb,
+            "BT2020_RGB" => Colorspace::Bt2020Rgb,
+            "BT2020_YCC" => Colorspace::Bt2020Ycc,
+            "DCI-P3_RGB_D65" => Colorspace::DciP3RgbD65,
+            _ => return None,
+        })
+    }
+}
+
+impl Default for Colorspace {
+    fn default() -> Self {
+        Colorspace::Default
+    }
+}
@@ -70,7 +70,9 @@
 //! [`DrmDevice`] instead.
 
 pub(crate) mod device;
+pub mod edid;
 pub(self) mod error;
+pub(self) mod hdr;
 pub mod node;
 
 #[cfg(feature = "backend_session")]
@@ -79,9 +81,13 @@ pub(self) mod surface;
 
 pub use device::{DevPath, DrmDevice, DrmEvent, EventMetadata as DrmEventMetadata, Time as DrmEventTime};
 pub use error::Error as DrmError;
+pub use hdr::{Colorspace, HdrEotf, HdrOutputMetadata};
 pub use node::{CreateDrmNodeError, DrmNode, NodeType};
 #[cfg(feature = "backend_gbm")]
-pub use surface::gbm::{Error as GbmBufferedSurfaceError, GbmBufferedSurface};
+pub use surface::gbm::{
+    Error as GbmBufferedSurfaceError, GbmBufferedSurface, HDR_10BIT_COLOR_FORMATS, HDR_FP16_COLOR_FORMATS,
+    SDR_COLOR_FORMATS,
+};
 pub use surface::DrmSurface;
 
 use drm::control::{crtc, plane, Device as ControlDevice, PlaneType};
@@ -68,6 +68,19 @@ impl<A: AsRawFd + 'static> DrmDeviceObserver<A> {
             }
         }
 
+        // the device was already paused by a previous signal
+        if !self.active.load(Ordering::SeqCst) {
+            return;
+        }
+
+        if let Some(device) = self.dev.upgrade() {
+            // Leave the gamma ramps as we found them for whoever takes over the device.
+            // Surfaces will set their ramps again on activation.
+            if let Err(err) = device.restore_gamma() {
+                warn!(self.logger, "Failed to restore gamma ramps. Error: {}", err);
+            }
+        }
+
         self.active.store(false, Ordering::SeqCst);
         if self.privileged {
             if let Some(device) = self.dev.upgrade() {
@@ -90,6 +103,10 @@ impl<A: AsRawFd + 'static> DrmDeviceObserver<A> {
                 }
             }
         }
+        // the device was already activated by a previous signal
+        if self.active.load(Ordering::SeqCst) {
+            return;
+        }
         if self.privileged {
             if let Some(device) = self.dev.upgrade() {
                 if let Err(err) = device.acquire_master_lock() {
@@ -106,6 +123,7 @@ struct DrmSurfaceObserver<A: AsRawFd + 'static> {
     dev_id: dev_t,
     crtc: crtc::Handle,
     surf: Weak<DrmSurfaceInternal<A>>,
+    paused: bool,
     logger: ::slog::Logger,
 }
 
@@ -120,6 +138,12 @@ impl ControlDevice for FdHack {}
 
 impl<A: AsRawFd + 'static> Linkable<SessionSignal> for DrmSurface<A> {
     fn link(&mut self, signaler: Signaler<SessionSignal>) {
+        self.link_session(signaler)
+    }
+}
+
+impl<A: AsRawFd + 'static> DrmSurface<A> {
+    pub(crate) fn link_session(&self, signaler: Signaler<SessionSignal>) {
         let logger = match &*self.internal {
             DrmSurfaceInternal::Atomic(surf) => surf.logger.clone(),
             DrmSurfaceInternal::Legacy(surf) => surf.logger.clone(),
@@ -128,6 +152,7 @@ impl<A: AsRawFd + 'static> Linkable<SessionSignal> for DrmSurface<A> {
             dev_id: self.dev_id,
             crtc: self.crtc(),
             surf: Arc::downgrade(&self.internal),
+            paused: false,
             logger: logger.new(o!("drm_module" => "observer")),
         };
 
@@ -139,11 +164,16 @@ impl<A: AsRawFd + 'static> Linkable<SessionSignal> for DrmSurface<A> {
 impl<A: AsRawFd + 'static> DrmSurfaceObserver<A> {
     fn signal(&mut self, signal: SessionSignal) {
         match signal {
+            SessionSignal::PauseSession => self.paused = true,
+            SessionSignal::PauseDevice { major, minor } => {
+                if major as u64 == stat::major(self.dev_id) && minor as u64 == stat::minor(self.dev_id) {
+                    self.paused = true;
+                }
+            }
             SessionSignal::ActivateSession => self.activate(None),
             SessionSignal::ActivateDevice { major, minor, new_fd } => {
                 self.activate(Some((major, minor, new_fd)))
             }
-            _ => {}
         }
     }
 
@@ -159,6 +189,11 @@ impl<A: AsRawFd + 'static> DrmSurfaceObserver<A> {
             } else {
                 None
             };
+            // the state was already reset by a previous activation
+            if !self.paused && fd.is_none() {
+                return;
+            }
+            self.paused = false;
 
             if let Err(err) = match &*surf {
                 DrmSurfaceInternal::Atomic(surf) => surf.reset_state(fd.as_ref()),
@@ -17,7 +17,7 @@ use crate::backend::{
         device::atomic::{map_props, Mapping},
         device::{DevPath, DrmDeviceInternal},
         error::Error,
-        plane_type,
+        plane_type, Colorspace, HdrOutputMetadata,
     },
 };
 
@@ -28,6 +28,8 @@ pub struct State {
     pub mode: Mode,
     pub blob: property::Value<'static>,
     pub connectors: HashSet<connector::Handle>,
+    pub hdr_metadata: Option<HdrOutputMetadata>,
+    pub colorspace: Colorspace,
 }
 
 impl State {
@@ -96,14 +98,61 @@ impl State {
                 }
             }
         }
+
+        // the color properties are the same for all connectors of a surface
+        let (hdr_metadata, colorspace) = current_connectors
+            .iter()
+            .next()
+            .map(|conn| color_state(fd, *conn, prop_mapping))
+            .unwrap_or_default();
+
         Ok(State {
             mode: current_mode,
             blob: current_blob,
             connectors: current_connectors,
+            hdr_metadata,
+            colorspace,
         })
     }
 }
 
+// Color management properties of our crtc, that are set via property blobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColorProperty {
+    DegammaLut,
+    Ctm,
+    GammaLut,
+}
+
+impl ColorProperty {
+    const ALL: [ColorProperty; 3] = [
+        ColorProperty::DegammaLut,
+        ColorProperty::Ctm,
+        ColorProperty::GammaLut,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            ColorProperty::DegammaLut => "DEGAMMA_LUT",
+            ColorProperty::Ctm => "CTM",
+            ColorProperty::GammaLut => "GAMMA_LUT",
+        }
+    }
+}
+
+// Blob ids of one of the color management properties of our crtc.
+//
+// A value of `0` means no blob is set (linear lut or identity matrix).
+#[derive(Debug, Default)]
+struct ColorBlob {
+    // the blob that was set when the surface was created
+    original: u64,
+    // the blob that is currently set
+    current: u64,
+    // the blob to be set with the next commit or page_flip
+    pending: Option<u64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct PlaneInfo {
     handle: plane::Handle,
@@ -123,6 +172,8 @@ pub struct AtomicDrmSurface<A: AsRawFd + 'static> {
     prop_mapping: RwLock<Mapping>,
     state: RwLock<State>,
     pending: RwLock<State>,
+    // indexed by `ColorProperty`
+    color: Mutex<[ColorBlob; 3]>,
     pub(crate) logger: ::slog::Logger,
 }
 
@@ -158,6 +209,8 @@ impl<A: AsRawFd + 'static> AtomicDrmSurface<A> {
             mode,
             blob,
             connectors: connectors.iter().copied().collect(),
+            hdr_metadata: state.hdr_metadata,
+            colorspace: state.colorspace,
         };
 
         let surface = AtomicDrmSurface {
@@ -169,9 +222,19 @@ impl<A: AsRawFd + 'static> AtomicDrmSurface<A> {
             prop_mapping: RwLock::new(prop_mapping),
             state: RwLock::new(state),
             pending: RwLock::new(pending),
+            color: Mutex::new(Default::default()),
             logger,
         };
 
+        // remember the current luts and matrix to be able to restore them later
+        for prop in ColorProperty::ALL {
+            if let Ok(original) = surface.crtc_prop_value(prop.name()) {
+                let mut color = surface.color.lock().unwrap();
+                color[prop as usize].original = original;
+                color[prop as usize].current = original;
+            }
+        }
+
         Ok(surface)
     }
 
@@ -492,6 +555,323 @@ impl<A: AsRawFd + 'static> AtomicDrmSurface<A> {
         *self.pending.read().unwrap() != *self.state.read().unwrap()
     }
 
+    fn crtc_prop_value(&self, name: &'static str) -> Result<u64, Error> {
+        let prop = crtc_prop_handle(&*self.prop_mapping.read().unwrap(), self.crtc, name)?;
+        let props = self
+            .fd
+            .get_properties(self.crtc)
+            .map_err(|source| Error::Access {
+                errmsg: "Failed to query crtc properties",
+                dev: self.fd.dev_path(),
+                source,
+            })?;
+        let (ids, vals) = props.as_props_and_values();
+        ids.iter()
+            .zip(vals.iter())
+            .find(|(id, _)| **id == prop)
+            .map(|(_, val)| *val)
+            .ok_or(Error::UnknownProperty {
+                handle: self.crtc.into(),
+                name,
+            })
+    }
+
+    pub fn gamma_size(&self) -> Result<usize, Error> {
+        self.lut_size("GAMMA_LUT_SIZE")
+    }
+
+    pub fn degamma_size(&self) -> Result<usize, Error> {
+        self.lut_size("DEGAMMA_LUT_SIZE")
+    }
+
+    fn lut_size(&self, name: &'static str) -> Result<usize, Error> {
+        match self.crtc_prop_value(name) {
+            Ok(size) => Ok(size as usize),
+            // no color management support
+            Err(Error::UnknownProperty { .. }) => Ok(0),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn set_gamma(&self, red: &[u16], green: &[u16], blue: &[u16]) -> Result<(), Error> {
+        let size = self.gamma_size()?;
+        self.set_lut(ColorProperty::GammaLut, size, red, green, blue)
+    }
+
+    pub fn reset_gamma(&self) -> Result<(), Error> {
+        self.reset_color_property(ColorProperty::GammaLut)
+    }
+
+    pub fn set_degamma(&self, red: &[u16], green: &[u16], blue: &[u16]) -> Result<(), Error> {
+        let size = self.degamma_size()?;
+        if size == 0 {
+            return Err(Error::UnknownProperty {
+                handle: self.crtc.into(),
+                name: "DEGAMMA_LUT",
+            });
+        }
+        self.set_lut(ColorProperty::DegammaLut, size, red, green, blue)
+    }
+
+    pub fn reset_degamma(&self) -> Result<(), Error> {
+        self.reset_color_property(ColorProperty::DegammaLut)
+    }
+
+    pub fn set_ctm(&self, matrix: [f64; 9]) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+        // fails with `UnknownProperty`, if the crtc has no color transformation matrix
+        self.crtc_prop_value(ColorProperty::Ctm.name())?;
+
+        // `drm_color_ctm` is just the nine fixed point values of the matrix
+        let mut data = matrix
+            .iter()
+            .flat_map(|value| ctm_value(*value).to_ne_bytes())
+            .collect::<Vec<u8>>();
+        self.queue_blob(ColorProperty::Ctm, &mut data)
+    }
+
+    pub fn reset_ctm(&self) -> Result<(), Error> {
+        self.reset_color_property(ColorProperty::Ctm)
+    }
+
+    fn set_lut(
+        &self,
+        prop: ColorProperty,
+        size: usize,
+        red: &[u16],
+        green: &[u16],
+        blue: &[u16],
+    ) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+
+        if let Some(provided) = [red.len(), green.len(), blue.len()]
+            .into_iter()
+            .find(|len| *len != size)
+        {
+            return Err(Error::GammaSizeMismatch {
+                expected: size,
+                provided,
+            });
+        }
+
+        let mut lut = red
+            .iter()
+            .zip(green.iter())
+            .zip(blue.iter())
+            .map(|((red, green), blue)| drm_ffi::drm_color_lut {
+                red: *red,
+                green: *green,
+                blue: *blue,
+                reserved: 0,
+            })
+            .collect::<Vec<_>>();
+        // `drm_color_lut` is a plain `repr(C)` struct of four u16, so viewing it as bytes is fine.
+        let data = unsafe {
+            std::slice::from_raw_parts_mut(
+                lut.as_mut_ptr() as *mut u8,
+                lut.len() * std::mem::size_of::<drm_ffi::drm_color_lut>(),
+            )
+        };
+        self.queue_blob(prop, data)
+    }
+
+    fn queue_blob(&self, prop: ColorProperty, data: &mut [u8]) -> Result<(), Error> {
+        let blob = drm_ffi::mode::create_property_blob(self.fd.as_raw_fd(), data).map_err(|source| {
+            Error::Access {
+                errmsg: "Failed to create property blob for color management",
+                dev: self.fd.dev_path(),
+                source,
+            }
+        })?;
+
+        trace!(
+            self.logger,
+            "Queueing new {} (blob {})",
+            prop.name(),
+            blob.blob_id
+        );
+        self.queue_color(prop, blob.blob_id as u64);
+        Ok(())
+    }
+
+    fn reset_color_property(&self, prop: ColorProperty) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+
+        let original = self.color.lock().unwrap()[prop as usize].original;
+        self.queue_color(prop, original);
+        Ok(())
+    }
+
+    fn queue_color(&self, prop: ColorProperty, blob: u64) {
+        let mut color = self.color.lock().unwrap();
+        let color = &mut color[prop as usize];
+        if let Some(old) = color.pending.replace(blob) {
+            // a blob, that was never committed, may be freed directly
+            if old != color.original && old != color.current && old != blob {
+                let _ = self.fd.destroy_property_blob(old);
+            }
+        }
+    }
+
+    // the pending color management blobs were successfully committed
+    fn color_applied(&self) {
+        let mut color = self.color.lock().unwrap();
+        for color in color.iter_mut() {
+            if let Some(pending) = color.pending.take() {
+                let old = std::mem::replace(&mut color.current, pending);
+                if old != color.original && old != pending {
+                    if let Err(err) = self.fd.destroy_property_blob(old) {
+                        warn!(
+                            self.logger,
+                            "Failed to destroy old color management blob: {}", err
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn hdr_output_metadata(&self) -> Option<HdrOutputMetadata> {
+        self.pending.read().unwrap().hdr_metadata
+    }
+
+    pub fn set_hdr_output_metadata(&self, metadata: Option<HdrOutputMetadata>) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+
+        let mut pending = self.pending.write().unwrap();
+        if metadata.is_some() {
+            let prop_mapping = self.prop_mapping.read().unwrap();
+            for conn in pending.connectors.iter() {
+                conn_prop_handle(&*prop_mapping, *conn, "HDR_OUTPUT_METADATA")?;
+            }
+        }
+
+        pending.hdr_metadata = metadata;
+        Ok(())
+    }
+
+    pub fn colorspace(&self) -> Colorspace {
+        self.pending.read().unwrap().colorspace
+    }
+
+    pub fn set_colorspace(&self, colorspace: Colorspace) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+
+        let mut pending = self.pending.write().unwrap();
+        if colorspace != Colorspace::Default {
+            let prop_mapping = self.prop_mapping.read().unwrap();
+            for conn in pending.connectors.iter() {
+                self.colorspace_value(&*prop_mapping, *conn, colorspace)?;
+            }
+        }
+
+        pending.colorspace = colorspace;
+        Ok(())
+    }
+
+    // Looks up the property handle and raw value of a colorspace for a given connector
+    fn colorspace_value(
+        &self,
+        prop_mapping: &Mapping,
+        conn: connector::Handle,
+        colorspace: Colorspace,
+    ) -> Result<(property::Handle, u64), Error> {
+        let handle = conn_prop_handle(prop_mapping, conn, "Colorspace")?;
+        let info = self.fd.get_property(handle).map_err(|source| Error::Access {
+            errmsg: "Failed to get property of connector",
+            dev: self.fd.dev_path(),
+            source,
+        })?;
+        if let property::ValueType::Enum(values) = info.value_type() {
+            let (_, values) = values.values();
+            if let Some(value) = values.iter().find(|value| {
+                value
+                    .name()
+                    .to_str()
+                    .map(|x| x == colorspace.name())
+                    .unwrap_or(false)
+            }) {
+                return Ok((handle, value.value()));
+            }
+        }
+
+        Err(Error::UnsupportedPropertyValue {
+            handle: conn.into(),
+            name: "Colorspace",
+            value: colorspace.name(),
+        })
+    }
+
+    // Adds the hdr metadata and colorspace to the given connectors.
+    //
+    // Returns the blob created for the hdr metadata, which should be destroyed after the commit.
+    // (The kernel keeps its own reference, as long as the blob is in use.)
+    fn add_color_props<'a>(
+        &self,
+        req: &mut AtomicModeReq,
+        connectors: impl Iterator<Item = &'a connector::Handle>,
+        hdr_metadata: Option<HdrOutputMetadata>,
+        colorspace: Colorspace,
+    ) -> Result<Option<u64>, Error> {
+        let prop_mapping = self.prop_mapping.read().unwrap();
+
+        // look up everything first, so we do not leak the blob on errors
+        let mut props = Vec::new();
+        for conn in connectors {
+            let hdr_prop = match conn_prop_handle(&*prop_mapping, *conn, "HDR_OUTPUT_METADATA") {
+                Ok(prop) => Some(prop),
+                // nothing to reset, if the connector does not support hdr
+                Err(_) if hdr_metadata.is_none() => None,
+                Err(err) => return Err(err),
+            };
+            let colorspace_prop = match self.colorspace_value(&*prop_mapping, *conn, colorspace) {
+                Ok(prop) => Some(prop),
+                Err(_) if colorspace == Colorspace::Default => None,
+                Err(err) => return Err(err),
+            };
+            props.push((*conn, hdr_prop, colorspace_prop));
+        }
+
+        if props.is_empty() {
+            return Ok(None);
+        }
+
+        let blob = match hdr_metadata {
+            Some(metadata) => Some(
+                self.fd
+                    .create_property_blob(&metadata.to_raw())
+                    .map_err(|source| Error::Access {
+                        errmsg: "Failed to create property blob for hdr metadata",
+                        dev: self.fd.dev_path(),
+                        source,
+                    })?
+                    .into(),
+            ),
+            None => None,
+        };
+
+        for (conn, hdr_prop, colorspace_prop) in props {
+            if let Some(prop) = hdr_prop {
+                req.add_property(conn, prop, property::Value::Blob(blob.unwrap_or(0)));
+            }
+            if let Some((prop, value)) = colorspace_prop {
+                req.add_property(conn, prop, property::Value::UnsignedRange(value));
+            }
+        }
+
+        Ok(blob)
+    }
+
     pub fn commit<'a>(
         &self,
         framebuffers: impl Iterator<Item = &'a (framebuffer::Handle, plane::Handle)>,
@@ -535,11 +915,26 @@ impl<A: AsRawFd + 'static> AtomicDrmSurface<A> {
             info!(self.logger, "Setting new mode: {:?}", pending.mode.name());
         }
 
+        // the color properties need to be set on all connectors, if they changed,
+        // otherwise only new connectors need to be updated.
+        let color_conns = if current.hdr_metadata != pending.hdr_metadata
+            || current.colorspace != pending.colorspace
+        {
+            info!(
+                self.logger,
+                "Setting new hdr metadata {:?} and colorspace {:?}", pending.hdr_metadata, pending.colorspace
+            );
+            pending_conns.iter().copied().collect::<Vec<_>>()
+        } else {
+            added.clone().copied().collect::<Vec<_>>()
+        };
+
         trace!(self.logger, "Testing screen config");
 
         // test the new config and return the request if it would be accepted by the driver.
+        let hdr_blob;
         let req = {
-            let req = self.build_request(
+            let mut req = self.build_request(
                 &mut added,
                 &mut removed,
                 self.plane,
@@ -548,6 +943,12 @@ impl<A: AsRawFd + 'static> AtomicDrmSurface<A> {
                 Some(pending.mode),
                 Some(pending.blob),
             )?;
+            hdr_blob = self.add_color_props(
+                &mut req,
+                color_conns.iter(),
+                pending.hdr_metadata,
+                pending.colorspace,
+            )?;
 
             if let Err(err) = self
                 .fd
@@ -561,6 +962,9 @@ impl<A: AsRawFd + 'static> AtomicDrmSurface<A> {
                     self.logger,
                     "New screen configuration invalid!:\n\t{:#?}\n\t{}\n", req, err
                 );
+                if let Some(blob) = hdr_blob {
+                    let _ = self.fd.destroy_property_blob(blob);
+                }
 
                 return Err(err);
             } else {
@@ -601,8 +1005,15 @@ impl<A: AsRawFd + 'static> AtomicDrmSurface<A> {
                 source,
             });
 
+        if let Some(blob) = hdr_blob {
+            if let Err(err) = self.fd.destroy_property_blob(blob) {
+                warn!(self.logger, "Failed to destroy hdr metadata blob: {}", err);
+            }
+        }
+
         if result.is_ok() {
             *current = pending.clone();
+            self.color_applied();
         }
 
         result
@@ -646,6 +1057,7 @@ impl<A: AsRawFd + 'static> AtomicDrmSurface<A> {
                 dev: self.fd.dev_path(),
                 source,
             })?;
+        self.color_applied();
 
         Ok(())
     }
@@ -787,6 +1199,18 @@ impl<A: AsRawFd + 'static> AtomicDrmSurface<A> {
             property::Value::Boolean(true),
         );
 
+        // and update the luts and color matrix, if they were changed
+        let color = self.color.lock().unwrap();
+        for prop in ColorProperty::ALL {
+            if let Some(blob) = color[prop as usize].pending {
+                req.add_property(
+                    self.crtc,
+                    crtc_prop_handle(&*prop_mapping, self.crtc, prop.name())?,
+                    property::Value::Blob(blob),
+                );
+            }
+        }
+
         // and we need to set the framebuffers for our planes
         if let Some(fbs) = framebuffers {
             for (fb, plane) in fbs {
@@ -959,6 +1383,15 @@ impl<A: AsRawFd + 'static> AtomicDrmSurface<A> {
         } else {
             State::current_state(&*self.fd, self.crtc, &mut *self.prop_mapping.write().unwrap())?
         };
+
+        // the luts and color matrix might have been changed (or restored) in the meantime,
+        // so we need to set ours again with the next commit.
+        for color in self.color.lock().unwrap().iter_mut() {
+            if color.pending.is_none() && color.current != color.original {
+                color.pending = Some(color.current);
+            }
+        }
+
         Ok(())
     }
 }
@@ -1033,9 +1466,33 @@ impl<A: AsRawFd + 'static> Drop for AtomicDrmSurface<A> {
 
         req.add_property(self.crtc, *active_prop, property::Value::Boolean(false));
         req.add_property(self.crtc, *mode_prop, property::Value::Unknown(0));
+
+        // restore the luts and color matrix, if we changed them
+        let color = self.color.lock().unwrap();
+        for prop in ColorProperty::ALL {
+            let color = &color[prop as usize];
+            if let Some(color_prop) = prop_mapping
+                .1
+                .get(&self.crtc)
+                .and_then(|props| props.get(prop.name()))
+            {
+                if color.current != color.original || color.pending.is_some() {
+                    req.add_property(self.crtc, *color_prop, property::Value::Blob(color.original));
+                }
+            }
+        }
+
         if let Err(err) = self.fd.atomic_commit(AtomicCommitFlags::ALLOW_MODESET, req) {
             warn!(self.logger, "Unable to disable connectors: {}", err);
         }
+
+        for color in color.iter() {
+            for blob in color.pending.iter().chain(std::iter::once(&color.current)) {
+                if *blob != color.original {
+                    let _ = self.fd.destroy_property_blob(*blob);
+                }
+            }
+        }
     }
 }
 
@@ -1089,9 +1546,59 @@ pub(crate) fn plane_prop_handle(
         })
         .map(|x| *x)
 }
+
+// Reads the hdr metadata and colorspace currently set on a connector
+fn color_state<A: AsRawFd + ControlDevice>(
+    fd: &A,
+    conn: connector::Handle,
+    prop_mapping: &Mapping,
+) -> (Option<HdrOutputMetadata>, Colorspace) {
+    let props = match fd.get_properties(conn) {
+        Ok(props) => props,
+        Err(_) => return Default::default(),
+    };
+    let (ids, vals) = props.as_props_and_values();
+    let value = |name| {
+        let handle = conn_prop_handle(prop_mapping, conn, name).ok()?;
+        ids.iter()
+            .zip(vals.iter())
+            .find(|(id, _)| **id == handle)
+            .map(|(_, val)| (handle, *val))
+    };
+
+    let hdr_metadata = value("HDR_OUTPUT_METADATA")
+        .filter(|(_, blob)| *blob != 0)
+        .and_then(|(_, blob)| fd.get_property_blob(blob).ok())
+        .and_then(|data| HdrOutputMetadata::from_blob(&data));
+    let colorspace = value("Colorspace")
+        .and_then(|(handle, val)| match fd.get_property(handle).ok()?.value_type() {
+            property::ValueType::Enum(values) => values
+                .values()
+                .1
+                .iter()
+                .find(|value| value.value() == val)
+                .and_then(|value| value.name().to_str().ok())
+                .and_then(Colorspace::from_name),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    (hdr_metadata, colorspace)
+}
+
+// Converts a matrix coefficient into the S31.32 sign-magnitude fixed point format of `drm_color_ctm`
+fn ctm_value(value: f64) -> u64 {
+    let magnitude = (value.abs() * (1u64 << 32) as f64).round() as u64 & !(1 << 63);
+    if value.is_sign_negative() && magnitude != 0 {
+        magnitude | (1 << 63)
+    } else {
+        magnitude
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::AtomicDrmSurface;
+    use super::{ctm_value, AtomicDrmSurface};
     use std::fs::File;
 
     fn is_send<S: Send>() {}
@@ -1100,4 +1607,14 @@ mod test {
     fn surface_is_send() {
         is_send::<AtomicDrmSurface<File>>();
     }
+
+    #[test]
+    fn ctm_values_are_sign_magnitude() {
+        assert_eq!(ctm_value(0.0), 0);
+        assert_eq!(ctm_value(-0.0), 0);
+        assert_eq!(ctm_value(1.0), 1 << 32);
+        assert_eq!(ctm_value(0.5), 1 << 31);
+        assert_eq!(ctm_value(-0.5), (1 << 63) | (1 << 31));
+        assert_eq!(ctm_value(-2.25), (1 << 63) | (9 << 30));
+    }
 }
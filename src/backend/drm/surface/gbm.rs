@@ -1,5 +1,7 @@
 use std::collections::HashSet;
 use std::os::unix::io::AsRawFd;
+#[cfg(feature = "backend_session")]
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use drm::control::{connector, crtc, framebuffer, plane, Device, Mode};
@@ -11,8 +13,17 @@ use crate::backend::allocator::{
     gbm::GbmConvertError,
     Allocator, Format, Fourcc, Modifier, Slot, Swapchain,
 };
-use crate::backend::drm::{device::DevPath, surface::DrmSurfaceInternal, DrmError, DrmSurface};
+use crate::backend::drm::{
+    device::DevPath, surface::DrmSurfaceInternal, Colorspace, DrmError, DrmSurface, HdrOutputMetadata,
+};
 use crate::backend::SwapBuffersError;
+#[cfg(feature = "backend_session")]
+use crate::{
+    backend::session::Signal as SessionSignal,
+    utils::signaling::{Linkable, SignalToken, Signaler},
+};
+#[cfg(feature = "backend_session")]
+use nix::sys::stat;
 
 use slog::{debug, error, o, trace, warn};
 
@@ -25,6 +36,10 @@ pub struct GbmBufferedSurface<A: Allocator<BufferObject<()>> + 'static, D: AsRaw
     next_fb: Option<Slot<BufferObject<()>>>,
     swapchain: Swapchain<A, BufferObject<()>>,
     drm: Arc<DrmSurface<D>>,
+    #[cfg(feature = "backend_session")]
+    needs_reset: Arc<AtomicBool>,
+    #[cfg(feature = "backend_session")]
+    links: Vec<SignalToken>,
 }
 
 // we cannot simply pick the first supported format of the intersection of *all* formats, because:
@@ -32,12 +47,38 @@ pub struct GbmBufferedSurface<A: Allocator<BufferObject<()>> + 'static, D: AsRaw
 // - some formats might perform terribly
 // - we might need some work-arounds, if one supports modifiers, but the other does not
 //
-// So lets just pick `ARGB8888` or `XRGB8888` for now, they are widely supported.
-// Once we have proper color management and possibly HDR support,
-// we need to have a more sophisticated picker.
-// (Or maybe just select A/XRGB2101010, if available, we will see.)
+// So lets just pick `ARGB8888` or `XRGB8888` by default, they are widely supported.
+// Compositors wanting to drive HDR outputs can explicitly ask for higher bit depths
+// (see `GbmBufferedSurface::new_with_formats`).
 const SUPPORTED_FORMATS: &[Fourcc] = &[Fourcc::Argb8888, Fourcc::Xrgb8888];
 
+/// 8-bit color formats used by [`GbmBufferedSurface::new`]
+pub const SDR_COLOR_FORMATS: &[Fourcc] = SUPPORTED_FORMATS;
+
+/// 10-bit color formats, suitable for HDR output with the PQ transfer function,
+/// falling back to 8-bit formats
+pub const HDR_10BIT_COLOR_FORMATS: &[Fourcc] = &[
+    Fourcc::Argb2101010,
+    Fourcc::Xrgb2101010,
+    Fourcc::Abgr2101010,
+    Fourcc::Xbgr2101010,
+    Fourcc::Argb8888,
+    Fourcc::Xrgb8888,
+];
+
+/// Half-float color formats, suitable for linear (scRGB) HDR output,
+/// falling back to 10-bit and 8-bit formats
+pub const HDR_FP16_COLOR_FORMATS: &[Fourcc] = &[
+    Fourcc::Abgr16161616f,
+    Fourcc::Xbgr16161616f,
+    Fourcc::Argb2101010,
+    Fourcc::Xrgb2101010,
+    Fourcc::Abgr2101010,
+    Fourcc::Xbgr2101010,
+    Fourcc::Argb8888,
+    Fourcc::Xrgb8888,
+];
+
 impl<A, D> GbmBufferedSurface<A, D>
 where
     A: Allocator<BufferObject<()>>,
@@ -51,9 +92,28 @@ where
     /// which can render into a Dmabuf, and a gbm allocator that can produce
     /// buffers of a supported format for rendering.
     pub fn new<L>(
+        drm: DrmSurface<D>,
+        allocator: A,
+        renderer_formats: HashSet<Format>,
+        log: L,
+    ) -> Result<GbmBufferedSurface<A, D>, Error<A::Error>>
+    where
+        L: Into<Option<::slog::Logger>>,
+    {
+        Self::new_with_formats(drm, allocator, renderer_formats, SDR_COLOR_FORMATS, log)
+    }
+
+    /// Create a new `GbmBufferedSurface` like [`GbmBufferedSurface::new`],
+    /// but pick the first working format of the given `color_formats`.
+    ///
+    /// This can be used to create surfaces with higher bit depths for HDR output
+    /// (see e.g. [`HDR_10BIT_COLOR_FORMATS`] and [`HDR_FP16_COLOR_FORMATS`]).
+    /// Use [`GbmBufferedSurface::format`] to find out, which format was chosen.
+    pub fn new_with_formats<L>(
         drm: DrmSurface<D>,
         mut allocator: A,
         renderer_formats: HashSet<Format>,
+        color_formats: &[Fourcc],
         log: L,
     ) -> Result<GbmBufferedSurface<A, D>, Error<A::Error>>
     where
@@ -63,7 +123,7 @@ where
         let drm = Arc::new(drm);
 
         let log = crate::slog_or_fallback(log).new(o!("backend" => "drm_render"));
-        for format in SUPPORTED_FORMATS {
+        for format in color_formats {
             debug!(log, "Testing color format: {}", format);
             match Self::new_internal(
                 drm.clone(),
@@ -80,6 +140,10 @@ where
                         next_fb: None,
                         swapchain,
                         drm,
+                        #[cfg(feature = "backend_session")]
+                        needs_reset: Arc::new(AtomicBool::new(false)),
+                        #[cfg(feature = "backend_session")]
+                        links: Vec::new(),
                     })
                 }
                 Err((alloc, err)) => {
@@ -89,7 +153,7 @@ where
                 }
             }
         }
-        Err(error.unwrap())
+        Err(error.unwrap_or(Error::NoSupportedPlaneFormat))
     }
 
     #[allow(clippy::type_complexity)]
@@ -211,6 +275,13 @@ where
     /// *Note*: This function can be called multiple times and
     /// will return the same buffer until it is queued (see [`GbmBufferedSurface::queue_buffer`]).
     pub fn next_buffer(&mut self) -> Result<(Dmabuf, u8), Error<A::Error>> {
+        // the contents of our buffers are undefined after the session got resumed
+        #[cfg(feature = "backend_session")]
+        if self.needs_reset.swap(false, Ordering::SeqCst) {
+            self.next_fb = None;
+            self.reset_buffers();
+        }
+
         if self.next_fb.is_none() {
             let slot = self
                 .swapchain
@@ -286,6 +357,11 @@ where
         self.swapchain.reset_buffers()
     }
 
+    /// Returns the pixel format of the buffers of this surface
+    pub fn format(&self) -> Fourcc {
+        self.swapchain.format()
+    }
+
     /// Returns the underlying [`crtc`](drm::control::crtc) of this surface
     pub fn crtc(&self) -> crtc::Handle {
         self.drm.crtc()
@@ -363,6 +439,88 @@ where
         self.swapchain.resize(w as _, h as _);
         Ok(())
     }
+
+    /// Returns the number of entries of the gamma ramps supported by the underlying
+    /// [`crtc`](drm::control::crtc).
+    ///
+    /// A size of `0` indicates, that the crtc does not support setting gamma ramps.
+    pub fn gamma_size(&self) -> Result<usize, Error<A::Error>> {
+        self.drm.gamma_size().map_err(Error::DrmError)
+    }
+
+    /// Sets new gamma ramps for the underlying [`crtc`](drm::control::crtc).
+    ///
+    /// See [`DrmSurface::set_gamma`] for details.
+    pub fn set_gamma(&self, red: &[u16], green: &[u16], blue: &[u16]) -> Result<(), Error<A::Error>> {
+        self.drm.set_gamma(red, green, blue).map_err(Error::DrmError)
+    }
+
+    /// Restores the gamma ramps, that were set on the underlying [`crtc`](drm::control::crtc)
+    /// when the surface was created.
+    pub fn reset_gamma(&self) -> Result<(), Error<A::Error>> {
+        self.drm.reset_gamma().map_err(Error::DrmError)
+    }
+
+    /// Returns the number of entries of the degamma ramps supported by the underlying
+    /// [`crtc`](drm::control::crtc).
+    pub fn degamma_size(&self) -> Result<usize, Error<A::Error>> {
+        self.drm.degamma_size().map_err(Error::DrmError)
+    }
+
+    /// Sets new degamma ramps for the underlying [`crtc`](drm::control::crtc).
+    ///
+    /// See [`DrmSurface::set_degamma`] for details.
+    pub fn set_degamma(&self, red: &[u16], green: &[u16], blue: &[u16]) -> Result<(), Error<A::Error>> {
+        self.drm.set_degamma(red, green, blue).map_err(Error::DrmError)
+    }
+
+    /// Restores the degamma ramps, that were set on the underlying [`crtc`](drm::control::crtc)
+    /// when the surface was created.
+    pub fn reset_degamma(&self) -> Result<(), Error<A::Error>> {
+        self.drm.reset_degamma().map_err(Error::DrmError)
+    }
+
+    /// Sets a new color transformation matrix for the underlying [`crtc`](drm::control::crtc).
+    ///
+    /// See [`DrmSurface::set_ctm`] for details.
+    pub fn set_ctm(&self, matrix: [f64; 9]) -> Result<(), Error<A::Error>> {
+        self.drm.set_ctm(matrix).map_err(Error::DrmError)
+    }
+
+    /// Restores the color transformation matrix, that was set on the underlying
+    /// [`crtc`](drm::control::crtc) when the surface was created.
+    pub fn reset_ctm(&self) -> Result<(), Error<A::Error>> {
+        self.drm.reset_ctm().map_err(Error::DrmError)
+    }
+
+    /// Returns the pending HDR metadata send to the monitors of this surface
+    pub fn hdr_output_metadata(&self) -> Option<HdrOutputMetadata> {
+        self.drm.hdr_output_metadata()
+    }
+
+    /// Sets the HDR metadata send to the monitors of this surface.
+    ///
+    /// See [`DrmSurface::set_hdr_output_metadata`] for details.
+    pub fn set_hdr_output_metadata(
+        &self,
+        metadata: Option<HdrOutputMetadata>,
+    ) -> Result<(), Error<A::Error>> {
+        self.drm
+            .set_hdr_output_metadata(metadata)
+            .map_err(Error::DrmError)
+    }
+
+    /// Returns the pending colorimetry signaled to the monitors of this surface
+    pub fn colorspace(&self) -> Colorspace {
+        self.drm.colorspace()
+    }
+
+    /// Sets the colorimetry signaled to the monitors of this surface.
+    ///
+    /// See [`DrmSurface::set_colorspace`] for details.
+    pub fn set_colorspace(&self, colorspace: Colorspace) -> Result<(), Error<A::Error>> {
+        self.drm.set_colorspace(colorspace).map_err(Error::DrmError)
+    }
 }
 
 #[derive(Debug)]
@@ -377,6 +535,35 @@ impl<A: AsRawFd + 'static> Drop for FbHandle<A> {
     }
 }
 
+#[cfg(feature = "backend_session")]
+impl<A, D> Linkable<SessionSignal> for GbmBufferedSurface<A, D>
+where
+    A: Allocator<BufferObject<()>>,
+    D: AsRawFd + 'static,
+{
+    /// Links the underlying [`DrmSurface`] and resets the buffers of this surface,
+    /// once the session or its device is activated again.
+    fn link(&mut self, signaler: Signaler<SessionSignal>) {
+        self.drm.link_session(signaler.clone());
+
+        let needs_reset = self.needs_reset.clone();
+        let dev_id = self.drm.dev_id;
+        let token = signaler.register(move |signal| match *signal {
+            SessionSignal::ActivateSession => needs_reset.store(true, Ordering::SeqCst),
+            // the device was re-opened, the state of the previous fd is gone
+            SessionSignal::ActivateDevice {
+                major,
+                minor,
+                new_fd: Some(_),
+            } if major as u64 == stat::major(dev_id) && minor as u64 == stat::minor(dev_id) => {
+                needs_reset.store(true, Ordering::SeqCst)
+            }
+            _ => {}
+        });
+        self.links.push(token);
+    }
+}
+
 fn attach_framebuffer<E, D>(drm: &Arc<DrmSurface<D>>, bo: &BufferObject<()>) -> Result<FbHandle<D>, Error<E>>
 where
     E: std::error::Error + Send + Sync,
@@ -4,16 +4,16 @@ use std::collections::HashSet;
 use std::os::unix::io::AsRawFd;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    Arc, RwLock,
+    Arc, Mutex, RwLock,
 };
 
 use crate::backend::drm::{
-    device::legacy::set_connector_state,
+    device::legacy::{get_gamma, set_connector_state},
     device::{DevPath, DrmDeviceInternal},
     error::Error,
 };
 
-use slog::{debug, info, o, trace};
+use slog::{debug, info, o, trace, warn};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct State {
@@ -77,6 +77,12 @@ pub struct LegacyDrmSurface<A: AsRawFd + 'static> {
     crtc: crtc::Handle,
     state: RwLock<State>,
     pending: RwLock<State>,
+    // gamma ramps (red, green, blue) found on creation
+    original_gamma: Option<[Vec<u16>; 3]>,
+    // gamma ramps set by us, if any
+    gamma: Mutex<Option<[Vec<u16>; 3]>>,
+    // gamma ramps need to be set again
+    gamma_dirty: AtomicBool,
     pub(crate) logger: ::slog::Logger,
 }
 
@@ -101,12 +107,23 @@ impl<A: AsRawFd + 'static> LegacyDrmSurface<A> {
             connectors: connectors.iter().copied().collect(),
         };
 
+        let original_gamma = match get_gamma(&*fd, crtc) {
+            Ok(gamma) => gamma,
+            Err(err) => {
+                warn!(logger, "Failed to read gamma ramps of crtc {:?}: {}", crtc, err);
+                None
+            }
+        };
+
         let surface = LegacyDrmSurface {
             fd,
             active,
             crtc,
             state: RwLock::new(state),
             pending: RwLock::new(pending),
+            original_gamma,
+            gamma: Mutex::new(None),
+            gamma_dirty: AtomicBool::new(false),
             logger,
         };
 
@@ -282,6 +299,7 @@ impl<A: AsRawFd + 'static> LegacyDrmSurface<A> {
             })?;
 
         *current = pending.clone();
+        self.restore_gamma_if_dirty()?;
 
         if event {
             // set crtc does not trigger page_flip events, so we immediately queue a flip
@@ -308,6 +326,8 @@ impl<A: AsRawFd + 'static> LegacyDrmSurface<A> {
             return Err(Error::DeviceInactive);
         }
 
+        self.restore_gamma_if_dirty()?;
+
         ControlDevice::page_flip(
             &*self.fd,
             self.crtc,
@@ -350,6 +370,84 @@ impl<A: AsRawFd + 'static> LegacyDrmSurface<A> {
             .is_ok())
     }
 
+    pub fn gamma_size(&self) -> Result<usize, Error> {
+        let info = self.fd.get_crtc(self.crtc).map_err(|source| Error::Access {
+            errmsg: "Error loading crtc info",
+            dev: self.fd.dev_path(),
+            source,
+        })?;
+        Ok(info.gamma_length() as usize)
+    }
+
+    pub fn set_gamma(&self, red: &[u16], green: &[u16], blue: &[u16]) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+
+        let size = self.gamma_size()?;
+        if let Some(provided) = [red.len(), green.len(), blue.len()]
+            .into_iter()
+            .find(|len| *len != size)
+        {
+            return Err(Error::GammaSizeMismatch {
+                expected: size,
+                provided,
+            });
+        }
+
+        trace!(self.logger, "Setting new gamma ramps");
+        // the legacy api applies the ramps immediately
+        self.fd
+            .set_gamma(self.crtc, red, green, blue)
+            .map_err(|source| Error::Access {
+                errmsg: "Failed to set gamma ramps",
+                dev: self.fd.dev_path(),
+                source,
+            })?;
+        *self.gamma.lock().unwrap() = Some([red.to_vec(), green.to_vec(), blue.to_vec()]);
+        self.gamma_dirty.store(false, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    pub fn reset_gamma(&self) -> Result<(), Error> {
+        if !self.active.load(Ordering::SeqCst) {
+            return Err(Error::DeviceInactive);
+        }
+
+        let mut gamma = self.gamma.lock().unwrap();
+        if gamma.is_some() {
+            if let Some([red, green, blue]) = self.original_gamma.as_ref() {
+                self.fd
+                    .set_gamma(self.crtc, red, green, blue)
+                    .map_err(|source| Error::Access {
+                        errmsg: "Failed to restore gamma ramps",
+                        dev: self.fd.dev_path(),
+                        source,
+                    })?;
+            }
+            *gamma = None;
+        }
+        self.gamma_dirty.store(false, Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    fn restore_gamma_if_dirty(&self) -> Result<(), Error> {
+        if self.gamma_dirty.swap(false, Ordering::SeqCst) {
+            if let Some([red, green, blue]) = self.gamma.lock().unwrap().as_ref() {
+                self.fd
+                    .set_gamma(self.crtc, red, green, blue)
+                    .map_err(|source| Error::Access {
+                        errmsg: "Failed to set gamma ramps",
+                        dev: self.fd.dev_path(),
+                        source,
+                    })?;
+            }
+        }
+        Ok(())
+    }
+
     // we use this function to verify, if a certain connector/mode combination
     // is valid on our crtc. We do this with the most basic information we have:
     // - is there a matching encoder
@@ -411,6 +509,13 @@ impl<A: AsRawFd + 'static> LegacyDrmSurface<A> {
         } else {
             State::current_state(&*self.fd, self.crtc)?
         };
+
+        // the gamma ramps might have been changed (or restored) in the meantime,
+        // so we need to set ours again. We might not be drm master yet, so wait for the next commit.
+        if self.gamma.lock().unwrap().is_some() {
+            self.gamma_dirty.store(true, Ordering::SeqCst);
+        }
+
         Ok(())
     }
 }
@@ -427,6 +532,13 @@ impl<A: AsRawFd + 'static> Drop for LegacyDrmSurface<A> {
             return;
         }
 
+        // restore the gamma ramps, if we changed them
+        if let (Some(_), Some([red, green, blue])) =
+            (self.gamma.lock().unwrap().as_ref(), self.original_gamma.as_ref())
+        {
+            let _ = self.fd.set_gamma(self.crtc, red, green, blue);
+        }
+
         // disable connectors again
         let current = self.state.read().unwrap();
         if set_connector_state(&*self.fd, current.connectors.iter().copied(), false).is_ok() {
@@ -14,7 +14,9 @@ pub(super) mod atomic;
 #[cfg(feature = "backend_gbm")]
 pub(super) mod gbm;
 pub(super) mod legacy;
-use super::{device::DevPath, error::Error, plane_type, planes, PlaneType, Planes};
+use super::{
+    device::DevPath, error::Error, plane_type, planes, Colorspace, HdrOutputMetadata, PlaneType, Planes,
+};
 use crate::backend::allocator::{Format, Fourcc, Modifier};
 use atomic::AtomicDrmSurface;
 use legacy::LegacyDrmSurface;
@@ -187,12 +189,176 @@ impl<A: AsRawFd + 'static> DrmSurface<A> {
         }
     }
 
+    /// Returns the number of entries of the gamma ramps supported by the underlying
+    /// [`crtc`](drm::control::crtc).
+    ///
+    /// A size of `0` indicates, that the crtc does not support setting gamma ramps.
+    pub fn gamma_size(&self) -> Result<usize, Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.gamma_size(),
+            DrmSurfaceInternal::Legacy(surf) => surf.gamma_size(),
+        }
+    }
+
+    /// Sets new gamma ramps for the underlying [`crtc`](drm::control::crtc).
+    ///
+    /// Every ramp needs to have exactly [`gamma_size`](DrmSurface::gamma_size) entries.
+    ///
+    /// On atomic devices this sets the `GAMMA_LUT` property, which is applied on the next
+    /// [`commit`](DrmSurface::commit) or [`page_flip`](DrmSurface::page_flip).
+    /// The legacy implementation applies the new ramps immediately.
+    ///
+    /// The ramps set are kept across session switches and the original ramps
+    /// are restored, when the surface is dropped.
+    pub fn set_gamma(&self, red: &[u16], green: &[u16], blue: &[u16]) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.set_gamma(red, green, blue),
+            DrmSurfaceInternal::Legacy(surf) => surf.set_gamma(red, green, blue),
+        }
+    }
+
+    /// Restores the gamma ramps, that were set on the underlying
+    /// [`crtc`](drm::control::crtc), when this surface was created.
+    ///
+    /// Like [`set_gamma`](DrmSurface::set_gamma) this is applied on the next commit
+    /// or page_flip on atomic devices.
+    pub fn reset_gamma(&self) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.reset_gamma(),
+            DrmSurfaceInternal::Legacy(surf) => surf.reset_gamma(),
+        }
+    }
+
+    /// Returns the number of entries of the degamma ramps supported by the underlying
+    /// [`crtc`](drm::control::crtc).
+    ///
+    /// A size of `0` indicates, that the crtc does not support setting degamma ramps,
+    /// which is always the case for legacy devices.
+    pub fn degamma_size(&self) -> Result<usize, Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.degamma_size(),
+            DrmSurfaceInternal::Legacy(_) => Ok(0),
+        }
+    }
+
+    /// Sets new degamma ramps for the underlying [`crtc`](drm::control::crtc).
+    ///
+    /// The degamma lut is applied before the [color transformation matrix](DrmSurface::set_ctm),
+    /// the gamma lut afterwards. Every ramp needs to have exactly
+    /// [`degamma_size`](DrmSurface::degamma_size) entries.
+    ///
+    /// This sets the `DEGAMMA_LUT` property, which is applied on the next
+    /// [`commit`](DrmSurface::commit) or [`page_flip`](DrmSurface::page_flip),
+    /// and fails with [`Error::UnknownProperty`] on legacy devices or crtcs without degamma support.
+    pub fn set_degamma(&self, red: &[u16], green: &[u16], blue: &[u16]) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.set_degamma(red, green, blue),
+            DrmSurfaceInternal::Legacy(_) => Err(Error::UnknownProperty {
+                handle: self.crtc.into(),
+                name: "DEGAMMA_LUT",
+            }),
+        }
+    }
+
+    /// Restores the degamma ramps, that were set on the underlying
+    /// [`crtc`](drm::control::crtc), when this surface was created.
+    pub fn reset_degamma(&self) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.reset_degamma(),
+            DrmSurfaceInternal::Legacy(_) => Ok(()),
+        }
+    }
+
+    /// Sets a new color transformation matrix for the underlying [`crtc`](drm::control::crtc).
+    ///
+    /// The matrix is given in row-major order and applied to the linear rgb values
+    /// between the degamma and the gamma lut.
+    ///
+    /// This sets the `CTM` property, which is applied on the next
+    /// [`commit`](DrmSurface::commit) or [`page_flip`](DrmSurface::page_flip),
+    /// and fails with [`Error::UnknownProperty`] on legacy devices or crtcs without a matrix.
+    pub fn set_ctm(&self, matrix: [f64; 9]) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.set_ctm(matrix),
+            DrmSurfaceInternal::Legacy(_) => Err(Error::UnknownProperty {
+                handle: self.crtc.into(),
+                name: "CTM",
+            }),
+        }
+    }
+
+    /// Restores the color transformation matrix, that was set on the underlying
+    /// [`crtc`](drm::control::crtc), when this surface was created.
+    pub fn reset_ctm(&self) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.reset_ctm(),
+            DrmSurfaceInternal::Legacy(_) => Ok(()),
+        }
+    }
+
+    /// Returns the pending HDR metadata send to the monitors of this surface
+    pub fn hdr_output_metadata(&self) -> Option<HdrOutputMetadata> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.hdr_output_metadata(),
+            DrmSurfaceInternal::Legacy(_) => None,
+        }
+    }
+
+    /// Sets the HDR metadata send to the monitors of this surface
+    /// via the `HDR_OUTPUT_METADATA` connector property.
+    ///
+    /// Passing `None` disables HDR signaling. The metadata will be applied on the next
+    /// [`commit`](DrmSurface::commit), which might require a modeset.
+    ///
+    /// Fails, if any of the pending connectors does not support HDR metadata.
+    /// This is only supported on atomic devices.
+    pub fn set_hdr_output_metadata(&self, metadata: Option<HdrOutputMetadata>) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.set_hdr_output_metadata(metadata),
+            DrmSurfaceInternal::Legacy(_) if metadata.is_none() => Ok(()),
+            DrmSurfaceInternal::Legacy(_) => Err(Error::UnknownProperty {
+                handle: self.crtc.into(),
+                name: "HDR_OUTPUT_METADATA",
+            }),
+        }
+    }
+
+    /// Returns the pending colorimetry signaled to the monitors of this surface
+    pub fn colorspace(&self) -> Colorspace {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.colorspace(),
+            DrmSurfaceInternal::Legacy(_) => Colorspace::Default,
+        }
+    }
+
+    /// Sets the colorimetry signaled to the monitors of this surface
+    /// via the `Colorspace` connector property.
+    ///
+    /// Will be applied on the next [`commit`](DrmSurface::commit).
+    /// Use [`Colorspace::Bt2020Rgb`] together with [`HdrEotf::Pq`](super::HdrEotf::Pq)
+    /// metadata to drive HDR10 monitors.
+    ///
+    /// Fails, if any of the pending connectors does not support the given colorspace.
+    /// This is only supported on atomic devices.
+    pub fn set_colorspace(&self, colorspace: Colorspace) -> Result<(), Error> {
+        match &*self.internal {
+            DrmSurfaceInternal::Atomic(surf) => surf.set_colorspace(colorspace),
+            DrmSurfaceInternal::Legacy(_) if colorspace == Colorspace::Default => Ok(()),
+            DrmSurfaceInternal::Legacy(_) => Err(Error::UnknownProperty {
+                handle: self.crtc.into(),
+                name: "Colorspace",
+            }),
+        }
+    }
+
     /// Returns true whenever any state changes are pending to be commited
     ///
     /// The following functions may trigger a pending commit:
     /// - [`add_connector`](DrmSurface::add_connector)
     /// - [`remove_connector`](DrmSurface::remove_connector)
     /// - [`use_mode`](DrmSurface::use_mode)
+    /// - [`set_hdr_output_metadata`](DrmSurface::set_hdr_output_metadata)
+    /// - [`set_colorspace`](DrmSurface::set_colorspace)
     pub fn commit_pending(&self) -> bool {
         match &*self.internal {
             DrmSurfaceInternal::Atomic(surf) => surf.commit_pending(),
@@ -0,0 +1,307 @@
+use super::Device;
+
+/// Error returned when applying a configuration option to a device failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum DeviceConfigError {
+    /// The device does not support the given option
+    #[error("The configuration option is not supported by this device")]
+    Unsupported,
+    /// The value is not valid for the given option
+    #[error("The configuration value is invalid for this device")]
+    Invalid,
+}
+
+/// Pointer acceleration profile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccelProfile {
+    /// No acceleration, the pointer speed is constant and only scaled by the configured speed
+    Flat,
+    /// Acceleration depends on the speed of the input movement
+    Adaptive,
+}
+
+/// Method used to generate scroll events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScrollMethod {
+    /// The device does not emit scroll events
+    NoScroll,
+    /// Scrolling is triggered by moving two fingers on the device
+    TwoFinger,
+    /// Scrolling is triggered by moving a finger along the edges of the device
+    Edge,
+    /// Scrolling is triggered by moving the device while holding down a button
+    OnButtonDown,
+}
+
+/// Mapping of tap gestures to pointer buttons
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TapButtonMap {
+    /// One finger is the left, two fingers the right and three fingers the middle button
+    LeftRightMiddle,
+    /// One finger is the left, two fingers the middle and three fingers the right button
+    LeftMiddleRight,
+}
+
+/// Trait for configuring the behavior of an input device
+///
+/// Every option has a way to query whether the device supports it. Setting an
+/// unsupported option returns [`DeviceConfigError::Unsupported`].
+///
+/// All methods have default implementations describing a device without any
+/// configurable options, so backends without configuration support only need an
+/// empty `impl`.
+pub trait DeviceConfig: Device {
+    /// Returns the number of fingers usable for tapping, `0` if tapping is unsupported
+    fn tap_finger_count(&self) -> u32 {
+        0
+    }
+    /// Returns whether tap-to-click is enabled
+    fn tap_enabled(&self) -> bool {
+        false
+    }
+    /// Enable or disable tap-to-click
+    fn set_tap_enabled(&mut self, _enabled: bool) -> Result<(), DeviceConfigError> {
+        Err(DeviceConfigError::Unsupported)
+    }
+    /// Returns whether tap-and-drag is enabled
+    fn tap_drag_enabled(&self) -> bool {
+        false
+    }
+    /// Enable or disable tap-and-drag
+    fn set_tap_drag_enabled(&mut self, _enabled: bool) -> Result<(), DeviceConfigError> {
+        Err(DeviceConfigError::Unsupported)
+    }
+    /// Returns the button mapping used for tapping, if tapping is supported
+    fn tap_button_map(&self) -> Option<TapButtonMap> {
+        None
+    }
+    /// Set the button mapping used for tapping
+    fn set_tap_button_map(&mut self, _map: TapButtonMap) -> Result<(), DeviceConfigError> {
+        Err(DeviceConfigError::Unsupported)
+    }
+
+    /// Returns whether the device supports natural scrolling
+    fn has_natural_scroll(&self) -> bool {
+        false
+    }
+    /// Returns whether natural scrolling is enabled
+    fn natural_scroll_enabled(&self) -> bool {
+        false
+    }
+    /// Enable or disable natural scrolling
+    fn set_natural_scroll_enabled(&mut self, _enabled: bool) -> Result<(), DeviceConfigError> {
+        Err(DeviceConfigError::Unsupported)
+    }
+
+    /// Returns the acceleration profiles supported by the device
+    fn accel_profiles(&self) -> Vec<AccelProfile> {
+        Vec::new()
+    }
+    /// Returns the current acceleration profile, if acceleration is supported
+    fn accel_profile(&self) -> Option<AccelProfile> {
+        None
+    }
+    /// Set the acceleration profile
+    fn set_accel_profile(&mut self, _profile: AccelProfile) -> Result<(), DeviceConfigError> {
+        Err(DeviceConfigError::Unsupported)
+    }
+    /// Returns the pointer speed in the range `[-1.0, 1.0]`, if acceleration is supported
+    fn accel_speed(&self) -> Option<f64> {
+        None
+    }
+    /// Set the pointer speed in the range `[-1.0, 1.0]`
+    fn set_accel_speed(&mut self, _speed: f64) -> Result<(), DeviceConfigError> {
+        Err(DeviceConfigError::Unsupported)
+    }
+
+    /// Returns whether the device supports left-handed mode
+    fn has_left_handed(&self) -> bool {
+        false
+    }
+    /// Returns whether left-handed mode is enabled
+    fn left_handed(&self) -> bool {
+        false
+    }
+    /// Enable or disable left-handed mode
+    fn set_left_handed(&mut self, _enabled: bool) -> Result<(), DeviceConfigError> {
+        Err(DeviceConfigError::Unsupported)
+    }
+
+    /// Returns whether the device supports a calibration matrix
+    fn has_calibration_matrix(&self) -> bool {
+        false
+    }
+    /// Returns the current calibration matrix, if the device supports one
+    ///
+    /// The matrix is given as the first two rows of a 3x3 row-major
+    /// transformation matrix applied to normalized absolute coordinates.
+    fn calibration_matrix(&self) -> Option<[f32; 6]> {
+        None
+    }
+    /// Set the calibration matrix
+    fn set_calibration_matrix(&mut self, _matrix: [f32; 6]) -> Result<(), DeviceConfigError> {
+        Err(DeviceConfigError::Unsupported)
+    }
+
+    /// Returns the scroll methods supported by the device
+    fn scroll_methods(&self) -> Vec<ScrollMethod> {
+        Vec::new()
+    }
+    /// Returns the current scroll method, if the device supports any
+    fn scroll_method(&self) -> Option<ScrollMethod> {
+        None
+    }
+    /// Set the scroll method
+    fn set_scroll_method(&mut self, _method: ScrollMethod) -> Result<(), DeviceConfigError> {
+        Err(DeviceConfigError::Unsupported)
+    }
+}
+
+/// Snapshot of the configuration of an input device
+///
+/// Can be obtained from a device via [`DeviceSettings::from_device`] and applied to any
+/// device via [`DeviceSettings::apply`], which makes it easy to persist configuration
+/// and to apply a common configuration to devices of different backends.
+///
+/// Options set to `None` are left untouched.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceSettings {
+    /// Tap-to-click
+    pub tap_enabled: Option<bool>,
+    /// Tap-and-drag
+    pub tap_drag_enabled: Option<bool>,
+    /// Button mapping used for tapping
+    pub tap_button_map: Option<TapButtonMap>,
+    /// Natural scrolling
+    pub natural_scroll_enabled: Option<bool>,
+    /// Acceleration profile
+    pub accel_profile: Option<AccelProfile>,
+    /// Pointer speed
+    pub accel_speed: Option<f64>,
+    /// Left-handed mode
+    pub left_handed: Option<bool>,
+    /// Calibration matrix
+    pub calibration_matrix: Option<[f32; 6]>,
+    /// Scroll method
+    pub scroll_method: Option<ScrollMethod>,
+}
+
+impl DeviceSettings {
+    /// Capture the current configuration of a device
+    ///
+    /// Options unsupported by the device are set to `None`.
+    pub fn from_device<D: DeviceConfig + ?Sized>(device: &D) -> DeviceSettings {
+        let tap = device.tap_finger_count() > 0;
+        DeviceSettings {
+            tap_enabled: tap.then(|| device.tap_enabled()),
+            tap_drag_enabled: tap.then(|| device.tap_drag_enabled()),
+            tap_button_map: device.tap_button_map(),
+            natural_scroll_enabled: device
+                .has_natural_scroll()
+                .then(|| device.natural_scroll_enabled()),
+            accel_profile: device.accel_profile(),
+            accel_speed: device.accel_speed(),
+            left_handed: device.has_left_handed().then(|| device.left_handed()),
+            calibration_matrix: device.calibration_matrix(),
+            scroll_method: device.scroll_method(),
+        }
+    }
+
+    /// Apply the configuration to a device
+    ///
+    /// Options unsupported by the device are skipped. All supported options are applied,
+    /// even if some of them fail, in which case the first error is returned.
+    pub fn apply<D: DeviceConfig + ?Sized>(&self, device: &mut D) -> Result<(), DeviceConfigError> {
+        let results = [
+            self.tap_enabled.map(|v| device.set_tap_enabled(v)),
+            self.tap_drag_enabled.map(|v| device.set_tap_drag_enabled(v)),
+            self.tap_button_map.map(|v| device.set_tap_button_map(v)),
+            self.natural_scroll_enabled
+                .map(|v| device.set_natural_scroll_enabled(v)),
+            self.accel_profile.map(|v| device.set_accel_profile(v)),
+            self.accel_speed.map(|v| device.set_accel_speed(v)),
+            self.left_handed.map(|v| device.set_left_handed(v)),
+            self.calibration_matrix.map(|v| device.set_calibration_matrix(v)),
+            self.scroll_method.map(|v| device.set_scroll_method(v)),
+        ];
+        results
+            .into_iter()
+            .flatten()
+            .filter(|res| *res != Err(DeviceConfigError::Unsupported))
+            .find(Result::is_err)
+            .unwrap_or(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::input::DeviceCapability;
+    use std::path::PathBuf;
+
+    #[derive(Debug, Default, PartialEq, Eq, Hash)]
+    struct Touchpad {
+        tap: bool,
+        natural_scroll: bool,
+    }
+
+    impl Device for Touchpad {
+        fn id(&self) -> String {
+            "touchpad".into()
+        }
+        fn name(&self) -> String {
+            "touchpad".into()
+        }
+        fn has_capability(&self, capability: DeviceCapability) -> bool {
+            capability == DeviceCapability::Pointer
+        }
+        fn usb_id(&self) -> Option<(u32, u32)> {
+            None
+        }
+        fn syspath(&self) -> Option<PathBuf> {
+            None
+        }
+    }
+
+    impl DeviceConfig for Touchpad {
+        fn tap_finger_count(&self) -> u32 {
+            3
+        }
+        fn tap_enabled(&self) -> bool {
+            self.tap
+        }
+        fn set_tap_enabled(&mut self, enabled: bool) -> Result<(), DeviceConfigError> {
+            self.tap = enabled;
+            Ok(())
+        }
+        fn has_natural_scroll(&self) -> bool {
+            true
+        }
+        fn natural_scroll_enabled(&self) -> bool {
+            self.natural_scroll
+        }
+        fn set_natural_scroll_enabled(&mut self, enabled: bool) -> Result<(), DeviceConfigError> {
+            self.natural_scroll = enabled;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn settings_roundtrip_skips_unsupported() {
+        let mut device = Touchpad::default();
+        let settings = DeviceSettings {
+            tap_enabled: Some(true),
+            natural_scroll_enabled: Some(true),
+            left_handed: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(settings.apply(&mut device), Ok(()));
+        assert!(device.tap && device.natural_scroll);
+
+        let captured = DeviceSettings::from_device(&device);
+        assert_eq!(captured.tap_enabled, Some(true));
+        assert_eq!(captured.tap_drag_enabled, Some(false));
+        assert_eq!(captured.natural_scroll_enabled, Some(true));
+        assert_eq!(captured.left_handed, None);
+    }
+}
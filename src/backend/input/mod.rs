@@ -2,11 +2,15 @@
 
 use std::path::PathBuf;
 
+mod config;
 mod tablet;
 
+pub use config::{AccelProfile, DeviceConfig, DeviceConfigError, DeviceSettings, ScrollMethod, TapButtonMap};
 pub use tablet::{
-    ProximityState, TabletToolAxisEvent, TabletToolButtonEvent, TabletToolCapabilitys, TabletToolDescriptor,
-    TabletToolEvent, TabletToolProximityEvent, TabletToolTipEvent, TabletToolTipState, TabletToolType,
+    ProximityState, TabletPadAxisSource, TabletPadButtonEvent, TabletPadDescriptor, TabletPadEvent,
+    TabletPadModeGroup, TabletPadRingEvent, TabletPadStripEvent, TabletToolAxisEvent, TabletToolButtonEvent,
+    TabletToolCapabilitys, TabletToolDescriptor, TabletToolEvent, TabletToolProximityEvent,
+    TabletToolTipEvent, TabletToolTipState, TabletToolType,
 };
 
 use crate::utils::{Logical, Point, Raw, Size};
@@ -29,6 +33,13 @@ pub trait Device: PartialEq + Eq + std::hash::Hash {
     ///
     /// The path is an absolute path and includes the sys mount point.
     fn syspath(&self) -> Option<PathBuf>;
+
+    /// Returns the layout of the buttons, rings and strips of a tablet pad
+    ///
+    /// Returns `None` for devices without the [`DeviceCapability::TabletPad`] capability.
+    fn tablet_pad(&self) -> Option<TabletPadDescriptor> {
+        None
+    }
 }
 
 /// Set of input types a device may provide
@@ -390,12 +401,52 @@ pub trait TouchFrameEvent<B: InputBackend>: Event<B> {}
 
 impl<B: InputBackend> TouchFrameEvent<B> for UnusedEvent {}
 
+/// Switches that may be toggled on a device with the [`DeviceCapability::Switch`] capability
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Switch {
+    /// The laptop lid was closed when the state is [`SwitchState::On`]
+    Lid,
+    /// The device is in tablet mode when the state is [`SwitchState::On`]
+    ///
+    /// What exactly tablet mode means depends on the device, usually the keyboard and
+    /// touchpad are not reachable anymore.
+    TabletMode,
+}
+
+/// State of a [`Switch`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SwitchState {
+    /// The switch is off
+    Off,
+    /// The switch is on
+    On,
+}
+
+/// Trait for switch toggle events
+pub trait SwitchToggleEvent<B: InputBackend>: Event<B> {
+    /// Switch that was toggled, `None` if the switch is unknown to smithay
+    fn switch(&self) -> Option<Switch>;
+
+    /// New state of the switch
+    fn state(&self) -> SwitchState;
+}
+
+impl<B: InputBackend> SwitchToggleEvent<B> for UnusedEvent {
+    fn switch(&self) -> Option<Switch> {
+        match *self {}
+    }
+
+    fn state(&self) -> SwitchState {
+        match *self {}
+    }
+}
+
 /// Trait that describes objects providing a source of input events. All input backends
 /// need to implement this and provide the same base guarantees about the precision of
 /// given events.
 pub trait InputBackend: Sized {
     /// Type representing input devices
-    type Device: Device;
+    type Device: Device + DeviceConfig;
     /// Type representing keyboard events
     type KeyboardKeyEvent: KeyboardKeyEvent<Self>;
     /// Type representing axis events on pointer devices
@@ -424,6 +475,14 @@ pub trait InputBackend: Sized {
     type TabletToolTipEvent: TabletToolTipEvent<Self>;
     /// Type representing button events on tablet tool devices
     type TabletToolButtonEvent: TabletToolButtonEvent<Self>;
+    /// Type representing button events on tablet pad devices
+    type TabletPadButtonEvent: TabletPadButtonEvent<Self>;
+    /// Type representing ring events on tablet pad devices
+    type TabletPadRingEvent: TabletPadRingEvent<Self>;
+    /// Type representing strip events on tablet pad devices
+    type TabletPadStripEvent: TabletPadStripEvent<Self>;
+    /// Type representing toggle events of switches
+    type SwitchToggleEvent: SwitchToggleEvent<Self>;
 
     /// Special events that are custom to this backend
     type SpecialEvent;
@@ -520,6 +579,30 @@ pub enum InputEvent<B: InputBackend> {
         event: B::TabletToolButtonEvent,
     },
 
+    /// A tablet pad button was pressed or released
+    TabletPadButton {
+        /// The tablet pad button event
+        event: B::TabletPadButtonEvent,
+    },
+
+    /// A tablet pad ring was touched or released
+    TabletPadRing {
+        /// The tablet pad ring event
+        event: B::TabletPadRingEvent,
+    },
+
+    /// A tablet pad strip was touched or released
+    TabletPadStrip {
+        /// The tablet pad strip event
+        event: B::TabletPadStripEvent,
+    },
+
+    /// A switch was toggled
+    SwitchToggle {
+        /// The switch toggle event
+        event: B::SwitchToggleEvent,
+    },
+
     /// Special event specific of this backend
     Special(B::SpecialEvent),
 }
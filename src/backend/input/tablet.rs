@@ -368,3 +368,145 @@ impl<B: InputBackend> TabletToolButtonEvent<B> for UnusedEvent {
         match *self {}
     }
 }
+
+/// Description of the buttons, rings and strips of a tablet pad
+#[derive(Debug, Clone, Default, Hash, Eq, PartialEq)]
+pub struct TabletPadDescriptor {
+    /// Number of buttons
+    pub buttons: u32,
+    /// Number of rings
+    pub rings: u32,
+    /// Number of strips
+    pub strips: u32,
+    /// Mode groups of the pad
+    ///
+    /// Every button, ring and strip belongs to exactly one mode group.
+    pub mode_groups: Vec<TabletPadModeGroup>,
+}
+
+/// Group of buttons, rings and strips of a tablet pad sharing a mode
+///
+/// Pads may provide a physical way to switch the mode of a group, after which the
+/// buttons, rings and strips of this group are expected to trigger different actions.
+#[derive(Debug, Clone, Default, Hash, Eq, PartialEq)]
+pub struct TabletPadModeGroup {
+    /// Number of modes of this group
+    pub modes: u32,
+    /// Mode of this group at the time the descriptor was created
+    pub mode: u32,
+    /// Indices of the buttons belonging to this group
+    pub buttons: Vec<u32>,
+    /// Indices of the rings belonging to this group
+    pub rings: Vec<u32>,
+    /// Indices of the strips belonging to this group
+    pub strips: Vec<u32>,
+}
+
+/// Source of a tablet pad ring or strip event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TabletPadAxisSource {
+    /// The ring or strip is touched by a finger
+    Finger,
+    /// The source is unknown
+    Unknown,
+}
+
+/// Trait with functions available for all tablet pad events
+pub trait TabletPadEvent<B: InputBackend>: Event<B> {
+    /// Index of the mode group the button, ring or strip of this event belongs to
+    fn mode_group(&self) -> u32;
+
+    /// Current mode of the mode group at the time of this event
+    fn mode(&self) -> u32;
+}
+
+impl<B: InputBackend> TabletPadEvent<B> for UnusedEvent {
+    fn mode_group(&self) -> u32 {
+        match *self {}
+    }
+
+    fn mode(&self) -> u32 {
+        match *self {}
+    }
+}
+
+/// Signals that a button of a device with the `DeviceCapability::TabletPad` capability
+/// was pressed or released
+pub trait TabletPadButtonEvent<B: InputBackend>: TabletPadEvent<B> {
+    /// Index of the button that triggered this event
+    ///
+    /// Pad buttons are numbered sequentially starting at `0`, they are no linux
+    /// button codes.
+    fn button(&self) -> u32;
+
+    /// Return the button state of the event
+    fn button_state(&self) -> ButtonState;
+}
+
+impl<B: InputBackend> TabletPadButtonEvent<B> for UnusedEvent {
+    fn button(&self) -> u32 {
+        match *self {}
+    }
+
+    fn button_state(&self) -> ButtonState {
+        match *self {}
+    }
+}
+
+/// Signals that a ring of a device with the `DeviceCapability::TabletPad` capability
+/// changed its position
+pub trait TabletPadRingEvent<B: InputBackend>: TabletPadEvent<B> {
+    /// Index of the ring that triggered this event
+    fn number(&self) -> u32;
+
+    /// Position of the ring in degrees clockwise from the logical north of the ring
+    ///
+    /// Returns `None` once the finger was lifted from the ring.
+    fn position(&self) -> Option<f64>;
+
+    /// Source of the interaction with the ring
+    fn source(&self) -> TabletPadAxisSource;
+}
+
+impl<B: InputBackend> TabletPadRingEvent<B> for UnusedEvent {
+    fn number(&self) -> u32 {
+        match *self {}
+    }
+
+    fn position(&self) -> Option<f64> {
+        match *self {}
+    }
+
+    fn source(&self) -> TabletPadAxisSource {
+        match *self {}
+    }
+}
+
+/// Signals that a strip of a device with the `DeviceCapability::TabletPad` capability
+/// changed its position
+pub trait TabletPadStripEvent<B: InputBackend>: TabletPadEvent<B> {
+    /// Index of the strip that triggered this event
+    fn number(&self) -> u32;
+
+    /// Position of the strip normalized to the range `[0, 1]`, where `0` is the top or left end
+    ///
+    /// Returns `None` once the finger was lifted from the strip.
+    fn position(&self) -> Option<f64>;
+
+    /// Source of the interaction with the strip
+    fn source(&self) -> TabletPadAxisSource;
+}
+
+impl<B: InputBackend> TabletPadStripEvent<B> for UnusedEvent {
+    fn number(&self) -> u32 {
+        match *self {}
+    }
+
+    fn position(&self) -> Option<f64> {
+        match *self {}
+    }
+
+    fn source(&self) -> TabletPadAxisSource {
+        match *self {}
+    }
+}
@@ -0,0 +1,177 @@
+use crate::backend::input::{self as backend, DeviceConfigError};
+
+use input as libinput;
+
+impl backend::DeviceConfig for libinput::Device {
+    fn tap_finger_count(&self) -> u32 {
+        libinput::Device::config_tap_finger_count(self)
+    }
+
+    fn tap_enabled(&self) -> bool {
+        libinput::Device::config_tap_enabled(self)
+    }
+
+    fn set_tap_enabled(&mut self, enabled: bool) -> Result<(), DeviceConfigError> {
+        libinput::Device::config_tap_set_enabled(self, enabled).map_err(Into::into)
+    }
+
+    fn tap_drag_enabled(&self) -> bool {
+        libinput::Device::config_tap_drag_enabled(self)
+    }
+
+    fn set_tap_drag_enabled(&mut self, enabled: bool) -> Result<(), DeviceConfigError> {
+        libinput::Device::config_tap_set_drag_enabled(self, enabled).map_err(Into::into)
+    }
+
+    fn tap_button_map(&self) -> Option<backend::TapButtonMap> {
+        if libinput::Device::config_tap_finger_count(self) == 0 {
+            return None;
+        }
+        libinput::Device::config_tap_button_map(self).map(Into::into)
+    }
+
+    fn set_tap_button_map(&mut self, map: backend::TapButtonMap) -> Result<(), DeviceConfigError> {
+        libinput::Device::config_tap_set_button_map(self, map.into()).map_err(Into::into)
+    }
+
+    fn has_natural_scroll(&self) -> bool {
+        libinput::Device::config_scroll_has_natural_scroll(self)
+    }
+
+    fn natural_scroll_enabled(&self) -> bool {
+        libinput::Device::config_scroll_natural_scroll_enabled(self)
+    }
+
+    fn set_natural_scroll_enabled(&mut self, enabled: bool) -> Result<(), DeviceConfigError> {
+        libinput::Device::config_scroll_set_natural_scroll_enabled(self, enabled).map_err(Into::into)
+    }
+
+    fn accel_profiles(&self) -> Vec<backend::AccelProfile> {
+        libinput::Device::config_accel_profiles(self)
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    fn accel_profile(&self) -> Option<backend::AccelProfile> {
+        libinput::Device::config_accel_profile(self).map(Into::into)
+    }
+
+    fn set_accel_profile(&mut self, profile: backend::AccelProfile) -> Result<(), DeviceConfigError> {
+        libinput::Device::config_accel_set_profile(self, profile.into()).map_err(Into::into)
+    }
+
+    fn accel_speed(&self) -> Option<f64> {
+        libinput::Device::config_accel_is_available(self).then(|| libinput::Device::config_accel_speed(self))
+    }
+
+    fn set_accel_speed(&mut self, speed: f64) -> Result<(), DeviceConfigError> {
+        libinput::Device::config_accel_set_speed(self, speed).map_err(Into::into)
+    }
+
+    fn has_left_handed(&self) -> bool {
+        libinput::Device::config_left_handed_is_available(self)
+    }
+
+    fn left_handed(&self) -> bool {
+        libinput::Device::config_left_handed(self)
+    }
+
+    fn set_left_handed(&mut self, enabled: bool) -> Result<(), DeviceConfigError> {
+        libinput::Device::config_left_handed_set(self, enabled).map_err(Into::into)
+    }
+
+    fn has_calibration_matrix(&self) -> bool {
+        libinput::Device::config_calibration_has_matrix(self)
+    }
+
+    fn calibration_matrix(&self) -> Option<[f32; 6]> {
+        libinput::Device::config_calibration_matrix(self)
+    }
+
+    fn set_calibration_matrix(&mut self, matrix: [f32; 6]) -> Result<(), DeviceConfigError> {
+        libinput::Device::config_calibration_set_matrix(self, matrix).map_err(Into::into)
+    }
+
+    fn scroll_methods(&self) -> Vec<backend::ScrollMethod> {
+        libinput::Device::config_scroll_methods(self)
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    fn scroll_method(&self) -> Option<backend::ScrollMethod> {
+        libinput::Device::config_scroll_method(self).map(Into::into)
+    }
+
+    fn set_scroll_method(&mut self, method: backend::ScrollMethod) -> Result<(), DeviceConfigError> {
+        libinput::Device::config_scroll_set_method(self, method.into()).map_err(Into::into)
+    }
+}
+
+impl From<libinput::DeviceConfigError> for DeviceConfigError {
+    fn from(other: libinput::DeviceConfigError) -> DeviceConfigError {
+        match other {
+            libinput::DeviceConfigError::Unsupported => DeviceConfigError::Unsupported,
+            libinput::DeviceConfigError::Invalid => DeviceConfigError::Invalid,
+        }
+    }
+}
+
+impl From<libinput::AccelProfile> for backend::AccelProfile {
+    fn from(other: libinput::AccelProfile) -> backend::AccelProfile {
+        match other {
+            libinput::AccelProfile::Flat => backend::AccelProfile::Flat,
+            libinput::AccelProfile::Adaptive => backend::AccelProfile::Adaptive,
+        }
+    }
+}
+
+impl From<backend::AccelProfile> for libinput::AccelProfile {
+    fn from(other: backend::AccelProfile) -> libinput::AccelProfile {
+        match other {
+            backend::AccelProfile::Flat => libinput::AccelProfile::Flat,
+            backend::AccelProfile::Adaptive => libinput::AccelProfile::Adaptive,
+        }
+    }
+}
+
+impl From<libinput::ScrollMethod> for backend::ScrollMethod {
+    fn from(other: libinput::ScrollMethod) -> backend::ScrollMethod {
+        match other {
+            libinput::ScrollMethod::NoScroll => backend::ScrollMethod::NoScroll,
+            libinput::ScrollMethod::TwoFinger => backend::ScrollMethod::TwoFinger,
+            libinput::ScrollMethod::Edge => backend::ScrollMethod::Edge,
+            libinput::ScrollMethod::OnButtonDown => backend::ScrollMethod::OnButtonDown,
+        }
+    }
+}
+
+impl From<backend::ScrollMethod> for libinput::ScrollMethod {
+    fn from(other: backend::ScrollMethod) -> libinput::ScrollMethod {
+        match other {
+            backend::ScrollMethod::NoScroll => libinput::ScrollMethod::NoScroll,
+            backend::ScrollMethod::TwoFinger => libinput::ScrollMethod::TwoFinger,
+            backend::ScrollMethod::Edge => libinput::ScrollMethod::Edge,
+            backend::ScrollMethod::OnButtonDown => libinput::ScrollMethod::OnButtonDown,
+        }
+    }
+}
+
+impl From<libinput::TapButtonMap> for backend::TapButtonMap {
+    fn from(other: libinput::TapButtonMap) -> backend::TapButtonMap {
+        match other {
+            libinput::TapButtonMap::LeftRightMiddle => backend::TapButtonMap::LeftRightMiddle,
+            libinput::TapButtonMap::LeftMiddleRight => backend::TapButtonMap::LeftMiddleRight,
+        }
+    }
+}
+
+impl From<backend::TapButtonMap> for libinput::TapButtonMap {
+    fn from(other: backend::TapButtonMap) -> libinput::TapButtonMap {
+        match other {
+            backend::TapButtonMap::LeftRightMiddle => libinput::TapButtonMap::LeftRightMiddle,
+            backend::TapButtonMap::LeftMiddleRight => libinput::TapButtonMap::LeftMiddleRight,
+        }
+    }
+}
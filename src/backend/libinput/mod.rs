@@ -21,6 +21,7 @@ use calloop::{EventSource, Interest, Mode, Poll, PostAction, Readiness, Token, T
 
 use slog::{info, o, trace};
 
+mod config;
 mod tablet;
 
 // No idea if this is the same across unix platforms
@@ -73,7 +74,10 @@ impl Linkable<SessionSignal> for LibinputInputBackend {
             } => {
                 input.suspend();
             }
-            SessionSignal::ActivateSession | SessionSignal::ActivateDevice { .. } => {
+            SessionSignal::ActivateSession
+            | SessionSignal::ActivateDevice {
+                major: INPUT_MAJOR, ..
+            } => {
                 if input.resume().is_err() {
                     slog::error!(log, "Failed to resume libinput context");
                 }
@@ -111,6 +115,10 @@ impl backend::Device for libinput::Device {
         #[cfg(not(feature = "udev"))]
         None
     }
+
+    fn tablet_pad(&self) -> Option<backend::TabletPadDescriptor> {
+        tablet::pad_descriptor(self)
+    }
 }
 
 impl From<backend::DeviceCapability> for libinput::DeviceCapability {
@@ -379,6 +387,33 @@ impl backend::Event<LibinputInputBackend> for event::touch::TouchFrameEvent {
 
 impl backend::TouchFrameEvent<LibinputInputBackend> for event::touch::TouchFrameEvent {}
 
+impl backend::Event<LibinputInputBackend> for event::switch::SwitchToggleEvent {
+    fn time(&self) -> u32 {
+        event::switch::SwitchEventTrait::time(self)
+    }
+
+    fn device(&self) -> libinput::Device {
+        event::EventTrait::device(self)
+    }
+}
+
+impl backend::SwitchToggleEvent<LibinputInputBackend> for event::switch::SwitchToggleEvent {
+    fn switch(&self) -> Option<backend::Switch> {
+        match event::switch::SwitchToggleEvent::switch(self) {
+            Some(event::switch::Switch::Lid) => Some(backend::Switch::Lid),
+            Some(event::switch::Switch::TabletMode) => Some(backend::Switch::TabletMode),
+            _ => None,
+        }
+    }
+
+    fn state(&self) -> backend::SwitchState {
+        match event::switch::SwitchToggleEvent::switch_state(self) {
+            event::switch::SwitchState::Off => backend::SwitchState::Off,
+            event::switch::SwitchState::On => backend::SwitchState::On,
+        }
+    }
+}
+
 impl InputBackend for LibinputInputBackend {
     type Device = libinput::Device;
     type KeyboardKeyEvent = event::keyboard::KeyboardKeyEvent;
@@ -395,6 +430,10 @@ impl InputBackend for LibinputInputBackend {
     type TabletToolProximityEvent = event::tablet_tool::TabletToolProximityEvent;
     type TabletToolTipEvent = event::tablet_tool::TabletToolTipEvent;
     type TabletToolButtonEvent = event::tablet_tool::TabletToolButtonEvent;
+    type TabletPadButtonEvent = event::tablet_pad::TabletPadButtonEvent;
+    type TabletPadRingEvent = event::tablet_pad::TabletPadRingEvent;
+    type TabletPadStripEvent = event::tablet_pad::TabletPadStripEvent;
+    type SwitchToggleEvent = event::switch::SwitchToggleEvent;
 
     type SpecialEvent = backend::UnusedEvent;
 }
@@ -580,6 +619,28 @@ impl EventSource for LibinputInputBackend {
                             trace!(self.logger, "Unknown libinput tablet event");
                         }
                     },
+                    libinput::Event::TabletPad(tablet_pad_event) => match tablet_pad_event {
+                        event::TabletPadEvent::Button(event) => {
+                            callback(InputEvent::TabletPadButton { event }, &mut ());
+                        }
+                        event::TabletPadEvent::Ring(event) => {
+                            callback(InputEvent::TabletPadRing { event }, &mut ());
+                        }
+                        event::TabletPadEvent::Strip(event) => {
+                            callback(InputEvent::TabletPadStrip { event }, &mut ());
+                        }
+                        _ => {
+                            trace!(self.logger, "Unknown libinput tablet pad event");
+                        }
+                    },
+                    libinput::Event::Switch(switch_event) => match switch_event {
+                        event::SwitchEvent::Toggle(event) => {
+                            callback(InputEvent::SwitchToggle { event }, &mut ());
+                        }
+                        _ => {
+                            trace!(self.logger, "Unknown libinput switch event");
+                        }
+                    },
                     _ => {} //FIXME: What to do with the rest.
                 }
             }
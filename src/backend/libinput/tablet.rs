@@ -1,10 +1,11 @@
 use crate::backend::input::{
-    self as backend, TabletToolCapabilitys, TabletToolDescriptor, TabletToolTipState, TabletToolType,
+    self as backend, TabletPadAxisSource, TabletPadDescriptor, TabletPadModeGroup, TabletToolCapabilitys,
+    TabletToolDescriptor, TabletToolTipState, TabletToolType,
 };
 
 use input as libinput;
 use input::event;
-use input::event::{tablet_tool, EventTrait};
+use input::event::{tablet_pad, tablet_tool, EventTrait};
 
 use super::LibinputInputBackend;
 
@@ -187,3 +188,128 @@ impl backend::TabletToolButtonEvent<LibinputInputBackend> for tablet_tool::Table
         tablet_tool::TabletToolButtonEvent::button_state(self).into()
     }
 }
+
+pub(super) fn pad_descriptor(device: &libinput::Device) -> Option<TabletPadDescriptor> {
+    if !device.has_capability(libinput::DeviceCapability::TabletPad) {
+        return None;
+    }
+
+    let buttons = device.tablet_pad_number_of_buttons().max(0) as u32;
+    let rings = device.tablet_pad_number_of_rings().max(0) as u32;
+    let strips = device.tablet_pad_number_of_strips().max(0) as u32;
+    let mode_groups = (0..device.tablet_pad_number_of_mode_groups().max(0) as u32)
+        .filter_map(|index| device.tablet_pad_mode_group(index))
+        .map(|group| TabletPadModeGroup {
+            modes: group.number_of_modes(),
+            mode: group.mode(),
+            buttons: (0..buttons).filter(|button| group.has_button(*button)).collect(),
+            rings: (0..rings).filter(|ring| group.has_ring(*ring)).collect(),
+            strips: (0..strips).filter(|strip| group.has_strip(*strip)).collect(),
+        })
+        .collect();
+
+    Some(TabletPadDescriptor {
+        buttons,
+        rings,
+        strips,
+        mode_groups,
+    })
+}
+
+/// Marker for tablet pad events
+pub trait IsTabletPadEvent: tablet_pad::TabletPadEventTrait + EventTrait {}
+
+impl IsTabletPadEvent for tablet_pad::TabletPadButtonEvent {}
+impl IsTabletPadEvent for tablet_pad::TabletPadRingEvent {}
+impl IsTabletPadEvent for tablet_pad::TabletPadStripEvent {}
+
+impl backend::Event<LibinputInputBackend> for tablet_pad::TabletPadButtonEvent {
+    fn time(&self) -> u32 {
+        tablet_pad::TabletPadEventTrait::time(self)
+    }
+
+    fn device(&self) -> libinput::Device {
+        event::EventTrait::device(self)
+    }
+}
+
+impl backend::Event<LibinputInputBackend> for tablet_pad::TabletPadRingEvent {
+    fn time(&self) -> u32 {
+        tablet_pad::TabletPadEventTrait::time(self)
+    }
+
+    fn device(&self) -> libinput::Device {
+        event::EventTrait::device(self)
+    }
+}
+
+impl backend::Event<LibinputInputBackend> for tablet_pad::TabletPadStripEvent {
+    fn time(&self) -> u32 {
+        tablet_pad::TabletPadEventTrait::time(self)
+    }
+
+    fn device(&self) -> libinput::Device {
+        event::EventTrait::device(self)
+    }
+}
+
+impl<E> backend::TabletPadEvent<LibinputInputBackend> for E
+where
+    E: IsTabletPadEvent + backend::Event<LibinputInputBackend>,
+{
+    fn mode_group(&self) -> u32 {
+        tablet_pad::TabletPadEventTrait::mode_group(self).index()
+    }
+
+    fn mode(&self) -> u32 {
+        tablet_pad::TabletPadEventTrait::mode(self)
+    }
+}
+
+impl backend::TabletPadButtonEvent<LibinputInputBackend> for tablet_pad::TabletPadButtonEvent {
+    fn button(&self) -> u32 {
+        tablet_pad::TabletPadButtonEvent::button_number(self)
+    }
+
+    fn button_state(&self) -> backend::ButtonState {
+        tablet_pad::TabletPadButtonEvent::button_state(self).into()
+    }
+}
+
+impl backend::TabletPadRingEvent<LibinputInputBackend> for tablet_pad::TabletPadRingEvent {
+    fn number(&self) -> u32 {
+        tablet_pad::TabletPadRingEvent::number(self)
+    }
+
+    fn position(&self) -> Option<f64> {
+        // libinput signals the end of an interaction with a position of -1
+        let position = tablet_pad::TabletPadRingEvent::position(self);
+        (position >= 0.0).then(|| position)
+    }
+
+    fn source(&self) -> TabletPadAxisSource {
+        match tablet_pad::TabletPadRingEvent::source(self) {
+            tablet_pad::RingAxisSource::Finger => TabletPadAxisSource::Finger,
+            _ => TabletPadAxisSource::Unknown,
+        }
+    }
+}
+
+impl backend::TabletPadStripEvent<LibinputInputBackend> for tablet_pad::TabletPadStripEvent {
+    fn number(&self) -> u32 {
+        tablet_pad::TabletPadStripEvent::number(self)
+    }
+
+    fn position(&self) -> Option<f64> {
+        // libinput signals the end of an interaction with a position of -1
+        let position = tablet_pad::TabletPadStripEvent::position(self);
+        (position >= 0.0).then(|| position)
+    }
+
+    fn source(&self) -> TabletPadAxisSource {
+        match tablet_pad::TabletPadStripEvent::source(self) {
+            tablet_pad::StripAxisSource::Finger => TabletPadAxisSource::Finger,
+            _ => TabletPadAxisSource::Unknown,
+        }
+    }
+}
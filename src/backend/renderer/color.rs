@@ -0,0 +1,415 @@
+//! Color spaces and conversions between them
+//!
+//! Renderers assume content to be in the [`ColorSpace::SRGB`] color space by default.
+//! To composite HDR content or to render onto an HDR output, textures and render targets
+//! can be tagged with a different [`ColorSpace`] and renderers supporting color management
+//! (like the [`Gles2Renderer`](super::gles2::Gles2Renderer)) will convert between them.
+//!
+//! Linear light values are normalized in a way, that `1.0` equals the reference white
+//! of SDR content. Absolute luminance (as required by [`TransferFunction::Pq`]) is calculated
+//! from the configured luminance of the SDR reference white.
+//!
+//! Independent of color management a [`ColorTransform`] can be applied to a
+//! [`Frame`](super::Frame) to e.g. desaturate or invert everything rendered.
+
+use cgmath::{Matrix, Matrix3, Matrix4, Vector3};
+
+/// Default luminance of the SDR reference white in cd/m² (see ITU-R BT.2408)
+pub const DEFAULT_SDR_WHITE_LEVEL: f32 = 203.0;
+
+/// Maximum luminance encodable by [`TransferFunction::Pq`] in cd/m²
+pub const PQ_MAX_LUMINANCE: f32 = 10000.0;
+
+/// Transfer function used to encode color values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransferFunction {
+    /// The piece-wise sRGB transfer function (IEC 61966-2-1)
+    Srgb,
+    /// No transfer function, values are linear light
+    Linear,
+    /// The perceptual quantizer (SMPTE ST 2084)
+    Pq,
+}
+
+impl TransferFunction {
+    /// Converts an encoded value into linear light
+    pub fn decode(self, value: f32, sdr_white_level: f32) -> f32 {
+        match self {
+            TransferFunction::Srgb => {
+                let value = value.max(0.0);
+                if value <= 0.04045 {
+                    value / 12.92
+                } else {
+                    ((value + 0.055) / 1.055).powf(2.4)
+                }
+            }
+            TransferFunction::Linear => value,
+            TransferFunction::Pq => {
+                let p = value.max(0.0).powf(1.0 / PQ_M2);
+                let nits = ((p - PQ_C1).max(0.0) / (PQ_C2 - PQ_C3 * p)).powf(1.0 / PQ_M1) * PQ_MAX_LUMINANCE;
+                nits / sdr_white_level
+            }
+        }
+    }
+
+    /// Encodes a linear light value
+    pub fn encode(self, value: f32, sdr_white_level: f32) -> f32 {
+        match self {
+            TransferFunction::Srgb => {
+                let value = value.clamp(0.0, 1.0);
+                if value <= 0.0031308 {
+                    value * 12.92
+                } else {
+                    1.055 * value.powf(1.0 / 2.4) - 0.055
+                }
+            }
+            TransferFunction::Linear => value,
+            TransferFunction::Pq => {
+                let value = (value * sdr_white_level / PQ_MAX_LUMINANCE).max(0.0);
+                let p = value.powf(PQ_M1);
+                ((PQ_C1 + PQ_C2 * p) / (1.0 + PQ_C3 * p)).powf(PQ_M2)
+            }
+        }
+    }
+}
+
+const PQ_M1: f32 = 0.159_301_76;
+const PQ_M2: f32 = 78.843_75;
+const PQ_C1: f32 = 0.835_937_5;
+const PQ_C2: f32 = 18.851_563;
+const PQ_C3: f32 = 18.6875;
+
+/// Color primaries (and white point) of a color space
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Primaries {
+    /// ITU-R BT.709 primaries, as used by sRGB
+    Bt709,
+    /// ITU-R BT.2020 primaries, as used by most HDR content
+    Bt2020,
+}
+
+impl Primaries {
+    /// Matrix to convert linear rgb values from these primaries into the `target` primaries.
+    pub fn conversion_matrix(self, target: Primaries) -> Matrix3<f32> {
+        // cgmath matrices are column-major, these are easier to read transposed.
+        match (self, target) {
+            (Primaries::Bt709, Primaries::Bt2020) => Matrix3::new(
+                0.627_404, 0.329_283, 0.043_313, //
+                0.069_097, 0.919_540, 0.011_362, //
+                0.016_391, 0.088_013, 0.895_595, //
+            )
+            .transpose(),
+            (Primaries::Bt2020, Primaries::Bt709) => Matrix3::new(
+                1.660_491, -0.587_641, -0.072_850, //
+                -0.124_550, 1.132_900, -0.008_349, //
+                -0.018_151, -0.100_579, 1.118_729, //
+            )
+            .transpose(),
+            _ => Matrix3::new(1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0),
+        }
+    }
+}
+
+/// A color space defined by its primaries and transfer function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ColorSpace {
+    /// Primaries of the color space
+    pub primaries: Primaries,
+    /// Transfer function used to encode values
+    pub transfer_function: TransferFunction,
+}
+
+impl ColorSpace {
+    /// The sRGB color space, used by almost all SDR content
+    pub const SRGB: ColorSpace = ColorSpace {
+        primaries: Primaries::Bt709,
+        transfer_function: TransferFunction::Srgb,
+    };
+    /// Linear light with BT.709 primaries (e.g. scRGB, if used with floating point buffers)
+    pub const LINEAR: ColorSpace = ColorSpace {
+        primaries: Primaries::Bt709,
+        transfer_function: TransferFunction::Linear,
+    };
+    /// BT.2020 primaries with the PQ transfer function (HDR10)
+    pub const BT2020_PQ: ColorSpace = ColorSpace {
+        primaries: Primaries::Bt2020,
+        transfer_function: TransferFunction::Pq,
+    };
+    /// Linear light with BT.2020 primaries
+    pub const BT2020_LINEAR: ColorSpace = ColorSpace {
+        primaries: Primaries::Bt2020,
+        transfer_function: TransferFunction::Linear,
+    };
+
+    /// Converts a premultiplied rgba color from this color space into the `target` color space.
+    pub fn convert(self, target: ColorSpace, color: [f32; 4], sdr_white_level: f32) -> [f32; 4] {
+        if self == target || color[3] <= 0.0 {
+            return color;
+        }
+
+        let alpha = color[3];
+        let linear = Vector3::new(
+            self.transfer_function.decode(color[0] / alpha, sdr_white_level),
+            self.transfer_function.decode(color[1] / alpha, sdr_white_level),
+            self.transfer_function.decode(color[2] / alpha, sdr_white_level),
+        );
+        let converted = self.primaries.conversion_matrix(target.primaries) * linear;
+        [
+            target.transfer_function.encode(converted.x, sdr_white_level) * alpha,
+            target.transfer_function.encode(converted.y, sdr_white_level) * alpha,
+            target.transfer_function.encode(converted.z, sdr_white_level) * alpha,
+            alpha,
+        ]
+    }
+}
+
+impl Default for ColorSpace {
+    fn default() -> Self {
+        ColorSpace::SRGB
+    }
+}
+
+/// An affine transformation of rgb color values
+///
+/// The transformation is applied to straight (not premultiplied) color values
+/// encoded in the color space of the render target, the alpha channel is left untouched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorTransform {
+    /// Row-major matrix the rgb values are multiplied with
+    pub matrix: [[f32; 3]; 3],
+    /// Offset added to the rgb values after the multiplication
+    pub offset: [f32; 3],
+}
+
+impl ColorTransform {
+    /// Transformation leaving all colors unchanged
+    pub const IDENTITY: ColorTransform = ColorTransform {
+        matrix: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+        offset: [0.0, 0.0, 0.0],
+    };
+
+    /// Transformation inverting all colors
+    pub const INVERT: ColorTransform = ColorTransform {
+        matrix: [[-1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, -1.0]],
+        offset: [1.0, 1.0, 1.0],
+    };
+
+    /// Transformation changing the saturation of all colors
+    ///
+    /// `0.0` results in grayscale, `1.0` leaves colors unchanged and larger values oversaturate.
+    pub fn saturation(saturation: f32) -> ColorTransform {
+        // BT.709 luma coefficients
+        let (r, g, b) = (
+            0.2126 * (1.0 - saturation),
+            0.7152 * (1.0 - saturation),
+            0.0722 * (1.0 - saturation),
+        );
+        ColorTransform {
+            matrix: [
+                [r + saturation, g, b],
+                [r, g + saturation, b],
+                [r, g, b + saturation],
+            ],
+            offset: [0.0, 0.0, 0.0],
+        }
+    }
+
+    /// Transformation multiplying all colors with `factor`
+    pub fn brightness(factor: f32) -> ColorTransform {
+        ColorTransform {
+            matrix: [[factor, 0.0, 0.0], [0.0, factor, 0.0], [0.0, 0.0, factor]],
+            offset: [0.0, 0.0, 0.0],
+        }
+    }
+
+    /// Returns a transformation applying `self` first and `other` afterwards
+    pub fn then(self, other: ColorTransform) -> ColorTransform {
+        let matrix = [0, 1, 2]
+            .map(|row| [0, 1, 2].map(|col| (0..3).map(|i| other.matrix[row][i] * self.matrix[i][col]).sum()));
+        let offset = [0, 1, 2].map(|row| {
+            (0..3).map(|i| other.matrix[row][i] * self.offset[i]).sum::<f32>() + other.offset[row]
+        });
+        ColorTransform { matrix, offset }
+    }
+
+    /// Applies the transformation to a premultiplied rgba color
+    pub fn apply(&self, color: [f32; 4]) -> [f32; 4] {
+        let alpha = color[3];
+        if alpha <= 0.0 {
+            return color;
+        }
+
+        let rgb = [color[0] / alpha, color[1] / alpha, color[2] / alpha];
+        let mut result = [0.0, 0.0, 0.0, alpha];
+        for (row, value) in result.iter_mut().take(3).enumerate() {
+            let transformed = (0..3).map(|i| self.matrix[row][i] * rgb[i]).sum::<f32>() + self.offset[row];
+            *value = transformed.clamp(0.0, 1.0) * alpha;
+        }
+        result
+    }
+
+    /// The transformation as a column-major matrix operating on `vec4(rgb, 1.0)`
+    pub(crate) fn to_matrix4(self) -> Matrix4<f32> {
+        let m = self.matrix;
+        let o = self.offset;
+        Matrix4::new(
+            m[0][0], m[1][0], m[2][0], 0.0, //
+            m[0][1], m[1][1], m[2][1], 0.0, //
+            m[0][2], m[1][2], m[2][2], 0.0, //
+            o[0], o[1], o[2], 1.0, //
+        )
+    }
+}
+
+impl Default for ColorTransform {
+    fn default() -> Self {
+        ColorTransform::IDENTITY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::SquareMatrix;
+
+    fn assert_close(a: f32, b: f32, epsilon: f32) {
+        assert!((a - b).abs() < epsilon, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn srgb_known_values() {
+        let srgb = TransferFunction::Srgb;
+        assert_eq!(srgb.decode(0.0, DEFAULT_SDR_WHITE_LEVEL), 0.0);
+        assert_close(srgb.decode(1.0, DEFAULT_SDR_WHITE_LEVEL), 1.0, 1e-6);
+        assert_close(srgb.decode(0.5, DEFAULT_SDR_WHITE_LEVEL), 0.214_041, 1e-5);
+        // both sides of the linear segment
+        assert_close(srgb.decode(0.04045, DEFAULT_SDR_WHITE_LEVEL), 0.003_130_8, 1e-6);
+        assert_close(srgb.encode(0.003_130_8, DEFAULT_SDR_WHITE_LEVEL), 0.04045, 1e-5);
+        assert_close(srgb.encode(0.214_041, DEFAULT_SDR_WHITE_LEVEL), 0.5, 1e-5);
+        // values are clamped to the encodable range
+        assert_eq!(srgb.encode(2.0, DEFAULT_SDR_WHITE_LEVEL), 1.0);
+        assert_eq!(srgb.encode(-1.0, DEFAULT_SDR_WHITE_LEVEL), 0.0);
+    }
+
+    #[test]
+    fn pq_known_values() {
+        let pq = TransferFunction::Pq;
+        assert_close(pq.decode(0.0, DEFAULT_SDR_WHITE_LEVEL), 0.0, 1e-6);
+        // the maximum encodes 10000 nits
+        assert_close(
+            pq.decode(1.0, DEFAULT_SDR_WHITE_LEVEL),
+            PQ_MAX_LUMINANCE / DEFAULT_SDR_WHITE_LEVEL,
+            1e-2,
+        );
+        assert_close(pq.encode(PQ_MAX_LUMINANCE / 100.0, 100.0), 1.0, 1e-5);
+        // 203 nits reference white is at about 58% (ITU-R BT.2408)
+        assert_close(pq.encode(1.0, DEFAULT_SDR_WHITE_LEVEL), 0.5806, 1e-3);
+        // 100 nits are at about 50.8%
+        assert_close(pq.encode(1.0, 100.0), 0.5081, 1e-3);
+    }
+
+    #[test]
+    fn transfer_functions_round_trip() {
+        for transfer_function in [
+            TransferFunction::Srgb,
+            TransferFunction::Linear,
+            TransferFunction::Pq,
+        ] {
+            for i in 0..=20 {
+                let value = i as f32 / 20.0;
+                let linear = transfer_function.decode(value, DEFAULT_SDR_WHITE_LEVEL);
+                assert_close(
+                    transfer_function.encode(linear, DEFAULT_SDR_WHITE_LEVEL),
+                    value,
+                    1e-4,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn primaries_conversion() {
+        let identity = Matrix3::identity();
+        assert_eq!(Primaries::Bt709.conversion_matrix(Primaries::Bt709), identity);
+        assert_eq!(Primaries::Bt2020.conversion_matrix(Primaries::Bt2020), identity);
+
+        // the conversions are inverse to each other
+        let round_trip = Primaries::Bt2020.conversion_matrix(Primaries::Bt709)
+            * Primaries::Bt709.conversion_matrix(Primaries::Bt2020);
+        for (column, identity_column) in [round_trip.x, round_trip.y, round_trip.z]
+            .iter()
+            .zip([identity.x, identity.y, identity.z].iter())
+        {
+            assert_close(column.x, identity_column.x, 1e-4);
+            assert_close(column.y, identity_column.y, 1e-4);
+            assert_close(column.z, identity_column.z, 1e-4);
+        }
+
+        // both share the D65 white point
+        for (source, target) in [
+            (Primaries::Bt709, Primaries::Bt2020),
+            (Primaries::Bt2020, Primaries::Bt709),
+        ] {
+            let white = source.conversion_matrix(target) * Vector3::new(1.0, 1.0, 1.0);
+            assert_close(white.x, 1.0, 1e-4);
+            assert_close(white.y, 1.0, 1e-4);
+            assert_close(white.z, 1.0, 1e-4);
+        }
+
+        // pure BT.709 red in BT.2020
+        let red = Primaries::Bt709.conversion_matrix(Primaries::Bt2020) * Vector3::new(1.0, 0.0, 0.0);
+        assert_close(red.x, 0.627_404, 1e-6);
+        assert_close(red.y, 0.069_097, 1e-6);
+        assert_close(red.z, 0.016_391, 1e-6);
+    }
+
+    #[test]
+    fn color_space_conversion() {
+        let color = [0.1, 0.2, 0.3, 0.5];
+        assert_eq!(ColorSpace::SRGB.convert(ColorSpace::SRGB, color, 203.0), color);
+        // fully transparent colors are left alone
+        let transparent = [0.0, 0.0, 0.0, 0.0];
+        assert_eq!(
+            ColorSpace::SRGB.convert(ColorSpace::BT2020_PQ, transparent, 203.0),
+            transparent
+        );
+
+        // premultiplied alpha is preserved
+        let linear = ColorSpace::SRGB.convert(ColorSpace::LINEAR, [0.25, 0.25, 0.25, 0.5], 203.0);
+        assert_close(linear[0], 0.214_041 * 0.5, 1e-5);
+        assert_eq!(linear[3], 0.5);
+
+        // sdr white ends up at the reference white of the hdr color space
+        let white = ColorSpace::SRGB.convert(ColorSpace::BT2020_PQ, [1.0, 1.0, 1.0, 1.0], 203.0);
+        for channel in &white[..3] {
+            assert_close(*channel, 0.5806, 1e-3);
+        }
+
+        let round_trip = ColorSpace::BT2020_PQ.convert(
+            ColorSpace::SRGB,
+            ColorSpace::SRGB.convert(ColorSpace::BT2020_PQ, color, 203.0),
+            203.0,
+        );
+        for (a, b) in round_trip.iter().zip(color.iter()) {
+            assert_close(*a, *b, 1e-3);
+        }
+    }
+
+    #[test]
+    fn color_transform() {
+        let color = [0.2, 0.3, 0.4, 0.5];
+        assert_eq!(ColorTransform::IDENTITY.apply(color), color);
+        assert_eq!(
+            ColorTransform::INVERT.apply([0.5, 0.0, 0.5, 0.5]),
+            [0.0, 0.5, 0.0, 0.5]
+        );
+
+        let gray = ColorTransform::saturation(0.0).apply([1.0, 0.0, 0.0, 1.0]);
+        assert!(gray.iter().take(3).all(|c| (c - 0.2126).abs() < 1e-6));
+
+        let inverted_twice = ColorTransform::INVERT.then(ColorTransform::INVERT);
+        assert_eq!(inverted_twice, ColorTransform::IDENTITY);
+        let darker_inverted = ColorTransform::brightness(0.5).then(ColorTransform::INVERT);
+        assert_eq!(darker_inverted.apply([1.0, 1.0, 1.0, 1.0]), [0.5, 0.5, 0.5, 1.0]);
+    }
+}
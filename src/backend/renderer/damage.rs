@@ -181,6 +181,7 @@ struct ElementState {
     last_commit: CommitCounter,
     last_geometry: Rectangle<i32, Physical>,
     last_z_index: usize,
+    last_alpha: f32,
 }
 
 #[derive(Debug, Default)]
@@ -369,14 +370,19 @@ impl DamageTrackedRenderer {
 
             damage.extend(element_output_damage);
 
-            let element_opaque_regions = element
-                .opaque_regions(output_scale)
-                .into_iter()
-                .map(|mut region| {
-                    region.loc += element_geometry.loc;
-                    region
-                })
-                .collect::<Vec<_>>();
+            // a translucent element does not hide anything below it
+            let element_opaque_regions = if element.alpha() < 1.0 {
+                Vec::new()
+            } else {
+                element
+                    .opaque_regions(output_scale)
+                    .into_iter()
+                    .map(|mut region| {
+                        region.loc += element_geometry.loc;
+                        region
+                    })
+                    .collect::<Vec<_>>()
+            };
             opaque_regions.push((z_index, element_opaque_regions));
             render_elements.push(element);
             z_index += 1;
@@ -403,13 +409,17 @@ impl DamageTrackedRenderer {
             .collect::<Vec<_>>();
         damage.extend(elements_gone);
 
-        // if the element has been moved or it's z index changed damage it
+        // if the element has been moved, it's z index or alpha changed damage it
         for (z_index, element) in render_elements.iter().enumerate() {
             let element_geometry = element.geometry(output_scale);
             let element_last_state = self.last_state.elements.get(element.id());
 
             if element_last_state
-                .map(|s| s.last_geometry != element_geometry || s.last_z_index != z_index)
+                .map(|s| {
+                    s.last_geometry != element_geometry
+                        || s.last_z_index != z_index
+                        || s.last_alpha != element.alpha()
+                })
                 .unwrap_or(false)
             {
                 let mut element_damage = vec![element_geometry];
@@ -437,6 +447,21 @@ impl DamageTrackedRenderer {
             damage = vec![output_geo];
         }
 
+        // Elements sampling already rendered content (e.g. blur) have to be redrawn completely,
+        // if anything inside of their sampled region got damaged
+        let sampling_elements = render_elements
+            .iter()
+            .enumerate()
+            .filter_map(|(z_index, element)| {
+                let element_geometry = element.geometry(output_scale);
+                element.sampled_region(output_scale).map(|mut region| {
+                    region.loc += element_geometry.loc;
+                    (z_index, element_geometry, region)
+                })
+            })
+            .collect::<Vec<_>>();
+        expand_sampled_damage(&mut damage, &sampling_elements, &opaque_regions);
+
         // That is all completely new damage, which we need to store for subsequent renders
         let new_damage = damage.clone();
 
@@ -456,6 +481,8 @@ impl DamageTrackedRenderer {
             // just damage everything, if we have no damage
             damage = vec![output_geo];
         };
+        // Old damage might touch sampled regions as well
+        expand_sampled_damage(&mut damage, &sampling_elements, &opaque_regions);
 
         // Optimize the damage for rendering
         damage.dedup();
@@ -564,6 +591,7 @@ impl DamageTrackedRenderer {
                     last_commit: current_commit,
                     last_geometry: elem_geometry,
                     last_z_index: z_index,
+                    last_alpha: elem.alpha(),
                 };
                 (id, state)
             })
@@ -575,3 +603,105 @@ impl DamageTrackedRenderer {
         Ok(Some(new_damage))
     }
 }
+
+// Adds the geometry of every element, whose sampled region overlaps the damage, to the damage.
+// As this creates new damage, which in turn might touch the sampled regions of other elements,
+// this is repeated until no more elements are affected.
+fn expand_sampled_damage(
+    damage: &mut Vec<Rectangle<i32, Physical>>,
+    sampling_elements: &[(usize, Rectangle<i32, Physical>, Rectangle<i32, Physical>)],
+    opaque_regions: &[(usize, Vec<Rectangle<i32, Physical>>)],
+) {
+    let mut expanded = vec![false; sampling_elements.len()];
+    loop {
+        let mut changed = false;
+        for ((z_index, geometry, sampled_region), expanded) in
+            sampling_elements.iter().zip(expanded.iter_mut())
+        {
+            if *expanded
+                || !damage
+                    .iter()
+                    .any(|rect| !rect.is_empty() && rect.overlaps(*sampled_region))
+            {
+                continue;
+            }
+
+            // the element itself might still be partially covered by opaque regions above it
+            damage.extend(
+                opaque_regions
+                    .iter()
+                    .filter(|(index, _)| index < z_index)
+                    .flat_map(|(_, regions)| regions)
+                    .fold(vec![*geometry], |damage, region| {
+                        damage
+                            .into_iter()
+                            .flat_map(|geo| geo.subtract_rect(*region))
+                            .collect::<Vec<_>>()
+                    }),
+            );
+            *expanded = true;
+            changed = true;
+        }
+
+        if !changed {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::expand_sampled_damage;
+    use crate::utils::Rectangle;
+
+    #[test]
+    fn sampled_damage_expands_transitively() {
+        // the first element samples around itself and touches the sampled region of the second one
+        let sampling_elements = [
+            (
+                1,
+                Rectangle::from_loc_and_size((100, 100), (50, 50)),
+                Rectangle::from_loc_and_size((90, 90), (70, 70)),
+            ),
+            (
+                2,
+                Rectangle::from_loc_and_size((170, 100), (50, 50)),
+                Rectangle::from_loc_and_size((140, 90), (100, 70)),
+            ),
+        ];
+        let mut damage = vec![Rectangle::from_loc_and_size((95, 95), (2, 2))];
+        expand_sampled_damage(&mut damage, &sampling_elements, &[]);
+        assert_eq!(
+            damage,
+            vec![
+                Rectangle::from_loc_and_size((95, 95), (2, 2)),
+                Rectangle::from_loc_and_size((100, 100), (50, 50)),
+                Rectangle::from_loc_and_size((170, 100), (50, 50)),
+            ]
+        );
+
+        // damage outside of any sampled region is left alone
+        let mut damage = vec![Rectangle::from_loc_and_size((0, 0), (10, 10))];
+        expand_sampled_damage(&mut damage, &sampling_elements, &[]);
+        assert_eq!(damage, vec![Rectangle::from_loc_and_size((0, 0), (10, 10))]);
+    }
+
+    #[test]
+    fn sampled_damage_respects_opaque_regions() {
+        let sampling_elements = [(
+            1,
+            Rectangle::from_loc_and_size((0, 0), (100, 100)),
+            Rectangle::from_loc_and_size((0, 0), (100, 100)),
+        )];
+        let opaque_regions = vec![(0, vec![Rectangle::from_loc_and_size((0, 0), (100, 50))])];
+        let mut damage = vec![Rectangle::from_loc_and_size((10, 60), (5, 5))];
+        expand_sampled_damage(&mut damage, &sampling_elements, &opaque_regions);
+        assert_eq!(
+            damage,
+            vec![
+                Rectangle::from_loc_and_size((10, 60), (5, 5)),
+                Rectangle::from_loc_and_size((0, 50), (100, 50)),
+            ]
+        );
+    }
+}
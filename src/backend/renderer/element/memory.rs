@@ -428,6 +428,7 @@ pub struct MemoryRenderBufferRenderElement {
     buffer: MemoryRenderBuffer,
     src: Option<Rectangle<f64, Logical>>,
     size: Option<Size<i32, Logical>>,
+    alpha: f32,
 }
 
 impl MemoryRenderBufferRenderElement {
@@ -444,9 +445,18 @@ impl MemoryRenderBufferRenderElement {
             buffer: buffer.clone(),
             src,
             size,
+            alpha: 1.0,
         }
     }
 
+    /// Draw the buffer with the provided alpha value
+    ///
+    /// `alpha` is clamped to `0.0..=1.0`, values smaller than `1.0` draw the buffer translucent.
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha.clamp(0.0, 1.0);
+        self
+    }
+
     fn logical_size(&self) -> Size<i32, Logical> {
         self.size
             .or_else(|| {
@@ -568,6 +578,10 @@ where
             .unwrap_or_else(|| Rectangle::from_loc_and_size(Point::default(), guard.size).to_f64())
     }
 
+    fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
     fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
         Rectangle::from_loc_and_size(self.location.to_i32_round(), self.physical_size(scale))
     }
@@ -580,6 +594,19 @@ where
         scale: Scale<f64>,
         damage: &[Rectangle<i32, Physical>],
         log: &slog::Logger,
+    ) -> Result<(), <R as Renderer>::Error> {
+        self.draw_with_alpha(renderer, frame, location, scale, damage, self.alpha, log)
+    }
+
+    fn draw_with_alpha(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        log: &slog::Logger,
     ) -> Result<(), <R as Renderer>::Error> {
         let physical_size = self.physical_size(scale);
         let mut guard = self.buffer.inner.lock().unwrap();
@@ -599,7 +626,7 @@ where
             .unwrap_or_else(|| Rectangle::from_loc_and_size(Point::default(), texture.size()).to_f64());
 
         let dst = Rectangle::from_loc_and_size(location, physical_size);
-        frame.render_texture_from_to(texture, src, dst, damage, transform, 1.0)
+        frame.render_texture_from_to(texture, src, dst, damage, transform, alpha)
     }
 
     fn damage_since(
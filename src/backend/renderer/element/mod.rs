@@ -11,6 +11,10 @@
 //! - [`memory`](crate::backend::renderer::element::memory) - Memory based render element
 //! - [`texture`](crate::backend::renderer::element::texture) - Texture based render element
 //! - [`surface`](crate::backend::renderer::element::surface) - Wayland surface render element
+//! - `text` - Text render element, requires the `renderer_text` feature
+//!
+//! Additionally [`utils`](crate::backend::renderer::element::utils) provides wrapping elements
+//! to rescale, relocate or fade any other element.
 //!
 //! The [`render_elements!`] macro provides an easy way to aggregate multiple different [RenderElement]s
 //! into a single enum.
@@ -30,7 +34,10 @@ use super::{utils::CommitCounter, Renderer};
 pub mod memory;
 #[cfg(feature = "wayland_frontend")]
 pub mod surface;
+#[cfg(feature = "renderer_text")]
+pub mod text;
 pub mod texture;
+pub mod utils;
 
 crate::utils::ids::id_gen!(next_external_id, EXTERNAL_ID, EXTERNAL_IDS);
 
@@ -123,10 +130,25 @@ pub trait RenderElement<R: Renderer> {
     fn opaque_regions(&self, _scale: Scale<f64>) -> Vec<Rectangle<i32, Physical>> {
         vec![]
     }
+    /// Get the region of already rendered content this element samples from, relative to the element
+    ///
+    /// Elements reading back the framebuffer (e.g. to blur what is below them) have to be
+    /// redrawn completely, whenever anything inside this region is damaged.
+    /// The region may exceed the geometry of the element.
+    fn sampled_region(&self, _scale: Scale<f64>) -> Option<Rectangle<i32, Physical>> {
+        None
+    }
     /// Get the underlying storage of this element, may be used to optimize rendering (eg. drm planes)
     fn underlying_storage(&self, _renderer: &R) -> Option<UnderlyingStorage<'_, R>> {
         None
     }
+    /// Get the alpha value this element should be drawn with
+    ///
+    /// Changes of the alpha value are tracked by the
+    /// [`DamageTrackedRenderer`](crate::backend::renderer::damage::DamageTrackedRenderer).
+    fn alpha(&self) -> f32 {
+        1.0
+    }
     /// Draw this element
     fn draw(
         &self,
@@ -137,6 +159,25 @@ pub trait RenderElement<R: Renderer> {
         damage: &[Rectangle<i32, Physical>],
         log: &slog::Logger,
     ) -> Result<(), R::Error>;
+    /// Draw this element with the provided alpha value instead of [`RenderElement::alpha`]
+    ///
+    /// This is used by wrapping elements like [`AlphaRenderElement`](utils::AlphaRenderElement)
+    /// to fade other elements. The default implementation ignores `alpha` and calls
+    /// [`RenderElement::draw`], elements supporting translucency should override it.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_with_alpha(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        log: &slog::Logger,
+    ) -> Result<(), R::Error> {
+        let _ = alpha;
+        self.draw(renderer, frame, location, scale, damage, log)
+    }
 }
 
 /// Types that can be converted into [`RenderElement`]s
@@ -195,10 +236,18 @@ where
         (*self).opaque_regions(scale)
     }
 
+    fn sampled_region(&self, scale: Scale<f64>) -> Option<Rectangle<i32, Physical>> {
+        (*self).sampled_region(scale)
+    }
+
     fn underlying_storage(&self, renderer: &R) -> Option<UnderlyingStorage<'_, R>> {
         (*self).underlying_storage(renderer)
     }
 
+    fn alpha(&self) -> f32 {
+        (*self).alpha()
+    }
+
     fn draw(
         &self,
         renderer: &mut R,
@@ -210,6 +259,19 @@ where
     ) -> Result<(), R::Error> {
         (*self).draw(renderer, frame, location, scale, damage, log)
     }
+
+    fn draw_with_alpha(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        log: &slog::Logger,
+    ) -> Result<(), R::Error> {
+        (*self).draw_with_alpha(renderer, frame, location, scale, damage, alpha, log)
+    }
 }
 
 #[macro_export]
@@ -346,6 +408,9 @@ macro_rules! render_elements_internal {
     (@call $renderer:ty as $other:ty; draw; $x:ident, $renderer_ref:ident, $frame:ident, $($tail:ident),*) => {
         $crate::backend::renderer::element::RenderElement::<$other>::draw($x, $renderer_ref.as_mut(), $frame.as_mut(), $($tail),*).map_err(Into::into)
     };
+    (@call $renderer:ty as $other:ty; draw_with_alpha; $x:ident, $renderer_ref:ident, $frame:ident, $($tail:ident),*) => {
+        $crate::backend::renderer::element::RenderElement::<$other>::draw_with_alpha($x, $renderer_ref.as_mut(), $frame.as_mut(), $($tail),*).map_err(Into::into)
+    };
     (@call $renderer:ty as $other:ty; $name:ident; $($x:ident),*) => {
         $crate::backend::renderer::element::RenderElement::<$other>::$name($($x),*)
     };
@@ -467,6 +532,32 @@ macro_rules! render_elements_internal {
                 Self::_GenericCatcher(_) => unreachable!(),
             }
         }
+
+        fn sampled_region(&self, scale: $crate::utils::Scale<f64>) -> Option<$crate::utils::Rectangle<i32, $crate::utils::Physical>> {
+            match self {
+                $(
+                    #[allow(unused_doc_comments)]
+                    $(
+                        #[$meta]
+                    )*
+                    Self::$body(x) => $crate::render_elements_internal!(@call $renderer $(as $other_renderer)?; sampled_region; x, scale)
+                ),*,
+                Self::_GenericCatcher(_) => unreachable!(),
+            }
+        }
+
+        fn alpha(&self) -> f32 {
+            match self {
+                $(
+                    #[allow(unused_doc_comments)]
+                    $(
+                        #[$meta]
+                    )*
+                    Self::$body(x) => $crate::render_elements_internal!(@call $renderer $(as $other_renderer)?; alpha; x)
+                ),*,
+                Self::_GenericCatcher(_) => unreachable!(),
+            }
+        }
     };
     (@draw <$renderer:ty>; $($(#[$meta:meta])* $body:ident=$field:ty $(as <$other_renderer:ty>)?),* $(,)?) => {
         fn draw(
@@ -498,6 +589,37 @@ macro_rules! render_elements_internal {
                 Self::_GenericCatcher(_) => unreachable!(),
             }
         }
+
+        fn draw_with_alpha(
+            &self,
+            renderer: &mut $renderer,
+            frame: &mut <$renderer as $crate::backend::renderer::Renderer>::Frame,
+            location: $crate::utils::Point<i32, $crate::utils::Physical>,
+            scale: $crate::utils::Scale<f64>,
+            damage: &[$crate::utils::Rectangle<i32, $crate::utils::Physical>],
+            alpha: f32,
+            log: &slog::Logger,
+        ) -> Result<(), <$renderer as $crate::backend::renderer::Renderer>::Error>
+        where
+        $(
+            $(
+                $renderer: std::convert::AsMut<$other_renderer>,
+                <$renderer as $crate::backend::renderer::Renderer>::Frame: std::convert::AsMut<<$other_renderer as $crate::backend::renderer::Renderer>::Frame>,
+                <$other_renderer as $crate::backend::renderer::Renderer>::Error: Into<<$renderer as $crate::backend::renderer::Renderer>::Error>,
+            )*
+        )*
+        {
+            match self {
+                $(
+                    #[allow(unused_doc_comments)]
+                    $(
+                        #[$meta]
+                    )*
+                    Self::$body(x) => $crate::render_elements_internal!(@call $renderer $(as $other_renderer)?; draw_with_alpha; x, renderer, frame, location, scale, damage, alpha, log)
+                ),*,
+                Self::_GenericCatcher(_) => unreachable!(),
+            }
+        }
     };
     (@draw $renderer:ty; $($(#[$meta:meta])* $body:ident=$field:ty $(as <$other_renderer:ty>)?),* $(,)?) => {
         fn draw(
@@ -521,6 +643,29 @@ macro_rules! render_elements_internal {
                 Self::_GenericCatcher(_) => unreachable!(),
             }
         }
+
+        fn draw_with_alpha(
+            &self,
+            renderer: &mut $renderer,
+            frame: &mut <$renderer as $crate::backend::renderer::Renderer>::Frame,
+            location: $crate::utils::Point<i32, $crate::utils::Physical>,
+            scale: $crate::utils::Scale<f64>,
+            damage: &[$crate::utils::Rectangle<i32, $crate::utils::Physical>],
+            alpha: f32,
+            log: &slog::Logger,
+        ) -> Result<(), <$renderer as $crate::backend::renderer::Renderer>::Error>
+        {
+            match self {
+                $(
+                    #[allow(unused_doc_comments)]
+                    $(
+                        #[$meta]
+                    )*
+                    Self::$body(x) => $crate::render_elements_internal!(@call $renderer $(as $other_renderer)?; draw_with_alpha; x, renderer, frame, location, scale, damage, alpha, log)
+                ),*,
+                Self::_GenericCatcher(_) => unreachable!(),
+            }
+        }
     };
     // Generic renderer
     (@impl $name:ident<$renderer:ident> $(where $($target:ty: $bound:tt $(+ $additional_bound:tt)*),+)?; $($tail:tt)*) => {
@@ -1055,6 +1200,20 @@ where
         self.0.draw(renderer, frame, location, scale, damage, log)
     }
 
+    fn draw_with_alpha(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        log: &slog::Logger,
+    ) -> Result<(), <R as Renderer>::Error> {
+        self.0
+            .draw_with_alpha(renderer, frame, location, scale, damage, alpha, log)
+    }
+
     fn damage_since(
         &self,
         scale: Scale<f64>,
@@ -1067,9 +1226,17 @@ where
         self.0.opaque_regions(scale)
     }
 
+    fn sampled_region(&self, scale: Scale<f64>) -> Option<Rectangle<i32, Physical>> {
+        self.0.sampled_region(scale)
+    }
+
     fn underlying_storage(&self, renderer: &R) -> Option<UnderlyingStorage<'_, R>> {
         self.0.underlying_storage(renderer)
     }
+
+    fn alpha(&self) -> f32 {
+        self.0.alpha()
+    }
 }
 
 #[cfg(all(test, feature = "renderer_gl"))]
@@ -262,6 +262,7 @@ where
 pub struct WaylandSurfaceRenderElement {
     id: Id,
     location: Point<f64, Physical>,
+    alpha: f32,
     surface: wl_surface::WlSurface,
 }
 
@@ -273,10 +274,19 @@ impl WaylandSurfaceRenderElement {
         Self {
             id,
             location,
+            alpha: 1.0,
             surface: surface.clone(),
         }
     }
 
+    /// Draw the surface with the provided alpha value
+    ///
+    /// `alpha` is clamped to `0.0..=1.0`, values smaller than `1.0` draw the surface translucent.
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha.clamp(0.0, 1.0);
+        self
+    }
+
     fn size(&self, scale: impl Into<Scale<f64>>) -> Size<i32, Physical> {
         compositor::with_states(&self.surface, |states| {
             let data = states.data_map.get::<RendererSurfaceStateUserData>();
@@ -412,6 +422,11 @@ where
     }
 
     fn underlying_storage(&self, _renderer: &R) -> Option<UnderlyingStorage<'_, R>> {
+        // the buffer alone does not represent a translucent surface
+        if self.alpha < 1.0 {
+            return None;
+        }
+
         compositor::with_states(&self.surface, |states| {
             let data = states.data_map.get::<RendererSurfaceStateUserData>();
             data.and_then(|d| d.borrow().wl_buffer().cloned())
@@ -419,6 +434,10 @@ where
         })
     }
 
+    fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
     fn draw(
         &self,
         renderer: &mut R,
@@ -427,6 +446,19 @@ where
         scale: Scale<f64>,
         damage: &[Rectangle<i32, Physical>],
         log: &slog::Logger,
+    ) -> Result<(), R::Error> {
+        self.draw_with_alpha(renderer, frame, location, scale, damage, self.alpha, log)
+    }
+
+    fn draw_with_alpha(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        log: &slog::Logger,
     ) -> Result<(), R::Error> {
         crate::backend::renderer::utils::import_surface_tree(renderer, &self.surface, log)?;
 
@@ -452,7 +484,7 @@ where
                             dst,
                             damage,
                             data.buffer_transform,
-                            1.0f32,
+                            alpha,
                         )?;
                     }
                 }
@@ -462,3 +494,103 @@ where
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backend::renderer::{
+            damage::DamageTrackedRenderer,
+            element::render_elements,
+            test::{DummyElement, DummyRenderer, Op},
+            utils::{RendererSurfaceState, SurfaceView},
+        },
+        delegate_compositor,
+        wayland::compositor::{create_test_surface, CompositorHandler, CompositorState},
+    };
+    use std::{cell::RefCell, os::unix::net::UnixStream, sync::Arc};
+    use wayland_server::{
+        backend::{ClientData, ClientId, DisconnectReason},
+        Display,
+    };
+
+    struct State;
+
+    impl CompositorHandler for State {
+        fn compositor_state(&mut self) -> &mut CompositorState {
+            unreachable!()
+        }
+
+        fn commit(&mut self, _surface: &wl_surface::WlSurface) {}
+    }
+
+    delegate_compositor!(State);
+
+    struct TestClientData;
+
+    impl ClientData for TestClientData {
+        fn initialized(&self, _client_id: ClientId) {}
+        fn disconnected(&self, _client_id: ClientId, _reason: DisconnectReason) {}
+    }
+
+    render_elements! {
+        TestElement<=DummyRenderer>;
+        Surface=WaylandSurfaceRenderElement,
+        Dummy=DummyElement,
+    }
+
+    // renders an opaque surface above an element and returns if the element got drawn
+    fn element_below_drawn(surface: &wl_surface::WlSurface, alpha: f32) -> bool {
+        let mut renderer = DummyRenderer::default();
+        let mut damage_tracked_renderer = DamageTrackedRenderer::new((100, 100), 1.0, Transform::Normal);
+        let elements = [
+            TestElement::Surface(
+                WaylandSurfaceRenderElement::from_surface(surface, (0.0, 0.0).into()).with_alpha(alpha),
+            ),
+            TestElement::Dummy(DummyElement::new(Rectangle::from_loc_and_size(
+                (10, 10),
+                (20, 20),
+            ))),
+        ];
+        damage_tracked_renderer
+            .render_output(&mut renderer, 0, &elements, [1.0, 1.0, 1.0, 1.0], None)
+            .unwrap();
+        let ops = renderer.ops.borrow();
+        ops.iter()
+            .any(|op| matches!(op, Op::Clear(color, _) if *color == [0.0, 0.0, 0.0, 1.0]))
+    }
+
+    #[test]
+    fn translucent_surface_is_not_opaque() {
+        let display = Display::<State>::new().unwrap();
+        let dh = display.handle();
+        let (_stream, server_stream) = UnixStream::pair().unwrap();
+        let client = dh.insert_client(server_stream, Arc::new(TestClientData)).unwrap();
+        let surface = create_test_surface::<State>(&dh, &client);
+        compositor::with_states(&surface, |states| {
+            let mut state = RendererSurfaceState::default();
+            state.buffer_dimensions = Some((50, 50).into());
+            state.buffer_scale = 1;
+            state.client_scale = 1;
+            state.surface_view = Some(SurfaceView {
+                src: Rectangle::from_loc_and_size((0.0, 0.0), (50.0, 50.0)),
+                dst: (50, 50).into(),
+                offset: (0, 0).into(),
+            });
+            state.opaque_regions = vec![Rectangle::from_loc_and_size((0, 0), (50, 50))];
+            states.data_map.insert_if_missing(|| RefCell::new(state));
+        });
+
+        let element = WaylandSurfaceRenderElement::from_surface(&surface, (0.0, 0.0).into());
+        assert_eq!(
+            RenderElement::<DummyRenderer>::opaque_regions(&element, Scale::from(1.0)),
+            vec![Rectangle::from_loc_and_size((0, 0), (50, 50))]
+        );
+        let element = element.with_alpha(0.5);
+        assert_eq!(RenderElement::<DummyRenderer>::alpha(&element), 0.5);
+
+        // the opaque surface hides the element below, the translucent one does not
+        assert!(!element_below_drawn(&surface, 1.0));
+        assert!(element_below_drawn(&surface, 0.5));
+    }
+}
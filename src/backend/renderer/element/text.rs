@@ -0,0 +1,661 @@
+//! Element to render text
+//!
+//! # Why use this implementation
+//!
+//! [`TextBuffer`] holds a single or multi-line string together with the [`Font`],
+//! size and color it should be rendered with. The text is rasterized lazily for the scale
+//! it is drawn at, so it stays crisp on outputs with different scales.
+//! Rasterized glyphs are cached per [`Font`] and renderer inside [`TextureRenderBuffer`]s,
+//! so texts sharing a font also share their glyphs. The glyphs do not depend on the color of the text,
+//! it is applied when drawing through a [`ColorTransform`] of the [`Frame`](crate::backend::renderer::Frame).
+//!
+//! Changing the text, size or color of a [`TextBuffer`] damages the whole element.
+//!
+//! # Why **not** to use this implementation
+//!
+//! The layout is intentionally simple. Text is laid out left-to-right with kerning and explicit
+//! line breaks, there is no support for shaping, bidirectional text or font fallback.
+//!
+//! Renderers not supporting [`Frame::set_color_transform`](crate::backend::renderer::Frame::set_color_transform)
+//! draw all text white.
+//!
+//! # How to use it
+//!
+//! ```no_run
+//! # use smithay::backend::renderer::{
+//! #     damage::DamageTrackedRenderer,
+//! #     element::text::{Font, TextBuffer, TextRenderElement},
+//! #     gles2::Gles2Renderer,
+//! # };
+//! # use smithay::utils::{Point, Transform};
+//! # let mut renderer: Gles2Renderer = unimplemented!();
+//! # let log = slog::Logger::root(slog::Discard, slog::o!());
+//! let font = Font::from_bytes(std::fs::read("/usr/share/fonts/TTF/DejaVuSans.ttf").unwrap())
+//!     .expect("invalid font");
+//! let mut text_buffer = TextBuffer::new(&font, "Hello world", 14.0, [1.0, 1.0, 1.0, 1.0]);
+//!
+//! let mut damage_tracked_renderer = DamageTrackedRenderer::new((800, 600), 2.0, Transform::Normal);
+//!
+//! loop {
+//!     // Create a render element from the buffer
+//!     let render_element = TextRenderElement::from_text_buffer(Point::from((20, 20)), &text_buffer);
+//!
+//!     // Render the element(s)
+//!     damage_tracked_renderer
+//!         .render_output(&mut renderer, 0, &[&render_element], [0.8, 0.8, 0.9, 1.0], log.clone())
+//!         .expect("failed to render output");
+//!
+//!     // Updating the text damages the element
+//!     text_buffer.set_text("Goodbye world");
+//! }
+//! ```
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    backend::renderer::{color::ColorTransform, utils::CommitCounter, Frame, ImportMem, Renderer},
+    utils::{Buffer, Logical, Physical, Point, Rectangle, Scale, Size, Transform},
+};
+
+use super::{
+    texture::{TextureRenderBuffer, TextureRenderElement},
+    Id, RenderElement,
+};
+
+/// Errors thrown when loading a [`Font`]
+#[derive(Debug, thiserror::Error)]
+pub enum TextError {
+    /// The font data could not be parsed
+    #[error("Failed to parse font: {0}")]
+    InvalidFont(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    index: u16,
+    // pixel size in 1/64th of a pixel
+    px: u32,
+}
+
+type GlyphCache<T> = HashMap<GlyphKey, Option<TextureRenderBuffer<T>>>;
+
+struct FontInner {
+    font: fontdue::Font,
+    glyphs: HashMap<(TypeId, usize), Box<dyn Any>>,
+}
+
+impl fmt::Debug for FontInner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FontInner")
+            .field("renderers", &self.glyphs.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A font used to render [`TextBuffer`]s
+///
+/// Cloning a [`Font`] is cheap and shares the glyph cache.
+#[derive(Debug, Clone)]
+pub struct Font {
+    inner: Arc<Mutex<FontInner>>,
+}
+
+impl Font {
+    /// Load a font from the contents of a TrueType or OpenType file
+    pub fn from_bytes(data: impl AsRef<[u8]>) -> Result<Font, TextError> {
+        let font = fontdue::Font::from_bytes(data.as_ref(), fontdue::FontSettings::default())
+            .map_err(TextError::InvalidFont)?;
+        Ok(Font {
+            inner: Arc::new(Mutex::new(FontInner {
+                font,
+                glyphs: HashMap::new(),
+            })),
+        })
+    }
+
+    /// Drop all cached glyphs
+    ///
+    /// Glyphs are never evicted automatically, rendering many different sizes
+    /// may make this necessary from time to time.
+    pub fn clear_cache(&self) {
+        self.inner.lock().unwrap().glyphs.clear();
+    }
+}
+
+impl PartialEq for Font {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PositionedGlyph {
+    index: u16,
+    geometry: Rectangle<i32, Physical>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct TextLayout {
+    size: Size<i32, Physical>,
+    glyphs: Vec<PositionedGlyph>,
+}
+
+fn pixel_size(size: f32, scale: f64) -> f32 {
+    // quantize to avoid caching glyphs for marginally different sizes
+    ((size as f64 * scale * 64.0).round() / 64.0) as f32
+}
+
+// replaces the white of the rasterized glyphs with the color of the text
+fn tint(color: [f32; 4]) -> ColorTransform {
+    ColorTransform {
+        matrix: [[0.0; 3]; 3],
+        offset: [color[0], color[1], color[2]],
+    }
+}
+
+fn layout_text(font: &fontdue::Font, text: &str, px: f32) -> TextLayout {
+    let line_metrics = match font.horizontal_line_metrics(px) {
+        Some(metrics) => metrics,
+        None => return TextLayout::default(),
+    };
+
+    let mut glyphs = Vec::new();
+    let mut width = 0.0f32;
+    let mut lines = 0;
+    for (line_index, line) in text.split('\n').enumerate() {
+        let baseline = line_metrics.ascent + line_index as f32 * line_metrics.new_line_size;
+        let mut pen = 0.0f32;
+        let mut previous = None;
+        for c in line.chars() {
+            let index = font.lookup_glyph_index(c);
+            if let Some(previous) = previous {
+                pen += font.horizontal_kern_indexed(previous, index, px).unwrap_or(0.0);
+            }
+            let metrics = font.metrics_indexed(index, px);
+            if metrics.width > 0 && metrics.height > 0 {
+                let x = pen.round() as i32 + metrics.xmin;
+                let y = (baseline - metrics.height as f32 - metrics.ymin as f32).round() as i32;
+                glyphs.push(PositionedGlyph {
+                    index,
+                    geometry: Rectangle::from_loc_and_size(
+                        (x, y),
+                        (metrics.width as i32, metrics.height as i32),
+                    ),
+                });
+            }
+            pen += metrics.advance_width;
+            previous = Some(index);
+        }
+        width = width.max(pen);
+        lines += 1;
+    }
+
+    TextLayout {
+        size: Size::from((
+            width.ceil() as i32,
+            (lines as f32 * line_metrics.new_line_size).ceil() as i32,
+        )),
+        glyphs,
+    }
+}
+
+#[derive(Debug)]
+struct TextBufferInner {
+    font: Font,
+    text: String,
+    size: f32,
+    color: [f32; 4],
+    commit: CommitCounter,
+    // last layout and the pixel size it was created for
+    layout: Option<(f32, TextLayout)>,
+}
+
+impl TextBufferInner {
+    fn layout(&mut self, scale: f64) -> TextLayout {
+        let px = pixel_size(self.size, scale);
+        match self.layout.as_ref() {
+            Some((layout_px, layout)) if *layout_px == px => layout.clone(),
+            _ => {
+                let layout = layout_text(&self.font.inner.lock().unwrap().font, &self.text, px);
+                self.layout = Some((px, layout.clone()));
+                layout
+            }
+        }
+    }
+
+    fn changed(&mut self) {
+        self.layout = None;
+        self.commit.increment();
+    }
+}
+
+/// A buffer holding a text to be rendered with a [`TextRenderElement`]
+///
+/// Cloning a [`TextBuffer`] is cheap, all clones refer to the same text.
+#[derive(Debug, Clone)]
+pub struct TextBuffer {
+    id: Id,
+    inner: Arc<Mutex<TextBufferInner>>,
+}
+
+impl TextBuffer {
+    /// Create a new [`TextBuffer`]
+    ///
+    /// - `size` is the pixel size of the font in logical coordinates
+    /// - `color` is a non-premultiplied rgba color
+    ///
+    /// Lines are separated by `\n`.
+    pub fn new(font: &Font, text: impl Into<String>, size: f32, color: [f32; 4]) -> Self {
+        TextBuffer {
+            id: Id::new(),
+            inner: Arc::new(Mutex::new(TextBufferInner {
+                font: font.clone(),
+                text: text.into(),
+                size,
+                color,
+                commit: CommitCounter::default(),
+                layout: None,
+            })),
+        }
+    }
+
+    /// The current text
+    pub fn text(&self) -> String {
+        self.inner.lock().unwrap().text.clone()
+    }
+
+    /// Update the text
+    pub fn set_text(&mut self, text: impl AsRef<str>) {
+        let mut guard = self.inner.lock().unwrap();
+        if guard.text != text.as_ref() {
+            guard.text = text.as_ref().to_string();
+            guard.changed();
+        }
+    }
+
+    /// Update the font
+    pub fn set_font(&mut self, font: &Font) {
+        let mut guard = self.inner.lock().unwrap();
+        if guard.font != *font {
+            guard.font = font.clone();
+            guard.changed();
+        }
+    }
+
+    /// Update the pixel size of the font in logical coordinates
+    pub fn set_size(&mut self, size: f32) {
+        let mut guard = self.inner.lock().unwrap();
+        if guard.size != size {
+            guard.size = size;
+            guard.changed();
+        }
+    }
+
+    /// Update the non-premultiplied rgba color of the text
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        let mut guard = self.inner.lock().unwrap();
+        if guard.color != color {
+            guard.color = color;
+            guard.changed();
+        }
+    }
+
+    /// Size of the laid out text in logical coordinates
+    pub fn size(&self) -> Size<i32, Logical> {
+        let size = self.inner.lock().unwrap().layout(1.0).size;
+        Size::from((size.w, size.h))
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.inner.lock().unwrap().commit
+    }
+}
+
+/// A render element for a [`TextBuffer`]
+#[derive(Debug)]
+pub struct TextRenderElement {
+    location: Point<i32, Physical>,
+    buffer: TextBuffer,
+}
+
+impl TextRenderElement {
+    /// Create a new [`TextRenderElement`] for a [`TextBuffer`]
+    ///
+    /// `location` is the top-left corner of the first line.
+    pub fn from_text_buffer(location: impl Into<Point<i32, Physical>>, buffer: &TextBuffer) -> Self {
+        TextRenderElement {
+            location: location.into(),
+            buffer: buffer.clone(),
+        }
+    }
+}
+
+impl<R> RenderElement<R> for TextRenderElement
+where
+    R: Renderer + ImportMem,
+    <R as Renderer>::TextureId: Clone + 'static,
+{
+    fn id(&self) -> &Id {
+        &self.buffer.id
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.buffer.current_commit()
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        let size = self.buffer.size();
+        Rectangle::from_loc_and_size((0.0, 0.0), (size.w as f64, size.h as f64))
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        let size = self.buffer.inner.lock().unwrap().layout(scale.x).size;
+        Rectangle::from_loc_and_size(self.location, size)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        log: &slog::Logger,
+    ) -> Result<(), <R as Renderer>::Error> {
+        self.draw_with_alpha(renderer, frame, location, scale, damage, 1.0, log)
+    }
+
+    fn draw_with_alpha(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        log: &slog::Logger,
+    ) -> Result<(), <R as Renderer>::Error> {
+        let mut guard = self.buffer.inner.lock().unwrap();
+        let layout = guard.layout(scale.x);
+        let px = pixel_size(guard.size, scale.x);
+        let color = guard.color.map(|c| c.clamp(0.0, 1.0));
+
+        let mut font = guard.font.inner.lock().unwrap();
+        let font = &mut *font;
+        let cache = font
+            .glyphs
+            .entry((TypeId::of::<<R as Renderer>::TextureId>(), renderer.id()))
+            .or_insert_with(|| Box::new(GlyphCache::<<R as Renderer>::TextureId>::new()))
+            .downcast_mut::<GlyphCache<<R as Renderer>::TextureId>>()
+            .unwrap();
+
+        let previous = frame.color_transform();
+        let transform = match previous {
+            Some(previous) => tint(color).then(previous),
+            None => tint(color),
+        };
+        frame.set_color_transform(Some(transform))?;
+
+        let res = (|| -> Result<(), <R as Renderer>::Error> {
+            for glyph in &layout.glyphs {
+                let glyph_damage = damage
+                    .iter()
+                    .filter_map(|rect| rect.intersection(glyph.geometry))
+                    .map(|mut rect| {
+                        rect.loc -= glyph.geometry.loc;
+                        rect
+                    })
+                    .collect::<Vec<_>>();
+                if glyph_damage.is_empty() {
+                    continue;
+                }
+
+                let key = GlyphKey {
+                    index: glyph.index,
+                    px: (px * 64.0) as u32,
+                };
+                if !cache.contains_key(&key) {
+                    let (metrics, coverage) = font.font.rasterize_indexed(glyph.index, px);
+                    let buffer = if metrics.width > 0 && metrics.height > 0 {
+                        // premultiplied white, the color is applied by the color transform
+                        let data = coverage.iter().flat_map(|c| [*c; 4]).collect::<Vec<_>>();
+                        Some(TextureRenderBuffer::from_memory(
+                            renderer,
+                            &data,
+                            (metrics.width as i32, metrics.height as i32),
+                            false,
+                            1,
+                            Transform::Normal,
+                            None,
+                        )?)
+                    } else {
+                        None
+                    };
+                    cache.insert(key, buffer);
+                }
+
+                if let Some(buffer) = cache.get(&key).and_then(Option::as_ref) {
+                    // glyphs are already rasterized for the output scale
+                    let glyph_size = glyph.geometry.size;
+                    TextureRenderElement::from_texture_render_buffer(
+                        (location + glyph.geometry.loc).to_f64(),
+                        buffer,
+                        None,
+                        Some(Size::from((glyph_size.w, glyph_size.h))),
+                    )
+                    .draw_with_alpha(
+                        renderer,
+                        frame,
+                        location + glyph.geometry.loc,
+                        Scale::from(1.0),
+                        &glyph_damage,
+                        alpha * color[3],
+                        log,
+                    )?;
+                }
+            }
+            Ok(())
+        })();
+
+        frame.set_color_transform(previous)?;
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::renderer::test::{DummyRenderer, Op};
+
+    fn words(data: &mut Vec<u8>, words: &[u16]) {
+        for word in words {
+            data.extend(word.to_be_bytes());
+        }
+    }
+
+    // minimal TrueType font mapping 'A' and 'B' to squares of 500 and 800 units
+    fn test_font() -> Font {
+        fn square(size: u16) -> Vec<u8> {
+            let mut glyph = Vec::new();
+            // one contour and its bounding box
+            words(&mut glyph, &[1, 0, 0, size, size]);
+            // last point of the contour, no instructions
+            words(&mut glyph, &[3, 0]);
+            // on-curve points with 16 bit coordinate deltas
+            glyph.extend([1u8; 4]);
+            words(&mut glyph, &[0, 0, size, 0]);
+            words(&mut glyph, &[0, size, 0, size.wrapping_neg()]);
+            glyph
+        }
+
+        let mut cmap = Vec::new();
+        words(&mut cmap, &[0, 1, 3, 1, 0, 12]);
+        // format 4: 'A'..='B' mapped to the glyphs 1 and 2, followed by the final segment
+        words(&mut cmap, &[4, 32, 0, 4, 4, 1, 0]);
+        words(
+            &mut cmap,
+            &[0x42, 0xffff, 0, 0x41, 0xffff, 1u16.wrapping_sub(0x41), 1, 0, 0],
+        );
+
+        let mut glyf = square(500);
+        glyf.extend(square(800));
+
+        let mut head = Vec::new();
+        words(&mut head, &[1, 0, 1, 0, 0, 0, 0x5f0f, 0x3cf5, 0, 1000]);
+        words(&mut head, &[0; 8]);
+        words(&mut head, &[0, 0, 1000, 1000, 0, 8, 2, 0, 0]);
+
+        let mut hhea = Vec::new();
+        words(
+            &mut hhea,
+            &[1, 0, 800, 200u16.wrapping_neg(), 0, 1000, 0, 0, 1000, 1, 0, 0],
+        );
+        words(&mut hhea, &[0, 0, 0, 0, 0, 3]);
+
+        let mut hmtx = Vec::new();
+        words(&mut hmtx, &[500, 0, 600, 0, 1000, 0]);
+
+        let mut loca = Vec::new();
+        words(&mut loca, &[0, 0, 17, 34]);
+
+        let mut maxp = Vec::new();
+        words(&mut maxp, &[0, 0x5000, 3]);
+
+        let tables: [(&[u8; 4], Vec<u8>); 7] = [
+            (b"cmap", cmap),
+            (b"glyf", glyf),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"loca", loca),
+            (b"maxp", maxp),
+        ];
+
+        let mut data = Vec::new();
+        words(&mut data, &[1, 0, tables.len() as u16, 64, 2, 48]);
+        let mut offset = 12 + 16 * tables.len();
+        for (tag, table) in &tables {
+            data.extend(*tag);
+            let padded = (table.len() + 3) & !3;
+            let checksum = table
+                .chunks(4)
+                .map(|chunk| {
+                    let mut word = [0; 4];
+                    word[..chunk.len()].copy_from_slice(chunk);
+                    u32::from_be_bytes(word)
+                })
+                .fold(0u32, u32::wrapping_add);
+            data.extend(checksum.to_be_bytes());
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((table.len() as u32).to_be_bytes());
+            offset += padded;
+        }
+        for (_, table) in &tables {
+            data.extend(table);
+            data.resize((data.len() + 3) & !3, 0);
+        }
+
+        Font::from_bytes(data).unwrap()
+    }
+
+    fn draw(renderer: &mut DummyRenderer, element: &TextRenderElement, scale: f64) {
+        let log = slog::Logger::root(slog::Discard, slog::o!());
+        let damage = [Rectangle::from_loc_and_size((0, 0), (1000, 1000))];
+        renderer
+            .render((1000, 1000).into(), Transform::Normal, |renderer, frame| {
+                element.draw(renderer, frame, (0, 0).into(), Scale::from(scale), &damage, &log)
+            })
+            .unwrap()
+            .unwrap();
+    }
+
+    #[test]
+    fn layout_lines() {
+        let font = test_font();
+        let layout = layout_text(&font.inner.lock().unwrap().font, "AB\nA", 10.0);
+
+        // advances of 600 and 1000 units, lines of 1000 units
+        assert_eq!(layout.size, Size::from((16, 20)));
+        assert_eq!(layout.glyphs.len(), 3);
+        assert_eq!(layout.glyphs[0].index, layout.glyphs[2].index);
+        assert_eq!(
+            layout.glyphs[1].geometry.loc.x - layout.glyphs[0].geometry.loc.x,
+            6
+        );
+        assert_eq!(
+            layout.glyphs[2].geometry.loc,
+            layout.glyphs[0].geometry.loc + Point::from((0, 10))
+        );
+    }
+
+    #[test]
+    fn glyphs_are_cached_per_size() {
+        let font = test_font();
+        let mut buffer = TextBuffer::new(&font, "ABA", 10.0, [1.0, 0.0, 0.0, 0.5]);
+        let element = TextRenderElement::from_text_buffer((0, 0), &buffer);
+        let mut renderer = DummyRenderer::default();
+        let imports = |renderer: &DummyRenderer| {
+            renderer
+                .ops
+                .borrow()
+                .iter()
+                .filter(|op| matches!(op, Op::Import(_)))
+                .count()
+        };
+
+        draw(&mut renderer, &element, 1.0);
+        assert_eq!(imports(&renderer), 2);
+        // the alpha of the color is applied when drawing
+        assert!(renderer
+            .ops
+            .borrow()
+            .iter()
+            .filter_map(|op| match op {
+                Op::RenderTexture { alpha, .. } => Some(*alpha),
+                _ => None,
+            })
+            .eq([0.5; 3]));
+
+        // changing the color does not rasterize the glyphs again
+        buffer.set_color([0.0, 0.0, 1.0, 1.0]);
+        draw(&mut renderer, &element, 1.0);
+        assert_eq!(imports(&renderer), 2);
+
+        // other sizes do
+        draw(&mut renderer, &element, 2.0);
+        assert_eq!(imports(&renderer), 4);
+
+        font.clear_cache();
+        draw(&mut renderer, &element, 1.0);
+        assert_eq!(imports(&renderer), 6);
+    }
+
+    #[test]
+    fn tint_replaces_white() {
+        let tint = tint([0.2, 0.4, 0.6, 0.5]);
+        // premultiplied white with half coverage
+        let tinted = tint.apply([0.5, 0.5, 0.5, 0.5]);
+        let expected = [0.1, 0.2, 0.3, 0.5];
+        assert!(tinted.iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-6));
+    }
+
+    #[test]
+    fn changes_are_tracked() {
+        let font = test_font();
+        let mut buffer = TextBuffer::new(&font, "A", 10.0, [1.0; 4]);
+        let commit = buffer.current_commit();
+
+        buffer.set_text("A");
+        buffer.set_color([1.0; 4]);
+        assert_eq!(buffer.current_commit(), commit);
+
+        buffer.set_text("AB");
+        assert_ne!(buffer.current_commit(), commit);
+        assert_eq!(buffer.size(), Size::from((16, 10)));
+    }
+}
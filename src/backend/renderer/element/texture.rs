@@ -545,6 +545,7 @@ pub struct TextureRenderElement<T> {
     texture: T,
     scale: i32,
     transform: Transform,
+    alpha: f32,
     src: Option<Rectangle<f64, Logical>>,
     size: Option<Size<i32, Logical>>,
     opaque_regions: Option<Vec<Rectangle<i32, Logical>>>,
@@ -634,6 +635,7 @@ impl<T: Texture> TextureRenderElement<T> {
             texture,
             scale,
             transform,
+            alpha: 1.0,
             src,
             size,
             opaque_regions,
@@ -641,6 +643,14 @@ impl<T: Texture> TextureRenderElement<T> {
         }
     }
 
+    /// Draw the texture with the provided alpha value
+    ///
+    /// `alpha` is clamped to `0.0..=1.0`, values smaller than `1.0` draw the texture translucent.
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha.clamp(0.0, 1.0);
+        self
+    }
+
     /// Create a static [`TextureRenderElement`] from
     /// an existing texture
     #[allow(clippy::too_many_arguments)]
@@ -734,6 +744,10 @@ where
             .to_buffer(self.scale as f64, self.transform, &size.to_f64())
     }
 
+    fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
     fn draw(
         &self,
         renderer: &mut R,
@@ -742,6 +756,19 @@ where
         scale: Scale<f64>,
         damage: &[Rectangle<i32, Physical>],
         log: &slog::Logger,
+    ) -> Result<(), <R as Renderer>::Error> {
+        self.draw_with_alpha(renderer, frame, location, scale, damage, self.alpha, log)
+    }
+
+    fn draw_with_alpha(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        log: &slog::Logger,
     ) -> Result<(), <R as Renderer>::Error> {
         if renderer.id() != self.renderer_id {
             warn!(log, "trying to render texture from different renderer");
@@ -762,7 +789,7 @@ where
             .unwrap_or_else(|| Rectangle::from_loc_and_size(Point::default(), texture_size).to_f64());
 
         let dst = Rectangle::from_loc_and_size(location, self.physical_size(scale));
-        frame.render_texture_from_to(&self.texture, src, dst, damage, self.transform, 1.0)
+        frame.render_texture_from_to(&self.texture, src, dst, damage, self.transform, alpha)
     }
 
     fn damage_since(
@@ -813,6 +840,11 @@ where
     }
 
     fn underlying_storage(&self, _renderer: &R) -> Option<UnderlyingStorage<'_, R>> {
+        // the texture alone does not represent a translucent element
+        if self.alpha < 1.0 {
+            return None;
+        }
+
         Some(UnderlyingStorage::External(&self.texture))
     }
 }
@@ -0,0 +1,446 @@
+//! Utilities and helpers around the [`RenderElement`] trait.
+//!
+//! This module provides wrapping elements, that modify how an existing
+//! [`RenderElement`] is drawn without the need to know its concrete type.
+//! This makes them useful for implementing animations on arbitrary elements.
+//!
+//! - [`RescaleRenderElement`] - Scales an element relative to an origin
+//! - [`RelocateRenderElement`] - Moves an element by an offset
+//! - [`AlphaRenderElement`] - Draws an element with a reduced alpha value
+//!
+//! The wrappers can be freely nested, e.g. to fade out and shrink a window at the same time.
+
+use crate::{
+    backend::renderer::{utils::CommitCounter, Renderer},
+    utils::{Buffer, Physical, Point, Rectangle, Scale, Transform},
+};
+
+use super::{Id, RenderElement, UnderlyingStorage};
+
+/// A element that scales another element relative to an origin
+///
+/// The origin is not moved by the scaling, so using the center of the element
+/// as the origin will scale the element "in place".
+#[derive(Debug)]
+pub struct RescaleRenderElement<E> {
+    element: E,
+    origin: Point<i32, Physical>,
+    scale: Scale<f64>,
+}
+
+impl<E> RescaleRenderElement<E> {
+    /// Create a new [`RescaleRenderElement`] scaling the provided element by `scale` around `origin`
+    pub fn from_element(element: E, origin: Point<i32, Physical>, scale: impl Into<Scale<f64>>) -> Self {
+        RescaleRenderElement {
+            element,
+            origin,
+            scale: scale.into(),
+        }
+    }
+
+    /// Access the wrapped element
+    pub fn element(&self) -> &E {
+        &self.element
+    }
+}
+
+impl<R, E> RenderElement<R> for RescaleRenderElement<E>
+where
+    R: Renderer,
+    E: RenderElement<R>,
+{
+    fn id(&self) -> &Id {
+        self.element.id()
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.element.current_commit()
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        self.element.src()
+    }
+
+    fn transform(&self) -> Transform {
+        self.element.transform()
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        let loc = self.element.geometry(scale).loc;
+        let offset = (loc - self.origin).to_f64().upscale(self.scale).to_i32_round();
+        let size = self.element.geometry(scale * self.scale).size;
+        Rectangle::from_loc_and_size(self.origin + offset, size)
+    }
+
+    fn damage_since(
+        &self,
+        scale: Scale<f64>,
+        commit: Option<CommitCounter>,
+    ) -> Vec<Rectangle<i32, Physical>> {
+        self.element.damage_since(scale * self.scale, commit)
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> Vec<Rectangle<i32, Physical>> {
+        self.element.opaque_regions(scale * self.scale)
+    }
+
+    fn sampled_region(&self, scale: Scale<f64>) -> Option<Rectangle<i32, Physical>> {
+        self.element.sampled_region(scale * self.scale)
+    }
+
+    fn alpha(&self) -> f32 {
+        self.element.alpha()
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        log: &slog::Logger,
+    ) -> Result<(), R::Error> {
+        self.element
+            .draw(renderer, frame, location, scale * self.scale, damage, log)
+    }
+
+    fn draw_with_alpha(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        log: &slog::Logger,
+    ) -> Result<(), R::Error> {
+        self.element
+            .draw_with_alpha(renderer, frame, location, scale * self.scale, damage, alpha, log)
+    }
+}
+
+/// A element that moves another element by an offset
+#[derive(Debug)]
+pub struct RelocateRenderElement<E> {
+    element: E,
+    offset: Point<i32, Physical>,
+}
+
+impl<E> RelocateRenderElement<E> {
+    /// Create a new [`RelocateRenderElement`] moving the provided element by `offset`
+    pub fn from_element(element: E, offset: impl Into<Point<i32, Physical>>) -> Self {
+        RelocateRenderElement {
+            element,
+            offset: offset.into(),
+        }
+    }
+
+    /// Access the wrapped element
+    pub fn element(&self) -> &E {
+        &self.element
+    }
+}
+
+impl<R, E> RenderElement<R> for RelocateRenderElement<E>
+where
+    R: Renderer,
+    E: RenderElement<R>,
+{
+    fn id(&self) -> &Id {
+        self.element.id()
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.element.current_commit()
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        self.element.src()
+    }
+
+    fn transform(&self) -> Transform {
+        self.element.transform()
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        let mut geometry = self.element.geometry(scale);
+        geometry.loc += self.offset;
+        geometry
+    }
+
+    fn damage_since(
+        &self,
+        scale: Scale<f64>,
+        commit: Option<CommitCounter>,
+    ) -> Vec<Rectangle<i32, Physical>> {
+        self.element.damage_since(scale, commit)
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> Vec<Rectangle<i32, Physical>> {
+        self.element.opaque_regions(scale)
+    }
+
+    fn sampled_region(&self, scale: Scale<f64>) -> Option<Rectangle<i32, Physical>> {
+        self.element.sampled_region(scale)
+    }
+
+    fn underlying_storage(&self, renderer: &R) -> Option<UnderlyingStorage<'_, R>> {
+        self.element.underlying_storage(renderer)
+    }
+
+    fn alpha(&self) -> f32 {
+        self.element.alpha()
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        log: &slog::Logger,
+    ) -> Result<(), R::Error> {
+        self.element.draw(renderer, frame, location, scale, damage, log)
+    }
+
+    fn draw_with_alpha(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        log: &slog::Logger,
+    ) -> Result<(), R::Error> {
+        self.element
+            .draw_with_alpha(renderer, frame, location, scale, damage, alpha, log)
+    }
+}
+
+/// A element that draws another element with a reduced alpha value
+///
+/// The alpha value is passed to [`RenderElement::draw_with_alpha`] of the wrapped element,
+/// elements not overriding it are drawn fully opaque.
+#[derive(Debug)]
+pub struct AlphaRenderElement<E> {
+    element: E,
+    alpha: f32,
+}
+
+impl<E> AlphaRenderElement<E> {
+    /// Create a new [`AlphaRenderElement`] multiplying the alpha of the provided element with `alpha`
+    ///
+    /// The value is clamped to `0.0..=1.0`.
+    pub fn from_element(element: E, alpha: f32) -> Self {
+        AlphaRenderElement {
+            element,
+            alpha: alpha.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Access the wrapped element
+    pub fn element(&self) -> &E {
+        &self.element
+    }
+}
+
+impl<R, E> RenderElement<R> for AlphaRenderElement<E>
+where
+    R: Renderer,
+    E: RenderElement<R>,
+{
+    fn id(&self) -> &Id {
+        self.element.id()
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.element.current_commit()
+    }
+
+    fn location(&self, scale: Scale<f64>) -> Point<i32, Physical> {
+        self.element.location(scale)
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        self.element.src()
+    }
+
+    fn transform(&self) -> Transform {
+        self.element.transform()
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.element.geometry(scale)
+    }
+
+    fn damage_since(
+        &self,
+        scale: Scale<f64>,
+        commit: Option<CommitCounter>,
+    ) -> Vec<Rectangle<i32, Physical>> {
+        self.element.damage_since(scale, commit)
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> Vec<Rectangle<i32, Physical>> {
+        // content below a translucent element stays visible
+        if self.alpha < 1.0 {
+            return Vec::new();
+        }
+
+        self.element.opaque_regions(scale)
+    }
+
+    fn sampled_region(&self, scale: Scale<f64>) -> Option<Rectangle<i32, Physical>> {
+        self.element.sampled_region(scale)
+    }
+
+    fn alpha(&self) -> f32 {
+        self.element.alpha() * self.alpha
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        log: &slog::Logger,
+    ) -> Result<(), R::Error> {
+        let alpha = RenderElement::<R>::alpha(self);
+        self.element
+            .draw_with_alpha(renderer, frame, location, scale, damage, alpha, log)
+    }
+
+    fn draw_with_alpha(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        log: &slog::Logger,
+    ) -> Result<(), R::Error> {
+        self.element
+            .draw_with_alpha(renderer, frame, location, scale, damage, alpha, log)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::renderer::test::{DummyElement, DummyRenderer, Op};
+
+    fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Physical> {
+        Rectangle::from_loc_and_size((x, y), (w, h))
+    }
+
+    fn draw<E: RenderElement<DummyRenderer>>(element: &E) -> Vec<Op> {
+        let mut renderer = DummyRenderer::default();
+        let location = element.geometry(Scale::from(1.0)).loc;
+        renderer
+            .render((500, 500).into(), Transform::Normal, |renderer, frame| {
+                element.draw(
+                    renderer,
+                    frame,
+                    location,
+                    Scale::from(1.0),
+                    &[],
+                    &slog::Logger::root(slog::Discard, slog::o!()),
+                )
+            })
+            .unwrap()
+            .unwrap();
+        let ops = renderer.ops.borrow().clone();
+        ops
+    }
+
+    #[test]
+    fn rescale_around_origin() {
+        let mut element = DummyElement::new(Rectangle::from_loc_and_size((150, 150), (50, 50)));
+        element.damage = vec![Rectangle::from_loc_and_size((0, 0), (10, 10))];
+        element.opaque_regions = vec![Rectangle::from_loc_and_size((10, 10), (20, 20))];
+        let element = RescaleRenderElement::from_element(element, (100, 100).into(), 2.0);
+        let scale = Scale::from(1.0);
+
+        assert_eq!(
+            RenderElement::<DummyRenderer>::geometry(&element, scale),
+            rect(200, 200, 100, 100)
+        );
+        assert_eq!(
+            RenderElement::<DummyRenderer>::damage_since(&element, scale, None),
+            vec![rect(0, 0, 20, 20)]
+        );
+        assert_eq!(
+            RenderElement::<DummyRenderer>::opaque_regions(&element, scale),
+            vec![rect(20, 20, 40, 40)]
+        );
+        assert_eq!(
+            draw(&element)[1..],
+            [Op::Clear([0.0, 0.0, 0.0, 1.0], vec![rect(200, 200, 100, 100)])]
+        );
+    }
+
+    #[test]
+    fn relocate_moves_geometry_only() {
+        let mut element = DummyElement::new(Rectangle::from_loc_and_size((10, 10), (50, 50)));
+        element.damage = vec![Rectangle::from_loc_and_size((0, 0), (10, 10))];
+        element.opaque_regions = vec![Rectangle::from_loc_and_size((0, 0), (50, 50))];
+        let element = RelocateRenderElement::from_element(element, (-10, 20));
+        let scale = Scale::from(2.0);
+
+        assert_eq!(
+            RenderElement::<DummyRenderer>::geometry(&element, scale),
+            rect(10, 40, 100, 100)
+        );
+        // damage and opaque regions are relative to the element
+        assert_eq!(
+            RenderElement::<DummyRenderer>::damage_since(&element, scale, None),
+            vec![rect(0, 0, 20, 20)]
+        );
+        assert_eq!(
+            RenderElement::<DummyRenderer>::opaque_regions(&element, scale),
+            vec![rect(0, 0, 100, 100)]
+        );
+    }
+
+    #[test]
+    fn alpha_is_multiplied() {
+        let mut element = DummyElement::new(Rectangle::from_loc_and_size((0, 0), (50, 50)));
+        element.alpha = 0.5;
+        element.opaque_regions = vec![Rectangle::from_loc_and_size((0, 0), (50, 50))];
+        let element = AlphaRenderElement::from_element(element, 0.5);
+
+        assert_eq!(RenderElement::<DummyRenderer>::alpha(&element), 0.25);
+        // content below stays visible
+        assert!(RenderElement::<DummyRenderer>::opaque_regions(&element, Scale::from(1.0)).is_empty());
+        assert_eq!(
+            draw(&element)[1..],
+            [Op::Clear([0.0, 0.0, 0.0, 0.25], vec![rect(0, 0, 50, 50)])]
+        );
+
+        // nested wrappers combine their values
+        let element = AlphaRenderElement::from_element(element, 0.5);
+        assert_eq!(
+            draw(&element)[1..],
+            [Op::Clear([0.0, 0.0, 0.0, 0.125], vec![rect(0, 0, 50, 50)])]
+        );
+    }
+
+    #[test]
+    fn alpha_is_clamped() {
+        let mut element = DummyElement::new(Rectangle::from_loc_and_size((0, 0), (50, 50)));
+        element.opaque_regions = vec![Rectangle::from_loc_and_size((0, 0), (50, 50))];
+        let element = AlphaRenderElement::from_element(element, 2.0);
+
+        assert_eq!(RenderElement::<DummyRenderer>::alpha(&element), 1.0);
+        assert_eq!(
+            RenderElement::<DummyRenderer>::opaque_regions(&element, Scale::from(1.0)),
+            vec![rect(0, 0, 50, 50)]
+        );
+    }
+}
@@ -0,0 +1,914 @@
+//! Render elements using custom shaders of the [`Gles2Renderer`]
+
+use std::{cell::RefCell, rc::Rc, sync::mpsc::Sender};
+
+use cgmath::{prelude::*, Matrix3, Vector2};
+
+use crate::{
+    backend::renderer::{
+        element::{texture::TextureRenderElement, Id, RenderElement, UnderlyingStorage},
+        utils::CommitCounter,
+        Frame, Renderer,
+    },
+    utils::{Buffer, Logical, Physical, Point, Rectangle, Scale, Size, Transform},
+};
+
+use super::{
+    ffi, set_additional_uniforms, shaders, CleanupResource, Gles2Error, Gles2Frame, Gles2PixelProgram,
+    Gles2Renderer, Gles2TexProgram, Gles2Texture, Uniform, UniformName, UniformType,
+};
+
+/// Render element drawing a rectangle using a custom pixel shader
+///
+/// See [`Gles2Renderer::compile_custom_pixel_shader`] for how to create the shader.
+///
+/// The element is considered damaged, whenever its area, opaque regions, alpha or uniforms are updated.
+#[derive(Debug, Clone)]
+pub struct PixelShaderElement {
+    shader: Gles2PixelProgram,
+    id: Id,
+    commit_counter: CommitCounter,
+    area: Rectangle<i32, Logical>,
+    opaque_regions: Vec<Rectangle<i32, Logical>>,
+    alpha: f32,
+    additional_uniforms: Vec<Uniform<'static>>,
+}
+
+impl PixelShaderElement {
+    /// Create a new [`PixelShaderElement`] covering the given area
+    ///
+    /// The opaque regions are relative to the area.
+    pub fn new(
+        shader: Gles2PixelProgram,
+        area: Rectangle<i32, Logical>,
+        opaque_regions: Option<Vec<Rectangle<i32, Logical>>>,
+        alpha: f32,
+        additional_uniforms: Vec<Uniform<'_>>,
+    ) -> Self {
+        PixelShaderElement {
+            shader,
+            id: Id::new(),
+            commit_counter: CommitCounter::default(),
+            area,
+            opaque_regions: opaque_regions.unwrap_or_default(),
+            alpha,
+            additional_uniforms: additional_uniforms.into_iter().map(|u| u.into_owned()).collect(),
+        }
+    }
+
+    /// Area covered by this element
+    pub fn area(&self) -> Rectangle<i32, Logical> {
+        self.area
+    }
+
+    /// Updates the area and opaque regions of this element
+    pub fn resize(
+        &mut self,
+        area: Rectangle<i32, Logical>,
+        opaque_regions: Option<Vec<Rectangle<i32, Logical>>>,
+    ) {
+        let opaque_regions = opaque_regions.unwrap_or_default();
+        if self.area != area || self.opaque_regions != opaque_regions {
+            self.area = area;
+            self.opaque_regions = opaque_regions;
+            self.commit_counter.increment();
+        }
+    }
+
+    /// Updates the alpha value passed to the shader
+    pub fn set_alpha(&mut self, alpha: f32) {
+        if self.alpha != alpha {
+            self.alpha = alpha;
+            self.commit_counter.increment();
+        }
+    }
+
+    /// Updates the additional uniforms passed to the shader
+    pub fn update_uniforms(&mut self, additional_uniforms: Vec<Uniform<'_>>) {
+        let additional_uniforms = additional_uniforms
+            .into_iter()
+            .map(|u| u.into_owned())
+            .collect::<Vec<_>>();
+        if self.additional_uniforms != additional_uniforms {
+            self.additional_uniforms = additional_uniforms;
+            self.commit_counter.increment();
+        }
+    }
+}
+
+impl RenderElement<Gles2Renderer> for PixelShaderElement {
+    fn id(&self) -> &Id {
+        &self.id
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.commit_counter
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        Rectangle::from_loc_and_size((0.0, 0.0), (self.area.size.w as f64, self.area.size.h as f64))
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.area.to_physical_precise_round(scale)
+    }
+
+    fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> Vec<Rectangle<i32, Physical>> {
+        if self.alpha < 1.0 {
+            return Vec::new();
+        }
+        self.opaque_regions
+            .iter()
+            .map(|region| region.to_physical_precise_round(scale))
+            .collect()
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Gles2Renderer,
+        frame: &mut Gles2Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        log: &slog::Logger,
+    ) -> Result<(), Gles2Error> {
+        self.draw_with_alpha(renderer, frame, location, scale, damage, self.alpha(), log)
+    }
+
+    fn draw_with_alpha(
+        &self,
+        _renderer: &mut Gles2Renderer,
+        frame: &mut Gles2Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        _log: &slog::Logger,
+    ) -> Result<(), Gles2Error> {
+        let dest = Rectangle::from_loc_and_size(location, self.geometry(scale).size);
+        frame.render_pixel_shader_to(&self.shader, dest, damage, alpha, &self.additional_uniforms)
+    }
+}
+
+/// Render element drawing a texture using a custom texture shader
+///
+/// Wraps a [`TextureRenderElement`] and renders it with the given shader
+/// (see [`Gles2Renderer::compile_custom_texture_shader`]) instead of the default one.
+///
+/// Damage is tracked by the wrapped element. If the uniforms change, the wrapped element
+/// needs to report new damage (e.g. by using a new [`Id`]) for the change to become visible.
+#[derive(Debug)]
+pub struct TextureShaderElement {
+    inner: TextureRenderElement<Gles2Texture>,
+    program: Gles2TexProgram,
+    additional_uniforms: Vec<Uniform<'static>>,
+}
+
+impl TextureShaderElement {
+    /// Create a new [`TextureShaderElement`] rendering `inner` with `program`
+    pub fn new(
+        inner: TextureRenderElement<Gles2Texture>,
+        program: Gles2TexProgram,
+        additional_uniforms: Vec<Uniform<'_>>,
+    ) -> Self {
+        TextureShaderElement {
+            inner,
+            program,
+            additional_uniforms: additional_uniforms.into_iter().map(|u| u.into_owned()).collect(),
+        }
+    }
+
+    /// Returns the wrapped texture element
+    pub fn inner(&self) -> &TextureRenderElement<Gles2Texture> {
+        &self.inner
+    }
+}
+
+impl RenderElement<Gles2Renderer> for TextureShaderElement {
+    fn id(&self) -> &Id {
+        RenderElement::<Gles2Renderer>::id(&self.inner)
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        RenderElement::<Gles2Renderer>::current_commit(&self.inner)
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        RenderElement::<Gles2Renderer>::src(&self.inner)
+    }
+
+    fn transform(&self) -> Transform {
+        RenderElement::<Gles2Renderer>::transform(&self.inner)
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        RenderElement::<Gles2Renderer>::geometry(&self.inner, scale)
+    }
+
+    fn damage_since(
+        &self,
+        scale: Scale<f64>,
+        commit: Option<CommitCounter>,
+    ) -> Vec<Rectangle<i32, Physical>> {
+        RenderElement::<Gles2Renderer>::damage_since(&self.inner, scale, commit)
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> Vec<Rectangle<i32, Physical>> {
+        RenderElement::<Gles2Renderer>::opaque_regions(&self.inner, scale)
+    }
+
+    fn alpha(&self) -> f32 {
+        RenderElement::<Gles2Renderer>::alpha(&self.inner)
+    }
+
+    fn underlying_storage(&self, _renderer: &Gles2Renderer) -> Option<UnderlyingStorage<'_, Gles2Renderer>> {
+        // the texture can not be scanned out directly, as it would skip the shader
+        None
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Gles2Renderer,
+        frame: &mut Gles2Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        log: &slog::Logger,
+    ) -> Result<(), Gles2Error> {
+        self.draw_with_alpha(renderer, frame, location, scale, damage, self.alpha(), log)
+    }
+
+    fn draw_with_alpha(
+        &self,
+        renderer: &mut Gles2Renderer,
+        frame: &mut Gles2Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        log: &slog::Logger,
+    ) -> Result<(), Gles2Error> {
+        frame.override_default_tex_program(self.program.clone(), self.additional_uniforms.clone());
+        let res = RenderElement::<Gles2Renderer>::draw_with_alpha(
+            &self.inner,
+            renderer,
+            frame,
+            location,
+            scale,
+            damage,
+            alpha,
+            log,
+        );
+        frame.clear_tex_program_override();
+        res
+    }
+}
+
+/// Maximum number of passes used by a [`BlurElement`]
+pub const MAX_BLUR_PASSES: usize = 8;
+
+/// Shaders used by [`BlurElement`]s
+///
+/// Compile them once using [`BlurShader::compile`] and share them between all blur elements.
+#[derive(Debug, Clone)]
+pub struct BlurShader {
+    down: Gles2PixelProgram,
+    up: Gles2PixelProgram,
+}
+
+impl BlurShader {
+    /// Compiles the shaders of the dual kawase blur
+    pub fn compile(renderer: &mut Gles2Renderer) -> Result<BlurShader, Gles2Error> {
+        let uniforms = [
+            UniformName::new("tex", UniformType::Int),
+            UniformName::new("half_pixel", UniformType::Vec2),
+            UniformName::new("offset", UniformType::Float),
+        ];
+        Ok(BlurShader {
+            down: renderer.compile_custom_pixel_shader(shaders::FRAGMENT_SHADER_BLUR_DOWN, &uniforms)?,
+            up: renderer.compile_custom_pixel_shader(shaders::FRAGMENT_SHADER_BLUR_UP, &uniforms)?,
+        })
+    }
+}
+
+/// Render element blurring the contents rendered below it
+///
+/// The element reads back the framebuffer region below it (including a margin depending on
+/// the blur strength), blurs it using a dual kawase blur and draws the result on top.
+/// It reports this region as its [`sampled_region`](RenderElement::sampled_region), so the
+/// [`DamageTrackedRenderer`](crate::backend::renderer::damage::DamageTrackedRenderer)
+/// redraws it completely, whenever anything below it changes.
+///
+/// The blurred contents are opaque, but scaled by the alpha value of the element.
+/// Elements are expected to be drawn in order, contents of elements above the blur element,
+/// that are not redrawn in the same frame, might bleed into the margin.
+///
+/// The intermediate textures of the blur are kept by the element (and its clones) and reused
+/// as long as the size of the blurred region does not change.
+#[derive(Debug, Clone)]
+pub struct BlurElement {
+    shader: BlurShader,
+    id: Id,
+    commit_counter: CommitCounter,
+    area: Rectangle<i32, Logical>,
+    passes: usize,
+    offset: f32,
+    alpha: f32,
+    buffers: Rc<RefCell<Option<BlurBuffers>>>,
+}
+
+impl BlurElement {
+    /// Create a new [`BlurElement`] covering the given area
+    ///
+    /// The strength of the blur is controlled by the number of `passes` (at least 1 and at
+    /// most [`MAX_BLUR_PASSES`]), each halving the resolution, and the sample `offset` of each pass.
+    pub fn new(shader: BlurShader, area: Rectangle<i32, Logical>, passes: usize, offset: f32) -> Self {
+        BlurElement {
+            shader,
+            id: Id::new(),
+            commit_counter: CommitCounter::default(),
+            area,
+            passes: passes.clamp(1, MAX_BLUR_PASSES),
+            offset: offset.max(0.0),
+            alpha: 1.0,
+            buffers: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// Area covered by this element
+    pub fn area(&self) -> Rectangle<i32, Logical> {
+        self.area
+    }
+
+    /// Updates the area of this element
+    pub fn resize(&mut self, area: Rectangle<i32, Logical>) {
+        if self.area != area {
+            self.area = area;
+            self.commit_counter.increment();
+        }
+    }
+
+    /// Updates the strength of the blur
+    pub fn set_strength(&mut self, passes: usize, offset: f32) {
+        let passes = passes.clamp(1, MAX_BLUR_PASSES);
+        let offset = offset.max(0.0);
+        if self.passes != passes || self.offset != offset {
+            self.passes = passes;
+            self.offset = offset;
+            self.commit_counter.increment();
+        }
+    }
+
+    /// Updates the alpha value the blurred contents are drawn with
+    pub fn set_alpha(&mut self, alpha: f32) {
+        if self.alpha != alpha {
+            self.alpha = alpha;
+            self.commit_counter.increment();
+        }
+    }
+}
+
+impl RenderElement<Gles2Renderer> for BlurElement {
+    fn id(&self) -> &Id {
+        &self.id
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.commit_counter
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        Rectangle::from_loc_and_size((0.0, 0.0), (self.area.size.w as f64, self.area.size.h as f64))
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.area.to_physical_precise_round(scale)
+    }
+
+    fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    fn sampled_region(&self, scale: Scale<f64>) -> Option<Rectangle<i32, Physical>> {
+        let size = self.geometry(scale).size;
+        let padding = blur_padding(self.passes, self.offset);
+        Some(Rectangle::from_loc_and_size(
+            (-padding, -padding),
+            (size.w + 2 * padding, size.h + 2 * padding),
+        ))
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Gles2Renderer,
+        frame: &mut Gles2Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        log: &slog::Logger,
+    ) -> Result<(), Gles2Error> {
+        self.draw_with_alpha(renderer, frame, location, scale, damage, self.alpha(), log)
+    }
+
+    fn draw_with_alpha(
+        &self,
+        renderer: &mut Gles2Renderer,
+        frame: &mut Gles2Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        _log: &slog::Logger,
+    ) -> Result<(), Gles2Error> {
+        let dest = Rectangle::from_loc_and_size(location, self.geometry(scale).size);
+        render_blur(
+            renderer,
+            frame,
+            &self.shader,
+            &mut self.buffers.borrow_mut(),
+            dest,
+            damage,
+            self.passes,
+            self.offset,
+            alpha,
+        )
+    }
+}
+
+// Distance in pixels the blur samples from outside of its area
+fn blur_padding(passes: usize, offset: f32) -> i32 {
+    (offset * 2f32.powi(passes as i32 + 1)).ceil() as i32
+}
+
+// Textures and framebuffers used by the passes of a blur
+#[derive(Debug)]
+struct BlurBuffers {
+    renderer_id: usize,
+    // size of the captured region and of every pass
+    sizes: Vec<Size<i32, Buffer>>,
+    // the captured region, one texture per pass and the result
+    textures: Vec<Gles2Texture>,
+    // framebuffers of every texture but the capture, which is only copied into
+    fbos: Vec<ffi::types::GLuint>,
+    destruction_callback_sender: Sender<CleanupResource>,
+}
+
+impl BlurBuffers {
+    unsafe fn new(
+        renderer: &Gles2Renderer,
+        sizes: Vec<Size<i32, Buffer>>,
+    ) -> Result<BlurBuffers, Gles2Error> {
+        let gl = &renderer.gl;
+        let passes = sizes.len() - 1;
+        // the framebuffer might not have an alpha channel, so capture into an rgb texture
+        let mut textures = vec![Gles2Texture::from_raw(
+            renderer,
+            create_blur_texture(gl, ffi::RGB, sizes[0]),
+            sizes[0],
+        )];
+        for size in sizes[1..].iter().chain(std::iter::once(&sizes[0])) {
+            textures.push(Gles2Texture::from_raw(
+                renderer,
+                create_blur_texture(gl, ffi::RGBA, *size),
+                *size,
+            ));
+        }
+
+        let mut buffers = BlurBuffers {
+            renderer_id: renderer.id(),
+            sizes,
+            textures,
+            fbos: Vec::with_capacity(passes + 1),
+            destruction_callback_sender: renderer.destruction_callback_sender.clone(),
+        };
+        for texture in &buffers.textures[1..] {
+            let mut fbo = 0;
+            gl.GenFramebuffers(1, &mut fbo);
+            buffers.fbos.push(fbo);
+            gl.BindFramebuffer(ffi::FRAMEBUFFER, fbo);
+            gl.FramebufferTexture2D(
+                ffi::FRAMEBUFFER,
+                ffi::COLOR_ATTACHMENT0,
+                ffi::TEXTURE_2D,
+                texture.tex_id(),
+                0,
+            );
+            if gl.CheckFramebufferStatus(ffi::FRAMEBUFFER) != ffi::FRAMEBUFFER_COMPLETE {
+                return Err(Gles2Error::FramebufferBindingError);
+            }
+        }
+
+        Ok(buffers)
+    }
+
+    fn result(&self) -> &Gles2Texture {
+        self.textures.last().unwrap()
+    }
+}
+
+impl Drop for BlurBuffers {
+    fn drop(&mut self) {
+        for fbo in &self.fbos {
+            let _ = self
+                .destruction_callback_sender
+                .send(CleanupResource::FramebufferObject(*fbo));
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_blur(
+    renderer: &Gles2Renderer,
+    frame: &mut Gles2Frame,
+    shader: &BlurShader,
+    buffers: &mut Option<BlurBuffers>,
+    dest: Rectangle<i32, Physical>,
+    damage: &[Rectangle<i32, Physical>],
+    passes: usize,
+    offset: f32,
+    alpha: f32,
+) -> Result<(), Gles2Error> {
+    let output = Rectangle::from_loc_and_size((0, 0), frame.size);
+    let visible = match dest.intersection(output) {
+        Some(visible) if !visible.is_empty() => visible,
+        _ => return Ok(()),
+    };
+    let damage = damage
+        .iter()
+        .filter_map(|rect| Rectangle::from_loc_and_size(rect.loc + dest.loc, rect.size).intersection(visible))
+        .filter(|rect| !rect.is_empty())
+        .map(|mut rect| {
+            rect.loc -= visible.loc;
+            rect
+        })
+        .collect::<Vec<_>>();
+    if damage.is_empty() {
+        return Ok(());
+    }
+
+    let padding = blur_padding(passes, offset);
+    let sample = Rectangle::from_loc_and_size(
+        (dest.loc.x - padding, dest.loc.y - padding),
+        (dest.size.w + 2 * padding, dest.size.h + 2 * padding),
+    )
+    .intersection(output)
+    .unwrap_or(visible);
+
+    // The framebuffer is laid out in the untransformed space of the output
+    let transform = frame.transform;
+    let fb_rect = transform.transform_rect_in(sample, &frame.size);
+    let sizes = blur_sizes(fb_rect.size, passes);
+
+    let gl = frame.gl.clone();
+    unsafe {
+        let mut previous_fbo = 0;
+        gl.GetIntegerv(ffi::FRAMEBUFFER_BINDING, &mut previous_fbo);
+        let mut viewport = [0; 4];
+        gl.GetIntegerv(ffi::VIEWPORT, viewport.as_mut_ptr());
+
+        // only recreate the textures, if the size of the blurred region changed
+        if !matches!(buffers, Some(buffers) if buffers.renderer_id == renderer.id() && buffers.sizes == sizes)
+        {
+            *buffers = None;
+            let res = BlurBuffers::new(renderer, sizes.clone());
+            gl.BindFramebuffer(ffi::FRAMEBUFFER, previous_fbo as u32);
+            *buffers = Some(res?);
+        }
+        let buffers = buffers.as_ref().unwrap();
+        let textures = buffers
+            .textures
+            .iter()
+            .map(Gles2Texture::tex_id)
+            .collect::<Vec<_>>();
+
+        gl.ActiveTexture(ffi::TEXTURE0);
+        gl.BindTexture(ffi::TEXTURE_2D, textures[0]);
+        gl.CopyTexSubImage2D(
+            ffi::TEXTURE_2D,
+            0,
+            0,
+            0,
+            fb_rect.loc.x,
+            fb_rect.loc.y,
+            fb_rect.size.w,
+            fb_rect.size.h,
+        );
+
+        gl.Disable(ffi::BLEND);
+        let mut res = Ok(());
+        for level in 1..=passes {
+            res = res.and_then(|_| {
+                blur_pass(
+                    frame,
+                    &shader.down,
+                    textures[level - 1],
+                    buffers.fbos[level - 1],
+                    sizes[level],
+                    offset,
+                )
+            });
+        }
+        for level in (1..=passes).rev() {
+            // the last pass renders into the result
+            let target = if level == 1 { passes + 1 } else { level - 1 };
+            res = res.and_then(|_| {
+                blur_pass(
+                    frame,
+                    &shader.up,
+                    textures[level],
+                    buffers.fbos[target - 1],
+                    sizes[level - 1],
+                    offset,
+                )
+            });
+        }
+        gl.Enable(ffi::BLEND);
+        gl.BlendFunc(ffi::ONE, ffi::ONE_MINUS_SRC_ALPHA);
+
+        gl.BindFramebuffer(ffi::FRAMEBUFFER, previous_fbo as u32);
+        gl.Viewport(viewport[0], viewport[1], viewport[2], viewport[3]);
+        res?;
+    }
+
+    let result = buffers.as_ref().unwrap().result();
+    result.set_color_space(frame.color_space);
+
+    let src = transform.transform_rect_in(
+        Rectangle::from_loc_and_size(visible.loc - sample.loc, visible.size),
+        &sample.size,
+    );
+    let src = Rectangle::<i32, Buffer>::from_loc_and_size((src.loc.x, src.loc.y), (src.size.w, src.size.h));
+    frame.render_texture_from_to(result, src.to_f64(), visible, &damage, transform.invert(), alpha)
+}
+
+// Size of the captured region followed by the sizes of every pass, halving the resolution each time
+fn blur_sizes(size: Size<i32, Physical>, passes: usize) -> Vec<Size<i32, Buffer>> {
+    (0..=passes)
+        .map(|level| Size::from(((size.w >> level).max(1), (size.h >> level).max(1))))
+        .collect()
+}
+
+unsafe fn create_blur_texture(
+    gl: &ffi::Gles2,
+    format: ffi::types::GLenum,
+    size: Size<i32, Buffer>,
+) -> ffi::types::GLuint {
+    let mut tex = 0;
+    gl.GenTextures(1, &mut tex);
+    gl.BindTexture(ffi::TEXTURE_2D, tex);
+    gl.TexParameteri(ffi::TEXTURE_2D, ffi::TEXTURE_MIN_FILTER, ffi::LINEAR as i32);
+    gl.TexParameteri(ffi::TEXTURE_2D, ffi::TEXTURE_MAG_FILTER, ffi::LINEAR as i32);
+    gl.TexParameteri(ffi::TEXTURE_2D, ffi::TEXTURE_WRAP_S, ffi::CLAMP_TO_EDGE as i32);
+    gl.TexParameteri(ffi::TEXTURE_2D, ffi::TEXTURE_WRAP_T, ffi::CLAMP_TO_EDGE as i32);
+    gl.TexImage2D(
+        ffi::TEXTURE_2D,
+        0,
+        format as i32,
+        size.w,
+        size.h,
+        0,
+        format,
+        ffi::UNSIGNED_BYTE,
+        std::ptr::null(),
+    );
+    tex
+}
+
+// Renders `src` into the framebuffer `fbo` of the given size using one of the blur shaders
+unsafe fn blur_pass(
+    frame: &Gles2Frame,
+    program: &Gles2PixelProgram,
+    src: ffi::types::GLuint,
+    fbo: ffi::types::GLuint,
+    size: Size<i32, Buffer>,
+    offset: f32,
+) -> Result<(), Gles2Error> {
+    let gl = &frame.gl;
+    gl.BindFramebuffer(ffi::FRAMEBUFFER, fbo);
+    gl.Viewport(0, 0, size.w, size.h);
+    gl.ActiveTexture(ffi::TEXTURE0);
+    gl.BindTexture(ffi::TEXTURE_2D, src);
+
+    // maps the pixels of the target to normalized device coordinates
+    let matrix = Matrix3::from_translation(Vector2::new(-1.0, -1.0))
+        * Matrix3::from_nonuniform_scale(2.0 / size.w as f32, 2.0 / size.h as f32);
+
+    let program = &program.0;
+    gl.UseProgram(program.program);
+    gl.UniformMatrix3fv(program.uniform_matrix, 1, ffi::FALSE, matrix.as_ptr());
+    gl.Uniform2f(program.uniform_size, size.w as f32, size.h as f32);
+    gl.Uniform1f(program.uniform_alpha, 1.0);
+    set_additional_uniforms(
+        gl,
+        &program.additional_uniforms,
+        &[
+            Uniform::new("tex", 0),
+            Uniform::new("half_pixel", [0.5 / size.w as f32, 0.5 / size.h as f32]),
+            Uniform::new("offset", offset),
+        ],
+    )?;
+    frame.draw_instances(
+        program.attrib_vert,
+        program.attrib_position,
+        &[0.0, 0.0, size.w as f32, size.h as f32],
+    );
+    Ok(())
+}
+
+/// Shader used by [`ShadowElement`]s
+///
+/// Compile it once using [`ShadowShader::compile`] and share it between all shadow elements.
+#[derive(Debug, Clone)]
+pub struct ShadowShader(Gles2PixelProgram);
+
+impl ShadowShader {
+    /// Compiles the shader drawing rounded rectangle shadows
+    pub fn compile(renderer: &mut Gles2Renderer) -> Result<ShadowShader, Gles2Error> {
+        renderer
+            .compile_custom_pixel_shader(
+                shaders::FRAGMENT_SHADER_SHADOW,
+                &[
+                    UniformName::new("color", UniformType::Vec4),
+                    UniformName::new("shadow_rect", UniformType::Vec4),
+                    UniformName::new("corner_radius", UniformType::Float),
+                    UniformName::new("sigma", UniformType::Float),
+                ],
+            )
+            .map(ShadowShader)
+    }
+}
+
+/// Render element drawing the soft drop shadow of a (rounded) rectangle
+///
+/// The shadow is computed analytically in a single pass and does not read back the framebuffer,
+/// so it is cheap to redraw. The element covers the shadow casting rectangle moved by the
+/// offset and extended by the blur radius and does not report any opaque regions.
+#[derive(Debug, Clone)]
+pub struct ShadowElement {
+    shader: ShadowShader,
+    id: Id,
+    commit_counter: CommitCounter,
+    geometry: Rectangle<i32, Logical>,
+    offset: Point<i32, Logical>,
+    radius: i32,
+    corner_radius: f32,
+    color: [f32; 4],
+}
+
+impl ShadowElement {
+    /// Create a new [`ShadowElement`] for a rectangle with the given geometry
+    ///
+    /// The color is expected to be premultiplied.
+    pub fn new(
+        shader: ShadowShader,
+        geometry: Rectangle<i32, Logical>,
+        offset: impl Into<Point<i32, Logical>>,
+        radius: i32,
+        corner_radius: f32,
+        color: [f32; 4],
+    ) -> Self {
+        ShadowElement {
+            shader,
+            id: Id::new(),
+            commit_counter: CommitCounter::default(),
+            geometry,
+            offset: offset.into(),
+            radius: radius.max(0),
+            corner_radius: corner_radius.max(0.0),
+            color,
+        }
+    }
+
+    /// Area covered by this element
+    pub fn area(&self) -> Rectangle<i32, Logical> {
+        Rectangle::from_loc_and_size(
+            (
+                self.geometry.loc.x + self.offset.x - self.radius,
+                self.geometry.loc.y + self.offset.y - self.radius,
+            ),
+            (
+                self.geometry.size.w + 2 * self.radius,
+                self.geometry.size.h + 2 * self.radius,
+            ),
+        )
+    }
+
+    /// Updates the geometry of the rectangle casting the shadow
+    pub fn set_geometry(&mut self, geometry: Rectangle<i32, Logical>) {
+        if self.geometry != geometry {
+            self.geometry = geometry;
+            self.commit_counter.increment();
+        }
+    }
+
+    /// Updates the offset, blur radius and corner radius of the shadow
+    pub fn set_shape(&mut self, offset: impl Into<Point<i32, Logical>>, radius: i32, corner_radius: f32) {
+        let offset = offset.into();
+        let radius = radius.max(0);
+        let corner_radius = corner_radius.max(0.0);
+        if self.offset != offset || self.radius != radius || self.corner_radius != corner_radius {
+            self.offset = offset;
+            self.radius = radius;
+            self.corner_radius = corner_radius;
+            self.commit_counter.increment();
+        }
+    }
+
+    /// Updates the (premultiplied) color of the shadow
+    pub fn set_color(&mut self, color: [f32; 4]) {
+        if self.color != color {
+            self.color = color;
+            self.commit_counter.increment();
+        }
+    }
+}
+
+impl RenderElement<Gles2Renderer> for ShadowElement {
+    fn id(&self) -> &Id {
+        &self.id
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.commit_counter
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        let area = self.area();
+        Rectangle::from_loc_and_size((0.0, 0.0), (area.size.w as f64, area.size.h as f64))
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.area().to_physical_precise_round(scale)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Gles2Renderer,
+        frame: &mut Gles2Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        log: &slog::Logger,
+    ) -> Result<(), Gles2Error> {
+        self.draw_with_alpha(renderer, frame, location, scale, damage, self.alpha(), log)
+    }
+
+    fn draw_with_alpha(
+        &self,
+        _renderer: &mut Gles2Renderer,
+        frame: &mut Gles2Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        _log: &slog::Logger,
+    ) -> Result<(), Gles2Error> {
+        let geometry = self.geometry(scale);
+        let caster = Rectangle::from_loc_and_size(self.geometry.loc + self.offset, self.geometry.size)
+            .to_physical_precise_round(scale);
+        let dest = Rectangle::from_loc_and_size(location, geometry.size);
+        frame.render_pixel_shader_to(
+            &self.shader.0,
+            dest,
+            damage,
+            alpha,
+            &[
+                Uniform::new("color", self.color),
+                Uniform::new(
+                    "shadow_rect",
+                    [
+                        (caster.loc.x - geometry.loc.x) as f32,
+                        (caster.loc.y - geometry.loc.y) as f32,
+                        caster.size.w as f32,
+                        caster.size.h as f32,
+                    ],
+                ),
+                Uniform::new("corner_radius", (self.corner_radius as f64 * scale.x) as f32),
+                Uniform::new("sigma", (self.radius as f64 * scale.x / 3.0) as f32),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blur_pass_sizes() {
+        assert_eq!(
+            blur_sizes(Size::from((100, 7)), 3),
+            vec![
+                Size::from((100, 7)),
+                Size::from((50, 3)),
+                Size::from((25, 1)),
+                Size::from((12, 1)),
+            ]
+        );
+        assert_eq!(blur_padding(2, 1.5), 12);
+    }
+}
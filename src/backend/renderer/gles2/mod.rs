@@ -4,7 +4,8 @@ use cgmath::{prelude::*, Matrix3, Vector2};
 use core::slice;
 use std::{
     borrow::Cow,
-    collections::HashSet,
+    cell::Cell,
+    collections::{HashMap, HashSet},
     convert::TryFrom,
     ffi::CStr,
     fmt, mem,
@@ -18,12 +19,22 @@ use std::{
 };
 
 #[cfg(feature = "wayland_frontend")]
-use std::{cell::RefCell, collections::HashMap};
+use std::cell::RefCell;
 
+mod element;
 mod shaders;
+mod uniform;
 mod version;
 
+pub use self::element::{
+    BlurElement, BlurShader, PixelShaderElement, ShadowElement, ShadowShader, TextureShaderElement,
+    MAX_BLUR_PASSES,
+};
+use self::uniform::UniformDesc;
+pub use self::uniform::{Uniform, UniformName, UniformType, UniformValue};
+
 use super::{
+    color::{ColorSpace, ColorTransform, TransferFunction, DEFAULT_SDR_WHITE_LEVEL},
     Bind, ExportDma, ExportMem, Frame, ImportDma, ImportMem, Offscreen, Renderer, Texture, TextureFilter,
     TextureMapping, Unbind,
 };
@@ -57,14 +68,22 @@ pub mod ffi {
 crate::utils::ids::id_gen!(next_renderer_id, RENDERER_ID, RENDERER_IDS);
 
 #[derive(Debug, Clone)]
-struct Gles2TexProgram {
+struct Gles2TexProgramVariant {
     program: ffi::types::GLuint,
     uniform_tex: ffi::types::GLint,
     uniform_tex_matrix: ffi::types::GLint,
     uniform_matrix: ffi::types::GLint,
     uniform_alpha: ffi::types::GLint,
+    // only used by the color conversion variants
+    uniform_src_tf: ffi::types::GLint,
+    uniform_dst_tf: ffi::types::GLint,
+    uniform_primaries: ffi::types::GLint,
+    uniform_white_level: ffi::types::GLint,
+    uniform_color_transform: ffi::types::GLint,
     attrib_vert: ffi::types::GLint,
     attrib_vert_position: ffi::types::GLint,
+    // only used by custom shaders
+    additional_uniforms: HashMap<String, UniformDesc>,
 }
 
 #[derive(Debug, Clone)]
@@ -76,6 +95,54 @@ struct Gles2SolidProgram {
     attrib_position: ffi::types::GLint,
 }
 
+/// A custom texture shader compiled by [`Gles2Renderer::compile_custom_texture_shader`]
+///
+/// The program is deleted, once all handles are dropped.
+#[derive(Debug, Clone)]
+pub struct Gles2TexProgram(Rc<Gles2TexProgramInternal>);
+
+#[derive(Debug)]
+struct Gles2TexProgramInternal {
+    variants: [Gles2TexProgramVariant; shaders::FRAGMENT_COUNT],
+    destruction_callback_sender: Sender<CleanupResource>,
+}
+
+impl Drop for Gles2TexProgramInternal {
+    fn drop(&mut self) {
+        for variant in &self.variants {
+            let _ = self
+                .destruction_callback_sender
+                .send(CleanupResource::Program(variant.program));
+        }
+    }
+}
+
+/// A custom pixel shader compiled by [`Gles2Renderer::compile_custom_pixel_shader`]
+///
+/// The program is deleted, once all handles are dropped.
+#[derive(Debug, Clone)]
+pub struct Gles2PixelProgram(Rc<Gles2PixelProgramInternal>);
+
+#[derive(Debug)]
+struct Gles2PixelProgramInternal {
+    program: ffi::types::GLuint,
+    uniform_matrix: ffi::types::GLint,
+    uniform_size: ffi::types::GLint,
+    uniform_alpha: ffi::types::GLint,
+    attrib_vert: ffi::types::GLint,
+    attrib_position: ffi::types::GLint,
+    additional_uniforms: HashMap<String, UniformDesc>,
+    destruction_callback_sender: Sender<CleanupResource>,
+}
+
+impl Drop for Gles2PixelProgramInternal {
+    fn drop(&mut self) {
+        let _ = self
+            .destruction_callback_sender
+            .send(CleanupResource::Program(self.program));
+    }
+}
+
 /// A handle to a GLES2 texture
 #[derive(Debug, Clone)]
 pub struct Gles2Texture(Rc<Gles2TextureInternal>);
@@ -104,6 +171,7 @@ impl Gles2Texture {
             y_inverted: false,
             size,
             egl_images: None,
+            color_space: Cell::new(ColorSpace::SRGB),
             destruction_callback_sender: renderer.destruction_callback_sender.clone(),
         }))
     }
@@ -114,6 +182,23 @@ impl Gles2Texture {
     pub fn tex_id(&self) -> ffi::types::GLuint {
         self.0.texture
     }
+
+    /// Color space of the contents of this texture
+    ///
+    /// Defaults to [`ColorSpace::SRGB`].
+    pub fn color_space(&self) -> ColorSpace {
+        self.0.color_space.get()
+    }
+
+    /// Sets the color space of the contents of this texture.
+    ///
+    /// Textures with a color space not matching the color space of the current
+    /// render target (see [`Gles2Renderer::set_target_color_space`]) will be converted while rendering.
+    ///
+    /// *Note*: Textures imported from the same buffer share their color space.
+    pub fn set_color_space(&self, color_space: ColorSpace) {
+        self.0.color_space.set(color_space);
+    }
 }
 
 #[derive(Debug)]
@@ -124,6 +209,7 @@ struct Gles2TextureInternal {
     y_inverted: bool,
     size: Size<i32, BufferCoord>,
     egl_images: Option<Vec<EGLImage>>,
+    color_space: Cell<ColorSpace>,
     destruction_callback_sender: Sender<CleanupResource>,
 }
 
@@ -148,6 +234,7 @@ enum CleanupResource {
     RenderbufferObject(ffi::types::GLuint),
     EGLImage(EGLImage),
     Mapping(ffi::types::GLuint, *const nix::libc::c_void),
+    Program(ffi::types::GLuint),
 }
 
 impl Texture for Gles2Texture {
@@ -270,8 +357,11 @@ pub struct Gles2Renderer {
     buffers: Vec<Gles2Buffer>,
     target: Option<Gles2Target>,
     pub(crate) extensions: Vec<String>,
-    tex_programs: [Gles2TexProgram; shaders::FRAGMENT_COUNT],
+    tex_programs: [Gles2TexProgramVariant; shaders::FRAGMENT_COUNT],
+    color_tex_programs: [Gles2TexProgramVariant; shaders::FRAGMENT_COUNT],
     solid_program: Gles2SolidProgram,
+    target_color_space: ColorSpace,
+    sdr_white_level: f32,
     dmabuf_cache: std::collections::HashMap<WeakDmabuf, Gles2Texture>,
     egl: EGLContext,
     #[cfg(all(feature = "wayland_frontend", feature = "use_system_lib"))]
@@ -303,8 +393,13 @@ pub struct Gles2Frame {
     current_projection: Matrix3<f32>,
     transform: Transform,
     gl: ffi::Gles2,
-    tex_programs: [Gles2TexProgram; shaders::FRAGMENT_COUNT],
+    tex_programs: [Gles2TexProgramVariant; shaders::FRAGMENT_COUNT],
+    color_tex_programs: [Gles2TexProgramVariant; shaders::FRAGMENT_COUNT],
     solid_program: Gles2SolidProgram,
+    color_space: ColorSpace,
+    sdr_white_level: f32,
+    color_transform: Option<ColorTransform>,
+    tex_program_override: Option<(Gles2TexProgram, Vec<Uniform<'static>>)>,
     vbos: [ffi::types::GLuint; 2],
     size: Size<i32, Physical>,
     min_filter: TextureFilter,
@@ -318,6 +413,9 @@ impl fmt::Debug for Gles2Frame {
             .field("current_projection", &self.current_projection)
             .field("tex_programs", &self.tex_programs)
             .field("solid_program", &self.solid_program)
+            .field("color_space", &self.color_space)
+            .field("color_transform", &self.color_transform)
+            .field("tex_program_override", &self.tex_program_override)
             .field("size", &self.size)
             .field("min_filter", &self.min_filter)
             .field("max_filter", &self.max_filter)
@@ -333,6 +431,8 @@ impl fmt::Debug for Gles2Renderer {
             .field("extensions", &self.extensions)
             .field("tex_programs", &self.tex_programs)
             .field("solid_program", &self.solid_program)
+            .field("target_color_space", &self.target_color_space)
+            .field("sdr_white_level", &self.sdr_white_level)
             // ffi::Gles2 does not implement Debug
             .field("egl", &self.egl)
             .field("min_filter", &self.min_filter)
@@ -345,9 +445,9 @@ impl fmt::Debug for Gles2Renderer {
 /// Error returned during rendering using GL ES
 #[derive(thiserror::Error, Debug)]
 pub enum Gles2Error {
-    /// A shader could not be compiled
+    /// A shader could not be compiled, contains the info log of the shader compiler
     #[error("Failed to compile Shader: {0}")]
-    ShaderCompileError(&'static str),
+    ShaderCompileError(String),
     /// A program could not be linked
     #[error("Failed to link Program")]
     ProgramLinkError,
@@ -395,6 +495,22 @@ pub enum Gles2Error {
     /// The provided buffer's size did not match the requested one.
     #[error("Error reading buffer, size is too small for the given dimensions")]
     UnexpectedSize,
+    /// The name of a uniform declared for a custom shader contains a NUL byte
+    #[error("Uniform name {0:?} contains a NUL byte")]
+    InvalidUniformName(String),
+    /// A uniform was set, that was not declared when compiling the custom shader
+    #[error("Uniform {0:?} was not declared by the shader")]
+    UnknownUniform(String),
+    /// A uniform was set with a value not matching the declared type
+    #[error("Uniform {name:?} was declared as {declared:?}, but {provided:?} was provided")]
+    UniformTypeMismatch {
+        /// Name of the uniform
+        name: String,
+        /// Type declared when compiling the shader
+        declared: UniformType,
+        /// Type of the provided value
+        provided: UniformType,
+    },
 }
 
 impl From<Gles2Error> for SwapBuffersError {
@@ -415,6 +531,9 @@ impl From<Gles2Error> for SwapBuffersError {
             | x @ Gles2Error::BufferAccessError(_)
             | x @ Gles2Error::MappingError
             | x @ Gles2Error::UnexpectedSize
+            | x @ Gles2Error::InvalidUniformName(_)
+            | x @ Gles2Error::UnknownUniform(_)
+            | x @ Gles2Error::UniformTypeMismatch { .. }
             | x @ Gles2Error::EGLBufferAccessError(_) => SwapBuffersError::TemporaryFailure(Box::new(x)),
         }
     }
@@ -432,6 +551,9 @@ impl From<Gles2Error> for SwapBuffersError {
             x @ Gles2Error::FramebufferBindingError
             | x @ Gles2Error::MappingError
             | x @ Gles2Error::UnexpectedSize
+            | x @ Gles2Error::InvalidUniformName(_)
+            | x @ Gles2Error::UnknownUniform(_)
+            | x @ Gles2Error::UniformTypeMismatch { .. }
             | x @ Gles2Error::BindBufferEGLError(_) => SwapBuffersError::TemporaryFailure(Box::new(x)),
         }
     }
@@ -464,7 +586,7 @@ extern "system" fn gl_debug_log(
 unsafe fn compile_shader(
     gl: &ffi::Gles2,
     variant: ffi::types::GLuint,
-    src: &'static str,
+    src: &str,
 ) -> Result<ffi::types::GLuint, Gles2Error> {
     let shader = gl.CreateShader(variant);
     gl.ShaderSource(
@@ -478,17 +600,32 @@ unsafe fn compile_shader(
     let mut status = ffi::FALSE as i32;
     gl.GetShaderiv(shader, ffi::COMPILE_STATUS, &mut status as *mut _);
     if status == ffi::FALSE as i32 {
+        let mut len = 0;
+        gl.GetShaderiv(shader, ffi::INFO_LOG_LENGTH, &mut len as *mut _);
+        let mut log = vec![0u8; len.max(1) as usize];
+        gl.GetShaderInfoLog(
+            shader,
+            log.len() as i32,
+            ptr::null_mut(),
+            log.as_mut_ptr() as *mut ffi::types::GLchar,
+        );
         gl.DeleteShader(shader);
-        return Err(Gles2Error::ShaderCompileError(src));
+        return Err(Gles2Error::ShaderCompileError(info_log(&log)));
     }
 
     Ok(shader)
 }
 
+// Converts the NUL-terminated info log of the shader compiler
+fn info_log(log: &[u8]) -> String {
+    let len = log.iter().position(|c| *c == 0).unwrap_or(log.len());
+    String::from_utf8_lossy(&log[..len]).trim_end().to_owned()
+}
+
 unsafe fn link_program(
     gl: &ffi::Gles2,
-    vert_src: &'static str,
-    frag_src: &'static str,
+    vert_src: &str,
+    frag_src: &str,
 ) -> Result<ffi::types::GLuint, Gles2Error> {
     let vert = compile_shader(gl, ffi::VERTEX_SHADER, vert_src)?;
     let frag = compile_shader(gl, ffi::FRAGMENT_SHADER, frag_src)?;
@@ -511,7 +648,7 @@ unsafe fn link_program(
     Ok(program)
 }
 
-unsafe fn texture_program(gl: &ffi::Gles2, frag: &'static str) -> Result<Gles2TexProgram, Gles2Error> {
+unsafe fn texture_program(gl: &ffi::Gles2, frag: &str) -> Result<Gles2TexProgramVariant, Gles2Error> {
     let program = link_program(gl, shaders::VERTEX_SHADER, frag)?;
 
     let vert = CStr::from_bytes_with_nul(b"vert\0").expect("NULL terminated");
@@ -520,19 +657,66 @@ unsafe fn texture_program(gl: &ffi::Gles2, frag: &'static str) -> Result<Gles2Te
     let matrix = CStr::from_bytes_with_nul(b"matrix\0").expect("NULL terminated");
     let tex_matrix = CStr::from_bytes_with_nul(b"tex_matrix\0").expect("NULL terminated");
     let alpha = CStr::from_bytes_with_nul(b"alpha\0").expect("NULL terminated");
+    let src_tf = CStr::from_bytes_with_nul(b"src_tf\0").expect("NULL terminated");
+    let dst_tf = CStr::from_bytes_with_nul(b"dst_tf\0").expect("NULL terminated");
+    let primaries = CStr::from_bytes_with_nul(b"primaries\0").expect("NULL terminated");
+    let white_level = CStr::from_bytes_with_nul(b"white_level\0").expect("NULL terminated");
+    let color_transform = CStr::from_bytes_with_nul(b"color_transform\0").expect("NULL terminated");
 
-    Ok(Gles2TexProgram {
+    Ok(Gles2TexProgramVariant {
         program,
         uniform_tex: gl.GetUniformLocation(program, tex.as_ptr() as *const ffi::types::GLchar),
         uniform_matrix: gl.GetUniformLocation(program, matrix.as_ptr() as *const ffi::types::GLchar),
         uniform_tex_matrix: gl.GetUniformLocation(program, tex_matrix.as_ptr() as *const ffi::types::GLchar),
         uniform_alpha: gl.GetUniformLocation(program, alpha.as_ptr() as *const ffi::types::GLchar),
+        uniform_src_tf: gl.GetUniformLocation(program, src_tf.as_ptr() as *const ffi::types::GLchar),
+        uniform_dst_tf: gl.GetUniformLocation(program, dst_tf.as_ptr() as *const ffi::types::GLchar),
+        uniform_primaries: gl.GetUniformLocation(program, primaries.as_ptr() as *const ffi::types::GLchar),
+        uniform_white_level: gl
+            .GetUniformLocation(program, white_level.as_ptr() as *const ffi::types::GLchar),
+        uniform_color_transform: gl
+            .GetUniformLocation(program, color_transform.as_ptr() as *const ffi::types::GLchar),
         attrib_vert: gl.GetAttribLocation(program, vert.as_ptr() as *const ffi::types::GLchar),
         attrib_vert_position: gl
             .GetAttribLocation(program, vert_position.as_ptr() as *const ffi::types::GLchar),
+        additional_uniforms: HashMap::new(),
     })
 }
 
+unsafe fn uniform_locations(
+    gl: &ffi::Gles2,
+    program: ffi::types::GLuint,
+    uniforms: &[UniformName<'_>],
+) -> Result<HashMap<String, UniformDesc>, Gles2Error> {
+    uniforms
+        .iter()
+        .map(|uniform| {
+            let name = uniform::uniform_c_name(&uniform.name)?;
+            let location = gl.GetUniformLocation(program, name.as_ptr() as *const ffi::types::GLchar);
+            Ok((
+                uniform.name.clone().into_owned(),
+                UniformDesc {
+                    location,
+                    type_: uniform.type_,
+                },
+            ))
+        })
+        .collect()
+}
+
+// The uniforms need to be set while the given program is in use
+unsafe fn set_additional_uniforms(
+    gl: &ffi::Gles2,
+    declared: &HashMap<String, UniformDesc>,
+    uniforms: &[Uniform<'_>],
+) -> Result<(), Gles2Error> {
+    for uniform in uniforms {
+        let location = uniform::declared_location(declared, uniform)?;
+        uniform.value.set(gl, location);
+    }
+    Ok(())
+}
+
 unsafe fn solid_program(gl: &ffi::Gles2) -> Result<Gles2SolidProgram, Gles2Error> {
     let program = link_program(gl, shaders::VERTEX_SHADER_SOLID, shaders::FRAGMENT_SHADER_SOLID)?;
 
@@ -643,6 +827,11 @@ impl Gles2Renderer {
             texture_program(&gl, shaders::FRAGMENT_SHADER_XBGR)?,
             texture_program(&gl, shaders::FRAGMENT_SHADER_EXTERNAL)?,
         ];
+        let color_tex_programs = [
+            texture_program(&gl, shaders::FRAGMENT_SHADER_ABGR_COLOR)?,
+            texture_program(&gl, shaders::FRAGMENT_SHADER_XBGR_COLOR)?,
+            texture_program(&gl, shaders::FRAGMENT_SHADER_EXTERNAL_COLOR)?,
+        ];
         let solid_program = solid_program(&gl)?;
 
         // Initialize vertices based on drawing methodology.
@@ -675,7 +864,10 @@ impl Gles2Renderer {
             extensions: exts,
             gl_version,
             tex_programs,
+            color_tex_programs,
             solid_program,
+            target_color_space: ColorSpace::SRGB,
+            sdr_white_level: DEFAULT_SDR_WHITE_LEVEL,
             target: None,
             buffers: Vec::new(),
             dmabuf_cache: std::collections::HashMap::new(),
@@ -758,6 +950,9 @@ impl Gles2Renderer {
                     }
                     self.gl.DeleteBuffers(1, &pbo);
                 },
+                CleanupResource::Program(program) => unsafe {
+                    self.gl.DeleteProgram(program);
+                },
             }
         }
     }
@@ -830,6 +1025,7 @@ impl ImportMemWl for Gles2Renderer {
                             y_inverted: false,
                             size: (width, height).into(),
                             egl_images: None,
+                            color_space: Cell::new(ColorSpace::SRGB),
                             destruction_callback_sender: self.destruction_callback_sender.clone(),
                         });
                         if let Some(surface) = surface {
@@ -949,6 +1145,7 @@ impl ImportMem for Gles2Renderer {
                 y_inverted: flipped,
                 size,
                 egl_images: None,
+                color_space: Cell::new(ColorSpace::SRGB),
                 destruction_callback_sender: self.destruction_callback_sender.clone(),
             }
         }));
@@ -1064,6 +1261,7 @@ impl ImportEgl for Gles2Renderer {
             y_inverted: egl.y_inverted,
             size: egl.size,
             egl_images: Some(egl.into_images()),
+            color_space: Cell::new(ColorSpace::SRGB),
             destruction_callback_sender: self.destruction_callback_sender.clone(),
         }));
 
@@ -1099,6 +1297,7 @@ impl ImportDma for Gles2Renderer {
                 y_inverted: buffer.y_inverted(),
                 size: buffer.size(),
                 egl_images: Some(vec![image]),
+                color_space: Cell::new(ColorSpace::SRGB),
                 destruction_callback_sender: self.destruction_callback_sender.clone(),
             }));
             self.dmabuf_cache.insert(buffer.weak(), texture.clone());
@@ -1644,7 +1843,7 @@ impl Drop for Gles2Renderer {
         unsafe {
             if self.egl.make_current().is_ok() {
                 self.gl.BindFramebuffer(ffi::FRAMEBUFFER, 0);
-                for program in &self.tex_programs {
+                for program in self.tex_programs.iter().chain(self.color_tex_programs.iter()) {
                     self.gl.DeleteProgram(program.program);
                 }
                 self.gl.DeleteProgram(self.solid_program.program);
@@ -1694,6 +1893,160 @@ impl Gles2Renderer {
         let gl = self.gl.clone();
         Ok(func(self, &gl))
     }
+
+    /// Color space of the render target
+    pub fn target_color_space(&self) -> ColorSpace {
+        self.target_color_space
+    }
+
+    /// Sets the color space of the render target used for following calls to [`Renderer::render`].
+    ///
+    /// Textures (see [`Gles2Texture::set_color_space`]) and colors of other color spaces will be converted
+    /// into this color space while rendering. Colors passed to [`Frame::clear`] are expected to be sRGB.
+    ///
+    /// Defaults to [`ColorSpace::SRGB`]. Use e.g. [`ColorSpace::BT2020_PQ`] to render onto HDR10 outputs.
+    pub fn set_target_color_space(&mut self, color_space: ColorSpace) {
+        self.target_color_space = color_space;
+    }
+
+    /// Luminance of the SDR reference white in cd/m², used to composite SDR and HDR content.
+    pub fn sdr_white_level(&self) -> f32 {
+        self.sdr_white_level
+    }
+
+    /// Sets the luminance of the SDR reference white in cd/m².
+    ///
+    /// Defaults to [`DEFAULT_SDR_WHITE_LEVEL`].
+    pub fn set_sdr_white_level(&mut self, nits: f32) {
+        self.sdr_white_level = nits;
+    }
+
+    /// Compiles a custom pixel shader, which can be used to fill arbitrary areas
+    /// via [`Gles2Frame::render_pixel_shader_to`] or a [`PixelShaderElement`].
+    ///
+    /// The source is expected to be a GLSL ES 1.00 fragment shader with the following inputs:
+    /// - `varying vec2 v_coords`: The position inside the rendered area ranging from `0.0` to `1.0`
+    /// - `uniform vec2 size`: The size of the rendered area in physical pixels
+    /// - `uniform float alpha`: The alpha value the output should be multiplied with
+    ///
+    /// The output is expected to use premultiplied alpha. Any other uniforms used by the shader
+    /// need to be declared via `additional_uniforms` to be able to set them while rendering.
+    ///
+    /// The resulting program can be used with every renderer sharing the [`EGLContext`] of this renderer.
+    pub fn compile_custom_pixel_shader(
+        &mut self,
+        src: impl AsRef<str>,
+        additional_uniforms: &[UniformName<'_>],
+    ) -> Result<Gles2PixelProgram, Gles2Error> {
+        self.make_current()?;
+
+        unsafe {
+            let program = link_program(&self.gl, shaders::VERTEX_SHADER_PIXEL, src.as_ref())?;
+            let additional_uniforms = match uniform_locations(&self.gl, program, additional_uniforms) {
+                Ok(additional_uniforms) => additional_uniforms,
+                Err(err) => {
+                    self.gl.DeleteProgram(program);
+                    return Err(err);
+                }
+            };
+
+            let vert = CStr::from_bytes_with_nul(b"vert\0").expect("NULL terminated");
+            let position = CStr::from_bytes_with_nul(b"position\0").expect("NULL terminated");
+            let matrix = CStr::from_bytes_with_nul(b"matrix\0").expect("NULL terminated");
+            let size = CStr::from_bytes_with_nul(b"size\0").expect("NULL terminated");
+            let alpha = CStr::from_bytes_with_nul(b"alpha\0").expect("NULL terminated");
+
+            Ok(Gles2PixelProgram(Rc::new(Gles2PixelProgramInternal {
+                program,
+                uniform_matrix: self
+                    .gl
+                    .GetUniformLocation(program, matrix.as_ptr() as *const ffi::types::GLchar),
+                uniform_size: self
+                    .gl
+                    .GetUniformLocation(program, size.as_ptr() as *const ffi::types::GLchar),
+                uniform_alpha: self
+                    .gl
+                    .GetUniformLocation(program, alpha.as_ptr() as *const ffi::types::GLchar),
+                attrib_vert: self
+                    .gl
+                    .GetAttribLocation(program, vert.as_ptr() as *const ffi::types::GLchar),
+                attrib_position: self
+                    .gl
+                    .GetAttribLocation(program, position.as_ptr() as *const ffi::types::GLchar),
+                additional_uniforms,
+                destruction_callback_sender: self.destruction_callback_sender.clone(),
+            })))
+        }
+    }
+
+    /// Compiles a custom texture shader, which can be used instead of the default shaders
+    /// to render textures (see [`Gles2Frame::override_default_tex_program`] and [`TextureShaderElement`]).
+    ///
+    /// The source is expected to be a GLSL ES 1.00 fragment shader with the following inputs:
+    /// - `uniform sampler2D tex` (or `uniform samplerExternalOES tex`, if `EXTERNAL` is defined):
+    ///   The texture to be rendered
+    /// - `varying vec2 v_tex_coords`: The texture coordinates
+    /// - `uniform float alpha`: The alpha value the output should be multiplied with
+    ///
+    /// The shader is compiled in three variants for the different kinds of textures.
+    /// To differentiate between them, the line `//_DEFINES_` (or if missing, the line following the
+    /// `#version` directive) is replaced with the following defines:
+    /// - `NO_ALPHA`: The alpha channel of the texture should be ignored
+    /// - `EXTERNAL`: The texture is an external texture, the shader needs to enable the
+    ///   `GL_OES_EGL_image_external` extension
+    ///
+    /// Any other uniforms used by the shader need to be declared via `additional_uniforms`.
+    ///
+    /// *Note*: Textures rendered with custom shaders are not converted into the
+    /// [`target_color_space`](Gles2Renderer::target_color_space) and the color transform
+    /// of the frame (see [`Frame::set_color_transform`]) is not applied.
+    pub fn compile_custom_texture_shader(
+        &mut self,
+        src: impl AsRef<str>,
+        additional_uniforms: &[UniformName<'_>],
+    ) -> Result<Gles2TexProgram, Gles2Error> {
+        self.make_current()?;
+
+        let src = src.as_ref();
+        let mut variants = Vec::with_capacity(shaders::FRAGMENT_COUNT);
+        for defines in shaders::CUSTOM_TEXTURE_DEFINES {
+            let compiled = unsafe { texture_program(&self.gl, &custom_shader_source(src, defines)) }
+                .and_then(|variant| {
+                    variants.push(variant);
+                    let variant = variants.last_mut().unwrap();
+                    variant.additional_uniforms =
+                        unsafe { uniform_locations(&self.gl, variant.program, additional_uniforms) }?;
+                    Ok(())
+                });
+            if let Err(err) = compiled {
+                for variant in variants {
+                    unsafe { self.gl.DeleteProgram(variant.program) };
+                }
+                return Err(err);
+            }
+        }
+
+        Ok(Gles2TexProgram(Rc::new(Gles2TexProgramInternal {
+            variants: <[Gles2TexProgramVariant; shaders::FRAGMENT_COUNT]>::try_from(variants)
+                .expect("Compiled all variants"),
+            destruction_callback_sender: self.destruction_callback_sender.clone(),
+        })))
+    }
+}
+
+// Inserts the defines of a variant into the source of a custom texture shader
+fn custom_shader_source(src: &str, defines: &str) -> String {
+    if src.contains(shaders::CUSTOM_DEFINES_MARKER) {
+        return src.replacen(shaders::CUSTOM_DEFINES_MARKER, defines, 1);
+    }
+
+    let src = src.trim_start();
+    if src.starts_with("#version") {
+        let (version, rest) = src.split_at(src.find('\n').map(|i| i + 1).unwrap_or(src.len()));
+        format!("{}{}{}", version, defines, rest)
+    } else {
+        format!("{}{}", defines, src)
+    }
 }
 
 impl Renderer for Gles2Renderer {
@@ -1740,32 +2093,17 @@ impl Renderer for Gles2Renderer {
             mem::swap(&mut output_size.w, &mut output_size.h);
         }
 
-        // replicate https://www.khronos.org/registry/OpenGL-Refpages/gl2.1/xhtml/glOrtho.xml
-        // glOrtho(0, width, 0, height, 1, 1);
-        let mut renderer = Matrix3::<f32>::identity();
-        let t = Matrix3::<f32>::identity();
-        let x = 2.0 / (output_size.w as f32);
-        let y = 2.0 / (output_size.h as f32);
-
-        // Rotation & Reflection
-        renderer[0][0] = x * t[0][0];
-        renderer[1][0] = x * t[0][1];
-        renderer[0][1] = y * -t[1][0];
-        renderer[1][1] = y * -t[1][1];
-
-        //Translation
-        renderer[2][0] = -(1.0f32.copysign(renderer[0][0] + renderer[1][0]));
-        renderer[2][1] = -(1.0f32.copysign(renderer[0][1] + renderer[1][1]));
-
-        // We account for OpenGLs coordinate system here
-        let flip180 = Matrix3::new(1.0, 0.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, 1.0);
-
         let mut frame = Gles2Frame {
             gl: self.gl.clone(),
             tex_programs: self.tex_programs.clone(),
+            color_tex_programs: self.color_tex_programs.clone(),
             solid_program: self.solid_program.clone(),
+            color_space: self.target_color_space,
+            sdr_white_level: self.sdr_white_level,
+            color_transform: None,
+            tex_program_override: None,
             // output transformation passed in by the user
-            current_projection: flip180 * transform.matrix() * renderer,
+            current_projection: projection(output_size, transform),
             transform,
             vbos: self.vbos,
             size: output_size,
@@ -1858,6 +2196,12 @@ impl Frame for Gles2Frame {
 
         let mut mat = Matrix3::<f32>::identity();
         mat = self.current_projection * mat;
+        let color = clear_color(
+            color,
+            self.color_space,
+            self.sdr_white_level,
+            self.color_transform.as_ref(),
+        );
 
         let damage = at
             .iter()
@@ -2052,6 +2396,15 @@ impl Frame for Gles2Frame {
         self.render_texture(texture, tex_mat, mat, Some(&instances), alpha)
     }
 
+    fn set_color_transform(&mut self, transform: Option<ColorTransform>) -> Result<(), Self::Error> {
+        self.color_transform = transform;
+        Ok(())
+    }
+
+    fn color_transform(&self) -> Option<ColorTransform> {
+        self.color_transform
+    }
+
     fn transformation(&self) -> Transform {
         self.transform
     }
@@ -2086,6 +2439,19 @@ impl Gles2Frame {
         //apply output transformation
         matrix = self.current_projection * matrix;
 
+        // textures of a different color space need to be converted and the color transform
+        // needs to be applied, unless a custom shader is used
+        let color_space = tex.0.color_space.get();
+        let convert = (color_space != self.color_space || self.color_transform.is_some())
+            && self.tex_program_override.is_none();
+        let program = if let Some((program, _)) = self.tex_program_override.as_ref() {
+            &program.0.variants[tex.0.texture_kind]
+        } else if convert {
+            &self.color_tex_programs[tex.0.texture_kind]
+        } else {
+            &self.tex_programs[tex.0.texture_kind]
+        };
+
         let target = if tex.0.is_external {
             ffi::TEXTURE_EXTERNAL_OES
         } else {
@@ -2112,27 +2478,50 @@ impl Gles2Frame {
                     TextureFilter::Linear => ffi::LINEAR as i32,
                 },
             );
-            self.gl.UseProgram(self.tex_programs[tex.0.texture_kind].program);
+            self.gl.UseProgram(program.program);
 
+            self.gl.Uniform1i(program.uniform_tex, 0);
             self.gl
-                .Uniform1i(self.tex_programs[tex.0.texture_kind].uniform_tex, 0);
-            self.gl.UniformMatrix3fv(
-                self.tex_programs[tex.0.texture_kind].uniform_matrix,
-                1,
-                ffi::FALSE,
-                matrix.as_ptr(),
-            );
-            self.gl.UniformMatrix3fv(
-                self.tex_programs[tex.0.texture_kind].uniform_tex_matrix,
-                1,
-                ffi::FALSE,
-                tex_matrix.as_ptr(),
-            );
+                .UniformMatrix3fv(program.uniform_matrix, 1, ffi::FALSE, matrix.as_ptr());
             self.gl
-                .Uniform1f(self.tex_programs[tex.0.texture_kind].uniform_alpha, alpha);
+                .UniformMatrix3fv(program.uniform_tex_matrix, 1, ffi::FALSE, tex_matrix.as_ptr());
+            self.gl.Uniform1f(program.uniform_alpha, alpha);
+            if let Some((_, uniforms)) = self.tex_program_override.as_ref() {
+                if let Err(err) = set_additional_uniforms(&self.gl, &program.additional_uniforms, uniforms) {
+                    self.gl.BindTexture(target, 0);
+                    return Err(err);
+                }
+            }
+            if convert {
+                // converting from and to linear is a no-op, if only the color transform is needed
+                let (src_space, dst_space) = if color_space != self.color_space {
+                    (color_space, self.color_space)
+                } else {
+                    (ColorSpace::LINEAR, ColorSpace::LINEAR)
+                };
+                let primaries = src_space.primaries.conversion_matrix(dst_space.primaries);
+                let color_transform = self.color_transform.unwrap_or_default().to_matrix4();
+                self.gl.Uniform1i(
+                    program.uniform_src_tf,
+                    shader_transfer_function(src_space.transfer_function),
+                );
+                self.gl.Uniform1i(
+                    program.uniform_dst_tf,
+                    shader_transfer_function(dst_space.transfer_function),
+                );
+                self.gl
+                    .UniformMatrix3fv(program.uniform_primaries, 1, ffi::FALSE, primaries.as_ptr());
+                self.gl
+                    .Uniform1f(program.uniform_white_level, self.sdr_white_level);
+                self.gl.UniformMatrix4fv(
+                    program.uniform_color_transform,
+                    1,
+                    ffi::FALSE,
+                    color_transform.as_ptr(),
+                );
+            }
 
-            self.gl
-                .EnableVertexAttribArray(self.tex_programs[tex.0.texture_kind].attrib_vert as u32);
+            self.gl.EnableVertexAttribArray(program.attrib_vert as u32);
             self.gl.BindBuffer(ffi::ARRAY_BUFFER, self.vbos[0]);
             self.gl.VertexAttribPointer(
                 self.solid_program.attrib_vert as u32,
@@ -2159,7 +2548,7 @@ impl Gles2Frame {
 
             // vert_position
             self.gl
-                .EnableVertexAttribArray(self.tex_programs[tex.0.texture_kind].attrib_vert_position as u32);
+                .EnableVertexAttribArray(program.attrib_vert_position as u32);
             self.gl.BindBuffer(ffi::ARRAY_BUFFER, self.vbos[1]);
             self.gl.BufferData(
                 ffi::ARRAY_BUFFER,
@@ -2169,7 +2558,7 @@ impl Gles2Frame {
             );
 
             self.gl.VertexAttribPointer(
-                self.tex_programs[tex.0.texture_kind].attrib_vert_position as u32,
+                program.attrib_vert_position as u32,
                 4,
                 ffi::FLOAT,
                 ffi::FALSE,
@@ -2179,12 +2568,9 @@ impl Gles2Frame {
 
             let damage_len = (damage.len() / 4) as i32;
             if self.supports_instancing {
+                self.gl.VertexAttribDivisor(program.attrib_vert as u32, 0);
                 self.gl
-                    .VertexAttribDivisor(self.tex_programs[tex.0.texture_kind].attrib_vert as u32, 0);
-                self.gl.VertexAttribDivisor(
-                    self.tex_programs[tex.0.texture_kind].attrib_vert_position as u32,
-                    1,
-                );
+                    .VertexAttribDivisor(program.attrib_vert_position as u32, 1);
 
                 self.gl.DrawArraysInstanced(ffi::TRIANGLE_STRIP, 0, 4, damage_len);
             } else {
@@ -2211,10 +2597,9 @@ impl Gles2Frame {
 
             self.gl.BindBuffer(ffi::ARRAY_BUFFER, 0);
             self.gl.BindTexture(target, 0);
+            self.gl.DisableVertexAttribArray(program.attrib_vert as u32);
             self.gl
-                .DisableVertexAttribArray(self.tex_programs[tex.0.texture_kind].attrib_vert as u32);
-            self.gl
-                .DisableVertexAttribArray(self.tex_programs[tex.0.texture_kind].attrib_vert_position as u32);
+                .DisableVertexAttribArray(program.attrib_vert_position as u32);
         }
 
         Ok(())
@@ -2224,4 +2609,283 @@ impl Gles2Frame {
     pub fn projection(&self) -> &[f32; 9] {
         self.current_projection.as_ref()
     }
+
+    /// Overrides the shader used to render textures for this frame with a custom one
+    /// (see [`Gles2Renderer::compile_custom_texture_shader`]).
+    ///
+    /// The override is used by all following texture rendering operations,
+    /// until [`Gles2Frame::clear_tex_program_override`] is called.
+    pub fn override_default_tex_program(
+        &mut self,
+        program: Gles2TexProgram,
+        additional_uniforms: Vec<Uniform<'static>>,
+    ) {
+        self.tex_program_override = Some((program, additional_uniforms));
+    }
+
+    /// Resets the shader used to render textures to the default one
+    pub fn clear_tex_program_override(&mut self) {
+        self.tex_program_override = None;
+    }
+
+    /// Render a custom pixel shader (see [`Gles2Renderer::compile_custom_pixel_shader`])
+    /// into the given destination rectangle.
+    ///
+    /// The damage is relative to the destination and clamped to its size.
+    pub fn render_pixel_shader_to(
+        &mut self,
+        program: &Gles2PixelProgram,
+        dest: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        additional_uniforms: &[Uniform<'_>],
+    ) -> Result<(), Gles2Error> {
+        let dest_size = dest.size;
+        let instances = damage
+            .iter()
+            .filter_map(|rect| rect.intersection(Rectangle::from_loc_and_size((0, 0), dest_size)))
+            .flat_map(|rect| {
+                [
+                    rect.loc.x as f32,
+                    rect.loc.y as f32,
+                    rect.size.w as f32,
+                    rect.size.h as f32,
+                ]
+            })
+            .collect::<Vec<_>>();
+        if instances.is_empty() {
+            return Ok(());
+        }
+
+        let mut matrix = Matrix3::from_translation(Vector2::new(dest.loc.x as f32, dest.loc.y as f32));
+        matrix = self.current_projection * matrix;
+
+        let program = &program.0;
+        unsafe {
+            self.gl.UseProgram(program.program);
+            self.gl
+                .UniformMatrix3fv(program.uniform_matrix, 1, ffi::FALSE, matrix.as_ptr());
+            self.gl
+                .Uniform2f(program.uniform_size, dest_size.w as f32, dest_size.h as f32);
+            self.gl.Uniform1f(program.uniform_alpha, alpha);
+            set_additional_uniforms(&self.gl, &program.additional_uniforms, additional_uniforms)?;
+
+            self.draw_instances(program.attrib_vert, program.attrib_position, &instances);
+        }
+
+        Ok(())
+    }
+
+    // Draws a rectangle for each instance of 4 floats (offset and scale) using the vertex
+    // attributes of the currently used program.
+    unsafe fn draw_instances(
+        &self,
+        attrib_vert: ffi::types::GLint,
+        attrib_position: ffi::types::GLint,
+        instances: &[ffi::types::GLfloat],
+    ) {
+        self.gl.EnableVertexAttribArray(attrib_vert as u32);
+        self.gl.BindBuffer(ffi::ARRAY_BUFFER, self.vbos[0]);
+        self.gl
+            .VertexAttribPointer(attrib_vert as u32, 2, ffi::FLOAT, ffi::FALSE, 0, std::ptr::null());
+
+        let vertices = if self.supports_instancing {
+            Cow::Borrowed(instances)
+        } else {
+            // Add the 4 f32s per rectangle for each of the 6 vertices.
+            let mut vertices = Vec::with_capacity(instances.len() * 6);
+            for chunk in instances.chunks(4) {
+                for _ in 0..6 {
+                    vertices.extend_from_slice(chunk);
+                }
+            }
+            Cow::Owned(vertices)
+        };
+
+        self.gl.EnableVertexAttribArray(attrib_position as u32);
+        self.gl.BindBuffer(ffi::ARRAY_BUFFER, self.vbos[1]);
+        self.gl.BufferData(
+            ffi::ARRAY_BUFFER,
+            (std::mem::size_of::<ffi::types::GLfloat>() * vertices.len()) as isize,
+            vertices.as_ptr() as *const _,
+            ffi::STREAM_DRAW,
+        );
+        self.gl.VertexAttribPointer(
+            attrib_position as u32,
+            4,
+            ffi::FLOAT,
+            ffi::FALSE,
+            0,
+            std::ptr::null(),
+        );
+
+        let count = (instances.len() / 4) as i32;
+        if self.supports_instancing {
+            self.gl.VertexAttribDivisor(attrib_vert as u32, 0);
+            self.gl.VertexAttribDivisor(attrib_position as u32, 1);
+            self.gl.DrawArraysInstanced(ffi::TRIANGLE_STRIP, 0, 4, count);
+        } else {
+            // When we have more than 10 rectangles, draw them in batches of 10.
+            for i in 0..(count - 1) / 10 {
+                self.gl.DrawArrays(ffi::TRIANGLES, 0, 60);
+
+                // Set the pointer to the next 10 rectangles.
+                let offset = (i + 1) as usize * 60 * 4 * std::mem::size_of::<ffi::types::GLfloat>();
+                self.gl.VertexAttribPointer(
+                    attrib_position as u32,
+                    4,
+                    ffi::FLOAT,
+                    ffi::FALSE,
+                    0,
+                    offset as *const _,
+                );
+            }
+
+            // Draw the up to 10 remaining rectangles.
+            let remaining = ((count - 1) % 10 + 1) * 6;
+            self.gl.DrawArrays(ffi::TRIANGLES, 0, remaining);
+        }
+
+        self.gl.BindBuffer(ffi::ARRAY_BUFFER, 0);
+        self.gl.DisableVertexAttribArray(attrib_vert as u32);
+        self.gl.DisableVertexAttribArray(attrib_position as u32);
+    }
+}
+
+// constants used by the color conversion shaders
+fn shader_transfer_function(transfer_function: TransferFunction) -> ffi::types::GLint {
+    match transfer_function {
+        TransferFunction::Srgb => 0,
+        TransferFunction::Linear => 1,
+        TransferFunction::Pq => 2,
+    }
+}
+
+// Converts a non-premultiplied clear color into the target color space and applies the color transform
+//
+// The conversions operate on premultiplied colors, so the color is converted as an opaque one
+// and its alpha value is restored afterwards.
+fn clear_color(
+    color: [f32; 4],
+    color_space: ColorSpace,
+    sdr_white_level: f32,
+    color_transform: Option<&ColorTransform>,
+) -> [f32; 4] {
+    let opaque = [color[0], color[1], color[2], 1.0];
+    let mut converted = ColorSpace::SRGB.convert(color_space, opaque, sdr_white_level);
+    if let Some(color_transform) = color_transform {
+        converted = color_transform.apply(converted);
+    }
+    [converted[0], converted[1], converted[2], color[3]]
+}
+
+// Projection of the (already swapped) output size onto the bound target, applying the output transformation
+//
+// The first row of the output ends up at the start of the target's memory,
+// so a read back of an offscreen target is ordered top to bottom.
+fn projection(output_size: Size<i32, Physical>, transform: Transform) -> Matrix3<f32> {
+    // replicate https://www.khronos.org/registry/OpenGL-Refpages/gl2.1/xhtml/glOrtho.xml
+    // glOrtho(0, width, 0, height, 1, 1);
+    let mut renderer = Matrix3::<f32>::identity();
+    let t = Matrix3::<f32>::identity();
+    let x = 2.0 / (output_size.w as f32);
+    let y = 2.0 / (output_size.h as f32);
+
+    // Rotation & Reflection
+    renderer[0][0] = x * t[0][0];
+    renderer[1][0] = x * t[0][1];
+    renderer[0][1] = y * -t[1][0];
+    renderer[1][1] = y * -t[1][1];
+
+    //Translation
+    renderer[2][0] = -(1.0f32.copysign(renderer[0][0] + renderer[1][0]));
+    renderer[2][1] = -(1.0f32.copysign(renderer[0][1] + renderer[1][1]));
+
+    // We account for OpenGLs coordinate system here
+    let flip180 = Matrix3::new(1.0, 0.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, 1.0);
+
+    flip180 * transform.matrix() * renderer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clear_color, custom_shader_source, info_log, projection, shaders};
+    use crate::{
+        backend::renderer::color::{ColorSpace, ColorTransform, DEFAULT_SDR_WHITE_LEVEL},
+        utils::Transform,
+    };
+    use cgmath::{Matrix3, Vector3};
+
+    #[test]
+    fn shader_info_log() {
+        assert_eq!(
+            info_log(b"0:3(12): error: `foo' undeclared\n\0\0\0"),
+            "0:3(12): error: `foo' undeclared"
+        );
+        // some drivers report an empty log
+        assert_eq!(info_log(b"\0"), "");
+        assert_eq!(info_log(b"no terminator"), "no terminator");
+        assert_eq!(info_log(b"invalid \xff utf8\0"), "invalid \u{fffd} utf8");
+    }
+
+    #[test]
+    fn custom_shader_defines() {
+        let defines = "#define EXTERNAL\n";
+
+        let marked = format!(
+            "precision mediump float;\n{}\nvoid main() {{}}\n",
+            shaders::CUSTOM_DEFINES_MARKER
+        );
+        assert_eq!(
+            custom_shader_source(&marked, defines),
+            "precision mediump float;\n#define EXTERNAL\n\nvoid main() {}\n"
+        );
+
+        // the defines need to follow the version directive
+        assert_eq!(
+            custom_shader_source("\n#version 100\nvoid main() {}\n", defines),
+            "#version 100\n#define EXTERNAL\nvoid main() {}\n"
+        );
+        assert_eq!(
+            custom_shader_source("void main() {}\n", defines),
+            "#define EXTERNAL\nvoid main() {}\n"
+        );
+    }
+
+    fn project(matrix: Matrix3<f32>, x: f32, y: f32) -> (f32, f32) {
+        let point = matrix * Vector3::new(x, y, 1.0);
+        (point.x, point.y)
+    }
+
+    #[test]
+    fn projection_orientation() {
+        // the top left corner maps to the start of the framebuffer (bottom left in gl coordinates),
+        // which is what offscreen read backs and scanout expect
+        let matrix = projection((128, 64).into(), Transform::Normal);
+        assert_eq!(project(matrix, 0.0, 0.0), (-1.0, -1.0));
+        assert_eq!(project(matrix, 128.0, 0.0), (1.0, -1.0));
+        assert_eq!(project(matrix, 128.0, 64.0), (1.0, 1.0));
+
+        let matrix = projection((128, 64).into(), Transform::Flipped180);
+        assert_eq!(project(matrix, 0.0, 0.0), (-1.0, 1.0));
+        assert_eq!(project(matrix, 128.0, 64.0), (1.0, -1.0));
+    }
+
+    #[test]
+    fn clear_color_is_not_premultiplied() {
+        let color = [0.5, 0.0, 0.5, 0.5];
+        assert_eq!(
+            clear_color(color, ColorSpace::SRGB, DEFAULT_SDR_WHITE_LEVEL, None),
+            color
+        );
+        assert_eq!(
+            clear_color(
+                color,
+                ColorSpace::SRGB,
+                DEFAULT_SDR_WHITE_LEVEL,
+                Some(&ColorTransform::INVERT)
+            ),
+            [0.5, 1.0, 0.5, 0.5]
+        );
+    }
 }
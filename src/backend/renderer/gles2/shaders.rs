@@ -101,3 +101,269 @@ void main() {
     gl_FragColor = color;
 }
 "#;
+
+/*
+ * Color conversion variants of the texture shaders
+ *
+ * Transfer functions are identified by the following constants:
+ * 0: sRGB, 1: linear, 2: PQ (SMPTE ST 2084)
+ *
+ * Afterwards the color transform of the frame is applied in the target color space.
+ */
+macro_rules! color_conversion {
+    () => {
+        r#"
+#ifdef GL_FRAGMENT_PRECISION_HIGH
+precision highp float;
+#else
+precision mediump float;
+#endif
+
+uniform int src_tf;
+uniform int dst_tf;
+uniform mat3 primaries;
+uniform float white_level;
+uniform mat4 color_transform;
+
+const float PQ_M1 = 0.1593017578125;
+const float PQ_M2 = 78.84375;
+const float PQ_C1 = 0.8359375;
+const float PQ_C2 = 18.8515625;
+const float PQ_C3 = 18.6875;
+const float PQ_MAX_LUMINANCE = 10000.0;
+
+vec3 srgb_to_linear(vec3 color) {
+    color = max(color, vec3(0.0));
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(vec3(0.04045), color));
+}
+
+vec3 linear_to_srgb(vec3 color) {
+    color = clamp(color, 0.0, 1.0);
+    return mix(color * 12.92, 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055, step(vec3(0.0031308), color));
+}
+
+vec3 pq_to_linear(vec3 color) {
+    vec3 p = pow(max(color, vec3(0.0)), vec3(1.0 / PQ_M2));
+    vec3 nits = pow(max(p - PQ_C1, vec3(0.0)) / (PQ_C2 - PQ_C3 * p), vec3(1.0 / PQ_M1)) * PQ_MAX_LUMINANCE;
+    return nits / white_level;
+}
+
+vec3 linear_to_pq(vec3 color) {
+    vec3 p = pow(max(color * white_level / PQ_MAX_LUMINANCE, vec3(0.0)), vec3(PQ_M1));
+    return pow((PQ_C1 + PQ_C2 * p) / (1.0 + PQ_C3 * p), vec3(PQ_M2));
+}
+
+vec4 convert_color(vec4 color) {
+    if (color.a <= 0.0) {
+        return color;
+    }
+
+    // color conversion needs to happen on straight alpha
+    vec3 rgb = color.rgb / color.a;
+    if (src_tf == 0) {
+        rgb = srgb_to_linear(rgb);
+    } else if (src_tf == 2) {
+        rgb = pq_to_linear(rgb);
+    }
+    rgb = primaries * rgb;
+    if (dst_tf == 0) {
+        rgb = linear_to_srgb(rgb);
+    } else if (dst_tf == 2) {
+        rgb = linear_to_pq(rgb);
+    }
+    rgb = clamp((color_transform * vec4(rgb, 1.0)).rgb, 0.0, 1.0);
+    return vec4(rgb * color.a, color.a);
+}
+"#
+    };
+}
+
+pub const FRAGMENT_SHADER_ABGR_COLOR: &str = concat!(
+    "#version 100\n",
+    color_conversion!(),
+    r#"
+uniform sampler2D tex;
+uniform float alpha;
+varying vec2 v_tex_coords;
+
+void main() {
+    gl_FragColor = convert_color(texture2D(tex, v_tex_coords)) * alpha;
+}
+"#
+);
+
+pub const FRAGMENT_SHADER_XBGR_COLOR: &str = concat!(
+    "#version 100\n",
+    color_conversion!(),
+    r#"
+uniform sampler2D tex;
+uniform float alpha;
+varying vec2 v_tex_coords;
+
+void main() {
+    gl_FragColor = convert_color(vec4(texture2D(tex, v_tex_coords).rgb, 1.0)) * alpha;
+}
+"#
+);
+
+pub const FRAGMENT_SHADER_EXTERNAL_COLOR: &str = concat!(
+    "#version 100\n",
+    "#extension GL_OES_EGL_image_external : require\n",
+    color_conversion!(),
+    r#"
+uniform samplerExternalOES tex;
+uniform float alpha;
+varying vec2 v_tex_coords;
+
+void main() {
+    gl_FragColor = convert_color(texture2D(tex, v_tex_coords)) * alpha;
+}
+"#
+);
+
+/*
+ * Custom shaders
+ */
+
+// Marker in custom texture shaders replaced by the defines of each variant
+pub const CUSTOM_DEFINES_MARKER: &str = "//_DEFINES_";
+
+// Defines used to compile the variants of custom texture shaders, indexed like the builtin variants
+pub const CUSTOM_TEXTURE_DEFINES: [&str; FRAGMENT_COUNT] = ["", "#define NO_ALPHA\n", "#define EXTERNAL\n"];
+
+pub const VERTEX_SHADER_PIXEL: &str = r#"
+#version 100
+
+uniform mat3 matrix;
+uniform vec2 size;
+attribute vec2 vert;
+attribute vec4 position;
+
+varying vec2 v_coords;
+
+mat2 scale(vec2 scale_vec){
+    return mat2(
+        scale_vec.x, 0.0,
+        0.0, scale_vec.y
+    );
+}
+
+void main() {
+    vec2 transform_translation = position.xy;
+    vec2 transform_scale = position.zw;
+    vec3 position = vec3(vert * scale(transform_scale) + transform_translation, 1.0);
+    v_coords = position.xy / size;
+    gl_Position = vec4(matrix * position, 1.0);
+}
+"#;
+
+/*
+ * Effect shaders, compiled as pixel shaders
+ */
+
+// Dual kawase blur, downsampling pass
+pub const FRAGMENT_SHADER_BLUR_DOWN: &str = r#"
+#version 100
+
+precision mediump float;
+uniform sampler2D tex;
+uniform vec2 half_pixel;
+uniform float offset;
+uniform float alpha;
+varying vec2 v_coords;
+
+void main() {
+    vec4 sum = texture2D(tex, v_coords) * 4.0;
+    sum += texture2D(tex, v_coords - half_pixel * offset);
+    sum += texture2D(tex, v_coords + half_pixel * offset);
+    sum += texture2D(tex, v_coords + vec2(half_pixel.x, -half_pixel.y) * offset);
+    sum += texture2D(tex, v_coords - vec2(half_pixel.x, -half_pixel.y) * offset);
+    gl_FragColor = sum / 8.0 * alpha;
+}
+"#;
+
+// Dual kawase blur, upsampling pass
+pub const FRAGMENT_SHADER_BLUR_UP: &str = r#"
+#version 100
+
+precision mediump float;
+uniform sampler2D tex;
+uniform vec2 half_pixel;
+uniform float offset;
+uniform float alpha;
+varying vec2 v_coords;
+
+void main() {
+    vec4 sum = texture2D(tex, v_coords + vec2(-half_pixel.x * 2.0, 0.0) * offset);
+    sum += texture2D(tex, v_coords + vec2(-half_pixel.x, half_pixel.y) * offset) * 2.0;
+    sum += texture2D(tex, v_coords + vec2(0.0, half_pixel.y * 2.0) * offset);
+    sum += texture2D(tex, v_coords + vec2(half_pixel.x, half_pixel.y) * offset) * 2.0;
+    sum += texture2D(tex, v_coords + vec2(half_pixel.x * 2.0, 0.0) * offset);
+    sum += texture2D(tex, v_coords + vec2(half_pixel.x, -half_pixel.y) * offset) * 2.0;
+    sum += texture2D(tex, v_coords + vec2(0.0, -half_pixel.y * 2.0) * offset);
+    sum += texture2D(tex, v_coords + vec2(-half_pixel.x, -half_pixel.y) * offset) * 2.0;
+    gl_FragColor = sum / 12.0 * alpha;
+}
+"#;
+
+// Gaussian shadow of a rounded rectangle, integrated analytically along x and sampled along y.
+// Based on https://madebyevan.com/shaders/fast-rounded-rectangle-shadows/
+pub const FRAGMENT_SHADER_SHADOW: &str = r#"
+#version 100
+
+precision mediump float;
+uniform vec2 size;
+uniform float alpha;
+uniform vec4 color;
+uniform vec4 shadow_rect;
+uniform float corner_radius;
+uniform float sigma;
+varying vec2 v_coords;
+
+float gaussian(float x, float sigma) {
+    const float pi = 3.141592653589793;
+    return exp(-(x * x) / (2.0 * sigma * sigma)) / (sqrt(2.0 * pi) * sigma);
+}
+
+vec2 erf(vec2 x) {
+    vec2 s = sign(x);
+    vec2 a = abs(x);
+    x = 1.0 + (0.278393 + (0.230389 + 0.078108 * (a * a)) * a) * a;
+    x *= x;
+    return s - s / (x * x);
+}
+
+float shadow_x(float x, float y, float sigma, float corner, vec2 half_size) {
+    float delta = min(half_size.y - corner - abs(y), 0.0);
+    float curved = half_size.x - corner + sqrt(max(0.0, corner * corner - delta * delta));
+    vec2 integral = 0.5 + 0.5 * erf((x + vec2(-curved, curved)) * (sqrt(0.5) / sigma));
+    return integral.y - integral.x;
+}
+
+float shadow(vec2 lower, vec2 upper, vec2 point, float sigma, float corner) {
+    vec2 center = (lower + upper) * 0.5;
+    vec2 half_size = (upper - lower) * 0.5;
+    point -= center;
+
+    float low = point.y - half_size.y;
+    float high = point.y + half_size.y;
+    float start = clamp(-3.0 * sigma, low, high);
+    float end = clamp(3.0 * sigma, low, high);
+
+    float step = (end - start) / 4.0;
+    float y = start + step * 0.5;
+    float value = 0.0;
+    for (int i = 0; i < 4; i++) {
+        value += shadow_x(point.x, point.y - y, sigma, corner, half_size) * gaussian(y, sigma) * step;
+        y += step;
+    }
+    return value;
+}
+
+void main() {
+    vec2 point = v_coords * size;
+    float corner = min(corner_radius, min(shadow_rect.z, shadow_rect.w) * 0.5);
+    float value = shadow(shadow_rect.xy, shadow_rect.xy + shadow_rect.zw, point, max(sigma, 0.001), corner);
+    gl_FragColor = color * value * alpha;
+}
+"#;
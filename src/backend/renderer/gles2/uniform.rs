@@ -0,0 +1,370 @@
+//! Typed uniforms for custom shaders of the [`Gles2Renderer`](super::Gles2Renderer)
+
+use std::{borrow::Cow, collections::HashMap, ffi::CString};
+
+use cgmath::{Matrix2, Matrix3, Matrix4};
+
+use super::{ffi, Gles2Error};
+
+/// Type of a uniform declared by a custom shader
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UniformType {
+    /// `float`
+    Float,
+    /// `vec2`
+    Vec2,
+    /// `vec3`
+    Vec3,
+    /// `vec4`
+    Vec4,
+    /// `int`
+    Int,
+    /// `ivec2`
+    IVec2,
+    /// `ivec3`
+    IVec3,
+    /// `ivec4`
+    IVec4,
+    /// `mat2`
+    Mat2,
+    /// `mat3`
+    Mat3,
+    /// `mat4`
+    Mat4,
+}
+
+/// Value of a uniform
+///
+/// Matrices are expected in column-major order, like used by `cgmath`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UniformValue {
+    /// `float` value
+    Float(f32),
+    /// `vec2` value
+    Vec2([f32; 2]),
+    /// `vec3` value
+    Vec3([f32; 3]),
+    /// `vec4` value
+    Vec4([f32; 4]),
+    /// `int` value
+    Int(i32),
+    /// `ivec2` value
+    IVec2([i32; 2]),
+    /// `ivec3` value
+    IVec3([i32; 3]),
+    /// `ivec4` value
+    IVec4([i32; 4]),
+    /// `mat2` value
+    Mat2([f32; 4]),
+    /// `mat3` value
+    Mat3([f32; 9]),
+    /// `mat4` value
+    Mat4([f32; 16]),
+}
+
+impl UniformValue {
+    /// Type of this value
+    pub fn uniform_type(&self) -> UniformType {
+        match self {
+            UniformValue::Float(_) => UniformType::Float,
+            UniformValue::Vec2(_) => UniformType::Vec2,
+            UniformValue::Vec3(_) => UniformType::Vec3,
+            UniformValue::Vec4(_) => UniformType::Vec4,
+            UniformValue::Int(_) => UniformType::Int,
+            UniformValue::IVec2(_) => UniformType::IVec2,
+            UniformValue::IVec3(_) => UniformType::IVec3,
+            UniformValue::IVec4(_) => UniformType::IVec4,
+            UniformValue::Mat2(_) => UniformType::Mat2,
+            UniformValue::Mat3(_) => UniformType::Mat3,
+            UniformValue::Mat4(_) => UniformType::Mat4,
+        }
+    }
+
+    // The program declaring the uniform at `location` needs to be in use
+    pub(super) unsafe fn set(&self, gl: &ffi::Gles2, location: ffi::types::GLint) {
+        match self {
+            UniformValue::Float(x) => gl.Uniform1f(location, *x),
+            UniformValue::Vec2(v) => gl.Uniform2f(location, v[0], v[1]),
+            UniformValue::Vec3(v) => gl.Uniform3f(location, v[0], v[1], v[2]),
+            UniformValue::Vec4(v) => gl.Uniform4f(location, v[0], v[1], v[2], v[3]),
+            UniformValue::Int(x) => gl.Uniform1i(location, *x),
+            UniformValue::IVec2(v) => gl.Uniform2i(location, v[0], v[1]),
+            UniformValue::IVec3(v) => gl.Uniform3i(location, v[0], v[1], v[2]),
+            UniformValue::IVec4(v) => gl.Uniform4i(location, v[0], v[1], v[2], v[3]),
+            UniformValue::Mat2(m) => gl.UniformMatrix2fv(location, 1, ffi::FALSE, m.as_ptr()),
+            UniformValue::Mat3(m) => gl.UniformMatrix3fv(location, 1, ffi::FALSE, m.as_ptr()),
+            UniformValue::Mat4(m) => gl.UniformMatrix4fv(location, 1, ffi::FALSE, m.as_ptr()),
+        }
+    }
+}
+
+impl From<f32> for UniformValue {
+    fn from(value: f32) -> Self {
+        UniformValue::Float(value)
+    }
+}
+
+impl From<[f32; 2]> for UniformValue {
+    fn from(value: [f32; 2]) -> Self {
+        UniformValue::Vec2(value)
+    }
+}
+
+impl From<(f32, f32)> for UniformValue {
+    fn from(value: (f32, f32)) -> Self {
+        UniformValue::Vec2([value.0, value.1])
+    }
+}
+
+impl From<[f32; 3]> for UniformValue {
+    fn from(value: [f32; 3]) -> Self {
+        UniformValue::Vec3(value)
+    }
+}
+
+impl From<(f32, f32, f32)> for UniformValue {
+    fn from(value: (f32, f32, f32)) -> Self {
+        UniformValue::Vec3([value.0, value.1, value.2])
+    }
+}
+
+impl From<[f32; 4]> for UniformValue {
+    fn from(value: [f32; 4]) -> Self {
+        UniformValue::Vec4(value)
+    }
+}
+
+impl From<(f32, f32, f32, f32)> for UniformValue {
+    fn from(value: (f32, f32, f32, f32)) -> Self {
+        UniformValue::Vec4([value.0, value.1, value.2, value.3])
+    }
+}
+
+impl From<i32> for UniformValue {
+    fn from(value: i32) -> Self {
+        UniformValue::Int(value)
+    }
+}
+
+impl From<[i32; 2]> for UniformValue {
+    fn from(value: [i32; 2]) -> Self {
+        UniformValue::IVec2(value)
+    }
+}
+
+impl From<[i32; 3]> for UniformValue {
+    fn from(value: [i32; 3]) -> Self {
+        UniformValue::IVec3(value)
+    }
+}
+
+impl From<[i32; 4]> for UniformValue {
+    fn from(value: [i32; 4]) -> Self {
+        UniformValue::IVec4(value)
+    }
+}
+
+impl From<Matrix2<f32>> for UniformValue {
+    fn from(value: Matrix2<f32>) -> Self {
+        let m: &[f32; 4] = value.as_ref();
+        UniformValue::Mat2(*m)
+    }
+}
+
+impl From<Matrix3<f32>> for UniformValue {
+    fn from(value: Matrix3<f32>) -> Self {
+        let m: &[f32; 9] = value.as_ref();
+        UniformValue::Mat3(*m)
+    }
+}
+
+impl From<Matrix4<f32>> for UniformValue {
+    fn from(value: Matrix4<f32>) -> Self {
+        let m: &[f32; 16] = value.as_ref();
+        UniformValue::Mat4(*m)
+    }
+}
+
+/// Declaration of an additional uniform of a custom shader
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UniformName<'a> {
+    /// Name of the uniform as used in the shader source
+    pub name: Cow<'a, str>,
+    /// Type of the uniform
+    pub type_: UniformType,
+}
+
+impl<'a> UniformName<'a> {
+    /// Declares a new uniform
+    pub fn new(name: impl Into<Cow<'a, str>>, type_: UniformType) -> Self {
+        UniformName {
+            name: name.into(),
+            type_,
+        }
+    }
+}
+
+/// Value of an additional uniform to be set for a custom shader
+#[derive(Debug, Clone, PartialEq)]
+pub struct Uniform<'a> {
+    /// Name of the uniform as used in the shader source
+    pub name: Cow<'a, str>,
+    /// Value of the uniform
+    pub value: UniformValue,
+}
+
+impl<'a> Uniform<'a> {
+    /// Creates a new uniform value
+    pub fn new(name: impl Into<Cow<'a, str>>, value: impl Into<UniformValue>) -> Self {
+        Uniform {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Converts this uniform into one not borrowing its name
+    pub fn into_owned(self) -> Uniform<'static> {
+        Uniform {
+            name: Cow::Owned(self.name.into_owned()),
+            value: self.value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct UniformDesc {
+    pub location: ffi::types::GLint,
+    pub type_: UniformType,
+}
+
+// GL expects the names of uniforms as NUL terminated strings
+pub(super) fn uniform_c_name(name: &str) -> Result<CString, Gles2Error> {
+    CString::new(name).map_err(|_| Gles2Error::InvalidUniformName(name.to_owned()))
+}
+
+// Returns the location of a declared uniform, if the provided value matches the declared type
+pub(super) fn declared_location(
+    declared: &HashMap<String, UniformDesc>,
+    uniform: &Uniform<'_>,
+) -> Result<ffi::types::GLint, Gles2Error> {
+    let desc = declared
+        .get(&*uniform.name)
+        .ok_or_else(|| Gles2Error::UnknownUniform(uniform.name.clone().into_owned()))?;
+    if desc.type_ != uniform.value.uniform_type() {
+        return Err(Gles2Error::UniformTypeMismatch {
+            name: uniform.name.clone().into_owned(),
+            declared: desc.type_,
+            provided: uniform.value.uniform_type(),
+        });
+    }
+    Ok(desc.location)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::SquareMatrix;
+
+    #[test]
+    fn value_types() {
+        assert_eq!(Uniform::new("a", 1.0f32).value.uniform_type(), UniformType::Float);
+        assert_eq!(
+            Uniform::new("a", (1.0f32, 2.0f32)).value.uniform_type(),
+            UniformType::Vec2
+        );
+        assert_eq!(
+            Uniform::new("a", [1.0f32, 2.0, 3.0]).value.uniform_type(),
+            UniformType::Vec3
+        );
+        assert_eq!(
+            Uniform::new("a", (1.0f32, 2.0f32, 3.0f32, 4.0f32))
+                .value
+                .uniform_type(),
+            UniformType::Vec4
+        );
+        assert_eq!(Uniform::new("a", 1).value.uniform_type(), UniformType::Int);
+        assert_eq!(Uniform::new("a", [1, 2]).value.uniform_type(), UniformType::IVec2);
+        assert_eq!(
+            Uniform::new("a", [1, 2, 3, 4]).value.uniform_type(),
+            UniformType::IVec4
+        );
+        assert_eq!(
+            Uniform::new("a", Matrix3::<f32>::identity()).value.uniform_type(),
+            UniformType::Mat3
+        );
+    }
+
+    #[test]
+    fn matrices_are_column_major() {
+        // cgmath takes the columns as arguments
+        let matrix = Matrix2::new(1.0f32, 2.0, 3.0, 4.0);
+        assert_eq!(
+            UniformValue::from(matrix),
+            UniformValue::Mat2([1.0, 2.0, 3.0, 4.0])
+        );
+        let matrix = Matrix3::from_translation(cgmath::Vector2::new(5.0f32, 6.0));
+        assert_eq!(
+            UniformValue::from(matrix),
+            UniformValue::Mat3([1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 5.0, 6.0, 1.0])
+        );
+    }
+
+    #[test]
+    fn uniform_validation() {
+        let mut declared = HashMap::new();
+        declared.insert(
+            String::from("offset"),
+            UniformDesc {
+                location: 3,
+                type_: UniformType::Vec2,
+            },
+        );
+        declared.insert(
+            String::from("count"),
+            UniformDesc {
+                location: 4,
+                type_: UniformType::Int,
+            },
+        );
+
+        assert_eq!(
+            declared_location(&declared, &Uniform::new("offset", (1.0f32, 0.5f32))).unwrap(),
+            3
+        );
+        assert_eq!(
+            declared_location(&declared, &Uniform::new("count", 2)).unwrap(),
+            4
+        );
+
+        match declared_location(&declared, &Uniform::new("missing", 1.0f32)) {
+            Err(Gles2Error::UnknownUniform(name)) => assert_eq!(name, "missing"),
+            res => panic!("unexpected result: {:?}", res),
+        }
+        // an int is no float, even though it would fit
+        match declared_location(&declared, &Uniform::new("count", 2.0f32)) {
+            Err(Gles2Error::UniformTypeMismatch {
+                name,
+                declared,
+                provided,
+            }) => {
+                assert_eq!(name, "count");
+                assert_eq!(declared, UniformType::Int);
+                assert_eq!(provided, UniformType::Float);
+            }
+            res => panic!("unexpected result: {:?}", res),
+        }
+        match declared_location(&declared, &Uniform::new("offset", [1.0f32, 0.5, 0.0])) {
+            Err(Gles2Error::UniformTypeMismatch { provided, .. }) => assert_eq!(provided, UniformType::Vec3),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+
+    #[test]
+    fn uniform_names_without_nul() {
+        assert_eq!(uniform_c_name("offset").unwrap().as_bytes(), b"offset");
+        match uniform_c_name("off\0set") {
+            Err(Gles2Error::InvalidUniformName(name)) => assert_eq!(name, "off\0set"),
+            res => panic!("unexpected result: {:?}", res),
+        }
+    }
+}
@@ -39,10 +39,16 @@ pub mod multigpu;
 
 pub mod utils;
 
+pub mod color;
+
 pub mod element;
 
 pub mod damage;
 
+#[cfg(test)]
+#[allow(dead_code)]
+pub(crate) mod test;
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 /// Texture filtering methods
 pub enum TextureFilter {
@@ -187,6 +193,24 @@ pub trait Frame {
         alpha: f32,
     ) -> Result<(), Self::Error>;
 
+    /// Set a [`ColorTransform`](color::ColorTransform) applied to everything rendered afterwards in this frame.
+    ///
+    /// Passing `None` renders colors unmodified again, which is also the state of every new frame.
+    /// Renderers might not apply the transformation to content drawn with custom shaders.
+    ///
+    /// The default implementation does not support color transformations and ignores the call.
+    fn set_color_transform(&mut self, transform: Option<color::ColorTransform>) -> Result<(), Self::Error> {
+        let _ = transform;
+        Ok(())
+    }
+
+    /// The [`ColorTransform`](color::ColorTransform) currently applied by this frame.
+    ///
+    /// Renderers not supporting color transformations always return `None`.
+    fn color_transform(&self) -> Option<color::ColorTransform> {
+        None
+    }
+
     /// Output transformation that is applied to this frame
     fn transformation(&self) -> Transform;
 }
@@ -1250,6 +1250,16 @@ where
         }
     }
 
+    fn set_color_transform(&mut self, transform: Option<color::ColorTransform>) -> Result<(), Self::Error> {
+        unsafe { &mut *self.frame }
+            .set_color_transform(transform)
+            .map_err(Error::Render)
+    }
+
+    fn color_transform(&self) -> Option<color::ColorTransform> {
+        unsafe { &*self.frame }.color_transform()
+    }
+
     fn transformation(&self) -> Transform {
         unsafe { &mut *self.frame }.transformation()
     }
@@ -0,0 +1,264 @@
+//! Renderer recording its operations, to test renderer-agnostic code without a gpu
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::utils::{Buffer as BufferCoord, Logical, Physical, Point, Rectangle, Scale, Size, Transform};
+
+use super::{
+    element::{Id, RenderElement},
+    utils::CommitCounter,
+    Bind, Frame, ImportMem, Offscreen, Renderer, Texture, TextureFilter, Unbind,
+};
+
+/// Operation executed by the [`DummyRenderer`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op {
+    Import(Size<i32, BufferCoord>),
+    Bind(Size<i32, BufferCoord>),
+    Unbind,
+    Render(Size<i32, Physical>, Transform),
+    Clear([f32; 4], Vec<Rectangle<i32, Physical>>),
+    RenderTexture {
+        dst: Rectangle<i32, Physical>,
+        alpha: f32,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("dummy renderer error")]
+pub struct DummyError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DummyTexture {
+    size: Size<i32, BufferCoord>,
+}
+
+impl Texture for DummyTexture {
+    fn width(&self) -> u32 {
+        self.size.w as u32
+    }
+
+    fn height(&self) -> u32 {
+        self.size.h as u32
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DummyRenderer {
+    pub ops: Rc<RefCell<Vec<Op>>>,
+}
+
+#[derive(Debug)]
+pub struct DummyFrame {
+    ops: Rc<RefCell<Vec<Op>>>,
+    transform: Transform,
+}
+
+impl Renderer for DummyRenderer {
+    type Error = DummyError;
+    type TextureId = DummyTexture;
+    type Frame = DummyFrame;
+
+    fn id(&self) -> usize {
+        0
+    }
+
+    fn downscale_filter(&mut self, _filter: TextureFilter) -> Result<(), DummyError> {
+        Ok(())
+    }
+
+    fn upscale_filter(&mut self, _filter: TextureFilter) -> Result<(), DummyError> {
+        Ok(())
+    }
+
+    fn render<F, R>(
+        &mut self,
+        output_size: Size<i32, Physical>,
+        dst_transform: Transform,
+        rendering: F,
+    ) -> Result<R, DummyError>
+    where
+        F: FnOnce(&mut Self, &mut DummyFrame) -> R,
+    {
+        self.ops.borrow_mut().push(Op::Render(output_size, dst_transform));
+        let mut frame = DummyFrame {
+            ops: self.ops.clone(),
+            transform: dst_transform,
+        };
+        Ok(rendering(self, &mut frame))
+    }
+}
+
+impl Frame for DummyFrame {
+    type Error = DummyError;
+    type TextureId = DummyTexture;
+
+    fn clear(&mut self, color: [f32; 4], at: &[Rectangle<i32, Physical>]) -> Result<(), DummyError> {
+        self.ops.borrow_mut().push(Op::Clear(color, at.to_vec()));
+        Ok(())
+    }
+
+    fn render_texture_from_to(
+        &mut self,
+        _texture: &DummyTexture,
+        _src: Rectangle<f64, BufferCoord>,
+        dst: Rectangle<i32, Physical>,
+        _damage: &[Rectangle<i32, Physical>],
+        _src_transform: Transform,
+        alpha: f32,
+    ) -> Result<(), DummyError> {
+        self.ops.borrow_mut().push(Op::RenderTexture { dst, alpha });
+        Ok(())
+    }
+
+    fn transformation(&self) -> Transform {
+        self.transform
+    }
+}
+
+impl Bind<DummyTexture> for DummyRenderer {
+    fn bind(&mut self, target: DummyTexture) -> Result<(), DummyError> {
+        self.ops.borrow_mut().push(Op::Bind(target.size));
+        Ok(())
+    }
+}
+
+impl Unbind for DummyRenderer {
+    fn unbind(&mut self) -> Result<(), DummyError> {
+        self.ops.borrow_mut().push(Op::Unbind);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "wayland_frontend")]
+impl super::ImportAll for DummyRenderer {
+    fn import_buffer(
+        &mut self,
+        _buffer: &wayland_server::protocol::wl_buffer::WlBuffer,
+        _surface: Option<&crate::wayland::compositor::SurfaceData>,
+        _damage: &[Rectangle<i32, BufferCoord>],
+    ) -> Option<Result<DummyTexture, DummyError>> {
+        None
+    }
+}
+
+impl ImportMem for DummyRenderer {
+    fn import_memory(
+        &mut self,
+        _data: &[u8],
+        size: Size<i32, BufferCoord>,
+        _flipped: bool,
+    ) -> Result<DummyTexture, DummyError> {
+        self.ops.borrow_mut().push(Op::Import(size));
+        Ok(DummyTexture { size })
+    }
+
+    fn update_memory(
+        &mut self,
+        _texture: &DummyTexture,
+        _data: &[u8],
+        _region: Rectangle<i32, BufferCoord>,
+    ) -> Result<(), DummyError> {
+        Ok(())
+    }
+}
+
+impl Offscreen<DummyTexture> for DummyRenderer {
+    fn create_buffer(&mut self, size: Size<i32, BufferCoord>) -> Result<DummyTexture, DummyError> {
+        Ok(DummyTexture { size })
+    }
+}
+
+/// Element clearing its geometry with black, using its alpha value as the alpha of the color
+#[derive(Debug)]
+pub struct DummyElement {
+    pub id: Id,
+    pub geometry: Rectangle<i32, Logical>,
+    pub damage: Vec<Rectangle<i32, Logical>>,
+    pub opaque_regions: Vec<Rectangle<i32, Logical>>,
+    pub alpha: f32,
+}
+
+impl DummyElement {
+    pub fn new(geometry: Rectangle<i32, Logical>) -> Self {
+        DummyElement {
+            id: Id::new(),
+            geometry,
+            damage: Vec::new(),
+            opaque_regions: Vec::new(),
+            alpha: 1.0,
+        }
+    }
+}
+
+impl<R: Renderer> RenderElement<R> for DummyElement {
+    fn id(&self) -> &Id {
+        &self.id
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        CommitCounter::default()
+    }
+
+    fn src(&self) -> Rectangle<f64, BufferCoord> {
+        Rectangle::from_loc_and_size(
+            (0.0, 0.0),
+            (self.geometry.size.w as f64, self.geometry.size.h as f64),
+        )
+    }
+
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.geometry.to_physical_precise_round(scale)
+    }
+
+    fn damage_since(
+        &self,
+        scale: Scale<f64>,
+        _commit: Option<CommitCounter>,
+    ) -> Vec<Rectangle<i32, Physical>> {
+        self.damage
+            .iter()
+            .map(|rect| rect.to_physical_precise_round(scale))
+            .collect()
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> Vec<Rectangle<i32, Physical>> {
+        self.opaque_regions
+            .iter()
+            .map(|rect| rect.to_physical_precise_round(scale))
+            .collect()
+    }
+
+    fn alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        log: &slog::Logger,
+    ) -> Result<(), R::Error> {
+        self.draw_with_alpha(renderer, frame, location, scale, damage, self.alpha, log)
+    }
+
+    fn draw_with_alpha(
+        &self,
+        _renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+        _damage: &[Rectangle<i32, Physical>],
+        alpha: f32,
+        _log: &slog::Logger,
+    ) -> Result<(), R::Error> {
+        let size = RenderElement::<R>::geometry(self, scale).size;
+        frame.clear(
+            [0.0, 0.0, 0.0, alpha],
+            &[Rectangle::from_loc_and_size(location, size)],
+        )
+    }
+}
@@ -3,9 +3,11 @@ use crate::{
         buffer_dimensions, buffer_has_alpha,
         element::{
             surface::{render_elements_from_surface_tree, WaylandSurfaceRenderElement},
+            texture::TextureRenderBuffer,
+            utils::RelocateRenderElement,
             RenderElement,
         },
-        ImportAll, Renderer,
+        Bind, Frame, ImportAll, Offscreen, Renderer, Texture, Unbind,
     },
     utils::{Buffer as BufferCoord, Coordinate, Logical, Physical, Point, Rectangle, Scale, Size, Transform},
     wayland::{
@@ -41,6 +43,7 @@ pub type RendererSurfaceStateUserData = RefCell<RendererSurfaceState>;
 pub struct RendererSurfaceState {
     pub(crate) buffer_dimensions: Option<Size<i32, BufferCoord>>,
     pub(crate) buffer_scale: i32,
+    pub(crate) client_scale: i32,
     pub(crate) buffer_transform: Transform,
     pub(crate) buffer_delta: Option<Point<i32, Logical>>,
     pub(crate) buffer_has_alpha: Option<bool>,
@@ -73,7 +76,9 @@ impl RendererSurfaceState {
                     return;
                 }
                 self.buffer_has_alpha = buffer_has_alpha(&buffer);
-                self.buffer_scale = attrs.buffer_scale;
+                // clients unaware of output scales get their buffers scaled by the compositor
+                self.client_scale = compositor::client_scale(states) as i32;
+                self.buffer_scale = attrs.buffer_scale * self.client_scale;
                 self.buffer_transform = attrs.buffer_transform.into();
 
                 if let Some(old_buffer) = std::mem::replace(&mut self.buffer, Some(buffer)) {
@@ -123,6 +128,7 @@ impl RendererSurfaceState {
                         .iter()
                         .map(|(kind, rect)| {
                             let dest_size = self.surface_view.unwrap().dst;
+                            let rect = client_rect_to_logical(*rect, self.client_scale);
 
                             let rect_constrained_loc = rect
                                 .loc
@@ -558,10 +564,13 @@ where
 
         render_damage.extend(damage);
 
-        opaque_regions.extend(element.opaque_regions(scale).into_iter().map(|mut region| {
-            region.loc += element_geometry.loc;
-            region
-        }));
+        // a translucent element does not hide anything below it
+        if element.alpha() >= 1.0 {
+            opaque_regions.extend(element.opaque_regions(scale).into_iter().map(|mut region| {
+                region.loc += element_geometry.loc;
+                region
+            }));
+        }
         render_elements.insert(0, element);
     }
 
@@ -615,3 +624,172 @@ where
 
     Ok(Some(render_damage))
 }
+
+/// Renders a surface and its subsurfaces into a newly created [`TextureRenderBuffer`]
+///
+/// The snapshot stays valid after the client released or destroyed its buffers,
+/// which allows to e.g. play an animation after a window got unmapped.
+///
+/// - `scale` is the integer scale the snapshot is rendered with and will be set as the buffer scale.
+///
+/// The snapshot is rendered by binding a new texture, which is unbound afterwards. Any previously
+/// bound target has to be bound again before rendering to it.
+///
+/// Returns the buffer and the offset of its top-left corner relative to the location of the surface,
+/// as subsurfaces may extend beyond their parent. Returns `None` if the surface tree has no content.
+pub fn snapshot_surface_tree<R, T>(
+    renderer: &mut R,
+    surface: &WlSurface,
+    scale: i32,
+    log: &slog::Logger,
+) -> Result<Option<(TextureRenderBuffer<T>, Point<i32, Physical>)>, <R as Renderer>::Error>
+where
+    R: Renderer<TextureId = T> + ImportAll + Offscreen<T>,
+    T: Texture + Clone + 'static,
+{
+    let elements: Vec<WaylandSurfaceRenderElement> =
+        render_elements_from_surface_tree(surface, (0, 0), Scale::from(scale as f64));
+    snapshot_render_elements(renderer, &elements, scale, log)
+}
+
+// renders elements into a new texture, moving the top-left corner of their bounding box to the origin
+fn snapshot_render_elements<R, T, E>(
+    renderer: &mut R,
+    elements: &[E],
+    scale: i32,
+    log: &slog::Logger,
+) -> Result<Option<(TextureRenderBuffer<T>, Point<i32, Physical>)>, <R as Renderer>::Error>
+where
+    R: Renderer<TextureId = T> + ImportAll + Offscreen<T>,
+    T: Texture + Clone + 'static,
+    E: RenderElement<R>,
+{
+    let scale = Scale::from(scale as f64);
+    let bbox = elements
+        .iter()
+        .map(|element| element.geometry(scale))
+        .reduce(|bbox, geometry| bbox.merge(geometry));
+    let bbox = match bbox {
+        Some(bbox) if !bbox.is_empty() => bbox,
+        _ => return Ok(None),
+    };
+
+    let elements = elements
+        .iter()
+        .map(|element| RelocateRenderElement::from_element(element, (-bbox.loc.x, -bbox.loc.y)))
+        .collect::<Vec<_>>();
+    let texture = renderer.create_buffer(Size::from((bbox.size.w, bbox.size.h)))?;
+    renderer.bind(texture.clone())?;
+    // rendering with `Transform::Normal` results in the same row order as buffers of clients,
+    // so the texture can be sampled like any imported buffer
+    let damage = [Rectangle::from_loc_and_size((0, 0), bbox.size)];
+    let res = renderer
+        .render(bbox.size, Transform::Normal, |renderer, frame| {
+            frame.clear([0.0, 0.0, 0.0, 0.0], &damage)?;
+            draw_render_elements(renderer, frame, scale, &*elements, &damage, log)
+        })
+        .and_then(std::convert::identity);
+    renderer.unbind()?;
+    res?;
+
+    let buffer =
+        TextureRenderBuffer::from_texture(renderer, texture, scale.x as i32, Transform::Normal, None);
+    Ok(Some((buffer, bbox.loc)))
+}
+
+// Regions of clients unaware of output scales are expressed in a coordinate space `scale` times
+// larger than the logical one, only the logical pixels they cover entirely are kept
+fn client_rect_to_logical(rect: Rectangle<i32, Logical>, scale: i32) -> Rectangle<i32, Logical> {
+    let scale = scale.max(1);
+    let end = rect.loc + rect.size.to_point();
+    let loc =
+        Point::<i32, Logical>::from((-(-rect.loc.x).div_euclid(scale), -(-rect.loc.y).div_euclid(scale)));
+    let end = Point::<i32, Logical>::from((end.x.div_euclid(scale), end.y.div_euclid(scale)));
+    Rectangle::from_loc_and_size(loc, ((end.x - loc.x).max(0), (end.y - loc.y).max(0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::renderer::{
+        element::texture::TextureRenderElement,
+        test::{DummyElement, DummyRenderer, Op},
+    };
+
+    #[test]
+    fn snapshot_moves_bounding_box_to_origin() {
+        let mut renderer = DummyRenderer::default();
+        let log = crate::slog_or_fallback(None);
+        let elements = [
+            DummyElement::new(Rectangle::from_loc_and_size((0, 0), (30, 30))),
+            // e.g. a subsurface above and left of its parent
+            DummyElement::new(Rectangle::from_loc_and_size((-10, -5), (20, 10))),
+        ];
+
+        let (buffer, offset) = snapshot_render_elements(&mut renderer, &elements, 2, &log)
+            .unwrap()
+            .unwrap();
+        assert_eq!(offset, Point::from((-20, -10)));
+        let ops = renderer.ops.borrow();
+        assert_eq!(ops[0], Op::Bind((80, 70).into()));
+        assert_eq!(ops[1], Op::Render((80, 70).into(), Transform::Normal));
+        // elements are drawn bottom to top
+        let clears = ops
+            .iter()
+            .filter_map(|op| match op {
+                Op::Clear(color, rects) if color[3] == 1.0 => Some(rects[0]),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            clears,
+            vec![
+                Rectangle::from_loc_and_size((0, 0), (40, 20)),
+                Rectangle::from_loc_and_size((20, 10), (60, 60)),
+            ]
+        );
+        assert_eq!(ops.last(), Some(&Op::Unbind));
+        drop(ops);
+
+        let element = TextureRenderElement::from_texture_render_buffer(offset.to_f64(), &buffer, None, None);
+        assert_eq!(
+            RenderElement::<DummyRenderer>::geometry(&element, Scale::from(2.0)),
+            Rectangle::from_loc_and_size((-20, -10), (80, 70))
+        );
+        assert_eq!(
+            RenderElement::<DummyRenderer>::transform(&element),
+            Transform::Normal
+        );
+    }
+
+    #[test]
+    fn empty_snapshot() {
+        let mut renderer = DummyRenderer::default();
+        let log = crate::slog_or_fallback(None);
+        let elements = [DummyElement::new(Rectangle::from_loc_and_size((5, 5), (0, 0)))];
+
+        assert!(snapshot_render_elements(&mut renderer, &elements, 1, &log)
+            .unwrap()
+            .is_none());
+        assert!(renderer.ops.borrow().is_empty());
+    }
+
+    #[test]
+    fn client_regions_shrink_to_logical_pixels() {
+        let rect = Rectangle::from_loc_and_size((10, 20), (30, 40));
+        assert_eq!(client_rect_to_logical(rect, 1), rect);
+        assert_eq!(
+            client_rect_to_logical(rect, 2),
+            Rectangle::from_loc_and_size((5, 10), (15, 20))
+        );
+        // partially covered logical pixels are not opaque
+        assert_eq!(
+            client_rect_to_logical(Rectangle::from_loc_and_size((1, -3), (6, 6)), 2),
+            Rectangle::from_loc_and_size((1, -1), (2, 2))
+        );
+        assert_eq!(
+            client_rect_to_logical(Rectangle::from_loc_and_size((1, 1), (2, 2)), 3),
+            Rectangle::from_loc_and_size((1, 1), (0, 0))
+        );
+    }
+}
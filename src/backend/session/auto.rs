@@ -37,7 +37,7 @@ use super::libseat::{LibSeatSession, LibSeatSessionNotifier};
 use super::logind::{self, LogindSession, LogindSessionNotifier};
 use super::{
     direct::{self, DirectSession, DirectSessionNotifier},
-    AsErrno, Session, Signal as SessionSignal,
+    AsErrno, Session, SessionState, Signal as SessionSignal,
 };
 use crate::utils::signaling::Signaler;
 use nix::fcntl::OFlag;
@@ -197,6 +197,17 @@ impl AutoSessionNotifier {
             AutoSessionNotifier::LibSeat(ref direct) => direct.signaler(),
         }
     }
+
+    /// Get a handle to the state of this session and its devices.
+    pub fn state(&self) -> SessionState {
+        match *self {
+            #[cfg(feature = "backend_session_logind")]
+            AutoSessionNotifier::Logind(ref logind) => logind.state(),
+            AutoSessionNotifier::Direct(ref direct) => direct.state(),
+            #[cfg(feature = "backend_session_libseat")]
+            AutoSessionNotifier::LibSeat(ref direct) => direct.state(),
+        }
+    }
 }
 
 impl EventSource for AutoSessionNotifier {
@@ -35,7 +35,7 @@
 //! a calloop event source to have its events processed.
 
 use crate::{
-    backend::session::{AsErrno, Session, Signal as SessionSignal},
+    backend::session::{AsErrno, Session, SessionState, Signal as SessionSignal},
     utils::signaling::Signaler,
 };
 use dbus::{
@@ -53,7 +53,6 @@ use std::{
     os::unix::io::RawFd,
     path::Path,
     rc::{Rc, Weak},
-    sync::atomic::{AtomicBool, Ordering},
 };
 
 use calloop::{EventSource, Poll, PostAction, Readiness, Token, TokenFactory};
@@ -66,7 +65,7 @@ struct LogindSessionImpl {
     session_id: String,
     conn: RefCell<DBusConnection>,
     session_path: DbusPath<'static>,
-    active: AtomicBool,
+    state: SessionState,
     signaler: Signaler<SessionSignal>,
     seat: String,
     logger: ::slog::Logger,
@@ -79,7 +78,7 @@ impl fmt::Debug for LogindSessionImpl {
             .field("session_id", &self.session_id)
             .field("conn", &"...")
             .field("session_path", &self.session_path)
-            .field("active", &self.active)
+            .field("state", &self.state)
             .field("signaler", &self.signaler)
             .field("seat", &self.seat)
             .field("logger", &self.logger)
@@ -191,7 +190,7 @@ impl LogindSession {
             session_id: session_id.clone(),
             conn,
             session_path,
-            active: AtomicBool::new(true),
+            state: SessionState::new(true),
             signaler: Signaler::new(),
             seat: seat.clone(),
             logger: logger.new(o!("id" => session_id, "seat" => seat.clone(), "vt" => format!("{:?}", &vt))),
@@ -222,6 +221,11 @@ impl LogindSessionNotifier {
     pub fn signaler(&self) -> Signaler<SessionSignal> {
         self.internal.signaler.clone()
     }
+
+    /// Get a handle to the state of this session and its devices.
+    pub fn state(&self) -> SessionState {
+        self.internal.state.clone()
+    }
 }
 
 impl LogindSessionImpl {
@@ -282,8 +286,7 @@ impl LogindSessionImpl {
             //Ok... now what?
             //This session will never live again, but the user maybe has other sessions open
             //So lets just put it to sleep.. forever
-            self.signaler.signal(SessionSignal::PauseSession);
-            self.active.store(false, Ordering::SeqCst);
+            self.state.set_active(&self.signaler, false);
             warn!(self.logger, "Session is now considered inactive");
         } else if &*message.interface().unwrap() == "org.freedesktop.login1.Session" {
             if &*message.member().unwrap() == "PauseDevice" {
@@ -309,7 +312,9 @@ impl LogindSessionImpl {
                 // notifications about it.
                 // This is handled via udev and is not part of our session api.
                 if pause_type != "gone" {
-                    self.signaler.signal(SessionSignal::PauseDevice { major, minor });
+                    self.state.pause_device(&self.signaler, major, minor);
+                } else {
+                    self.state.device_removed(major, minor);
                 }
                 // the other possible types are "force" or "gone" (unplugged),
                 // both expect no acknowledgement (note even this is not *really* necessary,
@@ -331,11 +336,7 @@ impl LogindSessionImpl {
                 let minor = minor.ok_or(Error::UnexpectedMethodReturn)?;
                 let fd = fd.ok_or(Error::UnexpectedMethodReturn)?.into_fd();
                 debug!(self.logger, "Reactivating device ({},{})", major, minor);
-                self.signaler.signal(SessionSignal::ActivateDevice {
-                    major,
-                    minor,
-                    new_fd: Some(fd),
-                });
+                self.state.activate_device(&self.signaler, major, minor, Some(fd));
             }
         } else if &*message.interface().unwrap() == "org.freedesktop.DBus.Properties"
             && &*message.member().unwrap() == "PropertiesChanged"
@@ -347,7 +348,8 @@ impl LogindSessionImpl {
             let mut changed = changed.ok_or(Error::UnexpectedMethodReturn)?;
             if let Some((_, mut value)) = changed.find(|&(ref key, _)| key == "Active") {
                 if let Some(active) = Get::get(&mut value.0) {
-                    self.active.store(active, Ordering::SeqCst);
+                    // logind pauses and resumes the devices on its own
+                    self.state.set_active(&self.signaler, active);
                 }
             }
         } else {
@@ -370,8 +372,7 @@ impl Session for LogindSession {
     fn open(&mut self, path: &Path, _flags: OFlag) -> Result<RawFd, Error> {
         if let Some(session) = self.internal.upgrade() {
             let stat = stat(path).map_err(Error::FailedToStatDevice)?;
-            // TODO handle paused
-            let (fd, _paused) = LogindSessionImpl::blocking_call(
+            let (fd, paused) = LogindSessionImpl::blocking_call(
                 &*session.conn.borrow(),
                 "org.freedesktop.login1",
                 session.session_path.clone(),
@@ -384,6 +385,7 @@ impl Session for LogindSession {
             )?
             .get2::<OwnedFd, bool>();
             let fd = fd.ok_or(Error::UnexpectedMethodReturn)?.into_fd();
+            session.state.device_opened(fd, paused.unwrap_or(false));
             Ok(fd)
         } else {
             Err(Error::SessionLost)
@@ -393,6 +395,7 @@ impl Session for LogindSession {
     fn close(&mut self, fd: RawFd) -> Result<(), Error> {
         if let Some(session) = self.internal.upgrade() {
             let stat = fstat(fd).map_err(Error::FailedToStatDevice)?;
+            session.state.device_closed(fd);
             LogindSessionImpl::blocking_call(
                 &*session.conn.borrow(),
                 "org.freedesktop.login1",
@@ -412,7 +415,7 @@ impl Session for LogindSession {
 
     fn is_active(&self) -> bool {
         if let Some(internal) = self.internal.upgrade() {
-            internal.active.load(Ordering::SeqCst)
+            internal.state.active()
         } else {
             false
         }
@@ -46,7 +46,7 @@
 //! The [`DirectSessionNotifier`] is to be inserted into
 //! a calloop event source to have its events processed.
 
-use super::{AsErrno, Session, Signal as SessionSignal};
+use super::{AsErrno, Session, SessionState, Signal as SessionSignal};
 use crate::utils::signaling::Signaler;
 
 use calloop::signals::{Signal, SignalError, Signals};
@@ -62,10 +62,6 @@ use std::{
     os::unix::io::RawFd,
     path::{Path, PathBuf},
     str::FromStr,
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
 };
 #[cfg(feature = "backend_udev")]
 use udev::Device as UdevDevice;
@@ -155,7 +151,7 @@ fn is_tty_device(dev: dev_t, path: Option<&Path>) -> bool {
 #[derive(Debug)]
 pub struct DirectSession {
     tty: RawFd,
-    active: Arc<AtomicBool>,
+    state: SessionState,
     vt: i32,
     old_keyboard_mode: i32,
     logger: ::slog::Logger,
@@ -164,7 +160,7 @@ pub struct DirectSession {
 /// Notifier of the virtual terminal direct kernel interface
 pub struct DirectSessionNotifier {
     tty: RawFd,
-    active: Arc<AtomicBool>,
+    state: SessionState,
     signaler: Signaler<SessionSignal>,
     signal: Signal,
     logger: ::slog::Logger,
@@ -175,7 +171,7 @@ impl fmt::Debug for DirectSessionNotifier {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("DirectSessionNotifier")
             .field("tty", &self.tty)
-            .field("active", &self.active)
+            .field("state", &self.state)
             .field("signaler", &self.signaler)
             .field("signal", &self.signal)
             .field("logger", &self.logger)
@@ -249,20 +245,20 @@ impl DirectSession {
                 dup(0 /*stdin*/).map_err(|source| Error::FailedToOpenTTY(String::from("<stdin>"), source))
             })?;
 
-        let active = Arc::new(AtomicBool::new(true));
+        let state = SessionState::new(true);
 
         match DirectSession::setup_tty(tty, fd, logger.clone()) {
             Ok((vt, old_keyboard_mode, signal)) => Ok((
                 DirectSession {
                     tty: fd,
-                    active: active.clone(),
+                    state: state.clone(),
                     vt,
                     old_keyboard_mode,
                     logger: logger.new(o!("vt" => format!("{}", vt), "component" => "session")),
                 },
                 DirectSessionNotifier {
                     tty: fd,
-                    active,
+                    state,
                     signaler: Signaler::new(),
                     signal,
                     logger: logger.new(o!("vt" => format!("{}", vt), "component" => "session_notifier")),
@@ -352,16 +348,18 @@ impl Session for DirectSession {
         debug!(self.logger, "Opening device: {:?}", path);
         let fd = open(path, flags, Mode::empty())?;
         trace!(self.logger, "Fd num: {:?}", fd);
+        self.state.device_opened(fd, !self.state.active());
         Ok(fd)
     }
 
     fn close(&mut self, fd: RawFd) -> nix::Result<()> {
         debug!(self.logger, "Closing device: {:?}", fd);
+        self.state.device_closed(fd);
         close(fd)
     }
 
     fn is_active(&self) -> bool {
-        self.active.load(Ordering::SeqCst)
+        self.state.active()
     }
 
     fn seat(&self) -> String {
@@ -413,10 +411,9 @@ pub struct Id(usize);
 
 impl DirectSessionNotifier {
     fn signal_received(&mut self) {
-        if self.active.load(Ordering::SeqCst) {
+        if self.state.active() {
             info!(self.logger, "Session shall become inactive.");
-            self.signaler.signal(SessionSignal::PauseSession);
-            self.active.store(false, Ordering::SeqCst);
+            self.state.pause(&self.signaler);
             unsafe {
                 tty::vt_rel_disp(self.tty, 1).expect("Unable to release tty lock");
             }
@@ -426,8 +423,7 @@ impl DirectSessionNotifier {
             unsafe {
                 tty::vt_rel_disp(self.tty, tty::VT_ACKACQ).expect("Unable to acquire tty lock");
             }
-            self.signaler.signal(SessionSignal::ActivateSession);
-            self.active.store(true, Ordering::SeqCst);
+            self.state.activate(&self.signaler);
             info!(self.logger, "Session is now active again");
         }
     }
@@ -438,6 +434,11 @@ impl DirectSessionNotifier {
     pub fn signaler(&self) -> Signaler<SessionSignal> {
         self.signaler.clone()
     }
+
+    /// Get a handle to the state of this session and its devices.
+    pub fn state(&self) -> SessionState {
+        self.state.clone()
+    }
 }
 
 impl calloop::EventSource for DirectSessionNotifier {
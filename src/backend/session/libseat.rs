@@ -10,10 +10,6 @@ use std::{
     os::unix::io::RawFd,
     path::Path,
     rc::{Rc, Weak},
-    sync::{
-        atomic::{AtomicBool, Ordering},
-        Arc,
-    },
 };
 
 use nix::{errno::Errno, fcntl::OFlag, unistd::close};
@@ -24,7 +20,7 @@ use calloop::{
 };
 
 use crate::{
-    backend::session::{AsErrno, Session, Signal as SessionSignal},
+    backend::session::{AsErrno, Session, SessionState, Signal as SessionSignal},
     utils::signaling::Signaler,
 };
 
@@ -33,7 +29,7 @@ use slog::{debug, error, o};
 #[derive(Debug)]
 struct LibSeatSessionImpl {
     seat: RefCell<Seat>,
-    active: Arc<AtomicBool>,
+    state: SessionState,
     devices: RefCell<HashMap<RawFd, i32>>,
     logger: ::slog::Logger,
 }
@@ -99,7 +95,7 @@ impl LibSeatSession {
 
             let internal = Rc::new(LibSeatSessionImpl {
                 seat: RefCell::new(seat),
-                active: Arc::new(AtomicBool::new(active)),
+                state: SessionState::new(active),
                 devices: RefCell::new(HashMap::new()),
                 logger,
             });
@@ -135,6 +131,7 @@ impl Session for LibSeatSession {
                 .open_device(&path)
                 .map(|(id, fd)| {
                     session.devices.borrow_mut().insert(fd, id);
+                    session.state.device_opened(fd, !session.state.active());
                     fd
                 })
                 .map_err(|err| Error::FailedToOpenDevice(Errno::from_i32(err.into())))
@@ -147,7 +144,8 @@ impl Session for LibSeatSession {
         if let Some(session) = self.internal.upgrade() {
             debug!(session.logger, "Closing device: {:?}", fd);
 
-            let dev = session.devices.borrow().get(&fd).copied();
+            let dev = session.devices.borrow_mut().remove(&fd);
+            session.state.device_closed(fd);
 
             let out = if let Some(dev) = dev {
                 session
@@ -182,7 +180,7 @@ impl Session for LibSeatSession {
 
     fn is_active(&self) -> bool {
         if let Some(internal) = self.internal.upgrade() {
-            internal.active.load(Ordering::SeqCst)
+            internal.state.active()
         } else {
             false
         }
@@ -208,6 +206,11 @@ impl LibSeatSessionNotifier {
     pub fn signaler(&self) -> Signaler<SessionSignal> {
         self.signaler.clone()
     }
+
+    /// Get a handle to the state of this session and its devices.
+    pub fn state(&self) -> SessionState {
+        self.internal.state.clone()
+    }
 }
 
 impl EventSource for LibSeatSessionNotifier {
@@ -230,13 +233,9 @@ impl EventSource for LibSeatSessionNotifier {
         self.rx
             .process_events(readiness, token, |event, _| match event {
                 channel::Event::Msg(event) => match event {
-                    SeatEvent::Enable => {
-                        internal.active.store(true, Ordering::SeqCst);
-                        signaler.signal(SessionSignal::ActivateSession);
-                    }
+                    SeatEvent::Enable => internal.state.activate(signaler),
                     SeatEvent::Disable => {
-                        internal.active.store(false, Ordering::SeqCst);
-                        signaler.signal(SessionSignal::PauseSession);
+                        internal.state.pause(signaler);
                         internal.seat.borrow_mut().disable().unwrap();
                     }
                 },
@@ -21,7 +21,13 @@
 //! directly. Instead, it gives you a [`Signaler`](crate::utils::signaling::Signaler) that you can
 //! pass around to other backend modules that need to be notified of the new session state, to stop
 //! accessing the devices while the session is disabled. Notable examples are the
-//! [`libinput`](super::libinput) and [`drm`](super::drm) backends.
+//! [`libinput`](super::libinput) and [`drm`](super::drm) backends, including the
+//! [`GbmBufferedSurface`](super::drm::GbmBufferedSurface), which reset themselves once the
+//! session is resumed.
+//!
+//! All providers send their signals in the same order, see [`SessionState`]. The notifiers
+//! also give you access to this [`SessionState`], which allows you to query if the session is
+//! active and which devices are currently paused.
 //!
 //! ## Available providers
 //!
@@ -75,6 +81,8 @@ pub trait Session {
 /// Objects that need to be notifier about activation and deactivation
 /// of devices need to be linked to the `Signaler` of the session providing
 /// these signals.
+///
+/// See [`SessionState`] for the order in which these signals are sent.
 #[derive(Copy, Clone, Debug)]
 pub enum Signal {
     /// The whole session has been paused
@@ -189,6 +197,8 @@ impl AsErrno for () {
 
 pub mod auto;
 pub mod direct;
+mod state;
+pub use self::state::SessionState;
 #[cfg(feature = "backend_session_libseat")]
 pub mod libseat;
 
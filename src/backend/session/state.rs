@@ -0,0 +1,277 @@
+use std::{
+    collections::{HashMap, HashSet},
+    os::unix::io::RawFd,
+    sync::{Arc, Mutex},
+};
+
+use nix::sys::stat::{fstat, major, minor};
+
+use super::Signal as SessionSignal;
+use crate::utils::signaling::Signaler;
+
+/// State of a session and of the devices opened through it
+///
+/// Every session provider drives its [`Signal`](SessionSignal)s through this state machine,
+/// which guarantees the following ordering:
+///
+/// - When the whole session gets paused, a [`PauseDevice`](SessionSignal::PauseDevice) signal
+///   is sent for every active device, followed by a single [`PauseSession`](SessionSignal::PauseSession).
+/// - When the session gets activated again, an [`ActivateDevice`](SessionSignal::ActivateDevice)
+///   signal is sent for every paused device, followed by a single
+///   [`ActivateSession`](SessionSignal::ActivateSession) once no device is paused anymore.
+/// - Signals for devices or the session are only sent, if their state actually changed.
+///
+/// Providers able to revoke devices individually (like logind) may additionally pause and
+/// activate single devices while the session stays active.
+#[derive(Debug, Clone)]
+pub struct SessionState {
+    inner: Arc<Mutex<SessionStateInner>>,
+}
+
+#[derive(Debug)]
+struct SessionStateInner {
+    active: bool,
+    activation_pending: bool,
+    devices: HashMap<RawFd, (u32, u32)>,
+    paused: HashSet<(u32, u32)>,
+}
+
+impl SessionState {
+    pub(crate) fn new(active: bool) -> SessionState {
+        SessionState {
+            inner: Arc::new(Mutex::new(SessionStateInner {
+                active,
+                activation_pending: false,
+                devices: HashMap::new(),
+                paused: HashSet::new(),
+            })),
+        }
+    }
+
+    /// Returns whether the session is currently active
+    pub fn active(&self) -> bool {
+        self.inner.lock().unwrap().active
+    }
+
+    /// Returns whether the device with the given major and minor numbers was opened
+    /// through the session and is currently usable
+    pub fn device_active(&self, major: u32, minor: u32) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.devices.values().any(|dev| *dev == (major, minor)) && !inner.paused.contains(&(major, minor))
+    }
+
+    /// Returns the major and minor numbers of all devices opened through the session,
+    /// which are currently paused
+    pub fn paused_devices(&self) -> Vec<(u32, u32)> {
+        self.inner.lock().unwrap().paused.iter().copied().collect()
+    }
+
+    /// Track a device opened through the session
+    pub(crate) fn device_opened(&self, fd: RawFd, paused: bool) {
+        let dev = match fstat(fd) {
+            Ok(stat) => (major(stat.st_rdev) as u32, minor(stat.st_rdev) as u32),
+            Err(_) => return,
+        };
+        let mut inner = self.inner.lock().unwrap();
+        inner.devices.insert(fd, dev);
+        if paused {
+            inner.paused.insert(dev);
+        }
+    }
+
+    /// Stop tracking a device, that is about to be closed
+    pub(crate) fn device_closed(&self, fd: RawFd) {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(dev) = inner.devices.remove(&fd) {
+            if !inner.devices.values().any(|other| *other == dev) {
+                inner.paused.remove(&dev);
+            }
+        }
+    }
+
+    /// Stop tracking a device, that is gone from the system
+    pub(crate) fn device_removed(&self, major: u32, minor: u32) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.devices.retain(|_, dev| *dev != (major, minor));
+        inner.paused.remove(&(major, minor));
+    }
+
+    /// Pause the session and all of its devices
+    pub(crate) fn pause(&self, signaler: &Signaler<SessionSignal>) {
+        let signals = {
+            let mut inner = self.inner.lock().unwrap();
+            let devices = inner.devices.values().copied().collect::<HashSet<_>>();
+            let mut signals = devices
+                .into_iter()
+                .filter(|dev| inner.paused.insert(*dev))
+                .map(|(major, minor)| SessionSignal::PauseDevice { major, minor })
+                .collect::<Vec<_>>();
+            signals.extend(inner.set_active(false));
+            signals
+        };
+        for signal in signals {
+            signaler.signal(signal);
+        }
+    }
+
+    /// Activate all paused devices and the session
+    pub(crate) fn activate(&self, signaler: &Signaler<SessionSignal>) {
+        let signals = {
+            let mut inner = self.inner.lock().unwrap();
+            let mut signals = inner
+                .paused
+                .drain()
+                .map(|(major, minor)| SessionSignal::ActivateDevice {
+                    major,
+                    minor,
+                    new_fd: None,
+                })
+                .collect::<Vec<_>>();
+            signals.extend(inner.set_active(true));
+            signals
+        };
+        for signal in signals {
+            signaler.signal(signal);
+        }
+    }
+
+    /// Change the state of the session, without touching the state of its devices
+    ///
+    /// Activation of the session is delayed until all of its devices got activated.
+    pub(crate) fn set_active(&self, signaler: &Signaler<SessionSignal>, active: bool) {
+        let signal = self.inner.lock().unwrap().set_active(active);
+        if let Some(signal) = signal {
+            signaler.signal(signal);
+        }
+    }
+
+    /// Pause a single device
+    pub(crate) fn pause_device(&self, signaler: &Signaler<SessionSignal>, major: u32, minor: u32) {
+        let changed = {
+            let mut inner = self.inner.lock().unwrap();
+            // untracked devices would block the activation of the session forever
+            inner.devices.values().any(|dev| *dev == (major, minor)) && inner.paused.insert((major, minor))
+        };
+        if changed {
+            signaler.signal(SessionSignal::PauseDevice { major, minor });
+        }
+    }
+
+    /// Activate a single device, optionally replacing its file descriptor
+    pub(crate) fn activate_device(
+        &self,
+        signaler: &Signaler<SessionSignal>,
+        major: u32,
+        minor: u32,
+        new_fd: Option<RawFd>,
+    ) {
+        let (changed, session_signal) = {
+            let mut inner = self.inner.lock().unwrap();
+            let changed = inner.paused.remove(&(major, minor));
+            (changed, inner.activate_pending())
+        };
+        // a new fd always needs to be handed out
+        if changed || new_fd.is_some() {
+            signaler.signal(SessionSignal::ActivateDevice { major, minor, new_fd });
+        }
+        if let Some(signal) = session_signal {
+            signaler.signal(signal);
+        }
+    }
+}
+
+impl SessionStateInner {
+    fn set_active(&mut self, active: bool) -> Option<SessionSignal> {
+        if active {
+            if self.active {
+                return None;
+            }
+            self.activation_pending = true;
+            self.activate_pending()
+        } else {
+            self.activation_pending = false;
+            if !self.active {
+                return None;
+            }
+            self.active = false;
+            Some(SessionSignal::PauseSession)
+        }
+    }
+
+    fn activate_pending(&mut self) -> Option<SessionSignal> {
+        if self.activation_pending && self.paused.is_empty() {
+            self.activation_pending = false;
+            self.active = true;
+            Some(SessionSignal::ActivateSession)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::{cell::RefCell, rc::Rc};
+
+    fn record(
+        signaler: &Signaler<SessionSignal>,
+    ) -> (Rc<RefCell<Vec<String>>>, crate::utils::signaling::SignalToken) {
+        let signals = Rc::new(RefCell::new(Vec::new()));
+        let signals2 = signals.clone();
+        let token = signaler.register(move |signal| signals2.borrow_mut().push(format!("{:?}", signal)));
+        (signals, token)
+    }
+
+    #[test]
+    fn session_activation_waits_for_devices() {
+        let signaler = Signaler::new();
+        let (signals, _token) = record(&signaler);
+        let state = SessionState::new(true);
+        state.inner.lock().unwrap().devices.insert(42, (226, 0));
+
+        state.pause_device(&signaler, 226, 0);
+        state.set_active(&signaler, false);
+        assert!(!state.active());
+        assert_eq!(state.paused_devices(), vec![(226, 0)]);
+
+        state.set_active(&signaler, true);
+        assert!(!state.active());
+        state.activate_device(&signaler, 226, 0, None);
+        assert!(state.active());
+        assert!(state.device_active(226, 0));
+
+        assert_eq!(
+            *signals.borrow(),
+            vec![
+                "PauseDevice { major: 226, minor: 0 }",
+                "PauseSession",
+                "ActivateDevice { major: 226, minor: 0, new_fd: None }",
+                "ActivateSession",
+            ]
+        );
+    }
+
+    #[test]
+    fn pause_is_idempotent() {
+        let signaler = Signaler::new();
+        let (signals, _token) = record(&signaler);
+        let state = SessionState::new(true);
+        state.inner.lock().unwrap().devices.insert(42, (13, 64));
+
+        state.pause(&signaler);
+        state.pause(&signaler);
+        state.activate(&signaler);
+
+        assert_eq!(
+            *signals.borrow(),
+            vec![
+                "PauseDevice { major: 13, minor: 64 }",
+                "PauseSession",
+                "ActivateDevice { major: 13, minor: 64, new_fd: None }",
+                "ActivateSession",
+            ]
+        );
+    }
+}
@@ -6,8 +6,8 @@ use winit::{
 };
 
 use crate::backend::input::{
-    self, AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, DeviceCapability, Event,
-    InputBackend, KeyState, KeyboardKeyEvent, PointerAxisEvent, PointerButtonEvent,
+    self, AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, DeviceCapability, DeviceConfig,
+    Event, InputBackend, KeyState, KeyboardKeyEvent, PointerAxisEvent, PointerButtonEvent,
     PointerMotionAbsoluteEvent, TouchCancelEvent, TouchDownEvent, TouchEvent, TouchMotionEvent, TouchSlot,
     TouchUpEvent, UnusedEvent,
 };
@@ -47,6 +47,8 @@ impl Device for WinitVirtualDevice {
     }
 }
 
+impl DeviceConfig for WinitVirtualDevice {}
+
 /// Winit-Backend internal event wrapping `winit`'s types into a [`KeyboardKeyEvent`]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct WinitKeyboardInputEvent {
@@ -392,6 +394,10 @@ impl InputBackend for WinitInput {
     type TabletToolProximityEvent = UnusedEvent;
     type TabletToolTipEvent = UnusedEvent;
     type TabletToolButtonEvent = UnusedEvent;
+    type TabletPadButtonEvent = UnusedEvent;
+    type TabletPadRingEvent = UnusedEvent;
+    type TabletPadStripEvent = UnusedEvent;
+    type SwitchToggleEvent = UnusedEvent;
 
     type SpecialEvent = UnusedEvent;
 }
@@ -3,9 +3,9 @@
 use super::{window_inner::WindowInner, Window, WindowTemporary};
 use crate::{
     backend::input::{
-        self, AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, DeviceCapability, InputBackend,
-        KeyState, KeyboardKeyEvent, PointerAxisEvent, PointerButtonEvent, PointerMotionAbsoluteEvent,
-        UnusedEvent,
+        self, AbsolutePositionEvent, Axis, AxisSource, ButtonState, Device, DeviceCapability, DeviceConfig,
+        InputBackend, KeyState, KeyboardKeyEvent, PointerAxisEvent, PointerButtonEvent,
+        PointerMotionAbsoluteEvent, UnusedEvent,
     },
     utils::{Logical, Size},
 };
@@ -44,6 +44,8 @@ impl Device for X11VirtualDevice {
     }
 }
 
+impl DeviceConfig for X11VirtualDevice {}
+
 /// X11-Backend internal event wrapping `X11`'s types into a [`KeyboardKeyEvent`].
 #[derive(Debug, Clone)]
 pub struct X11KeyboardInputEvent {
@@ -239,6 +241,10 @@ impl InputBackend for X11Input {
     type TabletToolProximityEvent = UnusedEvent;
     type TabletToolTipEvent = UnusedEvent;
     type TabletToolButtonEvent = UnusedEvent;
+    type TabletPadButtonEvent = UnusedEvent;
+    type TabletPadRingEvent = UnusedEvent;
+    type TabletPadStripEvent = UnusedEvent;
+    type SwitchToggleEvent = UnusedEvent;
 
     type SpecialEvent = UnusedEvent;
 }
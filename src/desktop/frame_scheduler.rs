@@ -0,0 +1,314 @@
+//! Repaint timing per output
+//!
+//! Rendering right after the previous frame was presented (e.g. right after a vblank) gives
+//! the longest possible time to render, but also maximizes the latency between a client
+//! committing new content and it showing up on screen.
+//!
+//! A [`FrameScheduler`] tracks the refresh rate of an output, the time the last frames were
+//! presented and how long the last frames took to render. From this it predicts the next
+//! presentation and delays rendering until just before it, leaving enough time for the
+//! slowest of the recently rendered frames plus a safety margin.
+//!
+//! Every [`Output`] can be associated a [`FrameScheduler`] by calling [`frame_scheduler_for_output`],
+//! which automatically follows the refresh rate of the current mode of the output.
+//!
+//! ```no_run
+//! # use smithay::{desktop::frame_scheduler_for_output, output::Output};
+//! # use std::time::Instant;
+//! # struct State;
+//! # impl State { fn render(&mut self, output: &Output) {} }
+//! # let output: Output = unimplemented!();
+//! # let handle: calloop::LoopHandle<'static, State> = unimplemented!();
+//! // once the last frame was presented, e.g. on a vblank event
+//! let mut scheduler = frame_scheduler_for_output(&output);
+//! scheduler.presented(Instant::now());
+//!
+//! let output_clone = output.clone();
+//! scheduler
+//!     .schedule_render(&handle, Instant::now(), move |state: &mut State| {
+//!         frame_scheduler_for_output(&output_clone).render_started(Instant::now());
+//!         state.render(&output_clone);
+//!         frame_scheduler_for_output(&output_clone).render_finished(Instant::now());
+//!     })
+//!     .expect("Failed to schedule render");
+//! ```
+//!
+//! Surfaces, that are currently not visible on any output, do not need to render at the full
+//! refresh rate. Their frame callbacks can be throttled with
+//! [`send_frames_surface_tree_throttled`](crate::desktop::utils::send_frames_surface_tree_throttled)
+//! or [`Window::send_frame_throttled`](crate::desktop::Window::send_frame_throttled).
+
+use std::{
+    cell::{Cell, RefCell, RefMut},
+    collections::VecDeque,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use calloop::{
+    timer::{TimeoutAction, Timer},
+    InsertError, LoopHandle, RegistrationToken,
+};
+
+use crate::output::Output;
+
+/// Number of render durations used to estimate the time of the next frame
+const RENDER_TIME_SAMPLES: usize = 16;
+/// Refresh interval used, if the refresh rate of an output is unknown
+const DEFAULT_REFRESH: Duration = Duration::from_micros(16_667);
+/// Default time reserved in addition to the estimated render time
+const DEFAULT_SAFETY_MARGIN: Duration = Duration::from_millis(1);
+
+/// Schedules the rendering of an output relative to its presentation timing
+#[derive(Debug)]
+pub struct FrameScheduler {
+    refresh: Duration,
+    safety_margin: Duration,
+    last_presentation: Option<Instant>,
+    render_start: Option<Instant>,
+    render_times: VecDeque<Duration>,
+    timer: Option<(RegistrationToken, Rc<Cell<bool>>)>,
+}
+
+impl FrameScheduler {
+    /// Create a new [`FrameScheduler`] for the given refresh interval
+    pub fn new(refresh: Duration) -> Self {
+        FrameScheduler {
+            refresh: sanitize_refresh(refresh),
+            safety_margin: DEFAULT_SAFETY_MARGIN,
+            last_presentation: None,
+            render_start: None,
+            render_times: VecDeque::with_capacity(RENDER_TIME_SAMPLES),
+            timer: None,
+        }
+    }
+
+    /// Create a new [`FrameScheduler`] for a refresh rate in millihertz,
+    /// as used by [`Mode`](crate::output::Mode)
+    pub fn from_refresh_rate(refresh_rate: i32) -> Self {
+        FrameScheduler::new(refresh_from_rate(refresh_rate))
+    }
+
+    /// The refresh interval of the output
+    pub fn refresh(&self) -> Duration {
+        self.refresh
+    }
+
+    /// Set the refresh interval of the output
+    pub fn set_refresh(&mut self, refresh: Duration) {
+        self.refresh = sanitize_refresh(refresh);
+    }
+
+    /// The time reserved in addition to the estimated render time
+    pub fn safety_margin(&self) -> Duration {
+        self.safety_margin
+    }
+
+    /// Set the time reserved in addition to the estimated render time
+    ///
+    /// A larger margin reduces the chance of missing a presentation
+    /// in exchange for a higher latency.
+    pub fn set_safety_margin(&mut self, margin: Duration) {
+        self.safety_margin = margin;
+    }
+
+    /// Notify the scheduler about a presented frame
+    ///
+    /// `time` should be the time of the presentation as reported by the backend
+    /// (e.g. the vblank timestamp) or the time the presentation event was received.
+    pub fn presented(&mut self, time: Instant) {
+        self.last_presentation = Some(time);
+    }
+
+    /// The time of the last presentation, if any
+    pub fn last_presentation(&self) -> Option<Instant> {
+        self.last_presentation
+    }
+
+    /// Notify the scheduler that rendering of a new frame started
+    ///
+    /// This also marks a pending timer of [`FrameScheduler::schedule_render`] as consumed,
+    /// as the frame it was scheduled for is now being rendered.
+    pub fn render_started(&mut self, now: Instant) {
+        self.render_start = Some(now);
+        if let Some((_, armed)) = self.timer.as_ref() {
+            if !armed.get() {
+                self.timer = None;
+            }
+        }
+    }
+
+    /// Notify the scheduler that rendering of a frame finished
+    ///
+    /// The duration since the last call to [`FrameScheduler::render_started`]
+    /// is used to estimate the time the next frames will take.
+    /// This should not be called for frames that ended up not being rendered,
+    /// e.g. because there was no damage.
+    pub fn render_finished(&mut self, now: Instant) {
+        if let Some(start) = self.render_start.take() {
+            if self.render_times.len() == RENDER_TIME_SAMPLES {
+                self.render_times.pop_front();
+            }
+            self.render_times.push_back(now.saturating_duration_since(start));
+        }
+    }
+
+    /// The estimated duration to render the next frame
+    ///
+    /// This is the longest render duration of the recently rendered frames.
+    /// Without any recorded frames a full refresh interval is assumed.
+    pub fn estimated_render_time(&self) -> Duration {
+        self.render_times.iter().max().copied().unwrap_or(self.refresh)
+    }
+
+    /// The predicted time of the next presentation after `now`
+    ///
+    /// Without any recorded presentation `now` is returned.
+    pub fn next_presentation(&self, now: Instant) -> Instant {
+        let last = match self.last_presentation {
+            Some(last) => last,
+            None => return now,
+        };
+
+        let elapsed = now.saturating_duration_since(last);
+        let refresh = self.refresh.as_nanos();
+        let cycles = elapsed.as_nanos() / refresh + 1;
+        last + Duration::from_nanos((cycles * refresh) as u64)
+    }
+
+    /// The latest time to start rendering to still hit the next presentation after `now`
+    ///
+    /// If that time has already passed, `now` is returned, as rendering
+    /// as soon as possible results in the lowest latency in that case.
+    pub fn render_deadline(&self, now: Instant) -> Instant {
+        let reserved = self.estimated_render_time() + self.safety_margin;
+        self.next_presentation(now)
+            .checked_sub(reserved)
+            .filter(|deadline| *deadline > now)
+            .unwrap_or(now)
+    }
+
+    /// Returns `true` if a render is currently scheduled
+    pub fn is_scheduled(&self) -> bool {
+        self.timer.as_ref().map(|(_, armed)| armed.get()).unwrap_or(false)
+    }
+
+    /// Schedule `callback` to be called at the [render deadline](FrameScheduler::render_deadline)
+    ///
+    /// Does nothing if a render is already scheduled, so it is safe to call this for
+    /// every event requiring a redraw.
+    /// The `callback` should call [`FrameScheduler::render_started`] and
+    /// [`FrameScheduler::render_finished`] around rendering the frame.
+    pub fn schedule_render<'l, Data, F>(
+        &mut self,
+        handle: &LoopHandle<'l, Data>,
+        now: Instant,
+        callback: F,
+    ) -> Result<(), InsertError<Timer>>
+    where
+        F: FnOnce(&mut Data) + 'l,
+    {
+        if self.is_scheduled() {
+            return Ok(());
+        }
+
+        let armed = Rc::new(Cell::new(true));
+        let timer_armed = armed.clone();
+        let mut callback = Some(callback);
+        let token = handle.insert_source(
+            Timer::from_deadline(self.render_deadline(now)),
+            move |_, _, data| {
+                timer_armed.set(false);
+                if let Some(callback) = callback.take() {
+                    callback(data);
+                }
+                TimeoutAction::Drop
+            },
+        )?;
+        self.timer = Some((token, armed));
+
+        Ok(())
+    }
+
+    /// Cancel a render scheduled by [`FrameScheduler::schedule_render`]
+    pub fn cancel<Data>(&mut self, handle: &LoopHandle<'_, Data>) {
+        if let Some((token, armed)) = self.timer.take() {
+            // the timer source removes itself after firing
+            if armed.get() {
+                handle.remove(token);
+            }
+        }
+    }
+}
+
+impl Default for FrameScheduler {
+    fn default() -> Self {
+        FrameScheduler::new(DEFAULT_REFRESH)
+    }
+}
+
+fn sanitize_refresh(refresh: Duration) -> Duration {
+    if refresh.is_zero() {
+        DEFAULT_REFRESH
+    } else {
+        refresh
+    }
+}
+
+fn refresh_from_rate(refresh_rate: i32) -> Duration {
+    if refresh_rate <= 0 {
+        DEFAULT_REFRESH
+    } else {
+        Duration::from_nanos(1_000_000_000_000 / refresh_rate as u64)
+    }
+}
+
+/// Retrieve the [`FrameScheduler`] for a given [`Output`].
+///
+/// If none existed before a new one will be created.
+/// The refresh interval of the scheduler is updated to match
+/// the current mode of the output on every call.
+pub fn frame_scheduler_for_output(o: &Output) -> RefMut<'_, FrameScheduler> {
+    let userdata = o.user_data();
+    userdata.insert_if_missing(|| RefCell::new(FrameScheduler::default()));
+    let mut scheduler = userdata.get::<RefCell<FrameScheduler>>().unwrap().borrow_mut();
+    if let Some(mode) = o.current_mode() {
+        scheduler.set_refresh(refresh_from_rate(mode.refresh));
+    }
+    scheduler
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_deadline() {
+        let mut scheduler = FrameScheduler::from_refresh_rate(60_000);
+        scheduler.set_safety_margin(Duration::from_millis(1));
+        let start = Instant::now();
+
+        // unknown timing renders right away
+        assert_eq!(scheduler.render_deadline(start), start);
+
+        scheduler.presented(start);
+        scheduler.render_started(start);
+        scheduler.render_finished(start + Duration::from_millis(4));
+        scheduler.render_started(start);
+        scheduler.render_finished(start + Duration::from_millis(2));
+        assert_eq!(scheduler.estimated_render_time(), Duration::from_millis(4));
+
+        let now = start + Duration::from_millis(1);
+        let vblank = start + scheduler.refresh();
+        assert_eq!(scheduler.next_presentation(now), vblank);
+        assert_eq!(scheduler.render_deadline(now), vblank - Duration::from_millis(5));
+
+        // predictions continue on the grid of the last presentation
+        let now = start + scheduler.refresh() * 2 + Duration::from_millis(3);
+        assert_eq!(scheduler.next_presentation(now), start + scheduler.refresh() * 3);
+
+        // too late for the next presentation
+        let now = vblank - Duration::from_millis(2);
+        assert_eq!(scheduler.render_deadline(now), now);
+    }
+}
@@ -43,25 +43,40 @@
 //! relations to one-another. Popups are then automatically rendered with their matching toplevel surfaces,
 //! when either [`draw_window`], [`draw_layer_surface`] or [`render_output`](crate::desktop::space::render_output) is called.
 //!
+//! ### Frame scheduling
+//!
+//! Each [`Output`](crate::output::Output) can be associated a [`FrameScheduler`] by calling
+//! [`frame_scheduler_for_output`], which helps delaying rendering to just before the next presentation
+//! of the output to reduce latency.
+//!
 //! ## Remarks
 //!
 //! Note that the desktop abstractions are concerned with easing rendering different clients and therefore need to be able
 //! to manage client buffers to do so. If you plan to use the provided drawing functions, you need to use
 //! [`on_commit_buffer_handler`](crate::backend::renderer::utils::on_commit_buffer_handler).
 
+pub mod frame_scheduler;
+pub use self::frame_scheduler::{frame_scheduler_for_output, FrameScheduler};
 pub mod space;
 pub use self::space::Space;
+pub mod workspace;
+pub use self::workspace::{WorkspaceId, WorkspaceManager};
 
 #[cfg(feature = "wayland_frontend")]
 pub use self::wayland::{
+    decoration, grabs,
     layer::{draw_layer_surface, layer_map_for_output, LayerMap, LayerSurface},
+    layout,
     popup::*,
     utils,
     window::*,
 };
 #[cfg(feature = "wayland_frontend")]
 mod wayland {
+    pub mod decoration;
+    pub mod grabs;
     pub(crate) mod layer;
+    pub mod layout;
     pub mod popup;
     pub mod utils;
     pub mod window;
@@ -157,6 +157,35 @@ impl<E: SpaceElement + PartialEq> Space<E> {
         }
     }
 
+    /// Moves a [`SpaceElement`] from this space into another one and puts it on top of the stack
+    ///
+    /// Unlike unmapping and mapping the element again, this keeps track of the outputs the element
+    /// currently overlaps with, so the next [`Space::refresh`] of the `target` space only sends
+    /// enter and leave events for outputs that actually changed.
+    ///
+    /// If no location is provided the element keeps its current location.
+    ///
+    /// Returns `false` and does nothing, if the element is not mapped in this space.
+    pub fn move_element_to(
+        &mut self,
+        element: &E,
+        target: &mut Space<E>,
+        location: Option<Point<i32, Logical>>,
+        activate: bool,
+    ) -> bool {
+        let pos = match self.elements.iter().position(|inner| &inner.element == element) {
+            Some(pos) => pos,
+            None => return false,
+        };
+        let mut inner = self.elements.remove(pos);
+        if let Some(location) = location {
+            inner.location = location;
+        }
+        target.elements.retain(|e| &e.element != element);
+        target.insert_elem(inner, activate);
+        true
+    }
+
     /// Iterate window in z-order back to front
     pub fn elements(&self) -> impl DoubleEndedIterator<Item = &E> {
         self.elements.iter().map(|e| &e.element)
@@ -0,0 +1,705 @@
+//! Server-side decorations for [`Window`]s
+//!
+//! Once a client agreed to use server-side decorations (e.g. via the
+//! [xdg-decoration](crate::wayland::shell::xdg::decoration) or the
+//! [kde server decoration](crate::wayland::shell::kde::decoration) protocol),
+//! the compositor is responsible for drawing a titlebar and borders around the window.
+//!
+//! A [`DecoratedWindow`] wraps a [`Window`] and takes care of the toolkit-agnostic parts of this:
+//! - layouting the titlebar, borders and buttons according to a [`DecorationTheme`],
+//! - rendering them as simple colored rectangles via [`DecorationRenderElement`],
+//!   which provides damage and opaque regions to the damage tracker,
+//! - hit-testing pointer locations against them ([`DecoratedWindow::hit_test`]),
+//! - offsetting the window inside the decorations, when mapped onto a [`Space`](crate::desktop::Space).
+//!
+//! Title text and button icons are left to the compositor, which may render them on top
+//! using the geometry returned by [`DecorationLayout`].
+//!
+//! ```no_run
+//! # use smithay::desktop::{Window, Space, decoration::{DecoratedWindow, DecorationTheme, DecorationHit}};
+//! # use smithay::utils::{Logical, Point};
+//! # let window: Window = unimplemented!();
+//! # let mut space: Space<DecoratedWindow> = unimplemented!();
+//! let decorated = DecoratedWindow::new(window, DecorationTheme::default());
+//! space.map_element(decorated.clone(), (0, 0), true);
+//!
+//! // on a pointer button press
+//! # let location: Point<f64, Logical> = (0.0, 0.0).into();
+//! if let Some((element, element_location)) = space.element_under(location) {
+//!     match element.hit_test(location - element_location.to_f64()) {
+//!         Some(DecorationHit::Titlebar) => { /* start a move grab */ }
+//!         Some(DecorationHit::Border(_edges)) => { /* start a resize grab */ }
+//!         Some(DecorationHit::Button(_button)) => { /* close, maximize or minimize */ }
+//!         None => { /* forward the event to the window */ }
+//!     }
+//! }
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use wayland_protocols::xdg::shell::server::xdg_toplevel::ResizeEdge;
+
+use crate::{
+    backend::renderer::{
+        element::{surface::WaylandSurfaceRenderElement, AsRenderElements, Id, RenderElement},
+        utils::CommitCounter,
+        Frame, ImportAll, Renderer,
+    },
+    desktop::{
+        grabs::{ResizableElement, ResizeState},
+        space::SpaceElement,
+        Window,
+    },
+    output::Output,
+    utils::{Buffer, IsAlive, Logical, Physical, Point, Rectangle, Scale, Size},
+};
+
+/// Buttons of the titlebar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DecorationButton {
+    /// Close the window
+    Close,
+    /// Toggle the maximized state of the window
+    Maximize,
+    /// Minimize the window
+    Minimize,
+}
+
+/// Part of the decorations at a given location, see [`DecoratedWindow::hit_test`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecorationHit {
+    /// The titlebar, usually starts an interactive move
+    Titlebar,
+    /// A button of the titlebar
+    Button(DecorationButton),
+    /// The border, usually starts an interactive resize on the given edges
+    Border(ResizeEdge),
+}
+
+/// Sizes and colors of server-side decorations
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecorationTheme {
+    /// Height of the titlebar
+    pub titlebar_height: i32,
+    /// Width of the border around the titlebar and the window
+    pub border_width: i32,
+    /// Length of the corner areas starting a resize on two edges
+    pub corner_size: i32,
+    /// Size of the square titlebar buttons
+    pub button_size: i32,
+    /// Space between the buttons and the right edge of the titlebar
+    pub button_spacing: i32,
+    /// Buttons shown in the titlebar, from right to left
+    pub buttons: Vec<DecorationButton>,
+    /// Color of the titlebar and border of the activated window
+    ///
+    /// All colors are rgb values, the decorations are always drawn opaque.
+    pub active_color: [f32; 3],
+    /// Color of the titlebar and border of inactive windows
+    pub inactive_color: [f32; 3],
+    /// Color of the buttons
+    pub button_color: [f32; 3],
+    /// Color of a button the pointer is hovering over
+    pub button_hover_color: [f32; 3],
+    /// Color of the hovered close button
+    pub close_hover_color: [f32; 3],
+}
+
+impl Default for DecorationTheme {
+    fn default() -> Self {
+        DecorationTheme {
+            titlebar_height: 28,
+            border_width: 2,
+            corner_size: 16,
+            button_size: 18,
+            button_spacing: 5,
+            buttons: vec![
+                DecorationButton::Close,
+                DecorationButton::Maximize,
+                DecorationButton::Minimize,
+            ],
+            active_color: [0.25, 0.25, 0.28],
+            inactive_color: [0.4, 0.4, 0.42],
+            button_color: [0.55, 0.55, 0.58],
+            button_hover_color: [0.75, 0.75, 0.78],
+            close_hover_color: [0.8, 0.2, 0.2],
+        }
+    }
+}
+
+impl DecorationTheme {
+    /// Size of the decorated frame for a given window size
+    pub fn frame_size(&self, window_size: Size<i32, Logical>) -> Size<i32, Logical> {
+        (
+            window_size.w + 2 * self.border_width,
+            window_size.h + self.titlebar_height + 2 * self.border_width,
+        )
+            .into()
+    }
+
+    /// Size of the window fitting into a given frame size
+    ///
+    /// Use this to calculate the size to configure a window with,
+    /// if the decorated frame should have a certain size.
+    pub fn window_size(&self, frame_size: Size<i32, Logical>) -> Size<i32, Logical> {
+        (
+            (frame_size.w - 2 * self.border_width).max(1),
+            (frame_size.h - self.titlebar_height - 2 * self.border_width).max(1),
+        )
+            .into()
+    }
+
+    /// Location of the window relative to the frame
+    pub fn window_location(&self) -> Point<i32, Logical> {
+        (self.border_width, self.border_width + self.titlebar_height).into()
+    }
+
+    /// Calculates the layout of the decorations for a given window size
+    pub fn layout(&self, window_size: Size<i32, Logical>) -> DecorationLayout {
+        let frame_size = self.frame_size(window_size);
+        let b = self.border_width;
+        let t = self.titlebar_height;
+
+        let titlebar = Rectangle::from_loc_and_size((b, b), (window_size.w, t));
+        let borders = [
+            Rectangle::from_loc_and_size((0, 0), (frame_size.w, b)),
+            Rectangle::from_loc_and_size((0, frame_size.h - b), (frame_size.w, b)),
+            Rectangle::from_loc_and_size((0, b), (b, frame_size.h - 2 * b)),
+            Rectangle::from_loc_and_size((frame_size.w - b, b), (b, frame_size.h - 2 * b)),
+        ];
+
+        let button_y = b + (t - self.button_size) / 2;
+        let mut button_x = b + window_size.w;
+        let mut buttons = Vec::with_capacity(self.buttons.len());
+        for button in &self.buttons {
+            button_x -= self.button_spacing + self.button_size;
+            if button_x < b {
+                // no more space left in the titlebar
+                break;
+            }
+            buttons.push((
+                *button,
+                Rectangle::from_loc_and_size((button_x, button_y), (self.button_size, self.button_size)),
+            ));
+        }
+
+        DecorationLayout {
+            frame: Rectangle::from_loc_and_size((0, 0), frame_size),
+            window: Rectangle::from_loc_and_size(self.window_location(), window_size),
+            titlebar,
+            borders,
+            buttons,
+        }
+    }
+}
+
+/// Geometry of the different parts of the decorations relative to the frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecorationLayout {
+    /// The whole decorated frame
+    pub frame: Rectangle<i32, Logical>,
+    /// The area of the window inside the frame
+    pub window: Rectangle<i32, Logical>,
+    /// The titlebar (including the area covered by buttons)
+    pub titlebar: Rectangle<i32, Logical>,
+    /// The top, bottom, left and right border
+    pub borders: [Rectangle<i32, Logical>; 4],
+    /// The visible buttons
+    pub buttons: Vec<(DecorationButton, Rectangle<i32, Logical>)>,
+}
+
+impl DecorationLayout {
+    /// Returns the part of the decorations at a given location relative to the frame
+    pub fn hit_test(&self, point: Point<f64, Logical>, corner_size: i32) -> Option<DecorationHit> {
+        if !self.frame.to_f64().contains(point) || self.window.to_f64().contains(point) {
+            return None;
+        }
+
+        if let Some((button, _)) = self
+            .buttons
+            .iter()
+            .find(|(_, geometry)| geometry.to_f64().contains(point))
+        {
+            return Some(DecorationHit::Button(*button));
+        }
+
+        let frame = self.frame.to_f64();
+        let border = self.borders[0].size.h as f64;
+        let corner = (corner_size as f64).max(border);
+        let mut top = point.y < frame.loc.y + border;
+        let mut bottom = point.y >= frame.loc.y + frame.size.h - border;
+        let mut left = point.x < frame.loc.x + border;
+        let mut right = point.x >= frame.loc.x + frame.size.w - border;
+        if top || bottom {
+            left |= point.x < frame.loc.x + corner;
+            right |= point.x >= frame.loc.x + frame.size.w - corner;
+        }
+        if left || right {
+            top |= point.y < frame.loc.y + corner;
+            bottom |= point.y >= frame.loc.y + frame.size.h - corner;
+        }
+
+        let edges = match (top, bottom, left, right) {
+            (true, _, true, _) => Some(ResizeEdge::TopLeft),
+            (true, _, _, true) => Some(ResizeEdge::TopRight),
+            (_, true, true, _) => Some(ResizeEdge::BottomLeft),
+            (_, true, _, true) => Some(ResizeEdge::BottomRight),
+            (true, _, _, _) => Some(ResizeEdge::Top),
+            (_, true, _, _) => Some(ResizeEdge::Bottom),
+            (_, _, true, _) => Some(ResizeEdge::Left),
+            (_, _, _, true) => Some(ResizeEdge::Right),
+            _ => None,
+        };
+
+        Some(
+            edges
+                .map(DecorationHit::Border)
+                .unwrap_or(DecorationHit::Titlebar),
+        )
+    }
+}
+
+#[derive(Debug)]
+struct DecorationState {
+    theme: DecorationTheme,
+    decorated: bool,
+    activated: bool,
+    hovered: Option<DecorationButton>,
+    window_size: Size<i32, Logical>,
+    commit_counter: CommitCounter,
+}
+
+#[derive(Debug)]
+struct DecoratedWindowInner {
+    window: Window,
+    id: Id,
+    state: Mutex<DecorationState>,
+}
+
+/// A [`Window`] with server-side decorations
+///
+/// Can be mapped onto a [`Space`](crate::desktop::Space) in place of the [`Window`].
+/// Its geometry is the decorated frame, so the location of the element inside
+/// the space is the top-left corner of the titlebar border. The window itself is offset by
+/// [`DecoratedWindow::window_offset`].
+///
+/// While [not decorated](DecoratedWindow::set_decorated), it behaves exactly like the wrapped [`Window`].
+#[derive(Debug, Clone)]
+pub struct DecoratedWindow(Arc<DecoratedWindowInner>);
+
+impl PartialEq for DecoratedWindow {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.window == other.0.window
+    }
+}
+
+impl IsAlive for DecoratedWindow {
+    fn alive(&self) -> bool {
+        self.0.window.alive()
+    }
+}
+
+impl DecoratedWindow {
+    /// Wraps a window with server-side decorations using the given theme
+    pub fn new(window: Window, theme: DecorationTheme) -> DecoratedWindow {
+        let window_size = window.geometry().size;
+        DecoratedWindow(Arc::new(DecoratedWindowInner {
+            window,
+            id: Id::new(),
+            state: Mutex::new(DecorationState {
+                theme,
+                decorated: true,
+                activated: false,
+                hovered: None,
+                window_size,
+                commit_counter: CommitCounter::default(),
+            }),
+        }))
+    }
+
+    /// Returns the wrapped window
+    pub fn window(&self) -> &Window {
+        &self.0.window
+    }
+
+    /// Returns the current theme
+    pub fn theme(&self) -> DecorationTheme {
+        self.0.state.lock().unwrap().theme.clone()
+    }
+
+    /// Changes the theme of the decorations
+    pub fn set_theme(&self, theme: DecorationTheme) {
+        let mut state = self.0.state.lock().unwrap();
+        if state.theme != theme {
+            state.theme = theme;
+            state.commit_counter.increment();
+        }
+    }
+
+    /// Returns whether the decorations are drawn
+    pub fn is_decorated(&self) -> bool {
+        self.0.state.lock().unwrap().decorated
+    }
+
+    /// Enables or disables the decorations
+    ///
+    /// Should be called whenever the decoration mode negotiated with the client changes.
+    /// Returns `true` if the state changed.
+    pub fn set_decorated(&self, decorated: bool) -> bool {
+        let mut state = self.0.state.lock().unwrap();
+        let changed = state.decorated != decorated;
+        if changed {
+            state.decorated = decorated;
+            state.hovered = None;
+            state.commit_counter.increment();
+        }
+        changed
+    }
+
+    /// Returns the layout of the decorations relative to the frame
+    ///
+    /// Returns `None` if the window is not decorated.
+    pub fn layout(&self) -> Option<DecorationLayout> {
+        let state = self.0.state.lock().unwrap();
+        state
+            .decorated
+            .then(|| state.theme.layout(self.0.window.geometry().size))
+    }
+
+    /// Offset of the window's surface relative to the location of this element
+    ///
+    /// Add this to the location returned by [`Space::element_under`](crate::desktop::Space::element_under)
+    /// to get the location of the window's surface, e.g. to send pointer events to the client.
+    pub fn window_offset(&self) -> Point<i32, Logical> {
+        let state = self.0.state.lock().unwrap();
+        if state.decorated {
+            state.theme.window_location() - self.0.window.geometry().loc
+        } else {
+            Point::default()
+        }
+    }
+
+    /// Returns the part of the decorations at a given location relative to this element
+    ///
+    /// Returns `None` if the location is not part of the decorations, e.g. inside the window.
+    pub fn hit_test(&self, point: Point<f64, Logical>) -> Option<DecorationHit> {
+        let state = self.0.state.lock().unwrap();
+        if !state.decorated {
+            return None;
+        }
+        state
+            .theme
+            .layout(self.0.window.geometry().size)
+            .hit_test(point, state.theme.corner_size)
+    }
+
+    /// Updates the location of the pointer relative to this element to highlight hovered buttons
+    ///
+    /// Pass `None` once the pointer left the element.
+    pub fn set_pointer_location(&self, point: Option<Point<f64, Logical>>) {
+        let hovered = match point.and_then(|point| self.hit_test(point)) {
+            Some(DecorationHit::Button(button)) => Some(button),
+            _ => None,
+        };
+        let mut state = self.0.state.lock().unwrap();
+        if state.hovered != hovered {
+            state.hovered = hovered;
+            state.commit_counter.increment();
+        }
+    }
+
+    /// Converts a size constraint of the window into one of the frame, keeping zero (unconstrained) components
+    fn frame_constraint(&self, size: Size<i32, Logical>) -> Size<i32, Logical> {
+        let state = self.0.state.lock().unwrap();
+        if !state.decorated {
+            return size;
+        }
+        let frame_size = state.theme.frame_size(size);
+        (
+            if size.w == 0 { 0 } else { frame_size.w },
+            if size.h == 0 { 0 } else { frame_size.h },
+        )
+            .into()
+    }
+
+    fn render_element(&self, location: Point<i32, Physical>, scale: Scale<f64>) -> DecorationRenderElement {
+        let mut state = self.0.state.lock().unwrap();
+        let window_size = self.0.window.geometry().size;
+        if state.window_size != window_size {
+            state.window_size = window_size;
+            state.commit_counter.increment();
+        }
+
+        let layout = state.theme.layout(window_size);
+        let color = if state.activated {
+            state.theme.active_color
+        } else {
+            state.theme.inactive_color
+        };
+        let mut parts = layout
+            .borders
+            .iter()
+            .chain(std::iter::once(&layout.titlebar))
+            .map(|rect| (rect.to_physical_precise_round(scale), color))
+            .collect::<Vec<_>>();
+        parts.extend(layout.buttons.iter().map(|(button, rect)| {
+            let color = match (state.hovered == Some(*button), button) {
+                (true, DecorationButton::Close) => state.theme.close_hover_color,
+                (true, _) => state.theme.button_hover_color,
+                (false, _) => state.theme.button_color,
+            };
+            (rect.to_physical_precise_round(scale), color)
+        }));
+
+        DecorationRenderElement {
+            id: self.0.id.clone(),
+            commit: state.commit_counter,
+            geometry: Rectangle::from_loc_and_size(
+                location,
+                layout.frame.size.to_physical_precise_round(scale),
+            ),
+            parts,
+        }
+    }
+}
+
+impl SpaceElement for DecoratedWindow {
+    fn geometry(&self) -> Rectangle<i32, Logical> {
+        let state = self.0.state.lock().unwrap();
+        if state.decorated {
+            Rectangle::from_loc_and_size((0, 0), state.theme.frame_size(self.0.window.geometry().size))
+        } else {
+            self.0.window.geometry()
+        }
+    }
+
+    fn bbox(&self) -> Rectangle<i32, Logical> {
+        let mut bbox = SpaceElement::bbox(&self.0.window);
+        bbox.loc += self.window_offset();
+        if self.is_decorated() {
+            bbox = bbox.merge(SpaceElement::geometry(self));
+        }
+        bbox
+    }
+
+    fn is_in_input_region(&self, point: &Point<f64, Logical>) -> bool {
+        self.hit_test(*point).is_some()
+            || SpaceElement::is_in_input_region(&self.0.window, &(*point - self.window_offset().to_f64()))
+    }
+
+    fn z_index(&self) -> u8 {
+        SpaceElement::z_index(&self.0.window)
+    }
+
+    fn set_activate(&self, activated: bool) {
+        {
+            let mut state = self.0.state.lock().unwrap();
+            if state.activated != activated {
+                state.activated = activated;
+                state.commit_counter.increment();
+            }
+        }
+        SpaceElement::set_activate(&self.0.window, activated)
+    }
+
+    fn output_enter(&self, output: &Output, overlap: Rectangle<i32, Logical>) {
+        SpaceElement::output_enter(&self.0.window, output, overlap)
+    }
+
+    fn output_leave(&self, output: &Output) {
+        SpaceElement::output_leave(&self.0.window, output)
+    }
+
+    fn refresh(&self) {
+        SpaceElement::refresh(&self.0.window)
+    }
+}
+
+impl ResizableElement for DecoratedWindow {
+    fn min_size(&self) -> Size<i32, Logical> {
+        self.frame_constraint(ResizableElement::min_size(&self.0.window))
+    }
+
+    fn max_size(&self) -> Size<i32, Logical> {
+        self.frame_constraint(ResizableElement::max_size(&self.0.window))
+    }
+
+    fn request_size(&self, size: Size<i32, Logical>, resizing: bool) -> bool {
+        let size = {
+            let state = self.0.state.lock().unwrap();
+            if state.decorated {
+                state.theme.window_size(size)
+            } else {
+                size
+            }
+        };
+        ResizableElement::request_size(&self.0.window, size, resizing)
+    }
+
+    fn resize_state(&self) -> ResizeState {
+        ResizableElement::resize_state(&self.0.window)
+    }
+
+    fn set_resize_state(&self, state: ResizeState) {
+        ResizableElement::set_resize_state(&self.0.window, state)
+    }
+}
+
+crate::backend::renderer::element::render_elements! {
+    /// Render elements of a [`DecoratedWindow`]
+    pub DecoratedWindowRenderElement<R> where
+        R: ImportAll;
+    /// The surfaces of the window
+    Window=WaylandSurfaceRenderElement,
+    /// The decorations
+    Decoration=DecorationRenderElement,
+}
+
+impl<R> AsRenderElements<R> for DecoratedWindow
+where
+    R: Renderer + ImportAll,
+    <R as Renderer>::TextureId: 'static,
+{
+    type RenderElement = DecoratedWindowRenderElement<R>;
+
+    fn render_elements<C: From<DecoratedWindowRenderElement<R>>>(
+        &self,
+        location: Point<i32, Physical>,
+        scale: Scale<f64>,
+    ) -> Vec<C> {
+        let window_location = location + self.window_offset().to_physical_precise_round(scale);
+        let mut elements = AsRenderElements::<R>::render_elements::<DecoratedWindowRenderElement<R>>(
+            &self.0.window,
+            window_location,
+            scale,
+        )
+        .into_iter()
+        .map(C::from)
+        .collect::<Vec<_>>();
+
+        if self.is_decorated() {
+            // decorations are drawn below the window
+            elements.push(C::from(DecoratedWindowRenderElement::Decoration(
+                self.render_element(location, scale),
+            )));
+        }
+
+        elements
+    }
+}
+
+/// Render element drawing the decorations of a [`DecoratedWindow`]
+///
+/// The titlebar, borders and buttons are drawn as opaque solid rectangles
+/// using [`Frame::clear`](crate::backend::renderer::Frame::clear), which does not blend.
+/// Therefore the alpha value of the element is ignored as well.
+#[derive(Debug)]
+pub struct DecorationRenderElement {
+    id: Id,
+    commit: CommitCounter,
+    geometry: Rectangle<i32, Physical>,
+    parts: Vec<(Rectangle<i32, Physical>, [f32; 3])>,
+}
+
+impl<R: Renderer> RenderElement<R> for DecorationRenderElement {
+    fn id(&self) -> &Id {
+        &self.id
+    }
+
+    fn current_commit(&self) -> CommitCounter {
+        self.commit
+    }
+
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        Rectangle::from_loc_and_size(
+            (0.0, 0.0),
+            (self.geometry.size.w as f64, self.geometry.size.h as f64),
+        )
+    }
+
+    fn geometry(&self, _scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.geometry
+    }
+
+    fn damage_since(
+        &self,
+        _scale: Scale<f64>,
+        commit: Option<CommitCounter>,
+    ) -> Vec<Rectangle<i32, Physical>> {
+        if commit != Some(self.commit) {
+            // only the decorations changed, not the area covered by the window
+            self.parts.iter().map(|(rect, _)| *rect).collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn opaque_regions(&self, _scale: Scale<f64>) -> Vec<Rectangle<i32, Physical>> {
+        self.parts.iter().map(|(rect, _)| *rect).collect()
+    }
+
+    fn draw(
+        &self,
+        _renderer: &mut R,
+        frame: &mut <R as Renderer>::Frame,
+        location: Point<i32, Physical>,
+        _scale: Scale<f64>,
+        damage: &[Rectangle<i32, Physical>],
+        _log: &slog::Logger,
+    ) -> Result<(), <R as Renderer>::Error> {
+        for (rect, color) in &self.parts {
+            let damage = damage
+                .iter()
+                .filter_map(|damage| damage.intersection(*rect))
+                .map(|mut rect| {
+                    rect.loc += location;
+                    rect
+                })
+                .collect::<Vec<_>>();
+            // `Frame::clear` does not blend, so the decorations are always drawn opaque
+            frame.clear([color[0], color[1], color[2], 1.0], &damage)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_test_layout() {
+        let theme = DecorationTheme {
+            titlebar_height: 20,
+            border_width: 2,
+            corner_size: 10,
+            button_size: 10,
+            button_spacing: 5,
+            ..Default::default()
+        };
+        let layout = theme.layout((100, 50).into());
+        assert_eq!(layout.frame, Rectangle::from_loc_and_size((0, 0), (104, 74)));
+        assert_eq!(layout.window, Rectangle::from_loc_and_size((2, 22), (100, 50)));
+        assert_eq!(theme.window_size(layout.frame.size), (100, 50).into());
+
+        let hit = |x: f64, y: f64| layout.hit_test((x, y).into(), theme.corner_size);
+        assert_eq!(hit(50.0, 10.0), Some(DecorationHit::Titlebar));
+        assert_eq!(hit(50.0, 40.0), None);
+        assert_eq!(hit(200.0, 40.0), None);
+        assert_eq!(
+            hit(90.0, 12.0),
+            Some(DecorationHit::Button(DecorationButton::Close))
+        );
+        assert_eq!(
+            hit(75.0, 12.0),
+            Some(DecorationHit::Button(DecorationButton::Maximize))
+        );
+        assert_eq!(hit(50.0, 0.5), Some(DecorationHit::Border(ResizeEdge::Top)));
+        assert_eq!(hit(5.0, 0.5), Some(DecorationHit::Border(ResizeEdge::TopLeft)));
+        assert_eq!(hit(1.0, 40.0), Some(DecorationHit::Border(ResizeEdge::Left)));
+        assert_eq!(
+            hit(103.0, 73.0),
+            Some(DecorationHit::Border(ResizeEdge::BottomRight))
+        );
+    }
+}
@@ -0,0 +1,749 @@
+//! Interactive move and resize grabs for elements of a [`Space`]
+//!
+//! [`MoveSurfaceGrab`] and [`ResizeSurfaceGrab`] implement [`PointerGrab`] for any element,
+//! that is mapped onto a [`Space`]. Your state has to implement [`SpaceGrabHandler`] to give
+//! the grabs access to the space of the grabbed element.
+//!
+//! The actual logic lives in the input-agnostic [`MoveGrab`] and [`ResizeGrab`].
+//! [`MoveSurfaceTouchGrab`] and [`ResizeSurfaceTouchGrab`] use them to move or resize elements via
+//! touch input, see [`TouchGrab`] for how to drive them.
+//!
+//! Resizing requires the element to implement [`ResizableElement`], which is provided for [`Window`]
+//! and [`DecoratedWindow`](super::decoration::DecoratedWindow). To keep the opposite edges of the element
+//! in place, while resizing the top or left edge, you need to call [`ack_resize`] from
+//! [`XdgShellHandler::ack_configure`](crate::wayland::shell::xdg::XdgShellHandler::ack_configure)
+//! and [`commit_resize`] for every commit of the element.
+//!
+//! ```no_run
+//! # use smithay::{
+//! #     desktop::{Space, Window, grabs::{ResizeSurfaceGrab, SpaceGrabHandler}},
+//! #     input::{Seat, SeatHandler, SeatState, pointer::Focus},
+//! #     reexports::wayland_protocols::xdg::shell::server::xdg_toplevel,
+//! #     reexports::wayland_server::protocol::wl_surface::WlSurface,
+//! #     utils::Serial,
+//! # };
+//! # struct State { space: Space<Window>, seat_state: SeatState<State> }
+//! # impl SeatHandler for State {
+//! #     type KeyboardFocus = WlSurface;
+//! #     type PointerFocus = WlSurface;
+//! #     fn seat_state(&mut self) -> &mut SeatState<Self> { &mut self.seat_state }
+//! # }
+//! impl SpaceGrabHandler<Window> for State {
+//!     fn space_for_element(&mut self, _window: &Window) -> Option<&mut Space<Window>> {
+//!         Some(&mut self.space)
+//!     }
+//! }
+//!
+//! # let state: &mut State = unimplemented!();
+//! # let seat: Seat<State> = unimplemented!();
+//! # let window: Window = unimplemented!();
+//! # let serial: Serial = unimplemented!();
+//! # let edges = xdg_toplevel::ResizeEdge::TopLeft;
+//! // in `XdgShellHandler::resize_request`
+//! let pointer = seat.get_pointer().unwrap();
+//! if let Some(start_data) = pointer.grab_start_data().filter(|_| pointer.has_grab(serial)) {
+//!     let geometry = state.space.element_geometry(&window).unwrap();
+//!     let grab = ResizeSurfaceGrab::new(start_data, window, edges.into(), geometry);
+//!     pointer.set_grab(state, grab, serial, Focus::Clear);
+//! }
+//! ```
+
+use std::sync::Mutex;
+
+use wayland_protocols::xdg::shell::server::xdg_toplevel;
+
+use crate::{
+    backend::input::TouchSlot,
+    desktop::{space::SpaceElement, Kind, Space, Window},
+    input::{
+        pointer::{
+            AxisFrame, ButtonEvent, GrabStartData as PointerGrabStartData, MotionEvent, PointerGrab,
+            PointerInnerHandle,
+        },
+        SeatHandler,
+    },
+    utils::{IsAlive, Logical, Point, Rectangle, Size},
+    wayland::{
+        compositor::with_states,
+        shell::xdg::{Configure, SurfaceCachedState},
+    },
+};
+
+bitflags::bitflags! {
+    /// Edges of an element being resized
+    pub struct ResizeEdge: u32 {
+        /// Top edge
+        const TOP = 0b0001;
+        /// Bottom edge
+        const BOTTOM = 0b0010;
+        /// Left edge
+        const LEFT = 0b0100;
+        /// Right edge
+        const RIGHT = 0b1000;
+
+        /// Top and left edge
+        const TOP_LEFT = Self::TOP.bits | Self::LEFT.bits;
+        /// Bottom and left edge
+        const BOTTOM_LEFT = Self::BOTTOM.bits | Self::LEFT.bits;
+        /// Top and right edge
+        const TOP_RIGHT = Self::TOP.bits | Self::RIGHT.bits;
+        /// Bottom and right edge
+        const BOTTOM_RIGHT = Self::BOTTOM.bits | Self::RIGHT.bits;
+    }
+}
+
+impl From<xdg_toplevel::ResizeEdge> for ResizeEdge {
+    #[inline]
+    fn from(x: xdg_toplevel::ResizeEdge) -> Self {
+        Self::from_bits_truncate(x as u32)
+    }
+}
+
+impl From<ResizeEdge> for xdg_toplevel::ResizeEdge {
+    #[inline]
+    fn from(x: ResizeEdge) -> Self {
+        use std::convert::TryFrom;
+
+        Self::try_from(x.bits()).unwrap_or(xdg_toplevel::ResizeEdge::None)
+    }
+}
+
+/// Information about an ongoing resize operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResizeData {
+    /// The edges the element is being resized with
+    pub edges: ResizeEdge,
+    /// The location of the element in its space, when the resize started
+    pub initial_location: Point<i32, Logical>,
+    /// The size of the element, when the resize started
+    pub initial_size: Size<i32, Logical>,
+}
+
+/// State of the resize operation of an element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizeState {
+    /// The element is not being resized
+    NotResizing,
+    /// The element is currently being resized
+    Resizing(ResizeData),
+    /// The resize has finished and the client needs to acknowledge the final configure
+    WaitingForFinalAck(ResizeData),
+    /// The resize has finished and the client needs to commit its final state
+    WaitingForCommit(ResizeData),
+}
+
+impl Default for ResizeState {
+    fn default() -> Self {
+        ResizeState::NotResizing
+    }
+}
+
+impl ResizeState {
+    /// Returns the data of the ongoing resize operation, if any
+    pub fn data(&self) -> Option<ResizeData> {
+        match *self {
+            ResizeState::NotResizing => None,
+            ResizeState::Resizing(data)
+            | ResizeState::WaitingForFinalAck(data)
+            | ResizeState::WaitingForCommit(data) => Some(data),
+        }
+    }
+}
+
+/// Elements that can be resized interactively by a [`ResizeGrab`]
+pub trait ResizableElement: SpaceElement {
+    /// Minimum size of the element, a zero component means unconstrained
+    fn min_size(&self) -> Size<i32, Logical> {
+        Size::default()
+    }
+    /// Maximum size of the element, a zero component means unconstrained
+    fn max_size(&self) -> Size<i32, Logical> {
+        Size::default()
+    }
+    /// Requests the element to change its geometry to a given size
+    ///
+    /// `resizing` is `true`, while the interactive resize is still ongoing.
+    /// Returns `true` if the client has to acknowledge the new size, or
+    /// `false`, if the request will take effect with the next commit.
+    fn request_size(&self, size: Size<i32, Logical>, resizing: bool) -> bool;
+    /// Returns the state of the resize operation of this element
+    fn resize_state(&self) -> ResizeState;
+    /// Sets the state of the resize operation of this element
+    fn set_resize_state(&self, state: ResizeState);
+}
+
+type ResizeStateUserData = Mutex<ResizeState>;
+
+impl ResizableElement for Window {
+    fn min_size(&self) -> Size<i32, Logical> {
+        with_states(self.toplevel().wl_surface(), |states| {
+            states.cached_state.current::<SurfaceCachedState>().min_size
+        })
+    }
+
+    fn max_size(&self) -> Size<i32, Logical> {
+        with_states(self.toplevel().wl_surface(), |states| {
+            states.cached_state.current::<SurfaceCachedState>().max_size
+        })
+    }
+
+    fn request_size(&self, size: Size<i32, Logical>, resizing: bool) -> bool {
+        match self.toplevel() {
+            Kind::Xdg(xdg) => {
+                xdg.with_pending_state(|state| {
+                    if resizing {
+                        state.states.set(xdg_toplevel::State::Resizing);
+                    } else {
+                        state.states.unset(xdg_toplevel::State::Resizing);
+                    }
+                    state.size = Some(size);
+                });
+                xdg.send_configure();
+                true
+            }
+            // X11 windows just commit their new size
+            #[cfg(feature = "xwayland")]
+            Kind::X11(x11) => {
+                x11.configure(size);
+                false
+            }
+        }
+    }
+
+    fn resize_state(&self) -> ResizeState {
+        with_states(self.toplevel().wl_surface(), |states| {
+            states
+                .data_map
+                .get::<ResizeStateUserData>()
+                .map(|state| *state.lock().unwrap())
+                .unwrap_or_default()
+        })
+    }
+
+    fn set_resize_state(&self, state: ResizeState) {
+        with_states(self.toplevel().wl_surface(), |states| {
+            states
+                .data_map
+                .insert_if_missing_threadsafe(ResizeStateUserData::default);
+            *states
+                .data_map
+                .get::<ResizeStateUserData>()
+                .unwrap()
+                .lock()
+                .unwrap() = state;
+        })
+    }
+}
+
+/// Gives grabs access to the [`Space`] of the grabbed element
+pub trait SpaceGrabHandler<E: SpaceElement>: SeatHandler {
+    /// Returns the space the element is mapped on
+    fn space_for_element(&mut self, element: &E) -> Option<&mut Space<E>>;
+}
+
+/// Input-agnostic logic of an interactive move
+#[derive(Debug)]
+pub struct MoveGrab<E> {
+    element: E,
+    start_location: Point<f64, Logical>,
+    initial_location: Point<i32, Logical>,
+}
+
+impl<E: SpaceElement + Clone + PartialEq> MoveGrab<E> {
+    /// Starts moving an element
+    ///
+    /// - `start_location` is the location of the pointer or touch point starting the move
+    /// - `initial_location` is the location of the element inside its space
+    pub fn new(
+        element: E,
+        start_location: Point<f64, Logical>,
+        initial_location: Point<i32, Logical>,
+    ) -> Self {
+        MoveGrab {
+            element,
+            start_location,
+            initial_location,
+        }
+    }
+
+    /// The element being moved
+    pub fn element(&self) -> &E {
+        &self.element
+    }
+
+    /// Moves the element according to a new pointer or touch point location
+    pub fn motion<D: SpaceGrabHandler<E>>(&mut self, data: &mut D, location: Point<f64, Logical>) {
+        let new_location = self.initial_location.to_f64() + (location - self.start_location);
+        if let Some(space) = data.space_for_element(&self.element) {
+            space.map_element(self.element.clone(), new_location.to_i32_round(), true);
+        }
+    }
+}
+
+/// Input-agnostic logic of an interactive resize
+#[derive(Debug)]
+pub struct ResizeGrab<E> {
+    element: E,
+    start_location: Point<f64, Logical>,
+    data: ResizeData,
+    last_size: Size<i32, Logical>,
+}
+
+impl<E: ResizableElement> ResizeGrab<E> {
+    /// Starts resizing an element
+    ///
+    /// - `start_location` is the location of the pointer or touch point starting the resize
+    /// - `initial_geometry` is the geometry of the element inside its space
+    pub fn new(
+        element: E,
+        edges: ResizeEdge,
+        start_location: Point<f64, Logical>,
+        initial_geometry: Rectangle<i32, Logical>,
+    ) -> Self {
+        let data = ResizeData {
+            edges,
+            initial_location: initial_geometry.loc,
+            initial_size: initial_geometry.size,
+        };
+        element.set_resize_state(ResizeState::Resizing(data));
+
+        ResizeGrab {
+            element,
+            start_location,
+            data,
+            last_size: initial_geometry.size,
+        }
+    }
+
+    /// The element being resized
+    pub fn element(&self) -> &E {
+        &self.element
+    }
+
+    /// Resizes the element according to a new pointer or touch point location
+    ///
+    /// Returns `false` if the element is dead and the grab should be released.
+    pub fn motion(&mut self, location: Point<f64, Logical>) -> bool {
+        if !self.element.alive() {
+            return false;
+        }
+
+        let new_size = resized_size(
+            self.data.initial_size,
+            self.data.edges,
+            location - self.start_location,
+            self.element.min_size(),
+            self.element.max_size(),
+        );
+
+        if new_size != self.last_size {
+            self.last_size = new_size;
+            self.element.request_size(new_size, true);
+        }
+        true
+    }
+
+    /// Finishes the resize
+    pub fn release(&mut self) {
+        if !self.element.alive() {
+            return;
+        }
+
+        if self.element.request_size(self.last_size, false) {
+            self.element
+                .set_resize_state(ResizeState::WaitingForFinalAck(self.data));
+        } else {
+            self.element
+                .set_resize_state(ResizeState::WaitingForCommit(self.data));
+        }
+    }
+}
+
+// Size of an element resized with the given edges by `delta`, clamped to its size constraints
+fn resized_size(
+    initial_size: Size<i32, Logical>,
+    edges: ResizeEdge,
+    mut delta: Point<f64, Logical>,
+    min_size: Size<i32, Logical>,
+    max_size: Size<i32, Logical>,
+) -> Size<i32, Logical> {
+    let mut new_size = initial_size;
+
+    if edges.intersects(ResizeEdge::LEFT | ResizeEdge::RIGHT) {
+        if edges.intersects(ResizeEdge::LEFT) {
+            delta.x = -delta.x;
+        }
+        new_size.w = (initial_size.w as f64 + delta.x) as i32;
+    }
+    if edges.intersects(ResizeEdge::TOP | ResizeEdge::BOTTOM) {
+        if edges.intersects(ResizeEdge::TOP) {
+            delta.y = -delta.y;
+        }
+        new_size.h = (initial_size.h as f64 + delta.y) as i32;
+    }
+
+    let max_w = if max_size.w == 0 { i32::MAX } else { max_size.w };
+    let max_h = if max_size.h == 0 { i32::MAX } else { max_size.h };
+    // max might be smaller than min for misbehaving clients, min wins in that case
+    new_size.w = new_size.w.min(max_w).max(min_size.w.max(1));
+    new_size.h = new_size.h.min(max_h).max(min_size.h.max(1));
+    new_size
+}
+
+/// Updates the resize state of an element, once a configure got acknowledged
+///
+/// Should be called from [`XdgShellHandler::ack_configure`](crate::wayland::shell::xdg::XdgShellHandler::ack_configure).
+pub fn ack_resize<E: ResizableElement>(element: &E, configure: &Configure) {
+    if let Configure::Toplevel(configure) = configure {
+        if let ResizeState::WaitingForFinalAck(data) = element.resize_state() {
+            if !configure.state.states.contains(xdg_toplevel::State::Resizing) {
+                element.set_resize_state(ResizeState::WaitingForCommit(data));
+            }
+        }
+    }
+}
+
+/// Keeps the opposite edges of a resized element in place and finishes the resize operation
+///
+/// Should be called for every commit of the element.
+pub fn commit_resize<E: ResizableElement + Clone + PartialEq>(space: &mut Space<E>, element: &E) {
+    let state = element.resize_state();
+    let data = match state.data() {
+        Some(data) => data,
+        None => return,
+    };
+    if let ResizeState::WaitingForCommit(_) = state {
+        element.set_resize_state(ResizeState::NotResizing);
+    }
+
+    if !data.edges.intersects(ResizeEdge::TOP_LEFT) {
+        return;
+    }
+    let mut location = match space.element_location(element) {
+        Some(location) => location,
+        None => return,
+    };
+    let size = element.geometry().size;
+    if data.edges.intersects(ResizeEdge::LEFT) {
+        location.x = data.initial_location.x + (data.initial_size.w - size.w);
+    }
+    if data.edges.intersects(ResizeEdge::TOP) {
+        location.y = data.initial_location.y + (data.initial_size.h - size.h);
+    }
+    space.map_element(element.clone(), location, false);
+}
+
+/// Pointer grab moving an element of a [`Space`]
+pub struct MoveSurfaceGrab<D: SeatHandler, E> {
+    start_data: PointerGrabStartData<D>,
+    grab: MoveGrab<E>,
+}
+
+impl<D: SeatHandler + 'static, E: std::fmt::Debug> std::fmt::Debug for MoveSurfaceGrab<D, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MoveSurfaceGrab")
+            .field("start_data", &self.start_data)
+            .field("grab", &self.grab)
+            .finish()
+    }
+}
+
+impl<D: SeatHandler, E: SpaceElement + Clone + PartialEq> MoveSurfaceGrab<D, E> {
+    /// Creates a new grab moving an element located at `initial_location` inside its space
+    pub fn new(
+        start_data: PointerGrabStartData<D>,
+        element: E,
+        initial_location: Point<i32, Logical>,
+    ) -> Self {
+        let grab = MoveGrab::new(element, start_data.location, initial_location);
+        MoveSurfaceGrab { start_data, grab }
+    }
+
+    /// The element being moved
+    pub fn element(&self) -> &E {
+        self.grab.element()
+    }
+}
+
+impl<D, E> PointerGrab<D> for MoveSurfaceGrab<D, E>
+where
+    D: SpaceGrabHandler<E> + 'static,
+    E: SpaceElement + Clone + PartialEq + Send + 'static,
+{
+    fn motion(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        _focus: Option<(<D as SeatHandler>::PointerFocus, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        // While the grab is active, no client has pointer focus
+        handle.motion(data, None, event);
+        self.grab.motion(data, event.location);
+    }
+
+    fn button(&mut self, data: &mut D, handle: &mut PointerInnerHandle<'_, D>, event: &ButtonEvent) {
+        handle.button(data, event);
+        if handle.current_pressed().is_empty() {
+            // No more buttons are pressed, release the grab.
+            handle.unset_grab(data, event.serial, event.time);
+        }
+    }
+
+    fn axis(&mut self, data: &mut D, handle: &mut PointerInnerHandle<'_, D>, details: AxisFrame) {
+        handle.axis(data, details)
+    }
+
+    fn start_data(&self) -> &PointerGrabStartData<D> {
+        &self.start_data
+    }
+}
+
+/// Pointer grab resizing an element of a [`Space`]
+pub struct ResizeSurfaceGrab<D: SeatHandler, E> {
+    start_data: PointerGrabStartData<D>,
+    grab: ResizeGrab<E>,
+}
+
+impl<D: SeatHandler + 'static, E: std::fmt::Debug> std::fmt::Debug for ResizeSurfaceGrab<D, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResizeSurfaceGrab")
+            .field("start_data", &self.start_data)
+            .field("grab", &self.grab)
+            .finish()
+    }
+}
+
+impl<D: SeatHandler, E: ResizableElement> ResizeSurfaceGrab<D, E> {
+    /// Creates a new grab resizing an element with the given geometry inside its space
+    pub fn new(
+        start_data: PointerGrabStartData<D>,
+        element: E,
+        edges: ResizeEdge,
+        initial_geometry: Rectangle<i32, Logical>,
+    ) -> Self {
+        let grab = ResizeGrab::new(element, edges, start_data.location, initial_geometry);
+        ResizeSurfaceGrab { start_data, grab }
+    }
+
+    /// The element being resized
+    pub fn element(&self) -> &E {
+        self.grab.element()
+    }
+}
+
+impl<D, E> PointerGrab<D> for ResizeSurfaceGrab<D, E>
+where
+    D: SeatHandler + 'static,
+    E: ResizableElement + Send + 'static,
+{
+    fn motion(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        _focus: Option<(<D as SeatHandler>::PointerFocus, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        // While the grab is active, no client has pointer focus
+        handle.motion(data, None, event);
+
+        if !self.grab.motion(event.location) {
+            handle.unset_grab(data, event.serial, event.time);
+        }
+    }
+
+    fn button(&mut self, data: &mut D, handle: &mut PointerInnerHandle<'_, D>, event: &ButtonEvent) {
+        handle.button(data, event);
+        if handle.current_pressed().is_empty() {
+            // No more buttons are pressed, release the grab.
+            handle.unset_grab(data, event.serial, event.time);
+            self.grab.release();
+        }
+    }
+
+    fn axis(&mut self, data: &mut D, handle: &mut PointerInnerHandle<'_, D>, details: AxisFrame) {
+        handle.axis(data, details)
+    }
+
+    fn start_data(&self) -> &PointerGrabStartData<D> {
+        &self.start_data
+    }
+}
+
+/// Touch grab moving or resizing an element of a [`Space`]
+///
+/// Touch events are not routed through grabs by the seat, so while a touch grab is active,
+/// forward the motion and up events of all touch points to it instead of sending them to clients
+/// and drop it, once [`TouchGrab::up`] returns `true` or the touch sequence got cancelled.
+pub trait TouchGrab<D> {
+    /// The touch point, that started the grab
+    fn slot(&self) -> TouchSlot;
+    /// A touch point moved to a new location
+    fn motion(&mut self, data: &mut D, slot: TouchSlot, location: Point<f64, Logical>);
+    /// A touch point was lifted
+    ///
+    /// Returns `true` if the grab has ended.
+    fn up(&mut self, data: &mut D, slot: TouchSlot) -> bool;
+    /// The touch sequence was cancelled, the grab ends
+    fn cancel(&mut self, data: &mut D);
+}
+
+/// Touch grab moving an element of a [`Space`]
+#[derive(Debug)]
+pub struct MoveSurfaceTouchGrab<E> {
+    slot: TouchSlot,
+    grab: MoveGrab<E>,
+}
+
+impl<E: SpaceElement + Clone + PartialEq> MoveSurfaceTouchGrab<E> {
+    /// Creates a new grab moving an element located at `initial_location` inside its space
+    ///
+    /// `start_location` is the location of the touch point `slot` starting the move.
+    pub fn new(
+        slot: TouchSlot,
+        start_location: Point<f64, Logical>,
+        element: E,
+        initial_location: Point<i32, Logical>,
+    ) -> Self {
+        MoveSurfaceTouchGrab {
+            slot,
+            grab: MoveGrab::new(element, start_location, initial_location),
+        }
+    }
+
+    /// The element being moved
+    pub fn element(&self) -> &E {
+        self.grab.element()
+    }
+}
+
+impl<D, E> TouchGrab<D> for MoveSurfaceTouchGrab<E>
+where
+    D: SpaceGrabHandler<E>,
+    E: SpaceElement + Clone + PartialEq,
+{
+    fn slot(&self) -> TouchSlot {
+        self.slot
+    }
+
+    fn motion(&mut self, data: &mut D, slot: TouchSlot, location: Point<f64, Logical>) {
+        if slot == self.slot {
+            self.grab.motion(data, location);
+        }
+    }
+
+    fn up(&mut self, _data: &mut D, slot: TouchSlot) -> bool {
+        slot == self.slot
+    }
+
+    fn cancel(&mut self, _data: &mut D) {}
+}
+
+/// Touch grab resizing an element of a [`Space`]
+#[derive(Debug)]
+pub struct ResizeSurfaceTouchGrab<E> {
+    slot: TouchSlot,
+    grab: ResizeGrab<E>,
+}
+
+impl<E: ResizableElement> ResizeSurfaceTouchGrab<E> {
+    /// Creates a new grab resizing an element with the given geometry inside its space
+    ///
+    /// `start_location` is the location of the touch point `slot` starting the resize.
+    pub fn new(
+        slot: TouchSlot,
+        start_location: Point<f64, Logical>,
+        element: E,
+        edges: ResizeEdge,
+        initial_geometry: Rectangle<i32, Logical>,
+    ) -> Self {
+        ResizeSurfaceTouchGrab {
+            slot,
+            grab: ResizeGrab::new(element, edges, start_location, initial_geometry),
+        }
+    }
+
+    /// The element being resized
+    pub fn element(&self) -> &E {
+        self.grab.element()
+    }
+}
+
+impl<D, E: ResizableElement> TouchGrab<D> for ResizeSurfaceTouchGrab<E> {
+    fn slot(&self) -> TouchSlot {
+        self.slot
+    }
+
+    fn motion(&mut self, _data: &mut D, slot: TouchSlot, location: Point<f64, Logical>) {
+        if slot == self.slot {
+            self.grab.motion(location);
+        }
+    }
+
+    fn up(&mut self, _data: &mut D, slot: TouchSlot) -> bool {
+        if slot != self.slot {
+            return false;
+        }
+        self.grab.release();
+        true
+    }
+
+    fn cancel(&mut self, _data: &mut D) {
+        self.grab.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resize(edges: ResizeEdge, delta: (f64, f64), min: (i32, i32), max: (i32, i32)) -> (i32, i32) {
+        resized_size((100, 50).into(), edges, delta.into(), min.into(), max.into()).into()
+    }
+
+    #[test]
+    fn resize_follows_edges() {
+        assert_eq!(resize(ResizeEdge::RIGHT, (10.0, 20.0), (0, 0), (0, 0)), (110, 50));
+        assert_eq!(
+            resize(ResizeEdge::BOTTOM, (10.0, 20.0), (0, 0), (0, 0)),
+            (100, 70)
+        );
+        // moving the left or top edge to the right or bottom shrinks the element
+        assert_eq!(resize(ResizeEdge::LEFT, (10.0, 20.0), (0, 0), (0, 0)), (90, 50));
+        assert_eq!(
+            resize(ResizeEdge::TOP_LEFT, (10.0, 20.0), (0, 0), (0, 0)),
+            (90, 30)
+        );
+        assert_eq!(
+            resize(ResizeEdge::BOTTOM_RIGHT, (-10.5, 20.5), (0, 0), (0, 0)),
+            (89, 70)
+        );
+    }
+
+    #[test]
+    fn resize_is_clamped() {
+        // zero means unconstrained
+        assert_eq!(
+            resize(ResizeEdge::BOTTOM_RIGHT, (1000.0, 1000.0), (0, 0), (0, 0)),
+            (1100, 1050)
+        );
+        assert_eq!(
+            resize(ResizeEdge::BOTTOM_RIGHT, (1000.0, 1000.0), (0, 0), (200, 0)),
+            (200, 1050)
+        );
+        assert_eq!(
+            resize(ResizeEdge::BOTTOM_RIGHT, (-80.0, -40.0), (40, 0), (0, 0)),
+            (40, 10)
+        );
+        // the size never gets empty
+        assert_eq!(
+            resize(ResizeEdge::BOTTOM_RIGHT, (-200.0, -200.0), (0, 0), (0, 0)),
+            (1, 1)
+        );
+        // min wins over a smaller max
+        assert_eq!(
+            resize(ResizeEdge::BOTTOM_RIGHT, (0.0, 0.0), (120, 60), (110, 55)),
+            (120, 60)
+        );
+    }
+}
@@ -0,0 +1,583 @@
+//! Tiling layouts for elements of a [`Space`]
+//!
+//! A [`TilingLayout`] arranges a list of elements inside the usable area of an [`Output`],
+//! that is the area not covered by exclusive zones of layer surfaces
+//! (see [`LayerMap::non_exclusive_zone`](crate::desktop::LayerMap::non_exclusive_zone)).
+//!
+//! The placement itself is calculated by a [`Layout`]. Smithay provides [`MasterStack`],
+//! [`Columns`] and [`BspLayout`], but you can plug in your own algorithms by implementing the trait.
+//!
+//! Elements are resized through [`ResizableElement`], which respects their min and max size hints,
+//! and smoothly moved into their new location, if an animation duration is set.
+//! Call [`TilingLayout::refresh`] once per frame to advance running animations.
+//!
+//! ```no_run
+//! # use smithay::{desktop::{Space, Window, layout::{TilingLayout, MasterStack}}, output::Output};
+//! # use std::time::{Duration, Instant};
+//! # let mut space: Space<Window> = unimplemented!();
+//! # let output: Output = unimplemented!();
+//! # let window: Window = unimplemented!();
+//! let mut layout = TilingLayout::new(output, MasterStack::default());
+//! layout.set_animation_duration(Duration::from_millis(150));
+//!
+//! layout.add_element(window);
+//! layout.arrange(&mut space);
+//!
+//! // once per frame
+//! layout.refresh(&mut space, Instant::now());
+//! ```
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    desktop::{grabs::ResizableElement, layer_map_for_output, Space},
+    output::Output,
+    utils::{
+        animation::{Animation, Curve},
+        IsAlive, Logical, Point, Rectangle, Size,
+    },
+};
+
+/// Size hints of an element passed to a [`Layout`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LayoutHints {
+    /// Minimum size of the element, a zero component means unconstrained
+    pub min_size: Size<i32, Logical>,
+    /// Maximum size of the element, a zero component means unconstrained
+    pub max_size: Size<i32, Logical>,
+}
+
+impl LayoutHints {
+    /// Fits a rectangle of a layout to these hints
+    ///
+    /// The size is clamped to the min and max size. An element smaller than the
+    /// given rectangle is centered inside it.
+    pub fn fit(&self, rect: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+        let clamp = |value: i32, min: i32, max: i32| {
+            let value = if max > 0 { value.min(max) } else { value };
+            value.max(min).max(1)
+        };
+        let size = Size::from((
+            clamp(rect.size.w, self.min_size.w, self.max_size.w),
+            clamp(rect.size.h, self.min_size.h, self.max_size.h),
+        ));
+        let loc = Point::from((
+            rect.loc.x + ((rect.size.w - size.w) / 2).max(0),
+            rect.loc.y + ((rect.size.h - size.h) / 2).max(0),
+        ));
+        Rectangle::from_loc_and_size(loc, size)
+    }
+}
+
+/// Algorithm placing elements inside an area
+pub trait Layout: fmt::Debug {
+    /// Calculates the geometry of every element
+    ///
+    /// `hints` contains the size hints of the elements in the order of the layout,
+    /// the returned geometries need to be in the same order. The geometries are fitted to the
+    /// hints afterwards (see [`LayoutHints::fit`]), so layouts may ignore them.
+    ///
+    /// Exactly one geometry has to be returned per element. This is asserted in debug builds,
+    /// otherwise elements without a geometry are placed over the whole area.
+    fn arrange(
+        &mut self,
+        area: Rectangle<i32, Logical>,
+        hints: &[LayoutHints],
+    ) -> Vec<Rectangle<i32, Logical>>;
+}
+
+impl Layout for Box<dyn Layout> {
+    fn arrange(
+        &mut self,
+        area: Rectangle<i32, Logical>,
+        hints: &[LayoutHints],
+    ) -> Vec<Rectangle<i32, Logical>> {
+        (**self).arrange(area, hints)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Horizontal,
+    Vertical,
+}
+
+/// Splits an area into `count` equally sized parts along an axis with `gap` between them
+fn split(area: Rectangle<i32, Logical>, count: usize, axis: Axis, gap: i32) -> Vec<Rectangle<i32, Logical>> {
+    if count == 0 {
+        return Vec::new();
+    }
+    let count_i32 = count as i32;
+    let total = match axis {
+        Axis::Horizontal => area.size.w,
+        Axis::Vertical => area.size.h,
+    } - gap * (count_i32 - 1);
+    let base = total / count_i32;
+    let remainder = total % count_i32;
+
+    let mut offset = 0;
+    (0..count_i32)
+        .map(|i| {
+            // distribute the remaining pixels over the first parts
+            let length = base + i32::from(i < remainder);
+            let rect = match axis {
+                Axis::Horizontal => {
+                    Rectangle::from_loc_and_size((area.loc.x + offset, area.loc.y), (length, area.size.h))
+                }
+                Axis::Vertical => {
+                    Rectangle::from_loc_and_size((area.loc.x, area.loc.y + offset), (area.size.w, length))
+                }
+            };
+            offset += length + gap;
+            rect
+        })
+        .collect()
+}
+
+/// Splits an area into two parts along an axis, the first one having `ratio` of the available space
+fn split_ratio(
+    area: Rectangle<i32, Logical>,
+    ratio: f64,
+    axis: Axis,
+    gap: i32,
+) -> (Rectangle<i32, Logical>, Rectangle<i32, Logical>) {
+    let ratio = ratio.clamp(0.05, 0.95);
+    match axis {
+        Axis::Horizontal => {
+            let first = ((area.size.w - gap) as f64 * ratio).round() as i32;
+            (
+                Rectangle::from_loc_and_size(area.loc, (first, area.size.h)),
+                Rectangle::from_loc_and_size(
+                    (area.loc.x + first + gap, area.loc.y),
+                    (area.size.w - first - gap, area.size.h),
+                ),
+            )
+        }
+        Axis::Vertical => {
+            let first = ((area.size.h - gap) as f64 * ratio).round() as i32;
+            (
+                Rectangle::from_loc_and_size(area.loc, (area.size.w, first)),
+                Rectangle::from_loc_and_size(
+                    (area.loc.x, area.loc.y + first + gap),
+                    (area.size.w, area.size.h - first - gap),
+                ),
+            )
+        }
+    }
+}
+
+fn inset(area: Rectangle<i32, Logical>, gap: i32) -> Rectangle<i32, Logical> {
+    Rectangle::from_loc_and_size(
+        (area.loc.x + gap, area.loc.y + gap),
+        ((area.size.w - 2 * gap).max(1), (area.size.h - 2 * gap).max(1)),
+    )
+}
+
+/// Master-stack layout
+///
+/// The first `master_count` elements are stacked vertically in the master column on the left,
+/// all remaining elements are stacked vertically in a column on the right.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MasterStack {
+    /// Fraction of the width used by the master column
+    pub master_ratio: f64,
+    /// Number of elements in the master column
+    pub master_count: usize,
+    /// Gap between elements and around the area
+    pub gap: i32,
+}
+
+impl Default for MasterStack {
+    fn default() -> Self {
+        MasterStack {
+            master_ratio: 0.55,
+            master_count: 1,
+            gap: 0,
+        }
+    }
+}
+
+impl Layout for MasterStack {
+    fn arrange(
+        &mut self,
+        area: Rectangle<i32, Logical>,
+        hints: &[LayoutHints],
+    ) -> Vec<Rectangle<i32, Logical>> {
+        let area = inset(area, self.gap);
+        let count = hints.len();
+        let master_count = self.master_count.max(1);
+        if count <= master_count {
+            return split(area, count, Axis::Vertical, self.gap);
+        }
+
+        let (master, stack) = split_ratio(area, self.master_ratio, Axis::Horizontal, self.gap);
+        let mut geometries = split(master, master_count, Axis::Vertical, self.gap);
+        geometries.extend(split(stack, count - master_count, Axis::Vertical, self.gap));
+        geometries
+    }
+}
+
+/// Column layout
+///
+/// All elements are placed next to each other in equally sized columns.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Columns {
+    /// Gap between elements and around the area
+    pub gap: i32,
+}
+
+impl Layout for Columns {
+    fn arrange(
+        &mut self,
+        area: Rectangle<i32, Logical>,
+        hints: &[LayoutHints],
+    ) -> Vec<Rectangle<i32, Logical>> {
+        split(inset(area, self.gap), hints.len(), Axis::Horizontal, self.gap)
+    }
+}
+
+/// Binary space partitioning layout
+///
+/// Every element splits the area left by the previous elements in two, alternating
+/// along the longer side of the remaining area, forming a binary tree of partitions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BspLayout {
+    /// Fraction of the remaining area kept by each element
+    pub split_ratio: f64,
+    /// Gap between elements and around the area
+    pub gap: i32,
+}
+
+impl Default for BspLayout {
+    fn default() -> Self {
+        BspLayout {
+            split_ratio: 0.5,
+            gap: 0,
+        }
+    }
+}
+
+impl Layout for BspLayout {
+    fn arrange(
+        &mut self,
+        area: Rectangle<i32, Logical>,
+        hints: &[LayoutHints],
+    ) -> Vec<Rectangle<i32, Logical>> {
+        let mut remaining = inset(area, self.gap);
+        let mut geometries = Vec::with_capacity(hints.len());
+        for i in 0..hints.len() {
+            if i + 1 == hints.len() {
+                geometries.push(remaining);
+                break;
+            }
+            let axis = if remaining.size.w >= remaining.size.h {
+                Axis::Horizontal
+            } else {
+                Axis::Vertical
+            };
+            let (first, second) = split_ratio(remaining, self.split_ratio, axis, self.gap);
+            geometries.push(first);
+            remaining = second;
+        }
+        geometries
+    }
+}
+
+#[derive(Debug)]
+struct TiledElement<E> {
+    element: E,
+    geometry: Option<Rectangle<i32, Logical>>,
+    animation: Option<Animation<Point<i32, Logical>>>,
+}
+
+/// Tiles elements of a [`Space`] on an [`Output`] using a [`Layout`]
+pub struct TilingLayout<E, L = Box<dyn Layout>> {
+    output: Output,
+    layout: L,
+    elements: Vec<TiledElement<E>>,
+    animation_duration: Duration,
+}
+
+impl<E: fmt::Debug, L: fmt::Debug> fmt::Debug for TilingLayout<E, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TilingLayout")
+            .field("output", &self.output)
+            .field("layout", &self.layout)
+            .field("elements", &self.elements)
+            .field("animation_duration", &self.animation_duration)
+            .finish()
+    }
+}
+
+impl<E, L> TilingLayout<E, L>
+where
+    E: ResizableElement + Clone + PartialEq,
+    L: Layout,
+{
+    /// Creates a new tiling layout for an output
+    pub fn new(output: Output, layout: L) -> Self {
+        TilingLayout {
+            output,
+            layout,
+            elements: Vec::new(),
+            animation_duration: Duration::ZERO,
+        }
+    }
+
+    /// The output this layout arranges elements on
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    /// Access the layout algorithm
+    ///
+    /// Call [`TilingLayout::arrange`] after changing its parameters.
+    pub fn layout_mut(&mut self) -> &mut L {
+        &mut self.layout
+    }
+
+    /// Replaces the layout algorithm
+    ///
+    /// Call [`TilingLayout::arrange`] afterwards.
+    pub fn set_layout(&mut self, layout: L) {
+        self.layout = layout;
+    }
+
+    /// Sets the duration of placement animations, [`Duration::ZERO`] disables them
+    pub fn set_animation_duration(&mut self, duration: Duration) {
+        self.animation_duration = duration;
+    }
+
+    /// Iterates over the tiled elements in layout order
+    pub fn elements(&self) -> impl Iterator<Item = &E> {
+        self.elements.iter().map(|e| &e.element)
+    }
+
+    /// Returns the geometry an element got assigned by the last [`TilingLayout::arrange`]
+    pub fn element_geometry(&self, element: &E) -> Option<Rectangle<i32, Logical>> {
+        self.elements
+            .iter()
+            .find(|e| &e.element == element)
+            .and_then(|e| e.geometry)
+    }
+
+    /// Appends an element to the layout
+    ///
+    /// Does nothing if the element is already tiled.
+    pub fn add_element(&mut self, element: E) {
+        if !self.elements.iter().any(|e| e.element == element) {
+            self.elements.push(TiledElement {
+                element,
+                geometry: None,
+                animation: None,
+            });
+        }
+    }
+
+    /// Removes an element from the layout
+    ///
+    /// Returns `true` if the element was tiled. The element stays mapped in the space.
+    pub fn remove_element(&mut self, element: &E) -> bool {
+        let len = self.elements.len();
+        self.elements.retain(|e| &e.element != element);
+        len != self.elements.len()
+    }
+
+    /// Moves an element to the first position of the layout (e.g. the master area)
+    pub fn promote_element(&mut self, element: &E) {
+        if let Some(pos) = self.elements.iter().position(|e| &e.element == element) {
+            let tiled = self.elements.remove(pos);
+            self.elements.insert(0, tiled);
+        }
+    }
+
+    /// Swaps the positions of two elements in the layout
+    pub fn swap_elements(&mut self, first: &E, second: &E) {
+        let first = self.elements.iter().position(|e| &e.element == first);
+        let second = self.elements.iter().position(|e| &e.element == second);
+        if let (Some(first), Some(second)) = (first, second) {
+            self.elements.swap(first, second);
+        }
+    }
+
+    /// Returns the area elements are arranged in, the output geometry minus exclusive zones
+    pub fn area(&self, space: &Space<E>) -> Option<Rectangle<i32, Logical>> {
+        let output_geometry = space.output_geometry(&self.output)?;
+        let zone = layer_map_for_output(&self.output).non_exclusive_zone();
+        Some(Rectangle::from_loc_and_size(
+            output_geometry.loc + zone.loc,
+            zone.size,
+        ))
+    }
+
+    /// Arranges all elements and maps them onto the space
+    ///
+    /// Sends a configure to every element, whose size changed. Dead elements are removed.
+    /// Does nothing if the output is not mapped in the space.
+    pub fn arrange(&mut self, space: &mut Space<E>) {
+        self.elements.retain(|e| e.element.alive());
+        let area = match self.area(space) {
+            Some(area) => area,
+            None => return,
+        };
+
+        let hints = self
+            .elements
+            .iter()
+            .map(|e| LayoutHints {
+                min_size: e.element.min_size(),
+                max_size: e.element.max_size(),
+            })
+            .collect::<Vec<_>>();
+        let geometries = self.layout.arrange(area, &hints);
+        debug_assert_eq!(
+            geometries.len(),
+            hints.len(),
+            "{:?} returned a geometry count not matching the element count",
+            self.layout
+        );
+        let geometries = complete_geometries(geometries, hints.len(), area);
+
+        let now = Instant::now();
+        for ((tiled, hints), geometry) in self.elements.iter_mut().zip(hints.iter()).zip(geometries) {
+            let geometry = hints.fit(geometry);
+
+            if tiled.geometry.map(|old| old.size) != Some(geometry.size) {
+                tiled.element.request_size(geometry.size, false);
+            }
+            tiled.geometry = Some(geometry);
+
+            let current = tiled
+                .animation
+                .as_ref()
+                .map(|animation| animation.value(now))
+                .or_else(|| space.element_location(&tiled.element));
+            match current {
+                Some(from) if from != geometry.loc && self.animation_duration > Duration::ZERO => {
+                    tiled.animation = Some(Animation::new(
+                        from,
+                        geometry.loc,
+                        now,
+                        self.animation_duration,
+                        Curve::EaseOut,
+                    ));
+                    space.map_element(tiled.element.clone(), from, false);
+                }
+                _ => {
+                    tiled.animation = None;
+                    space.map_element(tiled.element.clone(), geometry.loc, false);
+                }
+            }
+        }
+    }
+
+    /// Advances running placement animations
+    ///
+    /// Returns `true` if any animation is still running and the output needs to be redrawn.
+    pub fn refresh(&mut self, space: &mut Space<E>, now: Instant) -> bool {
+        let mut running = false;
+        for tiled in &mut self.elements {
+            if let Some(animation) = tiled.animation.as_ref() {
+                let location = animation.value(now);
+                if animation.is_done(now) {
+                    tiled.animation = None;
+                } else {
+                    running = true;
+                }
+                if space.element_location(&tiled.element) != Some(location) {
+                    space.map_element(tiled.element.clone(), location, false);
+                }
+            }
+        }
+        running
+    }
+}
+
+// Makes sure there is exactly one geometry per element, placing missing ones over the whole area
+fn complete_geometries(
+    mut geometries: Vec<Rectangle<i32, Logical>>,
+    count: usize,
+    area: Rectangle<i32, Logical>,
+) -> Vec<Rectangle<i32, Logical>> {
+    geometries.resize(count, area);
+    geometries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, w: i32, h: i32) -> Rectangle<i32, Logical> {
+        Rectangle::from_loc_and_size((x, y), (w, h))
+    }
+
+    #[test]
+    fn master_stack() {
+        let mut layout = MasterStack {
+            master_ratio: 0.5,
+            master_count: 1,
+            gap: 0,
+        };
+        let area = rect(0, 0, 100, 90);
+        assert_eq!(
+            layout.arrange(area, &[LayoutHints::default()]),
+            vec![rect(0, 0, 100, 90)]
+        );
+        assert_eq!(
+            layout.arrange(area, &[LayoutHints::default(); 4]),
+            vec![
+                rect(0, 0, 50, 90),
+                rect(50, 0, 50, 30),
+                rect(50, 30, 50, 30),
+                rect(50, 60, 50, 30)
+            ]
+        );
+    }
+
+    #[test]
+    fn columns_with_gap() {
+        let mut layout = Columns { gap: 10 };
+        assert_eq!(
+            layout.arrange(rect(0, 0, 111, 50), &[LayoutHints::default(); 3]),
+            vec![rect(10, 10, 24, 30), rect(44, 10, 24, 30), rect(78, 10, 23, 30)]
+        );
+    }
+
+    #[test]
+    fn bsp() {
+        let mut layout = BspLayout::default();
+        assert_eq!(
+            layout.arrange(rect(0, 0, 200, 100), &[LayoutHints::default(); 3]),
+            vec![
+                rect(0, 0, 100, 100),
+                rect(100, 0, 100, 50),
+                rect(100, 50, 100, 50)
+            ]
+        );
+    }
+
+    #[test]
+    fn fit_hints() {
+        let hints = LayoutHints {
+            min_size: (0, 80).into(),
+            max_size: (50, 0).into(),
+        };
+        assert_eq!(hints.fit(rect(0, 0, 100, 60)), rect(25, 0, 50, 80));
+    }
+
+    #[test]
+    fn missing_geometries_are_placed() {
+        let area = rect(0, 0, 100, 100);
+        assert_eq!(
+            complete_geometries(vec![rect(0, 0, 50, 100)], 3, area),
+            vec![rect(0, 0, 50, 100), area, area]
+        );
+        assert_eq!(
+            complete_geometries(vec![rect(0, 0, 50, 100), rect(50, 0, 50, 100)], 1, area),
+            vec![rect(0, 0, 50, 100)]
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{
+    desktop::layer_map_for_output,
     input::{Seat, SeatHandler},
-    utils::{DeadResource, IsAlive, Logical, Point, Serial},
+    output::Output,
+    utils::{DeadResource, IsAlive, Logical, Point, Rectangle, Serial},
     wayland::{
         compositor::{get_role, with_states},
         seat::WaylandFocus,
@@ -189,6 +191,47 @@ impl PopupManager {
         })
     }
 
+    /// Computes the area a popup should be constrained to
+    /// (see [`PositionerState::get_unconstrained_geometry`](crate::wayland::shell::xdg::PositionerState::get_unconstrained_geometry)).
+    ///
+    /// The area is the part of the `output` not occupied by exclusive zones of layer surfaces
+    /// (see [`LayerMap::non_exclusive_zone`](crate::desktop::LayerMap::non_exclusive_zone)), translated
+    /// into the coordinate space of the popups parent.
+    ///
+    /// `output_geometry` is the global geometry of the output and `root_geometry_location` the global
+    /// location of the window geometry of the popups root surface (see [`find_popup_root_surface`]).
+    ///
+    /// This can be called before the popup is mapped, e.g. while handling a new popup or a reposition request.
+    pub fn popup_target_area(
+        popup: &PopupKind,
+        root_geometry_location: Point<i32, Logical>,
+        output: &Output,
+        output_geometry: Rectangle<i32, Logical>,
+    ) -> Result<Rectangle<i32, Logical>, DeadResource> {
+        // popups are positioned relative to the window geometry of their parent,
+        // which for nested popups is offset by the positions of all parent popups
+        let mut parent_offset = Point::<i32, Logical>::default();
+        let mut parent = popup.parent().ok_or(DeadResource)?;
+        while get_role(&parent) == Some(XDG_POPUP_ROLE) {
+            let (location, next) = with_states(&parent, |states| {
+                let data = states
+                    .data_map
+                    .get::<XdgPopupSurfaceData>()
+                    .unwrap()
+                    .lock()
+                    .unwrap();
+                (data.current.geometry.loc, data.parent.clone())
+            });
+            parent_offset += location;
+            parent = next.ok_or(DeadResource)?;
+        }
+
+        let mut target = layer_map_for_output(output).non_exclusive_zone();
+        target.loc += output_geometry.loc;
+        target.loc -= root_geometry_location + parent_offset;
+        Ok(target)
+    }
+
     pub(crate) fn dismiss_popup(surface: &WlSurface, popup: &PopupKind) -> Result<(), DeadResource> {
         if !surface.alive() {
             return Err(DeadResource);
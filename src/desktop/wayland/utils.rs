@@ -4,9 +4,9 @@ use crate::{
     backend::renderer::utils::RendererSurfaceState,
     desktop::WindowSurfaceType,
     utils::{Logical, Point, Rectangle},
-    wayland::compositor::{with_surface_tree_downward, SurfaceAttributes, TraversalAction},
+    wayland::compositor::{with_states, with_surface_tree_downward, SurfaceAttributes, TraversalAction},
 };
-use std::cell::RefCell;
+use std::{cell::RefCell, sync::Mutex, time::Duration};
 use wayland_server::protocol::wl_surface;
 
 impl RendererSurfaceState {
@@ -34,6 +34,8 @@ impl RendererSurfaceState {
             return true;
         }
 
+        // clients unaware of output scales set their input region in a larger coordinate space
+        let point = point.upscale(self.client_scale.max(1) as f64);
         attrs
             .input_region
             .as_ref()
@@ -154,3 +156,86 @@ pub fn send_frames_surface_tree(surface: &wl_surface::WlSurface, time: u32) {
         |_, _, &()| true,
     );
 }
+
+/// Time of the last frame callbacks sent by [`send_frames_surface_tree_throttled`]
+#[derive(Debug, Default)]
+struct FrameThrottlingState(Mutex<Option<u32>>);
+
+/// Checks if at least `throttle` passed since the last throttled frame callbacks of `surface`
+/// and records `time` as the time of the last frame callbacks if so.
+pub(crate) fn frame_throttle_elapsed(surface: &wl_surface::WlSurface, time: u32, throttle: Duration) -> bool {
+    with_states(surface, |states| {
+        states
+            .data_map
+            .insert_if_missing_threadsafe(FrameThrottlingState::default);
+        let mut last = states
+            .data_map
+            .get::<FrameThrottlingState>()
+            .unwrap()
+            .0
+            .lock()
+            .unwrap();
+        match *last {
+            Some(last_time) if (time.wrapping_sub(last_time) as u128) < throttle.as_millis() => false,
+            _ => {
+                *last = Some(time);
+                true
+            }
+        }
+    })
+}
+
+/// Sends frame callbacks for a surface and its subsurfaces with the given `time`,
+/// but at most once every `throttle`.
+///
+/// This is useful for surfaces that are currently not visible on any output, which should not be
+/// able to render at full speed, but also should not be stalled entirely.
+///
+/// Returns `true` if the frame callbacks were sent.
+pub fn send_frames_surface_tree_throttled(
+    surface: &wl_surface::WlSurface,
+    time: u32,
+    throttle: Duration,
+) -> bool {
+    if !frame_throttle_elapsed(surface, time, throttle) {
+        return false;
+    }
+
+    send_frames_surface_tree(surface, time);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backend::renderer::utils::SurfaceView,
+        wayland::compositor::{RectangleKind, RegionAttributes},
+    };
+
+    #[test]
+    fn input_region_of_scaled_client() {
+        let mut state = RendererSurfaceState::default();
+        state.surface_view = Some(SurfaceView {
+            src: Rectangle::from_loc_and_size((0.0, 0.0), (50.0, 50.0)),
+            dst: (50, 50).into(),
+            offset: (0, 0).into(),
+        });
+        state.client_scale = 2;
+        let attrs = SurfaceAttributes {
+            input_region: Some(RegionAttributes {
+                rects: vec![(RectangleKind::Add, Rectangle::from_loc_and_size((0, 0), (40, 40)))],
+            }),
+            ..Default::default()
+        };
+
+        // the region covers the top left 20x20 logical pixels
+        assert!(state.contains_point(&attrs, (19.0, 19.0)));
+        assert!(!state.contains_point(&attrs, (25.0, 10.0)));
+        // outside of the surface
+        assert!(!state.contains_point(&attrs, (60.0, 10.0)));
+
+        state.client_scale = 1;
+        assert!(state.contains_point(&attrs, (25.0, 10.0)));
+    }
+}
@@ -13,7 +13,7 @@ use crate::{
         pointer::{AxisFrame, ButtonEvent, MotionEvent, PointerTarget},
         Seat, SeatHandler,
     },
-    utils::{user_data::UserDataMap, IsAlive, Logical, Physical, Point, Rectangle, Scale, Serial},
+    utils::{user_data::UserDataMap, IsAlive, Logical, Physical, Point, Rectangle, Scale, Serial, Size},
     wayland::{
         compositor::with_states,
         seat::WaylandFocus,
@@ -21,11 +21,13 @@ use crate::{
     },
 };
 use std::{
+    fmt,
     hash::{Hash, Hasher},
     sync::{
         atomic::{AtomicU8, Ordering},
         Arc, Mutex,
     },
+    time::Duration,
 };
 use wayland_protocols::xdg::shell::server::xdg_toplevel;
 use wayland_server::{backend::ObjectId, protocol::wl_surface, Resource};
@@ -42,12 +44,27 @@ pub enum Kind {
     X11(X11Surface),
 }
 
+/// Callback configuring the size of an X11 window, in X11 coordinates
+#[cfg(feature = "xwayland")]
+pub type X11ConfigureFn = dyn Fn(Size<i32, Physical>) + Send + Sync;
+
 /// Xwayland surface
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 #[cfg(feature = "xwayland")]
 pub struct X11Surface {
     /// underlying wl_surface
     pub surface: wl_surface::WlSurface,
+    configure: Option<Arc<X11ConfigureFn>>,
+}
+
+#[cfg(feature = "xwayland")]
+impl fmt::Debug for X11Surface {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("X11Surface")
+            .field("surface", &self.surface)
+            .field("configure", &self.configure.is_some())
+            .finish()
+    }
 }
 
 #[cfg(feature = "xwayland")]
@@ -66,10 +83,43 @@ impl IsAlive for X11Surface {
 
 #[cfg(feature = "xwayland")]
 impl X11Surface {
+    /// Creates a new Xwayland surface
+    ///
+    /// `configure` is called to resize the X11 window, e.g. by an interactive resize, with the new
+    /// size in X11 coordinates. Without it, the window cannot be resized by the compositor.
+    pub fn new(surface: wl_surface::WlSurface, configure: Option<Arc<X11ConfigureFn>>) -> X11Surface {
+        X11Surface { surface, configure }
+    }
+
+    /// Configures the X11 window with a new size
+    ///
+    /// The size is converted into X11 coordinates using the [scale](X11Surface::scale) of the surface.
+    pub fn configure(&self, size: Size<i32, Logical>) {
+        if let Some(configure) = self.configure.as_ref() {
+            let scale = self.scale() as i32;
+            configure((size.w * scale, size.h * scale).into());
+        }
+    }
+
     /// Returns the underlying [`WlSurface`](wl_surface::WlSurface), if still any.
     pub fn wl_surface(&self) -> &wl_surface::WlSurface {
         &self.surface
     }
+
+    /// Returns the scale between the X11 coordinate space and logical coordinates
+    pub fn scale(&self) -> u32 {
+        crate::wayland::compositor::get_client_scale(&self.surface)
+    }
+
+    /// Sets the scale between the X11 coordinate space and logical coordinates
+    ///
+    /// Use this when XWayland runs at a higher scale than 1, e.g. at the highest output scale.
+    /// Buffers of this surface are then rendered with this scale and surface-local input
+    /// coordinates are sent scaled accordingly, see
+    /// [`set_client_scale`](crate::wayland::compositor::set_client_scale).
+    pub fn set_scale(&self, scale: u32) {
+        crate::wayland::compositor::set_client_scale(&self.surface, scale)
+    }
 }
 
 impl Kind {
@@ -229,6 +279,17 @@ impl Window {
         }
     }
 
+    /// Sends the frame callback to all the subsurfaces in this
+    /// window that requested it, but at most once every `throttle`
+    ///
+    /// Useful for windows not visible on any output,
+    /// see [`send_frames_surface_tree_throttled`].
+    pub fn send_frame_throttled(&self, time: u32, throttle: Duration) {
+        if frame_throttle_elapsed(self.0.toplevel.wl_surface(), time, throttle) {
+            self.send_frame(time);
+        }
+    }
+
     /// Updates internal values
     ///
     /// Needs to be called whenever the toplevel surface or any unsynchronized subsurfaces of this window are updated
@@ -0,0 +1,478 @@
+//! Workspaces on top of [`Space`]
+//!
+//! A [`WorkspaceManager`] holds multiple [`Space`]s, called workspaces, and tracks which one of them
+//! is shown on every output. Switching the active workspace of an output unmaps the output from the
+//! previously active space and maps it into the new one at the same location, so elements receive
+//! correct output enter and leave events.
+//!
+//! Layer surfaces are attached to outputs instead of spaces
+//! (see [`layer_map_for_output`](crate::desktop::layer_map_for_output)) and therefore stay visible
+//! on every workspace of an output.
+//!
+//! To expose the workspaces to clients like panels, see the `ext-workspace` protocol implementation
+//! in `wayland::workspace`, which needs the `wayland_ext_workspace` feature.
+//!
+//! ```no_run
+//! # use smithay::{desktop::{Window, workspace::WorkspaceManager}, output::Output};
+//! # let output: Output = unimplemented!();
+//! # let window: Window = unimplemented!();
+//! let mut workspaces = WorkspaceManager::<Window>::new(None);
+//! let first = workspaces.add_workspace("1");
+//! let second = workspaces.add_workspace("2");
+//!
+//! workspaces.map_output(&output, (0, 0), first).unwrap();
+//! workspaces.workspace_mut(first).unwrap().space_mut().map_element(window.clone(), (0, 0), true);
+//!
+//! // move the window over and show the second workspace
+//! workspaces.move_element(&window, second, None).unwrap();
+//! workspaces.activate_workspace(&output, second).unwrap();
+//! ```
+
+use std::fmt;
+
+use crate::{
+    desktop::space::{Space, SpaceElement},
+    output::Output,
+    utils::{Logical, Point},
+};
+
+/// Identifier of a workspace of a [`WorkspaceManager`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WorkspaceId(usize);
+
+/// A named [`Space`] managed by a [`WorkspaceManager`]
+pub struct Workspace<E: SpaceElement> {
+    id: WorkspaceId,
+    name: String,
+    space: Space<E>,
+}
+
+impl<E: SpaceElement + fmt::Debug> fmt::Debug for Workspace<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Workspace")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("space", &self.space)
+            .finish()
+    }
+}
+
+impl<E: SpaceElement> Workspace<E> {
+    /// Id of this workspace
+    pub fn id(&self) -> WorkspaceId {
+        self.id
+    }
+
+    /// Name of this workspace
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Renames this workspace
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = name.into();
+    }
+
+    /// Access the space of this workspace
+    pub fn space(&self) -> &Space<E> {
+        &self.space
+    }
+
+    /// Mutably access the space of this workspace
+    ///
+    /// Use the methods of the [`WorkspaceManager`] to map or unmap outputs,
+    /// the space should only be used to manage its elements.
+    pub fn space_mut(&mut self) -> &mut Space<E> {
+        &mut self.space
+    }
+}
+
+#[derive(Debug)]
+struct WorkspaceOutput {
+    output: Output,
+    location: Point<i32, Logical>,
+    active: WorkspaceId,
+}
+
+/// Errors of the [`WorkspaceManager`]
+#[derive(Debug, thiserror::Error)]
+pub enum WorkspaceError {
+    /// The given workspace does not exist
+    #[error("Unknown workspace")]
+    UnknownWorkspace,
+    /// The given output was not mapped
+    #[error("Output was not mapped")]
+    UnmappedOutput,
+    /// The given element was not mapped on any workspace
+    #[error("Element was not mapped on any workspace")]
+    UnmappedElement,
+    /// A workspace cannot be removed in favor of itself
+    #[error("Fallback workspace is the removed workspace")]
+    InvalidFallback,
+}
+
+/// Manages multiple workspaces and the workspace shown on every output
+pub struct WorkspaceManager<E: SpaceElement> {
+    workspaces: Vec<Workspace<E>>,
+    outputs: Vec<WorkspaceOutput>,
+    next_id: usize,
+    logger: ::slog::Logger,
+}
+
+impl<E: SpaceElement + fmt::Debug> fmt::Debug for WorkspaceManager<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorkspaceManager")
+            .field("workspaces", &self.workspaces)
+            .field("outputs", &self.outputs)
+            .finish()
+    }
+}
+
+impl<E: SpaceElement + PartialEq + Clone> WorkspaceManager<E> {
+    /// Creates a new workspace manager without any workspaces
+    pub fn new<L>(log: L) -> Self
+    where
+        L: Into<Option<slog::Logger>>,
+    {
+        WorkspaceManager {
+            workspaces: Vec::new(),
+            outputs: Vec::new(),
+            next_id: 0,
+            logger: crate::slog_or_fallback(log),
+        }
+    }
+
+    /// Adds a new empty workspace
+    pub fn add_workspace(&mut self, name: impl Into<String>) -> WorkspaceId {
+        let id = WorkspaceId(self.next_id);
+        self.next_id += 1;
+        self.workspaces.push(Workspace {
+            id,
+            name: name.into(),
+            space: Space::new(self.logger.clone()),
+        });
+        id
+    }
+
+    /// Removes a workspace
+    ///
+    /// All elements are moved to the `fallback` workspace keeping their location
+    /// and every output showing the removed workspace switches to the `fallback`.
+    pub fn remove_workspace(&mut self, id: WorkspaceId, fallback: WorkspaceId) -> Result<(), WorkspaceError> {
+        if id == fallback {
+            return Err(WorkspaceError::InvalidFallback);
+        }
+        let pos = self.position(id)?;
+        self.position(fallback)?;
+
+        let outputs = self
+            .outputs
+            .iter()
+            .filter(|o| o.active == id)
+            .map(|o| o.output.clone())
+            .collect::<Vec<_>>();
+        for output in outputs {
+            self.activate_workspace(&output, fallback)?;
+        }
+
+        let mut removed = self.workspaces.remove(pos);
+        let fallback_pos = self.position(fallback)?;
+        let fallback = &mut self.workspaces[fallback_pos].space;
+        let elements = removed.space.elements().cloned().collect::<Vec<_>>();
+        for element in elements {
+            removed.space.move_element_to(&element, fallback, None, false);
+        }
+        fallback.refresh();
+        Ok(())
+    }
+
+    /// Iterates over all workspaces
+    pub fn workspaces(&self) -> impl Iterator<Item = &Workspace<E>> {
+        self.workspaces.iter()
+    }
+
+    /// Returns the workspace with the given id
+    pub fn workspace(&self, id: WorkspaceId) -> Option<&Workspace<E>> {
+        self.workspaces.iter().find(|w| w.id == id)
+    }
+
+    /// Mutably returns the workspace with the given id
+    pub fn workspace_mut(&mut self, id: WorkspaceId) -> Option<&mut Workspace<E>> {
+        self.workspaces.iter_mut().find(|w| w.id == id)
+    }
+
+    /// Returns the workspace an element is mapped on
+    pub fn workspace_for_element(&self, element: &E) -> Option<WorkspaceId> {
+        self.workspaces
+            .iter()
+            .find(|w| w.space.elements().any(|e| e == element))
+            .map(|w| w.id)
+    }
+
+    /// Maps an output at the given location and shows the given workspace on it
+    ///
+    /// Can be safely called on an already mapped output to update its location.
+    pub fn map_output<P: Into<Point<i32, Logical>>>(
+        &mut self,
+        output: &Output,
+        location: P,
+        workspace: WorkspaceId,
+    ) -> Result<(), WorkspaceError> {
+        let location = location.into();
+        self.position(workspace)?;
+        match self.outputs.iter_mut().find(|o| &o.output == output) {
+            Some(state) => {
+                state.location = location;
+                let active = state.active;
+                self.workspace_mut(active)
+                    .unwrap()
+                    .space
+                    .map_output(output, location);
+                self.activate_workspace(output, workspace)
+            }
+            None => {
+                self.outputs.push(WorkspaceOutput {
+                    output: output.clone(),
+                    location,
+                    active: workspace,
+                });
+                let space = &mut self.workspace_mut(workspace).unwrap().space;
+                space.map_output(output, location);
+                space.refresh();
+                Ok(())
+            }
+        }
+    }
+
+    /// Unmaps an output from the workspace it is showing
+    ///
+    /// Does nothing if the output was not previously mapped.
+    pub fn unmap_output(&mut self, output: &Output) {
+        if let Some(pos) = self.outputs.iter().position(|o| &o.output == output) {
+            let state = self.outputs.remove(pos);
+            if let Some(workspace) = self.workspace_mut(state.active) {
+                workspace.space.unmap_output(output);
+                workspace.space.refresh();
+            }
+        }
+    }
+
+    /// Iterates over all mapped outputs
+    pub fn outputs(&self) -> impl Iterator<Item = &Output> {
+        self.outputs.iter().map(|o| &o.output)
+    }
+
+    /// Returns the location of a mapped output
+    pub fn output_location(&self, output: &Output) -> Option<Point<i32, Logical>> {
+        self.outputs
+            .iter()
+            .find(|o| &o.output == output)
+            .map(|o| o.location)
+    }
+
+    /// Returns the workspace currently shown on an output
+    pub fn active_workspace(&self, output: &Output) -> Option<WorkspaceId> {
+        self.outputs
+            .iter()
+            .find(|o| &o.output == output)
+            .map(|o| o.active)
+    }
+
+    /// Returns all outputs currently showing the given workspace
+    pub fn outputs_for_workspace(&self, id: WorkspaceId) -> impl Iterator<Item = &Output> {
+        self.outputs
+            .iter()
+            .filter(move |o| o.active == id)
+            .map(|o| &o.output)
+    }
+
+    /// Shows a workspace on an output
+    ///
+    /// The output is moved from the previously active space into the space of the given workspace
+    /// at the same location. Both spaces are refreshed, which sends the necessary output enter
+    /// and leave events. A workspace may be active on multiple outputs at once.
+    pub fn activate_workspace(&mut self, output: &Output, id: WorkspaceId) -> Result<(), WorkspaceError> {
+        self.position(id)?;
+        let state = self
+            .outputs
+            .iter_mut()
+            .find(|o| &o.output == output)
+            .ok_or(WorkspaceError::UnmappedOutput)?;
+        let previous = std::mem::replace(&mut state.active, id);
+        let location = state.location;
+        if previous == id {
+            return Ok(());
+        }
+
+        if let Some(workspace) = self.workspace_mut(previous) {
+            workspace.space.unmap_output(output);
+            workspace.space.refresh();
+        }
+        let space = &mut self.workspace_mut(id).unwrap().space;
+        space.map_output(output, location);
+        space.refresh();
+        Ok(())
+    }
+
+    /// Moves an element to another workspace
+    ///
+    /// If no location is provided the element keeps its current location.
+    /// The element is put on top of the stack of the target workspace.
+    pub fn move_element(
+        &mut self,
+        element: &E,
+        target: WorkspaceId,
+        location: Option<Point<i32, Logical>>,
+    ) -> Result<(), WorkspaceError> {
+        let target_pos = self.position(target)?;
+        let source_pos = self
+            .workspace_for_element(element)
+            .and_then(|id| self.position(id).ok())
+            .ok_or(WorkspaceError::UnmappedElement)?;
+
+        if source_pos == target_pos {
+            if let Some(location) = location {
+                self.workspaces[target_pos]
+                    .space
+                    .map_element(element.clone(), location, false);
+            }
+            return Ok(());
+        }
+
+        let (source, target) = if source_pos < target_pos {
+            let (left, right) = self.workspaces.split_at_mut(target_pos);
+            (&mut left[source_pos], &mut right[0])
+        } else {
+            let (left, right) = self.workspaces.split_at_mut(source_pos);
+            (&mut right[0], &mut left[target_pos])
+        };
+        source
+            .space
+            .move_element_to(element, &mut target.space, location, false);
+        target.space.refresh();
+        Ok(())
+    }
+
+    /// Refreshes the spaces of all workspaces
+    ///
+    /// Needs to be called periodically, at best before every wayland socket flush.
+    /// See [`Space::refresh`].
+    pub fn refresh(&mut self) {
+        for workspace in &mut self.workspaces {
+            workspace.space.refresh();
+        }
+    }
+
+    fn position(&self, id: WorkspaceId) -> Result<usize, WorkspaceError> {
+        self.workspaces
+            .iter()
+            .position(|w| w.id == id)
+            .ok_or(WorkspaceError::UnknownWorkspace)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::{
+        output::{Mode, PhysicalProperties, Subpixel},
+        utils::{IsAlive, Rectangle},
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestElement {
+        id: usize,
+        outputs: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl PartialEq for TestElement {
+        fn eq(&self, other: &Self) -> bool {
+            self.id == other.id
+        }
+    }
+
+    impl IsAlive for TestElement {
+        fn alive(&self) -> bool {
+            true
+        }
+    }
+
+    impl SpaceElement for TestElement {
+        fn bbox(&self) -> Rectangle<i32, Logical> {
+            Rectangle::from_loc_and_size((0, 0), (100, 100))
+        }
+        fn is_in_input_region(&self, _point: &Point<f64, Logical>) -> bool {
+            true
+        }
+        fn set_activate(&self, _activated: bool) {}
+        fn output_enter(&self, output: &Output, _overlap: Rectangle<i32, Logical>) {
+            let mut outputs = self.outputs.borrow_mut();
+            if !outputs.contains(&output.name()) {
+                outputs.push(output.name());
+            }
+        }
+        fn output_leave(&self, output: &Output) {
+            self.outputs.borrow_mut().retain(|o| o != &output.name());
+        }
+    }
+
+    fn output() -> Output {
+        let output = Output::new(
+            "test".into(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: "make".into(),
+                model: "model".into(),
+                hdr: None,
+            },
+            None,
+        );
+        output.change_current_state(
+            Some(Mode {
+                size: (1920, 1080).into(),
+                refresh: 60_000,
+            }),
+            None,
+            None,
+            None,
+        );
+        output
+    }
+
+    #[test]
+    fn switch_workspaces() {
+        let output = output();
+        let mut manager = WorkspaceManager::new(None);
+        let first = manager.add_workspace("1");
+        let second = manager.add_workspace("2");
+        manager.map_output(&output, (0, 0), first).unwrap();
+
+        let element = TestElement {
+            id: 0,
+            outputs: Rc::default(),
+        };
+        manager
+            .workspace_mut(first)
+            .unwrap()
+            .space_mut()
+            .map_element(element.clone(), (0, 0), false);
+        manager.refresh();
+        assert_eq!(*element.outputs.borrow(), vec![String::from("test")]);
+
+        manager.move_element(&element, second, None).unwrap();
+        assert_eq!(manager.workspace_for_element(&element), Some(second));
+        assert!(element.outputs.borrow().is_empty());
+
+        manager.activate_workspace(&output, second).unwrap();
+        assert_eq!(manager.active_workspace(&output), Some(second));
+        assert_eq!(*element.outputs.borrow(), vec![String::from("test")]);
+
+        manager.remove_workspace(second, first).unwrap();
+        assert_eq!(manager.active_workspace(&output), Some(first));
+        assert_eq!(manager.workspace_for_element(&element), Some(first));
+        assert_eq!(*element.outputs.borrow(), vec![String::from("test")]);
+    }
+}
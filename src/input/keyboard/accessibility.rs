@@ -0,0 +1,313 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    time::Duration,
+};
+
+use xkbcommon::xkb;
+
+use crate::backend::input::KeyState;
+
+/// State of the AccessX-style accessibility features of a keyboard
+#[derive(Default)]
+pub(super) struct AccessX {
+    pub(super) sticky_keys: Option<StickyKeys>,
+    pub(super) bounce_delay: Option<Duration>,
+    pub(super) slow_keys: Option<SlowKeys>,
+    // time of the last release of every key, for bounce keys
+    last_release: HashMap<u32, u32>,
+    // keys whose press was ignored, their release has to be ignored as well
+    ignored: HashSet<u32>,
+}
+
+impl fmt::Debug for AccessX {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessX")
+            .field("sticky_keys", &self.sticky_keys)
+            .field("bounce_delay", &self.bounce_delay)
+            .field("slow_keys", &self.slow_keys.as_ref().map(|slow| slow.delay))
+            .finish()
+    }
+}
+
+impl AccessX {
+    // returns false if the key event should be dropped
+    pub(super) fn filter(&mut self, keycode: u32, state: KeyState, time: u32) -> bool {
+        match state {
+            KeyState::Pressed => {
+                if let (Some(delay), Some(released)) = (self.bounce_delay, self.last_release.get(&keycode)) {
+                    if (time.wrapping_sub(*released) as u128) < delay.as_millis() {
+                        self.ignored.insert(keycode);
+                        return false;
+                    }
+                }
+                if let Some(slow_keys) = self.slow_keys.as_mut() {
+                    if !slow_keys.accepted.remove(&keycode) {
+                        slow_keys.generation = slow_keys.generation.wrapping_add(1);
+                        slow_keys.pending.insert(keycode, slow_keys.generation);
+                        (slow_keys.schedule)(keycode, slow_keys.generation, time);
+                        self.ignored.insert(keycode);
+                        return false;
+                    }
+                }
+                true
+            }
+            KeyState::Released => {
+                if let Some(slow_keys) = self.slow_keys.as_mut() {
+                    slow_keys.pending.remove(&keycode);
+                    slow_keys.accepted.remove(&keycode);
+                }
+                if self.bounce_delay.is_some() {
+                    self.last_release.insert(keycode, time);
+                }
+                !self.ignored.remove(&keycode)
+            }
+        }
+    }
+
+    // called once a slow key was held down long enough, returns true if it is still held
+    pub(super) fn slow_key_elapsed(&mut self, keycode: u32, generation: u64) -> bool {
+        let slow_keys = match self.slow_keys.as_mut() {
+            Some(slow_keys) => slow_keys,
+            None => return false,
+        };
+        if slow_keys.pending.get(&keycode) != Some(&generation) {
+            return false;
+        }
+        slow_keys.pending.remove(&keycode);
+        slow_keys.accepted.insert(keycode);
+        // the press is replayed, which then is no longer ignored
+        self.ignored.remove(&keycode);
+        true
+    }
+
+    pub(super) fn set_bounce_delay(&mut self, delay: Option<Duration>) {
+        self.bounce_delay = delay;
+        if delay.is_none() {
+            self.last_release.clear();
+        }
+    }
+}
+
+pub(super) struct SlowKeys {
+    pub(super) delay: Duration,
+    // starts a timer for the given keycode, generation and time of the press,
+    // has to be `Send` as it is owned by the keyboard
+    pub(super) schedule: Box<dyn Fn(u32, u64, u32) + Send>,
+    generation: u64,
+    pending: HashMap<u32, u64>,
+    accepted: HashSet<u32>,
+}
+
+impl SlowKeys {
+    pub(super) fn new(delay: Duration, schedule: Box<dyn Fn(u32, u64, u32) + Send>) -> SlowKeys {
+        SlowKeys {
+            delay,
+            schedule,
+            generation: 0,
+            pending: HashMap::new(),
+            accepted: HashSet::new(),
+        }
+    }
+}
+
+/// Modifiers latched and locked by sticky keys
+#[derive(Debug, Default)]
+pub(super) struct StickyKeys {
+    latched: xkb::ModMask,
+    locked: xkb::ModMask,
+    // modifier key pressed without any other key so far, with the modifiers it sets
+    pending: Option<(u32, xkb::ModMask)>,
+}
+
+impl StickyKeys {
+    // returns true if the modifiers changed
+    pub(super) fn key_input(
+        &mut self,
+        xkb_state: &mut xkb::State,
+        keycode: u32,
+        direction: xkb::KeyDirection,
+    ) -> bool {
+        let depressed = xkb_state.serialize_mods(xkb::STATE_MODS_DEPRESSED);
+        let components = xkb_state.update_key(keycode, direction);
+
+        match direction {
+            xkb::KeyDirection::Down => {
+                let set = xkb_state.serialize_mods(xkb::STATE_MODS_DEPRESSED) & !depressed;
+                self.pending = (set != 0).then(|| (keycode, set));
+                components != 0
+            }
+            xkb::KeyDirection::Up => match self.pending {
+                // a modifier was pressed and released on its own:
+                // latch it, lock it if it already was latched and unlock it if it was locked
+                Some((pending, mods)) if pending == keycode => {
+                    self.pending = None;
+                    let previous = (self.latched, self.locked);
+                    if self.locked & mods != 0 {
+                        self.locked &= !mods;
+                    } else if self.latched & mods != 0 {
+                        self.latched &= !mods;
+                        self.locked |= mods;
+                    } else {
+                        self.latched |= mods;
+                    }
+                    self.apply(xkb_state, previous);
+                    true
+                }
+                // a regular key consumes the latched modifiers
+                _ if self.latched != 0 && components == 0 => {
+                    let previous = (self.latched, self.locked);
+                    self.latched = 0;
+                    self.apply(xkb_state, previous);
+                    true
+                }
+                _ => components != 0,
+            },
+        }
+    }
+
+    // replace the previously latched and locked modifiers, keeping those of the keymap itself
+    fn apply(&self, xkb_state: &mut xkb::State, (latched, locked): (xkb::ModMask, xkb::ModMask)) {
+        let depressed = xkb_state.serialize_mods(xkb::STATE_MODS_DEPRESSED);
+        let latched = xkb_state.serialize_mods(xkb::STATE_MODS_LATCHED) & !latched | self.latched;
+        let locked = xkb_state.serialize_mods(xkb::STATE_MODS_LOCKED) & !locked | self.locked;
+        let layout = xkb_state.serialize_layout(xkb::STATE_LAYOUT_LOCKED);
+        xkb_state.update_mask(depressed, latched, locked, 0, 0, layout);
+    }
+
+    // drop all latched and locked modifiers, returns true if there were any
+    pub(super) fn clear(&mut self, xkb_state: &mut xkb::State) -> bool {
+        let previous = (self.latched, self.locked);
+        let changed = self.latched != 0 || self.locked != 0;
+        self.latched = 0;
+        self.locked = 0;
+        self.pending = None;
+        if changed {
+            self.apply(xkb_state, previous);
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::keyboard::{compile_keymap, XkbConfig};
+    use std::sync::{Arc, Mutex};
+
+    // xkb keycodes of the default keymap
+    const SHIFT: u32 = 50;
+    const A: u32 = 38;
+
+    fn xkb_state() -> xkb::State {
+        xkb::State::new(&compile_keymap(XkbConfig::default()).unwrap())
+    }
+
+    fn shift_active(state: &xkb::State) -> bool {
+        state.mod_name_is_active(&xkb::MOD_NAME_SHIFT, xkb::STATE_MODS_EFFECTIVE)
+    }
+
+    fn tap(sticky_keys: &mut StickyKeys, state: &mut xkb::State, keycode: u32) -> bool {
+        let pressed = sticky_keys.key_input(state, keycode, xkb::KeyDirection::Down);
+        let released = sticky_keys.key_input(state, keycode, xkb::KeyDirection::Up);
+        pressed || released
+    }
+
+    #[test]
+    fn bounce_keys_drop_press_and_release() {
+        let mut accessx = AccessX::default();
+        accessx.set_bounce_delay(Some(Duration::from_millis(100)));
+
+        assert!(accessx.filter(30, KeyState::Pressed, 0));
+        assert!(accessx.filter(30, KeyState::Released, 10));
+        // bounce of the same key
+        assert!(!accessx.filter(30, KeyState::Pressed, 50));
+        assert!(!accessx.filter(30, KeyState::Released, 60));
+        // other keys are not affected
+        assert!(accessx.filter(31, KeyState::Pressed, 70));
+        assert!(accessx.filter(31, KeyState::Released, 80));
+        assert!(accessx.filter(30, KeyState::Pressed, 200));
+    }
+
+    #[test]
+    fn sticky_keys_latch_until_next_key() {
+        let mut state = xkb_state();
+        let mut sticky_keys = StickyKeys::default();
+
+        assert!(tap(&mut sticky_keys, &mut state, SHIFT));
+        assert!(shift_active(&state));
+        assert_ne!(state.serialize_mods(xkb::STATE_MODS_LATCHED), 0);
+
+        // the latched modifier applies to the next key and is consumed once it is released
+        assert!(!sticky_keys.key_input(&mut state, A, xkb::KeyDirection::Down));
+        assert!(shift_active(&state));
+        assert!(sticky_keys.key_input(&mut state, A, xkb::KeyDirection::Up));
+        assert!(!shift_active(&state));
+    }
+
+    #[test]
+    fn sticky_keys_lock_and_unlock() {
+        let mut state = xkb_state();
+        let mut sticky_keys = StickyKeys::default();
+
+        tap(&mut sticky_keys, &mut state, SHIFT);
+        tap(&mut sticky_keys, &mut state, SHIFT);
+        assert_eq!(state.serialize_mods(xkb::STATE_MODS_LATCHED), 0);
+        assert_ne!(state.serialize_mods(xkb::STATE_MODS_LOCKED), 0);
+
+        // locked modifiers are not consumed
+        assert!(!tap(&mut sticky_keys, &mut state, A));
+        assert!(shift_active(&state));
+
+        assert!(tap(&mut sticky_keys, &mut state, SHIFT));
+        assert!(!shift_active(&state));
+    }
+
+    #[test]
+    fn sticky_keys_ignore_modifiers_used_with_other_keys() {
+        let mut state = xkb_state();
+        let mut sticky_keys = StickyKeys::default();
+
+        sticky_keys.key_input(&mut state, SHIFT, xkb::KeyDirection::Down);
+        tap(&mut sticky_keys, &mut state, A);
+        sticky_keys.key_input(&mut state, SHIFT, xkb::KeyDirection::Up);
+        assert!(!shift_active(&state));
+
+        tap(&mut sticky_keys, &mut state, SHIFT);
+        assert!(sticky_keys.clear(&mut state));
+        assert!(!shift_active(&state));
+        assert!(!sticky_keys.clear(&mut state));
+    }
+
+    #[test]
+    fn slow_keys_accept_held_keys() {
+        let scheduled = Arc::new(Mutex::new(Vec::new()));
+        let mut accessx = AccessX::default();
+        let timers = scheduled.clone();
+        accessx.slow_keys = Some(SlowKeys::new(
+            Duration::from_millis(300),
+            Box::new(move |keycode, generation, time| {
+                timers.lock().unwrap().push((keycode, generation, time));
+            }),
+        ));
+
+        // the press is held back until the timer elapsed
+        assert!(!accessx.filter(30, KeyState::Pressed, 0));
+        let (keycode, generation, time) = scheduled.lock().unwrap()[0];
+        assert_eq!((keycode, time), (30, 0));
+        assert!(accessx.slow_key_elapsed(keycode, generation));
+        // and replayed afterwards
+        assert!(accessx.filter(30, KeyState::Pressed, 300));
+        assert!(accessx.filter(30, KeyState::Released, 400));
+
+        // keys released early are ignored
+        assert!(!accessx.filter(30, KeyState::Pressed, 500));
+        assert!(!accessx.filter(30, KeyState::Released, 600));
+        let (keycode, generation, _) = scheduled.lock().unwrap()[1];
+        assert!(!accessx.slow_key_elapsed(keycode, generation));
+        // timers of earlier presses do not accept later ones
+        assert!(!accessx.filter(30, KeyState::Pressed, 700));
+        assert!(!accessx.slow_key_elapsed(keycode, generation));
+        assert_eq!(scheduled.lock().unwrap().len(), 3);
+    }
+}
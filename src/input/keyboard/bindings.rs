@@ -0,0 +1,723 @@
+//! Compositor keybindings
+//!
+//! [`KeyBindings`] implements the usual keybinding logic of a compositor on top of the filter
+//! of [`KeyboardHandle::input`](super::KeyboardHandle::input):
+//!
+//! ```no_run
+//! # use smithay::input::keyboard::{Binding, KeyBindings};
+//! #[derive(Debug, Clone)]
+//! enum Action {
+//!     Terminal,
+//!     Close,
+//!     EnterResize,
+//!     LeaveResize,
+//! }
+//!
+//! let mut bindings = KeyBindings::new();
+//! bindings.bind("default", "Super+Return", Action::Terminal).unwrap();
+//! // key sequences are separated by spaces
+//! bindings.bind("default", "Super+k Super+c", Action::Close).unwrap();
+//! bindings.bind("default", "Super+r", Action::EnterResize).unwrap();
+//! // triggered when escape is released while in the "resize" mode
+//! bindings.add("resize", Binding::parse("Escape", Action::LeaveResize).unwrap().on_release());
+//! ```
+//!
+//! The filter of [`KeyboardHandle::input`](super::KeyboardHandle::input) then only needs to call
+//! [`KeyBindings::filter`] and handle the returned action, for example by switching to another
+//! mode via [`KeyBindings::set_mode`].
+
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use thiserror::Error;
+use xkbcommon::xkb;
+
+use super::{FilterResult, Keysym, KeysymHandle, ModifiersState};
+use crate::backend::input::KeyState;
+
+/// Name of the mode [`KeyBindings`] start in
+pub const DEFAULT_MODE: &str = "default";
+
+/// Errors that can occur when parsing a key combination
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ParseBindingError {
+    /// The binding did not contain any key
+    #[error("The binding does not contain a key")]
+    Empty,
+    /// The binding contained an unknown modifier or more than one key
+    #[error("Expected a modifier, found {0:?}")]
+    UnknownModifier(String),
+    /// The key name is not a known keysym
+    #[error("Unknown key {0:?}")]
+    UnknownKey(String),
+}
+
+/// Modifiers of a [`KeyCombo`]
+///
+/// Unlike [`ModifiersState`] this does not contain the lock modifiers, which are ignored when
+/// matching bindings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct BindingModifiers {
+    /// The "control" key
+    pub ctrl: bool,
+    /// The "alt" key
+    pub alt: bool,
+    /// The "shift" key
+    pub shift: bool,
+    /// The "logo" key
+    pub logo: bool,
+}
+
+impl From<&ModifiersState> for BindingModifiers {
+    fn from(state: &ModifiersState) -> BindingModifiers {
+        BindingModifiers {
+            ctrl: state.ctrl,
+            alt: state.alt,
+            shift: state.shift,
+            logo: state.logo,
+        }
+    }
+}
+
+/// A single key pressed together with a set of modifiers
+///
+/// Can be parsed from strings like `Super+Shift+Return`, where the key is given by its keysym
+/// name and the modifiers are any of `Super`/`Logo`/`Mod4`, `Ctrl`/`Control`, `Alt`/`Mod1`
+/// and `Shift`. Modifier names are case insensitive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyCombo {
+    /// Modifiers that have to be active
+    pub modifiers: BindingModifiers,
+    /// The key that has to be pressed
+    pub keysym: Keysym,
+}
+
+impl KeyCombo {
+    /// Whether this combination matches a key press
+    ///
+    /// The modifiers have to match exactly, the keysym is compared to the keysyms of the key
+    /// with and without the current modifiers applied. This way both `Shift+1` and `Shift+exclam`
+    /// match the same key on a US layout.
+    pub fn matches(&self, modifiers: &ModifiersState, handle: &KeysymHandle<'_>) -> bool {
+        self.modifiers == BindingModifiers::from(modifiers)
+            && (handle.raw_syms().contains(&self.keysym) || handle.modified_syms().contains(&self.keysym))
+    }
+}
+
+impl FromStr for KeyCombo {
+    type Err = ParseBindingError;
+
+    fn from_str(s: &str) -> Result<KeyCombo, ParseBindingError> {
+        let mut parts = s.split('+').map(str::trim).collect::<Vec<_>>();
+        let key = parts
+            .pop()
+            .filter(|key| !key.is_empty())
+            .ok_or(ParseBindingError::Empty)?;
+
+        let mut modifiers = BindingModifiers::default();
+        for modifier in parts {
+            match modifier.to_lowercase().as_str() {
+                "super" | "logo" | "mod4" | "win" => modifiers.logo = true,
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "alt" | "mod1" => modifiers.alt = true,
+                "shift" => modifiers.shift = true,
+                _ => return Err(ParseBindingError::UnknownModifier(modifier.to_owned())),
+            }
+        }
+
+        let mut keysym = xkb::keysym_from_name(key, xkb::KEYSYM_NO_FLAGS);
+        if keysym == xkb::keysyms::KEY_NoSymbol {
+            keysym = xkb::keysym_from_name(key, xkb::KEYSYM_CASE_INSENSITIVE);
+        }
+        if keysym == xkb::keysyms::KEY_NoSymbol {
+            return Err(ParseBindingError::UnknownKey(key.to_owned()));
+        }
+
+        Ok(KeyCombo { modifiers, keysym })
+    }
+}
+
+impl fmt::Display for KeyCombo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.logo {
+            f.write_str("Super+")?;
+        }
+        if self.modifiers.ctrl {
+            f.write_str("Ctrl+")?;
+        }
+        if self.modifiers.alt {
+            f.write_str("Alt+")?;
+        }
+        if self.modifiers.shift {
+            f.write_str("Shift+")?;
+        }
+        f.write_str(&xkb::keysym_get_name(self.keysym))
+    }
+}
+
+/// When a binding is triggered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Trigger {
+    /// When the last key of the sequence is pressed
+    Press,
+    /// When the last key of the sequence is released
+    Release,
+}
+
+/// A keybinding
+#[derive(Debug, Clone)]
+pub struct Binding<T> {
+    /// Sequence of key combinations that have to be pressed after each other
+    ///
+    /// Contains a single combination for most bindings, more for key-chords like
+    /// `Ctrl+x Ctrl+c`.
+    pub sequence: Vec<KeyCombo>,
+    /// When the binding is triggered
+    pub trigger: Trigger,
+    /// Whether the binding is also active while keyboard shortcuts are inhibited
+    pub ignore_inhibitor: bool,
+    /// Value returned by [`KeyBindings::filter`] when the binding is triggered
+    pub action: T,
+}
+
+impl<T> Binding<T> {
+    /// Create a binding triggered when the last key of a sequence is pressed
+    pub fn new(sequence: Vec<KeyCombo>, action: T) -> Binding<T> {
+        Binding {
+            sequence,
+            trigger: Trigger::Press,
+            ignore_inhibitor: false,
+            action,
+        }
+    }
+
+    /// Parse a binding from a string
+    ///
+    /// The string contains the key combinations of the sequence separated by whitespace,
+    /// see [`KeyCombo`] for the syntax of a single combination.
+    pub fn parse(sequence: &str, action: T) -> Result<Binding<T>, ParseBindingError> {
+        let sequence = sequence
+            .split_whitespace()
+            .map(KeyCombo::from_str)
+            .collect::<Result<Vec<_>, _>>()?;
+        if sequence.is_empty() {
+            return Err(ParseBindingError::Empty);
+        }
+        Ok(Binding::new(sequence, action))
+    }
+
+    /// Trigger this binding on release instead of press
+    pub fn on_release(mut self) -> Self {
+        self.trigger = Trigger::Release;
+        self
+    }
+
+    /// Keep this binding active while keyboard shortcuts are inhibited
+    ///
+    /// This should be used for bindings the user must always be able to reach, like
+    /// switching virtual terminals.
+    pub fn ignore_inhibitor(mut self) -> Self {
+        self.ignore_inhibitor = true;
+        self
+    }
+}
+
+#[derive(Debug)]
+struct PendingSequence {
+    depth: usize,
+    candidates: Vec<usize>,
+}
+
+/// Set of keybindings grouped into modes
+///
+/// Only the bindings of the active mode are considered, which allows implementing submaps
+/// like a resize mode. The active mode starts as [`DEFAULT_MODE`].
+///
+/// Keys consumed by a binding are not sent to clients, including their release and the keys
+/// of incomplete key-chords.
+#[derive(Debug)]
+pub struct KeyBindings<T> {
+    modes: HashMap<String, Vec<Binding<T>>>,
+    mode: String,
+    pending: Option<PendingSequence>,
+    // keycodes of intercepted keys, with the action to trigger on release
+    intercepted: HashMap<u32, Option<T>>,
+}
+
+impl<T> Default for KeyBindings<T> {
+    fn default() -> Self {
+        KeyBindings {
+            modes: HashMap::new(),
+            mode: DEFAULT_MODE.to_owned(),
+            pending: None,
+            intercepted: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Clone> KeyBindings<T> {
+    /// Create an empty set of keybindings
+    pub fn new() -> KeyBindings<T> {
+        KeyBindings::default()
+    }
+
+    /// Add a binding to a mode
+    pub fn add(&mut self, mode: &str, binding: Binding<T>) {
+        self.pending = None;
+        self.modes.entry(mode.to_owned()).or_default().push(binding);
+    }
+
+    /// Parse a binding triggered on press and add it to a mode
+    ///
+    /// See [`Binding::parse`] for the syntax.
+    pub fn bind(&mut self, mode: &str, sequence: &str, action: T) -> Result<(), ParseBindingError> {
+        self.add(mode, Binding::parse(sequence, action)?);
+        Ok(())
+    }
+
+    /// Remove all bindings of a mode
+    pub fn clear_mode(&mut self, mode: &str) {
+        self.pending = None;
+        self.modes.remove(mode);
+    }
+
+    /// Returns the bindings of a mode
+    pub fn bindings(&self, mode: &str) -> &[Binding<T>] {
+        self.modes.get(mode).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Returns the active mode
+    pub fn mode(&self) -> &str {
+        &self.mode
+    }
+
+    /// Change the active mode
+    ///
+    /// Aborts an incomplete key-chord.
+    pub fn set_mode(&mut self, mode: &str) {
+        self.pending = None;
+        self.mode = mode.to_owned();
+    }
+
+    /// Returns true while a key-chord was started, but is not complete yet
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Process a key event
+    ///
+    /// Meant to be called from the filter passed to
+    /// [`KeyboardHandle::input`](super::KeyboardHandle::input). Returns
+    /// [`FilterResult::Intercept`] for keys consumed by a binding, which contains the action
+    /// of the binding if it was triggered by this event. If `inhibited` is true, only bindings
+    /// created with [`Binding::ignore_inhibitor`] are considered.
+    pub fn filter(
+        &mut self,
+        modifiers: &ModifiersState,
+        handle: KeysymHandle<'_>,
+        state: KeyState,
+        inhibited: bool,
+    ) -> FilterResult<Option<T>> {
+        let keycode = handle.raw_code();
+        if state == KeyState::Released {
+            return match self.intercepted.remove(&keycode) {
+                Some(action) => FilterResult::Intercept(action),
+                None => FilterResult::Forward,
+            };
+        }
+
+        if let Some(result) = self.press(modifiers, &handle, inhibited) {
+            return result;
+        }
+
+        // a key not continuing a key-chord aborts it, but may start a new one
+        if self.pending.is_some() {
+            // modifiers are pressed before the next key of a chord
+            if handle.raw_syms().iter().copied().all(is_modifier) {
+                return FilterResult::Forward;
+            }
+            self.pending = None;
+            if let Some(result) = self.press(modifiers, &handle, inhibited) {
+                return result;
+            }
+        }
+
+        FilterResult::Forward
+    }
+
+    fn press(
+        &mut self,
+        modifiers: &ModifiersState,
+        handle: &KeysymHandle<'_>,
+        inhibited: bool,
+    ) -> Option<FilterResult<Option<T>>> {
+        let bindings = self.modes.get(&self.mode)?;
+        let (depth, candidates) = match self.pending.as_ref() {
+            Some(pending) => (pending.depth, pending.candidates.clone()),
+            None => (0, (0..bindings.len()).collect()),
+        };
+
+        let matching = candidates
+            .into_iter()
+            .filter(|idx| {
+                let binding = &bindings[*idx];
+                (!inhibited || binding.ignore_inhibitor)
+                    && binding
+                        .sequence
+                        .get(depth)
+                        .map(|combo| combo.matches(modifiers, handle))
+                        .unwrap_or(false)
+            })
+            .collect::<Vec<_>>();
+        if matching.is_empty() {
+            return None;
+        }
+
+        let keycode = handle.raw_code();
+        self.pending = None;
+        let complete = matching
+            .iter()
+            .map(|idx| &bindings[*idx])
+            .find(|binding| binding.sequence.len() == depth + 1);
+        match complete {
+            Some(binding) if binding.trigger == Trigger::Press => {
+                self.intercepted.insert(keycode, None);
+                Some(FilterResult::Intercept(Some(binding.action.clone())))
+            }
+            Some(binding) => {
+                self.intercepted.insert(keycode, Some(binding.action.clone()));
+                Some(FilterResult::Intercept(None))
+            }
+            None => {
+                self.intercepted.insert(keycode, None);
+                self.pending = Some(PendingSequence {
+                    depth: depth + 1,
+                    candidates: matching,
+                });
+                Some(FilterResult::Intercept(None))
+            }
+        }
+    }
+}
+
+fn is_modifier(keysym: &Keysym) -> bool {
+    (xkb::keysyms::KEY_Shift_L..=xkb::keysyms::KEY_Hyper_R).contains(keysym)
+        || (xkb::keysyms::KEY_ISO_Lock..=xkb::keysyms::KEY_ISO_Level5_Lock).contains(keysym)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        input::{
+            keyboard::{KeyboardHandle, KeyboardTarget, XkbConfig},
+            pointer::{AxisFrame, ButtonEvent, MotionEvent, PointerTarget},
+            Seat, SeatHandler, SeatState,
+        },
+        utils::{IsAlive, Serial},
+    };
+
+    // evdev keycodes
+    const KEY_ESC: u32 = 1;
+    const KEY_Q: u32 = 16;
+    const KEY_RETURN: u32 = 28;
+    const KEY_LEFTCTRL: u32 = 29;
+    const KEY_A: u32 = 30;
+    const KEY_X: u32 = 45;
+    const KEY_C: u32 = 46;
+    const KEY_CAPSLOCK: u32 = 58;
+    const KEY_LEFTMETA: u32 = 125;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    enum Action {
+        Quit,
+        Select,
+        Terminal,
+        Switch,
+        Leave,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Target;
+
+    impl IsAlive for Target {
+        fn alive(&self) -> bool {
+            true
+        }
+    }
+
+    impl PointerTarget<State> for Target {
+        fn enter(&self, _seat: &Seat<State>, _data: &mut State, _event: &MotionEvent) {}
+        fn motion(&self, _seat: &Seat<State>, _data: &mut State, _event: &MotionEvent) {}
+        fn button(&self, _seat: &Seat<State>, _data: &mut State, _event: &ButtonEvent) {}
+        fn axis(&self, _seat: &Seat<State>, _data: &mut State, _frame: AxisFrame) {}
+        fn leave(&self, _seat: &Seat<State>, _data: &mut State, _serial: Serial, _time: u32) {}
+    }
+
+    impl KeyboardTarget<State> for Target {
+        fn enter(
+            &self,
+            _seat: &Seat<State>,
+            _data: &mut State,
+            _keys: Vec<KeysymHandle<'_>>,
+            _serial: Serial,
+        ) {
+        }
+        fn leave(&self, _seat: &Seat<State>, _data: &mut State, _serial: Serial) {}
+        fn key(
+            &self,
+            _seat: &Seat<State>,
+            _data: &mut State,
+            _key: KeysymHandle<'_>,
+            _state: KeyState,
+            _serial: Serial,
+            _time: u32,
+        ) {
+        }
+        fn modifiers(
+            &self,
+            _seat: &Seat<State>,
+            _data: &mut State,
+            _modifiers: ModifiersState,
+            _serial: Serial,
+        ) {
+        }
+    }
+
+    struct State {
+        seat_state: SeatState<State>,
+        bindings: KeyBindings<Action>,
+        inhibited: bool,
+    }
+
+    impl SeatHandler for State {
+        type KeyboardFocus = Target;
+        type PointerFocus = Target;
+
+        fn seat_state(&mut self) -> &mut SeatState<State> {
+            &mut self.seat_state
+        }
+    }
+
+    // a keyboard with the default keymap, filtering its input through the bindings of the state
+    fn setup() -> (State, KeyboardHandle<State>) {
+        let mut seat_state = SeatState::new();
+        let mut seat = seat_state.new_seat("seat-0", None);
+        let keyboard = seat.add_keyboard(XkbConfig::default(), 200, 25).unwrap();
+        let state = State {
+            seat_state,
+            bindings: KeyBindings::new(),
+            inhibited: false,
+        };
+        (state, keyboard)
+    }
+
+    // returns `None` if the key was forwarded, the result of the bindings otherwise
+    fn key(
+        state: &mut State,
+        keyboard: &KeyboardHandle<State>,
+        keycode: u32,
+        key_state: KeyState,
+    ) -> Option<Option<Action>> {
+        keyboard.input(
+            state,
+            keycode,
+            key_state,
+            Serial::from(0),
+            0,
+            |state, modifiers, handle| {
+                let inhibited = state.inhibited;
+                state.bindings.filter(modifiers, handle, key_state, inhibited)
+            },
+        )
+    }
+
+    fn press(state: &mut State, keyboard: &KeyboardHandle<State>, keycode: u32) -> Option<Option<Action>> {
+        key(state, keyboard, keycode, KeyState::Pressed)
+    }
+
+    fn release(state: &mut State, keyboard: &KeyboardHandle<State>, keycode: u32) -> Option<Option<Action>> {
+        key(state, keyboard, keycode, KeyState::Released)
+    }
+
+    #[test]
+    fn parse_binding() {
+        let binding = Binding::parse("Super+Shift+Return", ()).unwrap();
+        assert_eq!(
+            binding.sequence,
+            vec![KeyCombo {
+                modifiers: BindingModifiers {
+                    logo: true,
+                    shift: true,
+                    ..Default::default()
+                },
+                keysym: xkb::keysyms::KEY_Return,
+            }]
+        );
+        assert_eq!(binding.sequence[0].to_string(), "Super+Shift+Return");
+
+        let chord = Binding::parse("ctrl+x  Ctrl+c", ()).unwrap();
+        assert_eq!(chord.sequence.len(), 2);
+        assert!(chord.sequence.iter().all(|combo| combo.modifiers.ctrl));
+
+        assert_eq!(
+            Binding::parse("Super+", ()).unwrap_err(),
+            ParseBindingError::Empty
+        );
+        assert_eq!(
+            Binding::parse("Hyper+a", ()).unwrap_err(),
+            ParseBindingError::UnknownModifier("Hyper".into())
+        );
+        assert_eq!(
+            Binding::parse("Super+NotAKey", ()).unwrap_err(),
+            ParseBindingError::UnknownKey("NotAKey".into())
+        );
+    }
+
+    #[test]
+    fn chord_triggers_on_last_key() {
+        let (mut state, keyboard) = setup();
+        state
+            .bindings
+            .bind(DEFAULT_MODE, "Ctrl+x Ctrl+c", Action::Quit)
+            .unwrap();
+
+        assert_eq!(press(&mut state, &keyboard, KEY_LEFTCTRL), None);
+        assert_eq!(press(&mut state, &keyboard, KEY_X), Some(None));
+        assert!(state.bindings.is_pending());
+        // the release of an intercepted key is intercepted as well
+        assert_eq!(release(&mut state, &keyboard, KEY_X), Some(None));
+        assert_eq!(press(&mut state, &keyboard, KEY_C), Some(Some(Action::Quit)));
+        assert!(!state.bindings.is_pending());
+        assert_eq!(release(&mut state, &keyboard, KEY_C), Some(None));
+        assert_eq!(release(&mut state, &keyboard, KEY_LEFTCTRL), None);
+    }
+
+    #[test]
+    fn modifiers_are_forwarded_mid_chord() {
+        let (mut state, keyboard) = setup();
+        state
+            .bindings
+            .bind(DEFAULT_MODE, "Ctrl+x Ctrl+c", Action::Quit)
+            .unwrap();
+
+        press(&mut state, &keyboard, KEY_LEFTCTRL);
+        press(&mut state, &keyboard, KEY_X);
+        release(&mut state, &keyboard, KEY_X);
+        // modifiers are released and pressed again before the next key of the chord
+        assert_eq!(release(&mut state, &keyboard, KEY_LEFTCTRL), None);
+        assert_eq!(press(&mut state, &keyboard, KEY_LEFTCTRL), None);
+        assert!(state.bindings.is_pending());
+        assert_eq!(press(&mut state, &keyboard, KEY_C), Some(Some(Action::Quit)));
+    }
+
+    #[test]
+    fn other_keys_abort_chords() {
+        let (mut state, keyboard) = setup();
+        state
+            .bindings
+            .bind(DEFAULT_MODE, "Ctrl+x Ctrl+c", Action::Quit)
+            .unwrap();
+        state
+            .bindings
+            .bind(DEFAULT_MODE, "Ctrl+a", Action::Select)
+            .unwrap();
+
+        press(&mut state, &keyboard, KEY_LEFTCTRL);
+        press(&mut state, &keyboard, KEY_X);
+        release(&mut state, &keyboard, KEY_X);
+        // not part of the chord, but a binding on its own
+        assert_eq!(press(&mut state, &keyboard, KEY_A), Some(Some(Action::Select)));
+        assert!(!state.bindings.is_pending());
+        release(&mut state, &keyboard, KEY_A);
+
+        press(&mut state, &keyboard, KEY_X);
+        release(&mut state, &keyboard, KEY_X);
+        release(&mut state, &keyboard, KEY_LEFTCTRL);
+        // neither part of the chord nor bound
+        assert_eq!(press(&mut state, &keyboard, KEY_C), None);
+        assert!(!state.bindings.is_pending());
+        assert_eq!(release(&mut state, &keyboard, KEY_C), None);
+    }
+
+    #[test]
+    fn release_triggers_and_modes() {
+        let (mut state, keyboard) = setup();
+        state
+            .bindings
+            .bind(DEFAULT_MODE, "Ctrl+x Ctrl+c", Action::Quit)
+            .unwrap();
+        state.bindings.add(
+            "resize",
+            Binding::parse("Escape", Action::Leave).unwrap().on_release(),
+        );
+
+        // bindings of other modes are not active
+        assert_eq!(press(&mut state, &keyboard, KEY_ESC), None);
+        assert_eq!(release(&mut state, &keyboard, KEY_ESC), None);
+
+        press(&mut state, &keyboard, KEY_LEFTCTRL);
+        press(&mut state, &keyboard, KEY_X);
+        release(&mut state, &keyboard, KEY_X);
+        release(&mut state, &keyboard, KEY_LEFTCTRL);
+        // changing the mode aborts the chord
+        state.bindings.set_mode("resize");
+        assert!(!state.bindings.is_pending());
+        assert_eq!(state.bindings.mode(), "resize");
+
+        assert_eq!(press(&mut state, &keyboard, KEY_ESC), Some(None));
+        assert_eq!(release(&mut state, &keyboard, KEY_ESC), Some(Some(Action::Leave)));
+
+        state.bindings.set_mode(DEFAULT_MODE);
+        assert_eq!(press(&mut state, &keyboard, KEY_ESC), None);
+    }
+
+    #[test]
+    fn inhibitor_bypass() {
+        let (mut state, keyboard) = setup();
+        state
+            .bindings
+            .bind(DEFAULT_MODE, "Super+Return", Action::Terminal)
+            .unwrap();
+        state.bindings.add(
+            DEFAULT_MODE,
+            Binding::parse("Super+q", Action::Switch)
+                .unwrap()
+                .ignore_inhibitor(),
+        );
+        state.inhibited = true;
+
+        press(&mut state, &keyboard, KEY_LEFTMETA);
+        assert_eq!(press(&mut state, &keyboard, KEY_RETURN), None);
+        assert_eq!(release(&mut state, &keyboard, KEY_RETURN), None);
+        assert_eq!(press(&mut state, &keyboard, KEY_Q), Some(Some(Action::Switch)));
+        assert_eq!(release(&mut state, &keyboard, KEY_Q), Some(None));
+
+        state.inhibited = false;
+        assert_eq!(
+            press(&mut state, &keyboard, KEY_RETURN),
+            Some(Some(Action::Terminal))
+        );
+    }
+
+    #[test]
+    fn remapped_keys_are_released_as_pressed() {
+        let (mut state, keyboard) = setup();
+        state.bindings.add(
+            DEFAULT_MODE,
+            Binding::parse("Escape", Action::Leave).unwrap().on_release(),
+        );
+        keyboard.set_key_remap([(KEY_CAPSLOCK, KEY_ESC)].into_iter().collect());
+
+        assert_eq!(press(&mut state, &keyboard, KEY_CAPSLOCK), Some(None));
+        // the remapping changes while the key is held down
+        keyboard.set_key_remap(HashMap::new());
+        assert_eq!(
+            release(&mut state, &keyboard, KEY_CAPSLOCK),
+            Some(Some(Action::Leave))
+        );
+
+        // without remapping caps lock is no binding
+        assert_eq!(press(&mut state, &keyboard, KEY_CAPSLOCK), None);
+        assert_eq!(release(&mut state, &keyboard, KEY_CAPSLOCK), None);
+    }
+}
@@ -2,13 +2,21 @@
 
 use crate::backend::input::KeyState;
 use crate::utils::{IsAlive, Serial};
-use slog::{debug, error, info, o, trace};
-use std::collections::HashSet;
+use calloop::{
+    channel::{self, Sender},
+    timer::{TimeoutAction, Timer},
+    LoopHandle,
+};
+use slog::{debug, error, info, o, trace, warn};
+use std::collections::{HashMap, HashSet};
 use std::{
+    cell::{Cell, RefCell},
     default::Default,
     ffi::CString,
     fmt, io,
+    rc::Rc,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 use thiserror::Error;
 
@@ -16,12 +24,23 @@ pub use xkbcommon::xkb::{self, keysyms, Keysym};
 
 use super::{Seat, SeatHandler};
 
+mod accessibility;
+use accessibility::{AccessX, SlowKeys, StickyKeys};
+
+mod bindings;
+pub use bindings::{
+    Binding, BindingModifiers, KeyBindings, KeyCombo, ParseBindingError, Trigger, DEFAULT_MODE,
+};
+
 mod keymap_file;
 pub(crate) use keymap_file::KeymapFile;
 
 mod modifiers_state;
 pub use modifiers_state::ModifiersState;
 
+mod repeat;
+use repeat::{repeat_interval, KeyRepeat};
+
 mod xkb_config;
 pub use xkb_config::XkbConfig;
 
@@ -64,6 +83,12 @@ pub(crate) struct KbdInternal<D: SeatHandler> {
     pub(crate) repeat_rate: i32,
     pub(crate) repeat_delay: i32,
     grab: GrabStatus<D>,
+    focus_layouts: Option<Vec<(<D as SeatHandler>::KeyboardFocus, u32)>>,
+    remap: HashMap<u32, u32>,
+    // remapped keycodes of the currently pressed keys
+    remapped_keys: HashMap<u32, u32>,
+    accessx: AccessX,
+    repeat: Option<KeyRepeat>,
 }
 
 // focus_hook does not implement debug, so we have to impl Debug manually
@@ -81,6 +106,10 @@ where
             .field("state", &self.state.get_raw_ptr())
             .field("repeat_rate", &self.repeat_rate)
             .field("repeat_delay", &self.repeat_delay)
+            .field("focus_layouts", &self.focus_layouts)
+            .field("remap", &self.remap)
+            .field("accessx", &self.accessx)
+            .field("repeat", &self.repeat)
             .finish()
     }
 }
@@ -91,23 +120,7 @@ unsafe impl<D: SeatHandler> Send for KbdInternal<D> {}
 
 impl<D: SeatHandler + 'static> KbdInternal<D> {
     fn new(xkb_config: XkbConfig<'_>, repeat_rate: i32, repeat_delay: i32) -> Result<KbdInternal<D>, ()> {
-        // we create a new contex for each keyboard because libxkbcommon is actually NOT threadsafe
-        // so confining it inside the KbdInternal allows us to use Rusts mutability rules to make
-        // sure nothing goes wrong.
-        //
-        // FIXME: This is an issue with the xkbcommon-rs crate that does not reflect this
-        // non-threadsafety properly.
-        let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
-        let keymap = xkb::Keymap::new_from_names(
-            &context,
-            &xkb_config.rules,
-            &xkb_config.model,
-            &xkb_config.layout,
-            &xkb_config.variant,
-            xkb_config.options,
-            xkb::KEYMAP_COMPILE_NO_FLAGS,
-        )
-        .ok_or(())?;
+        let keymap = compile_keymap(xkb_config).ok_or(())?;
         let state = xkb::State::new(&keymap);
         Ok(KbdInternal {
             focus: None,
@@ -119,9 +132,102 @@ impl<D: SeatHandler + 'static> KbdInternal<D> {
             repeat_rate,
             repeat_delay,
             grab: GrabStatus::None,
+            focus_layouts: None,
+            remap: HashMap::new(),
+            remapped_keys: HashMap::new(),
+            accessx: AccessX::default(),
+            repeat: None,
         })
     }
 
+    fn active_layout(&self) -> u32 {
+        self.state.serialize_layout(xkb::STATE_LAYOUT_EFFECTIVE)
+    }
+
+    // return true if the layout has changed
+    fn set_layout(&mut self, layout: u32) -> bool {
+        if layout >= self.keymap.num_layouts() || layout == self.active_layout() {
+            return false;
+        }
+
+        let depressed = self.state.serialize_mods(xkb::STATE_MODS_DEPRESSED);
+        let latched = self.state.serialize_mods(xkb::STATE_MODS_LATCHED);
+        let locked = self.state.serialize_mods(xkb::STATE_MODS_LOCKED);
+        self.state.update_mask(depressed, latched, locked, 0, 0, layout);
+        self.mods_state.update_with(&self.state);
+        true
+    }
+
+    fn set_keymap(&mut self, keymap: xkb::Keymap) {
+        let mut state = xkb::State::new(&keymap);
+        // keep keys held down across the change
+        for keycode in self.pressed_keys.iter() {
+            state.update_key(keycode + 8, xkb::KeyDirection::Down);
+        }
+        self.keymap = keymap;
+        self.state = state;
+        self.mods_state.update_with(&self.state);
+        if let Some(repeat) = self.repeat.as_mut() {
+            repeat.cancel();
+        }
+        // modifiers latched by sticky keys are lost with the old state
+        if let Some(sticky_keys) = self.accessx.sticky_keys.as_mut() {
+            *sticky_keys = StickyKeys::default();
+        }
+        // layout indices of the old keymap are meaningless now
+        if let Some(layouts) = self.focus_layouts.as_mut() {
+            layouts.clear();
+        }
+    }
+
+    // remember the active layout for the current focus
+    fn save_focus_layout(&mut self) {
+        let layout = self.active_layout();
+        if let (Some(layouts), Some((focus, _))) = (self.focus_layouts.as_mut(), self.focus.as_ref()) {
+            layouts.retain(|(f, _)| f.alive() && f != focus);
+            layouts.push((focus.clone(), layout));
+        }
+    }
+
+    // restore the layout remembered for the current focus, falling back to the first layout
+    fn restore_focus_layout(&mut self) {
+        let layout = match (self.focus_layouts.as_ref(), self.focus.as_ref()) {
+            (Some(layouts), Some((focus, _))) => layouts
+                .iter()
+                .find(|(f, _)| f == focus)
+                .map(|(_, layout)| *layout)
+                .unwrap_or(0),
+            _ => return,
+        };
+        self.set_layout(layout);
+    }
+
+    // translate a keycode using the remapping table
+    fn remap_key(&mut self, keycode: u32, state: KeyState) -> u32 {
+        match state {
+            KeyState::Pressed => {
+                let remapped = self.remap.get(&keycode).copied().unwrap_or(keycode);
+                self.remapped_keys.insert(keycode, remapped);
+                remapped
+            }
+            // release the key that was pressed, even if the remapping changed in between
+            KeyState::Released => self.remapped_keys.remove(&keycode).unwrap_or(keycode),
+        }
+    }
+
+    // start repeating a key, if server-side key repeat is enabled
+    fn start_repeat(&mut self, keycode: u32, time: u32) {
+        let repeats = self.keymap.key_repeats(keycode + 8) && repeat_interval(self.repeat_rate).is_some();
+        let delay = Duration::from_millis(self.repeat_delay.max(0) as u64);
+        if let Some(repeat) = self.repeat.as_mut() {
+            if repeats {
+                repeat.start(keycode, time, delay);
+            } else {
+                repeat.cancel();
+            }
+        }
+    }
+
     // return true if modifier state has changed
     fn key_input(&mut self, keycode: u32, state: KeyState) -> bool {
         // track pressed keys as xkbcommon does not seem to expose it :(
@@ -139,9 +245,12 @@ impl<D: SeatHandler + 'static> KbdInternal<D> {
         // update state
         // Offset the keycode by 8, as the evdev XKB rules reflect X's
         // broken keycode system, which starts at 8.
-        let state_components = self.state.update_key(keycode + 8, direction);
+        let changed = match self.accessx.sticky_keys.as_mut() {
+            Some(sticky_keys) => sticky_keys.key_input(&mut self.state, keycode + 8, direction),
+            None => self.state.update_key(keycode + 8, direction) != 0,
+        };
 
-        if state_components != 0 {
+        if changed {
             self.mods_state.update_with(&self.state);
             true
         } else {
@@ -200,6 +309,60 @@ impl<D: SeatHandler + 'static> KbdInternal<D> {
     }
 }
 
+fn compile_keymap(xkb_config: XkbConfig<'_>) -> Option<xkb::Keymap> {
+    // we create a new contex for each keymap because libxkbcommon is actually NOT threadsafe
+    // so confining it inside the KbdInternal allows us to use Rusts mutability rules to make
+    // sure nothing goes wrong.
+    //
+    // FIXME: This is an issue with the xkbcommon-rs crate that does not reflect this
+    // non-threadsafety properly.
+    let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+    xkb::Keymap::new_from_names(
+        &context,
+        &xkb_config.rules,
+        &xkb_config.model,
+        &xkb_config.layout,
+        &xkb_config.variant,
+        xkb_config.options,
+        xkb::KEYMAP_COMPILE_NO_FLAGS,
+    )
+}
+
+fn keymap_string(keymap: &xkb::Keymap) -> CString {
+    let keymap = keymap.get_as_string(xkb::KEYMAP_FORMAT_TEXT_V1);
+    CString::new(keymap).expect("Keymap should not contain interior nul bytes")
+}
+
+// The keyboard can be used from any thread, but its timers have to be inserted into the event
+// loop. Messages sent through the returned sender are passed to `callback` on the event loop,
+// the source is removed again once the sender is dropped.
+fn loop_sender<Data, T, F>(
+    handle: &LoopHandle<'static, Data>,
+    mut callback: F,
+) -> Result<Sender<T>, calloop::Error>
+where
+    Data: 'static,
+    T: 'static,
+    F: FnMut(T, &mut Data) + 'static,
+{
+    let (sender, channel) = channel::channel::<T>();
+    let token = Rc::new(Cell::new(None));
+    let source_token = token.clone();
+    let remove_handle = handle.clone();
+    let registration = handle
+        .insert_source(channel, move |event, _, data| match event {
+            channel::Event::Msg(msg) => callback(msg, data),
+            channel::Event::Closed => {
+                if let Some(token) = source_token.take() {
+                    remove_handle.remove(token);
+                }
+            }
+        })
+        .map_err(|err| err.error)?;
+    token.set(Some(registration));
+    Ok(sender)
+}
+
 /// Errors that can be encountered when creating a keyboard handler
 #[derive(Debug, Error)]
 pub enum Error {
@@ -213,8 +376,7 @@ pub enum Error {
 
 pub(crate) struct KbdRc<D: SeatHandler> {
     pub(crate) internal: Mutex<KbdInternal<D>>,
-    #[allow(dead_code)]
-    pub(crate) keymap: KeymapFile,
+    pub(crate) keymap: Mutex<KeymapFile>,
     pub(crate) logger: ::slog::Logger,
     #[cfg(feature = "wayland_frontend")]
     pub(crate) known_kbds: Mutex<Vec<wayland_server::protocol::wl_keyboard::WlKeyboard>>,
@@ -362,6 +524,34 @@ pub trait KeyboardGrab<D: SeatHandler> {
         serial: Serial,
     );
 
+    /// A key held down was repeated
+    ///
+    /// Only called if server-side key repeat was enabled via
+    /// [`KeyboardHandle::enable_key_repeat`]. Clients repeat keys on their own, so the
+    /// default implementation does nothing.
+    fn repeat(
+        &mut self,
+        _data: &mut D,
+        _handle: &mut KeyboardInnerHandle<'_, D>,
+        _keycode: u32,
+        _serial: Serial,
+        _time: u32,
+    ) {
+    }
+
+    /// The modifiers changed without a key input, e.g. because the active layout changed
+    ///
+    /// The default implementation forwards the modifiers to the current focus.
+    fn modifiers(
+        &mut self,
+        data: &mut D,
+        handle: &mut KeyboardInnerHandle<'_, D>,
+        modifiers: ModifiersState,
+        serial: Serial,
+    ) {
+        handle.modifiers(data, modifiers, serial)
+    }
+
     /// The data about the event that started the grab.
     fn start_data(&self) -> &GrabStartData<D>;
 }
@@ -424,13 +614,12 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
 
         info!(log, "Loaded Keymap"; "name" => internal.keymap.layouts().next());
 
-        let keymap = internal.keymap.get_as_string(xkb::KEYMAP_FORMAT_TEXT_V1);
-        let keymap = CString::new(keymap).expect("Keymap should not contain interior nul bytes");
+        let keymap = keymap_string(&internal.keymap);
 
         Ok(Self {
             arc: Arc::new(KbdRc {
                 internal: Mutex::new(internal),
-                keymap: KeymapFile::new(keymap, log.clone()),
+                keymap: Mutex::new(KeymapFile::new(keymap, log.clone())),
                 logger: log,
                 #[cfg(feature = "wayland_frontend")]
                 known_kbds: Mutex::new(Vec::new()),
@@ -483,7 +672,8 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
     /// as interpreted by the keymap before it is forwarded to the focused client. If this closure
     /// returns [`FilterResult::Forward`], the input will not be sent to the client. If it returns
     /// [`FilterResult::Intercept`] a value can be passed to be returned by the whole function.
-    /// This mechanism can be used to implement compositor-level key bindings for example.
+    /// This mechanism can be used to implement compositor-level key bindings for example,
+    /// see [`KeyBindings`].
     ///
     /// The module [`crate::wayland::seat::keysyms`] exposes definitions of all possible keysyms
     /// to be compared against. This includes non-character keysyms, such as XF86 special keys.
@@ -501,7 +691,15 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
     {
         trace!(self.arc.logger, "Handling keystroke"; "keycode" => keycode, "state" => format_args!("{:?}", state));
         let mut guard = self.arc.internal.lock().unwrap();
+        if !guard.accessx.filter(keycode, state, time) {
+            trace!(self.arc.logger, "Input was dropped by accessibility filters");
+            return None;
+        }
+        let keycode = guard.remap_key(keycode, state);
         let mods_changed = guard.key_input(keycode, state);
+        if let (KeyState::Released, Some(repeat)) = (state, guard.repeat.as_mut()) {
+            repeat.released(keycode);
+        }
         let key_handle = KeysymHandle {
             // Offset the keycode by 8, as the evdev XKB rules reflect X's
             // broken keycode system, which starts at 8.
@@ -517,9 +715,21 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
         if let FilterResult::Intercept(val) = filter(data, &guard.mods_state, key_handle) {
             // the filter returned false, we do not forward to client
             trace!(self.arc.logger, "Input was intercepted by filter");
+            if state == KeyState::Pressed {
+                guard.start_repeat(keycode, time);
+            }
             return Some(val);
         }
 
+        // clients repeat keys on their own, only grabs need server-side repeat
+        if state == KeyState::Pressed {
+            if let GrabStatus::Active(..) = guard.grab {
+                guard.start_repeat(keycode, time);
+            } else if let Some(repeat) = guard.repeat.as_mut() {
+                repeat.cancel();
+            }
+        }
+
         // forward to client if no keybinding is triggered
         let seat = self.get_seat(data);
         let modifiers = mods_changed.then(|| guard.mods_state);
@@ -539,6 +749,58 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
         None
     }
 
+    /// Handle a repeat of a key held down
+    ///
+    /// Meant to be called from the callback passed to [`KeyboardHandle::enable_key_repeat`]
+    /// with the keycode it received. The `filter` is called the same way as for
+    /// [`KeyboardHandle::input`]. Repeats not intercepted by the filter are passed to the active
+    /// [`KeyboardGrab`], but never to clients, which handle key repeat on their own.
+    ///
+    /// Does nothing if the key is not repeated anymore.
+    pub fn repeat_key<T, F>(
+        &self,
+        data: &mut D,
+        keycode: u32,
+        serial: Serial,
+        time: u32,
+        filter: F,
+    ) -> Option<T>
+    where
+        F: FnOnce(&mut D, &ModifiersState, KeysymHandle<'_>) -> FilterResult<T>,
+    {
+        let mut guard = self.arc.internal.lock().unwrap();
+        if !guard
+            .repeat
+            .as_ref()
+            .map(|repeat| repeat.is_repeating(keycode))
+            .unwrap_or(false)
+        {
+            return None;
+        }
+        trace!(self.arc.logger, "Handling key repeat"; "keycode" => keycode);
+
+        let key_handle = KeysymHandle {
+            keycode: keycode + 8,
+            state: &guard.state,
+            keymap: &guard.keymap,
+        };
+        if let FilterResult::Intercept(val) = filter(data, &guard.mods_state, key_handle) {
+            trace!(self.arc.logger, "Key repeat was intercepted by filter");
+            return Some(val);
+        }
+
+        let seat = self.get_seat(data);
+        guard.with_grab(
+            &seat,
+            move |mut handle, grab| {
+                grab.repeat(data, &mut handle, keycode, serial, time);
+            },
+            self.arc.logger.clone(),
+        );
+
+        None
+    }
+
     /// Set the current focus of this keyboard
     ///
     /// If the new focus is different from the previous one, any previous focus
@@ -574,6 +836,301 @@ impl<D: SeatHandler + 'static> KeyboardHandle<D> {
         }
     }
 
+    /// Enable server-side key repeat
+    ///
+    /// Clients repeat keys held down on their own, but keys intercepted by the filter of
+    /// [`KeyboardHandle::input`] and keys sent to a [`KeyboardGrab`] need to be repeated by
+    /// the compositor. With server-side key repeat enabled, a timer is inserted into the event
+    /// loop of `handle` for such keys, using the rate and delay set by
+    /// [`KeyboardHandle::change_repeat_info`].
+    ///
+    /// For every repeat `callback` is called with the keycode and time of the repeat. It is
+    /// expected to pass them to [`KeyboardHandle::repeat_key`] with the same filter used for
+    /// [`KeyboardHandle::input`]. Repeating stops once the key is released, another key is
+    /// pressed or the focus changes.
+    pub fn enable_key_repeat<Data, F>(&self, handle: &LoopHandle<'static, Data>, callback: F)
+    where
+        Data: 'static,
+        F: FnMut(&mut Data, u32, u32) + 'static,
+    {
+        let arc = Arc::downgrade(&self.arc);
+        let timer_handle = handle.clone();
+        let callback = Rc::new(RefCell::new(callback));
+        let logger = self.arc.logger.clone();
+        let start_timer = move |(keycode, generation, time, delay): (u32, u64, u32, Duration),
+                                _: &mut Data| {
+            let arc = arc.clone();
+            let callback = callback.clone();
+            let mut time = time.wrapping_add(delay.as_millis() as u32);
+            let res = timer_handle.insert_source(Timer::from_duration(delay), move |_, _, data| {
+                let interval = arc.upgrade().and_then(|arc| {
+                    let guard = arc.internal.lock().unwrap();
+                    match guard.repeat.as_ref() {
+                        Some(repeat) if repeat.is_current(keycode, generation) => {
+                            repeat_interval(guard.repeat_rate)
+                        }
+                        _ => None,
+                    }
+                });
+                match interval {
+                    Some(interval) => {
+                        (callback.borrow_mut())(data, keycode, time);
+                        time = time.wrapping_add(interval.as_millis() as u32);
+                        TimeoutAction::ToDuration(interval)
+                    }
+                    None => TimeoutAction::Drop,
+                }
+            });
+            if let Err(err) = res {
+                warn!(logger, "Failed to insert key repeat timer"; "err" => format!("{}", err.error));
+            }
+        };
+        let sender = match loop_sender(handle, start_timer) {
+            Ok(sender) => sender,
+            Err(err) => {
+                warn!(self.arc.logger, "Failed to enable key repeat"; "err" => format!("{}", err));
+                return;
+            }
+        };
+        // the event loop is gone if sending fails, no timer can run anymore
+        let schedule = move |keycode: u32, generation: u64, time: u32, delay: Duration| {
+            let _ = sender.send((keycode, generation, time, delay));
+        };
+
+        let mut guard = self.arc.internal.lock().unwrap();
+        guard.repeat = Some(KeyRepeat::new(Box::new(schedule)));
+    }
+
+    /// Disable server-side key repeat
+    pub fn disable_key_repeat(&self) {
+        self.arc.internal.lock().unwrap().repeat = None;
+    }
+
+    #[cfg(feature = "wayland_frontend")]
+    pub(crate) fn is_key_repeat_enabled(&self) -> bool {
+        self.arc.internal.lock().unwrap().repeat.is_some()
+    }
+
+    /// Remap keys before they are processed
+    ///
+    /// Every key press with a keycode found in `remap` is handled as if the key it maps to was
+    /// pressed instead, both by the filter of [`KeyboardHandle::input`] and by clients. Keycodes
+    /// are given as evdev keycodes, for example `remap.insert(58, 1)` turns caps lock into escape.
+    ///
+    /// Keys held down while the remapping changes are released with the keycode they were
+    /// pressed with. Remapping keysyms instead of keys is better done through the xkb options
+    /// of the keymap.
+    pub fn set_key_remap(&self, remap: HashMap<u32, u32>) {
+        self.arc.internal.lock().unwrap().remap = remap;
+    }
+
+    /// Enable or disable sticky keys
+    ///
+    /// With sticky keys enabled, pressing and releasing a modifier key on its own latches its
+    /// modifiers until the next key is released. Doing so twice locks them until the modifier
+    /// key is pressed and released once more. Latched and locked modifiers are part of the
+    /// [`ModifiersState`] and are sent to clients like any other modifiers.
+    pub fn set_sticky_keys(&self, data: &mut D, enabled: bool, serial: Serial) {
+        let mut guard = self.arc.internal.lock().unwrap();
+        let internal = &mut *guard;
+        match (enabled, internal.accessx.sticky_keys.take()) {
+            (true, sticky_keys) => internal.accessx.sticky_keys = Some(sticky_keys.unwrap_or_default()),
+            (false, Some(mut sticky_keys)) => {
+                if sticky_keys.clear(&mut internal.state) {
+                    internal.mods_state.update_with(&internal.state);
+                    let seat = self.get_seat(data);
+                    self.send_modifiers(&seat, data, internal, serial);
+                }
+            }
+            (false, None) => {}
+        }
+    }
+
+    /// Set the delay used by bounce keys
+    ///
+    /// Presses of a key within `delay` after it was released are ignored, which filters out
+    /// accidental repeated presses. `None` disables bounce keys.
+    ///
+    /// The delay is measured using the `time` passed to [`KeyboardHandle::input`].
+    pub fn set_bounce_keys(&self, delay: Option<Duration>) {
+        self.arc.internal.lock().unwrap().accessx.set_bounce_delay(delay);
+    }
+
+    /// Enable slow keys
+    ///
+    /// Key presses are only accepted once the key was held down for `delay`, keys released
+    /// earlier are ignored.
+    ///
+    /// Accepted presses have to pass through the input handling of the compositor, including
+    /// the filter of [`KeyboardHandle::input`]. For this `callback` is called from a timer
+    /// inserted into the event loop of `handle` with the keycode and time of the accepted
+    /// press. It is expected to feed the press into [`KeyboardHandle::input`] again, like a
+    /// key press of the input backend.
+    pub fn enable_slow_keys<Data, F>(&self, handle: &LoopHandle<'static, Data>, delay: Duration, callback: F)
+    where
+        Data: 'static,
+        F: FnMut(&mut Data, u32, u32) + 'static,
+    {
+        let arc = Arc::downgrade(&self.arc);
+        let timer_handle = handle.clone();
+        let callback = Rc::new(RefCell::new(callback));
+        let logger = self.arc.logger.clone();
+        let start_timer = move |(keycode, generation, time): (u32, u64, u32), _: &mut Data| {
+            let arc = arc.clone();
+            let callback = callback.clone();
+            let time = time.wrapping_add(delay.as_millis() as u32);
+            let res = timer_handle.insert_source(Timer::from_duration(delay), move |_, _, data| {
+                let accepted = arc
+                    .upgrade()
+                    .map(|arc| {
+                        let mut guard = arc.internal.lock().unwrap();
+                        guard.accessx.slow_key_elapsed(keycode, generation)
+                    })
+                    .unwrap_or(false);
+                if accepted {
+                    (callback.borrow_mut())(data, keycode, time);
+                }
+                TimeoutAction::Drop
+            });
+            if let Err(err) = res {
+                warn!(logger, "Failed to insert slow keys timer"; "err" => format!("{}", err.error));
+            }
+        };
+        let sender = match loop_sender(handle, start_timer) {
+            Ok(sender) => sender,
+            Err(err) => {
+                warn!(self.arc.logger, "Failed to enable slow keys"; "err" => format!("{}", err));
+                return;
+            }
+        };
+        // the event loop is gone if sending fails, no timer can run anymore
+        let schedule = move |keycode: u32, generation: u64, time: u32| {
+            let _ = sender.send((keycode, generation, time));
+        };
+
+        let mut guard = self.arc.internal.lock().unwrap();
+        guard.accessx.slow_keys = Some(SlowKeys::new(delay, Box::new(schedule)));
+    }
+
+    /// Disable slow keys
+    ///
+    /// Keys currently waiting for their delay to elapse are ignored until they are released.
+    pub fn disable_slow_keys(&self) {
+        self.arc.internal.lock().unwrap().accessx.slow_keys = None;
+    }
+
+    /// Returns the names of the layouts of the current keymap
+    ///
+    /// Layouts are identified by their index in this list.
+    pub fn layouts(&self) -> Vec<String> {
+        let guard = self.arc.internal.lock().unwrap();
+        guard.keymap.layouts().map(String::from).collect()
+    }
+
+    /// Returns the index of the currently active layout
+    pub fn active_layout(&self) -> u32 {
+        self.arc.internal.lock().unwrap().active_layout()
+    }
+
+    /// Change the active layout
+    ///
+    /// Indices out of range of [`KeyboardHandle::layouts`] are ignored. The focused client
+    /// is notified about the change via its modifiers.
+    pub fn set_layout(&self, data: &mut D, layout: u32, serial: Serial) {
+        let mut guard = self.arc.internal.lock().unwrap();
+        if guard.set_layout(layout) {
+            let seat = self.get_seat(data);
+            self.send_modifiers(&seat, data, &mut guard, serial);
+        }
+    }
+
+    /// Switch to the next layout, wrapping around after the last one
+    pub fn cycle_layout_next(&self, data: &mut D, serial: Serial) {
+        let (active, count) = self.layout_position();
+        self.set_layout(data, (active + 1) % count, serial);
+    }
+
+    /// Switch to the previous layout, wrapping around after the first one
+    pub fn cycle_layout_prev(&self, data: &mut D, serial: Serial) {
+        let (active, count) = self.layout_position();
+        self.set_layout(data, (active + count - 1) % count, serial);
+    }
+
+    fn layout_position(&self) -> (u32, u32) {
+        let guard = self.arc.internal.lock().unwrap();
+        (guard.active_layout(), guard.keymap.num_layouts().max(1))
+    }
+
+    /// Remember the active layout separately for every keyboard focus
+    ///
+    /// When enabled, the layout active while a target had the keyboard focus is restored
+    /// once it gets focused again. Targets focused for the first time start with the first
+    /// layout.
+    pub fn set_layout_per_focus(&self, enabled: bool) {
+        let mut guard = self.arc.internal.lock().unwrap();
+        match (enabled, guard.focus_layouts.is_some()) {
+            (true, false) => guard.focus_layouts = Some(Vec::new()),
+            (false, true) => guard.focus_layouts = None,
+            _ => {}
+        }
+    }
+
+    /// Replace the keymap of this keyboard
+    ///
+    /// The new keymap is sent to all clients, including an input method currently grabbing
+    /// this keyboard. Keys held down are kept pressed, but the active layout is reset to the
+    /// first layout of the new keymap.
+    pub fn set_xkb_config(
+        &self,
+        data: &mut D,
+        xkb_config: XkbConfig<'_>,
+        serial: Serial,
+    ) -> Result<(), Error> {
+        info!(self.arc.logger, "Loading new keymap";
+            "rules" => xkb_config.rules, "model" => xkb_config.model, "layout" => xkb_config.layout,
+            "variant" => xkb_config.variant, "options" => &xkb_config.options
+        );
+        let keymap = compile_keymap(xkb_config).ok_or_else(|| {
+            debug!(self.arc.logger, "Loading keymap failed");
+            Error::BadKeymap
+        })?;
+
+        let mut guard = self.arc.internal.lock().unwrap();
+        let keymap_string = keymap_string(&keymap);
+        guard.set_keymap(keymap);
+
+        let seat = self.get_seat(data);
+        #[cfg(feature = "wayland_frontend")]
+        if let Some(input_method) = seat
+            .user_data()
+            .get::<crate::wayland::input_method::InputMethodHandle>()
+        {
+            input_method.keymap_changed(&keymap_string);
+        }
+
+        *self.arc.keymap.lock().unwrap() = KeymapFile::new(keymap_string, self.arc.logger.clone());
+        #[cfg(feature = "wayland_frontend")]
+        for kbd in &*self.arc.known_kbds.lock().unwrap() {
+            // failures are logged and there is nothing else to do about them
+            let _ = self.send_keymap(kbd);
+        }
+        self.send_modifiers(&seat, data, &mut guard, serial);
+
+        Ok(())
+    }
+
+    // modifiers are passed through the active grab, like key inputs
+    fn send_modifiers(&self, seat: &Seat<D>, data: &mut D, guard: &mut KbdInternal<D>, serial: Serial) {
+        let modifiers = guard.mods_state;
+        guard.with_grab(
+            seat,
+            move |mut handle, grab| {
+                grab.modifiers(data, &mut handle, modifiers, serial);
+            },
+            self.arc.logger.clone(),
+        );
+    }
+
     fn get_seat(&self, data: &mut D) -> Seat<D> {
         let seat_state = data.seat_state();
         seat_state
@@ -649,6 +1206,16 @@ impl<'a, D: SeatHandler + 'static> KeyboardInnerHandle<'a, D> {
         self.inner.focus.as_ref().map(|f| &f.0)
     }
 
+    /// Access the current modifiers of this keyboard
+    pub fn modifier_state(&self) -> ModifiersState {
+        self.inner.mods_state
+    }
+
+    #[cfg(feature = "wayland_frontend")]
+    pub(crate) fn is_key_repeat_enabled(&self) -> bool {
+        self.inner.repeat.is_some()
+    }
+
     /// Convert a given keycode as a [`KeysymHandle`] modified by this keyboards state
     pub fn keysym_handle(&self, keycode: u32) -> KeysymHandle<'_> {
         KeysymHandle {
@@ -685,6 +1252,13 @@ impl<'a, D: SeatHandler + 'static> KeyboardInnerHandle<'a, D> {
         };
     }
 
+    /// Send the modifiers to the focused keyboards
+    pub fn modifiers(&mut self, data: &mut D, modifiers: ModifiersState, serial: Serial) {
+        if let Some((focus, _)) = self.inner.focus.as_mut() {
+            focus.modifiers(self.seat, data, modifiers, serial);
+        }
+    }
+
     /// Set the current focus of this keyboard
     ///
     /// If the new focus is different from the previous one, any previous focus
@@ -706,13 +1280,20 @@ impl<'a, D: SeatHandler + 'static> KeyboardInnerHandle<'a, D> {
             .unwrap_or(false);
 
         if !same {
+            // keys repeated for the old focus must not reach the new one
+            if let Some(repeat) = self.inner.repeat.as_mut() {
+                repeat.cancel();
+            }
+
             // unset old focus
+            self.inner.save_focus_layout();
             if let Some((focus, _)) = self.inner.focus.as_mut() {
                 focus.leave(self.seat, data, serial);
             };
 
             // set new focus
             self.inner.focus = focus.map(|f| (f, serial));
+            self.inner.restore_focus_layout();
             if let Some((focus, _)) = self.inner.focus.as_mut() {
                 let keys = self
                     .inner
@@ -777,3 +1358,244 @@ impl<D: SeatHandler + 'static> KeyboardGrab<D> for DefaultGrab {
         unreachable!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{
+        pointer::{AxisFrame, ButtonEvent, MotionEvent, PointerTarget},
+        SeatState,
+    };
+
+    // evdev keycode
+    const KEY_LEFTSHIFT: u32 = 42;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Target(u32);
+
+    impl IsAlive for Target {
+        fn alive(&self) -> bool {
+            true
+        }
+    }
+
+    impl PointerTarget<State> for Target {
+        fn enter(&self, _seat: &Seat<State>, _data: &mut State, _event: &MotionEvent) {}
+        fn motion(&self, _seat: &Seat<State>, _data: &mut State, _event: &MotionEvent) {}
+        fn button(&self, _seat: &Seat<State>, _data: &mut State, _event: &ButtonEvent) {}
+        fn axis(&self, _seat: &Seat<State>, _data: &mut State, _frame: AxisFrame) {}
+        fn leave(&self, _seat: &Seat<State>, _data: &mut State, _serial: Serial, _time: u32) {}
+    }
+
+    impl KeyboardTarget<State> for Target {
+        fn enter(
+            &self,
+            _seat: &Seat<State>,
+            _data: &mut State,
+            _keys: Vec<KeysymHandle<'_>>,
+            _serial: Serial,
+        ) {
+        }
+        fn leave(&self, _seat: &Seat<State>, _data: &mut State, _serial: Serial) {}
+        fn key(
+            &self,
+            _seat: &Seat<State>,
+            _data: &mut State,
+            _key: KeysymHandle<'_>,
+            _state: KeyState,
+            _serial: Serial,
+            _time: u32,
+        ) {
+        }
+        fn modifiers(
+            &self,
+            _seat: &Seat<State>,
+            data: &mut State,
+            modifiers: ModifiersState,
+            _serial: Serial,
+        ) {
+            data.modifiers
+                .push((Some(self.0), modifiers.serialized.layout_locked));
+        }
+    }
+
+    struct State {
+        seat_state: SeatState<State>,
+        // receiver of the modifiers, `None` for the grab, and their layout
+        modifiers: Vec<(Option<u32>, u32)>,
+    }
+
+    impl SeatHandler for State {
+        type KeyboardFocus = Target;
+        type PointerFocus = Target;
+
+        fn seat_state(&mut self) -> &mut SeatState<State> {
+            &mut self.seat_state
+        }
+    }
+
+    // grab keeping modifiers away from the focus
+    struct ModifiersGrab(GrabStartData<State>);
+
+    impl KeyboardGrab<State> for ModifiersGrab {
+        fn input(
+            &mut self,
+            _data: &mut State,
+            _handle: &mut KeyboardInnerHandle<'_, State>,
+            _keycode: u32,
+            _state: KeyState,
+            _modifiers: Option<ModifiersState>,
+            _serial: Serial,
+            _time: u32,
+        ) {
+        }
+
+        fn set_focus(
+            &mut self,
+            _data: &mut State,
+            _handle: &mut KeyboardInnerHandle<'_, State>,
+            _focus: Option<Target>,
+            _serial: Serial,
+        ) {
+        }
+
+        fn modifiers(
+            &mut self,
+            data: &mut State,
+            _handle: &mut KeyboardInnerHandle<'_, State>,
+            modifiers: ModifiersState,
+            _serial: Serial,
+        ) {
+            data.modifiers.push((None, modifiers.serialized.layout_locked));
+        }
+
+        fn start_data(&self) -> &GrabStartData<State> {
+            &self.0
+        }
+    }
+
+    fn layouts(layout: &str) -> XkbConfig<'_> {
+        XkbConfig {
+            layout,
+            ..XkbConfig::default()
+        }
+    }
+
+    // a focused keyboard with two layouts
+    fn setup() -> (State, KeyboardHandle<State>) {
+        let mut seat_state = SeatState::new();
+        let mut seat = seat_state.new_seat("seat-0", None);
+        let keyboard = seat.add_keyboard(layouts("us,de"), 200, 25).unwrap();
+        let mut state = State {
+            seat_state,
+            modifiers: Vec::new(),
+        };
+        keyboard.set_focus(&mut state, Some(Target(1)), Serial::from(0));
+        state.modifiers.clear();
+        (state, keyboard)
+    }
+
+    #[test]
+    fn cycle_layouts() {
+        let (mut state, keyboard) = setup();
+        assert_eq!(keyboard.layouts().len(), 2);
+        assert_eq!(keyboard.active_layout(), 0);
+
+        keyboard.cycle_layout_next(&mut state, Serial::from(1));
+        assert_eq!(keyboard.active_layout(), 1);
+        keyboard.cycle_layout_next(&mut state, Serial::from(2));
+        assert_eq!(keyboard.active_layout(), 0);
+        keyboard.cycle_layout_prev(&mut state, Serial::from(3));
+        assert_eq!(keyboard.active_layout(), 1);
+        keyboard.cycle_layout_prev(&mut state, Serial::from(4));
+        assert_eq!(keyboard.active_layout(), 0);
+
+        // the focus is notified about every change
+        assert_eq!(
+            state.modifiers,
+            vec![(Some(1), 1), (Some(1), 0), (Some(1), 1), (Some(1), 0)]
+        );
+    }
+
+    #[test]
+    fn invalid_layouts_are_ignored() {
+        let (mut state, keyboard) = setup();
+        keyboard.set_layout(&mut state, 2, Serial::from(1));
+        assert_eq!(keyboard.active_layout(), 0);
+        // as is setting the active layout again
+        keyboard.set_layout(&mut state, 0, Serial::from(2));
+        assert!(state.modifiers.is_empty());
+    }
+
+    #[test]
+    fn layout_per_focus() {
+        let (mut state, keyboard) = setup();
+        keyboard.set_layout_per_focus(true);
+        keyboard.set_layout(&mut state, 1, Serial::from(1));
+
+        // targets focused for the first time start with the first layout
+        keyboard.set_focus(&mut state, Some(Target(2)), Serial::from(2));
+        assert_eq!(keyboard.active_layout(), 0);
+        keyboard.set_focus(&mut state, Some(Target(1)), Serial::from(3));
+        assert_eq!(keyboard.active_layout(), 1);
+        keyboard.set_focus(&mut state, Some(Target(2)), Serial::from(4));
+        assert_eq!(keyboard.active_layout(), 0);
+
+        // the restored layout is part of the modifiers sent on enter
+        assert_eq!(state.modifiers.last(), Some(&(Some(2), 0)));
+        assert!(state.modifiers.contains(&(Some(1), 1)));
+
+        keyboard.set_layout_per_focus(false);
+        keyboard.set_layout(&mut state, 1, Serial::from(5));
+        keyboard.set_focus(&mut state, Some(Target(1)), Serial::from(6));
+        assert_eq!(keyboard.active_layout(), 1);
+    }
+
+    #[test]
+    fn new_keymap_keeps_pressed_keys() {
+        let (mut state, keyboard) = setup();
+        keyboard.set_layout_per_focus(true);
+        keyboard.set_layout(&mut state, 1, Serial::from(1));
+        keyboard.set_focus(&mut state, Some(Target(2)), Serial::from(2));
+        keyboard.set_layout(&mut state, 1, Serial::from(3));
+        keyboard.input::<(), _>(
+            &mut state,
+            KEY_LEFTSHIFT,
+            KeyState::Pressed,
+            Serial::from(4),
+            0,
+            |_, _, _| FilterResult::Forward,
+        );
+        state.modifiers.clear();
+
+        keyboard
+            .set_xkb_config(&mut state, layouts("de,us"), Serial::from(5))
+            .unwrap();
+        assert_eq!(keyboard.active_layout(), 0);
+        {
+            let guard = keyboard.arc.internal.lock().unwrap();
+            assert!(guard.pressed_keys.contains(&KEY_LEFTSHIFT));
+            assert!(guard.mods_state.shift);
+            assert!(guard.focus_layouts.as_ref().unwrap().is_empty());
+        }
+        assert_eq!(state.modifiers, vec![(Some(2), 0)]);
+
+        assert!(matches!(
+            keyboard.set_xkb_config(&mut state, layouts("invalid"), Serial::from(6)),
+            Err(Error::BadKeymap)
+        ));
+        assert_eq!(keyboard.layouts().len(), 2);
+    }
+
+    #[test]
+    fn layout_changes_go_through_grabs() {
+        let (mut state, keyboard) = setup();
+        keyboard.set_grab(ModifiersGrab(GrabStartData { focus: None }), Serial::from(1));
+        keyboard.set_layout(&mut state, 1, Serial::from(2));
+        assert_eq!(state.modifiers, vec![(None, 1)]);
+
+        keyboard.unset_grab();
+        keyboard.set_layout(&mut state, 0, Serial::from(3));
+        assert_eq!(state.modifiers, vec![(None, 1), (Some(1), 0)]);
+    }
+}
@@ -0,0 +1,122 @@
+use std::{fmt, time::Duration};
+
+/// State of the server-side key repeat of a keyboard
+pub(super) struct KeyRepeat {
+    // starts a timer for the given keycode, generation, time of the press and delay,
+    // has to be `Send` as it is owned by the keyboard
+    schedule: Box<dyn Fn(u32, u64, u32, Duration) + Send>,
+    generation: u64,
+    current: Option<(u32, u64)>,
+}
+
+impl fmt::Debug for KeyRepeat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyRepeat")
+            .field("generation", &self.generation)
+            .field("current", &self.current)
+            .finish()
+    }
+}
+
+impl KeyRepeat {
+    pub(super) fn new(schedule: Box<dyn Fn(u32, u64, u32, Duration) + Send>) -> KeyRepeat {
+        KeyRepeat {
+            schedule,
+            generation: 0,
+            current: None,
+        }
+    }
+
+    // start repeating a key, replacing the key repeated so far
+    pub(super) fn start(&mut self, keycode: u32, time: u32, delay: Duration) {
+        self.generation = self.generation.wrapping_add(1);
+        self.current = Some((keycode, self.generation));
+        (self.schedule)(keycode, self.generation, time, delay);
+    }
+
+    // stop repeating any key
+    pub(super) fn cancel(&mut self) {
+        self.current = None;
+    }
+
+    // stop repeating a key once it is released
+    pub(super) fn released(&mut self, keycode: u32) {
+        if self.current.map(|(current, _)| current) == Some(keycode) {
+            self.current = None;
+        }
+    }
+
+    // returns true if the key is currently repeated
+    pub(super) fn is_repeating(&self, keycode: u32) -> bool {
+        self.current.map(|(current, _)| current) == Some(keycode)
+    }
+
+    // returns true if the timer of the given press is still active
+    pub(super) fn is_current(&self, keycode: u32, generation: u64) -> bool {
+        self.current == Some((keycode, generation))
+    }
+}
+
+/// Interval between two repeats for a rate in repeats per second
+pub(super) fn repeat_interval(rate: i32) -> Option<Duration> {
+    (rate > 0).then(|| Duration::from_micros(1_000_000 / rate as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn key_repeat() -> (KeyRepeat, Arc<Mutex<Vec<(u32, u64, u32, Duration)>>>) {
+        let scheduled = Arc::new(Mutex::new(Vec::new()));
+        let timers = scheduled.clone();
+        let repeat = KeyRepeat::new(Box::new(move |keycode, generation, time, delay| {
+            timers.lock().unwrap().push((keycode, generation, time, delay));
+        }));
+        (repeat, scheduled)
+    }
+
+    #[test]
+    fn repeat_follows_last_pressed_key() {
+        let (mut repeat, scheduled) = key_repeat();
+        let delay = Duration::from_millis(200);
+
+        repeat.start(30, 0, delay);
+        let (keycode, first, time, scheduled_delay) = scheduled.lock().unwrap()[0];
+        assert_eq!((keycode, time, scheduled_delay), (30, 0, delay));
+        assert!(repeat.is_repeating(30));
+        assert!(repeat.is_current(30, first));
+
+        // a new press replaces the repeated key, the timer of the old one stops
+        repeat.start(31, 10, delay);
+        let (_, second, _, _) = scheduled.lock().unwrap()[1];
+        assert_ne!(first, second);
+        assert!(!repeat.is_repeating(30));
+        assert!(!repeat.is_current(30, first));
+        assert!(repeat.is_current(31, second));
+
+        // releasing another key keeps repeating
+        repeat.released(30);
+        assert!(repeat.is_current(31, second));
+        repeat.released(31);
+        assert!(!repeat.is_repeating(31));
+        assert!(!repeat.is_current(31, second));
+
+        // pressing the same key again starts a new timer
+        repeat.start(31, 20, delay);
+        let (_, third, _, _) = scheduled.lock().unwrap()[2];
+        assert!(!repeat.is_current(31, second));
+        assert!(repeat.is_current(31, third));
+        repeat.cancel();
+        assert!(!repeat.is_repeating(31));
+        assert!(!repeat.is_current(31, third));
+    }
+
+    #[test]
+    fn interval_from_rate() {
+        assert_eq!(repeat_interval(25), Some(Duration::from_millis(40)));
+        assert_eq!(repeat_interval(3), Some(Duration::from_micros(333_333)));
+        assert_eq!(repeat_interval(0), None);
+        assert_eq!(repeat_interval(-1), None);
+    }
+}
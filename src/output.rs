@@ -33,6 +33,7 @@
 //!         subpixel: Subpixel::HorizontalRgb,  // subpixel information
 //!         make: "Screens Inc".into(),     // make of the monitor
 //!         model: "Monitor Ultra".into(),  // model of the monitor
+//!         hdr: None,                      // hdr capabilities of the monitor
 //!     },
 //!     None // insert a logger here
 //! );
@@ -58,7 +59,7 @@ use std::{
 use slog::{info, o};
 
 #[cfg(feature = "wayland_frontend")]
-use crate::wayland::output::xdg::XdgOutput;
+use crate::wayland::output::{xdg::XdgOutput, BindHooks};
 #[cfg(feature = "wayland_frontend")]
 use wayland_server::{backend::WeakHandle, protocol::wl_output::WlOutput};
 
@@ -114,6 +115,29 @@ pub struct PhysicalProperties {
     pub make: String,
     /// Textual representation of the model
     pub model: String,
+    /// HDR capabilities of the monitor, `None` if HDR is not supported or unknown
+    pub hdr: Option<HdrCapabilities>,
+}
+
+/// HDR capabilities of a monitor, as advertised by its EDID
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HdrCapabilities {
+    /// The traditional gamma SDR transfer function is supported
+    pub traditional_sdr: bool,
+    /// The traditional gamma HDR transfer function is supported
+    pub traditional_hdr: bool,
+    /// The SMPTE ST 2084 (PQ) transfer function is supported
+    pub pq: bool,
+    /// The hybrid log-gamma (HLG) transfer function is supported
+    pub hlg: bool,
+    /// BT.2020 RGB colorimetry is supported
+    pub bt2020_rgb: bool,
+    /// Desired maximum content luminance in cd/m², if known
+    pub max_luminance: Option<f32>,
+    /// Desired maximum frame-average content luminance in cd/m², if known
+    pub max_frame_average_luminance: Option<f32>,
+    /// Desired minimum content luminance in cd/m², if known
+    pub min_luminance: Option<f32>,
 }
 
 /// Describes the scale advertised to clients.
@@ -177,6 +201,8 @@ pub(crate) struct Inner {
     pub(crate) handle: Option<WeakHandle>,
     #[cfg(feature = "wayland_frontend")]
     pub(crate) xdg_output: Option<XdgOutput>,
+    #[cfg(feature = "wayland_frontend")]
+    pub(crate) bind_hooks: BindHooks,
 
     #[allow(dead_code)]
     pub(crate) log: ::slog::Logger,
@@ -230,6 +256,8 @@ impl Output {
                 preferred_mode: None,
                 #[cfg(feature = "wayland_frontend")]
                 xdg_output: None,
+                #[cfg(feature = "wayland_frontend")]
+                bind_hooks: BindHooks::default(),
                 log,
             }),
             UserDataMap::default(),
@@ -0,0 +1,288 @@
+//! Time based animations
+//!
+//! An [`Animation`] interpolates a value between two states over a fixed [`Duration`].
+//! It does not drive itself, instead it is sampled for a given point in time, which makes it
+//! easy to drive animations from the presentation clock of an output.
+//!
+//! Values that can be animated implement [`Lerp`], which is provided for floats and the
+//! geometry types of smithay. The pacing of an animation is defined by its [`Curve`].
+//!
+//! ```
+//! use smithay::utils::{animation::{Animation, Curve}, Point, Logical};
+//! use std::time::{Duration, Instant};
+//!
+//! let start = Instant::now();
+//! let animation = Animation::new(
+//!     Point::<i32, Logical>::from((0, 0)),
+//!     Point::from((100, 0)),
+//!     start,
+//!     Duration::from_millis(200),
+//!     Curve::Linear,
+//! );
+//!
+//! assert_eq!(animation.value(start + Duration::from_millis(100)), Point::from((50, 0)));
+//! assert!(animation.is_done(start + Duration::from_millis(200)));
+//! ```
+
+use std::time::{Duration, Instant};
+
+use super::{Point, Rectangle, Scale, Size};
+
+/// Timing curve of an [`Animation`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Curve {
+    /// Constant speed
+    Linear,
+    /// Cubic ease-in, starting slow
+    EaseIn,
+    /// Cubic ease-out, ending slow
+    EaseOut,
+    /// Cubic ease-in-out, starting and ending slow
+    EaseInOut,
+    /// A css-like cubic bézier curve defined by its two control points `(x1, y1, x2, y2)`
+    ///
+    /// The x coordinates are clamped to `0.0..=1.0`, the y coordinates may exceed
+    /// this range to overshoot the target.
+    CubicBezier(f64, f64, f64, f64),
+}
+
+impl Default for Curve {
+    fn default() -> Self {
+        Curve::EaseOut
+    }
+}
+
+impl Curve {
+    /// Map the linear progress `t` in `0.0..=1.0` onto this curve
+    pub fn sample(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Curve::Linear => t,
+            Curve::EaseIn => t.powi(3),
+            Curve::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Curve::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t.powi(3)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Curve::CubicBezier(x1, y1, x2, y2) => {
+                let x1 = x1.clamp(0.0, 1.0);
+                let x2 = x2.clamp(0.0, 1.0);
+                let bezier = |a: f64, b: f64, s: f64| {
+                    3.0 * a * s * (1.0 - s).powi(2) + 3.0 * b * s.powi(2) * (1.0 - s) + s.powi(3)
+                };
+
+                // x(s) is monotonic for x1, x2 in 0..=1, so we can bisect for the parameter
+                let (mut low, mut high) = (0.0, 1.0);
+                for _ in 0..32 {
+                    let mid = (low + high) / 2.0;
+                    if bezier(x1, x2, mid) < t {
+                        low = mid;
+                    } else {
+                        high = mid;
+                    }
+                }
+                bezier(y1, y2, (low + high) / 2.0)
+            }
+        }
+    }
+}
+
+/// Types that can be linearly interpolated
+pub trait Lerp: Sized {
+    /// Interpolate between `self` and `other`
+    ///
+    /// `t` is `0.0` for `self` and `1.0` for `other`. Values outside of this
+    /// range extrapolate, which is used by overshooting curves.
+    fn lerp(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for f32 {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        (*self as f64).lerp(&(*other as f64), t) as f32
+    }
+}
+
+impl Lerp for i32 {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        (*self as f64).lerp(&(*other as f64), t).round() as i32
+    }
+}
+
+impl Lerp for Scale<f64> {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        Scale::from((self.x.lerp(&other.x, t), self.y.lerp(&other.y, t)))
+    }
+}
+
+macro_rules! geometry_lerp_impl {
+    ($($ty:ty),*) => {
+        $(
+            impl<Kind> Lerp for Point<$ty, Kind> {
+                fn lerp(&self, other: &Self, t: f64) -> Self {
+                    Point::from((self.x.lerp(&other.x, t), self.y.lerp(&other.y, t)))
+                }
+            }
+
+            impl<Kind> Lerp for Size<$ty, Kind> {
+                fn lerp(&self, other: &Self, t: f64) -> Self {
+                    // sizes can not become negative when extrapolating
+                    let w = self.w.lerp(&other.w, t);
+                    let h = self.h.lerp(&other.h, t);
+                    Size::from((w.max(<$ty>::default()), h.max(<$ty>::default())))
+                }
+            }
+
+            impl<Kind> Lerp for Rectangle<$ty, Kind> {
+                fn lerp(&self, other: &Self, t: f64) -> Self {
+                    Rectangle::from_loc_and_size(self.loc.lerp(&other.loc, t), self.size.lerp(&other.size, t))
+                }
+            }
+        )*
+    };
+}
+
+geometry_lerp_impl!(i32, f64);
+
+/// An animation of a value over time
+#[derive(Debug, Clone)]
+pub struct Animation<T> {
+    from: T,
+    to: T,
+    start: Instant,
+    duration: Duration,
+    curve: Curve,
+}
+
+impl<T: Lerp + Clone> Animation<T> {
+    /// Create a new animation from `from` to `to` starting at `start`
+    pub fn new(from: T, to: T, start: Instant, duration: Duration, curve: Curve) -> Self {
+        Animation {
+            from,
+            to,
+            start,
+            duration,
+            curve,
+        }
+    }
+
+    /// The value the animation started from
+    pub fn from(&self) -> &T {
+        &self.from
+    }
+
+    /// The value the animation ends at
+    pub fn to(&self) -> &T {
+        &self.to
+    }
+
+    /// The point in time the animation started
+    pub fn start(&self) -> Instant {
+        self.start
+    }
+
+    /// The duration of the animation
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// The curve of the animation
+    pub fn curve(&self) -> Curve {
+        self.curve
+    }
+
+    /// The linear progress of the animation at `now` in `0.0..=1.0`
+    pub fn progress(&self, now: Instant) -> f64 {
+        if self.duration == Duration::ZERO {
+            return 1.0;
+        }
+
+        let elapsed = now.saturating_duration_since(self.start);
+        (elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.0)
+    }
+
+    /// Returns `true` if the animation has finished at `now`
+    pub fn is_done(&self, now: Instant) -> bool {
+        self.progress(now) >= 1.0
+    }
+
+    /// The value of the animation at `now`
+    pub fn value(&self, now: Instant) -> T {
+        let progress = self.progress(now);
+        if progress >= 1.0 {
+            return self.to.clone();
+        }
+
+        self.from.lerp(&self.to, self.curve.sample(progress))
+    }
+
+    /// Change the target of the animation
+    ///
+    /// The animation restarts at `now` from its current value, which avoids
+    /// jumps when an animation is interrupted.
+    pub fn retarget(&mut self, to: T, now: Instant) {
+        self.from = self.value(now);
+        self.to = to;
+        self.start = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::Logical;
+
+    #[test]
+    fn curves_start_and_end() {
+        for curve in [
+            Curve::Linear,
+            Curve::EaseIn,
+            Curve::EaseOut,
+            Curve::EaseInOut,
+            Curve::CubicBezier(0.25, 0.1, 0.25, 1.0),
+        ] {
+            assert!(curve.sample(0.0).abs() < 1e-6, "{:?}", curve);
+            assert!((curve.sample(1.0) - 1.0).abs() < 1e-6, "{:?}", curve);
+        }
+        assert!((Curve::EaseInOut.sample(0.5) - 0.5).abs() < 1e-6);
+        assert!((Curve::CubicBezier(0.0, 0.0, 1.0, 1.0).sample(0.3) - 0.3).abs() < 1e-3);
+    }
+
+    #[test]
+    fn animation_value() {
+        let start = Instant::now();
+        let mut animation = Animation::new(
+            Rectangle::<i32, Logical>::from_loc_and_size((0, 0), (10, 10)),
+            Rectangle::from_loc_and_size((100, 50), (20, 10)),
+            start,
+            Duration::from_millis(100),
+            Curve::Linear,
+        );
+
+        assert_eq!(animation.value(start), *animation.from());
+        assert_eq!(
+            animation.value(start + Duration::from_millis(50)),
+            Rectangle::from_loc_and_size((50, 25), (15, 10))
+        );
+        assert!(!animation.is_done(start + Duration::from_millis(50)));
+        assert_eq!(animation.value(start + Duration::from_secs(1)), *animation.to());
+
+        animation.retarget(
+            Rectangle::from_loc_and_size((0, 0), (10, 10)),
+            start + Duration::from_millis(50),
+        );
+        assert_eq!(
+            *animation.from(),
+            Rectangle::from_loc_and_size((50, 25), (15, 10))
+        );
+        assert!(animation.is_done(start + Duration::from_millis(150)));
+    }
+}
@@ -1,5 +1,6 @@
 //! Various utilities functions and types
 
+pub mod animation;
 mod geometry;
 pub mod signaling;
 
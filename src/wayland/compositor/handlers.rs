@@ -76,13 +76,7 @@ where
             wl_compositor::Request::CreateSurface { id } => {
                 trace!(log, "Creating a new wl_surface.");
 
-                let surface = data_init.init(
-                    id,
-                    SurfaceUserData {
-                        inner: PrivateSurfaceData::new(),
-                        alive_tracker: Default::default(),
-                    },
-                );
+                let surface = data_init.init(id, SurfaceUserData::new());
                 PrivateSurfaceData::init(&surface);
             }
             wl_compositor::Request::CreateRegion { id } => {
@@ -149,6 +143,28 @@ pub struct SurfaceUserData {
     alive_tracker: AliveTracker,
 }
 
+impl SurfaceUserData {
+    fn new() -> SurfaceUserData {
+        SurfaceUserData {
+            inner: PrivateSurfaceData::new(),
+            alive_tracker: Default::default(),
+        }
+    }
+}
+
+/// Create a surface for `client` without a `wl_compositor` request, for tests
+#[cfg(test)]
+pub(crate) fn create_test_surface<D>(dh: &DisplayHandle, client: &wayland_server::Client) -> WlSurface
+where
+    D: Dispatch<WlSurface, SurfaceUserData> + 'static,
+{
+    let surface = client
+        .create_resource::<WlSurface, _, D>(dh, 4, SurfaceUserData::new())
+        .unwrap();
+    PrivateSurfaceData::init(&surface);
+    surface
+}
+
 impl<D> Dispatch<WlSurface, SurfaceUserData, D> for CompositorState
 where
     D: Dispatch<WlSurface, SurfaceUserData>,
@@ -106,10 +106,13 @@ mod transaction;
 mod tree;
 
 pub use self::cache::{Cacheable, MultiCache};
+#[cfg(test)]
+pub(crate) use self::handlers::create_test_surface;
 pub use self::handlers::{RegionUserData, SubsurfaceCachedState, SubsurfaceUserData, SurfaceUserData};
 use self::tree::PrivateSurfaceData;
 pub use self::tree::{AlreadyHasRole, TraversalAction};
 use crate::utils::{user_data::UserDataMap, Buffer, Logical, Point, Rectangle};
+use std::sync::atomic::{AtomicU32, Ordering};
 use wayland_server::backend::GlobalId;
 use wayland_server::protocol::wl_compositor::WlCompositor;
 use wayland_server::protocol::wl_subcompositor::WlSubcompositor;
@@ -387,6 +390,42 @@ where
     PrivateSurfaceData::with_states(surface, f)
 }
 
+/// Set a compositor-side scale for the buffers of this surface
+///
+/// Some clients, most notably XWayland, are not aware of output scales and always attach
+/// buffers with a `buffer_scale` of 1, while actually rendering for a scaled output.
+/// Setting a client scale of `N` multiplies the buffer scale of the surface by `N`
+/// and makes input events report surface-local coordinates multiplied by `N`, so the
+/// client effectively works in buffer coordinates while the compositor keeps using
+/// logical coordinates.
+///
+/// The new scale is applied starting with the next buffer attached to the surface.
+pub fn set_client_scale(surface: &WlSurface, scale: u32) {
+    with_states(surface, |states| {
+        let client_scale = states
+            .data_map
+            .insert_if_missing_threadsafe(|| ClientScale(AtomicU32::new(1)));
+        client_scale.0.store(scale.max(1), Ordering::SeqCst);
+    })
+}
+
+/// Retrieve the client scale of this surface
+///
+/// Returns 1 if no client scale was set, see [`set_client_scale`].
+pub fn get_client_scale(surface: &WlSurface) -> u32 {
+    with_states(surface, client_scale)
+}
+
+struct ClientScale(AtomicU32);
+
+pub(crate) fn client_scale(states: &SurfaceData) -> u32 {
+    states
+        .data_map
+        .get::<ClientScale>()
+        .map(|scale| scale.0.load(Ordering::SeqCst))
+        .unwrap_or(1)
+}
+
 /// Retrieve the metadata associated with a `wl_region`
 ///
 /// If the region is not managed by the `CompositorGlobal` that provided this token, this
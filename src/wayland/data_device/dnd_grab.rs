@@ -25,7 +25,7 @@ use crate::{
         Seat, SeatHandler,
     },
     utils::{IsAlive, Logical, Point},
-    wayland::seat::WaylandFocus,
+    wayland::{compositor::get_client_scale, seat::WaylandFocus},
 };
 
 use super::{seat_data::SeatData, with_source_metadata, ClientDndGrabHandler, DataDeviceHandler};
@@ -115,7 +115,9 @@ where
                 Ok(c) => c,
                 Err(_) => return,
             };
-            let (x, y) = (event.location - surface_location.to_f64()).into();
+            let (x, y) = (event.location - surface_location.to_f64())
+                .upscale(get_client_scale(surface) as f64)
+                .into();
             if self.current_focus.is_none() {
                 // We entered a new surface, send the data offer if appropriate
                 if let Some(ref source) = self.data_source {
@@ -186,18 +188,22 @@ where
     fn button(&mut self, data: &mut D, handle: &mut PointerInnerHandle<'_, D>, event: &ButtonEvent) {
         if handle.current_pressed().is_empty() {
             // the user dropped, proceed to the drop
-            let seat_data = self
-                .seat
-                .user_data()
-                .get::<RefCell<SeatData>>()
-                .unwrap()
-                .borrow_mut();
             let validated = if let Some(ref data) = self.offer_data {
                 let data = data.lock().unwrap();
                 data.accepted && (!data.chosen_action.is_empty())
             } else {
                 false
             };
+            // no wayland client took the drop, the compositor may forward it elsewhere
+            let external = !validated
+                && self.data_source.is_some()
+                && ClientDndGrabHandler::external_drop(data, self.seat.clone());
+            let seat_data = self
+                .seat
+                .user_data()
+                .get::<RefCell<SeatData>>()
+                .unwrap()
+                .borrow_mut();
             if let Some(ref surface) = self.current_focus {
                 if self.data_source.is_some() || self.origin.id().same_client_as(&surface.id()) {
                     for device in seat_data.known_devices() {
@@ -217,7 +223,7 @@ where
             }
             if let Some(ref source) = self.data_source {
                 source.dnd_drop_performed();
-                if !validated {
+                if !validated && !external {
                     source.cancelled();
                 }
             }
@@ -16,6 +16,8 @@
 //!
 //! - the freestanding function [`set_data_device_selection`]
 //!   allows you to set the contents of the selection for your clients
+//! - the freestanding functions [`request_data_device_client_selection`] and
+//!   [`clear_data_device_selection`] allow you to read and clear the selection set by a client
 //! - the freestanding function [`start_dnd`] allows you to initiate a drag'n'drop event from the compositor
 //!   itself and receive interactions of clients with it via an other dedicated callback.
 //!
@@ -64,7 +66,7 @@
 //! // You're now ready to go!
 //! ```
 
-use std::cell::RefCell;
+use std::{cell::RefCell, os::unix::io::AsRawFd};
 
 use io_lifetimes::OwnedFd;
 use wayland_server::{
@@ -82,7 +84,7 @@ use crate::{
         pointer::{Focus, GrabStartData as PointerGrabStartData},
         Seat, SeatHandler,
     },
-    utils::Serial,
+    utils::{IsAlive, Serial},
     wayland::seat::WaylandFocus,
 };
 
@@ -93,6 +95,7 @@ mod server_dnd_grab;
 mod source;
 
 pub use device::{DataDeviceUserData, DND_ICON_ROLE};
+pub(crate) use server_dnd_grab::ServerDnd;
 pub use source::{with_source_metadata, DataSourceUserData, SourceMetadata};
 
 use seat_data::{SeatData, Selection};
@@ -139,6 +142,19 @@ pub trait ClientDndGrabHandler: SeatHandler + Sized {
     ///
     /// * `seat` - The seat on which the DnD action was finished.
     fn dropped(&mut self, seat: Seat<Self>) {}
+
+    /// The drag'n'drop action was dropped outside of any wayland client accepting it
+    ///
+    /// This allows the compositor to forward the drop to a target it manages itself,
+    /// like an X11 window. Return `true` if the drop was taken, in which case the data source is
+    /// not cancelled and the compositor becomes responsible for finishing it through
+    /// [`WlDataSource::dnd_finished`] or [`WlDataSource::cancelled`].
+    /// This is called before [`ClientDndGrabHandler::dropped`].
+    ///
+    /// * `seat` - The seat on which the DnD action was finished.
+    fn external_drop(&mut self, seat: Seat<Self>) -> bool {
+        false
+    }
 }
 
 /// Event generated by the interactions of clients with a server initiated drag'n'drop
@@ -252,6 +268,68 @@ where
     );
 }
 
+/// Errors happening when requesting selection contents
+#[derive(Debug, thiserror::Error)]
+pub enum SelectionRequestError {
+    /// Requested mime type is not available
+    #[error("Requested mime type is not available")]
+    InvalidMimetype,
+    /// Requesting server side selection contents is not supported
+    #[error("Current selection is server-side")]
+    ServerSideSelection,
+    /// There is no active selection
+    #[error("No active selection to query")]
+    NoSelection,
+}
+
+/// Request the current data device selection of the given seat
+/// to be written to the provided file descriptor in the given mime type.
+///
+/// This only works for selections set by clients. Selections set by the compositor through
+/// [`set_data_device_selection`] are already known to the compositor and return
+/// [`SelectionRequestError::ServerSideSelection`].
+pub fn request_data_device_client_selection<D>(
+    seat: &Seat<D>,
+    mime_type: String,
+    fd: OwnedFd,
+) -> Result<(), SelectionRequestError>
+where
+    D: SeatHandler + DataDeviceHandler + 'static,
+{
+    seat.user_data()
+        .insert_if_missing(|| RefCell::new(SeatData::new()));
+    let seat_data = seat.user_data().get::<RefCell<SeatData>>().unwrap();
+    match seat_data.borrow().get_selection() {
+        Selection::Empty => Err(SelectionRequestError::NoSelection),
+        Selection::Compositor(_) => Err(SelectionRequestError::ServerSideSelection),
+        Selection::Client(source) => {
+            if !source.alive() {
+                return Err(SelectionRequestError::NoSelection);
+            }
+            let valid =
+                with_source_metadata(source, |meta| meta.mime_types.contains(&mime_type)).unwrap_or(false);
+            if !valid {
+                return Err(SelectionRequestError::InvalidMimetype);
+            }
+            source.send(mime_type, fd.as_raw_fd());
+            Ok(())
+        }
+    }
+}
+
+/// Clear the current data device selection of the given seat
+///
+/// A selection of a client is cancelled and clients are notified about the empty selection.
+pub fn clear_data_device_selection<D>(dh: &DisplayHandle, seat: &Seat<D>)
+where
+    D: SeatHandler + DataDeviceHandler + 'static,
+{
+    seat.user_data()
+        .insert_if_missing(|| RefCell::new(SeatData::new()));
+    let seat_data = seat.user_data().get::<RefCell<SeatData>>().unwrap();
+    seat_data.borrow_mut().set_selection::<D>(dh, Selection::Empty);
+}
+
 /// Start a drag'n'drop from a resource controlled by the compositor
 ///
 /// You'll receive events generated by the interaction of clients with your
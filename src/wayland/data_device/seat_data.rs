@@ -58,6 +58,10 @@ impl SeatData {
         self.known_devices.retain(f)
     }
 
+    pub fn get_selection(&self) -> &Selection {
+        &self.selection
+    }
+
     pub fn set_selection<D>(&mut self, dh: &DisplayHandle, new_selection: Selection)
     where
         D: DataDeviceHandler,
@@ -21,88 +21,55 @@ use crate::input::{
     },
     Seat, SeatHandler,
 };
-use crate::utils::{Logical, Point};
+use crate::utils::{Logical, Point, Serial};
 use crate::wayland::seat::WaylandFocus;
 
 use super::{DataDeviceHandler, SeatData, ServerDndGrabHandler, SourceMetadata};
 
-pub(crate) struct ServerDnDGrab<D: SeatHandler> {
-    dh: DisplayHandle,
-    start_data: PointerGrabStartData<D>,
-    metadata: super::SourceMetadata,
+/// Drag'n'drop of a resource controlled by the compositor over the surfaces of wayland clients
+///
+/// This is independent of any pointer grab, so it can also be driven by drag'n'drop operations
+/// the pointer of the seat is not grabbed for, like those of X11 clients.
+#[derive(Debug)]
+pub(crate) struct ServerDnd {
+    metadata: SourceMetadata,
     current_focus: Option<WlSurface>,
     pending_offers: Vec<wl_data_offer::WlDataOffer>,
     offer_data: Option<Arc<Mutex<ServerDndOfferData>>>,
-    seat: Seat<D>,
 }
 
-impl<D: SeatHandler> ServerDnDGrab<D> {
-    pub(crate) fn new(
-        dh: &DisplayHandle,
-        start_data: PointerGrabStartData<D>,
-        metadata: super::SourceMetadata,
-        seat: Seat<D>,
-    ) -> Self {
+impl ServerDnd {
+    pub(crate) fn new(metadata: SourceMetadata) -> Self {
         Self {
-            dh: dh.clone(),
-            start_data,
             metadata,
             current_focus: None,
             pending_offers: Vec::with_capacity(1),
             offer_data: None,
-            seat,
         }
     }
-}
 
-impl<D> PointerGrab<D> for ServerDnDGrab<D>
-where
-    D: DataDeviceHandler,
-    D: SeatHandler,
-    <D as SeatHandler>::PointerFocus: WaylandFocus,
-    D: 'static,
-{
-    fn motion(
+    /// Move the operation over `focus`, the surface under the pointer and its location
+    pub(crate) fn motion<D>(
         &mut self,
-        data: &mut D,
-        handle: &mut PointerInnerHandle<'_, D>,
-        focus: Option<(<D as SeatHandler>::PointerFocus, Point<i32, Logical>)>,
-        event: &MotionEvent,
-    ) {
-        let location = event.location;
-        let serial = event.serial;
-        let time = event.time;
-
-        // While the grab is active, no client has pointer focus
-        handle.motion(data, None, event);
-
-        let seat_data = self
-            .seat
-            .user_data()
-            .get::<RefCell<SeatData>>()
-            .unwrap()
-            .borrow_mut();
-        if focus.as_ref().and_then(|&(ref s, _)| s.wl_surface()) != self.current_focus.as_ref() {
+        dh: &DisplayHandle,
+        seat: &Seat<D>,
+        focus: Option<(&WlSurface, Point<i32, Logical>)>,
+        location: Point<f64, Logical>,
+        serial: Serial,
+        time: u32,
+    ) where
+        D: SeatHandler + DataDeviceHandler + 'static,
+    {
+        seat.user_data()
+            .insert_if_missing(|| RefCell::new(SeatData::new()));
+        let seat_data = seat.user_data().get::<RefCell<SeatData>>().unwrap().borrow_mut();
+        if focus.map(|(s, _)| s) != self.current_focus.as_ref() {
             // focus changed, we need to make a leave if appropriate
-            if let Some(surface) = self.current_focus.take() {
-                for device in seat_data.known_devices() {
-                    if device.id().same_client_as(&surface.id()) {
-                        device.leave();
-                    }
-                }
-                // disable the offers
-                self.pending_offers.clear();
-                if let Some(offer_data) = self.offer_data.take() {
-                    offer_data.lock().unwrap().active = false;
-                }
-            }
+            self.leave_focus(&seat_data);
         }
-        if let Some((surface, surface_location)) = focus
-            .as_ref()
-            .and_then(|(h, loc)| h.wl_surface().map(|s| (s, loc)))
-        {
+        if let Some((surface, surface_location)) = focus {
             // early return if the surface is no longer valid
-            let client = match self.dh.get_client(surface.id()) {
+            let client = match dh.get_client(surface.id()) {
                 Ok(c) => c,
                 _ => return,
             };
@@ -120,7 +87,7 @@ where
                     .iter()
                     .filter(|d| d.id().same_client_as(&surface.id()))
                 {
-                    let handle = self.dh.backend_handle();
+                    let handle = dh.backend_handle();
                     // create a data offer
                     let offer = handle
                         .create_object::<D>(
@@ -133,7 +100,7 @@ where
                             }),
                         )
                         .unwrap();
-                    let offer = WlDataOffer::from_id(&self.dh, offer).unwrap();
+                    let offer = WlDataOffer::from_id(dh, offer).unwrap();
 
                     // advertize the offer to the client
                     device.data_offer(&offer);
@@ -157,54 +124,145 @@ where
         }
     }
 
-    fn button(&mut self, data: &mut D, handle: &mut PointerInnerHandle<'_, D>, event: &ButtonEvent) {
-        let serial = event.serial;
-        let time = event.time;
-
-        if handle.current_pressed().is_empty() {
-            // the user dropped, proceed to the drop
-            let seat_data = self
-                .seat
-                .user_data()
-                .get::<RefCell<SeatData>>()
-                .unwrap()
-                .borrow_mut();
-            let validated = if let Some(ref data) = self.offer_data {
+    /// The action chosen by the client under the pointer, empty if it does not accept the drop
+    pub(crate) fn action(&self) -> DndAction {
+        match self.offer_data {
+            Some(ref data) => {
                 let data = data.lock().unwrap();
-                data.accepted && (!data.chosen_action.is_empty())
+                if data.accepted {
+                    data.chosen_action
+                } else {
+                    DndAction::empty()
+                }
+            }
+            None => DndAction::empty(),
+        }
+    }
+
+    /// Drop the resource onto the surface under the pointer
+    ///
+    /// Returns `true` if the client accepted the drop, it can then still interact with the offer.
+    pub(crate) fn drop<D: SeatHandler>(&mut self, seat: &Seat<D>) -> bool {
+        let validated = !self.action().is_empty();
+        let seat_data = match seat.user_data().get::<RefCell<SeatData>>() {
+            Some(seat_data) => seat_data.borrow_mut(),
+            None => return false,
+        };
+        if let Some(ref surface) = self.current_focus {
+            for device in seat_data.known_devices() {
+                if device.id().same_client_as(&surface.id()) && validated {
+                    device.drop();
+                }
+            }
+        }
+        if let Some(ref offer_data) = self.offer_data {
+            let mut data = offer_data.lock().unwrap();
+            if validated {
+                data.dropped = true;
             } else {
-                false
-            };
-            if let Some(ref surface) = self.current_focus {
-                for device in seat_data.known_devices() {
-                    if device.id().same_client_as(&surface.id()) && validated {
-                        device.drop();
-                    }
+                data.active = false;
+            }
+        }
+
+        // in all cases abandon the drop
+        if let Some(surface) = self.current_focus.take() {
+            for device in seat_data.known_devices() {
+                if device.id().same_client_as(&surface.id()) {
+                    device.leave();
                 }
             }
-            if let Some(ref offer_data) = self.offer_data {
-                let mut data = offer_data.lock().unwrap();
-                if validated {
-                    data.dropped = true;
-                } else {
-                    data.active = false;
+        }
+        validated
+    }
+
+    /// Leave the surface under the pointer, disabling its offer
+    ///
+    /// Moving over a surface again creates a new offer.
+    pub(crate) fn leave<D: SeatHandler>(&mut self, seat: &Seat<D>) {
+        if let Some(seat_data) = seat.user_data().get::<RefCell<SeatData>>() {
+            self.leave_focus(&seat_data.borrow());
+        }
+    }
+
+    fn leave_focus(&mut self, seat_data: &SeatData) {
+        if let Some(surface) = self.current_focus.take() {
+            for device in seat_data.known_devices() {
+                if device.id().same_client_as(&surface.id()) {
+                    device.leave();
                 }
             }
+            // disable the offers
+            self.pending_offers.clear();
+            if let Some(offer_data) = self.offer_data.take() {
+                offer_data.lock().unwrap().active = false;
+            }
+        }
+    }
+}
+
+pub(crate) struct ServerDnDGrab<D: SeatHandler> {
+    dh: DisplayHandle,
+    start_data: PointerGrabStartData<D>,
+    dnd: ServerDnd,
+    seat: Seat<D>,
+}
+
+impl<D: SeatHandler> ServerDnDGrab<D> {
+    pub(crate) fn new(
+        dh: &DisplayHandle,
+        start_data: PointerGrabStartData<D>,
+        metadata: super::SourceMetadata,
+        seat: Seat<D>,
+    ) -> Self {
+        Self {
+            dh: dh.clone(),
+            start_data,
+            dnd: ServerDnd::new(metadata),
+            seat,
+        }
+    }
+}
+
+impl<D> PointerGrab<D> for ServerDnDGrab<D>
+where
+    D: DataDeviceHandler,
+    D: SeatHandler,
+    <D as SeatHandler>::PointerFocus: WaylandFocus,
+    D: 'static,
+{
+    fn motion(
+        &mut self,
+        data: &mut D,
+        handle: &mut PointerInnerHandle<'_, D>,
+        focus: Option<(<D as SeatHandler>::PointerFocus, Point<i32, Logical>)>,
+        event: &MotionEvent,
+    ) {
+        // While the grab is active, no client has pointer focus
+        handle.motion(data, None, event);
 
+        let surface = focus
+            .as_ref()
+            .and_then(|(h, loc)| h.wl_surface().map(|s| (s, *loc)));
+        self.dnd.motion(
+            &self.dh,
+            &self.seat,
+            surface,
+            event.location,
+            event.serial,
+            event.time,
+        );
+    }
+
+    fn button(&mut self, data: &mut D, handle: &mut PointerInnerHandle<'_, D>, event: &ButtonEvent) {
+        if handle.current_pressed().is_empty() {
+            // the user dropped, proceed to the drop
+            let validated = self.dnd.drop(&self.seat);
             ServerDndGrabHandler::dropped(data);
             if !validated {
                 data.cancelled();
             }
-            // in all cases abandon the drop
             // no more buttons are pressed, release the grab
-            if let Some(ref surface) = self.current_focus {
-                for device in seat_data.known_devices() {
-                    if device.id().same_client_as(&surface.id()) {
-                        device.leave();
-                    }
-                }
-            }
-            handle.unset_grab(data, serial, time);
+            handle.unset_grab(data, event.serial, event.time);
         }
     }
 
@@ -0,0 +1,396 @@
+//! Utilities for handling the `wlr-gamma-control` protocol
+//!
+//! This protocol allows privileged clients (like night-light tools such as `gammastep` or `wlsunset`)
+//! to set the gamma tables of outputs.
+//!
+//! Only one client may control the gamma tables of an output at a time. Once the client destroys its
+//! gamma control or disconnects, the original gamma tables of the output should be restored.
+//! Smithay takes care of the bookkeeping and asks your [`GammaControlHandler`] to apply or restore
+//! the gamma tables, e.g. using [`DrmSurface::set_gamma`](crate::backend::drm::DrmSurface::set_gamma)
+//! and [`DrmSurface::reset_gamma`](crate::backend::drm::DrmSurface::reset_gamma).
+//!
+//! ### Example
+//!
+//! ```no_run
+//! # extern crate wayland_server;
+//! #
+//! use smithay::{
+//!     delegate_gamma_control,
+//!     output::Output,
+//!     wayland::gamma_control::{GammaControlHandler, GammaControlManagerState},
+//! };
+//!
+//! pub struct State {
+//!     gamma_control_state: GammaControlManagerState,
+//! }
+//!
+//! impl GammaControlHandler for State {
+//!     fn gamma_control_state(&mut self) -> &mut GammaControlManagerState {
+//!         &mut self.gamma_control_state
+//!     }
+//!
+//!     fn get_gamma_size(&mut self, output: &Output) -> Option<u32> {
+//!         // Query the size of the gamma ramps of your backend, e.g. `DrmSurface::gamma_size`
+//!         # unimplemented!()
+//!     }
+//!
+//!     fn set_gamma(&mut self, output: &Output, ramps: Option<[&[u16]; 3]>) -> bool {
+//!         // Apply the provided ramps (or restore the original ones, if `None`)
+//!         // and return if that was successful.
+//!         # unimplemented!()
+//!     }
+//! }
+//!
+//! delegate_gamma_control!(State);
+//!
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! # let display_handle = display.handle();
+//! let state = State {
+//!     // Only allow privileged clients to bind the global
+//!     gamma_control_state: GammaControlManagerState::new_with_filter::<State, _>(
+//!         &display_handle,
+//!         |client| true,
+//!     ),
+//! };
+//! ```
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    os::unix::fs::FileExt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use wayland_protocols_wlr::gamma_control::v1::server::{
+    zwlr_gamma_control_manager_v1::{self, ZwlrGammaControlManagerV1},
+    zwlr_gamma_control_v1::{self, ZwlrGammaControlV1},
+};
+use wayland_server::{
+    backend::{ClientId, GlobalId, ObjectId},
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use crate::output::{Output, WeakOutput};
+
+/// State of the wlr gamma control protocol
+#[derive(Debug)]
+pub struct GammaControlManagerState {
+    global: GlobalId,
+    controls: HashMap<WeakOutput, ZwlrGammaControlV1>,
+}
+
+impl GammaControlManagerState {
+    /// Creates a new [`ZwlrGammaControlManagerV1`] global visible to all clients.
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData>
+            + Dispatch<ZwlrGammaControlManagerV1, ()>
+            + Dispatch<ZwlrGammaControlV1, GammaControlData>
+            + GammaControlHandler
+            + 'static,
+    {
+        Self::new_with_filter::<D, _>(display, |_| true)
+    }
+
+    /// Creates a new [`ZwlrGammaControlManagerV1`] global.
+    ///
+    /// The `filter` decides, which clients may see and bind the global.
+    /// As this protocol allows to alter the appearance of the whole screen,
+    /// it is recommended to only expose it to trusted clients.
+    pub fn new_with_filter<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData>
+            + Dispatch<ZwlrGammaControlManagerV1, ()>
+            + Dispatch<ZwlrGammaControlV1, GammaControlData>
+            + GammaControlHandler
+            + 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let global = display.create_global::<D, ZwlrGammaControlManagerV1, _>(
+            1,
+            GammaControlManagerGlobalData {
+                filter: Box::new(filter),
+            },
+        );
+
+        GammaControlManagerState {
+            global,
+            controls: HashMap::new(),
+        }
+    }
+
+    /// Returns the [`ZwlrGammaControlManagerV1`] global id
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Returns if a client currently controls the gamma tables of the given output
+    pub fn is_controlled(&self, output: &Output) -> bool {
+        self.controls.contains_key(&output.downgrade())
+    }
+
+    /// Invalidates the gamma control of the given output, if any.
+    ///
+    /// The client is notified, that it lost control over the gamma tables of the output.
+    /// [`GammaControlHandler::set_gamma`] is **not** called, you are expected to restore
+    /// the original gamma tables yourself, if the output still exists.
+    ///
+    /// You should call this when an output is removed or the gamma tables
+    /// are taken over by something else.
+    pub fn output_removed(&mut self, output: &Output) {
+        if let Some(control) = self.controls.remove(&output.downgrade()) {
+            if let Some(data) = control.data::<GammaControlData>() {
+                data.failed.store(true, Ordering::SeqCst);
+            }
+            control.failed();
+        }
+    }
+}
+
+/// Handler trait for the wlr gamma control protocol
+pub trait GammaControlHandler {
+    /// [`GammaControlManagerState`] getter
+    fn gamma_control_state(&mut self) -> &mut GammaControlManagerState;
+
+    /// Returns the number of entries of the gamma ramps of the given output.
+    ///
+    /// Return `None` (or `Some(0)`), if the output does not support gamma tables.
+    fn get_gamma_size(&mut self, output: &Output) -> Option<u32>;
+
+    /// Sets new gamma ramps for the given output.
+    ///
+    /// `ramps` contains the red, green and blue ramps, each of the size returned by
+    /// [`GammaControlHandler::get_gamma_size`]. If `ramps` is `None`, the original gamma tables
+    /// of the output should be restored, because the controlling client went away.
+    ///
+    /// Returns if the gamma tables were successfully applied. Returning `false` will
+    /// invalidate the gamma control of the client.
+    fn set_gamma(&mut self, output: &Output, ramps: Option<[&[u16]; 3]>) -> bool;
+}
+
+/// Data associated with the [`ZwlrGammaControlManagerV1`] global
+#[allow(missing_debug_implementations)]
+pub struct GammaControlManagerGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+/// User data of a [`ZwlrGammaControlV1`] object
+#[derive(Debug)]
+pub struct GammaControlData {
+    output: Option<WeakOutput>,
+    gamma_size: u32,
+    failed: AtomicBool,
+}
+
+impl<D> GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData, D>
+    for GammaControlManagerState
+where
+    D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData>
+        + Dispatch<ZwlrGammaControlManagerV1, ()>
+        + Dispatch<ZwlrGammaControlV1, GammaControlData>
+        + GammaControlHandler
+        + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrGammaControlManagerV1>,
+        _global_data: &GammaControlManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, global_data: &GammaControlManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrGammaControlManagerV1, (), D> for GammaControlManagerState
+where
+    D: Dispatch<ZwlrGammaControlManagerV1, ()>
+        + Dispatch<ZwlrGammaControlV1, GammaControlData>
+        + GammaControlHandler
+        + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ZwlrGammaControlManagerV1,
+        request: zwlr_gamma_control_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_gamma_control_manager_v1::Request::GetGammaControl { id, output } => {
+                let output = Output::from_resource(&output);
+                let gamma_size = output
+                    .as_ref()
+                    // there can only be one gamma control per output
+                    .filter(|output| !state.gamma_control_state().is_controlled(output))
+                    .and_then(|output| state.get_gamma_size(output))
+                    .unwrap_or(0);
+
+                let failed = gamma_size == 0;
+                let control = data_init.init(
+                    id,
+                    GammaControlData {
+                        output: output.as_ref().map(Output::downgrade),
+                        gamma_size,
+                        failed: AtomicBool::new(failed),
+                    },
+                );
+
+                if failed {
+                    control.failed();
+                } else {
+                    control.gamma_size(gamma_size);
+                    state
+                        .gamma_control_state()
+                        .controls
+                        .insert(output.unwrap().downgrade(), control);
+                }
+            }
+            zwlr_gamma_control_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrGammaControlV1, GammaControlData, D> for GammaControlManagerState
+where
+    D: Dispatch<ZwlrGammaControlV1, GammaControlData> + GammaControlHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZwlrGammaControlV1,
+        request: zwlr_gamma_control_v1::Request,
+        data: &GammaControlData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_gamma_control_v1::Request::SetGamma { fd } => {
+                if data.failed.load(Ordering::SeqCst) {
+                    return;
+                }
+                let output = match data.output.as_ref().and_then(WeakOutput::upgrade) {
+                    Some(output) => output,
+                    None => return,
+                };
+
+                let size = data.gamma_size as usize;
+                let table = match read_gamma_table(&File::from(fd), size) {
+                    Ok(table) => table,
+                    Err(_) => {
+                        resource.post_error(
+                            zwlr_gamma_control_v1::Error::InvalidGamma,
+                            "Failed to read gamma tables",
+                        );
+                        return;
+                    }
+                };
+                let (red, rest) = table.split_at(size);
+                let (green, blue) = rest.split_at(size);
+
+                if !state.set_gamma(&output, Some([red, green, blue])) {
+                    data.failed.store(true, Ordering::SeqCst);
+                    state.gamma_control_state().controls.remove(&output.downgrade());
+                    state.set_gamma(&output, None);
+                    resource.failed();
+                }
+            }
+            zwlr_gamma_control_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, _resource: ObjectId, data: &GammaControlData) {
+        if data.failed.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        if let Some(output) = data.output.as_ref() {
+            state.gamma_control_state().controls.remove(output);
+            if let Some(output) = output.upgrade() {
+                state.set_gamma(&output, None);
+            }
+        }
+    }
+}
+
+// Reads the red, green and blue ramps of the given size, the table consists of three ramps of u16 values
+fn read_gamma_table(file: &File, size: usize) -> std::io::Result<Vec<u16>> {
+    let mut table = vec![0u8; size * 3 * std::mem::size_of::<u16>()];
+    file.read_exact_at(&mut table, 0)?;
+    Ok(table
+        .chunks_exact(2)
+        .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]))
+        .collect())
+}
+
+/// Macro to delegate implementation of the wlr gamma control protocol to [`GammaControlManagerState`].
+///
+/// You must also implement [`GammaControlHandler`] to use this.
+#[macro_export]
+macro_rules! delegate_gamma_control {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1: $crate::wayland::gamma_control::GammaControlManagerGlobalData
+        ] => $crate::wayland::gamma_control::GammaControlManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1: ()
+        ] => $crate::wayland::gamma_control::GammaControlManagerState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_v1::ZwlrGammaControlV1: $crate::wayland::gamma_control::GammaControlData
+        ] => $crate::wayland::gamma_control::GammaControlManagerState);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn table_file(values: &[u16]) -> File {
+        let mut file = tempfile::tempfile().unwrap();
+        for value in values {
+            file.write_all(&value.to_ne_bytes()).unwrap();
+        }
+        file
+    }
+
+    #[test]
+    fn gamma_table_is_split_into_ramps() {
+        let values = [0, 1, 2, 100, 101, 102, 0xfffd, 0xfffe, 0xffff];
+        let file = table_file(&values);
+
+        let table = read_gamma_table(&file, 3).unwrap();
+        assert_eq!(table, values);
+        let (red, rest) = table.split_at(3);
+        let (green, blue) = rest.split_at(3);
+        assert_eq!(red, [0, 1, 2]);
+        assert_eq!(green, [100, 101, 102]);
+        assert_eq!(blue, [0xfffd, 0xfffe, 0xffff]);
+    }
+
+    #[test]
+    fn gamma_table_ignores_trailing_data() {
+        let values = [1, 2, 3, 4];
+        let file = table_file(&values);
+
+        assert_eq!(read_gamma_table(&file, 1).unwrap(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn short_gamma_table_is_rejected() {
+        // only two of the three ramps
+        let file = table_file(&[1, 2, 3, 4]);
+
+        assert!(read_gamma_table(&file, 2).is_err());
+    }
+}
@@ -78,6 +78,20 @@ impl InputMethodHandle {
         keyboard_inner.keymap_file = Some(KeymapFile::new(keymap, log));
     }
 
+    /// Replace the keymap of the keyboard grab and send it to an active grab
+    pub(crate) fn keymap_changed(&self, keymap: &CString) {
+        let inner = self.inner.lock().unwrap();
+        let mut keyboard = inner.keyboard_grab.inner.lock().unwrap();
+        let log = crate::slog_or_fallback(None);
+        let keymap_file = KeymapFile::new(keymap.clone(), log);
+        if let Some(grab) = keyboard.grab.as_ref() {
+            let _ = keymap_file.with_fd(false, |fd, size| {
+                grab.keymap(KeymapFormat::XkbV1, fd, size as u32);
+            });
+        }
+        keyboard.keymap_file = Some(keymap_file);
+    }
+
     /// Callback function to access the input method object
     pub fn with_instance<F>(&self, mut f: F)
     where
@@ -218,7 +232,8 @@ where
                 keyboard.grab = Some(instance.clone());
                 keyboard.text_input_handle = data.text_input_handle.clone();
                 keyboard.popup_handle = input_method.popup.clone();
-                instance.repeat_info(keyboard.repeat_rate, keyboard.repeat_delay);
+                keyboard.sent_repeat_rate = None;
+                keyboard.update_repeat_info(data.keyboard_handle.is_key_repeat_enabled());
                 keyboard
                     .keymap_file
                     .as_ref()
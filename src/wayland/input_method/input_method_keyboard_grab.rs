@@ -30,6 +30,23 @@ pub(crate) struct InputMethodKeyboard {
     pub keymap_file: Option<KeymapFile>,
     pub text_input_handle: TextInputHandle,
     pub popup_handle: InputMethodPopupSurfaceHandle,
+    // repeat rate last sent to the input method
+    pub sent_repeat_rate: Option<i32>,
+}
+
+impl InputMethodKeyboard {
+    // repeated keys are sent by the compositor with server-side key repeat,
+    // the input method must not repeat them on its own in that case
+    pub(crate) fn update_repeat_info(&mut self, server_repeat: bool) {
+        let repeat_rate = if server_repeat { 0 } else { self.repeat_rate };
+        if self.sent_repeat_rate == Some(repeat_rate) {
+            return;
+        }
+        if let Some(grab) = self.grab.as_ref() {
+            grab.repeat_info(repeat_rate, self.repeat_delay);
+            self.sent_repeat_rate = Some(repeat_rate);
+        }
+    }
 }
 
 /// Handle to an input method instance
@@ -46,14 +63,18 @@ where
     fn input(
         &mut self,
         _data: &mut D,
-        _handle: &mut KeyboardInnerHandle<'_, D>,
+        handle: &mut KeyboardInnerHandle<'_, D>,
         keycode: u32,
         key_state: KeyState,
         modifiers: Option<ModifiersState>,
         _serial: crate::utils::Serial,
         time: u32,
     ) {
-        let inner = self.inner.lock().unwrap();
+        let mut inner = self.inner.lock().unwrap();
+        if key_state == KeyState::Pressed {
+            // key repeat might have been toggled since the keyboard was grabbed
+            inner.update_repeat_info(handle.is_key_repeat_enabled());
+        }
         let keyboard = inner.grab.as_ref().unwrap();
         inner.text_input_handle.with_focused_text_input(|_, _, serial| {
             if let Some(serialized) = modifiers.map(|m| m.serialized) {
@@ -69,6 +90,53 @@ where
         });
     }
 
+    fn repeat(
+        &mut self,
+        _data: &mut D,
+        handle: &mut KeyboardInnerHandle<'_, D>,
+        keycode: u32,
+        _serial: crate::utils::Serial,
+        time: u32,
+    ) {
+        // the input method does not repeat keys on its own with server-side key repeat,
+        // a repeat is sent as a release and a new press of the key
+        let modifiers = handle.modifier_state().serialized;
+        let inner = self.inner.lock().unwrap();
+        let keyboard = inner.grab.as_ref().unwrap();
+        inner.text_input_handle.with_focused_text_input(|_, _, serial| {
+            keyboard.modifiers(
+                *serial,
+                modifiers.depressed,
+                modifiers.latched,
+                modifiers.locked,
+                modifiers.layout_locked,
+            );
+            keyboard.key(*serial, time, keycode, KeyState::Released.into());
+            keyboard.key(*serial, time, keycode, KeyState::Pressed.into());
+        });
+    }
+
+    fn modifiers(
+        &mut self,
+        _data: &mut D,
+        _handle: &mut KeyboardInnerHandle<'_, D>,
+        modifiers: ModifiersState,
+        _serial: crate::utils::Serial,
+    ) {
+        let inner = self.inner.lock().unwrap();
+        let keyboard = inner.grab.as_ref().unwrap();
+        let serialized = modifiers.serialized;
+        inner.text_input_handle.with_focused_text_input(|_, _, serial| {
+            keyboard.modifiers(
+                *serial,
+                serialized.depressed,
+                serialized.latched,
+                serialized.locked,
+                serialized.layout_locked,
+            );
+        });
+    }
+
     fn set_focus(
         &mut self,
         data: &mut D,
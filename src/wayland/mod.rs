@@ -49,6 +49,7 @@ pub mod buffer;
 pub mod compositor;
 pub mod data_device;
 pub mod dmabuf;
+pub mod gamma_control;
 pub mod input_method;
 pub mod keyboard_shortcuts_inhibit;
 pub mod output;
@@ -60,4 +61,6 @@ pub mod socket;
 pub mod tablet_manager;
 pub mod text_input;
 pub mod viewporter;
+#[cfg(feature = "wayland_ext_workspace")]
+pub mod workspace;
 pub mod xdg_activation;
@@ -72,7 +72,13 @@ where
             output.done();
         }
 
-        inner.instances.push(output);
+        inner.instances.push(output.clone());
+
+        let hooks = inner.bind_hooks.alive();
+        std::mem::drop(inner);
+        for hook in hooks {
+            hook(&output);
+        }
     }
 }
 
@@ -137,7 +143,7 @@ where
 {
     fn request(
         _state: &mut D,
-        _client: &Client,
+        client: &Client,
         _resource: &ZxdgOutputManagerV1,
         request: zxdg_output_manager_v1::Request,
         _data: &(),
@@ -160,7 +166,11 @@ where
 
                 let id = data_init.init(id, XdgOutputUserData { xdg_output });
 
-                inner.xdg_output.as_ref().unwrap().add_instance(&id, &wl_output);
+                inner
+                    .xdg_output
+                    .as_ref()
+                    .unwrap()
+                    .add_instance(&id, &wl_output, client.id());
             }
             zxdg_output_manager_v1::Request::Destroy => {}
             _ => {}
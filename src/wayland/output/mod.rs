@@ -36,6 +36,7 @@
 //!         subpixel: Subpixel::HorizontalRgb,  // subpixel information
 //!         make: "Screens Inc".into(),     // make of the monitor
 //!         model: "Monitor Ultra".into(),  // model of the monitor
+//!         hdr: None,                      // hdr capabilities of the monitor
 //!     },
 //!     None // insert a logger here
 //! );
@@ -61,6 +62,7 @@
 
 mod handlers;
 pub(crate) mod xdg;
+use self::xdg::XdgOutput;
 use crate::output::{Inner, Mode, Output, OutputData, Scale, Subpixel};
 
 use wayland_protocols::xdg::xdg_output::zv1::server::zxdg_output_manager_v1::ZxdgOutputManagerV1;
@@ -73,6 +75,11 @@ use wayland_server::{
     Client, DisplayHandle, GlobalDispatch, Resource,
 };
 
+use std::{
+    fmt,
+    sync::{Arc, Weak},
+};
+
 use crate::utils::{Logical, Point};
 
 pub use self::handlers::XdgOutputUserData;
@@ -117,6 +124,27 @@ impl OutputManagerState {
     }
 }
 
+/// Callback invoked, when a client binds a [`WlOutput`] of an [`Output`]
+pub(crate) type OutputBindHook = dyn Fn(&WlOutput) + Send + Sync;
+
+// hooks registered through `Output::add_bind_hook`, dropped hooks are skipped
+#[derive(Default)]
+pub(crate) struct BindHooks(Vec<Weak<OutputBindHook>>);
+
+impl fmt::Debug for BindHooks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BindHooks").field(&self.0.len()).finish()
+    }
+}
+
+impl BindHooks {
+    // returns the hooks still alive
+    fn alive(&mut self) -> Vec<Arc<OutputBindHook>> {
+        self.0.retain(|hook| hook.strong_count() > 0);
+        self.0.iter().filter_map(Weak::upgrade).collect()
+    }
+}
+
 /// User data for WlOutput
 #[derive(Debug, Clone)]
 pub struct OutputUserData {
@@ -174,6 +202,19 @@ impl Output {
         )
     }
 
+    /// Calls `hook` for every [`WlOutput`] of this output bound by a client from now on
+    ///
+    /// The hook is unregistered once the passed `Arc` is dropped.
+    pub(crate) fn add_bind_hook(&self, hook: &Arc<OutputBindHook>) {
+        self.inner
+            .0
+            .lock()
+            .unwrap()
+            .bind_hooks
+            .0
+            .push(Arc::downgrade(hook));
+    }
+
     /// Attempt to retrieve a [`Output`] from an existing resource
     pub fn from_resource(output: &WlOutput) -> Option<Output> {
         output.data::<OutputUserData>().map(|ud| Output {
@@ -218,6 +259,35 @@ impl Output {
         }
     }
 
+    /// Scale the logical position and size advertised through xdg-output to the given client
+    ///
+    /// This is meant for clients unaware of output scales, most notably XWayland,
+    /// that should lay out their contents in a coordinate space `scale` times larger than
+    /// the logical one. See also [`set_client_scale`](crate::wayland::compositor::set_client_scale).
+    pub fn set_xdg_output_client_scale(&self, client: &Client, scale: u32) {
+        let mut inner = self.inner.0.lock().unwrap();
+        if inner.xdg_output.is_none() {
+            let xdg_output = XdgOutput::new(&inner, inner.log.clone());
+            inner.xdg_output = Some(xdg_output);
+        }
+
+        let changed = inner
+            .xdg_output
+            .as_ref()
+            .unwrap()
+            .set_client_scale(client.id(), scale.max(1) as i32);
+        if !changed {
+            return;
+        }
+
+        std::mem::drop(inner);
+        self.with_client_outputs(client, |output| {
+            if output.version() >= 2 {
+                output.done();
+            }
+        });
+    }
+
     /// Check is given [`wl_output`](WlOutput) instance is managed by this [`Output`].
     pub fn owns(&self, output: &WlOutput) -> bool {
         self.inner
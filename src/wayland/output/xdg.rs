@@ -3,11 +3,14 @@
 //! This protocol is meant for describing outputs in a way
 //! which is more in line with the concept of an output on desktop oriented systems.
 
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 use slog::{o, trace};
 use wayland_protocols::xdg::xdg_output::zv1::server::zxdg_output_v1::ZxdgOutputV1;
-use wayland_server::{protocol::wl_output::WlOutput, Resource};
+use wayland_server::{backend::ClientId, protocol::wl_output::WlOutput, Resource};
 
 use crate::utils::{Logical, Physical, Point, Size};
 
@@ -21,11 +24,31 @@ pub(crate) struct Inner {
 
     pub(super) physical_size: Option<Size<i32, Physical>>,
     pub(super) scale: Scale,
+    client_scales: HashMap<ClientId, i32>,
 
-    pub instances: Vec<ZxdgOutputV1>,
+    pub instances: Vec<(ZxdgOutputV1, ClientId)>,
     _log: ::slog::Logger,
 }
 
+impl Inner {
+    fn client_scale(&self, client: &ClientId) -> i32 {
+        self.client_scales.get(client).copied().unwrap_or(1)
+    }
+
+    fn logical_size(&self, client: &ClientId) -> Option<Size<i32, Logical>> {
+        self.physical_size.map(|size| {
+            size.to_f64()
+                .to_logical(self.scale.fractional_scale())
+                .to_i32_round()
+                .upscale(self.client_scale(client))
+        })
+    }
+
+    fn logical_position(&self, client: &ClientId) -> Point<i32, Logical> {
+        self.logical_position.upscale(self.client_scale(client))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct XdgOutput {
     pub(crate) inner: Arc<Mutex<Inner>>,
@@ -47,6 +70,7 @@ impl XdgOutput {
 
                 physical_size,
                 scale: output.scale,
+                client_scales: HashMap::new(),
 
                 instances: Vec::new(),
                 _log: log,
@@ -54,16 +78,13 @@ impl XdgOutput {
         }
     }
 
-    pub(super) fn add_instance(&self, xdg_output: &ZxdgOutputV1, wl_output: &WlOutput) {
+    pub(super) fn add_instance(&self, xdg_output: &ZxdgOutputV1, wl_output: &WlOutput, client: ClientId) {
         let mut inner = self.inner.lock().unwrap();
 
-        xdg_output.logical_position(inner.logical_position.x, inner.logical_position.y);
+        let logical_position = inner.logical_position(&client);
+        xdg_output.logical_position(logical_position.x, logical_position.y);
 
-        if let Some(size) = inner.physical_size {
-            let logical_size = size
-                .to_f64()
-                .to_logical(inner.scale.fractional_scale())
-                .to_i32_round();
+        if let Some(logical_size) = inner.logical_size(&client) {
             xdg_output.logical_size(logical_size.w, logical_size.h);
         }
 
@@ -79,7 +100,32 @@ impl XdgOutput {
 
         wl_output.done();
 
-        inner.instances.push(xdg_output.clone());
+        inner.instances.push((xdg_output.clone(), client));
+    }
+
+    /// Scales the logical coordinates advertised to `client`, returns `true` if the scale changed
+    pub(super) fn set_client_scale(&self, client: ClientId, scale: i32) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.client_scale(&client) == scale {
+            return false;
+        }
+        inner.client_scales.insert(client.clone(), scale);
+
+        for (instance, _) in inner.instances.iter().filter(|(_, c)| *c == client) {
+            if let Some(logical_size) = inner.logical_size(&client) {
+                instance.logical_size(logical_size.w, logical_size.h);
+            }
+            let logical_position = inner.logical_position(&client);
+            instance.logical_position(logical_position.x, logical_position.y);
+
+            // xdg_output.done() is deprecated since version 3
+            if instance.version() < 3 {
+                instance.done();
+            }
+        }
+
+        true
     }
 
     pub(super) fn change_current_state(
@@ -100,19 +146,16 @@ impl XdgOutput {
             output.logical_position = new_location;
         }
 
-        for instance in output.instances.iter() {
+        for (instance, client) in output.instances.iter() {
             if new_mode.is_some() | new_scale.is_some() {
-                if let Some(size) = output.physical_size {
-                    let logical_size = size
-                        .to_f64()
-                        .to_logical(output.scale.fractional_scale())
-                        .to_i32_round();
+                if let Some(logical_size) = output.logical_size(client) {
                     instance.logical_size(logical_size.w, logical_size.h);
                 }
             }
 
             if new_location.is_some() {
-                instance.logical_position(output.logical_position.x, output.logical_position.y);
+                let logical_position = output.logical_position(client);
+                instance.logical_position(logical_position.x, logical_position.y);
             }
 
             // xdg_output.done() is deprecated since version 3
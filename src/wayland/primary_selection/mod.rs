@@ -15,6 +15,8 @@
 //!
 //! - the freestanding function [`set_primary_selection`]
 //!   allows you to set the contents of the selection for your clients
+//! - the freestanding functions [`request_primary_client_selection`] and [`clear_primary_selection`]
+//!   allow you to read and clear the selection set by a client
 //! - the `PrimarySelectionHandle` gives you the option to inspect new selections
 //!   by overriding [`PrimarySelectionHandler::new_selection].
 //!
@@ -59,7 +61,7 @@
 //! // You're now ready to go!
 //! ```
 
-use std::cell::RefCell;
+use std::{cell::RefCell, os::unix::io::AsRawFd};
 
 use io_lifetimes::OwnedFd;
 use wayland_protocols::wp::primary_selection::zv1::server::{
@@ -68,7 +70,10 @@ use wayland_protocols::wp::primary_selection::zv1::server::{
 };
 use wayland_server::{backend::GlobalId, Client, DisplayHandle, GlobalDispatch};
 
-use crate::input::{Seat, SeatHandler};
+use crate::{
+    input::{Seat, SeatHandler},
+    utils::IsAlive,
+};
 
 mod device;
 mod seat_data;
@@ -77,6 +82,8 @@ mod source;
 pub use device::PrimaryDeviceUserData;
 pub use source::{with_source_metadata, PrimarySourceUserData, SourceMetadata};
 
+pub use super::data_device::SelectionRequestError;
+
 use seat_data::{SeatData, Selection};
 
 /// Events that are generated by interactions of the clients with the data device
@@ -153,6 +160,53 @@ where
         .set_selection::<D>(dh, Selection::Compositor(SourceMetadata { mime_types }));
 }
 
+/// Request the current primary selection of the given seat
+/// to be written to the provided file descriptor in the given mime type.
+///
+/// This only works for selections set by clients. Selections set by the compositor through
+/// [`set_primary_selection`] return [`SelectionRequestError::ServerSideSelection`].
+pub fn request_primary_client_selection<D>(
+    seat: &Seat<D>,
+    mime_type: String,
+    fd: OwnedFd,
+) -> Result<(), SelectionRequestError>
+where
+    D: SeatHandler + PrimarySelectionHandler + 'static,
+{
+    seat.user_data()
+        .insert_if_missing(|| RefCell::new(SeatData::new()));
+    let seat_data = seat.user_data().get::<RefCell<SeatData>>().unwrap();
+    match seat_data.borrow().get_selection() {
+        Selection::Empty => Err(SelectionRequestError::NoSelection),
+        Selection::Compositor(_) => Err(SelectionRequestError::ServerSideSelection),
+        Selection::Client(source) => {
+            if !source.alive() {
+                return Err(SelectionRequestError::NoSelection);
+            }
+            let valid =
+                with_source_metadata(source, |meta| meta.mime_types.contains(&mime_type)).unwrap_or(false);
+            if !valid {
+                return Err(SelectionRequestError::InvalidMimetype);
+            }
+            source.send(mime_type, fd.as_raw_fd());
+            Ok(())
+        }
+    }
+}
+
+/// Clear the current primary selection of the given seat
+///
+/// A selection of a client is cancelled and clients are notified about the empty selection.
+pub fn clear_primary_selection<D>(dh: &DisplayHandle, seat: &Seat<D>)
+where
+    D: SeatHandler + PrimarySelectionHandler + 'static,
+{
+    seat.user_data()
+        .insert_if_missing(|| RefCell::new(SeatData::new()));
+    let seat_data = seat.user_data().get::<RefCell<SeatData>>().unwrap();
+    seat_data.borrow_mut().set_selection::<D>(dh, Selection::Empty);
+}
+
 mod handlers {
     use std::cell::RefCell;
 
@@ -63,6 +63,10 @@ impl SeatData {
         self.send_selection::<D>(dh);
     }
 
+    pub fn get_selection(&self) -> &Selection {
+        &self.selection
+    }
+
     pub fn set_selection<D>(&mut self, dh: &DisplayHandle, new_selection: Selection)
     where
         D: PrimarySelectionHandler,
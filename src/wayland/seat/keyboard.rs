@@ -42,20 +42,9 @@ where
     ///
     /// This should be done first, before anything else is done with this keyboard.
     pub(crate) fn new_kbd(&self, kbd: WlKeyboard) {
-        trace!(self.arc.logger, "Sending keymap to client");
-
-        // prepare a tempfile with the keymap, to send it to the client
-        let ret = self.arc.keymap.with_fd(kbd.version() >= 7, |fd, size| {
-            kbd.keymap(KeymapFormat::XkbV1, fd, size as u32);
-        });
-
-        if let Err(e) = ret {
-            warn!(self.arc.logger,
-                "Failed write keymap to client in a tempfile";
-                "err" => format!("{:?}", e)
-            );
+        if self.send_keymap(&kbd).is_err() {
             return;
-        };
+        }
 
         let guard = self.arc.internal.lock().unwrap();
         if kbd.version() >= 4 {
@@ -80,6 +69,31 @@ where
     }
 }
 
+impl<D: SeatHandler + 'static> KeyboardHandle<D> {
+    /// Send the current keymap to a keyboard
+    pub(crate) fn send_keymap(&self, kbd: &WlKeyboard) -> Result<(), std::io::Error> {
+        trace!(self.arc.logger, "Sending keymap to client");
+
+        // prepare a tempfile with the keymap, to send it to the client
+        let ret = self
+            .arc
+            .keymap
+            .lock()
+            .unwrap()
+            .with_fd(kbd.version() >= 7, |fd, size| {
+                kbd.keymap(KeymapFormat::XkbV1, fd, size as u32);
+            });
+
+        if let Err(ref e) = ret {
+            warn!(self.arc.logger,
+                "Failed write keymap to client in a tempfile";
+                "err" => format!("{:?}", e)
+            );
+        };
+        ret
+    }
+}
+
 /// User data for keyboard
 pub struct KeyboardUserData<D: SeatHandler> {
     pub(crate) handle: Option<KeyboardHandle<D>>,
@@ -52,14 +52,23 @@ fn for_each_focused_pointers<D: SeatHandler + 'static>(
     }
 }
 
+/// Surface-local coordinates of the event as expected by the client,
+/// taking the client scale of the surface into account
+#[cfg(feature = "wayland_frontend")]
+fn surface_local_location(surface: &WlSurface, event: &MotionEvent) -> (f64, f64) {
+    let scale = compositor::get_client_scale(surface) as f64;
+    (event.location.x * scale, event.location.y * scale)
+}
+
 #[cfg(feature = "wayland_frontend")]
 impl<D> PointerTarget<D> for WlSurface
 where
     D: SeatHandler + 'static,
 {
     fn enter(&self, seat: &Seat<D>, _data: &mut D, event: &MotionEvent) {
+        let (x, y) = surface_local_location(self, event);
         for_each_focused_pointers(seat, self, |ptr| {
-            ptr.enter(event.serial.into(), self, event.location.x, event.location.y);
+            ptr.enter(event.serial.into(), self, x, y);
             if ptr.version() >= 5 {
                 ptr.frame();
             }
@@ -74,8 +83,9 @@ where
         })
     }
     fn motion(&self, seat: &Seat<D>, _data: &mut D, event: &MotionEvent) {
+        let (x, y) = surface_local_location(self, event);
         for_each_focused_pointers(seat, self, |ptr| {
-            ptr.motion(event.time, event.location.x, event.location.y);
+            ptr.motion(event.time, x, y);
             if ptr.version() >= 5 {
                 ptr.frame();
             }
@@ -13,7 +13,7 @@ use super::{SeatHandler, SeatState};
 use crate::backend::input::TouchSlot;
 use crate::utils::Serial;
 use crate::utils::{Logical, Point};
-use crate::wayland::seat::wl_surface::WlSurface;
+use crate::wayland::{compositor::get_client_scale, seat::wl_surface::WlSurface};
 
 /// An handle to a touch handler.
 ///
@@ -89,6 +89,7 @@ impl TouchHandle {
 #[derive(Default, Debug)]
 struct TouchFocus {
     surface_offset: Point<f64, Logical>,
+    client_scale: f64,
     handles: Vec<WlTouch>,
 }
 
@@ -111,6 +112,7 @@ impl TouchInternal {
         // Update focused client state.
         let focus = self.focus.entry(slot).or_default();
         focus.surface_offset = surface_offset.to_f64();
+        focus.client_scale = get_client_scale(surface) as f64;
         focus.handles.clear();
 
         // Select all WlTouch instances associated to the active WlSurface.
@@ -120,7 +122,9 @@ impl TouchInternal {
             }
         }
 
-        let (x, y) = (location - focus.surface_offset).into();
+        let (x, y) = (location - focus.surface_offset)
+            .upscale(focus.client_scale)
+            .into();
         self.with_focused_handles(slot, |handle| {
             handle.down(serial.into(), time, surface, slot.into(), x, y)
         });
@@ -136,7 +140,9 @@ impl TouchInternal {
             None => return,
         };
 
-        let (x, y) = (location - focus.surface_offset).into();
+        let (x, y) = (location - focus.surface_offset)
+            .upscale(focus.client_scale)
+            .into();
         self.with_focused_handles(slot, |handle| handle.motion(time, slot.into(), x, y));
     }
 
@@ -111,15 +111,14 @@ where
                     },
                 );
 
-                state
-                    .xdg_shell_state()
-                    .inner
-                    .lock()
-                    .unwrap()
-                    .known_toplevels
-                    .push(make_toplevel_handle(&toplevel));
+                let capabilities = {
+                    let mut inner = state.xdg_shell_state().inner.lock().unwrap();
+                    inner.known_toplevels.push(make_toplevel_handle(&toplevel));
+                    inner.capabilities.clone()
+                };
 
                 let handle = make_toplevel_handle(&toplevel);
+                handle.with_pending_state(|state| state.capabilities = capabilities);
 
                 XdgShellHandler::new_toplevel(state, handle);
             }
@@ -198,7 +198,11 @@ where
     })
 }
 
-pub fn send_toplevel_configure(resource: &xdg_toplevel::XdgToplevel, configure: ToplevelConfigure) {
+pub fn send_toplevel_configure(
+    resource: &xdg_toplevel::XdgToplevel,
+    configure: ToplevelConfigure,
+    capabilities_changed: bool,
+) {
     let data = resource.data::<XdgShellSurfaceUserData>().unwrap();
     let (width, height) = configure.state.size.unwrap_or_default().into();
     // convert the Vec<State> (which is really a Vec<u32>) into Vec<u8>
@@ -213,6 +217,22 @@ pub fn send_toplevel_configure(resource: &xdg_toplevel::XdgToplevel, configure:
     };
     let serial = configure.serial;
 
+    if let Some(bounds) = configure.state.bounds {
+        if resource.version() >= xdg_toplevel::EVT_CONFIGURE_BOUNDS_SINCE {
+            resource.configure_bounds(bounds.w, bounds.h);
+        }
+    }
+
+    if capabilities_changed && resource.version() >= xdg_toplevel::EVT_WM_CAPABILITIES_SINCE {
+        let capabilities = configure
+            .state
+            .capabilities
+            .iter()
+            .flat_map(|capability| (capability as u32).to_ne_bytes())
+            .collect::<Vec<u8>>();
+        resource.wm_capabilities(capabilities);
+    }
+
     // Send the toplevel configure
     resource.configure(width, height, states);
 
@@ -127,6 +127,9 @@ pub const XDG_POPUP_ROLE: &str = "xdg_popup";
 /// Constant for toplevel state version checking
 const XDG_TOPLEVEL_STATE_TILED_SINCE: u32 = 2;
 
+/// Version of the advertised `xdg_wm_base` global
+const XDG_WM_BASE_VERSION: u32 = 5;
+
 macro_rules! xdg_role {
     ($state:ty,
      $(#[$configure_meta:meta])* $configure_name:ident $({$($(#[$configure_field_meta:meta])* $configure_field_vis:vis$configure_field_name:ident:$configure_field_type:ty),*}),*,
@@ -560,8 +563,8 @@ impl PositionerState {
     /// The position is calculated according to the rules defined
     /// in the `xdg_shell` protocol.
     /// The `constraint_adjustment` will not be considered by this
-    /// implementation and the position and size should be re-calculated
-    /// in the compositor if the compositor implements `constraint_adjustment`
+    /// implementation, use [`PositionerState::get_unconstrained_geometry`]
+    /// to also apply the constraint adjustments.
     pub fn get_geometry(&self) -> Rectangle<i32, Logical> {
         // From the `xdg_shell` prococol specification:
         //
@@ -619,6 +622,204 @@ impl PositionerState {
 
         geometry
     }
+
+    /// Get the geometry for a popup as defined by this positioner, adjusted
+    /// to fit into the given target area according to the `constraint_adjustment`.
+    ///
+    /// The target is expected in the same coordinate space as the returned geometry,
+    /// relative to the parent surface `window_geometry`. It usually is the area of the
+    /// output the parent is on, not occupied by exclusive zones (see `PopupManager::popup_target_area`
+    /// in the `desktop` module for a helper to calculate it).
+    ///
+    /// The adjustments are applied in the order mandated by the `xdg_shell` protocol,
+    /// first flipping, then sliding and at last resizing on each axis.
+    /// If the popup still does not fit, the remaining constraints are ignored.
+    pub fn get_unconstrained_geometry(&self, target: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+        let mut geometry = self.get_geometry();
+
+        let (constrained_x, constrained_y) = constrained_axes(geometry, target);
+        if !constrained_x && !constrained_y {
+            return geometry;
+        }
+
+        // If the adjusted position also ends up being constrained, the resulting position of the
+        // flip adjustment will be the one before the adjustment.
+        if constrained_x
+            && self
+                .constraint_adjustment
+                .contains(xdg_positioner::ConstraintAdjustment::FlipX)
+        {
+            let flipped = PositionerState {
+                anchor_edges: flip_anchor_x(self.anchor_edges),
+                gravity: flip_gravity_x(self.gravity),
+                ..*self
+            }
+            .get_geometry();
+            if !constrained_axes(flipped, target).0 {
+                geometry.loc.x = flipped.loc.x;
+            }
+        }
+        if constrained_y
+            && self
+                .constraint_adjustment
+                .contains(xdg_positioner::ConstraintAdjustment::FlipY)
+        {
+            let flipped = PositionerState {
+                anchor_edges: flip_anchor_y(self.anchor_edges),
+                gravity: flip_gravity_y(self.gravity),
+                ..*self
+            }
+            .get_geometry();
+            if !constrained_axes(flipped, target).1 {
+                geometry.loc.y = flipped.loc.y;
+            }
+        }
+
+        // First slide towards the direction of the gravity until the opposite edge is unconstrained
+        // or the edge in the direction of the gravity becomes constrained, then slide the other way.
+        let (constrained_x, constrained_y) = constrained_axes(geometry, target);
+        if constrained_x
+            && self
+                .constraint_adjustment
+                .contains(xdg_positioner::ConstraintAdjustment::SlideX)
+        {
+            geometry.loc.x = slide(
+                geometry.loc.x,
+                geometry.size.w,
+                target.loc.x,
+                target.size.w,
+                self.gravity_has_edge(xdg_positioner::Gravity::Left),
+            );
+        }
+        if constrained_y
+            && self
+                .constraint_adjustment
+                .contains(xdg_positioner::ConstraintAdjustment::SlideY)
+        {
+            geometry.loc.y = slide(
+                geometry.loc.y,
+                geometry.size.h,
+                target.loc.y,
+                target.size.h,
+                self.gravity_has_edge(xdg_positioner::Gravity::Top),
+            );
+        }
+
+        // Shrink the popup to the part still inside of the target, if anything is left
+        let (constrained_x, constrained_y) = constrained_axes(geometry, target);
+        if constrained_x
+            && self
+                .constraint_adjustment
+                .contains(xdg_positioner::ConstraintAdjustment::ResizeX)
+        {
+            let x = geometry.loc.x.max(target.loc.x);
+            let right = (geometry.loc.x + geometry.size.w).min(target.loc.x + target.size.w);
+            if right > x {
+                geometry.loc.x = x;
+                geometry.size.w = right - x;
+            }
+        }
+        if constrained_y
+            && self
+                .constraint_adjustment
+                .contains(xdg_positioner::ConstraintAdjustment::ResizeY)
+        {
+            let y = geometry.loc.y.max(target.loc.y);
+            let bottom = (geometry.loc.y + geometry.size.h).min(target.loc.y + target.size.h);
+            if bottom > y {
+                geometry.loc.y = y;
+                geometry.size.h = bottom - y;
+            }
+        }
+
+        geometry
+    }
+}
+
+// Returns whether the geometry exceeds the target horizontally and vertically
+fn constrained_axes(geometry: Rectangle<i32, Logical>, target: Rectangle<i32, Logical>) -> (bool, bool) {
+    (
+        geometry.loc.x < target.loc.x || geometry.loc.x + geometry.size.w > target.loc.x + target.size.w,
+        geometry.loc.y < target.loc.y || geometry.loc.y + geometry.size.h > target.loc.y + target.size.h,
+    )
+}
+
+// Slides a span along one axis, `towards_start` being true, if the gravity points to the start of the axis
+fn slide(pos: i32, len: i32, target_pos: i32, target_len: i32, towards_start: bool) -> i32 {
+    let target_end = target_pos + target_len;
+    let mut pos = pos;
+    if towards_start {
+        let overflow_end = pos + len - target_end;
+        if overflow_end > 0 {
+            pos -= overflow_end.min((pos - target_pos).max(0));
+        }
+        let overflow_start = target_pos - pos;
+        if overflow_start > 0 {
+            pos += overflow_start.min((target_end - (pos + len)).max(0));
+        }
+    } else {
+        let overflow_start = target_pos - pos;
+        if overflow_start > 0 {
+            pos += overflow_start.min((target_end - (pos + len)).max(0));
+        }
+        let overflow_end = pos + len - target_end;
+        if overflow_end > 0 {
+            pos -= overflow_end.min((pos - target_pos).max(0));
+        }
+    }
+    pos
+}
+
+fn flip_anchor_x(anchor: xdg_positioner::Anchor) -> xdg_positioner::Anchor {
+    use xdg_positioner::Anchor;
+    match anchor {
+        Anchor::Left => Anchor::Right,
+        Anchor::Right => Anchor::Left,
+        Anchor::TopLeft => Anchor::TopRight,
+        Anchor::TopRight => Anchor::TopLeft,
+        Anchor::BottomLeft => Anchor::BottomRight,
+        Anchor::BottomRight => Anchor::BottomLeft,
+        anchor => anchor,
+    }
+}
+
+fn flip_anchor_y(anchor: xdg_positioner::Anchor) -> xdg_positioner::Anchor {
+    use xdg_positioner::Anchor;
+    match anchor {
+        Anchor::Top => Anchor::Bottom,
+        Anchor::Bottom => Anchor::Top,
+        Anchor::TopLeft => Anchor::BottomLeft,
+        Anchor::BottomLeft => Anchor::TopLeft,
+        Anchor::TopRight => Anchor::BottomRight,
+        Anchor::BottomRight => Anchor::TopRight,
+        anchor => anchor,
+    }
+}
+
+fn flip_gravity_x(gravity: xdg_positioner::Gravity) -> xdg_positioner::Gravity {
+    use xdg_positioner::Gravity;
+    match gravity {
+        Gravity::Left => Gravity::Right,
+        Gravity::Right => Gravity::Left,
+        Gravity::TopLeft => Gravity::TopRight,
+        Gravity::TopRight => Gravity::TopLeft,
+        Gravity::BottomLeft => Gravity::BottomRight,
+        Gravity::BottomRight => Gravity::BottomLeft,
+        gravity => gravity,
+    }
+}
+
+fn flip_gravity_y(gravity: xdg_positioner::Gravity) -> xdg_positioner::Gravity {
+    use xdg_positioner::Gravity;
+    match gravity {
+        Gravity::Top => Gravity::Bottom,
+        Gravity::Bottom => Gravity::Top,
+        Gravity::TopLeft => Gravity::BottomLeft,
+        Gravity::BottomLeft => Gravity::TopLeft,
+        Gravity::TopRight => Gravity::BottomRight,
+        Gravity::BottomRight => Gravity::TopRight,
+        gravity => gravity,
+    }
 }
 
 /// State of a regular toplevel surface
@@ -635,6 +836,17 @@ pub struct ToplevelState {
 
     /// The xdg decoration mode of the surface
     pub decoration_mode: Option<zxdg_toplevel_decoration_v1::Mode>,
+
+    /// The bounds the surface should ideally fit into, e.g. the usable area of the output
+    ///
+    /// Sent as `xdg_toplevel.configure_bounds` to clients supporting version 4.
+    pub bounds: Option<Size<i32, Logical>>,
+
+    /// The window management capabilities the compositor supports for this surface
+    ///
+    /// Initialized from the capabilities of the [`XdgShellState`] and sent as
+    /// `xdg_toplevel.wm_capabilities` to clients supporting version 5, whenever they change.
+    pub capabilities: WmCapabilitySet,
 }
 
 impl Clone for ToplevelState {
@@ -644,6 +856,8 @@ impl Clone for ToplevelState {
             states: self.states.clone(),
             size: self.size,
             decoration_mode: self.decoration_mode,
+            bounds: self.bounds,
+            capabilities: self.capabilities.clone(),
         }
     }
 }
@@ -724,6 +938,71 @@ impl ToplevelStateSet {
     }
 }
 
+/// Container holding the window management capabilities of a `XdgToplevel`
+///
+/// Like [`ToplevelStateSet`] this prevents the same `xdg_toplevel::WmCapabilities`
+/// from being present multiple times.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WmCapabilitySet {
+    capabilities: Vec<xdg_toplevel::WmCapabilities>,
+}
+
+impl WmCapabilitySet {
+    /// Returns a set of all capabilities defined by the protocol
+    pub fn all() -> Self {
+        WmCapabilitySet {
+            capabilities: vec![
+                xdg_toplevel::WmCapabilities::WindowMenu,
+                xdg_toplevel::WmCapabilities::Maximize,
+                xdg_toplevel::WmCapabilities::Fullscreen,
+                xdg_toplevel::WmCapabilities::Minimize,
+            ],
+        }
+    }
+
+    /// Returns `true` if the set contains a capability.
+    pub fn contains(&self, capability: xdg_toplevel::WmCapabilities) -> bool {
+        self.capabilities.iter().any(|c| *c == capability)
+    }
+
+    /// Adds a capability to the set.
+    ///
+    /// Returns `true` if the capability was not present before.
+    pub fn set(&mut self, capability: xdg_toplevel::WmCapabilities) -> bool {
+        if self.contains(capability) {
+            false
+        } else {
+            self.capabilities.push(capability);
+            true
+        }
+    }
+
+    /// Removes a capability from the set. Returns whether the capability was present.
+    pub fn unset(&mut self, capability: xdg_toplevel::WmCapabilities) -> bool {
+        if !self.contains(capability) {
+            false
+        } else {
+            self.capabilities.retain(|c| *c != capability);
+            true
+        }
+    }
+
+    /// Iterate over the capabilities in this set
+    pub fn iter(&self) -> impl Iterator<Item = xdg_toplevel::WmCapabilities> + '_ {
+        self.capabilities.iter().copied()
+    }
+}
+
+impl FromIterator<xdg_toplevel::WmCapabilities> for WmCapabilitySet {
+    fn from_iter<T: IntoIterator<Item = xdg_toplevel::WmCapabilities>>(iter: T) -> Self {
+        let mut set = WmCapabilitySet::default();
+        for capability in iter {
+            set.set(capability);
+        }
+        set
+    }
+}
+
 impl IntoIterator for ToplevelStateSet {
     type Item = xdg_toplevel::State;
     type IntoIter = std::vec::IntoIter<Self::Item>;
@@ -882,6 +1161,7 @@ pub trait XdgShellHandler {
 pub(crate) struct InnerState {
     known_toplevels: Vec<ToplevelSurface>,
     known_popups: Vec<PopupSurface>,
+    capabilities: WmCapabilitySet,
 }
 
 /// Shell global state
@@ -897,24 +1177,74 @@ pub struct XdgShellState {
 
 impl XdgShellState {
     /// Create a new `xdg_shell` global
+    ///
+    /// All window management capabilities are advertised to clients,
+    /// use [`XdgShellState::new_with_capabilities`] to restrict them.
     pub fn new<D, L>(display: &DisplayHandle, logger: L) -> XdgShellState
     where
         L: Into<Option<::slog::Logger>>,
         D: GlobalDispatch<XdgWmBase, ()> + 'static,
+    {
+        Self::new_with_capabilities::<D, _, _>(display, WmCapabilitySet::all(), logger)
+    }
+
+    /// Create a new `xdg_shell` global advertising the given window management capabilities
+    pub fn new_with_capabilities<D, C, L>(
+        display: &DisplayHandle,
+        capabilities: C,
+        logger: L,
+    ) -> XdgShellState
+    where
+        C: IntoIterator<Item = xdg_toplevel::WmCapabilities>,
+        L: Into<Option<::slog::Logger>>,
+        D: GlobalDispatch<XdgWmBase, ()> + 'static,
     {
         let log = crate::slog_or_fallback(logger);
-        let global = display.create_global::<D, XdgWmBase, _>(3, ());
+        let global = display.create_global::<D, XdgWmBase, _>(XDG_WM_BASE_VERSION, ());
 
         XdgShellState {
             inner: Arc::new(Mutex::new(InnerState {
                 known_toplevels: Vec::new(),
                 known_popups: Vec::new(),
+                capabilities: capabilities.into_iter().collect(),
             })),
             global,
             _log: log.new(slog::o!("smithay_module" => "xdg_shell_handler")),
         }
     }
 
+    /// Window management capabilities advertised to new toplevel surfaces
+    pub fn capabilities(&self) -> WmCapabilitySet {
+        self.inner.lock().unwrap().capabilities.clone()
+    }
+
+    /// Updates the window management capabilities
+    ///
+    /// The new capabilities are sent to all known toplevel surfaces, that have already been configured.
+    pub fn set_capabilities(&mut self, capabilities: impl IntoIterator<Item = xdg_toplevel::WmCapabilities>) {
+        let capabilities = capabilities.into_iter().collect::<WmCapabilitySet>();
+        let toplevels = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.capabilities = capabilities.clone();
+            inner.known_toplevels.clone()
+        };
+        for toplevel in toplevels.iter().filter(|toplevel| toplevel.alive()) {
+            let configured = compositor::with_states(toplevel.wl_surface(), |states| {
+                states
+                    .data_map
+                    .get::<XdgToplevelSurfaceData>()
+                    .unwrap()
+                    .lock()
+                    .unwrap()
+                    .initial_configure_sent
+            });
+            toplevel.with_pending_state(|state| state.capabilities = capabilities.clone());
+            if configured {
+                toplevel.send_configure();
+            }
+        }
+    }
+
     /// Access all the shell surfaces known by this handler
     pub fn toplevel_surfaces<T, F: FnMut(&[ToplevelSurface]) -> T>(&self, mut cb: F) -> T {
         cb(&self.inner.lock().unwrap().known_toplevels)
@@ -1113,6 +1443,8 @@ impl ToplevelSurface {
                 // to the pending configures.
                 let decoration_mode_changed =
                     pending.decoration_mode != attributes.current_server_state().decoration_mode;
+                let capabilities_changed = !attributes.initial_configure_sent
+                    || pending.capabilities != attributes.current_server_state().capabilities;
 
                 let configure = ToplevelConfigure {
                     serial: SERIAL_COUNTER.next_serial(),
@@ -1122,12 +1454,12 @@ impl ToplevelSurface {
                 attributes.pending_configures.push(configure.clone());
                 attributes.initial_configure_sent = true;
 
-                Some((configure, decoration_mode_changed))
+                Some((configure, decoration_mode_changed, capabilities_changed))
             } else {
                 None
             }
         });
-        if let Some((configure, decoration_mode_changed)) = configure {
+        if let Some((configure, decoration_mode_changed, capabilities_changed)) = configure {
             if decoration_mode_changed {
                 if let Some(data) = self.shell_surface.data::<XdgShellSurfaceUserData>() {
                     if let Some(decoration) = &*data.decoration.lock().unwrap() {
@@ -1142,7 +1474,7 @@ impl ToplevelSurface {
                 }
             }
 
-            self::handlers::send_toplevel_configure(&self.shell_surface, configure)
+            self::handlers::send_toplevel_configure(&self.shell_surface, configure, capabilities_changed)
         }
     }
 
@@ -1607,3 +1939,108 @@ macro_rules! delegate_xdg_shell {
         ] => $crate::wayland::shell::xdg::XdgShellState);
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positioner(constraint_adjustment: xdg_positioner::ConstraintAdjustment) -> PositionerState {
+        PositionerState {
+            rect_size: (100, 50).into(),
+            anchor_rect: Rectangle::from_loc_and_size((0, 0), (20, 20)),
+            anchor_edges: xdg_positioner::Anchor::BottomRight,
+            gravity: xdg_positioner::Gravity::BottomRight,
+            constraint_adjustment,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unconstrained_geometry_untouched_if_fitting() {
+        let positioner = positioner(xdg_positioner::ConstraintAdjustment::all());
+        let target = Rectangle::from_loc_and_size((0, 0), (200, 200));
+        assert_eq!(
+            positioner.get_unconstrained_geometry(target),
+            positioner.get_geometry()
+        );
+    }
+
+    #[test]
+    fn unconstrained_geometry_flip() {
+        let positioner = positioner(
+            xdg_positioner::ConstraintAdjustment::FlipX | xdg_positioner::ConstraintAdjustment::FlipY,
+        );
+        // not enough space to the right and below the anchor
+        let target = Rectangle::from_loc_and_size((-150, -100), (200, 150));
+        assert_eq!(
+            positioner.get_unconstrained_geometry(target),
+            Rectangle::from_loc_and_size((-100, -50), (100, 50))
+        );
+
+        // flipping would not help, so the geometry stays as before
+        let target = Rectangle::from_loc_and_size((-50, -100), (100, 150));
+        assert_eq!(
+            positioner.get_unconstrained_geometry(target),
+            Rectangle::from_loc_and_size((20, -50), (100, 50))
+        );
+    }
+
+    #[test]
+    fn unconstrained_geometry_slide_and_resize() {
+        let positioner = positioner(xdg_positioner::ConstraintAdjustment::SlideX);
+        let target = Rectangle::from_loc_and_size((0, 0), (110, 200));
+        assert_eq!(
+            positioner.get_unconstrained_geometry(target),
+            Rectangle::from_loc_and_size((10, 20), (100, 50))
+        );
+
+        let positioner = positioner(
+            xdg_positioner::ConstraintAdjustment::SlideX | xdg_positioner::ConstraintAdjustment::ResizeX,
+        );
+        let target = Rectangle::from_loc_and_size((0, 0), (80, 200));
+        assert_eq!(
+            positioner.get_unconstrained_geometry(target),
+            Rectangle::from_loc_and_size((0, 20), (80, 50))
+        );
+    }
+
+    #[test]
+    fn advertised_version_is_supported() {
+        // creating the global panics, if the protocol definitions do not support the version
+        assert!(XDG_WM_BASE_VERSION <= XdgWmBase::interface().version);
+        assert!(XDG_WM_BASE_VERSION >= xdg_toplevel::EVT_WM_CAPABILITIES_SINCE);
+    }
+
+    #[test]
+    fn wm_capability_set() {
+        use xdg_toplevel::WmCapabilities;
+
+        let mut capabilities = [
+            WmCapabilities::Maximize,
+            WmCapabilities::Fullscreen,
+            WmCapabilities::Maximize,
+        ]
+        .into_iter()
+        .collect::<WmCapabilitySet>();
+        assert_eq!(
+            capabilities.iter().collect::<Vec<_>>(),
+            [WmCapabilities::Maximize, WmCapabilities::Fullscreen]
+        );
+
+        assert!(!capabilities.set(WmCapabilities::Fullscreen));
+        assert!(capabilities.set(WmCapabilities::Minimize));
+        assert!(capabilities.unset(WmCapabilities::Maximize));
+        assert!(!capabilities.unset(WmCapabilities::WindowMenu));
+        assert!(!capabilities.contains(WmCapabilities::Maximize));
+        assert_eq!(
+            capabilities,
+            [WmCapabilities::Fullscreen, WmCapabilities::Minimize]
+                .into_iter()
+                .collect::<WmCapabilitySet>()
+        );
+
+        let all = WmCapabilitySet::all();
+        assert_eq!(all.iter().count(), 4);
+        assert!(all.contains(WmCapabilities::WindowMenu));
+    }
+}
@@ -82,6 +82,10 @@
 use crate::input::{Seat, SeatHandler};
 use wayland_protocols::wp::tablet::zv2::server::{
     zwp_tablet_manager_v2::{self, ZwpTabletManagerV2},
+    zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2,
+    zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2,
+    zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2,
+    zwp_tablet_pad_v2::ZwpTabletPadV2,
     zwp_tablet_seat_v2::ZwpTabletSeatV2,
     zwp_tablet_tool_v2::ZwpTabletToolV2,
     zwp_tablet_v2::ZwpTabletV2,
@@ -91,10 +95,12 @@ use wayland_server::{backend::GlobalId, Client, DataInit, Dispatch, DisplayHandl
 const MANAGER_VERSION: u32 = 1;
 
 mod tablet;
+mod tablet_pad;
 mod tablet_seat;
 mod tablet_tool;
 
 pub use tablet::{TabletDescriptor, TabletHandle, TabletUserData};
+pub use tablet_pad::{TabletPadHandle, TabletPadUserData};
 pub use tablet_seat::{TabletSeatHandle, TabletSeatUserData};
 pub use tablet_tool::{TabletToolHandle, TabletToolUserData};
 
@@ -126,6 +132,10 @@ impl TabletManagerState {
         D: Dispatch<ZwpTabletManagerV2, ()>,
         D: Dispatch<ZwpTabletSeatV2, TabletSeatUserData>,
         D: Dispatch<ZwpTabletToolV2, TabletToolUserData>,
+        D: Dispatch<ZwpTabletPadV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadGroupV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadRingV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadStripV2, TabletPadUserData>,
         D: 'static,
     {
         let global = display.create_global::<D, ZwpTabletManagerV2, _>(MANAGER_VERSION, ());
@@ -164,6 +174,10 @@ where
     D: Dispatch<ZwpTabletSeatV2, TabletSeatUserData>,
     D: Dispatch<ZwpTabletV2, TabletUserData>,
     D: Dispatch<ZwpTabletToolV2, TabletToolUserData>,
+    D: Dispatch<ZwpTabletPadV2, TabletPadUserData>,
+    D: Dispatch<ZwpTabletPadGroupV2, TabletPadUserData>,
+    D: Dispatch<ZwpTabletPadRingV2, TabletPadUserData>,
+    D: Dispatch<ZwpTabletPadStripV2, TabletPadUserData>,
     D: SeatHandler + 'static,
 {
     fn request(
@@ -220,5 +234,17 @@ macro_rules! delegate_tablet_manager {
         $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
             $crate::reexports::wayland_protocols::wp::tablet::zv2::server::zwp_tablet_v2::ZwpTabletV2: $crate::wayland::tablet_manager::TabletUserData
         ] => $crate::wayland::tablet_manager::TabletManagerState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::tablet::zv2::server::zwp_tablet_pad_v2::ZwpTabletPadV2: $crate::wayland::tablet_manager::TabletPadUserData
+        ] => $crate::wayland::tablet_manager::TabletManagerState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::tablet::zv2::server::zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2: $crate::wayland::tablet_manager::TabletPadUserData
+        ] => $crate::wayland::tablet_manager::TabletManagerState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::tablet::zv2::server::zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2: $crate::wayland::tablet_manager::TabletPadUserData
+        ] => $crate::wayland::tablet_manager::TabletManagerState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::reexports::wayland_protocols::wp::tablet::zv2::server::zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2: $crate::wayland::tablet_manager::TabletPadUserData
+        ] => $crate::wayland::tablet_manager::TabletManagerState);
     };
 }
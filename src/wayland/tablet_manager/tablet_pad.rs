@@ -0,0 +1,610 @@
+use std::sync::{Arc, Mutex};
+
+use wayland_protocols::wp::tablet::zv2::server::{
+    zwp_tablet_pad_group_v2::{self, ZwpTabletPadGroupV2},
+    zwp_tablet_pad_ring_v2::{self, ZwpTabletPadRingV2},
+    zwp_tablet_pad_strip_v2::{self, ZwpTabletPadStripV2},
+    zwp_tablet_pad_v2::{self, ZwpTabletPadV2},
+    zwp_tablet_seat_v2::ZwpTabletSeatV2,
+};
+use wayland_server::{
+    backend::{ClientId, ObjectId},
+    protocol::wl_surface::WlSurface,
+    Client, DataInit, Dispatch, DisplayHandle, Resource,
+};
+
+use crate::backend::input::{ButtonState, TabletPadAxisSource, TabletPadDescriptor};
+use crate::utils::Serial;
+
+use super::tablet::{TabletDescriptor, TabletHandle};
+use super::TabletManagerState;
+
+#[derive(Debug)]
+struct PadInstance {
+    pad: ZwpTabletPadV2,
+    groups: Vec<ZwpTabletPadGroupV2>,
+    rings: Vec<ZwpTabletPadRingV2>,
+    strips: Vec<ZwpTabletPadStripV2>,
+}
+
+#[derive(Debug, Default)]
+struct TabletPad {
+    instances: Vec<PadInstance>,
+    focus: Option<WlSurface>,
+    modes: Vec<u32>,
+}
+
+impl TabletPad {
+    fn focused_instance(&self) -> Option<&PadInstance> {
+        let focus = self.focus.as_ref()?;
+        self.instances
+            .iter()
+            .find(|i| i.pad.id().same_client_as(&focus.id()))
+    }
+
+    fn enter(&mut self, focus: &WlSurface, tablet: &TabletHandle, serial: Serial) {
+        if self.focus.as_ref() == Some(focus) {
+            return;
+        }
+        self.leave(serial);
+
+        if let Some(instance) = self
+            .instances
+            .iter()
+            .find(|i| i.pad.id().same_client_as(&focus.id()))
+        {
+            tablet.with_focused_tablet(focus, |wl_tablet| {
+                instance.pad.enter(serial.into(), wl_tablet, focus);
+            });
+        }
+
+        self.focus = Some(focus.clone());
+    }
+
+    fn leave(&mut self, serial: Serial) {
+        if let (Some(instance), Some(focus)) = (self.focused_instance(), self.focus.as_ref()) {
+            instance.pad.leave(serial.into(), focus);
+        }
+        self.focus = None;
+    }
+}
+
+impl Drop for TabletPad {
+    fn drop(&mut self) {
+        for instance in self.instances.iter() {
+            // This event is sent when the pad is removed from the system and will send no further events.
+            instance.pad.removed();
+        }
+    }
+}
+
+/// Handle to a tablet pad device
+///
+/// A TabletPad represents the buttons, rings and strips of a graphics tablet.
+///
+/// The pad follows the focus of the tablet tools of the same seat, which means it enters
+/// the surface a tool of the seat last came into proximity of and leaves it along with the tool.
+#[derive(Debug, Default, Clone)]
+pub struct TabletPadHandle {
+    inner: Arc<Mutex<TabletPad>>,
+}
+
+impl TabletPadHandle {
+    pub(super) fn new_instance<D>(
+        &mut self,
+        client: &Client,
+        dh: &DisplayHandle,
+        seat: &ZwpTabletSeatV2,
+        tablet: &TabletDescriptor,
+        pad: &TabletPadDescriptor,
+    ) where
+        D: Dispatch<ZwpTabletPadV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadGroupV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadRingV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadStripV2, TabletPadUserData>,
+        D: 'static,
+    {
+        let user_data = || TabletPadUserData { handle: self.clone() };
+        let version = seat.version();
+
+        let wl_pad = client
+            .create_resource::<ZwpTabletPadV2, _, D>(dh, version, user_data())
+            .unwrap();
+        seat.pad_added(&wl_pad);
+
+        let mut rings = Vec::with_capacity(pad.rings as usize);
+        let mut strips = Vec::with_capacity(pad.strips as usize);
+        let mut groups = Vec::with_capacity(pad.mode_groups.len());
+
+        for group in pad.mode_groups.iter() {
+            let wl_group = client
+                .create_resource::<ZwpTabletPadGroupV2, _, D>(dh, version, user_data())
+                .unwrap();
+            wl_pad.group(&wl_group);
+
+            wl_group.buttons(group.buttons.iter().flat_map(|b| b.to_ne_bytes()).collect());
+            for ring in group.rings.iter() {
+                let wl_ring = client
+                    .create_resource::<ZwpTabletPadRingV2, _, D>(dh, version, user_data())
+                    .unwrap();
+                wl_group.ring(&wl_ring);
+                rings.push((*ring, wl_ring));
+            }
+            for strip in group.strips.iter() {
+                let wl_strip = client
+                    .create_resource::<ZwpTabletPadStripV2, _, D>(dh, version, user_data())
+                    .unwrap();
+                wl_group.strip(&wl_strip);
+                strips.push((*strip, wl_strip));
+            }
+            wl_group.modes(group.modes);
+            wl_group.done();
+
+            groups.push(wl_group);
+        }
+
+        if let Some(syspath) = tablet.syspath.as_ref().and_then(|p| p.to_str()) {
+            wl_pad.path(syspath.to_owned());
+        }
+        wl_pad.buttons(pad.buttons);
+        wl_pad.done();
+
+        // rings and strips are addressed by their index on the device
+        rings.sort_by_key(|(index, _)| *index);
+        strips.sort_by_key(|(index, _)| *index);
+
+        let mut inner = self.inner.lock().unwrap();
+        if inner.modes.is_empty() {
+            inner.modes = pad.mode_groups.iter().map(|group| group.mode).collect();
+        }
+        inner.instances.push(PadInstance {
+            pad: wl_pad,
+            groups,
+            rings: rings.into_iter().map(|(_, ring)| ring).collect(),
+            strips: strips.into_iter().map(|(_, strip)| strip).collect(),
+        });
+    }
+
+    /// Notify that this pad is focused on a certain surface
+    ///
+    /// The `tablet` is the tablet this pad belongs to, focus is
+    /// normally set automatically alongside the focus of the tablet tools.
+    pub fn enter(&self, focus: &WlSurface, tablet: &TabletHandle, serial: Serial) {
+        self.inner.lock().unwrap().enter(focus, tablet, serial);
+    }
+
+    /// Notify that this pad is no longer focused on any surface
+    pub fn leave(&self, serial: Serial) {
+        self.inner.lock().unwrap().leave(serial);
+    }
+
+    /// Surface currently focused by this pad
+    pub fn focus(&self) -> Option<WlSurface> {
+        self.inner.lock().unwrap().focus.clone()
+    }
+
+    /// Button on the pad was pressed or released
+    pub fn button(&self, button: u32, state: ButtonState, time: u32) {
+        let inner = self.inner.lock().unwrap();
+        if let Some(instance) = inner.focused_instance() {
+            instance.pad.button(time, button, state.into());
+        }
+    }
+
+    /// Ring on the pad changed its position
+    ///
+    /// The position is given in degrees clockwise from the logical north of the ring,
+    /// `None` signals that the interaction with the ring stopped.
+    pub fn ring(&self, ring: u32, position: Option<f64>, source: TabletPadAxisSource, time: u32) {
+        let inner = self.inner.lock().unwrap();
+        if let Some(wl_ring) = inner
+            .focused_instance()
+            .and_then(|instance| instance.rings.get(ring as usize))
+        {
+            if let TabletPadAxisSource::Finger = source {
+                wl_ring.source(zwp_tablet_pad_ring_v2::Source::Finger);
+            }
+            match position {
+                Some(angle) => wl_ring.angle(angle),
+                None => wl_ring.stop(),
+            }
+            wl_ring.frame(time);
+        }
+    }
+
+    /// Strip on the pad changed its position
+    ///
+    /// The position is normalized to the range `[0, 1]`, `None` signals that the
+    /// interaction with the strip stopped.
+    pub fn strip(&self, strip: u32, position: Option<f64>, source: TabletPadAxisSource, time: u32) {
+        let inner = self.inner.lock().unwrap();
+        if let Some(wl_strip) = inner
+            .focused_instance()
+            .and_then(|instance| instance.strips.get(strip as usize))
+        {
+            if let TabletPadAxisSource::Finger = source {
+                wl_strip.source(zwp_tablet_pad_strip_v2::Source::Finger);
+            }
+            match position {
+                Some(position) => wl_strip.position((position.clamp(0.0, 1.0) * 65535.0).round() as u32),
+                None => wl_strip.stop(),
+            }
+            wl_strip.frame(time);
+        }
+    }
+
+    /// Update the mode of a mode group
+    ///
+    /// Pad events carry the current mode of their group, pass it here before
+    /// forwarding the event. Clients are only notified if the mode actually changed.
+    pub fn mode(&self, group: u32, mode: u32, serial: Serial, time: u32) {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.modes.get_mut(group as usize) {
+            Some(current) if *current != mode => *current = mode,
+            _ => return,
+        }
+        if let Some(wl_group) = inner
+            .focused_instance()
+            .and_then(|instance| instance.groups.get(group as usize))
+        {
+            wl_group.mode_switch(time, serial.into(), mode);
+        }
+    }
+}
+
+impl From<ButtonState> for zwp_tablet_pad_v2::ButtonState {
+    fn from(from: ButtonState) -> zwp_tablet_pad_v2::ButtonState {
+        match from {
+            ButtonState::Pressed => zwp_tablet_pad_v2::ButtonState::Pressed,
+            ButtonState::Released => zwp_tablet_pad_v2::ButtonState::Released,
+        }
+    }
+}
+
+/// User data of ZwpTabletPadV2 object and its groups, rings and strips
+#[derive(Debug)]
+pub struct TabletPadUserData {
+    handle: TabletPadHandle,
+}
+
+impl<D> Dispatch<ZwpTabletPadV2, TabletPadUserData, D> for TabletManagerState
+where
+    D: Dispatch<ZwpTabletPadV2, TabletPadUserData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _pad: &ZwpTabletPadV2,
+        request: zwp_tablet_pad_v2::Request,
+        _data: &TabletPadUserData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwp_tablet_pad_v2::Request::SetFeedback { .. } => {
+                // Displaying the feedback is up to the compositor, which we do not support yet
+            }
+            zwp_tablet_pad_v2::Request::Destroy => {
+                // Nothing to do
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(_state: &mut D, _client: ClientId, pad: ObjectId, data: &TabletPadUserData) {
+        data.handle
+            .inner
+            .lock()
+            .unwrap()
+            .instances
+            .retain(|i| i.pad.id() != pad);
+    }
+}
+
+impl<D> Dispatch<ZwpTabletPadGroupV2, TabletPadUserData, D> for TabletManagerState
+where
+    D: Dispatch<ZwpTabletPadGroupV2, TabletPadUserData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _group: &ZwpTabletPadGroupV2,
+        _request: zwp_tablet_pad_group_v2::Request,
+        _data: &TabletPadUserData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+    }
+}
+
+impl<D> Dispatch<ZwpTabletPadRingV2, TabletPadUserData, D> for TabletManagerState
+where
+    D: Dispatch<ZwpTabletPadRingV2, TabletPadUserData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _ring: &ZwpTabletPadRingV2,
+        _request: zwp_tablet_pad_ring_v2::Request,
+        _data: &TabletPadUserData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+    }
+}
+
+impl<D> Dispatch<ZwpTabletPadStripV2, TabletPadUserData, D> for TabletManagerState
+where
+    D: Dispatch<ZwpTabletPadStripV2, TabletPadUserData>,
+    D: 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _strip: &ZwpTabletPadStripV2,
+        _request: zwp_tablet_pad_strip_v2::Request,
+        _data: &TabletPadUserData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        backend::input::{TabletPadModeGroup, TabletToolCapabilitys, TabletToolDescriptor, TabletToolType},
+        wayland::tablet_manager::{TabletSeatHandle, TabletSeatUserData, TabletToolUserData, TabletUserData},
+    };
+    use std::{
+        io::{ErrorKind, Read},
+        os::unix::net::UnixStream,
+    };
+    use wayland_protocols::wp::tablet::zv2::server::{
+        zwp_tablet_tool_v2::ZwpTabletToolV2, zwp_tablet_v2::ZwpTabletV2,
+    };
+    use wayland_server::{
+        backend::{ClientData, DisconnectReason},
+        delegate_dispatch,
+        protocol::wl_surface,
+        Display,
+    };
+
+    // event opcodes, in the order of the protocol definition
+    const SEAT_TABLET_ADDED: u16 = 0;
+    const SEAT_PAD_ADDED: u16 = 2;
+    const PAD_GROUP: u16 = 0;
+    const PAD_BUTTON: u16 = 4;
+    const PAD_ENTER: u16 = 5;
+    const PAD_LEAVE: u16 = 6;
+    const GROUP_MODE_SWITCH: u16 = 5;
+
+    struct State;
+
+    impl Dispatch<WlSurface, ()> for State {
+        fn request(
+            _state: &mut Self,
+            _client: &Client,
+            _surface: &WlSurface,
+            _request: wl_surface::Request,
+            _data: &(),
+            _dh: &DisplayHandle,
+            _data_init: &mut DataInit<'_, Self>,
+        ) {
+        }
+    }
+
+    delegate_dispatch!(State: [
+        ZwpTabletSeatV2: TabletSeatUserData,
+        ZwpTabletV2: TabletUserData,
+        ZwpTabletToolV2: TabletToolUserData,
+        ZwpTabletPadV2: TabletPadUserData,
+        ZwpTabletPadGroupV2: TabletPadUserData,
+        ZwpTabletPadRingV2: TabletPadUserData,
+        ZwpTabletPadStripV2: TabletPadUserData
+    ] => TabletManagerState);
+
+    struct TestClientData;
+
+    impl ClientData for TestClientData {
+        fn initialized(&self, _client_id: ClientId) {}
+        fn disconnected(&self, _client_id: ClientId, _reason: DisconnectReason) {}
+    }
+
+    struct Setup {
+        display: Display<State>,
+        client: Client,
+        stream: UnixStream,
+        seat: TabletSeatHandle,
+        wl_seat: ZwpTabletSeatV2,
+    }
+
+    impl Setup {
+        // a client with a bound tablet seat
+        fn new() -> Setup {
+            let display = Display::<State>::new().unwrap();
+            let dh = display.handle();
+            let (stream, server_stream) = UnixStream::pair().unwrap();
+            stream.set_nonblocking(true).unwrap();
+            let client = dh.insert_client(server_stream, Arc::new(TestClientData)).unwrap();
+
+            let seat = TabletSeatHandle::default();
+            let wl_seat = client
+                .create_resource::<ZwpTabletSeatV2, _, State>(
+                    &dh,
+                    1,
+                    TabletSeatUserData { handle: seat.clone() },
+                )
+                .unwrap();
+            seat.add_instance::<State>(&dh, &wl_seat, &client);
+
+            Setup {
+                display,
+                client,
+                stream,
+                seat,
+                wl_seat,
+            }
+        }
+
+        fn surface(&self) -> WlSurface {
+            self.client
+                .create_resource::<WlSurface, _, State>(&self.display.handle(), 4, ())
+                .unwrap()
+        }
+
+        // object ids, opcodes and arguments of all events sent to the client so far
+        fn events(&mut self) -> Vec<(u32, u16, Vec<u32>)> {
+            self.display.flush_clients().unwrap();
+            let mut bytes = Vec::new();
+            let mut buffer = [0u8; 4096];
+            loop {
+                match self.stream.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(len) => bytes.extend_from_slice(&buffer[..len]),
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                    Err(err) => panic!("{}", err),
+                }
+            }
+
+            let word = |offset: usize| u32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap());
+            let mut events = Vec::new();
+            let mut offset = 0;
+            while offset + 8 <= bytes.len() {
+                let size = (word(offset + 4) >> 16) as usize;
+                let args = (offset + 8..offset + size).step_by(4).map(word).collect();
+                events.push((word(offset), word(offset + 4) as u16, args));
+                offset += size;
+            }
+            events
+        }
+
+        // ids of the objects announced by the tablet seat with the given event
+        fn added(&self, events: &[(u32, u16, Vec<u32>)], opcode: u16) -> Vec<u32> {
+            let seat = self.wl_seat.id().protocol_id();
+            events
+                .iter()
+                .filter(|(id, op, _)| *id == seat && *op == opcode)
+                .map(|(_, _, args)| args[0])
+                .collect()
+        }
+    }
+
+    fn tablet_desc(name: &str) -> TabletDescriptor {
+        TabletDescriptor {
+            name: name.into(),
+            usb_id: None,
+            syspath: None,
+        }
+    }
+
+    fn pad_desc() -> TabletPadDescriptor {
+        TabletPadDescriptor {
+            buttons: 1,
+            rings: 0,
+            strips: 0,
+            mode_groups: vec![TabletPadModeGroup {
+                modes: 2,
+                mode: 0,
+                buttons: vec![0],
+                rings: Vec::new(),
+                strips: Vec::new(),
+            }],
+        }
+    }
+
+    fn tool_desc() -> TabletToolDescriptor {
+        TabletToolDescriptor {
+            tool_type: TabletToolType::Pen,
+            hardware_serial: 0,
+            hardware_id_wacom: 0,
+            capabilitys: TabletToolCapabilitys::empty(),
+        }
+    }
+
+    #[test]
+    fn pads_follow_tools_with_their_own_tablet() {
+        let mut setup = Setup::new();
+        let dh = setup.display.handle();
+        let tool_tablet = setup.seat.add_tablet::<State>(&dh, &tablet_desc("a"));
+        setup.seat.add_tablet::<State>(&dh, &tablet_desc("b"));
+        let pad = setup.seat.add_pad::<State>(&dh, &tablet_desc("b"), &pad_desc());
+        // the tablet of this pad is unknown to the seat
+        let lonely_pad = setup.seat.add_pad::<State>(&dh, &tablet_desc("c"), &pad_desc());
+        let tool = setup.seat.add_tool::<State>(&dh, &tool_desc());
+        let events = setup.events();
+        let tablets = setup.added(&events, SEAT_TABLET_ADDED);
+        let pads = setup.added(&events, SEAT_PAD_ADDED);
+        let wl_pad = pads[0];
+
+        let surface = setup.surface();
+        tool.proximity_in(
+            (5.0, 5.0).into(),
+            (surface.clone(), (0, 0).into()),
+            &tool_tablet,
+            Serial::from(1),
+            0,
+        );
+        assert_eq!(pad.focus(), Some(surface.clone()));
+        assert_eq!(lonely_pad.focus(), None);
+        let events = setup.events();
+        let enter = events
+            .iter()
+            .find(|(id, op, _)| *id == wl_pad && *op == PAD_ENTER)
+            .unwrap();
+        // serial, tablet and surface
+        assert_eq!(enter.2, vec![1, tablets[1], surface.id().protocol_id()]);
+        assert!(!events.iter().any(|(id, _, _)| *id == pads[1]));
+
+        tool.proximity_out(1);
+        assert_eq!(pad.focus(), None);
+        let events = setup.events();
+        let leave = events
+            .iter()
+            .find(|(id, op, _)| *id == wl_pad && *op == PAD_LEAVE)
+            .unwrap();
+        assert_eq!(leave.2[1], surface.id().protocol_id());
+    }
+
+    #[test]
+    fn pad_events_go_to_the_focused_client() {
+        let mut setup = Setup::new();
+        let dh = setup.display.handle();
+        let tablet_handle = setup.seat.add_tablet::<State>(&dh, &tablet_desc("a"));
+        let pad = setup.seat.add_pad::<State>(&dh, &tablet_desc("a"), &pad_desc());
+        let events = setup.events();
+        let wl_pad = setup.added(&events, SEAT_PAD_ADDED)[0];
+        let group = events
+            .iter()
+            .find(|(id, op, _)| *id == wl_pad && *op == PAD_GROUP)
+            .map(|(_, _, args)| args[0])
+            .unwrap();
+
+        // without focus nothing is sent, but the mode is tracked
+        pad.button(0, ButtonState::Pressed, 0);
+        pad.mode(0, 1, Serial::from(1), 0);
+        assert!(setup.events().is_empty());
+
+        let surface = setup.surface();
+        pad.enter(&surface, &tablet_handle, Serial::from(2));
+        setup.events();
+
+        pad.mode(0, 1, Serial::from(3), 10);
+        assert!(setup.events().is_empty());
+        pad.mode(0, 0, Serial::from(4), 20);
+        pad.button(0, ButtonState::Pressed, 30);
+        assert_eq!(
+            setup.events(),
+            vec![
+                // time, serial and mode
+                (group, GROUP_MODE_SWITCH, vec![20, 4, 0]),
+                // time, button and state
+                (wl_pad, PAD_BUTTON, vec![30, 0, 1]),
+            ]
+        );
+    }
+}
@@ -1,4 +1,8 @@
 use wayland_protocols::wp::tablet::zv2::server::{
+    zwp_tablet_pad_group_v2::ZwpTabletPadGroupV2,
+    zwp_tablet_pad_ring_v2::ZwpTabletPadRingV2,
+    zwp_tablet_pad_strip_v2::ZwpTabletPadStripV2,
+    zwp_tablet_pad_v2::ZwpTabletPadV2,
     zwp_tablet_seat_v2::{self, ZwpTabletSeatV2},
     zwp_tablet_tool_v2::ZwpTabletToolV2,
     zwp_tablet_v2::ZwpTabletV2,
@@ -8,11 +12,12 @@ use wayland_server::{
     Client, DataInit, Dispatch, DisplayHandle, Resource,
 };
 
-use crate::backend::input::TabletToolDescriptor;
+use crate::backend::input::{TabletPadDescriptor, TabletToolDescriptor};
 use crate::input::pointer::CursorImageStatus;
 
 use super::{
     tablet::TabletUserData,
+    tablet_pad::{TabletPadHandle, TabletPadUserData},
     tablet_tool::{TabletToolHandle, TabletToolUserData},
 };
 use super::{
@@ -25,11 +30,17 @@ use std::fmt;
 use std::ops::Deref as _;
 use std::sync::{Arc, Mutex};
 
+/// Tablets of a seat, shared with its tools, so pads can enter surfaces along with their tablet
+pub(super) type SeatTablets = Arc<Mutex<HashMap<TabletDescriptor, TabletHandle>>>;
+/// Pads of a seat, shared with its tools, so pads can follow their focus
+pub(super) type SeatPads = Arc<Mutex<HashMap<TabletDescriptor, (TabletPadDescriptor, TabletPadHandle)>>>;
+
 #[derive(Default)]
 pub(crate) struct TabletSeat {
     instances: Vec<ZwpTabletSeatV2>,
-    tablets: HashMap<TabletDescriptor, TabletHandle>,
+    tablets: SeatTablets,
     tools: HashMap<TabletToolDescriptor, TabletToolHandle>,
+    pads: SeatPads,
 
     cursor_callback: Option<Box<dyn FnMut(&TabletToolDescriptor, CursorImageStatus) + Send>>,
 }
@@ -40,6 +51,7 @@ impl fmt::Debug for TabletSeat {
             .field("instances", &self.instances)
             .field("tablets", &self.tablets)
             .field("tools", &self.tools)
+            .field("pads", &self.pads)
             .field(
                 "cursor_callback",
                 if self.cursor_callback.is_some() {
@@ -67,12 +79,16 @@ impl TabletSeatHandle {
     where
         D: Dispatch<ZwpTabletV2, TabletUserData>,
         D: Dispatch<ZwpTabletToolV2, TabletToolUserData>,
+        D: Dispatch<ZwpTabletPadV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadGroupV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadRingV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadStripV2, TabletPadUserData>,
         D: 'static,
     {
         let mut inner = self.inner.lock().unwrap();
 
         // Notify new instance about available tablets
-        for (desc, tablet) in inner.tablets.iter_mut() {
+        for (desc, tablet) in inner.tablets.lock().unwrap().iter_mut() {
             tablet.new_instance::<D>(client, dh, seat, desc);
         }
 
@@ -86,6 +102,11 @@ impl TabletSeatHandle {
             });
         }
 
+        // Notify new instance about available pads
+        for (tablet_desc, (pad_desc, pad)) in inner.pads.lock().unwrap().iter_mut() {
+            pad.new_instance::<D>(client, dh, seat, tablet_desc, pad_desc);
+        }
+
         inner.instances.push(seat.clone());
     }
 
@@ -110,7 +131,7 @@ impl TabletSeatHandle {
     {
         let inner = &mut *self.inner.lock().unwrap();
 
-        let tablets = &mut inner.tablets;
+        let mut tablets = inner.tablets.lock().unwrap();
         let instances = &inner.instances;
 
         let tablet = tablets.entry(tablet_desc.clone()).or_insert_with(|| {
@@ -129,12 +150,19 @@ impl TabletSeatHandle {
 
     /// Get a handle to a tablet
     pub fn get_tablet(&self, tablet_desc: &TabletDescriptor) -> Option<TabletHandle> {
-        self.inner.lock().unwrap().tablets.get(tablet_desc).cloned()
+        self.inner
+            .lock()
+            .unwrap()
+            .tablets
+            .lock()
+            .unwrap()
+            .get(tablet_desc)
+            .cloned()
     }
 
     /// Count all tablet devices
     pub fn count_tablets(&self) -> usize {
-        self.inner.lock().unwrap().tablets.len()
+        self.inner.lock().unwrap().tablets.lock().unwrap().len()
     }
 
     /// Remove tablet device
@@ -142,12 +170,18 @@ impl TabletSeatHandle {
     /// Called when tablet is no longer available
     /// For example on [input::Event::DeviceRemoved](crate::backend::input::InputEvent::DeviceRemoved) event.
     pub fn remove_tablet(&self, tablet_desc: &TabletDescriptor) {
-        self.inner.lock().unwrap().tablets.remove(tablet_desc);
+        self.inner
+            .lock()
+            .unwrap()
+            .tablets
+            .lock()
+            .unwrap()
+            .remove(tablet_desc);
     }
 
     /// Remove all tablet devices
     pub fn clear_tablets(&self) {
-        self.inner.lock().unwrap().tablets.clear();
+        self.inner.lock().unwrap().tablets.lock().unwrap().clear();
     }
 
     /// Add a new tool to a seat.
@@ -166,8 +200,11 @@ impl TabletSeatHandle {
         let tools = &mut inner.tools;
         let instances = &inner.instances;
 
+        let tablets = &inner.tablets;
+        let pads = &inner.pads;
+
         let tool = tools.entry(tool_desc.clone()).or_insert_with(|| {
-            let mut tool = TabletToolHandle::default();
+            let mut tool = TabletToolHandle::new(tablets.clone(), pads.clone());
             // Create new tool instance for every seat instance
             for seat in instances.iter() {
                 let inner = self.inner.clone();
@@ -209,6 +246,77 @@ impl TabletSeatHandle {
     pub fn clear_tools(&self) {
         self.inner.lock().unwrap().tools.clear();
     }
+
+    /// Add a new pad to a seat.
+    ///
+    /// Pads are usually added on [input::Event::DeviceAdded](crate::backend::input::InputEvent::DeviceAdded) event
+    /// for devices providing a [TabletPadDescriptor](crate::backend::input::Device::tablet_pad).
+    ///
+    /// The focus of the pad follows the tools of this seat, once its tablet was added
+    /// through [`TabletSeatHandle::add_tablet`].
+    ///
+    /// Returns new [TabletPadHandle] if pad was not know by this seat, if pad was already know it returns existing handle,
+    /// it allows you to send pad input events to clients.
+    pub fn add_pad<D>(
+        &self,
+        dh: &DisplayHandle,
+        tablet_desc: &TabletDescriptor,
+        pad_desc: &TabletPadDescriptor,
+    ) -> TabletPadHandle
+    where
+        D: Dispatch<ZwpTabletPadV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadGroupV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadRingV2, TabletPadUserData>,
+        D: Dispatch<ZwpTabletPadStripV2, TabletPadUserData>,
+        D: 'static,
+    {
+        let inner = self.inner.lock().unwrap();
+        let mut pads = inner.pads.lock().unwrap();
+
+        let (_, pad) = pads.entry(tablet_desc.clone()).or_insert_with(|| {
+            let mut pad = TabletPadHandle::default();
+            // Create new pad instance for every seat instance
+            for seat in inner.instances.iter() {
+                if let Ok(client) = dh.get_client(seat.id()) {
+                    pad.new_instance::<D>(&client, dh, seat, tablet_desc, pad_desc);
+                }
+            }
+            (pad_desc.clone(), pad)
+        });
+
+        pad.clone()
+    }
+
+    /// Get a handle to a tablet pad
+    pub fn get_pad(&self, tablet_desc: &TabletDescriptor) -> Option<TabletPadHandle> {
+        let inner = self.inner.lock().unwrap();
+        let pads = inner.pads.lock().unwrap();
+        pads.get(tablet_desc).map(|(_, pad)| pad.clone())
+    }
+
+    /// Count all tablet pad devices
+    pub fn count_pads(&self) -> usize {
+        self.inner.lock().unwrap().pads.lock().unwrap().len()
+    }
+
+    /// Remove tablet pad device
+    ///
+    /// Called when pad is no longer available
+    /// For example on [input::Event::DeviceRemoved](crate::backend::input::InputEvent::DeviceRemoved) event.
+    pub fn remove_pad(&self, tablet_desc: &TabletDescriptor) {
+        self.inner
+            .lock()
+            .unwrap()
+            .pads
+            .lock()
+            .unwrap()
+            .remove(tablet_desc);
+    }
+
+    /// Remove all tablet pad devices
+    pub fn clear_pads(&self) {
+        self.inner.lock().unwrap().pads.lock().unwrap().clear();
+    }
 }
 
 /// User data of ZwpTabletSeatV2 object
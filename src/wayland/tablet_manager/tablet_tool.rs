@@ -15,9 +15,13 @@ use wayland_server::{
     Client, DataInit, Dispatch, DisplayHandle, Resource,
 };
 
-use crate::{utils::Serial, wayland::compositor};
+use crate::{
+    utils::{Serial, SERIAL_COUNTER},
+    wayland::compositor,
+};
 
 use super::tablet::TabletHandle;
+use super::tablet_seat::{SeatPads, SeatTablets};
 use super::TabletManagerState;
 
 #[derive(Debug, Default)]
@@ -33,6 +37,9 @@ struct TabletTool {
     pending_slider: Option<f64>,
     pending_rotation: Option<f64>,
     pending_wheel: Option<(f64, i32)>,
+
+    tablets: SeatTablets,
+    pads: SeatPads,
 }
 
 impl TabletTool {
@@ -56,6 +63,14 @@ impl TabletTool {
             });
         }
 
+        // pads of the seat follow the focus of its tools, entering along with their own tablet
+        let tablets = self.tablets.lock().unwrap();
+        for (tablet_desc, (_, pad)) in self.pads.lock().unwrap().iter() {
+            if let Some(pad_tablet) = tablets.get(tablet_desc) {
+                pad.enter(&focus, pad_tablet, serial);
+            }
+        }
+
         self.focus = Some(focus.clone());
     }
 
@@ -71,6 +86,13 @@ impl TabletTool {
                 wl_tool.proximity_out();
                 wl_tool.frame(time);
             }
+
+            let serial = SERIAL_COUNTER.next_serial();
+            for (_, pad) in self.pads.lock().unwrap().values() {
+                if pad.focus().as_ref() == Some(focus) {
+                    pad.leave(serial);
+                }
+            }
         }
 
         self.focus = None;
@@ -218,6 +240,24 @@ pub struct TabletToolHandle {
 }
 
 impl TabletToolHandle {
+    pub(super) fn new(tablets: SeatTablets, pads: SeatPads) -> TabletToolHandle {
+        TabletToolHandle {
+            inner: Arc::new(Mutex::new(TabletTool {
+                instances: Vec::new(),
+                focus: None,
+                is_down: false,
+                pending_pressure: None,
+                pending_distance: None,
+                pending_tilt: None,
+                pending_slider: None,
+                pending_rotation: None,
+                pending_wheel: None,
+                tablets,
+                pads,
+            })),
+        }
+    }
+
     pub(super) fn new_instance<D, F>(
         &mut self,
         client: &Client,
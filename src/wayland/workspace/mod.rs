@@ -0,0 +1,1106 @@
+//! Utilities for handling the `ext-workspace` protocol
+//!
+//! The protocol is not part of `wayland-protocols` yet, its bindings are generated from a vendored
+//! definition and this module needs the `wayland_ext_workspace` feature.
+//!
+//! This protocol allows clients like panels or docks to list the workspaces of the compositor
+//! and request to activate, deactivate, remove, create or re-assign them.
+//!
+//! Workspaces are organized in groups, which are assigned a set of outputs. A compositor with
+//! independent workspaces per output would advertise one group per output, while a compositor with
+//! workspaces spanning all outputs would advertise a single group containing all outputs.
+//!
+//! The compositor creates groups and workspaces through the [`WorkspaceManagerState`] and updates
+//! their properties through the returned [`WorkspaceGroupHandle`] and [`WorkspaceHandle`].
+//! After a set of changes, [`WorkspaceManagerState::done`] needs to be called to let clients
+//! apply them atomically.
+//!
+//! Outputs assigned to a group are announced to clients as soon as they bind them.
+//!
+//! Requests of clients are collected and passed to [`WorkspaceHandler::commit_requests`] once
+//! the client commits them. Requests are only forwarded, if the matching capability was
+//! advertised for the workspace or group.
+//!
+//! ### Example
+//!
+//! ```no_run
+//! # extern crate wayland_server;
+//! #
+//! use smithay::{
+//!     delegate_workspace,
+//!     wayland::workspace::{
+//!         protocol::ext_workspace_handle_v1::{State as WorkspaceStates, WorkspaceCapabilities},
+//!         WorkspaceHandler, WorkspaceManagerState, WorkspaceRequest,
+//!     },
+//! };
+//!
+//! pub struct State {
+//!     workspace_state: WorkspaceManagerState,
+//! }
+//!
+//! impl WorkspaceHandler for State {
+//!     fn workspace_state(&mut self) -> &mut WorkspaceManagerState {
+//!         &mut self.workspace_state
+//!     }
+//!
+//!     fn commit_requests(&mut self, requests: Vec<WorkspaceRequest>) {
+//!         for request in requests {
+//!             // e.g. switch to the requested workspace
+//!         }
+//!     }
+//! }
+//!
+//! delegate_workspace!(State);
+//!
+//! # let mut display = wayland_server::Display::<State>::new().unwrap();
+//! # let display_handle = display.handle();
+//! let mut state = State {
+//!     workspace_state: WorkspaceManagerState::new::<State>(&display_handle),
+//! };
+//!
+//! let group = state.workspace_state.new_group::<State>();
+//! let workspace = state.workspace_state.new_workspace::<State>("1");
+//! workspace.set_capabilities(WorkspaceCapabilities::Activate);
+//! workspace.set_state(WorkspaceStates::Active);
+//! workspace.set_group(Some(&group));
+//! state.workspace_state.done();
+//! ```
+
+use std::sync::{Arc, Mutex, Weak};
+
+use wayland_server::{
+    backend::{ClientId, GlobalId, ObjectId},
+    protocol::wl_output::WlOutput,
+    Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New, Resource,
+};
+
+use crate::{output::Output, wayland::output::OutputBindHook};
+
+use self::protocol::{
+    ext_workspace_group_handle_v1::{self, ExtWorkspaceGroupHandleV1, GroupCapabilities},
+    ext_workspace_handle_v1::{self, ExtWorkspaceHandleV1, State as WorkspaceStates, WorkspaceCapabilities},
+    ext_workspace_manager_v1::{self, ExtWorkspaceManagerV1},
+};
+
+/// Generated bindings of the `ext-workspace-v1` protocol
+#[allow(
+    missing_docs,
+    missing_debug_implementations,
+    non_camel_case_types,
+    non_upper_case_globals,
+    non_snake_case,
+    unused_imports,
+    unused_unsafe,
+    unused_variables,
+    dead_code,
+    clippy::all
+)]
+pub mod protocol {
+    use wayland_server;
+    use wayland_server::protocol::*;
+
+    pub mod __interfaces {
+        use wayland_server::protocol::__interfaces::*;
+        wayland_scanner::generate_interfaces!("protocols/ext-workspace-v1.xml");
+    }
+    use self::__interfaces::*;
+
+    wayland_scanner::generate_server_code!("protocols/ext-workspace-v1.xml");
+}
+
+/// State of the ext workspace protocol
+#[derive(Debug)]
+pub struct WorkspaceManagerState {
+    global: GlobalId,
+    dh: DisplayHandle,
+    managers: Vec<ExtWorkspaceManagerV1>,
+    groups: Vec<WorkspaceGroupHandle>,
+    workspaces: Vec<WorkspaceHandle>,
+}
+
+/// Handler trait for the ext workspace protocol
+pub trait WorkspaceHandler {
+    /// [`WorkspaceManagerState`] getter
+    fn workspace_state(&mut self) -> &mut WorkspaceManagerState;
+
+    /// A client committed a set of requests, which should be applied atomically
+    ///
+    /// Send the resulting changes via the [`WorkspaceGroupHandle`]s and [`WorkspaceHandle`]s
+    /// and call [`WorkspaceManagerState::done`] afterwards.
+    fn commit_requests(&mut self, requests: Vec<WorkspaceRequest>);
+}
+
+/// Request of a client regarding workspaces
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorkspaceRequest {
+    /// Activate the workspace
+    Activate(WorkspaceHandle),
+    /// Deactivate the workspace
+    Deactivate(WorkspaceHandle),
+    /// Remove the workspace
+    Remove(WorkspaceHandle),
+    /// Assign the workspace to a group
+    Assign {
+        /// Workspace to re-assign
+        workspace: WorkspaceHandle,
+        /// Group to assign the workspace to
+        group: WorkspaceGroupHandle,
+    },
+    /// Create a new workspace in a group
+    Create {
+        /// Group the new workspace should be assigned to
+        group: WorkspaceGroupHandle,
+        /// Requested name of the new workspace
+        name: String,
+    },
+}
+
+#[derive(Debug)]
+struct GroupInner {
+    dh: DisplayHandle,
+    capabilities: GroupCapabilities,
+    // outputs with the hook announcing newly bound wl_outputs
+    outputs: Vec<(Output, Arc<OutputBindHook>)>,
+    instances: Vec<ExtWorkspaceGroupHandleV1>,
+}
+
+/// Handle to a workspace group
+#[derive(Debug, Clone)]
+pub struct WorkspaceGroupHandle {
+    inner: Arc<Mutex<GroupInner>>,
+}
+
+impl PartialEq for WorkspaceGroupHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+#[derive(Debug)]
+struct WorkspaceInner {
+    id: Option<String>,
+    name: String,
+    coordinates: Vec<u32>,
+    state: WorkspaceStates,
+    capabilities: WorkspaceCapabilities,
+    group: Option<WorkspaceGroupHandle>,
+    instances: Vec<ExtWorkspaceHandleV1>,
+}
+
+/// Handle to a workspace
+#[derive(Debug, Clone)]
+pub struct WorkspaceHandle {
+    inner: Arc<Mutex<WorkspaceInner>>,
+}
+
+impl PartialEq for WorkspaceHandle {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+/// User data of a [`ExtWorkspaceManagerV1`] object
+#[derive(Debug, Default)]
+pub struct WorkspaceManagerData {
+    pending: Mutex<Vec<WorkspaceRequest>>,
+}
+
+/// User data of a [`ExtWorkspaceGroupHandleV1`] object
+#[derive(Debug)]
+pub struct WorkspaceGroupData {
+    group: Weak<Mutex<GroupInner>>,
+    manager: ExtWorkspaceManagerV1,
+}
+
+/// User data of a [`ExtWorkspaceHandleV1`] object
+#[derive(Debug)]
+pub struct WorkspaceData {
+    workspace: Weak<Mutex<WorkspaceInner>>,
+    manager: ExtWorkspaceManagerV1,
+}
+
+/// Data associated with the [`ExtWorkspaceManagerV1`] global
+#[allow(missing_debug_implementations)]
+pub struct WorkspaceManagerGlobalData {
+    filter: Box<dyn for<'c> Fn(&'c Client) -> bool + Send + Sync>,
+}
+
+fn coordinates_to_bytes(coordinates: &[u32]) -> Vec<u8> {
+    coordinates.iter().flat_map(|c| c.to_ne_bytes()).collect()
+}
+
+impl WorkspaceManagerState {
+    /// Creates a new [`ExtWorkspaceManagerV1`] global visible to all clients.
+    pub fn new<D>(display: &DisplayHandle) -> Self
+    where
+        D: GlobalDispatch<ExtWorkspaceManagerV1, WorkspaceManagerGlobalData>
+            + Dispatch<ExtWorkspaceManagerV1, WorkspaceManagerData>
+            + Dispatch<ExtWorkspaceGroupHandleV1, WorkspaceGroupData>
+            + Dispatch<ExtWorkspaceHandleV1, WorkspaceData>
+            + WorkspaceHandler
+            + 'static,
+    {
+        Self::new_with_filter::<D, _>(display, |_| true)
+    }
+
+    /// Creates a new [`ExtWorkspaceManagerV1`] global.
+    ///
+    /// The `filter` decides, which clients may see and bind the global.
+    pub fn new_with_filter<D, F>(display: &DisplayHandle, filter: F) -> Self
+    where
+        D: GlobalDispatch<ExtWorkspaceManagerV1, WorkspaceManagerGlobalData>
+            + Dispatch<ExtWorkspaceManagerV1, WorkspaceManagerData>
+            + Dispatch<ExtWorkspaceGroupHandleV1, WorkspaceGroupData>
+            + Dispatch<ExtWorkspaceHandleV1, WorkspaceData>
+            + WorkspaceHandler
+            + 'static,
+        F: for<'c> Fn(&'c Client) -> bool + Send + Sync + 'static,
+    {
+        let global = display.create_global::<D, ExtWorkspaceManagerV1, _>(
+            1,
+            WorkspaceManagerGlobalData {
+                filter: Box::new(filter),
+            },
+        );
+
+        WorkspaceManagerState {
+            global,
+            dh: display.clone(),
+            managers: Vec::new(),
+            groups: Vec::new(),
+            workspaces: Vec::new(),
+        }
+    }
+
+    /// Returns the [`ExtWorkspaceManagerV1`] global id
+    pub fn global(&self) -> GlobalId {
+        self.global.clone()
+    }
+
+    /// Iterates over all workspace groups
+    pub fn groups(&self) -> impl Iterator<Item = &WorkspaceGroupHandle> {
+        self.groups.iter()
+    }
+
+    /// Iterates over all workspaces
+    pub fn workspaces(&self) -> impl Iterator<Item = &WorkspaceHandle> {
+        self.workspaces.iter()
+    }
+
+    /// Creates a new workspace group without any outputs
+    pub fn new_group<D>(&mut self) -> WorkspaceGroupHandle
+    where
+        D: Dispatch<ExtWorkspaceGroupHandleV1, WorkspaceGroupData> + 'static,
+    {
+        let group = WorkspaceGroupHandle {
+            inner: Arc::new(Mutex::new(GroupInner {
+                dh: self.dh.clone(),
+                capabilities: GroupCapabilities::empty(),
+                outputs: Vec::new(),
+                instances: Vec::new(),
+            })),
+        };
+        for manager in &self.managers {
+            group.new_instance::<D>(&self.dh, manager);
+        }
+        self.groups.push(group.clone());
+        group
+    }
+
+    /// Removes a workspace group
+    ///
+    /// All workspaces of the group are unassigned first.
+    pub fn remove_group(&mut self, group: &WorkspaceGroupHandle) {
+        for workspace in &self.workspaces {
+            if workspace.group().as_ref() == Some(group) {
+                workspace.set_group(None);
+            }
+        }
+        for instance in group.inner.lock().unwrap().instances.drain(..) {
+            instance.removed();
+        }
+        self.groups.retain(|g| g != group);
+    }
+
+    /// Creates a new workspace, which is not assigned to any group
+    pub fn new_workspace<D>(&mut self, name: impl Into<String>) -> WorkspaceHandle
+    where
+        D: Dispatch<ExtWorkspaceHandleV1, WorkspaceData> + 'static,
+    {
+        let workspace = WorkspaceHandle {
+            inner: Arc::new(Mutex::new(WorkspaceInner {
+                id: None,
+                name: name.into(),
+                coordinates: Vec::new(),
+                state: WorkspaceStates::empty(),
+                capabilities: WorkspaceCapabilities::empty(),
+                group: None,
+                instances: Vec::new(),
+            })),
+        };
+        for manager in &self.managers {
+            workspace.new_instance::<D>(&self.dh, manager);
+        }
+        self.workspaces.push(workspace.clone());
+        workspace
+    }
+
+    /// Removes a workspace
+    ///
+    /// The workspace is unassigned from its group first.
+    pub fn remove_workspace(&mut self, workspace: &WorkspaceHandle) {
+        workspace.set_group(None);
+        for instance in workspace.inner.lock().unwrap().instances.drain(..) {
+            instance.removed();
+        }
+        self.workspaces.retain(|w| w != workspace);
+    }
+
+    // stops sending events to a manager and all its objects
+    fn remove_manager(&mut self, manager: &ObjectId) {
+        self.managers.retain(|m| &m.id() != manager);
+        for group in &self.groups {
+            group.inner.lock().unwrap().instances.retain(|instance| {
+                instance
+                    .data::<WorkspaceGroupData>()
+                    .map(|data| &data.manager.id() != manager)
+                    .unwrap_or(false)
+            });
+        }
+        for workspace in &self.workspaces {
+            workspace.inner.lock().unwrap().instances.retain(|instance| {
+                instance
+                    .data::<WorkspaceData>()
+                    .map(|data| &data.manager.id() != manager)
+                    .unwrap_or(false)
+            });
+        }
+    }
+
+    /// Notifies clients, that all pending changes have been sent
+    pub fn done(&self) {
+        for manager in &self.managers {
+            manager.done();
+        }
+    }
+}
+
+impl WorkspaceGroupHandle {
+    /// Attempts to retrieve the group of a [`ExtWorkspaceGroupHandleV1`] object
+    pub fn from_resource(resource: &ExtWorkspaceGroupHandleV1) -> Option<Self> {
+        resource
+            .data::<WorkspaceGroupData>()
+            .and_then(|data| data.group.upgrade())
+            .map(|inner| WorkspaceGroupHandle { inner })
+    }
+
+    fn new_instance<D>(&self, dh: &DisplayHandle, manager: &ExtWorkspaceManagerV1)
+    where
+        D: Dispatch<ExtWorkspaceGroupHandleV1, WorkspaceGroupData> + 'static,
+    {
+        let client = match dh.get_client(manager.id()) {
+            Ok(client) => client,
+            Err(_) => return,
+        };
+        let instance = match client.create_resource::<ExtWorkspaceGroupHandleV1, _, D>(
+            dh,
+            manager.version(),
+            WorkspaceGroupData {
+                group: Arc::downgrade(&self.inner),
+                manager: manager.clone(),
+            },
+        ) {
+            Ok(instance) => instance,
+            Err(_) => return,
+        };
+        manager.workspace_group(&instance);
+
+        let mut inner = self.inner.lock().unwrap();
+        instance.capabilities(inner.capabilities);
+        for (output, _) in &inner.outputs {
+            output.with_client_outputs(&client, |wl_output| instance.output_enter(wl_output));
+        }
+        inner.instances.push(instance);
+    }
+
+    /// Returns the capabilities of this group
+    pub fn capabilities(&self) -> GroupCapabilities {
+        self.inner.lock().unwrap().capabilities
+    }
+
+    /// Sets the capabilities of this group
+    pub fn set_capabilities(&self, capabilities: GroupCapabilities) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capabilities != capabilities {
+            inner.capabilities = capabilities;
+            for instance in &inner.instances {
+                instance.capabilities(capabilities);
+            }
+        }
+    }
+
+    /// Returns the outputs assigned to this group
+    pub fn outputs(&self) -> Vec<Output> {
+        self.inner
+            .lock()
+            .unwrap()
+            .outputs
+            .iter()
+            .map(|(output, _)| output.clone())
+            .collect()
+    }
+
+    /// Assigns an output to this group
+    pub fn output_enter(&self, output: &Output) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.outputs.iter().any(|(o, _)| o == output) {
+            return;
+        }
+        let group = Arc::downgrade(&self.inner);
+        let hook: Arc<OutputBindHook> = Arc::new(move |wl_output: &WlOutput| output_bound(&group, wl_output));
+        output.add_bind_hook(&hook);
+        inner.outputs.push((output.clone(), hook));
+        for instance in &inner.instances {
+            if let Ok(client) = inner.dh.get_client(instance.id()) {
+                output.with_client_outputs(&client, |wl_output| instance.output_enter(wl_output));
+            }
+        }
+    }
+
+    /// Removes an output from this group
+    pub fn output_leave(&self, output: &Output) {
+        let mut inner = self.inner.lock().unwrap();
+        if !inner.outputs.iter().any(|(o, _)| o == output) {
+            return;
+        }
+        // dropping the hook unregisters it
+        inner.outputs.retain(|(o, _)| o != output);
+        for instance in &inner.instances {
+            if let Ok(client) = inner.dh.get_client(instance.id()) {
+                output.with_client_outputs(&client, |wl_output| instance.output_leave(wl_output));
+            }
+        }
+    }
+
+    // calls `f` for every pair of group and workspace instances belonging to the same manager
+    fn with_matching_instances<F>(&self, workspace: &WorkspaceInner, mut f: F)
+    where
+        F: FnMut(&ExtWorkspaceGroupHandleV1, &ExtWorkspaceHandleV1),
+    {
+        let inner = self.inner.lock().unwrap();
+        for group_instance in &inner.instances {
+            let manager = match group_instance.data::<WorkspaceGroupData>() {
+                Some(data) => &data.manager,
+                None => continue,
+            };
+            for instance in &workspace.instances {
+                if instance.data::<WorkspaceData>().map(|data| &data.manager) == Some(manager) {
+                    f(group_instance, instance);
+                }
+            }
+        }
+    }
+}
+
+// announces a wl_output bound after its output was assigned to a group
+fn output_bound(group: &Weak<Mutex<GroupInner>>, wl_output: &WlOutput) {
+    let group = match group.upgrade() {
+        Some(group) => group,
+        None => return,
+    };
+    let inner = group.lock().unwrap();
+    let client = match inner.dh.get_client(wl_output.id()) {
+        Ok(client) => client,
+        Err(_) => return,
+    };
+    for instance in &inner.instances {
+        if inner.dh.get_client(instance.id()).ok().as_ref() != Some(&client) {
+            continue;
+        }
+        instance.output_enter(wl_output);
+        if let Some(data) = instance.data::<WorkspaceGroupData>() {
+            data.manager.done();
+        }
+    }
+}
+
+impl WorkspaceHandle {
+    /// Attempts to retrieve the workspace of a [`ExtWorkspaceHandleV1`] object
+    pub fn from_resource(resource: &ExtWorkspaceHandleV1) -> Option<Self> {
+        resource
+            .data::<WorkspaceData>()
+            .and_then(|data| data.workspace.upgrade())
+            .map(|inner| WorkspaceHandle { inner })
+    }
+
+    fn new_instance<D>(&self, dh: &DisplayHandle, manager: &ExtWorkspaceManagerV1)
+    where
+        D: Dispatch<ExtWorkspaceHandleV1, WorkspaceData> + 'static,
+    {
+        let client = match dh.get_client(manager.id()) {
+            Ok(client) => client,
+            Err(_) => return,
+        };
+        let instance = match client.create_resource::<ExtWorkspaceHandleV1, _, D>(
+            dh,
+            manager.version(),
+            WorkspaceData {
+                workspace: Arc::downgrade(&self.inner),
+                manager: manager.clone(),
+            },
+        ) {
+            Ok(instance) => instance,
+            Err(_) => return,
+        };
+        manager.workspace(&instance);
+
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(id) = inner.id.clone() {
+            instance.id(id);
+        }
+        instance.name(inner.name.clone());
+        if !inner.coordinates.is_empty() {
+            instance.coordinates(coordinates_to_bytes(&inner.coordinates));
+        }
+        instance.state(inner.state);
+        instance.capabilities(inner.capabilities);
+        inner.instances.push(instance.clone());
+
+        if let Some(group) = inner.group.clone() {
+            group.with_matching_instances(&inner, |group_instance, workspace_instance| {
+                if workspace_instance == &instance {
+                    group_instance.workspace_enter(workspace_instance);
+                }
+            });
+        }
+    }
+
+    /// Returns the stable id of this workspace, if any
+    pub fn id(&self) -> Option<String> {
+        self.inner.lock().unwrap().id.clone()
+    }
+
+    /// Sets a stable id for this workspace
+    ///
+    /// Ids may only be assigned once, later calls are ignored.
+    /// Only assign ids to workspaces, which are likely stable across sessions.
+    pub fn set_id(&self, id: impl Into<String>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.id.is_some() {
+            return;
+        }
+        let id = id.into();
+        for instance in &inner.instances {
+            instance.id(id.clone());
+        }
+        inner.id = Some(id);
+    }
+
+    /// Returns the name of this workspace
+    pub fn name(&self) -> String {
+        self.inner.lock().unwrap().name.clone()
+    }
+
+    /// Sets the human readable name of this workspace
+    pub fn set_name(&self, name: impl Into<String>) {
+        let mut inner = self.inner.lock().unwrap();
+        let name = name.into();
+        if inner.name != name {
+            for instance in &inner.instances {
+                instance.name(name.clone());
+            }
+            inner.name = name;
+        }
+    }
+
+    /// Returns the coordinates of this workspace inside its group
+    pub fn coordinates(&self) -> Vec<u32> {
+        self.inner.lock().unwrap().coordinates.clone()
+    }
+
+    /// Sets the coordinates of this workspace inside its group
+    ///
+    /// Coordinates may have any number of dimensions, but need to be unique and of the same
+    /// dimensionality inside a group. Empty coordinates reset the geometric ordering.
+    pub fn set_coordinates(&self, coordinates: Vec<u32>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.coordinates != coordinates {
+            let bytes = coordinates_to_bytes(&coordinates);
+            for instance in &inner.instances {
+                instance.coordinates(bytes.clone());
+            }
+            inner.coordinates = coordinates;
+        }
+    }
+
+    /// Returns the state of this workspace
+    pub fn state(&self) -> WorkspaceStates {
+        self.inner.lock().unwrap().state
+    }
+
+    /// Sets the state of this workspace
+    pub fn set_state(&self, state: WorkspaceStates) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state != state {
+            inner.state = state;
+            for instance in &inner.instances {
+                instance.state(state);
+            }
+        }
+    }
+
+    /// Returns the capabilities of this workspace
+    pub fn capabilities(&self) -> WorkspaceCapabilities {
+        self.inner.lock().unwrap().capabilities
+    }
+
+    /// Sets the capabilities of this workspace
+    pub fn set_capabilities(&self, capabilities: WorkspaceCapabilities) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.capabilities != capabilities {
+            inner.capabilities = capabilities;
+            for instance in &inner.instances {
+                instance.capabilities(capabilities);
+            }
+        }
+    }
+
+    /// Returns the group this workspace is assigned to
+    pub fn group(&self) -> Option<WorkspaceGroupHandle> {
+        self.inner.lock().unwrap().group.clone()
+    }
+
+    /// Assigns this workspace to a group or unassigns it, if `None` is given
+    pub fn set_group(&self, group: Option<&WorkspaceGroupHandle>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.group.as_ref() == group {
+            return;
+        }
+        if let Some(old) = inner.group.take() {
+            old.with_matching_instances(&inner, |group_instance, instance| {
+                group_instance.workspace_leave(instance)
+            });
+        }
+        if let Some(group) = group {
+            group.with_matching_instances(&inner, |group_instance, instance| {
+                group_instance.workspace_enter(instance)
+            });
+        }
+        inner.group = group.cloned();
+    }
+}
+
+impl<D> GlobalDispatch<ExtWorkspaceManagerV1, WorkspaceManagerGlobalData, D> for WorkspaceManagerState
+where
+    D: GlobalDispatch<ExtWorkspaceManagerV1, WorkspaceManagerGlobalData>
+        + Dispatch<ExtWorkspaceManagerV1, WorkspaceManagerData>
+        + Dispatch<ExtWorkspaceGroupHandleV1, WorkspaceGroupData>
+        + Dispatch<ExtWorkspaceHandleV1, WorkspaceData>
+        + WorkspaceHandler
+        + 'static,
+{
+    fn bind(
+        state: &mut D,
+        dh: &DisplayHandle,
+        _client: &Client,
+        resource: New<ExtWorkspaceManagerV1>,
+        _global_data: &WorkspaceManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        let manager = data_init.init(resource, WorkspaceManagerData::default());
+
+        let workspace_state = state.workspace_state();
+        for group in &workspace_state.groups {
+            group.new_instance::<D>(dh, &manager);
+        }
+        // workspaces announce themselves to their group, which needs to exist already
+        for workspace in &workspace_state.workspaces {
+            workspace.new_instance::<D>(dh, &manager);
+        }
+        manager.done();
+        workspace_state.managers.push(manager);
+    }
+
+    fn can_view(client: Client, global_data: &WorkspaceManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ExtWorkspaceManagerV1, WorkspaceManagerData, D> for WorkspaceManagerState
+where
+    D: Dispatch<ExtWorkspaceManagerV1, WorkspaceManagerData> + WorkspaceHandler + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ExtWorkspaceManagerV1,
+        request: ext_workspace_manager_v1::Request,
+        data: &WorkspaceManagerData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_workspace_manager_v1::Request::Commit => {
+                let requests = std::mem::take(&mut *data.pending.lock().unwrap());
+                if !requests.is_empty() {
+                    state.commit_requests(requests);
+                }
+            }
+            ext_workspace_manager_v1::Request::Stop => {
+                state.workspace_state().remove_manager(&resource.id());
+                resource.finished();
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: ObjectId, _data: &WorkspaceManagerData) {
+        state.workspace_state().remove_manager(&resource);
+    }
+}
+
+impl<D> Dispatch<ExtWorkspaceGroupHandleV1, WorkspaceGroupData, D> for WorkspaceManagerState
+where
+    D: Dispatch<ExtWorkspaceGroupHandleV1, WorkspaceGroupData> + WorkspaceHandler + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ExtWorkspaceGroupHandleV1,
+        request: ext_workspace_group_handle_v1::Request,
+        data: &WorkspaceGroupData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            ext_workspace_group_handle_v1::Request::CreateWorkspace { workspace } => {
+                let group = match data.group.upgrade() {
+                    Some(inner) => WorkspaceGroupHandle { inner },
+                    None => return,
+                };
+                if !group.capabilities().contains(GroupCapabilities::CreateWorkspace) {
+                    return;
+                }
+                if let Some(manager_data) = data.manager.data::<WorkspaceManagerData>() {
+                    manager_data
+                        .pending
+                        .lock()
+                        .unwrap()
+                        .push(WorkspaceRequest::Create {
+                            group,
+                            name: workspace,
+                        });
+                }
+            }
+            ext_workspace_group_handle_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(_state: &mut D, _client: ClientId, resource: ObjectId, data: &WorkspaceGroupData) {
+        if let Some(inner) = data.group.upgrade() {
+            inner
+                .lock()
+                .unwrap()
+                .instances
+                .retain(|instance| instance.id() != resource);
+        }
+    }
+}
+
+impl<D> Dispatch<ExtWorkspaceHandleV1, WorkspaceData, D> for WorkspaceManagerState
+where
+    D: Dispatch<ExtWorkspaceHandleV1, WorkspaceData> + WorkspaceHandler + 'static,
+{
+    fn request(
+        _state: &mut D,
+        _client: &Client,
+        _resource: &ExtWorkspaceHandleV1,
+        request: ext_workspace_handle_v1::Request,
+        data: &WorkspaceData,
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        let workspace = match data.workspace.upgrade() {
+            Some(inner) => WorkspaceHandle { inner },
+            None => return,
+        };
+        let capabilities = workspace.capabilities();
+
+        let request = match request {
+            ext_workspace_handle_v1::Request::Activate
+                if capabilities.contains(WorkspaceCapabilities::Activate) =>
+            {
+                WorkspaceRequest::Activate(workspace)
+            }
+            ext_workspace_handle_v1::Request::Deactivate
+                if capabilities.contains(WorkspaceCapabilities::Deactivate) =>
+            {
+                WorkspaceRequest::Deactivate(workspace)
+            }
+            ext_workspace_handle_v1::Request::Remove
+                if capabilities.contains(WorkspaceCapabilities::Remove) =>
+            {
+                WorkspaceRequest::Remove(workspace)
+            }
+            ext_workspace_handle_v1::Request::Assign { workspace_group }
+                if capabilities.contains(WorkspaceCapabilities::Assign) =>
+            {
+                match WorkspaceGroupHandle::from_resource(&workspace_group) {
+                    Some(group) => WorkspaceRequest::Assign { workspace, group },
+                    None => return,
+                }
+            }
+            // unsupported requests are ignored
+            _ => return,
+        };
+
+        if let Some(manager_data) = data.manager.data::<WorkspaceManagerData>() {
+            manager_data.pending.lock().unwrap().push(request);
+        }
+    }
+
+    fn destroyed(_state: &mut D, _client: ClientId, resource: ObjectId, data: &WorkspaceData) {
+        if let Some(inner) = data.workspace.upgrade() {
+            inner
+                .lock()
+                .unwrap()
+                .instances
+                .retain(|instance| instance.id() != resource);
+        }
+    }
+}
+
+/// Macro to delegate implementation of the ext workspace protocol to [`WorkspaceManagerState`].
+///
+/// You must also implement [`WorkspaceHandler`] to use this.
+#[macro_export]
+macro_rules! delegate_workspace {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        $crate::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::workspace::protocol::ext_workspace_manager_v1::ExtWorkspaceManagerV1: $crate::wayland::workspace::WorkspaceManagerGlobalData
+        ] => $crate::wayland::workspace::WorkspaceManagerState);
+
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::workspace::protocol::ext_workspace_manager_v1::ExtWorkspaceManagerV1: $crate::wayland::workspace::WorkspaceManagerData
+        ] => $crate::wayland::workspace::WorkspaceManagerState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::workspace::protocol::ext_workspace_group_handle_v1::ExtWorkspaceGroupHandleV1: $crate::wayland::workspace::WorkspaceGroupData
+        ] => $crate::wayland::workspace::WorkspaceManagerState);
+        $crate::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            $crate::wayland::workspace::protocol::ext_workspace_handle_v1::ExtWorkspaceHandleV1: $crate::wayland::workspace::WorkspaceData
+        ] => $crate::wayland::workspace::WorkspaceManagerState);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        delegate_output,
+        output::{PhysicalProperties, Subpixel},
+        wayland::output::{OutputManagerState, OutputUserData},
+    };
+    use std::{
+        io::{ErrorKind, Read},
+        os::unix::net::UnixStream,
+    };
+    use wayland_server::{
+        backend::{ClientData, DisconnectReason},
+        Display,
+    };
+
+    // event opcodes, in the order of the protocol definition
+    const MANAGER_DONE: u16 = 2;
+    const GROUP_OUTPUT_ENTER: u16 = 1;
+
+    struct State {
+        workspace_state: WorkspaceManagerState,
+    }
+
+    impl WorkspaceHandler for State {
+        fn workspace_state(&mut self) -> &mut WorkspaceManagerState {
+            &mut self.workspace_state
+        }
+
+        fn commit_requests(&mut self, _requests: Vec<WorkspaceRequest>) {}
+    }
+
+    delegate_workspace!(State);
+    delegate_output!(State);
+
+    struct TestClientData;
+
+    impl ClientData for TestClientData {
+        fn initialized(&self, _client_id: ClientId) {}
+        fn disconnected(&self, _client_id: ClientId, _reason: DisconnectReason) {}
+    }
+
+    struct Setup {
+        display: Display<State>,
+        state: State,
+        client: Client,
+        stream: UnixStream,
+        manager: ExtWorkspaceManagerV1,
+    }
+
+    impl Setup {
+        // a client with a bound workspace manager
+        fn new() -> Setup {
+            let display = Display::<State>::new().unwrap();
+            let dh = display.handle();
+            let (stream, server_stream) = UnixStream::pair().unwrap();
+            stream.set_nonblocking(true).unwrap();
+            let client = dh.insert_client(server_stream, Arc::new(TestClientData)).unwrap();
+
+            let mut state = State {
+                workspace_state: WorkspaceManagerState::new::<State>(&dh),
+            };
+            let manager = client
+                .create_resource::<ExtWorkspaceManagerV1, _, State>(&dh, 1, WorkspaceManagerData::default())
+                .unwrap();
+            state.workspace_state.managers.push(manager.clone());
+
+            Setup {
+                display,
+                state,
+                client,
+                stream,
+                manager,
+            }
+        }
+
+        // object ids and opcodes of all events sent to the client so far
+        fn events(&mut self) -> Vec<(u32, u16)> {
+            self.display.flush_clients().unwrap();
+            let mut bytes = Vec::new();
+            let mut buffer = [0u8; 4096];
+            loop {
+                match self.stream.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(len) => bytes.extend_from_slice(&buffer[..len]),
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                    Err(err) => panic!("{}", err),
+                }
+            }
+
+            let word = |offset: usize| u32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap());
+            let mut events = Vec::new();
+            let mut offset = 0;
+            while offset + 8 <= bytes.len() {
+                let size = (word(offset + 4) >> 16) as usize;
+                events.push((word(offset), word(offset + 4) as u16));
+                offset += size;
+            }
+            events
+        }
+
+        // simulates the client binding a wl_output of `output`
+        fn bind_output(&self, output: &Output) -> WlOutput {
+            let dh = self.display.handle();
+            let wl_output = self
+                .client
+                .create_resource::<WlOutput, _, State>(
+                    &dh,
+                    4,
+                    OutputUserData {
+                        global_data: output.inner.clone(),
+                    },
+                )
+                .unwrap();
+            let hooks = {
+                let mut inner = output.inner.0.lock().unwrap();
+                inner.instances.push(wl_output.clone());
+                inner.bind_hooks.alive()
+            };
+            for hook in hooks {
+                hook(&wl_output);
+            }
+            wl_output
+        }
+    }
+
+    fn output() -> Output {
+        Output::new(
+            "test".into(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: "make".into(),
+                model: "model".into(),
+                hdr: None,
+            },
+            None,
+        )
+    }
+
+    #[test]
+    fn outputs_bound_later_are_announced() {
+        let mut setup = Setup::new();
+        let output = output();
+        output.create_global::<State>(&setup.display.handle());
+
+        let group = setup.state.workspace_state.new_group::<State>();
+        group.output_enter(&output);
+        let instance = group.inner.lock().unwrap().instances[0].id().protocol_id();
+        assert!(!setup.events().contains(&(instance, GROUP_OUTPUT_ENTER)));
+
+        setup.bind_output(&output);
+        let events = setup.events();
+        let manager = setup.manager.id().protocol_id();
+        let enter = events.iter().position(|e| e == &(instance, GROUP_OUTPUT_ENTER));
+        let done = events.iter().position(|e| e == &(manager, MANAGER_DONE));
+        assert!(matches!((enter, done), (Some(enter), Some(done)) if enter < done));
+
+        // leaving the group unregisters the hook
+        group.output_leave(&output);
+        assert!(output.inner.0.lock().unwrap().bind_hooks.alive().is_empty());
+        setup.bind_output(&output);
+        assert!(!setup.events().contains(&(instance, GROUP_OUTPUT_ENTER)));
+    }
+
+    #[test]
+    fn removed_manager_drops_instances() {
+        let mut setup = Setup::new();
+        let group = setup.state.workspace_state.new_group::<State>();
+        let workspace = setup.state.workspace_state.new_workspace::<State>("1");
+        workspace.set_group(Some(&group));
+        assert_eq!(group.inner.lock().unwrap().instances.len(), 1);
+        assert_eq!(workspace.inner.lock().unwrap().instances.len(), 1);
+        setup.events();
+
+        setup.state.workspace_state.remove_manager(&setup.manager.id());
+        assert!(setup.state.workspace_state.managers.is_empty());
+        assert!(group.inner.lock().unwrap().instances.is_empty());
+        assert!(workspace.inner.lock().unwrap().instances.is_empty());
+
+        // no more events are sent to the client
+        workspace.set_name("2");
+        group.set_capabilities(GroupCapabilities::CreateWorkspace);
+        setup.state.workspace_state.done();
+        assert!(setup.events().is_empty());
+    }
+
+    #[test]
+    fn new_objects_are_announced_to_managers() {
+        let mut setup = Setup::new();
+        let group = setup.state.workspace_state.new_group::<State>();
+        let workspace = setup.state.workspace_state.new_workspace::<State>("1");
+        workspace.set_group(Some(&group));
+
+        let group_instance = group.inner.lock().unwrap().instances[0].clone();
+        let workspace_instance = workspace.inner.lock().unwrap().instances[0].clone();
+        assert_eq!(
+            WorkspaceGroupHandle::from_resource(&group_instance),
+            Some(group.clone())
+        );
+        assert_eq!(
+            WorkspaceHandle::from_resource(&workspace_instance),
+            Some(workspace)
+        );
+
+        setup.state.workspace_state.remove_group(&group);
+        assert!(group.inner.lock().unwrap().instances.is_empty());
+        assert!(!setup.events().is_empty());
+    }
+}
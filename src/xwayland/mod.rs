@@ -5,14 +5,31 @@
 //!
 //! The starting point is the [`XWayland`] struct, which represents the
 //! running XWayland instance. Dropping it will shutdown XWayland.
+//! Through [`XWaylandConfig`] XWayland can be started lazily once the first X11 client
+//! connects and restarted after crashes.
 //!
 //! You need to provide an implementation of a X11 Window Manager for XWayland to
 //! function properly. You'll need to treat XWayland (and all its X11 apps) as one
 //! special client, and play the role of an X11 Window Manager.
 //!
-//! Smithay does not provide any helper for doing that yet, but it is planned.
+//! Smithay does not provide a full window manager yet, but the [`selection`] module
+//! bridges the clipboard, primary selection and drag'n'drop between X11 and wayland
+//! clients on top of your window manager connection.
+//!
+//! ## HiDPI
+//!
+//! X11 clients have no notion of output scales. To have them render crisply on scaled
+//! outputs, XWayland can be run at the highest output scale `N`: advertise its outputs
+//! `N` times larger with [`Output::set_xdg_output_client_scale`](crate::output::Output::set_xdg_output_client_scale)
+//! and set the scale of every X11 window through [`X11Surface::set_scale`](crate::desktop::X11Surface::set_scale).
+//! X11 window geometry is then expressed in a coordinate space `N` times larger than the
+//! logical one and needs to be translated by your window manager, e.g. using
+//! [`Point::to_logical`](crate::utils::Point::to_logical) and
+//! [`Point::to_physical`](crate::utils::Point::to_physical).
+//! Input and opaque regions set by XWayland are scaled along with its buffers.
 
+pub mod selection;
 mod x11_sockets;
 mod xserver;
 
-pub use self::xserver::{XWayland, XWaylandEvent, XWaylandSource};
+pub use self::xserver::{XWayland, XWaylandConfig, XWaylandEvent, XWaylandSource};
@@ -0,0 +1,1338 @@
+//! Selection and drag'n'drop bridging between wayland and X11 clients
+//!
+//! XWayland does not use the wayland data device or primary selection protocols.
+//! Instead the X11 window manager has to translate between the X11 selections and
+//! their wayland counterparts. [`XWaylandSelection`] implements this translation on an
+//! X11 connection of your window manager:
+//!
+//! - The `CLIPBOARD` and `PRIMARY` selections are bridged to the
+//!   [data device](crate::wayland::data_device) and
+//!   [primary selection](crate::wayland::primary_selection) in both directions.
+//! - Selection targets are converted to and from mime types (e.g. `UTF8_STRING` is
+//!   offered to wayland clients as `text/plain;charset=utf-8`).
+//! - Transfers larger than a single X11 request use the `INCR` protocol.
+//! - Drag'n'drop operations of wayland clients are forwarded to X11 windows using the XDND protocol.
+//! - Drag'n'drop operations of X11 clients are forwarded to wayland clients. While such an operation
+//!   is over a wayland surface, a hidden XDND aware window is stacked above all X11 windows, which
+//!   acts as the drop target on behalf of the wayland clients.
+//!
+//! The bridge does not own the X11 connection. Pass every event of your window manager connection
+//! to [`XWaylandSelection::handle_event`] and the returned [`SelectionEvent`]s to
+//! [`XWaylandSelection::forward_event`], which updates the selections of a seat accordingly:
+//!
+//! ```no_run
+//! # use calloop::LoopHandle;
+//! # use smithay::input::Seat;
+//! # use smithay::reexports::wayland_server::DisplayHandle;
+//! # use smithay::wayland::{data_device::DataDeviceHandler, primary_selection::PrimarySelectionHandler};
+//! use smithay::xwayland::selection::XWaylandSelection;
+//!
+//! // for every event of the window manager connection
+//! fn handle_x11_event<D>(
+//!     selection: &mut XWaylandSelection,
+//!     event: &x11rb::protocol::Event,
+//!     handle: &LoopHandle<'static, D>,
+//!     dh: &DisplayHandle,
+//!     seat: &Seat<D>,
+//! ) where
+//!     D: DataDeviceHandler + PrimarySelectionHandler + 'static,
+//! {
+//!     if let Ok(Some(event)) = selection.handle_event(event, handle) {
+//!         selection.forward_event(event, dh, seat).expect("Failed to forward selection event");
+//!     }
+//! }
+//! ```
+//!
+//! Additionally the compositor has to
+//!
+//! - offer selections of wayland clients through [`XWaylandSelection::set_wayland_selection`],
+//! - forward requests of wayland clients for selections of X11 clients, including those of
+//!   [`ServerDndGrabHandler::send`](crate::wayland::data_device::ServerDndGrabHandler::send),
+//!   to [`XWaylandSelection::request_x11_selection`],
+//! - forward drag'n'drop operations of wayland clients to [`XWaylandSelection::start_wayland_dnd`]
+//!   and report their movement through [`XWaylandSelection::wayland_dnd_motion`],
+//! - report the pointer movement during drag'n'drop operations of X11 clients through
+//!   [`XWaylandSelection::x11_dnd_motion`] and the end of a drop through
+//!   [`XWaylandSelection::x11_dnd_finished`].
+
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::TryFrom,
+    sync::Arc,
+};
+
+use calloop::LoopHandle;
+use io_lifetimes::OwnedFd;
+use slog::{debug, trace, warn};
+use wayland_server::protocol::{wl_data_device_manager::DndAction, wl_data_source::WlDataSource};
+use x11rb::{
+    connection::Connection,
+    errors::{ConnectionError, ReplyError, ReplyOrIdError},
+    protocol::{
+        xfixes::{self, ConnectionExt as _, SelectionEventMask},
+        xproto::{
+            Atom, AtomEnum, ClientMessageEvent, ConfigureWindowAux, ConnectionExt as _, CreateWindowAux,
+            EventMask, PropMode, Property, PropertyNotifyEvent, SelectionNotifyEvent, SelectionRequestEvent,
+            StackMode, Timestamp, Window, WindowClass, CLIENT_MESSAGE_EVENT,
+        },
+        Event,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+};
+
+use crate::{
+    utils::{Logical, Point},
+    wayland::data_device::{with_source_metadata, ServerDnd},
+};
+
+mod transfer;
+mod wayland;
+
+use self::transfer::{notify, pipe, IncomingTransfer, OutgoingTransfer};
+
+/// Version of the XDND protocol implemented
+const XDND_VERSION: u32 = 5;
+
+const MIME_TEXT_UTF8: &str = "text/plain;charset=utf-8";
+const MIME_TEXT: &str = "text/plain";
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        CLIPBOARD,
+        PRIMARY,
+        TARGETS,
+        TIMESTAMP,
+        INCR,
+        UTF8_STRING,
+        TEXT,
+        STRING,
+        TEXT_PLAIN_UTF8: b"text/plain;charset=utf-8",
+        TEXT_PLAIN: b"text/plain",
+
+        _SMITHAY_CLIPBOARD,
+        _SMITHAY_CLIPBOARD_TARGETS,
+        _SMITHAY_PRIMARY,
+        _SMITHAY_PRIMARY_TARGETS,
+        _SMITHAY_DND,
+        _SMITHAY_DND_TARGETS,
+
+        XdndSelection,
+        XdndAware,
+        XdndTypeList,
+        XdndEnter,
+        XdndLeave,
+        XdndPosition,
+        XdndStatus,
+        XdndDrop,
+        XdndFinished,
+        XdndActionCopy,
+        XdndActionMove,
+        XdndActionAsk,
+    }
+}
+
+/// Errors of the [`XWaylandSelection`] bridge
+#[derive(Debug, thiserror::Error)]
+pub enum SelectionError {
+    /// The request to the X server failed
+    #[error(transparent)]
+    X11(#[from] ReplyOrIdError),
+    /// Creating or configuring the pipe of a transfer failed
+    #[error("Failed to setup a pipe for the selection transfer")]
+    Pipe(#[source] nix::Error),
+    /// The transfer could not be registered with the event loop
+    #[error("Failed to register the selection transfer with the event loop")]
+    EventLoop(#[source] calloop::Error),
+}
+
+impl From<ConnectionError> for SelectionError {
+    fn from(err: ConnectionError) -> Self {
+        SelectionError::X11(err.into())
+    }
+}
+
+impl From<ReplyError> for SelectionError {
+    fn from(err: ReplyError) -> Self {
+        SelectionError::X11(err.into())
+    }
+}
+
+/// X11 selections bridged by [`XWaylandSelection`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SelectionType {
+    /// The `CLIPBOARD` selection, corresponding to the selection of the data device
+    Clipboard,
+    /// The `PRIMARY` selection, corresponding to the primary selection
+    Primary,
+    /// The `XdndSelection`, transferring the data of drag'n'drop operations
+    Dnd,
+}
+
+/// Events generated by [`XWaylandSelection::handle_event`]
+#[derive(Debug)]
+pub enum SelectionEvent {
+    /// An X11 client took ownership of a selection
+    ///
+    /// For [`SelectionType::Dnd`] this means an X11 client started a drag'n'drop operation.
+    /// Requests of wayland clients for this selection should be forwarded
+    /// to [`XWaylandSelection::request_x11_selection`].
+    NewSelection {
+        /// The selection that changed
+        selection: SelectionType,
+        /// The mime types offered by the X11 client
+        mime_types: Vec<String>,
+    },
+    /// The X11 client owning a selection gave it up or went away
+    SelectionCleared {
+        /// The selection that was cleared
+        selection: SelectionType,
+    },
+    /// An X11 client requested the contents of a selection provided
+    /// by [`XWaylandSelection::set_wayland_selection`]
+    ///
+    /// The data should be written into `fd`, e.g. by forwarding it to the wayland client owning
+    /// the selection. Closing the fd ends the transfer.
+    SendSelection {
+        /// The requested selection
+        selection: SelectionType,
+        /// The requested mime type
+        mime_type: String,
+        /// The fd to write the data into
+        fd: OwnedFd,
+    },
+    /// The X11 window targeted by a wayland drag'n'drop operation chose an action
+    ///
+    /// An empty action means the window does not accept the drop at its current position.
+    DndAction {
+        /// The chosen action
+        action: DndAction,
+    },
+    /// The X11 window targeted by a wayland drag'n'drop operation finished the drop
+    DndFinished {
+        /// The action performed, empty if the drop was rejected
+        action: DndAction,
+    },
+    /// A drag'n'drop operation of an X11 client left the wayland side
+    ///
+    /// This happens when the pointer moves back over X11 windows or the operation is cancelled.
+    X11DndLeave,
+    /// A drag'n'drop operation of an X11 client was dropped on the wayland side
+    ///
+    /// The X11 client waits for [`XWaylandSelection::finish_x11_dnd`] afterwards.
+    X11DndDrop,
+}
+
+/// State of a drag'n'drop operation of a wayland client over X11 windows
+#[derive(Debug)]
+struct WaylandDnd {
+    target: Option<Window>,
+    version: u32,
+    accepted: bool,
+    action: DndAction,
+    dropped: bool,
+}
+
+impl Default for WaylandDnd {
+    fn default() -> Self {
+        WaylandDnd {
+            target: None,
+            version: XDND_VERSION,
+            accepted: false,
+            action: DndAction::empty(),
+            dropped: false,
+        }
+    }
+}
+
+/// State of a drag'n'drop operation of an X11 client over the proxy window
+#[derive(Debug)]
+struct X11Dnd {
+    source: Window,
+    version: u32,
+    dropped: bool,
+}
+
+/// Bridge between X11 selections and the wayland selection protocols
+///
+/// See the [module-level documentation](self) for details.
+#[derive(Debug)]
+pub struct XWaylandSelection {
+    conn: Arc<RustConnection>,
+    atoms: Atoms,
+    root: Window,
+    window: Window,
+    timestamp: Timestamp,
+    /// Mime types offered to X11 clients for selections owned by wayland clients
+    wayland_offers: HashMap<SelectionType, Vec<String>>,
+    /// X11 windows owning a selection
+    x11_owners: HashMap<SelectionType, Window>,
+    incoming: HashMap<SelectionType, VecDeque<IncomingTransfer>>,
+    outgoing: Vec<OutgoingTransfer>,
+    wayland_dnd: Option<WaylandDnd>,
+    /// Source of the wayland drag'n'drop operation offered to X11 windows
+    dnd_source: Option<WlDataSource>,
+    /// XDND aware window receiving drag'n'drop operations of X11 clients over wayland surfaces
+    proxy: Window,
+    proxy_mapped: bool,
+    x11_dnd: Option<X11Dnd>,
+    /// Action reported to the X11 drag'n'drop source through `XdndStatus`
+    x11_dnd_action: DndAction,
+    /// Forwards the drag'n'drop operation of an X11 client to wayland clients
+    x11_dnd_offer: Option<ServerDnd>,
+    mime_atoms: HashMap<String, Atom>,
+    atom_mimes: HashMap<Atom, Option<String>>,
+    log: slog::Logger,
+}
+
+impl XWaylandSelection {
+    /// Setup the selection bridge on an X11 connection to XWayland
+    ///
+    /// This creates hidden windows on `screen` owning the selections and receiving drag'n'drop
+    /// operations on behalf of wayland clients and starts tracking the selection owners through
+    /// the XFixes extension.
+    pub fn new<L>(conn: Arc<RustConnection>, screen: usize, logger: L) -> Result<Self, SelectionError>
+    where
+        L: Into<Option<::slog::Logger>>,
+    {
+        let log = crate::slog_or_fallback(logger).new(slog::o!("smithay_module" => "xwayland_selection"));
+        let atoms = Atoms::new(&*conn)?.reply()?;
+        let root = conn.setup().roots[screen].root;
+
+        let window = conn.generate_id()?;
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            // x, y, width, height, border width
+            -1,
+            -1,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )?;
+        conn.change_property32(
+            PropMode::REPLACE,
+            window,
+            atoms.XdndAware,
+            AtomEnum::ATOM,
+            &[XDND_VERSION],
+        )?;
+
+        // only mapped while an X11 drag'n'drop operation is over a wayland surface,
+        // it is resized to cover the root window then
+        let proxy = conn.generate_id()?;
+        conn.create_window(
+            0,
+            proxy,
+            root,
+            // x, y, width, height, border width
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new().override_redirect(1),
+        )?;
+        conn.change_property32(
+            PropMode::REPLACE,
+            proxy,
+            atoms.XdndAware,
+            AtomEnum::ATOM,
+            &[XDND_VERSION],
+        )?;
+
+        conn.xfixes_query_version(5, 0)?.reply()?;
+        let mask = SelectionEventMask::SET_SELECTION_OWNER
+            | SelectionEventMask::SELECTION_WINDOW_DESTROY
+            | SelectionEventMask::SELECTION_CLIENT_CLOSE;
+        for selection in [atoms.CLIPBOARD, atoms.PRIMARY, atoms.XdndSelection] {
+            conn.xfixes_select_selection_input(window, selection, mask)?;
+        }
+        conn.flush()?;
+
+        Ok(XWaylandSelection {
+            conn,
+            atoms,
+            root,
+            window,
+            timestamp: x11rb::CURRENT_TIME,
+            wayland_offers: HashMap::new(),
+            x11_owners: HashMap::new(),
+            incoming: HashMap::new(),
+            outgoing: Vec::new(),
+            wayland_dnd: None,
+            dnd_source: None,
+            proxy,
+            proxy_mapped: false,
+            x11_dnd: None,
+            x11_dnd_action: DndAction::empty(),
+            x11_dnd_offer: None,
+            mime_atoms: HashMap::new(),
+            atom_mimes: HashMap::new(),
+            log,
+        })
+    }
+
+    /// The window used by the bridge to own selections
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    /// Returns `true` if the selection is currently owned by an X11 client
+    pub fn x11_selection_owned(&self, selection: SelectionType) -> bool {
+        self.x11_owners.contains_key(&selection)
+    }
+
+    fn selection_atom(&self, selection: SelectionType) -> Atom {
+        match selection {
+            SelectionType::Clipboard => self.atoms.CLIPBOARD,
+            SelectionType::Primary => self.atoms.PRIMARY,
+            SelectionType::Dnd => self.atoms.XdndSelection,
+        }
+    }
+
+    fn selection_type(&self, atom: Atom) -> Option<SelectionType> {
+        if atom == self.atoms.CLIPBOARD {
+            Some(SelectionType::Clipboard)
+        } else if atom == self.atoms.PRIMARY {
+            Some(SelectionType::Primary)
+        } else if atom == self.atoms.XdndSelection {
+            Some(SelectionType::Dnd)
+        } else {
+            None
+        }
+    }
+
+    /// Property of our window receiving converted selection data
+    fn data_property(&self, selection: SelectionType) -> Atom {
+        match selection {
+            SelectionType::Clipboard => self.atoms._SMITHAY_CLIPBOARD,
+            SelectionType::Primary => self.atoms._SMITHAY_PRIMARY,
+            SelectionType::Dnd => self.atoms._SMITHAY_DND,
+        }
+    }
+
+    /// Property of our window receiving the `TARGETS` of a selection
+    fn targets_property(&self, selection: SelectionType) -> Atom {
+        match selection {
+            SelectionType::Clipboard => self.atoms._SMITHAY_CLIPBOARD_TARGETS,
+            SelectionType::Primary => self.atoms._SMITHAY_PRIMARY_TARGETS,
+            SelectionType::Dnd => self.atoms._SMITHAY_DND_TARGETS,
+        }
+    }
+
+    /// Selection targets of text, which are not named after their mime type
+    fn text_targets(&self) -> [(Atom, &'static str); 5] {
+        [
+            (self.atoms.UTF8_STRING, MIME_TEXT_UTF8),
+            (self.atoms.TEXT_PLAIN_UTF8, MIME_TEXT_UTF8),
+            (self.atoms.TEXT, MIME_TEXT),
+            (self.atoms.STRING, MIME_TEXT),
+            (self.atoms.TEXT_PLAIN, MIME_TEXT),
+        ]
+    }
+
+    /// Convert a mime type into the matching selection target
+    fn mime_to_atom(&mut self, mime_type: &str) -> Result<Atom, SelectionError> {
+        if let Some(atom) = target_for_mime(&self.text_targets(), mime_type) {
+            return Ok(atom);
+        }
+        if let Some(atom) = self.mime_atoms.get(mime_type) {
+            return Ok(*atom);
+        }
+
+        let atom = self.conn.intern_atom(false, mime_type.as_bytes())?.reply()?.atom;
+        self.mime_atoms.insert(mime_type.to_owned(), atom);
+        self.atom_mimes.insert(atom, Some(mime_type.to_owned()));
+        Ok(atom)
+    }
+
+    /// Convert a selection target into a mime type, if it represents data
+    fn atom_to_mime(&mut self, atom: Atom) -> Result<Option<String>, SelectionError> {
+        if let Some(mime_type) = mime_for_target(&self.text_targets(), atom) {
+            return Ok(Some(mime_type.to_owned()));
+        }
+        if let Some(mime_type) = self.atom_mimes.get(&atom) {
+            return Ok(mime_type.clone());
+        }
+
+        let name = self.conn.get_atom_name(atom)?.reply()?.name;
+        let mime_type = mime_from_atom_name(name);
+        if let Some(mime_type) = mime_type.as_ref() {
+            self.mime_atoms.insert(mime_type.clone(), atom);
+        }
+        self.atom_mimes.insert(atom, mime_type.clone());
+        Ok(mime_type)
+    }
+
+    /// Offer a selection of a wayland client to X11 clients
+    ///
+    /// Call this whenever a wayland client sets the selection with the mime types of the new
+    /// selection or `None` if the selection was cleared. Requests of X11 clients for the selection
+    /// are reported as [`SelectionEvent::SendSelection`].
+    ///
+    /// This should not be called for selections set in response to [`SelectionEvent::NewSelection`].
+    pub fn set_wayland_selection(
+        &mut self,
+        selection: SelectionType,
+        mime_types: Option<Vec<String>>,
+    ) -> Result<(), SelectionError> {
+        let atom = self.selection_atom(selection);
+        match mime_types {
+            Some(mime_types) => {
+                debug!(self.log, "Offering wayland selection to X11"; "selection" => ?selection, "mime_types" => ?mime_types);
+                self.wayland_offers.insert(selection, mime_types);
+                self.x11_owners.remove(&selection);
+                self.conn.set_selection_owner(self.window, atom, self.timestamp)?;
+            }
+            None => {
+                if self.wayland_offers.remove(&selection).is_some() {
+                    self.conn.set_selection_owner(x11rb::NONE, atom, self.timestamp)?;
+                }
+            }
+        }
+        self.conn.flush()?;
+        Ok(())
+    }
+
+    /// Request the contents of a selection owned by an X11 client
+    ///
+    /// The data will be written into `fd` in the requested mime type, which is closed
+    /// once the transfer is done. Transfers of the same selection are processed in order.
+    pub fn request_x11_selection<D: 'static>(
+        &mut self,
+        selection: SelectionType,
+        mime_type: String,
+        fd: OwnedFd,
+        handle: &LoopHandle<'static, D>,
+    ) -> Result<(), SelectionError> {
+        if !self.x11_owners.contains_key(&selection) {
+            debug!(self.log, "Ignoring request for selection not owned by X11"; "selection" => ?selection);
+            return Ok(());
+        }
+
+        let target = self.mime_to_atom(&mime_type)?;
+        let mut transfer = IncomingTransfer::new(mime_type, target, fd);
+        transfer.start(handle, &self.log)?;
+
+        let queue = self.incoming.entry(selection).or_default();
+        queue.push_back(transfer);
+        if queue.len() == 1 {
+            self.convert_next(selection)?;
+        }
+        Ok(())
+    }
+
+    /// Ask the owner of the selection to convert the selection for the next queued transfer
+    fn convert_next(&mut self, selection: SelectionType) -> Result<(), SelectionError> {
+        let target = match self.incoming.get(&selection).and_then(|queue| queue.front()) {
+            Some(transfer) => transfer.target,
+            None => return Ok(()),
+        };
+
+        trace!(self.log, "Converting X11 selection"; "selection" => ?selection, "target" => target);
+        self.conn.convert_selection(
+            self.window,
+            self.selection_atom(selection),
+            target,
+            self.data_property(selection),
+            self.timestamp,
+        )?;
+        self.conn.flush()?;
+        Ok(())
+    }
+
+    /// Finish the current transfer of a selection and start the next one
+    fn finish_transfer(&mut self, selection: SelectionType) -> Result<(), SelectionError> {
+        if let Some(mut transfer) = self
+            .incoming
+            .get_mut(&selection)
+            .and_then(|queue| queue.pop_front())
+        {
+            transfer.finish();
+        }
+        self.convert_next(selection)
+    }
+
+    /// Start forwarding a drag'n'drop operation of a wayland client to X11 windows
+    ///
+    /// Afterwards the position of the pointer needs to be reported through
+    /// [`XWaylandSelection::wayland_dnd_motion`] and the drop through [`XWaylandSelection::wayland_dnd_drop`].
+    /// The X11 window receiving the drop interacts with `source` through [`XWaylandSelection::forward_event`].
+    pub fn start_wayland_dnd(&mut self, source: WlDataSource) -> Result<(), SelectionError> {
+        let mime_types = with_source_metadata(&source, |meta| meta.mime_types.clone()).unwrap_or_default();
+        let targets = mime_types
+            .iter()
+            .map(|mime_type| self.mime_to_atom(mime_type))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.conn.change_property32(
+            PropMode::REPLACE,
+            self.window,
+            self.atoms.XdndTypeList,
+            AtomEnum::ATOM,
+            &targets,
+        )?;
+        self.wayland_dnd = Some(WaylandDnd::default());
+        self.dnd_source = Some(source);
+        self.set_wayland_selection(SelectionType::Dnd, Some(mime_types))
+    }
+
+    /// Report the position of a wayland drag'n'drop operation
+    ///
+    /// `target` is the X11 window under the pointer, if any, and `location` the position
+    /// of the pointer in the coordinate space of the X11 root window.
+    pub fn wayland_dnd_motion(
+        &mut self,
+        target: Option<Window>,
+        location: Point<i32, Logical>,
+        time: u32,
+    ) -> Result<(), SelectionError> {
+        let current = match self.wayland_dnd.as_ref() {
+            Some(dnd) if !dnd.dropped => dnd.target,
+            _ => return Ok(()),
+        };
+
+        if current != target {
+            if let Some(window) = current {
+                self.send_dnd_message(window, self.atoms.XdndLeave, [self.window, 0, 0, 0, 0])?;
+            }
+
+            let version = match target {
+                Some(window) => self.xdnd_version(window)?,
+                None => None,
+            };
+            let dnd = self.wayland_dnd.as_mut().unwrap();
+            dnd.target = None;
+            dnd.accepted = false;
+            dnd.action = DndAction::empty();
+
+            if let (Some(window), Some(version)) = (target, version) {
+                let dnd = self.wayland_dnd.as_mut().unwrap();
+                dnd.target = Some(window);
+                dnd.version = version;
+
+                let types = self
+                    .wayland_offers
+                    .get(&SelectionType::Dnd)
+                    .cloned()
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|mime_type| self.mime_to_atom(mime_type))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut data = [self.window, version << 24, 0, 0, 0];
+                if types.len() > 3 {
+                    // the target has to read the full list from XdndTypeList
+                    data[1] |= 1;
+                }
+                for (slot, atom) in data[2..].iter_mut().zip(types.iter()) {
+                    *slot = *atom;
+                }
+                self.send_dnd_message(window, self.atoms.XdndEnter, data)?;
+            }
+        }
+
+        if let Some(window) = target.filter(|_| self.wayland_dnd.as_ref().unwrap().target.is_some()) {
+            let x = u32::from(u16::try_from(location.x.max(0)).unwrap_or(u16::MAX));
+            let y = u32::from(u16::try_from(location.y.max(0)).unwrap_or(u16::MAX));
+            let actions = self
+                .dnd_source
+                .as_ref()
+                .and_then(|source| with_source_metadata(source, |meta| meta.dnd_action).ok())
+                .unwrap_or_else(DndAction::empty);
+            let action = self.action_to_atom(actions);
+            self.send_dnd_message(
+                window,
+                self.atoms.XdndPosition,
+                [self.window, 0, (x << 16) | y, time, action],
+            )?;
+        }
+        self.conn.flush()?;
+        Ok(())
+    }
+
+    /// Drop a wayland drag'n'drop operation
+    ///
+    /// Returns `true` if an X11 window accepted the drop. The X11 window will then request the data
+    /// through [`SelectionEvent::SendSelection`] and report the end of the operation through
+    /// [`SelectionEvent::DndFinished`]. Otherwise the operation is cancelled and the wayland source
+    /// is released.
+    pub fn wayland_dnd_drop(&mut self, time: u32) -> Result<bool, SelectionError> {
+        let target = match self.wayland_dnd.as_ref() {
+            Some(dnd) if !dnd.dropped => dnd.target.filter(|_| dnd.accepted),
+            _ => return Ok(false),
+        };
+
+        match target {
+            Some(window) => {
+                self.wayland_dnd.as_mut().unwrap().dropped = true;
+                self.send_dnd_message(window, self.atoms.XdndDrop, [self.window, 0, time, 0, 0])?;
+                self.conn.flush()?;
+                Ok(true)
+            }
+            None => {
+                self.cancel_wayland_dnd()?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Cancel a wayland drag'n'drop operation
+    ///
+    /// Does nothing once the operation was dropped on an X11 window,
+    /// which ends it by sending [`SelectionEvent::DndFinished`].
+    pub fn cancel_wayland_dnd(&mut self) -> Result<(), SelectionError> {
+        if self.wayland_dnd.as_ref().map(|dnd| dnd.dropped).unwrap_or(true) {
+            return Ok(());
+        }
+
+        let dnd = self.wayland_dnd.take().unwrap();
+        self.dnd_source = None;
+        if let Some(window) = dnd.target {
+            self.send_dnd_message(window, self.atoms.XdndLeave, [self.window, 0, 0, 0, 0])?;
+        }
+        self.set_wayland_selection(SelectionType::Dnd, None)
+    }
+
+    /// Stack the proxy window above all X11 windows or hide it
+    ///
+    /// While shown, drag'n'drop operations of X11 clients target the proxy window,
+    /// which accepts them on behalf of the wayland surface under the pointer.
+    pub fn set_dnd_proxy(&mut self, mapped: bool) -> Result<(), SelectionError> {
+        if mapped == self.proxy_mapped {
+            return Ok(());
+        }
+
+        if mapped {
+            let geometry = self.conn.get_geometry(self.root)?.reply()?;
+            self.conn.configure_window(
+                self.proxy,
+                &ConfigureWindowAux::new()
+                    .x(0)
+                    .y(0)
+                    .width(u32::from(geometry.width))
+                    .height(u32::from(geometry.height))
+                    .stack_mode(StackMode::ABOVE),
+            )?;
+            self.conn.map_window(self.proxy)?;
+        } else {
+            self.conn.unmap_window(self.proxy)?;
+        }
+        self.proxy_mapped = mapped;
+        self.conn.flush()?;
+        Ok(())
+    }
+
+    /// Set the action reported to the X11 client dragging over the proxy window
+    ///
+    /// An empty action rejects the drop at the current position.
+    pub fn set_x11_dnd_action(&mut self, action: DndAction) {
+        self.x11_dnd_action = action;
+    }
+
+    /// End a drag'n'drop operation of an X11 client dropped onto the proxy window
+    ///
+    /// `action` is the action performed by the wayland client, empty if it rejected the drop.
+    pub fn finish_x11_dnd(&mut self, action: DndAction) -> Result<(), SelectionError> {
+        let dnd = match self.x11_dnd.take() {
+            Some(dnd) if dnd.dropped => dnd,
+            dnd => {
+                self.x11_dnd = dnd;
+                return Ok(());
+            }
+        };
+
+        let accepted = u32::from(!action.is_empty());
+        let action = if dnd.version >= 5 {
+            self.action_to_atom(action)
+        } else {
+            x11rb::NONE
+        };
+        self.send_dnd_message(
+            dnd.source,
+            self.atoms.XdndFinished,
+            [self.proxy, accepted, action, 0, 0],
+        )?;
+        self.x11_dnd_action = DndAction::empty();
+        self.set_dnd_proxy(false)
+    }
+
+    /// Returns the XDND version supported by `window`, if it supports drag'n'drop at all
+    fn xdnd_version(&self, window: Window) -> Result<Option<u32>, SelectionError> {
+        let reply = self
+            .conn
+            .get_property(false, window, self.atoms.XdndAware, AtomEnum::ATOM, 0, 1)?
+            .reply()?;
+        let version = reply.value32().and_then(|mut values| values.next());
+        // XDND versions before 3 are not interoperable
+        Ok(version
+            .filter(|version| *version >= 3)
+            .map(|version| version.min(XDND_VERSION)))
+    }
+
+    fn send_dnd_message(&self, window: Window, type_: Atom, data: [u32; 5]) -> Result<(), SelectionError> {
+        self.conn.send_event(
+            false,
+            window,
+            EventMask::NO_EVENT,
+            ClientMessageEvent {
+                response_type: CLIENT_MESSAGE_EVENT,
+                format: 32,
+                sequence: 0,
+                window,
+                type_,
+                data: data.into(),
+            },
+        )?;
+        Ok(())
+    }
+
+    fn action_to_atom(&self, actions: DndAction) -> Atom {
+        if actions.contains(DndAction::Copy) {
+            self.atoms.XdndActionCopy
+        } else if actions.contains(DndAction::Move) {
+            self.atoms.XdndActionMove
+        } else if actions.contains(DndAction::Ask) {
+            self.atoms.XdndActionAsk
+        } else {
+            x11rb::NONE
+        }
+    }
+
+    fn atom_to_action(&self, atom: Atom) -> DndAction {
+        if atom == self.atoms.XdndActionCopy {
+            DndAction::Copy
+        } else if atom == self.atoms.XdndActionMove {
+            DndAction::Move
+        } else if atom == self.atoms.XdndActionAsk {
+            DndAction::Ask
+        } else {
+            DndAction::empty()
+        }
+    }
+
+    /// Process an event of the X11 connection
+    ///
+    /// Events not concerning selections are ignored, so every event of the connection can be passed.
+    pub fn handle_event<D: 'static>(
+        &mut self,
+        event: &Event,
+        handle: &LoopHandle<'static, D>,
+    ) -> Result<Option<SelectionEvent>, SelectionError> {
+        let result = match event {
+            Event::XfixesSelectionNotify(event) => self.handle_owner_change(event),
+            Event::SelectionRequest(event) => self.handle_selection_request(event, handle),
+            Event::SelectionNotify(event) => self.handle_selection_notify(event),
+            Event::SelectionClear(event) => {
+                if let Some(selection) = self.selection_type(event.selection) {
+                    if event.owner == self.window {
+                        self.wayland_offers.remove(&selection);
+                    }
+                }
+                Ok(None)
+            }
+            Event::PropertyNotify(event) => self.handle_property_notify(event),
+            Event::ClientMessage(event) => self.handle_client_message(event),
+            Event::DestroyNotify(event) => {
+                // abort transfers to clients that went away
+                let (gone, outgoing) = std::mem::take(&mut self.outgoing)
+                    .into_iter()
+                    .partition::<Vec<_>, _>(|transfer| transfer.matches_requestor(event.window));
+                self.outgoing = outgoing;
+                for transfer in gone {
+                    transfer.cancel(handle);
+                }
+                Ok(None)
+            }
+            _ => Ok(None),
+        };
+
+        self.outgoing.retain(|transfer| !transfer.is_done());
+        result
+    }
+
+    fn handle_owner_change(
+        &mut self,
+        event: &xfixes::SelectionNotifyEvent,
+    ) -> Result<Option<SelectionEvent>, SelectionError> {
+        let selection = match self.selection_type(event.selection) {
+            Some(selection) => selection,
+            None => return Ok(None),
+        };
+        self.timestamp = event.timestamp;
+
+        if event.owner == self.window {
+            // we took the selection on behalf of a wayland client
+            return Ok(None);
+        }
+
+        // pending transfers of the previous owner will not complete anymore
+        self.incoming.remove(&selection);
+
+        if event.owner == x11rb::NONE {
+            if self.x11_owners.remove(&selection).is_some() {
+                debug!(self.log, "X11 selection cleared"; "selection" => ?selection);
+                return Ok(Some(SelectionEvent::SelectionCleared { selection }));
+            }
+            return Ok(None);
+        }
+
+        // an X11 client took the selection, query what it offers
+        self.wayland_offers.remove(&selection);
+        self.x11_owners.insert(selection, event.owner);
+        self.conn.convert_selection(
+            self.window,
+            event.selection,
+            self.atoms.TARGETS,
+            self.targets_property(selection),
+            event.timestamp,
+        )?;
+        self.conn.flush()?;
+        Ok(None)
+    }
+
+    fn handle_selection_request<D: 'static>(
+        &mut self,
+        event: &SelectionRequestEvent,
+        handle: &LoopHandle<'static, D>,
+    ) -> Result<Option<SelectionEvent>, SelectionError> {
+        let selection = self.selection_type(event.selection);
+        let mime_types = selection.and_then(|selection| self.wayland_offers.get(&selection).cloned());
+        let (selection, mime_types) = match (selection, mime_types) {
+            (Some(selection), Some(mime_types)) if event.owner == self.window => (selection, mime_types),
+            _ => {
+                notify(
+                    &self.conn,
+                    event.requestor,
+                    event.selection,
+                    event.target,
+                    x11rb::NONE,
+                    event.time,
+                )?;
+                self.conn.flush()?;
+                return Ok(None);
+            }
+        };
+        // obsolete clients may not set a property
+        let property = if event.property == x11rb::NONE {
+            event.target
+        } else {
+            event.property
+        };
+
+        if event.target == self.atoms.TARGETS {
+            let mut targets = vec![self.atoms.TARGETS, self.atoms.TIMESTAMP];
+            for mime_type in &mime_types {
+                targets.push(self.mime_to_atom(mime_type)?);
+                if mime_type == MIME_TEXT_UTF8 {
+                    // legacy clients only know about STRING
+                    targets.push(self.atoms.STRING);
+                }
+            }
+            self.conn.change_property32(
+                PropMode::REPLACE,
+                event.requestor,
+                property,
+                AtomEnum::ATOM,
+                &targets,
+            )?;
+            notify(
+                &self.conn,
+                event.requestor,
+                event.selection,
+                event.target,
+                property,
+                event.time,
+            )?;
+        } else if event.target == self.atoms.TIMESTAMP {
+            self.conn.change_property32(
+                PropMode::REPLACE,
+                event.requestor,
+                property,
+                AtomEnum::INTEGER,
+                &[self.timestamp],
+            )?;
+            notify(
+                &self.conn,
+                event.requestor,
+                event.selection,
+                event.target,
+                property,
+                event.time,
+            )?;
+        } else {
+            let mime_type = if event.target == self.atoms.STRING {
+                Some(MIME_TEXT_UTF8.to_owned())
+            } else {
+                self.atom_to_mime(event.target)?
+            };
+
+            match mime_type.filter(|mime_type| mime_types.contains(mime_type)) {
+                Some(mime_type) => {
+                    let (read, write) = pipe()?;
+                    let transfer = OutgoingTransfer::new(
+                        self.conn.clone(),
+                        self.atoms.INCR,
+                        event.requestor,
+                        event.selection,
+                        event.target,
+                        property,
+                        event.time,
+                        read,
+                        handle,
+                        &self.log,
+                    )?;
+                    self.outgoing.push(transfer);
+                    self.conn.flush()?;
+                    return Ok(Some(SelectionEvent::SendSelection {
+                        selection,
+                        mime_type,
+                        fd: write,
+                    }));
+                }
+                None => {
+                    debug!(self.log, "X11 client requested unsupported target"; "target" => event.target);
+                    notify(
+                        &self.conn,
+                        event.requestor,
+                        event.selection,
+                        event.target,
+                        x11rb::NONE,
+                        event.time,
+                    )?;
+                }
+            }
+        }
+
+        self.conn.flush()?;
+        Ok(None)
+    }
+
+    fn handle_selection_notify(
+        &mut self,
+        event: &SelectionNotifyEvent,
+    ) -> Result<Option<SelectionEvent>, SelectionError> {
+        let selection = match self.selection_type(event.selection) {
+            Some(selection) if event.requestor == self.window => selection,
+            _ => return Ok(None),
+        };
+
+        if event.target == self.atoms.TARGETS {
+            return self.handle_targets(selection, event.property);
+        }
+
+        if event.property == x11rb::NONE {
+            warn!(self.log, "X11 client failed to convert selection"; "selection" => ?selection);
+            self.incoming
+                .get_mut(&selection)
+                .and_then(|queue| queue.pop_front());
+            self.convert_next(selection)?;
+            return Ok(None);
+        }
+
+        let reply = self
+            .conn
+            .get_property(true, self.window, event.property, AtomEnum::ANY, 0, 0x1fff_ffff)?
+            .reply()?;
+        let transfer = match self
+            .incoming
+            .get_mut(&selection)
+            .and_then(|queue| queue.front_mut())
+        {
+            Some(transfer) => transfer,
+            None => return Ok(None),
+        };
+
+        if reply.type_ == self.atoms.INCR {
+            // deleting the property (done by get_property) starts the transfer,
+            // the chunks are announced through PropertyNotify events
+            trace!(self.log, "Starting incremental selection transfer"; "mime_type" => &transfer.mime_type);
+            transfer.incr = true;
+            self.conn.flush()?;
+        } else {
+            transfer.push(&reply.value);
+            self.finish_transfer(selection)?;
+        }
+
+        Ok(None)
+    }
+
+    fn handle_targets(
+        &mut self,
+        selection: SelectionType,
+        property: Atom,
+    ) -> Result<Option<SelectionEvent>, SelectionError> {
+        if property == x11rb::NONE {
+            warn!(self.log, "X11 client did not provide selection targets"; "selection" => ?selection);
+            return Ok(None);
+        }
+
+        let reply = self
+            .conn
+            .get_property(true, self.window, property, AtomEnum::ATOM, 0, 4096)?
+            .reply()?;
+        let targets = reply
+            .value32()
+            .map(|targets| targets.collect::<Vec<_>>())
+            .unwrap_or_default();
+
+        let mut mime_types = Vec::new();
+        for target in targets {
+            if let Some(mime_type) = self.atom_to_mime(target)? {
+                if !mime_types.contains(&mime_type) {
+                    mime_types.push(mime_type);
+                }
+            }
+        }
+
+        debug!(self.log, "New X11 selection"; "selection" => ?selection, "mime_types" => ?mime_types);
+        Ok(Some(SelectionEvent::NewSelection {
+            selection,
+            mime_types,
+        }))
+    }
+
+    fn handle_property_notify(
+        &mut self,
+        event: &PropertyNotifyEvent,
+    ) -> Result<Option<SelectionEvent>, SelectionError> {
+        self.timestamp = event.time;
+
+        // the requestor of an incremental transfer consumed the last chunk
+        if event.state == Property::DELETE {
+            if let Some(transfer) = self
+                .outgoing
+                .iter_mut()
+                .find(|transfer| transfer.matches(event.window, event.atom))
+            {
+                transfer.property_deleted()?;
+            }
+            return Ok(None);
+        }
+
+        // the owner of an incremental transfer provided the next chunk
+        if event.window != self.window {
+            return Ok(None);
+        }
+        let selection = match [
+            SelectionType::Clipboard,
+            SelectionType::Primary,
+            SelectionType::Dnd,
+        ]
+        .into_iter()
+        .find(|selection| self.data_property(*selection) == event.atom)
+        {
+            Some(selection) => selection,
+            None => return Ok(None),
+        };
+        let incr = self
+            .incoming
+            .get(&selection)
+            .and_then(|queue| queue.front())
+            .map(|transfer| transfer.incr)
+            .unwrap_or(false);
+        if !incr {
+            return Ok(None);
+        }
+
+        let reply = self
+            .conn
+            .get_property(true, self.window, event.atom, AtomEnum::ANY, 0, 0x1fff_ffff)?
+            .reply()?;
+        if reply.value.is_empty() {
+            // a zero-sized chunk ends the transfer
+            self.finish_transfer(selection)?;
+        } else if let Some(transfer) = self
+            .incoming
+            .get_mut(&selection)
+            .and_then(|queue| queue.front_mut())
+        {
+            transfer.push(&reply.value);
+        }
+        self.conn.flush()?;
+
+        Ok(None)
+    }
+
+    fn handle_client_message(
+        &mut self,
+        event: &ClientMessageEvent,
+    ) -> Result<Option<SelectionEvent>, SelectionError> {
+        if event.window == self.proxy {
+            return self.handle_proxy_message(event);
+        }
+        if event.window != self.window {
+            return Ok(None);
+        }
+        let data = event.data.as_data32();
+        let (version, current_action) = match self.wayland_dnd.as_ref() {
+            // messages need to originate from the current target
+            Some(dnd) if dnd.target == Some(data[0]) => (dnd.version, dnd.action),
+            _ => return Ok(None),
+        };
+
+        if event.type_ == self.atoms.XdndStatus {
+            let accepted = data[1] & 1 != 0;
+            let action = if !accepted {
+                DndAction::empty()
+            } else if version >= 2 {
+                self.atom_to_action(data[4])
+            } else {
+                DndAction::Copy
+            };
+
+            let dnd = self.wayland_dnd.as_mut().unwrap();
+            dnd.accepted = accepted;
+            if current_action != action {
+                dnd.action = action;
+                return Ok(Some(SelectionEvent::DndAction { action }));
+            }
+        } else if event.type_ == self.atoms.XdndFinished {
+            // only version 5 reports the result of the drop
+            let action = if version < 5 {
+                current_action
+            } else if data[1] & 1 != 0 {
+                self.atom_to_action(data[2])
+            } else {
+                DndAction::empty()
+            };
+
+            self.wayland_dnd = None;
+            self.set_wayland_selection(SelectionType::Dnd, None)?;
+            return Ok(Some(SelectionEvent::DndFinished { action }));
+        }
+
+        Ok(None)
+    }
+
+    /// Handle the XDND messages of an X11 drag'n'drop source targeting the proxy window
+    fn handle_proxy_message(
+        &mut self,
+        event: &ClientMessageEvent,
+    ) -> Result<Option<SelectionEvent>, SelectionError> {
+        let data = event.data.as_data32();
+        let source = data[0];
+
+        if event.type_ == self.atoms.XdndEnter {
+            let version = (data[1] >> 24).min(XDND_VERSION);
+            trace!(self.log, "X11 drag'n'drop entered the proxy window"; "source" => source, "version" => version);
+            self.x11_dnd = Some(X11Dnd {
+                source,
+                version,
+                dropped: false,
+            });
+            return Ok(None);
+        }
+
+        let version = match self.x11_dnd.as_ref() {
+            // messages need to originate from the current source
+            Some(dnd) if dnd.source == source && !dnd.dropped => dnd.version,
+            _ => return Ok(None),
+        };
+
+        if event.type_ == self.atoms.XdndPosition {
+            let accepted = !self.x11_dnd_action.is_empty();
+            let action = if version >= 2 {
+                self.action_to_atom(self.x11_dnd_action)
+            } else {
+                x11rb::NONE
+            };
+            // an empty rectangle makes the source send every position
+            self.send_dnd_message(
+                source,
+                self.atoms.XdndStatus,
+                [self.proxy, u32::from(accepted) | 0b10, 0, 0, action],
+            )?;
+            self.conn.flush()?;
+        } else if event.type_ == self.atoms.XdndLeave {
+            self.x11_dnd = None;
+            return Ok(Some(SelectionEvent::X11DndLeave));
+        } else if event.type_ == self.atoms.XdndDrop {
+            self.x11_dnd.as_mut().unwrap().dropped = true;
+            // the data has to be converted with the time of the drop
+            if data[2] != 0 {
+                self.timestamp = data[2];
+            }
+            return Ok(Some(SelectionEvent::X11DndDrop));
+        }
+
+        Ok(None)
+    }
+}
+
+/// Returns the target of a text mime type
+///
+/// The first target matching the mime type in `targets` is preferred.
+fn target_for_mime(targets: &[(Atom, &'static str)], mime_type: &str) -> Option<Atom> {
+    targets
+        .iter()
+        .find(|(_, mime)| *mime == mime_type)
+        .map(|(atom, _)| *atom)
+}
+
+/// Returns the mime type of a text target
+fn mime_for_target(targets: &[(Atom, &'static str)], target: Atom) -> Option<&'static str> {
+    targets
+        .iter()
+        .find(|(atom, _)| *atom == target)
+        .map(|(_, mime)| *mime)
+}
+
+/// Returns the mime type named by an atom, if it represents data
+///
+/// Targets like `TARGETS` or `MULTIPLE` are not data, mime types always contain a slash.
+fn mime_from_atom_name(name: Vec<u8>) -> Option<String> {
+    String::from_utf8(name).ok().filter(|name| name.contains('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGETS: [(Atom, &str); 5] = [
+        (1, MIME_TEXT_UTF8),
+        (2, MIME_TEXT_UTF8),
+        (3, MIME_TEXT),
+        (4, MIME_TEXT),
+        (5, MIME_TEXT),
+    ];
+
+    #[test]
+    fn text_mime_types_use_preferred_target() {
+        assert_eq!(target_for_mime(&TARGETS, MIME_TEXT_UTF8), Some(1));
+        assert_eq!(target_for_mime(&TARGETS, MIME_TEXT), Some(3));
+        assert_eq!(target_for_mime(&TARGETS, "image/png"), None);
+        assert_eq!(target_for_mime(&TARGETS, "text/plain;charset=UTF-8"), None);
+    }
+
+    #[test]
+    fn text_targets_map_to_mime_types() {
+        for (atom, mime_type) in TARGETS {
+            assert_eq!(mime_for_target(&TARGETS, atom), Some(mime_type));
+            // converting back yields an equivalent target
+            let target = target_for_mime(&TARGETS, mime_type).unwrap();
+            assert_eq!(mime_for_target(&TARGETS, target), Some(mime_type));
+        }
+        assert_eq!(mime_for_target(&TARGETS, 6), None);
+    }
+
+    #[test]
+    fn only_mime_type_atoms_are_data() {
+        assert_eq!(
+            mime_from_atom_name(b"image/png".to_vec()),
+            Some("image/png".to_owned())
+        );
+        assert_eq!(
+            mime_from_atom_name(b"text/uri-list".to_vec()),
+            Some("text/uri-list".to_owned())
+        );
+        assert_eq!(mime_from_atom_name(b"TARGETS".to_vec()), None);
+        assert_eq!(mime_from_atom_name(b"MULTIPLE".to_vec()), None);
+        assert_eq!(mime_from_atom_name(vec![b'a', b'/', 0xff]), None);
+    }
+}
@@ -0,0 +1,423 @@
+//! Data transfers between X11 selections and wayland file descriptors
+//!
+//! Both directions buffer the data in memory and use non-blocking file descriptors,
+//! so a slow or misbehaving client can never block the event loop.
+
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    io,
+    os::unix::io::{AsRawFd, FromRawFd},
+    rc::Rc,
+    sync::Arc,
+};
+
+use calloop::{generic::Generic, Interest, LoopHandle, Mode, PostAction, RegistrationToken};
+use io_lifetimes::OwnedFd;
+use nix::{
+    errno::Errno,
+    fcntl::{fcntl, FcntlArg, OFlag},
+    unistd,
+};
+use slog::{debug, warn};
+use x11rb::{
+    connection::Connection,
+    errors::ConnectionError,
+    protocol::xproto::{
+        Atom, ChangeWindowAttributesAux, ConnectionExt as _, EventMask, PropMode, SelectionNotifyEvent,
+        Timestamp, Window, SELECTION_NOTIFY_EVENT,
+    },
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+};
+
+use super::SelectionError;
+
+/// Size of the chunks used for incremental (`INCR`) transfers
+///
+/// Data larger than a single chunk is always transferred incrementally.
+const INCR_CHUNK_SIZE: usize = 64 * 1024;
+
+fn set_nonblocking(fd: &OwnedFd) -> Result<(), Errno> {
+    let flags = OFlag::from_bits_truncate(fcntl(fd.as_raw_fd(), FcntlArg::F_GETFL)?);
+    fcntl(fd.as_raw_fd(), FcntlArg::F_SETFL(flags | OFlag::O_NONBLOCK))?;
+    Ok(())
+}
+
+/// Create a pipe, returning a non-blocking read end and a blocking write end
+pub(super) fn pipe() -> Result<(OwnedFd, OwnedFd), SelectionError> {
+    let (read, write) = unistd::pipe2(OFlag::O_CLOEXEC).map_err(SelectionError::Pipe)?;
+    // SAFETY: both fds were just created and are exclusively owned by us
+    let (read, write) = unsafe { (OwnedFd::from_raw_fd(read), OwnedFd::from_raw_fd(write)) };
+    set_nonblocking(&read).map_err(SelectionError::Pipe)?;
+    Ok((read, write))
+}
+
+/// Send a `SelectionNotify` event to the requestor of a selection conversion
+///
+/// A `property` of `NONE` signals a failed conversion.
+pub(super) fn notify(
+    conn: &RustConnection,
+    requestor: Window,
+    selection: Atom,
+    target: Atom,
+    property: Atom,
+    time: Timestamp,
+) -> Result<(), ConnectionError> {
+    conn.send_event(
+        false,
+        requestor,
+        EventMask::NO_EVENT,
+        SelectionNotifyEvent {
+            response_type: SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time,
+            requestor,
+            selection,
+            target,
+            property,
+        },
+    )?;
+    Ok(())
+}
+
+/// Buffered data waiting to be written into a wayland client's fd
+#[derive(Default)]
+struct WriteBuffer {
+    data: VecDeque<u8>,
+    complete: bool,
+    enable: Option<Box<dyn Fn()>>,
+}
+
+impl std::fmt::Debug for WriteBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WriteBuffer")
+            .field("data", &self.data.len())
+            .field("complete", &self.complete)
+            .finish()
+    }
+}
+
+impl WriteBuffer {
+    fn wake(&self) {
+        if let Some(enable) = self.enable.as_ref() {
+            enable();
+        }
+    }
+}
+
+/// A transfer of an X11 selection into a wayland client's fd
+#[derive(Debug)]
+pub(super) struct IncomingTransfer {
+    pub(super) mime_type: String,
+    pub(super) target: Atom,
+    pub(super) incr: bool,
+    fd: Option<OwnedFd>,
+    buffer: Rc<RefCell<WriteBuffer>>,
+}
+
+impl IncomingTransfer {
+    pub(super) fn new(mime_type: String, target: Atom, fd: OwnedFd) -> Self {
+        IncomingTransfer {
+            mime_type,
+            target,
+            incr: false,
+            fd: Some(fd),
+            buffer: Default::default(),
+        }
+    }
+
+    /// Start writing into the fd of this transfer once data becomes available
+    pub(super) fn start<D: 'static>(
+        &mut self,
+        handle: &LoopHandle<'static, D>,
+        log: &slog::Logger,
+    ) -> Result<(), SelectionError> {
+        let fd = match self.fd.take() {
+            Some(fd) => fd,
+            None => return Ok(()),
+        };
+        set_nonblocking(&fd).map_err(SelectionError::Pipe)?;
+
+        let buffer = self.buffer.clone();
+        let log = log.clone();
+        let token = handle
+            .insert_source(
+                Generic::<_, io::Error>::new(fd, Interest::WRITE, Mode::Level),
+                move |_, fd, _| {
+                    let mut buffer = buffer.borrow_mut();
+                    while !buffer.data.is_empty() {
+                        let (chunk, _) = buffer.data.as_slices();
+                        match unistd::write(fd.as_raw_fd(), chunk) {
+                            Ok(written) => {
+                                buffer.data.drain(..written);
+                            }
+                            Err(Errno::EINTR) => continue,
+                            Err(Errno::EAGAIN) => return Ok(PostAction::Continue),
+                            Err(err) => {
+                                // the reading client went away, nothing left to do
+                                debug!(log, "Aborting selection transfer"; "err" => %err);
+                                return Ok(PostAction::Remove);
+                            }
+                        }
+                    }
+
+                    if buffer.complete {
+                        Ok(PostAction::Remove)
+                    } else {
+                        // wait for more data to arrive
+                        Ok(PostAction::Disable)
+                    }
+                },
+            )
+            .map_err(|err| SelectionError::EventLoop(err.error))?;
+
+        let loop_handle = handle.clone();
+        self.buffer.borrow_mut().enable = Some(Box::new(move || {
+            let _ = loop_handle.enable(&token);
+        }));
+        Ok(())
+    }
+
+    /// Append data received from the selection owner
+    pub(super) fn push(&mut self, data: &[u8]) {
+        let mut buffer = self.buffer.borrow_mut();
+        buffer.data.extend(data);
+        buffer.wake();
+    }
+
+    /// Mark the transfer as complete, closing the fd once all data was written
+    pub(super) fn finish(&mut self) {
+        let mut buffer = self.buffer.borrow_mut();
+        buffer.complete = true;
+        buffer.wake();
+    }
+}
+
+impl Drop for IncomingTransfer {
+    fn drop(&mut self) {
+        // an unfinished transfer was aborted, close the fd without sending anything further
+        let mut buffer = self.buffer.borrow_mut();
+        if !buffer.complete {
+            buffer.data.clear();
+            buffer.complete = true;
+            buffer.wake();
+        }
+    }
+}
+
+/// State of a transfer of wayland data to an X11 requestor
+#[derive(Debug)]
+struct OutgoingState {
+    conn: Arc<RustConnection>,
+    incr_atom: Atom,
+    requestor: Window,
+    selection: Atom,
+    target: Atom,
+    property: Atom,
+    time: Timestamp,
+    data: Vec<u8>,
+    /// The fd is still registered in the event loop
+    reading: bool,
+    eof: bool,
+    /// `INCR` transfer started, waiting for the requestor to delete the property
+    incr: bool,
+    /// The requestor deleted the property, but no data was available to send
+    waiting: bool,
+    done: bool,
+}
+
+impl OutgoingState {
+    /// Advance the transfer as far as the available data allows
+    fn flush(&mut self) -> Result<(), ConnectionError> {
+        if self.done {
+            return Ok(());
+        }
+
+        if !self.incr {
+            if self.eof && self.data.len() <= INCR_CHUNK_SIZE {
+                self.conn.change_property8(
+                    PropMode::REPLACE,
+                    self.requestor,
+                    self.property,
+                    self.target,
+                    &self.data,
+                )?;
+                notify(
+                    &self.conn,
+                    self.requestor,
+                    self.selection,
+                    self.target,
+                    self.property,
+                    self.time,
+                )?;
+                self.done = true;
+            } else if self.data.len() > INCR_CHUNK_SIZE {
+                // too large for a single property, switch to an incremental transfer
+                self.conn.change_window_attributes(
+                    self.requestor,
+                    &ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+                )?;
+                self.conn.change_property32(
+                    PropMode::REPLACE,
+                    self.requestor,
+                    self.property,
+                    self.incr_atom,
+                    &[self.data.len() as u32],
+                )?;
+                notify(
+                    &self.conn,
+                    self.requestor,
+                    self.selection,
+                    self.target,
+                    self.property,
+                    self.time,
+                )?;
+                self.incr = true;
+            }
+        } else if self.waiting {
+            self.send_chunk()?;
+        }
+
+        self.conn.flush()
+    }
+
+    fn send_chunk(&mut self) -> Result<(), ConnectionError> {
+        if self.data.is_empty() && !self.eof {
+            self.waiting = true;
+            return Ok(());
+        }
+
+        let len = self.data.len().min(INCR_CHUNK_SIZE);
+        let chunk = self.data.drain(..len).collect::<Vec<_>>();
+        self.conn.change_property8(
+            PropMode::REPLACE,
+            self.requestor,
+            self.property,
+            self.target,
+            &chunk,
+        )?;
+        // a zero-sized chunk terminates the transfer
+        if chunk.is_empty() {
+            self.done = true;
+            self.conn.change_window_attributes(
+                self.requestor,
+                &ChangeWindowAttributesAux::new().event_mask(EventMask::NO_EVENT),
+            )?;
+        }
+        self.waiting = false;
+        Ok(())
+    }
+}
+
+/// A transfer of wayland data to an X11 requestor
+#[derive(Debug)]
+pub(super) struct OutgoingTransfer {
+    state: Rc<RefCell<OutgoingState>>,
+    token: RegistrationToken,
+}
+
+impl OutgoingTransfer {
+    /// Start reading from `fd` and converting the data for `requestor`
+    #[allow(clippy::too_many_arguments)]
+    pub(super) fn new<D: 'static>(
+        conn: Arc<RustConnection>,
+        incr: Atom,
+        requestor: Window,
+        selection: Atom,
+        target: Atom,
+        property: Atom,
+        time: Timestamp,
+        fd: OwnedFd,
+        handle: &LoopHandle<'static, D>,
+        log: &slog::Logger,
+    ) -> Result<Self, SelectionError> {
+        let state = Rc::new(RefCell::new(OutgoingState {
+            conn,
+            incr_atom: incr,
+            requestor,
+            selection,
+            target,
+            property,
+            time,
+            data: Vec::new(),
+            reading: true,
+            eof: false,
+            incr: false,
+            waiting: false,
+            done: false,
+        }));
+
+        let source_state = state.clone();
+        let log = log.clone();
+        let token = handle
+            .insert_source(
+                Generic::<_, io::Error>::new(fd, Interest::READ, Mode::Level),
+                move |_, fd, _| {
+                    let mut state = source_state.borrow_mut();
+                    let mut buf = [0u8; 4096];
+                    loop {
+                        match unistd::read(fd.as_raw_fd(), &mut buf) {
+                            Ok(0) => {
+                                state.eof = true;
+                                break;
+                            }
+                            Ok(len) => state.data.extend_from_slice(&buf[..len]),
+                            Err(Errno::EINTR) => continue,
+                            Err(Errno::EAGAIN) => break,
+                            Err(err) => {
+                                warn!(log, "Failed to read selection data"; "err" => %err);
+                                state.eof = true;
+                                break;
+                            }
+                        }
+                    }
+
+                    if let Err(err) = state.flush() {
+                        warn!(log, "Failed to send selection data to X11 client"; "err" => %err);
+                        state.done = true;
+                    }
+
+                    if state.eof || state.done {
+                        state.reading = false;
+                        Ok(PostAction::Remove)
+                    } else {
+                        Ok(PostAction::Continue)
+                    }
+                },
+            )
+            .map_err(|err| SelectionError::EventLoop(err.error))?;
+
+        Ok(OutgoingTransfer { state, token })
+    }
+
+    /// Returns `true` if this transfer is waiting for `property` of `window` to be deleted
+    pub(super) fn matches(&self, window: Window, property: Atom) -> bool {
+        let state = self.state.borrow();
+        state.incr && state.requestor == window && state.property == property
+    }
+
+    /// Returns `true` if this transfer sends data to `window`
+    pub(super) fn matches_requestor(&self, window: Window) -> bool {
+        self.state.borrow().requestor == window
+    }
+
+    /// The requestor deleted the property, send the next chunk
+    pub(super) fn property_deleted(&mut self) -> Result<(), ConnectionError> {
+        let mut state = self.state.borrow_mut();
+        state.send_chunk()?;
+        state.conn.flush()
+    }
+
+    /// Returns `true` if all data was transferred
+    pub(super) fn is_done(&self) -> bool {
+        self.state.borrow().done
+    }
+
+    /// Abort the transfer, e.g. because the requestor went away
+    pub(super) fn cancel<D>(self, handle: &LoopHandle<'static, D>) {
+        if self.state.borrow().reading {
+            handle.remove(self.token);
+        }
+    }
+}
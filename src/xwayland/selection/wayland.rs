@@ -0,0 +1,192 @@
+//! Forwarding of the bridged selections to the wayland clients of a seat
+
+use std::os::unix::io::AsRawFd;
+
+use slog::warn;
+use wayland_server::{
+    protocol::{wl_data_device_manager::DndAction, wl_surface::WlSurface},
+    DisplayHandle, Resource,
+};
+
+use crate::{
+    input::Seat,
+    utils::{Logical, Point, SERIAL_COUNTER},
+    wayland::{
+        data_device::{
+            clear_data_device_selection, request_data_device_client_selection, set_data_device_selection,
+            DataDeviceHandler, ServerDnd, SourceMetadata,
+        },
+        primary_selection::{
+            clear_primary_selection, request_primary_client_selection, set_primary_selection,
+            PrimarySelectionHandler,
+        },
+    },
+};
+
+use super::{SelectionError, SelectionEvent, SelectionType, XWaylandSelection};
+
+impl XWaylandSelection {
+    /// Forward an event returned by [`XWaylandSelection::handle_event`] to the wayland clients of `seat`
+    ///
+    /// - Selections of X11 clients are set as the data device and primary selection of the seat.
+    /// - Requests of X11 clients for selections of wayland clients are forwarded to their sources.
+    /// - The X11 targets of wayland drag'n'drop operations interact with the wayland source.
+    /// - Drag'n'drop operations of X11 clients are offered to wayland clients as described
+    ///   in [`XWaylandSelection::x11_dnd_motion`].
+    pub fn forward_event<D>(
+        &mut self,
+        event: SelectionEvent,
+        dh: &DisplayHandle,
+        seat: &Seat<D>,
+    ) -> Result<(), SelectionError>
+    where
+        D: DataDeviceHandler + PrimarySelectionHandler + 'static,
+    {
+        match event {
+            SelectionEvent::NewSelection {
+                selection,
+                mime_types,
+            } => match selection {
+                SelectionType::Clipboard => set_data_device_selection(dh, seat, mime_types),
+                SelectionType::Primary => set_primary_selection(dh, seat, mime_types),
+                SelectionType::Dnd => {
+                    // a new operation started, a previous drop is not going to finish anymore
+                    if let Some(mut offer) = self.x11_dnd_offer.take() {
+                        offer.leave(seat);
+                    }
+                    self.x11_dnd = None;
+                    self.x11_dnd_offer = Some(ServerDnd::new(SourceMetadata {
+                        mime_types,
+                        dnd_action: DndAction::Copy | DndAction::Move,
+                    }));
+                }
+            },
+            SelectionEvent::SelectionCleared { selection } => match selection {
+                SelectionType::Clipboard => clear_data_device_selection(dh, seat),
+                SelectionType::Primary => clear_primary_selection(dh, seat),
+                SelectionType::Dnd => self.end_x11_dnd(seat)?,
+            },
+            SelectionEvent::SendSelection {
+                selection,
+                mime_type,
+                fd,
+            } => {
+                let result = match selection {
+                    SelectionType::Clipboard => request_data_device_client_selection(seat, mime_type, fd),
+                    SelectionType::Primary => request_primary_client_selection(seat, mime_type, fd),
+                    SelectionType::Dnd => {
+                        if let Some(source) = self.dnd_source.as_ref() {
+                            source.send(mime_type, fd.as_raw_fd());
+                        }
+                        Ok(())
+                    }
+                };
+                if let Err(err) = result {
+                    warn!(self.log, "Failed to forward selection to X11"; "err" => %err);
+                }
+            }
+            SelectionEvent::DndAction { action } => {
+                if let Some(source) = self.dnd_source.as_ref().filter(|source| source.version() >= 3) {
+                    source.action(action);
+                }
+            }
+            SelectionEvent::DndFinished { action } => {
+                if let Some(source) = self.dnd_source.take() {
+                    if action.is_empty() {
+                        source.cancelled();
+                    } else if source.version() >= 3 {
+                        source.dnd_finished();
+                    }
+                }
+            }
+            SelectionEvent::X11DndLeave => {
+                if let Some(offer) = self.x11_dnd_offer.as_mut() {
+                    offer.leave(seat);
+                }
+                self.set_x11_dnd_action(DndAction::empty());
+            }
+            SelectionEvent::X11DndDrop => {
+                let accepted = match self.x11_dnd_offer.as_mut() {
+                    Some(offer) => offer.drop(seat),
+                    None => false,
+                };
+                if !accepted {
+                    self.x11_dnd_offer = None;
+                    self.finish_x11_dnd(DndAction::empty())?;
+                }
+                // the pointer is released, X11 windows need to receive input again
+                self.set_dnd_proxy(false)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forward the drag'n'drop operation of an X11 client to wayland clients
+    ///
+    /// Call this on every pointer motion with the wayland surface under the pointer and
+    /// its location, `None` if the pointer is over an X11 window. `location` is the position
+    /// of the pointer.
+    ///
+    /// The operation ends once the pointer is no longer grabbed by the X11 client. Otherwise XWayland
+    /// needs to keep the pointer focus, while the wayland surface under the pointer receives an offer.
+    /// Once the wayland client is done with a drop, [`XWaylandSelection::x11_dnd_finished`] has to be called.
+    pub fn x11_dnd_motion<D>(
+        &mut self,
+        dh: &DisplayHandle,
+        seat: &Seat<D>,
+        focus: Option<(WlSurface, Point<i32, Logical>)>,
+        location: Point<f64, Logical>,
+        time: u32,
+    ) -> Result<(), SelectionError>
+    where
+        D: DataDeviceHandler + 'static,
+    {
+        if self.x11_dnd_offer.is_none() || self.x11_dnd.as_ref().map(|dnd| dnd.dropped).unwrap_or(false) {
+            return Ok(());
+        }
+        let grabbed = seat
+            .get_pointer()
+            .map(|pointer| pointer.is_grabbed())
+            .unwrap_or(false);
+        if !grabbed {
+            // the operation ended over X11 windows
+            return self.end_x11_dnd(seat);
+        }
+
+        self.set_dnd_proxy(focus.is_some())?;
+        let offer = self.x11_dnd_offer.as_mut().unwrap();
+        offer.motion(
+            dh,
+            seat,
+            focus.as_ref().map(|(surface, loc)| (surface, *loc)),
+            location,
+            SERIAL_COUNTER.next_serial(),
+            time,
+        );
+        let action = offer.action();
+        self.set_x11_dnd_action(action);
+        Ok(())
+    }
+
+    /// The wayland client receiving the drop of an X11 client finished the operation
+    ///
+    /// Call this from [`ServerDndGrabHandler::finished`](crate::wayland::data_device::ServerDndGrabHandler::finished).
+    pub fn x11_dnd_finished(&mut self) -> Result<(), SelectionError> {
+        let action = match self.x11_dnd_offer.take() {
+            Some(offer) => offer.action(),
+            None => return Ok(()),
+        };
+        self.finish_x11_dnd(action)
+    }
+
+    fn end_x11_dnd<D>(&mut self, seat: &Seat<D>) -> Result<(), SelectionError>
+    where
+        D: DataDeviceHandler + 'static,
+    {
+        if let Some(mut offer) = self.x11_dnd_offer.take() {
+            offer.leave(seat);
+        }
+        self.set_x11_dnd_action(DndAction::empty());
+        self.set_dnd_proxy(false)
+    }
+}
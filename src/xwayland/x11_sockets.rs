@@ -8,12 +8,18 @@ use slog::{debug, info, warn};
 use nix::{errno::Errno, sys::socket};
 
 /// Find a free X11 display slot and setup
-pub(crate) fn prepare_x11_sockets(log: ::slog::Logger) -> Result<(X11Lock, [UnixStream; 2]), std::io::Error> {
+///
+/// Listens on `/tmp/.X11-unix/X$D` and, if `abstract_socket` is set, on the abstract
+/// socket of the same name.
+pub(crate) fn prepare_x11_sockets(
+    abstract_socket: bool,
+    log: ::slog::Logger,
+) -> Result<(X11Lock, Vec<UnixStream>), std::io::Error> {
     for d in 0..33 {
         // if fails, try the next one
         if let Ok(lock) = X11Lock::grab(d, log.clone()) {
             // we got a lockfile, try and create the socket
-            match open_x11_sockets_for_display(d) {
+            match open_x11_sockets_for_display(d, abstract_socket) {
                 Ok(sockets) => return Ok((lock, sockets)),
                 Err(err) => warn!(log, "Failed to create sockets: {}", err),
             }
@@ -110,18 +116,20 @@ impl Drop for X11Lock {
     }
 }
 
-/// Open the two unix sockets an X server listens on
+/// Open the unix sockets an X server listens on
 ///
 /// Should only be done after the associated lockfile is acquired!
-fn open_x11_sockets_for_display(display: u32) -> nix::Result<[UnixStream; 2]> {
+fn open_x11_sockets_for_display(display: u32, abstract_socket: bool) -> nix::Result<Vec<UnixStream>> {
     let path = format!("/tmp/.X11-unix/X{}", display);
     let _ = ::std::fs::remove_file(&path);
     // We know this path is not to long, these unwrap cannot fail
     let fs_addr = socket::UnixAddr::new(path.as_bytes()).unwrap();
-    let abs_addr = socket::UnixAddr::new_abstract(path.as_bytes()).unwrap();
-    let fs_socket = open_socket(fs_addr)?;
-    let abstract_socket = open_socket(abs_addr)?;
-    Ok([fs_socket, abstract_socket])
+    let mut sockets = vec![open_socket(fs_addr)?];
+    if abstract_socket {
+        let abs_addr = socket::UnixAddr::new_abstract(path.as_bytes()).unwrap();
+        sockets.push(open_socket(abs_addr)?);
+    }
+    Ok(sockets)
 }
 
 /// Open an unix socket for listening and bind it to given path
@@ -21,6 +21,17 @@
  *    if will consider its parent as "smart", and send a SIGUSR1 signal when
  *    startup completes. We want to catch this so we can launch the VM.
  * -> we need to track if the XWayland crashes, to restart it
+ * -> alternatively XWayland can report its readiness by writing the display
+ *    number to the fd given with `-displayfd`, in which case it is spawned directly
+ *
+ * The listening sockets and the display lock outlive a single XWayland instance
+ * if it is started lazily or restarted after crashes, so clients always find the
+ * same $DISPLAY. In lazy mode we watch the listening sockets ourselves and only
+ * spawn XWayland once a client connects, which then accepts the pending connection.
+ *
+ * The manager itself has no access to the event loop outside of `XWayland::start`,
+ * so restarts, reaping exited processes and removing the sources of the manager are
+ * requested through a channel, which is handled on the event loop.
  *
  * cf https://github.com/swaywm/wlroots/blob/master/xwayland/xwayland.c
  *
@@ -39,29 +50,33 @@
  * STDOUT.
  */
 use std::{
+    cell::Cell,
     env,
-    fmt::Write,
+    fs::File,
     io::{self, Read},
     os::unix::{
-        io::{AsRawFd, RawFd},
+        io::{AsRawFd, FromRawFd, RawFd},
         net::UnixStream,
         process::CommandExt,
     },
-    process::{ChildStdout, Command, Stdio},
-    sync::{Arc, Mutex},
+    process::{Child, ChildStdout, Command, Stdio},
+    rc::Rc,
+    sync::{Arc, Mutex, Weak},
+    time::Duration,
 };
 
 use calloop::{
-    channel::{self, sync_channel, Channel, SyncSender},
+    channel::{self, sync_channel, Channel, Sender, SyncSender},
     generic::Generic,
-    Interest, LoopHandle, Mode,
+    timer::{TimeoutAction, Timer},
+    Interest, LoopHandle, Mode, PostAction, RegistrationToken,
 };
 use wayland_server::{
     backend::{ClientData, ClientId, DisconnectReason},
     Client, DisplayHandle,
 };
 
-use slog::{error, info, o};
+use slog::{debug, error, info, o, warn};
 
 use super::x11_sockets::{prepare_x11_sockets, X11Lock};
 
@@ -71,6 +86,42 @@ pub struct XWayland {
     inner: Arc<Mutex<Inner>>,
 }
 
+/// Configuration of the XWayland manager
+#[derive(Debug, Clone)]
+pub struct XWaylandConfig {
+    /// Only spawn XWayland once the first X11 client connects
+    ///
+    /// [`XWayland::start`] then only sets up the listening sockets and `DISPLAY`.
+    /// Once XWayland exited, e.g. because its last client disconnected or it crashed,
+    /// it is spawned again when the next X11 client connects.
+    pub lazy: bool,
+    /// Restart XWayland right away after it exited
+    ///
+    /// Only instances that successfully started are restarted, to avoid restarting a broken
+    /// setup in a loop. Lazy managers always restart XWayland on demand instead.
+    pub restart_on_crash: bool,
+    /// Listen on the abstract socket `@/tmp/.X11-unix/X$D` in addition to `/tmp/.X11-unix/X$D`
+    pub abstract_socket: bool,
+    /// Let XWayland report its startup through `-displayfd` instead of `SIGUSR1`
+    ///
+    /// This spawns XWayland directly instead of through an intermediate shell.
+    pub displayfd: bool,
+    /// Additional command line arguments passed to XWayland
+    pub extra_args: Vec<String>,
+}
+
+impl Default for XWaylandConfig {
+    fn default() -> Self {
+        XWaylandConfig {
+            lazy: false,
+            restart_on_crash: false,
+            abstract_socket: true,
+            displayfd: false,
+            extra_args: Vec::new(),
+        }
+    }
+}
+
 /// Events generated by the XWayland manager
 ///
 /// This is a very low-level interface, only notifying you when the connection
@@ -102,7 +153,9 @@ pub enum XWaylandEvent {
 
     /// The XWayland server exited
     ///
-    /// This event is sent when the [`XWayland`] handle is dropped.
+    /// This event is sent when the [`XWayland`] handle is dropped or shut down and whenever
+    /// the XWayland server crashes. All state associated with the previous connection should be
+    /// cleaned up, as a restarted server will send a new [`XWaylandEvent::Ready`].
     Exited,
 }
 
@@ -112,6 +165,16 @@ impl XWayland {
     /// This function returns both the [`XWayland`] handle and an [`XWaylandSource`] that needs to be inserted
     /// into the [`calloop`] event loop, producing the Xwayland startup and shutdown events.
     pub fn new<L>(logger: L, dh: &DisplayHandle) -> (XWayland, XWaylandSource)
+    where
+        L: Into<Option<::slog::Logger>>,
+    {
+        XWayland::with_config(logger, dh, XWaylandConfig::default())
+    }
+
+    /// Create a new XWayland manager with the given configuration
+    ///
+    /// See [`XWayland::new`] for details.
+    pub fn with_config<L>(logger: L, dh: &DisplayHandle, config: XWaylandConfig) -> (XWayland, XWaylandSource)
     where
         L: Into<Option<::slog::Logger>>,
     {
@@ -122,6 +185,11 @@ impl XWayland {
             instance: None,
             sender,
             dh: dh.clone(),
+            config,
+            sockets: None,
+            listening: false,
+            sources: Vec::new(),
+            control: None,
             log: log.new(o!("smithay_module" => "XWayland")),
         }));
         (XWayland { inner }, XWaylandSource { channel })
@@ -134,19 +202,50 @@ impl XWayland {
     /// `UnixStream` representing your WM connection to XWayland, and the
     /// wayland `Client` for XWayland.
     ///
+    /// If the manager is [lazy](XWaylandConfig::lazy), this only starts listening for X11 clients
+    /// and XWayland is spawned once the first one connects.
+    ///
     /// Does nothing if XWayland is already started or starting.
     pub fn start<D>(&self, loop_handle: LoopHandle<'_, D>) -> io::Result<()> {
-        let dh = self.inner.lock().unwrap().dh.clone();
-        launch(&self.inner, loop_handle, dh)
+        let mut guard = self.inner.lock().unwrap();
+
+        if guard.control.is_none() {
+            guard.control = Some(control(&self.inner, loop_handle.clone())?);
+        }
+
+        if guard.config.lazy {
+            listen(&self.inner, &mut guard, loop_handle)
+        } else {
+            std::mem::drop(guard);
+            launch(&self.inner, loop_handle)
+        }
     }
 
     /// Shutdown XWayland
     ///
     /// Does nothing if it was not already running, otherwise kills it and you will
     /// later receive a `XWaylandEvent::Exited` event.
+    ///
+    /// This also stops listening for X11 clients, disables restarts and removes all event sources
+    /// of the manager from the event loop until [`XWayland::start`] is called again.
     pub fn shutdown(&self) {
         self.inner.lock().unwrap().shutdown();
     }
+
+    /// The display number XWayland is available at, if it is started or listening for clients
+    pub fn display(&self) -> Option<u32> {
+        self.inner
+            .lock()
+            .unwrap()
+            .sockets
+            .as_ref()
+            .map(|(lock, _)| lock.display())
+    }
+
+    /// The configuration of this manager
+    pub fn config(&self) -> XWaylandConfig {
+        self.inner.lock().unwrap().config.clone()
+    }
 }
 
 impl Drop for XWayland {
@@ -155,13 +254,47 @@ impl Drop for XWayland {
     }
 }
 
+/// The pipe XWayland reports its readiness on
+#[derive(Debug)]
+enum StartupNotify {
+    /// The stdout of the intermediate shell, which prints 'S' on `SIGUSR1`
+    Signal(ChildStdout),
+    /// The read end of the `-displayfd` pipe, XWayland writes the display number followed by a newline
+    DisplayFd(File),
+}
+
+impl StartupNotify {
+    fn as_raw_fd(&self) -> RawFd {
+        match self {
+            StartupNotify::Signal(stdout) => stdout.as_raw_fd(),
+            StartupNotify::DisplayFd(file) => file.as_raw_fd(),
+        }
+    }
+
+    fn success(&mut self) -> io::Result<bool> {
+        let mut buffer = [0; 16];
+        match self {
+            // This reads the one byte that is written when sh receives SIGUSR1
+            StartupNotify::Signal(stdout) => {
+                let len = stdout.read(&mut buffer[..1])?;
+                Ok(len > 0 && buffer[0] == b'S')
+            }
+            StartupNotify::DisplayFd(file) => {
+                let len = file.read(&mut buffer)?;
+                Ok(len > 0 && buffer[..len].ends_with(b"\n"))
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 struct XWaylandInstance {
-    display_lock: X11Lock,
     wayland_client: Client,
     wayland_client_fd: RawFd,
     wm_fd: Option<UnixStream>,
-    child_stdout: ChildStdout,
+    startup: StartupNotify,
+    child: Child,
+    ready: bool,
 }
 
 // Inner implementation of the XWayland manager
@@ -170,9 +303,114 @@ struct Inner {
     sender: SyncSender<XWaylandEvent>,
     instance: Option<XWaylandInstance>,
     dh: DisplayHandle,
+    config: XWaylandConfig,
+    // the display lock and listening sockets, they outlive instances if started lazily or restarted
+    sockets: Option<(X11Lock, Vec<UnixStream>)>,
+    listening: bool,
+    // sources watching the listening sockets
+    sources: Vec<RegistrationToken>,
+    control: Option<Sender<Control>>,
     log: ::slog::Logger,
 }
 
+// Requests handled on the event loop
+#[derive(Debug)]
+enum Control {
+    // launch XWayland again after it exited
+    Launch,
+    // wait for the process of an exited instance
+    Reap(Child),
+    // remove sources of the manager from the event loop
+    Remove(Vec<RegistrationToken>),
+}
+
+// What happens once an instance exited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExitAction {
+    // stop the manager
+    Shutdown,
+    // wait for the next X11 client to connect
+    Listen,
+    // launch a new instance right away
+    Relaunch,
+}
+
+fn exit_action(config: &XWaylandConfig, ready: bool, pending_clients: bool) -> ExitAction {
+    if !ready {
+        // don't restart a broken setup in a loop
+        ExitAction::Shutdown
+    } else if config.lazy {
+        // clients that connected while XWayland was exiting are not reported by the
+        // edge triggered listening sources anymore
+        if pending_clients {
+            ExitAction::Relaunch
+        } else {
+            ExitAction::Listen
+        }
+    } else if config.restart_on_crash {
+        ExitAction::Relaunch
+    } else {
+        ExitAction::Shutdown
+    }
+}
+
+// Returns true if a client is waiting to be accepted on any of the listening sockets
+fn pending_clients(sockets: &[UnixStream]) -> bool {
+    use nix::poll::{poll, PollFd, PollFlags};
+
+    let mut fds = sockets
+        .iter()
+        .map(|socket| PollFd::new(socket.as_raw_fd(), PollFlags::POLLIN))
+        .collect::<Vec<_>>();
+    matches!(poll(&mut fds, 0), Ok(ready) if ready > 0)
+}
+
+// Interval at which the process of an exited instance is checked until it is gone
+const REAP_INTERVAL: Duration = Duration::from_millis(100);
+
+// Register the source handling the `Control` requests of the manager
+//
+// The source removes itself once the sender is dropped.
+fn control<D>(inner: &Arc<Mutex<Inner>>, loop_handle: LoopHandle<'_, D>) -> io::Result<Sender<Control>> {
+    let (sender, channel) = channel::channel();
+    let weak: Weak<Mutex<Inner>> = Arc::downgrade(inner);
+    let handle = loop_handle.clone();
+    let token = Rc::new(Cell::new(None));
+    let own_token = token.clone();
+    let registered = loop_handle
+        .insert_source(channel, move |event, _, _| match event {
+            channel::Event::Msg(Control::Launch) => {
+                if let Some(inner) = weak.upgrade() {
+                    if let Err(err) = launch(&inner, handle.clone()) {
+                        error!(inner.lock().unwrap().log, "Failed to restart XWayland"; "err" => %err);
+                    }
+                }
+            }
+            channel::Event::Msg(Control::Reap(mut child)) => {
+                let _ = handle.insert_source(Timer::from_duration(REAP_INTERVAL), move |_, _, _| match child
+                    .try_wait()
+                {
+                    Ok(None) => TimeoutAction::ToDuration(REAP_INTERVAL),
+                    _ => TimeoutAction::Drop,
+                });
+            }
+            channel::Event::Msg(Control::Remove(tokens)) => {
+                for token in tokens {
+                    handle.remove(token);
+                }
+            }
+            // the manager was shut down or dropped
+            channel::Event::Closed => {
+                if let Some(token) = own_token.take() {
+                    handle.remove(token);
+                }
+            }
+        })
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err.error))?;
+    token.set(Some(registered));
+    Ok(sender)
+}
+
 struct XWaylandClientData {
     inner: Arc<Mutex<Inner>>,
 }
@@ -184,19 +422,61 @@ impl ClientData for XWaylandClientData {
         // a shutdown. This will definitely be the case when the compositor exits
         // and the XWayland instance is dropped.
         if let Ok(mut guard) = self.inner.try_lock() {
-            guard.shutdown();
+            guard.exited();
         }
     }
 }
 
+// Watch the listening sockets and launch XWayland once a client connects
+//
+// Does nothing if we are already listening
+fn listen<D>(inner: &Arc<Mutex<Inner>>, guard: &mut Inner, loop_handle: LoopHandle<'_, D>) -> io::Result<()> {
+    if guard.listening {
+        return Ok(());
+    }
+
+    let (lock, sockets) = guard.prepare_sockets()?;
+    let display = lock.display();
+    let fds = sockets
+        .iter()
+        .map(|socket| socket.as_raw_fd())
+        .collect::<Vec<_>>();
+    for fd in fds {
+        let weak: Weak<Mutex<Inner>> = Arc::downgrade(inner);
+        let handle = loop_handle.clone();
+        let token = loop_handle
+            .insert_source(
+                // edge triggered, so we only get notified about new clients and not
+                // about pending ones XWayland has yet to accept
+                Generic::<_, io::Error>::new(fd, Interest::READ, Mode::Edge),
+                move |_, _, _| {
+                    // the source is removed through the control source once we stop listening
+                    let inner = match weak.upgrade() {
+                        Some(inner) if inner.lock().unwrap().listening => inner,
+                        _ => return Ok(PostAction::Continue),
+                    };
+                    if let Err(err) = launch(&inner, handle.clone()) {
+                        error!(inner.lock().unwrap().log, "Failed to start XWayland"; "err" => %err);
+                    }
+                    Ok(PostAction::Continue)
+                },
+            )
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err.error))?;
+        guard.sources.push(token);
+    }
+    guard.listening = true;
+
+    // clients need to know where to connect before XWayland exists
+    ::std::env::set_var("DISPLAY", format!(":{}", display));
+    info!(guard.log, "Listening for X11 clients on DISPLAY \":{}\"", display);
+
+    Ok(())
+}
+
 // Launch an XWayland server
 //
 // Does nothing if there is already a launched instance
-fn launch<D>(
-    inner: &Arc<Mutex<Inner>>,
-    loop_handle: LoopHandle<'_, D>,
-    mut dh: DisplayHandle,
-) -> io::Result<()> {
+fn launch<D>(inner: &Arc<Mutex<Inner>>, loop_handle: LoopHandle<'_, D>) -> io::Result<()> {
     let mut guard = inner.lock().unwrap();
     if guard.instance.is_some() {
         return Ok(());
@@ -207,13 +487,15 @@ fn launch<D>(
     let (x_wm_x11, x_wm_me) = UnixStream::pair()?;
     let (wl_x11, wl_me) = UnixStream::pair()?;
 
-    let (lock, x_fds) = prepare_x11_sockets(guard.log.clone())?;
+    let config = guard.config.clone();
+    let (lock, x_fds) = guard.prepare_sockets()?;
+    let display = lock.display();
 
     // we have now created all the required sockets
 
     // all is ready, we can do the fork dance
-    let child_stdout = match spawn_xwayland(lock.display(), wl_x11, x_wm_x11, &x_fds) {
-        Ok(child_stdout) => child_stdout,
+    let (child, startup) = match spawn_xwayland(display, wl_x11, x_wm_x11, x_fds, &config) {
+        Ok(spawned) => spawned,
         Err(e) => {
             error!(guard.log, "XWayland failed to spawn"; "err" => format!("{:?}", e));
             return Err(e);
@@ -223,23 +505,25 @@ fn launch<D>(
     let loop_inner = inner.clone();
     loop_handle
         .insert_source(
-            Generic::<_, io::Error>::new(child_stdout.as_raw_fd(), Interest::READ, Mode::Level),
+            Generic::<_, io::Error>::new(startup.as_raw_fd(), Interest::READ, Mode::Level),
             move |_, _, _| {
                 // the closure must be called exactly one time, this cannot panic
                 xwayland_ready(&loop_inner);
-                Ok(calloop::PostAction::Remove)
+                Ok(PostAction::Remove)
             },
         )
         .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
 
     let client_fd = wl_me.as_raw_fd();
+    let mut dh = guard.dh.clone();
     let client = dh.insert_client(wl_me, Arc::new(XWaylandClientData { inner: inner.clone() }))?;
     guard.instance = Some(XWaylandInstance {
-        display_lock: lock,
         wayland_client: client,
         wayland_client_fd: client_fd,
         wm_fd: Some(x_wm_me),
-        child_stdout,
+        startup,
+        child,
+        ready: false,
     });
 
     Ok(())
@@ -301,26 +585,96 @@ impl calloop::EventSource for XWaylandSource {
 }
 
 impl Inner {
+    // Grab a display and open the listening sockets, unless we already have them
+    fn prepare_sockets(&mut self) -> io::Result<(&X11Lock, &[UnixStream])> {
+        if self.sockets.is_none() {
+            self.sockets = Some(prepare_x11_sockets(
+                self.config.abstract_socket,
+                self.log.clone(),
+            )?);
+        }
+        let (lock, sockets) = self.sockets.as_ref().unwrap();
+        Ok((lock, sockets))
+    }
+
+    // Cleanup the state of the current instance
+    //
+    // Returns whether the instance was running
+    fn cleanup(&mut self) -> bool {
+        // don't do anything if not running
+        let instance = match self.instance.take() {
+            Some(instance) => instance,
+            None => return false,
+        };
+
+        self.dh
+            .backend_handle()
+            .kill_client(instance.wayland_client.id(), DisconnectReason::ConnectionClosed);
+
+        // send error occurs if the user dropped the channel... We cannot do much except ignore.
+        let _ = self.sender.send(XWaylandEvent::Exited);
+
+        // We do like wlroots:
+        // > We do not kill the XWayland process, it dies to broken pipe
+        // > after we close our side of the wm/wl fds. This is more reliable
+        // > than trying to kill something that might no longer be XWayland.
+        // We still have to reap it, once it is gone.
+        let mut child = instance.child;
+        if let (Ok(None), Some(control)) = (child.try_wait(), self.control.as_ref()) {
+            let _ = control.send(Control::Reap(child));
+        }
+
+        instance.ready
+    }
+
     // Shutdown the XWayland server and cleanup everything
     fn shutdown(&mut self) {
-        // don't do anything if not running
-        if let Some(instance) = self.instance.take() {
+        if self.cleanup() || self.sockets.is_some() {
             info!(self.log, "Shutting down XWayland.");
-            self.dh
-                .backend_handle()
-                .kill_client(instance.wayland_client.id(), DisconnectReason::ConnectionClosed);
-
-            // send error occurs if the user dropped the channel... We cannot do much except ignore.
-            let _ = self.sender.send(XWaylandEvent::Exited);
+        }
+        self.listening = false;
+        // the control source removes itself once the sender is dropped
+        if let Some(control) = self.control.take() {
+            let _ = control.send(Control::Remove(std::mem::take(&mut self.sources)));
+        }
 
-            // All connections and lockfiles are cleaned by their destructors
+        // All connections and lockfiles are cleaned by their destructors
+        self.sockets = None;
+        // Remove DISPLAY from the env
+        ::std::env::remove_var("DISPLAY");
+    }
 
-            // Remove DISPLAY from the env
-            ::std::env::remove_var("DISPLAY");
-            // We do like wlroots:
-            // > We do not kill the XWayland process, it dies to broken pipe
-            // > after we close our side of the wm/wl fds. This is more reliable
-            // > than trying to kill something that might no longer be XWayland.
+    // The XWayland client disconnected, restart XWayland if configured to do so
+    fn exited(&mut self) {
+        let ready = self.cleanup();
+        let pending = self
+            .sockets
+            .as_ref()
+            .map(|(_, sockets)| self.listening && pending_clients(sockets))
+            .unwrap_or(false);
+        match exit_action(&self.config, ready, pending) {
+            ExitAction::Shutdown => {
+                if !ready {
+                    warn!(
+                        self.log,
+                        "XWayland exited during startup, will not try to restart it."
+                    );
+                }
+                self.shutdown();
+            }
+            ExitAction::Listen => {
+                // the listening sockets relaunch XWayland once the next client connects
+                debug!(
+                    self.log,
+                    "XWayland exited, waiting for the next X11 client to restart it."
+                );
+            }
+            ExitAction::Relaunch => {
+                info!(self.log, "XWayland exited, restarting it.");
+                if let Some(control) = self.control.as_ref() {
+                    let _ = control.send(Control::Launch);
+                }
+            }
         }
     }
 }
@@ -330,74 +684,111 @@ fn xwayland_ready(inner: &Arc<Mutex<Inner>>) {
     let mut guard = inner.lock().unwrap();
     let guard = &mut *guard;
     info!(guard.log, "XWayland ready");
-    // instance should never be None at this point
-    let instance = guard.instance.as_mut().unwrap();
-    // neither the child_stdout
-    let child_stdout = &mut instance.child_stdout;
-
-    // This reads the one byte that is written when sh receives SIGUSR1
-    let mut buffer = [0];
-    let success = match child_stdout.read(&mut buffer) {
-        Ok(len) => len > 0 && buffer[0] == b'S',
+    let display = match guard.sockets.as_ref() {
+        Some((lock, _)) => lock.display(),
+        // XWayland was shut down in the meantime
+        None => return,
+    };
+    let instance = match guard.instance.as_mut() {
+        Some(instance) => instance,
+        None => return,
+    };
+
+    let success = match instance.startup.success() {
+        Ok(success) => success,
         Err(e) => {
             error!(guard.log, "Checking launch status failed"; "err" => format!("{:?}", e));
             false
         }
     };
+    // the intermediate shell exits once XWayland is up
+    let _ = instance.child.try_wait();
 
     if success {
+        instance.ready = true;
         // setup the environment
-        ::std::env::set_var("DISPLAY", format!(":{}", instance.display_lock.display()));
+        ::std::env::set_var("DISPLAY", format!(":{}", display));
 
         // signal the WM
         info!(
             guard.log,
-            "XWayland is ready on DISPLAY \":{}\", signaling the WM.",
-            instance.display_lock.display()
+            "XWayland is ready on DISPLAY \":{}\", signaling the WM.", display
         );
         // send error occurs if the user dropped the channel... We cannot do much except ignore.
         let _ = guard.sender.send(XWaylandEvent::Ready {
             connection: instance.wm_fd.take().unwrap(), // This is a bug if None
             client: instance.wayland_client.clone(),
             client_fd: instance.wayland_client_fd,
-            display: instance.display_lock.display(),
+            display,
         });
     } else {
         error!(
             guard.log,
             "XWayland crashed at startup, will not try to restart it."
         );
+        guard.shutdown();
     }
 }
 
 /// Spawn XWayland with given sockets on given display
 ///
-/// Returns a pipe that outputs 'S' upon successful launch.
+/// Returns the spawned process and the pipe it reports a successful launch on.
 fn spawn_xwayland(
     display: u32,
     wayland_socket: UnixStream,
     wm_socket: UnixStream,
     listen_sockets: &[UnixStream],
-) -> io::Result<ChildStdout> {
-    let mut command = Command::new("sh");
-
-    // We use output stream to communicate because FD is easier to handle than exit code.
-    command.stdout(Stdio::piped());
-
-    let mut xwayland_args = format!(":{} -rootless -terminate -wm {}", display, wm_socket.as_raw_fd());
+    config: &XWaylandConfig,
+) -> io::Result<(Child, StartupNotify)> {
+    let mut xwayland_args = vec![
+        format!(":{}", display),
+        "-rootless".into(),
+        "-terminate".into(),
+        "-wm".into(),
+        wm_socket.as_raw_fd().to_string(),
+    ];
     for socket in listen_sockets {
-        // Will only fail to write on OOM, so this panic is fine.
-        write!(xwayland_args, " -listenfd {}", socket.as_raw_fd()).unwrap();
+        xwayland_args.push("-listenfd".into());
+        xwayland_args.push(socket.as_raw_fd().to_string());
     }
-    // This command let sh to:
-    // * Set up signal handler for USR1
-    // * Launch Xwayland with USR1 ignored so Xwayland will signal us when it is ready (also redirect
-    //   Xwayland's STDOUT to STDERR so its output, if any, won't distract us)
-    // * Print "S" and exit if USR1 is received
-    command.arg("-c").arg(format!(
-        "trap 'echo S' USR1; (trap '' USR1; exec Xwayland {}) 1>&2 & wait",
-        xwayland_args
-    ));
+
+    // the write end of the -displayfd pipe, it needs to stay open until XWayland was spawned
+    let mut displayfd = None;
+    let mut command = if config.displayfd {
+        let (read, write) = nix::unistd::pipe2(nix::fcntl::OFlag::O_CLOEXEC)?;
+        // SAFETY: both fds were just created and are exclusively owned by us
+        let (read, write) = unsafe { (File::from_raw_fd(read), File::from_raw_fd(write)) };
+        xwayland_args.push("-displayfd".into());
+        xwayland_args.push(write.as_raw_fd().to_string());
+        xwayland_args.extend(config.extra_args.iter().cloned());
+        displayfd = Some((read, write));
+
+        let mut command = Command::new("Xwayland");
+        command.args(&xwayland_args);
+        command.stdout(Stdio::null());
+        command
+    } else {
+        xwayland_args.extend(config.extra_args.iter().cloned());
+        let xwayland_args = xwayland_args
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let mut command = Command::new("sh");
+        // We use output stream to communicate because FD is easier to handle than exit code.
+        command.stdout(Stdio::piped());
+        // This command let sh to:
+        // * Set up signal handler for USR1
+        // * Launch Xwayland with USR1 ignored so Xwayland will signal us when it is ready (also redirect
+        //   Xwayland's STDOUT to STDERR so its output, if any, won't distract us)
+        // * Print "S" and exit if USR1 is received
+        command.arg("-c").arg(format!(
+            "trap 'echo S' USR1; (trap '' USR1; exec Xwayland {}) 1>&2 & wait",
+            xwayland_args
+        ));
+        command
+    };
 
     // Setup the environment: clear everything except PATH and XDG_RUNTIME_DIR
     command.env_clear();
@@ -412,6 +803,7 @@ fn spawn_xwayland(
     unsafe {
         let wayland_socket_fd = wayland_socket.as_raw_fd();
         let wm_socket_fd = wm_socket.as_raw_fd();
+        let displayfd_fd = displayfd.as_ref().map(|(_, write)| write.as_raw_fd());
         let socket_fds: Vec<_> = listen_sockets.iter().map(|socket| socket.as_raw_fd()).collect();
         command.pre_exec(move || {
             // unset the CLOEXEC flag from the sockets we need to pass
@@ -421,12 +813,25 @@ fn spawn_xwayland(
             for &socket in socket_fds.iter() {
                 unset_cloexec(socket)?;
             }
+            if let Some(fd) = displayfd_fd {
+                unset_cloexec(fd)?;
+            }
             Ok(())
         });
     }
 
     let mut child = command.spawn()?;
-    Ok(child.stdout.take().expect("stdout should be piped"))
+    let startup = match displayfd {
+        // our copy of the write end is dropped here, so we see EOF if XWayland dies
+        Some((read, _write)) => StartupNotify::DisplayFd(read),
+        None => StartupNotify::Signal(child.stdout.take().expect("stdout should be piped")),
+    };
+    Ok((child, startup))
+}
+
+/// Quote an argument for the intermediate shell
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
 }
 
 /// Remove the `O_CLOEXEC` flag from this `Fd`
@@ -438,3 +843,79 @@ fn unset_cloexec(fd: RawFd) -> io::Result<()> {
     fcntl(fd, FcntlArg::F_SETFD(FdFlag::empty()))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use calloop::{ping::make_ping, EventLoop};
+
+    use super::*;
+
+    #[test]
+    fn quote_arguments() {
+        assert_eq!(shell_quote("-listen"), "'-listen'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn exit_actions() {
+        let eager = XWaylandConfig::default();
+        let restart = XWaylandConfig {
+            restart_on_crash: true,
+            ..XWaylandConfig::default()
+        };
+        let lazy = XWaylandConfig {
+            lazy: true,
+            ..XWaylandConfig::default()
+        };
+
+        // instances failing to start are never restarted
+        for config in [&eager, &restart, &lazy] {
+            assert_eq!(exit_action(config, false, false), ExitAction::Shutdown);
+            assert_eq!(exit_action(config, false, true), ExitAction::Shutdown);
+        }
+        assert_eq!(exit_action(&eager, true, false), ExitAction::Shutdown);
+        assert_eq!(exit_action(&restart, true, false), ExitAction::Relaunch);
+        assert_eq!(exit_action(&lazy, true, false), ExitAction::Listen);
+        // a client connected while XWayland was exiting
+        assert_eq!(exit_action(&lazy, true, true), ExitAction::Relaunch);
+    }
+
+    #[test]
+    fn pending_clients_are_detected() {
+        let (listening, mut client) = UnixStream::pair().unwrap();
+        let (other, _) = UnixStream::pair().unwrap();
+        let sockets = [other, listening];
+        assert!(!pending_clients(&sockets));
+        client.write_all(b"X").unwrap();
+        assert!(pending_clients(&sockets));
+        assert!(!pending_clients(&[]));
+    }
+
+    #[test]
+    fn control_removes_sources() {
+        let mut event_loop = EventLoop::<u32>::try_new().unwrap();
+        let display = wayland_server::Display::<()>::new().unwrap();
+        let (xwayland, _source) = XWayland::new(None::<slog::Logger>, &display.handle());
+        let control = control(&xwayland.inner, event_loop.handle()).unwrap();
+
+        let (ping, ping_source) = make_ping().unwrap();
+        let token = event_loop
+            .handle()
+            .insert_source(ping_source, |_, _, count| *count += 1)
+            .unwrap();
+        let mut count = 0;
+        ping.ping();
+        event_loop.dispatch(Duration::ZERO, &mut count).unwrap();
+        assert_eq!(count, 1);
+
+        // shutting down removes the sources and closes the control channel
+        control.send(Control::Remove(vec![token])).unwrap();
+        std::mem::drop(control);
+        event_loop.dispatch(Duration::ZERO, &mut count).unwrap();
+        ping.ping();
+        event_loop.dispatch(Duration::ZERO, &mut count).unwrap();
+        assert_eq!(count, 1);
+    }
+}
@@ -84,6 +84,7 @@ pub fn run(channel: Channel<WlcsEvent>) {
             subpixel: Subpixel::Unknown,
             make: "Smithay".into(),
             model: "WLCS".into(),
+            hdr: None,
         },
         logger.clone(),
     );